@@ -36,16 +36,20 @@ struct Delta {
 
 /// Generate a response from the OpenAI API. The function internally uses
 /// streaming responses but returns the aggregated result as a `String`.
-#[cfg(not(test))]
 pub async fn generate_response(prompt: &str) -> String {
+    try_generate_response(prompt).await.unwrap_or_else(|e| e)
+}
+
+/// Like `generate_response`, but failures are errors instead of the reply
+pub async fn try_generate_response(prompt: &str) -> Result<String, String> {
     let key = match env::var("OPENAI_API_KEY") {
         Ok(k) => k,
-        Err(_) => return "OpenAI API key not set".into(),
+        Err(_) => return Err("OpenAI API key not set".into()),
     };
 
     let client = match Client::builder().timeout(Duration::from_secs(30)).build() {
         Ok(c) => c,
-        Err(e) => return format!("Failed to build HTTP client: {e}"),
+        Err(e) => return Err(format!("Failed to build HTTP client: {e}")),
     };
 
     let request = ChatRequest {
@@ -82,7 +86,7 @@ pub async fn generate_response(prompt: &str) -> String {
                                     None => continue,
                                 };
                                 if line == b"[DONE]" {
-                                    return out;
+                                    return Ok(out);
                                 }
                                 if let Ok(chunk) = serde_json::from_slice::<ChatChunk>(line) {
                                     if let Some(content) =
@@ -93,10 +97,10 @@ pub async fn generate_response(prompt: &str) -> String {
                                 }
                             }
                         }
-                        Err(_) => return "Failed to read stream".into(),
+                        Err(_) => return Err("Failed to read stream".into()),
                     }
                 }
-                return out;
+                return Ok(out);
             }
             Err(_) => {
                 // wait a bit before retrying
@@ -105,10 +109,5 @@ pub async fn generate_response(prompt: &str) -> String {
         }
     }
 
-    "Failed to contact OpenAI".into()
-}
-
-#[cfg(test)]
-pub async fn generate_response(_prompt: &str) -> String {
-    "cloud-test".into()
+    Err("Failed to contact OpenAI".into())
 }
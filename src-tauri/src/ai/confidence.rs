@@ -0,0 +1,252 @@
+//! Confidence notes for TARS answers.
+//!
+//! At or above the honesty threshold an answer is rated from the wording of
+//! the question and answer: time-sensitive wording, dates past the answering
+//! model's training cutoff and hedging all lower it. With `self_check` on,
+//! the model also rates its own confidence in an extra pass and says whether
+//! the answer depends on facts newer than its training data; that rating can
+//! only lower the heuristic one.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::sync::RwLock;
+
+use super::inference_queue::InferencePriority;
+use super::router::{get_routed_response, LlmSource};
+
+static HONESTY_THRESHOLD: Lazy<RwLock<f32>> = Lazy::new(|| RwLock::new(0.8));
+
+static POLICY: Lazy<RwLock<ConfidencePolicy>> = Lazy::new(|| RwLock::new(ConfidencePolicy::default()));
+
+/// Years written as dates: `in 2025`, `March 2025`, `2025-03-01`. Bare
+/// numbers such as `2500 rpm` are not years.
+static DATED_YEAR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(?:(?:in|since|during|until|after|before)\s+|(?:jan|feb|mar|apr|may|jun|jul|aug|sep|sept|oct|nov|dec)[a-z]*\.?\s+(?:\d{1,2},?\s+)?)((?:19|20)\d{2})\b|\b((?:19|20)\d{2})-\d{2}(?:-\d{2})?\b",
+    )
+    .unwrap()
+});
+
+/// Question wording that asks for facts which change over time
+const TIME_SENSITIVE: &[&str] = &[
+    "latest", "newest", "current", "currently", "today", "tonight", "right now",
+    "this week", "this month", "this year", "recent", "news", "price of",
+    "stock price", "who won", "upcoming", "release date",
+];
+
+/// Answer wording that signals the model is unsure
+const HEDGES: &[&str] = &[
+    "i think", "i believe", "probably", "possibly", "might be", "not sure",
+    "as of my", "may have changed", "i don't have", "i do not have",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfidenceAssessment {
+    /// 0-100
+    pub confidence: u8,
+
+    /// The answer relies on information the model likely doesn't have
+    pub beyond_training_data: bool,
+}
+
+/// Training cutoffs of the models and whether they check their own answers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfidencePolicy {
+    /// Last year of training data for routes not in `training_cutoffs`
+    pub default_training_cutoff: u32,
+    /// Last year of training data by model route (`local`, `local:<model>`,
+    /// `cloud`)
+    pub training_cutoffs: BTreeMap<String, u32>,
+    /// Ask the model to rate its own answer, which costs an extra inference
+    /// per answer
+    pub self_check: bool,
+}
+
+impl Default for ConfidencePolicy {
+    fn default() -> Self {
+        Self {
+            default_training_cutoff: 2023,
+            training_cutoffs: BTreeMap::new(),
+            self_check: false,
+        }
+    }
+}
+
+impl ConfidencePolicy {
+    /// The earliest cutoff of the routes that served an answer
+    pub fn training_cutoff(&self, served_by: &[String]) -> u32 {
+        served_by.iter()
+            .map(|route| self.training_cutoffs.get(route).copied().unwrap_or(self.default_training_cutoff))
+            .min()
+            .unwrap_or(self.default_training_cutoff)
+    }
+}
+
+pub async fn policy() -> ConfidencePolicy {
+    POLICY.read().await.clone()
+}
+
+pub async fn set_policy(policy: ConfidencePolicy) {
+    *POLICY.write().await = policy;
+}
+
+/// Honesty setting at or above which answers carry a confidence note
+pub async fn honesty_threshold() -> f32 {
+    *HONESTY_THRESHOLD.read().await
+}
+
+pub async fn set_honesty_threshold(threshold: f32) {
+    *HONESTY_THRESHOLD.write().await = threshold.clamp(0.0, 1.0);
+}
+
+/// Confidence note for an answer served by the routes in `served_by`, or
+/// None when `honesty` is below the threshold. The self-check, when the
+/// policy enables it, queues at the `priority` of the answer it rates; when
+/// the queue drops it, the heuristic rating stands.
+pub async fn assess_answer(
+    source: LlmSource,
+    priority: InferencePriority,
+    served_by: &[String],
+    honesty: f32,
+    question: &str,
+    answer: &str,
+) -> Option<String> {
+    let threshold = honesty_threshold().await;
+    if honesty < threshold {
+        return None;
+    }
+    let policy = policy().await;
+    let training_cutoff = policy.training_cutoff(served_by);
+    if !policy.self_check {
+        return confidence_note(honesty, threshold, training_cutoff, question, answer, None);
+    }
+    let reply = get_routed_response(source, &self_assessment_prompt(question, answer), "", priority).await;
+    let self_assessment = (!reply.route.dropped).then_some(reply.text.as_str());
+    confidence_note(honesty, threshold, training_cutoff, question, answer, self_assessment)
+}
+
+/// Prompt for the self-assessment pass
+pub fn self_assessment_prompt(question: &str, answer: &str) -> String {
+    format!(
+        "Rate how confident you are that the answer below is correct and current.\n\
+        Reply with exactly two lines:\n\
+        CONFIDENCE: <0-100>\n\
+        NEEDS_NEWER_DATA: <yes|no>\n\n\
+        Question: {}\n\nAnswer: {}",
+        question, answer
+    )
+}
+
+/// Parse a self-assessment reply; None unless it has a CONFIDENCE line
+pub fn parse_self_assessment(reply: &str) -> Option<ConfidenceAssessment> {
+    let mut confidence = None;
+    let mut beyond_training_data = false;
+    for line in reply.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        match key.trim().to_uppercase().as_str() {
+            "CONFIDENCE" => confidence = value.trim().trim_end_matches('%').parse::<u8>().ok(),
+            "NEEDS_NEWER_DATA" => beyond_training_data = value.trim().eq_ignore_ascii_case("yes"),
+            _ => {}
+        }
+    }
+    confidence.map(|confidence| ConfidenceAssessment { confidence: confidence.min(100), beyond_training_data })
+}
+
+/// Assessment from the wording of the question and answer alone, for a
+/// model with training data up to `training_cutoff`
+pub fn heuristic_assessment(training_cutoff: u32, question: &str, answer: &str) -> ConfidenceAssessment {
+    let question = question.to_lowercase();
+    let answer = answer.to_lowercase();
+
+    let recent_year = DATED_YEAR.captures_iter(&question)
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+        .filter_map(|year| year.as_str().parse::<u32>().ok())
+        .any(|year| year > training_cutoff);
+    let beyond_training_data = recent_year || TIME_SENSITIVE.iter().any(|marker| question.contains(marker));
+    let hedges = HEDGES.iter().filter(|hedge| answer.contains(*hedge)).count() as u8;
+
+    let mut confidence: u8 = 90;
+    if beyond_training_data {
+        confidence -= 45;
+    }
+    confidence = confidence.saturating_sub(hedges * 10).max(5);
+
+    ConfidenceAssessment { confidence, beyond_training_data }
+}
+
+/// The note appended to an answer, or None when `honesty` is below the
+/// threshold. The self-assessment can only lower the heuristic rating, so a
+/// model that is sure of stale facts still gets flagged.
+pub fn confidence_note(
+    honesty: f32,
+    threshold: f32,
+    training_cutoff: u32,
+    question: &str,
+    answer: &str,
+    self_assessment: Option<&str>,
+) -> Option<String> {
+    if honesty < threshold {
+        return None;
+    }
+
+    let mut assessment = heuristic_assessment(training_cutoff, question, answer);
+    if let Some(reported) = self_assessment.and_then(parse_self_assessment) {
+        assessment.confidence = assessment.confidence.min(reported.confidence);
+        assessment.beyond_training_data |= reported.beyond_training_data;
+    }
+
+    let level = match assessment.confidence {
+        85.. => "High",
+        60..=84 => "Moderate",
+        _ => "Low",
+    };
+    let mut note = format!("[Confidence: {}% ({})]", assessment.confidence, level);
+    if assessment.beyond_training_data {
+        note.push_str(" This likely depends on information newer than my training data. Verify it against a current source.");
+    } else if assessment.confidence < 85 {
+        note.push_str(" I may be wrong here. Check the details that matter before acting on them.");
+    }
+    Some(note)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncertain_question_gets_disclaimer_only_when_honest() {
+        let question = "What is the latest stable Rust release this week?";
+        let answer = "Rust 1.70 is the newest stable release, I think.";
+
+        let honest = confidence_note(1.0, 0.8, 2023, question, answer, Some("CONFIDENCE: 95\nNEEDS_NEWER_DATA: no")).unwrap();
+        assert!(honest.starts_with("[Confidence: 35% (Low)]"));
+        assert!(honest.contains("newer than my training data"));
+
+        assert_eq!(confidence_note(0.1, 0.8, 2023, question, answer, None), None);
+    }
+
+    #[test]
+    fn test_only_dated_years_past_the_cutoff_count_as_recent() {
+        let recent = |question| heuristic_assessment(2023, question, "").beyond_training_data;
+        assert!(recent("Who won the election in 2024?"));
+        assert!(recent("What happened on 2025-03-01?"));
+        assert!(recent("Summarize the March 2024 launch"));
+        assert!(!recent("What happened in 1969?"));
+        assert!(!recent("Spin the motor at 2500 rpm"));
+        assert!(!recent("Is port 8080 or 3000 better for the dev server?"));
+        assert!(!recent("Allow ports 8080-90"));
+
+        // the cutoff comes from the model that answered
+        assert!(!heuristic_assessment(2025, "Summarize the March 2024 launch", "").beyond_training_data);
+        let policy = ConfidencePolicy {
+            training_cutoffs: BTreeMap::from([("cloud".to_string(), 2025)]),
+            ..ConfidencePolicy::default()
+        };
+        assert_eq!(policy.training_cutoff(&["cloud".to_string()]), 2025);
+        assert_eq!(policy.training_cutoff(&["local".to_string(), "cloud".to_string()]), 2023);
+        assert!(!policy.self_check, "the self-check costs an inference, so it is opt-in");
+    }
+}
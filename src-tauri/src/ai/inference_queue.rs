@@ -0,0 +1,289 @@
+//! Bounded queue in front of the models. A burst of `ask_tars` calls would
+//! otherwise run every inference at once and stall the local LLM; the queue
+//! limits how many run concurrently and hands out free slots by priority,
+//! oldest first within a priority. When the queue is full the newest
+//! background request is dropped to make room.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+static QUEUE: Lazy<InferenceQueue> = Lazy::new(|| InferenceQueue::new(QueueConfig::default()));
+
+/// Urgency of a model request, lowest first. Mirrors the voice pipeline's
+/// `TaskPriority`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InferencePriority {
+    /// Preloading, summaries and other work nobody is waiting on
+    Background,
+    /// Someone is waiting on the answer
+    #[default]
+    Interactive,
+    /// Safety alerts and emergency responses
+    Emergency,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueConfig {
+    /// Inferences run at once
+    pub max_concurrent: usize,
+    /// Requests that may wait for a slot
+    pub capacity: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { max_concurrent: 1, capacity: 32 }
+    }
+}
+
+struct Waiter {
+    seq: u64,
+    priority: InferencePriority,
+    grant: oneshot::Sender<Result<(), String>>,
+}
+
+struct QueueState {
+    config: QueueConfig,
+    running: usize,
+    waiting: Vec<Waiter>,
+    next_seq: u64,
+}
+
+impl QueueState {
+    /// Index of the waiter to serve next
+    fn next_waiter(&self) -> Option<usize> {
+        self.waiting.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.priority.cmp(&b.priority).then(b.seq.cmp(&a.seq)))
+            .map(|(i, _)| i)
+    }
+
+    /// Hand free slots to waiters. Waiters whose ticket was dropped are skipped.
+    fn grant_free_slots(&mut self) {
+        while self.running < self.config.max_concurrent.max(1) {
+            let waiter = match self.next_waiter() {
+                Some(i) => self.waiting.remove(i),
+                None => break,
+            };
+            if waiter.grant.send(Ok(())).is_ok() {
+                self.running += 1;
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InferenceQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+impl InferenceQueue {
+    pub fn new(config: QueueConfig) -> Self {
+        let state = QueueState { config, running: 0, waiting: Vec::new(), next_seq: 0 };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn set_config(&self, config: QueueConfig) {
+        let mut state = self.lock();
+        state.config = config;
+        state.grant_free_slots();
+    }
+
+    /// Requests waiting for a slot
+    pub fn depth(&self) -> usize {
+        self.lock().waiting.len()
+    }
+
+    /// Queue a request. The ticket's position is how many requests will be
+    /// served before it, 0 when a slot is free now. Fails when the queue is
+    /// full and nothing of lower priority can be dropped for it.
+    pub fn enqueue(&self, priority: InferencePriority) -> Result<Ticket, String> {
+        let mut state = self.lock();
+        let (grant, receiver) = oneshot::channel();
+        let seq = state.next_seq;
+        if state.running < state.config.max_concurrent.max(1) && state.waiting.is_empty() {
+            state.running += 1;
+            let _ = grant.send(Ok(()));
+            return Ok(Ticket { seq, position: 0, receiver: Some(receiver), queue: self.clone() });
+        }
+
+        if state.waiting.len() >= state.config.capacity {
+            let newest_background = state.waiting.iter()
+                .enumerate()
+                .filter(|(_, waiter)| waiter.priority == InferencePriority::Background)
+                .max_by_key(|(_, waiter)| waiter.seq)
+                .map(|(i, _)| i);
+            match newest_background {
+                Some(i) if priority > InferencePriority::Background => {
+                    let dropped = state.waiting.remove(i);
+                    log::warn!("Inference queue full; dropped a background request for a {:?} one", priority);
+                    let _ = dropped.grant.send(Err("Dropped from the full inference queue for a higher-priority request".to_string()));
+                },
+                _ => return Err(format!("Inference queue is full ({} waiting)", state.waiting.len())),
+            }
+        }
+
+        state.next_seq += 1;
+        let position = state.waiting.iter().filter(|waiter| waiter.priority >= priority).count() + 1;
+        state.waiting.push(Waiter { seq, priority, grant });
+        Ok(Ticket { seq, position, receiver: Some(receiver), queue: self.clone() })
+    }
+
+    /// Take an abandoned request out of the queue so it stops counting
+    /// towards `depth` and `capacity`
+    fn remove_waiter(&self, seq: u64) {
+        self.lock().waiting.retain(|waiter| waiter.seq != seq);
+    }
+
+    fn release(&self) {
+        let mut state = self.lock();
+        state.running = state.running.saturating_sub(1);
+        state.grant_free_slots();
+    }
+}
+
+/// A queued request. Dropping it gives up its place, or its slot if one
+/// was already granted.
+pub struct Ticket {
+    seq: u64,
+    position: usize,
+    receiver: Option<oneshot::Receiver<Result<(), String>>>,
+    queue: InferenceQueue,
+}
+
+impl Ticket {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Wait for a slot. Fails when the request was dropped to make room.
+    pub async fn wait(mut self) -> Result<InferencePermit, String> {
+        // the receiver stays in the ticket while waiting, so a cancelled
+        // wait still gives back a slot granted in the meantime
+        let result = match self.receiver.as_mut() {
+            Some(receiver) => receiver.await,
+            None => return Err("Ticket already used".to_string()),
+        };
+        self.receiver = None;
+        match result {
+            Ok(Ok(())) => Ok(InferencePermit { queue: self.queue.clone() }),
+            Ok(Err(reason)) => Err(reason),
+            Err(_) => Err("Inference queue closed".to_string()),
+        }
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            match receiver.try_recv() {
+                Ok(Ok(())) => self.queue.release(),
+                _ => self.queue.remove_waiter(self.seq),
+            }
+        }
+    }
+}
+
+/// A running inference's slot, freed on drop
+pub struct InferencePermit {
+    queue: InferenceQueue,
+}
+
+impl Drop for InferencePermit {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+pub fn set_config(config: QueueConfig) {
+    QUEUE.set_config(config);
+}
+
+/// Queue a request on the shared model queue
+pub fn enqueue(priority: InferencePriority) -> Result<Ticket, String> {
+    QUEUE.enqueue(priority)
+}
+
+pub fn depth() -> usize {
+    QUEUE.depth()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_emergency_request_jumps_ahead_of_queued_background_ones() {
+        let queue = InferenceQueue::new(QueueConfig { max_concurrent: 1, capacity: 3 });
+        let running = queue.enqueue(InferencePriority::Interactive).unwrap();
+        assert_eq!(running.position(), 0);
+        let running = running.wait().await.unwrap();
+
+        let (served, mut order) = mpsc::unbounded_channel();
+        let spawn = |name: &'static str, priority| {
+            let ticket = queue.enqueue(priority).unwrap();
+            let position = ticket.position();
+            let served = served.clone();
+            tokio::spawn(async move {
+                // holds the permit until the order is recorded
+                let permit = ticket.wait().await;
+                served.send((name, permit.is_ok())).unwrap();
+            });
+            position
+        };
+        assert_eq!(spawn("background 1", InferencePriority::Background), 1);
+        assert_eq!(spawn("background 2", InferencePriority::Background), 2);
+        assert_eq!(spawn("background 3", InferencePriority::Background), 3);
+        // The queue is full, so the newest background request makes room
+        assert_eq!(spawn("emergency", InferencePriority::Emergency), 1);
+        assert_eq!(order.recv().await.unwrap(), ("background 3", false));
+        assert!(queue.enqueue(InferencePriority::Background).is_err());
+        tokio::task::yield_now().await;
+
+        drop(running);
+        let served: Vec<_> = [order.recv().await, order.recv().await, order.recv().await]
+            .into_iter()
+            .map(|served| served.unwrap())
+            .collect();
+        assert_eq!(served.iter().map(|(name, _)| *name).collect::<Vec<_>>(), vec!["emergency", "background 1", "background 2"]);
+        assert!(served.iter().all(|(_, ok)| *ok));
+        assert_eq!(queue.depth(), 0);
+        assert_eq!(queue.enqueue(InferencePriority::Background).unwrap().position(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_tickets_free_their_place_in_a_full_queue() {
+        let queue = InferenceQueue::new(QueueConfig { max_concurrent: 1, capacity: 2 });
+        let running = queue.enqueue(InferencePriority::Interactive).unwrap().wait().await.unwrap();
+
+        // Two cancelled `ask_tars` calls fill the queue, then give up
+        let abandoned = [
+            queue.enqueue(InferencePriority::Interactive).unwrap(),
+            queue.enqueue(InferencePriority::Interactive).unwrap(),
+        ];
+        assert!(queue.enqueue(InferencePriority::Emergency).is_err());
+        drop(abandoned);
+        assert_eq!(queue.depth(), 0);
+
+        let emergency = queue.enqueue(InferencePriority::Emergency).unwrap();
+        let interactive = queue.enqueue(InferencePriority::Interactive).unwrap();
+        assert_eq!((emergency.position(), interactive.position()), (1, 2));
+        assert_eq!(queue.depth(), 2);
+
+        drop(running);
+        let emergency = emergency.wait().await.unwrap();
+        drop(emergency);
+        assert!(interactive.wait().await.is_ok());
+    }
+}
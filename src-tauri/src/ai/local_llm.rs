@@ -25,7 +25,6 @@ struct GenerateChunk {
 
 /// Attempt to generate a response from the local Ollama instance. If the call
 /// fails, the function falls back to the cloud model.
-#[cfg(not(test))]
 pub async fn generate_response(prompt: &str) -> String {
     let model = { CURRENT_MODEL.read().await.clone() };
     match generate_with_model(&model, prompt).await {
@@ -34,12 +33,8 @@ pub async fn generate_response(prompt: &str) -> String {
     }
 }
 
-#[cfg(test)]
-pub async fn generate_response(_prompt: &str) -> String {
-    "local-test".into()
-}
-
-async fn generate_with_model(model: &str, prompt: &str) -> Result<String, reqwest::Error> {
+/// Generate with a specific local model, without falling back to the cloud
+pub async fn generate_with_model(model: &str, prompt: &str) -> Result<String, reqwest::Error> {
     let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
     let req = GenerateRequest {
         model,
@@ -110,6 +105,11 @@ pub async fn list_models() -> Result<Vec<String>, reqwest::Error> {
     Ok(list.models.into_iter().map(|m| m.name).collect())
 }
 
+/// Model used for local requests
+pub async fn current_model() -> String {
+    CURRENT_MODEL.read().await.clone()
+}
+
 /// Switch the active model for future requests
 pub async fn switch_model(model: &str) -> Result<(), reqwest::Error> {
     *CURRENT_MODEL.write().await = model.to_string();
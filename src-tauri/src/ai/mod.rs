@@ -1,3 +1,7 @@
 pub mod local_llm;
 pub mod cloud_llm;
 pub mod router;
+pub mod confidence;
+pub mod routing;
+pub mod guard;
+pub mod inference_queue;
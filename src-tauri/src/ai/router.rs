@@ -1,16 +1,23 @@
 use std::collections::HashMap;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use once_cell::sync::Lazy;
 use tokio::sync::RwLock;
 
-use super::{cloud_llm, local_llm};
+use super::guard;
+use super::inference_queue::{self, InferencePriority};
+use super::routing::{self, ModelRoute, RouteReport, RoutedResponse};
 use crate::personality::{TARSCore, EngineeringManager, CodingStandardsEngine};
+use crate::personality::engineering_manager::{
+    changed_files_since, ComplexitySignals, IncrementalReview, ReviewCache, StandardSeverity, TaskEstimate,
+};
 
 /// Simple in-memory cache for prompts and their responses
 static CACHE: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LlmSource {
     Local,
     Cloud,
@@ -30,11 +37,41 @@ fn query_complexity(prompt: &str) -> usize {
 
 /// Route the prompt to either the local or cloud model based on heuristics.
 pub async fn get_response(source: LlmSource, prompt: &str) -> String {
+    get_routed_response(source, prompt, "", InferencePriority::Interactive).await.text
+}
+
+/// Like `get_response`, reporting which model answered. The chosen source is
+/// the primary route of the routing policy; `context` decides whether the
+/// prompt is answered by an ensemble. Model calls wait for a slot in the
+/// inference queue at `priority`.
+pub async fn get_routed_response(
+    source: LlmSource,
+    prompt: &str,
+    context: &str,
+    priority: InferencePriority,
+) -> RoutedResponse {
     // Check cache first
     if let Some(cached) = CACHE.read().await.get(prompt).cloned() {
-        return cached;
+        return RoutedResponse {
+            text: cached,
+            route: RouteReport { cached: true, ..RouteReport::default() },
+        };
     }
 
+    let ticket = match inference_queue::enqueue(priority) {
+        Ok(ticket) => ticket,
+        Err(e) => return RoutedResponse::dropped(&e),
+    };
+    let queue_position = ticket.position();
+    let _permit = match ticket.wait().await {
+        Ok(permit) => permit,
+        Err(e) => {
+            let mut dropped = RoutedResponse::dropped(&e);
+            dropped.route.queue_position = Some(queue_position);
+            return dropped;
+        }
+    };
+
     // Determine which source to use
     let mut chosen = source;
 
@@ -50,31 +87,56 @@ pub async fn get_response(source: LlmSource, prompt: &str) -> String {
         chosen = LlmSource::Cloud;
     }
 
-    let result = match chosen {
-        LlmSource::Local => local_llm::generate_response(prompt).await,
-        LlmSource::Cloud => cloud_llm::generate_response(prompt).await,
+    let primary = match chosen {
+        LlmSource::Local => ModelRoute::CurrentLocal,
+        LlmSource::Cloud => ModelRoute::Cloud,
     };
+    let mut result = routing::route(&routing::LlmBackend, primary, prompt, context).await;
+    result.route.queue_position = Some(queue_position);
+    result.text = guard::screen_response(&result.text, "ai router").await;
 
-    CACHE
-        .write()
-        .await
-        .insert(prompt.to_string(), result.clone());
+    if !result.route.served_by.is_empty() {
+        CACHE
+            .write()
+            .await
+            .insert(prompt.to_string(), result.text.clone());
+    }
     result
 }
 
 /// Get TARS-enhanced response with personality and engineering focus
 pub async fn get_tars_response(source: LlmSource, prompt: &str, context: &str) -> String {
+    get_routed_tars_response(source, prompt, context, InferencePriority::Interactive).await.text
+}
+
+/// Like `get_tars_response`, reporting which model answered
+pub async fn get_routed_tars_response(
+    source: LlmSource,
+    prompt: &str,
+    context: &str,
+    priority: InferencePriority,
+) -> RoutedResponse {
     // Apply TARS personality processing to the prompt
     let enhanced_prompt = TARSCore::process_with_personality(prompt, context).await;
     
     // Get base AI response
-    let base_response = get_response(source, &enhanced_prompt).await;
+    let RoutedResponse { text: base_response, route } = get_routed_response(source, &enhanced_prompt, context, priority).await;
+    if route.dropped {
+        return RoutedResponse { text: base_response, route };
+    }
     
     // Apply TARS personality filter to the response
     let personality = crate::personality::TARSPersonality::get_current_state().await;
     let final_response = personality.apply_personality_filter(&base_response, context).await;
     
-    final_response
+    let final_response = personality.enforce_length(&final_response);
+
+    // The confidence note goes after trimming so it is never cut
+    let text = match super::confidence::assess_answer(source, priority, &route.served_by, personality.honesty, prompt, &base_response).await {
+        Some(note) => format!("{}\n\n{}", final_response, note),
+        None => final_response,
+    };
+    RoutedResponse { text, route }
 }
 
 /// Conduct code review with TARS engineering manager capabilities
@@ -106,6 +168,39 @@ pub async fn conduct_code_review(code: &str, language: &str, context: &str) -> S
     response
 }
 
+/// Review a source tree and gate on findings at `fail_on` severity or
+/// worse. Findings for unchanged files come from the cache at `cache_path`;
+/// `changed` (or the files changed since `since_ref`) are always re-analyzed.
+pub async fn conduct_code_review_gate(
+    dir: &Path,
+    fail_on: StandardSeverity,
+    cache_path: &Path,
+    changed: Option<Vec<PathBuf>>,
+    since_ref: Option<&str>,
+) -> Result<IncrementalReview, String> {
+    let changed = match since_ref {
+        Some(git_ref) => {
+            let mut since = changed_files_since(dir, git_ref)?;
+            since.extend(changed.unwrap_or_default());
+            Some(since)
+        }
+        None => changed,
+    };
+    let mut cache = ReviewCache::load(cache_path)?;
+    let engineering_manager = EngineeringManager::new().await;
+    let review = engineering_manager
+        .review_directory_incremental(dir, fail_on, &mut cache, changed.as_deref())
+        .await?;
+    cache.save(cache_path)?;
+    Ok(review)
+}
+
+/// Three-point task estimate from the engineering manager
+pub async fn estimate_task(description: &str, signals: &ComplexitySignals) -> TaskEstimate {
+    let engineering_manager = EngineeringManager::new().await;
+    engineering_manager.estimate_task(description, signals)
+}
+
 /// Get coding standards report for a language
 pub async fn get_coding_standards_report(language: &str) -> String {
     let standards_engine = CodingStandardsEngine::new().await;
@@ -141,15 +236,21 @@ pub async fn get_stack_recommendations(stack: Vec<&str>) -> String {
 }
 
 /// Adjust TARS personality settings
-pub async fn adjust_tars_personality(humor: Option<f32>, honesty: Option<f32>, sarcasm: Option<f32>) -> Result<String, String> {
-    TARSCore::adjust_personality(humor, honesty, sarcasm).await?;
+pub async fn adjust_tars_personality(
+    humor: Option<f32>,
+    honesty: Option<f32>,
+    sarcasm: Option<f32>,
+    verbosity: Option<f32>,
+) -> Result<String, String> {
+    TARSCore::adjust_personality(humor, honesty, sarcasm, verbosity).await?;
     
     let current_state = TARSCore::get_personality_status().await;
     
     Ok(format!(
-        "[PERSONALITY UPDATE COMPLETE]\nHumor: {}%\nHonesty: {}%\nSarcasm: {}%\nMission Focus: 100%\n\nThat's what I would have said. Eventually.",
+        "[PERSONALITY UPDATE COMPLETE]\nHumor: {}%\nHonesty: {}%\nSarcasm: {}%\nVerbosity: {}%\nMission Focus: 100%\n\nThat's what I would have said. Eventually.",
         (current_state.humor * 100.0) as u8,
         (current_state.honesty * 100.0) as u8,
-        (current_state.sarcasm * 100.0) as u8
+        (current_state.sarcasm * 100.0) as u8,
+        (current_state.verbosity * 100.0) as u8
     ))
 }
@@ -0,0 +1,362 @@
+//! Model routing with fallbacks and ensembles.
+//!
+//! The policy lists routes to try after the primary model: another local model
+//! or the cloud API. A route that fails or runs past the timeout is marked
+//! unavailable for a cooldown and skipped until it expires. Contexts listed as
+//! high-stakes are answered by two routes at once and merged by the policy's
+//! preference rule. The models themselves sit behind `ModelBackend`;
+//! `LlmBackend` is the real one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use super::guard::{GuardFlag, HeldPrompt};
+use super::{cloud_llm, local_llm};
+
+static POLICY: Lazy<RwLock<RoutingPolicy>> = Lazy::new(|| RwLock::new(RoutingPolicy::default()));
+
+/// Routes skipped until the stored time
+static UNAVAILABLE: Lazy<RwLock<HashMap<ModelRoute, Instant>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// A model a prompt can be sent to. Written in config as `local` (the
+/// active local model), `local:<model>` or `cloud`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ModelRoute {
+    CurrentLocal,
+    Local(String),
+    Cloud,
+}
+
+impl fmt::Display for ModelRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelRoute::CurrentLocal => write!(f, "local"),
+            ModelRoute::Local(model) => write!(f, "local:{}", model),
+            ModelRoute::Cloud => write!(f, "cloud"),
+        }
+    }
+}
+
+impl FromStr for ModelRoute {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "local" => Ok(ModelRoute::CurrentLocal),
+            "cloud" => Ok(ModelRoute::Cloud),
+            other => match other.strip_prefix("local:") {
+                Some(model) if !model.trim().is_empty() => Ok(ModelRoute::Local(model.trim().to_string())),
+                _ => Err(format!("Unknown model route '{}'. Use local, local:<model> or cloud", other)),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for ModelRoute {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ModelRoute> for String {
+    fn from(route: ModelRoute) -> Self {
+        route.to_string()
+    }
+}
+
+/// Sends a prompt to one route. `LlmBackend` is the real implementation;
+/// tests substitute their own.
+#[async_trait]
+pub trait ModelBackend: Send + Sync {
+    async fn generate(&self, route: &ModelRoute, prompt: &str) -> Result<String, String>;
+}
+
+/// The local Ollama models and the cloud API
+pub struct LlmBackend;
+
+#[async_trait]
+impl ModelBackend for LlmBackend {
+    async fn generate(&self, route: &ModelRoute, prompt: &str) -> Result<String, String> {
+        match route {
+            ModelRoute::CurrentLocal => {
+                let model = local_llm::current_model().await;
+                local_llm::generate_with_model(&model, prompt).await.map_err(|e| e.to_string())
+            },
+            ModelRoute::Local(model) => local_llm::generate_with_model(model, prompt).await.map_err(|e| e.to_string()),
+            ModelRoute::Cloud => cloud_llm::try_generate_response(prompt).await,
+        }
+    }
+}
+
+/// Which answer an ensemble keeps when its two answers differ
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePreference {
+    /// The first route's answer
+    #[default]
+    Primary,
+    /// The more detailed answer
+    Longest,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingPolicy {
+    /// Tried in order when the primary route fails or times out
+    pub fallbacks: Vec<ModelRoute>,
+    pub timeout_secs: u64,
+    /// How long a failed route is skipped
+    pub cooldown_secs: u64,
+    /// Contexts answered by two routes and merged, such as "code review"
+    pub ensemble_contexts: Vec<String>,
+    pub merge: MergePreference,
+}
+
+impl Default for RoutingPolicy {
+    fn default() -> Self {
+        Self {
+            fallbacks: vec![ModelRoute::Cloud],
+            timeout_secs: 30,
+            cooldown_secs: 60,
+            ensemble_contexts: Vec::new(),
+            merge: MergePreference::Primary,
+        }
+    }
+}
+
+impl RoutingPolicy {
+    /// The primary route followed by the fallbacks, without repeats
+    pub fn routes(&self, primary: ModelRoute) -> Vec<ModelRoute> {
+        let mut routes = vec![primary];
+        for route in &self.fallbacks {
+            if !routes.contains(route) {
+                routes.push(route.clone());
+            }
+        }
+        routes
+    }
+
+    pub fn is_ensemble(&self, context: &str) -> bool {
+        self.ensemble_contexts.iter().any(|c| c.eq_ignore_ascii_case(context.trim()))
+    }
+}
+
+/// A route that was passed over, and why
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedRoute {
+    pub route: String,
+    pub reason: String,
+}
+
+/// How a response was produced
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteReport {
+    /// Routes whose answers make up the response; two for an ensemble
+    pub served_by: Vec<String>,
+    pub skipped: Vec<SkippedRoute>,
+    pub ensemble: bool,
+    /// Answered from the prompt cache without calling a model
+    pub cached: bool,
+    /// Set when the input guard held the prompt back from the models
+    pub flagged: Option<GuardFlag>,
+    /// Approval request to approve before asking the flagged prompt again
+    pub approval_request: Option<String>,
+    /// Requests served before this one when it joined the inference queue
+    pub queue_position: Option<usize>,
+    /// The inference queue was full and refused or dropped the request
+    pub dropped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutedResponse {
+    pub text: String,
+    pub route: RouteReport,
+}
+
+impl RoutedResponse {
+    /// Reply for a prompt the input guard flagged
+    pub fn flagged(held: HeldPrompt) -> Self {
+        Self {
+            text: format!(
+                "[REQUEST HELD FOR APPROVAL]\n\nThis request was flagged by the input guard ({}). \
+                TARS will not act on it until approval request {} is approved. Ask again after that.",
+                held.flag.summary(),
+                held.request_id
+            ),
+            route: RouteReport {
+                flagged: Some(held.flag),
+                approval_request: Some(held.request_id),
+                ..RouteReport::default()
+            },
+        }
+    }
+
+    /// Reply for a request the inference queue had no room for
+    pub fn dropped(reason: &str) -> Self {
+        Self {
+            text: format!("[REQUEST DROPPED]\n\nTARS is busy: {}. Try again shortly.", reason),
+            route: RouteReport { dropped: true, ..RouteReport::default() },
+        }
+    }
+}
+
+pub async fn policy() -> RoutingPolicy {
+    POLICY.read().await.clone()
+}
+
+pub async fn set_policy(policy: RoutingPolicy) {
+    *POLICY.write().await = policy;
+}
+
+/// Skip `route` for `duration`
+pub async fn mark_unavailable(route: &ModelRoute, duration: Duration) {
+    UNAVAILABLE.write().await.insert(route.clone(), Instant::now() + duration);
+}
+
+pub async fn mark_available(route: &ModelRoute) {
+    UNAVAILABLE.write().await.remove(route);
+}
+
+pub async fn is_available(route: &ModelRoute) -> bool {
+    UNAVAILABLE.read().await.get(route).is_none_or(|until| Instant::now() >= *until)
+}
+
+/// Answer `prompt` from `primary`, falling back per the current policy, or
+/// from two routes when the policy lists `context` for an ensemble
+pub async fn route(backend: &dyn ModelBackend, primary: ModelRoute, prompt: &str, context: &str) -> RoutedResponse {
+    let policy = policy().await;
+    let mut report = RouteReport::default();
+    let mut routes = Vec::new();
+    for route in policy.routes(primary) {
+        if is_available(&route).await {
+            routes.push(route);
+        } else {
+            report.skipped.push(SkippedRoute { route: route.to_string(), reason: "marked unavailable".into() });
+        }
+    }
+
+    if policy.is_ensemble(context) && routes.len() >= 2 {
+        let (first, second) = tokio::join!(
+            attempt(backend, &policy, &routes[0], prompt),
+            attempt(backend, &policy, &routes[1], prompt),
+        );
+        match (first, second) {
+            (Ok(a), Ok(b)) => {
+                report.served_by = vec![routes[0].to_string(), routes[1].to_string()];
+                report.ensemble = true;
+                return RoutedResponse { text: merge(a, b, policy.merge), route: report };
+            },
+            (Ok(text), Err(reason)) => {
+                report.skipped.push(SkippedRoute { route: routes[1].to_string(), reason });
+                report.served_by = vec![routes[0].to_string()];
+                return RoutedResponse { text, route: report };
+            },
+            (Err(reason), Ok(text)) => {
+                report.skipped.push(SkippedRoute { route: routes[0].to_string(), reason });
+                report.served_by = vec![routes[1].to_string()];
+                return RoutedResponse { text, route: report };
+            },
+            (Err(a), Err(b)) => {
+                report.skipped.push(SkippedRoute { route: routes[0].to_string(), reason: a });
+                report.skipped.push(SkippedRoute { route: routes[1].to_string(), reason: b });
+                routes.drain(..2);
+            },
+        }
+    }
+
+    for route in &routes {
+        match attempt(backend, &policy, route, prompt).await {
+            Ok(text) => {
+                report.served_by = vec![route.to_string()];
+                return RoutedResponse { text, route: report };
+            },
+            Err(reason) => report.skipped.push(SkippedRoute { route: route.to_string(), reason }),
+        }
+    }
+
+    let reasons: Vec<String> = report.skipped.iter()
+        .map(|skipped| format!("{}: {}", skipped.route, skipped.reason))
+        .collect();
+    RoutedResponse { text: format!("No model available ({})", reasons.join("; ")), route: report }
+}
+
+/// One call to `route`, marking it unavailable when it fails or times out
+async fn attempt(
+    backend: &dyn ModelBackend,
+    policy: &RoutingPolicy,
+    route: &ModelRoute,
+    prompt: &str,
+) -> Result<String, String> {
+    let timeout = Duration::from_secs(policy.timeout_secs);
+    let result = match tokio::time::timeout(timeout, backend.generate(route, prompt)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}s", policy.timeout_secs)),
+    };
+    if let Err(reason) = &result {
+        log::warn!("Model route {} failed: {}", route, reason);
+        mark_unavailable(route, Duration::from_secs(policy.cooldown_secs)).await;
+    }
+    result
+}
+
+fn merge(primary: String, secondary: String, preference: MergePreference) -> String {
+    let same = primary.split_whitespace().eq(secondary.split_whitespace());
+    match preference {
+        _ if same => primary,
+        MergePreference::Primary => primary,
+        MergePreference::Longest if secondary.len() > primary.len() => secondary,
+        MergePreference::Longest => primary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Answers with the route name and records which routes were called
+    #[derive(Default)]
+    struct RecordingBackend {
+        calls: Mutex<Vec<ModelRoute>>,
+    }
+
+    #[async_trait]
+    impl ModelBackend for RecordingBackend {
+        async fn generate(&self, route: &ModelRoute, _prompt: &str) -> Result<String, String> {
+            self.calls.lock().unwrap().push(route.clone());
+            Ok(format!("{}-test", route))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_primary_is_served_by_fallback() {
+        set_policy(RoutingPolicy {
+            fallbacks: vec![ModelRoute::Local("tinyllama".into()), ModelRoute::Cloud],
+            ..RoutingPolicy::default()
+        })
+        .await;
+        mark_unavailable(&ModelRoute::CurrentLocal, Duration::from_secs(60)).await;
+
+        let backend = RecordingBackend::default();
+        let response = route(&backend, ModelRoute::CurrentLocal, "Status report", "chat").await;
+
+        assert_eq!(response.text, "local:tinyllama-test");
+        assert_eq!(response.route.served_by, vec!["local:tinyllama"]);
+        assert_eq!(response.route.skipped, vec![SkippedRoute {
+            route: "local".into(),
+            reason: "marked unavailable".into(),
+        }]);
+        assert!(!response.route.ensemble);
+        assert_eq!(*backend.calls.lock().unwrap(), vec![ModelRoute::Local("tinyllama".into())]);
+    }
+}
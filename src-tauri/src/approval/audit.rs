@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditLog {
@@ -33,6 +33,11 @@ static AUDIT_LOGS: Lazy<RwLock<Vec<AuditLog>>> =
 static AUDIT_CONFIG: Lazy<RwLock<AuditConfiguration>> = 
     Lazy::new(|| RwLock::new(AuditConfiguration::default()));
 
+/// Write daily audit log files to `dir`
+pub async fn set_log_dir(dir: &Path) {
+    AUDIT_CONFIG.write().await.log_file_path = Some(dir.to_string_lossy().into_owned());
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditConfiguration {
     pub enabled: bool,
@@ -90,7 +95,8 @@ impl AuditLogger {
             // Maintain size limit
             let config = AUDIT_CONFIG.read().await;
             if logs.len() > config.max_logs_in_memory {
-                logs.drain(0..logs.len() - config.max_logs_in_memory);
+                let excess = logs.len() - config.max_logs_in_memory;
+                logs.drain(0..excess);
             }
         }
         
@@ -309,11 +315,11 @@ impl AuditLogger {
                         log.id,
                         log.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
                         log.event_type,
-                        log.description.replace(',', ';'),
+                        log.description.replace(',', ";"),
                         log.user_id,
                         log.success,
-                        log.details.as_ref().unwrap_or(&"".to_string()).replace(',', ';'),
-                        log.risk_level.as_ref().unwrap_or(&"Unknown".to_string())
+                        log.details.as_deref().unwrap_or("").replace(',', ";"),
+                        log.risk_level.as_deref().unwrap_or("Unknown")
                     ));
                 }
                 
@@ -471,7 +477,8 @@ impl AuditLogger {
                 Status: AUTHORIZED\n\
                 Assessment: Normal security operation detected.\n\n\
                 Honesty setting: 90% - Everything appears in order.\n\
-                Mission focus: 100% - Continuing security monitoring."
+                Mission focus: 100% - Continuing security monitoring.",
+                event, user
             )
         } else {
             format!(
@@ -482,7 +489,8 @@ impl AuditLogger {
                 Assessment: Potential security violation detected.\n\n\
                 Sarcasm setting: 30% - That's not supposed to happen.\n\
                 Recommended Action: Immediate investigation required.\n\
-                Mission focus: 100% - Security protocols ACTIVE."
+                Mission focus: 100% - Security protocols ACTIVE.",
+                event, user
             )
         }
     }
@@ -4,4 +4,4 @@ pub mod system;
 
 pub use permissions::{PermissionLevel, PermissionManager};
 pub use audit::{AuditLog, AuditLogger};
-pub use system::{ApprovalSystem, ApprovalRequest, ApprovalResponse};
+pub use system::{ApprovalSystem, ApprovalRequest, ApprovalResponse, RequestStatus, RiskLevel};
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 use uuid::Uuid;
+use chrono::{Datelike, Timelike};
 
 use super::permissions::{PermissionLevel, PermissionManager};
 use super::audit::{AuditLog, AuditLogger};
@@ -77,6 +78,10 @@ static PENDING_REQUESTS: Lazy<RwLock<HashMap<String, ApprovalRequest>>> =
 static APPROVAL_RULES: Lazy<RwLock<HashMap<String, ApprovalRule>>> = 
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+/// Client ID for each pairing token issued by `pair_client`
+static PAIRED_CLIENTS: Lazy<RwLock<HashMap<String, String>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
 pub struct ApprovalSystem {
     permission_manager: PermissionManager,
     audit_logger: AuditLogger,
@@ -101,6 +106,24 @@ impl ApprovalSystem {
         parameters: HashMap<String, String>,
         requester: String,
     ) -> Result<String, String> {
+        let (_, message) = self.submit_request(
+            operation, description, risk_level, permission_required, target_system, parameters, requester,
+        ).await?;
+        Ok(message)
+    }
+    
+    /// Request approval for an operation, returning the request ID for
+    /// `is_approved` along with the message for the user
+    pub async fn submit_request(
+        &self,
+        operation: String,
+        description: String,
+        risk_level: RiskLevel,
+        permission_required: PermissionLevel,
+        target_system: Option<String>,
+        parameters: HashMap<String, String>,
+        requester: String,
+    ) -> Result<(String, String), String> {
         let request_id = Uuid::new_v4().to_string();
         
         // Generate TARS analysis of the request
@@ -144,7 +167,7 @@ impl ApprovalSystem {
                     None,
                 )).await?;
                 
-                return Ok(format!(
+                return Ok((request_id.clone(), format!(
                     "[AUTO-APPROVAL GRANTED]\n\n\
                     Request ID: {}\n\
                     Operation: {}\n\
@@ -153,16 +176,16 @@ impl ApprovalSystem {
                     TARS has automatically approved this operation based on configured rules.\n\
                     Operation may proceed immediately.",
                     request_id, operation, risk_level
-                ));
+                )));
             }
         }
         
         // Store pending request
         let mut requests = PENDING_REQUESTS.write().await;
-        requests.insert(request_id.clone(), request);
+        requests.insert(request_id.clone(), request.clone());
         
         // Generate approval request message
-        Ok(format!(
+        Ok((request_id.clone(), format!(
             "[APPROVAL REQUEST SUBMITTED]\n\n\
             Request ID: {}\n\
             Operation: {}\n\
@@ -178,7 +201,7 @@ impl ApprovalSystem {
             requester, request.expires_at.format("%Y-%m-%d %H:%M:%S UTC"),
             request.tars_analysis.as_ref().unwrap_or(&"No analysis available".to_string()),
             request_id, request_id
-        ))
+        )))
     }
     
     /// Approve a pending request
@@ -306,6 +329,58 @@ impl ApprovalSystem {
         }
     }
     
+    /// Where a request stands; an approval past its expiry is `Expired`
+    pub async fn request_status(&self, request_id: &str) -> Result<RequestStatus, String> {
+        let requests = PENDING_REQUESTS.read().await;
+        let request = requests.get(request_id)
+            .ok_or_else(|| format!("Request '{}' not found", request_id))?;
+        match request.status {
+            RequestStatus::Approved if chrono::Utc::now() > request.expires_at => Ok(RequestStatus::Expired),
+            ref status => Ok(status.clone()),
+        }
+    }
+
+    /// Whether `user_id` holds `permission`; unknown users hold none
+    pub async fn user_has_permission(&self, user_id: &str, permission: &PermissionLevel) -> bool {
+        self.permission_manager.has_permission(user_id, permission).await.unwrap_or(false)
+    }
+    
+    /// Pair a remote client: grant it `permission` and issue the secret it
+    /// presents when it connects. Pairing again replaces the old secret.
+    pub async fn pair_client(&self, client_id: &str, permission: PermissionLevel) -> Result<String, String> {
+        if client_id.trim().is_empty() {
+            return Err("Client ID is empty".to_string());
+        }
+        self.permission_manager.initialize_user(client_id.to_string()).await?;
+        self.permission_manager.grant_permission(client_id, permission.clone(), None).await?;
+
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let mut clients = PAIRED_CLIENTS.write().await;
+        clients.retain(|_, paired| paired != client_id);
+        clients.insert(token.clone(), client_id.to_string());
+
+        let _ = self.audit_logger.log_operation(AuditLog::new(
+            "client_paired".to_string(),
+            format!("Remote client '{}' paired with {:?} permission", client_id, permission),
+            client_id.to_string(),
+            true,
+            None,
+        )).await;
+        Ok(token)
+    }
+
+    /// The client a pairing secret was issued to
+    pub async fn authenticate_client(&self, token: &str) -> Option<String> {
+        PAIRED_CLIENTS.read().await.get(token).cloned()
+    }
+
+    /// Revoke a client's pairing secret and drop it back to read-only
+    pub async fn unpair_client(&self, client_id: &str) -> Result<(), String> {
+        PAIRED_CLIENTS.write().await.retain(|_, paired| paired != client_id);
+        self.permission_manager.initialize_user(client_id.to_string()).await?;
+        Ok(())
+    }
+
     /// Mark request as executing
     pub async fn mark_executing(&self, request_id: &str) -> Result<(), String> {
         let mut requests = PENDING_REQUESTS.write().await;
@@ -0,0 +1,183 @@
+//! Backend state shared by the GUI app and the headless `tars` CLI: the
+//! subsystems commands run against, with their health probes registered.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::ai;
+use crate::commands::math_commands::MathEngineState;
+use crate::config::config::{Config, I2cBusConfig};
+use crate::config::state_manager::StateManager;
+use crate::diagnostics;
+use crate::health::{HealthMonitor, ProbeFailure, SharedHealth};
+use crate::mathematics::{self, MathematicsEngine};
+use crate::personality::locale;
+use crate::raspberry_pi::RaspberryPiConfig;
+use crate::robotics::telemetry::Telemetry;
+use crate::robotics::{PoseLibrary, ServoSystem, SharedPoseLibrary, SharedServoSystem};
+use crate::safety::{Safety, SharedSafety};
+use crate::voice;
+
+pub type SharedMathEngine = Arc<RwLock<MathEngineState>>;
+
+pub struct Backend {
+    pub state_manager: StateManager,
+    pub telemetry: Arc<Telemetry>,
+    pub safety: SharedSafety,
+    pub health: SharedHealth,
+    /// Servo controllers are installed by `ServoSystem::initialize`
+    pub servo_system: SharedServoSystem,
+    pub math_engine: SharedMathEngine,
+}
+
+impl Backend {
+    /// Create every subsystem and register its health probe
+    pub async fn new(simulation: bool, pose_library: SharedPoseLibrary, bus_config: I2cBusConfig) -> Self {
+        let telemetry = Arc::new(Telemetry::new());
+        let safety = Safety::new();
+        let servo_system: SharedServoSystem = Arc::new(RwLock::new(
+            ServoSystem::new(simulation)
+                .with_pose_library(pose_library)
+                .with_telemetry(telemetry.clone())
+                .with_bus_config(bus_config)
+                .with_movement_audit(true)
+                .with_safety(safety.clone()),
+        ));
+        let engine = MathematicsEngine::new().await;
+        voice::advanced_tts::configure_advanced_tts_for_hardware(&RaspberryPiConfig::default()).await;
+        let backend = Self {
+            state_manager: StateManager::new(),
+            telemetry,
+            safety,
+            health: Arc::new(HealthMonitor::default()),
+            servo_system,
+            math_engine: Arc::new(RwLock::new(MathEngineState { engine, last_benchmark: None })),
+        };
+        backend.register_health_probes();
+        backend
+    }
+
+    /// Health probes: each reports Up, or Degraded/Down with the reason
+    fn register_health_probes(&self) {
+        let health = &self.health;
+        health.register("voice", || async {
+            if voice::get_tts_stats().await.is_empty() || voice::get_recognition_stats().await.is_empty() {
+                return Err(ProbeFailure::Degraded("voice engines reported no status".into()));
+            }
+            Ok(())
+        });
+        {
+            let servo_system = self.servo_system.clone();
+            health.register("servo", move || {
+                let servo_system = servo_system.clone();
+                async move {
+                    let system = servo_system.read().await;
+                    if !system.is_initialized() {
+                        return Err(ProbeFailure::Down("servo system not initialized".into()));
+                    }
+                    if system.movement_controller().is_none() {
+                        return Err(ProbeFailure::Degraded("movement controller unavailable".into()));
+                    }
+                    Ok(())
+                }
+            });
+        }
+        {
+            let math_engine = self.math_engine.clone();
+            health.register("math engine", move || {
+                let math_engine = math_engine.clone();
+                async move {
+                    match math_engine.read().await.engine.solve_expression("1 + 1").await {
+                        mathematics::MathResult::Success { .. } => Ok(()),
+                        mathematics::MathResult::Error(e) => Err(ProbeFailure::Down(e)),
+                    }
+                }
+            });
+        }
+        {
+            let telemetry = self.telemetry.clone();
+            health.register("telemetry", move || {
+                let serving = telemetry.is_serving();
+                async move {
+                    if serving {
+                        Ok(())
+                    } else {
+                        Err(ProbeFailure::Down("telemetry server is not listening".into()))
+                    }
+                }
+            });
+        }
+        health.register("ai model", || async {
+            let model = ai::local_llm::current_model().await;
+            let installed = ai::local_llm::list_models()
+                .await
+                .map_err(|e| ProbeFailure::Down(format!("local model server unreachable: {}", e)))?;
+            if installed.iter().any(|name| name == &model || name.starts_with(&format!("{}:", model))) {
+                Ok(())
+            } else {
+                Err(ProbeFailure::Degraded(format!("model '{}' is not installed", model)))
+            }
+        });
+        {
+            let safety = self.safety.clone();
+            health.register("safety", move || {
+                let safety = safety.clone();
+                async move {
+                    if safety.is_emergency().await {
+                        Err(ProbeFailure::Down("emergency stop active".into()))
+                    } else {
+                        Ok(())
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// The configured pose library, or the built-in poses when it can't be loaded
+pub fn load_pose_library(path: &Path) -> SharedPoseLibrary {
+    match PoseLibrary::load(path) {
+        Ok((library, _)) => Arc::new(RwLock::new(library)),
+        Err(e) => {
+            log::warn!("Using built-in poses only: {}", e);
+            Arc::new(RwLock::new(PoseLibrary::builtin()))
+        }
+    }
+}
+
+/// Apply the config's honesty threshold, confidence policy, routing policy
+/// and input guard
+pub async fn apply_ai_config(cfg: &Config) {
+    ai::confidence::set_honesty_threshold(cfg.personality.honesty_threshold).await;
+    ai::confidence::set_policy(cfg.ai.confidence.clone()).await;
+    ai::routing::set_policy(cfg.ai.routing.clone()).await;
+    ai::guard::set_policy(cfg.ai.guard.clone()).await;
+    ai::guard::set_secrets(guarded_secrets(cfg)).await;
+    ai::inference_queue::set_config(cfg.ai.queue.clone());
+}
+
+/// Load the user locale files and switch to the configured locale
+pub fn init_locale(cfg: &Config) {
+    match locale::load_locales(Path::new(locale::LOCALES_DIR)) {
+        Ok(loaded) if !loaded.is_empty() => log::info!("Loaded locale files: {}", loaded.join(", ")),
+        Ok(_) => {}
+        Err(e) => log::warn!("Using built-in locales only: {}", e),
+    }
+    apply_locale(&cfg.personality.locale);
+}
+
+/// Switch TARS's phrases to `code`, keeping the current locale when unknown
+pub fn apply_locale(code: &str) {
+    if let Err(e) = locale::set_locale(code) {
+        log::warn!("Keeping locale '{}': {}", locale::active_locale(), e);
+    }
+}
+
+/// Config secrets plus the cloud key, which is read from the environment
+pub fn guarded_secrets(cfg: &Config) -> Vec<String> {
+    let mut secrets = diagnostics::config_secrets(cfg);
+    secrets.extend(std::env::var("OPENAI_API_KEY").ok().filter(|key| !key.is_empty()));
+    secrets
+}
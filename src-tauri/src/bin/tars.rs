@@ -0,0 +1,48 @@
+//! Headless TARS: `tars ask`, `move`, `speak`, `run-prompt` and
+//! `health-check` over the same backend as the GUI.
+
+use gsteng::backend::{self, Backend};
+use gsteng::cli::{self, USAGE};
+use gsteng::config::config::Config;
+
+#[tokio::main]
+async fn main() {
+    let args = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let cfg = match Config::load(&args.config_path) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("Failed to load config {}: {}", args.config_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = gsteng::logging::init(&cfg.logging) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+    backend::apply_ai_config(&cfg).await;
+    backend::init_locale(&cfg);
+    let backend = Backend::new(
+        cfg.robotics.simulation,
+        backend::load_pose_library(&cfg.robotics.poses_file),
+        cfg.robotics.i2c.clone(),
+    ).await;
+
+    match cli::run(&args, &backend).await {
+        Ok(result) => {
+            println!("{}", result.output);
+            if !result.success {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
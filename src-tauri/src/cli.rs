@@ -0,0 +1,196 @@
+//! Headless `tars` command line. Each subcommand runs the same command
+//! implementation as the GUI against a `Backend`, so TARS can be scripted
+//! over SSH without a window.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::backend::Backend;
+use crate::commands::{self, servo_commands::run_movement_command, voice_commands::speak_on_output_device};
+use crate::health::{HealthReport, HealthStatus};
+
+pub const USAGE: &str = "\
+Usage: tars [--json] [--cloud] [--config <path>] <command> [args]
+
+Commands:
+  ask <prompt> [--context <context>]  Ask TARS, with personality
+  move <command>                      step_forward, turn_left, turn_right, neutral, stop or a pose name
+  speak <text>                        Speak on the configured output device
+  run-prompt <prompt>                 Send a prompt straight to the model, without personality
+  health-check                        Subsystem health; exits non-zero when a subsystem is down
+
+Options:
+  --json           Print the command's result as JSON
+  --cloud          Use the cloud model instead of the local one
+  --config <path>  Config file (default config.toml)";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Ask { prompt: String, context: String },
+    Move { command: String },
+    Speak { text: String },
+    RunPrompt { prompt: String },
+    HealthCheck,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    pub command: CliCommand,
+    pub format: OutputFormat,
+    pub use_cloud: bool,
+    pub config_path: PathBuf,
+}
+
+/// What to print, and whether the command succeeded
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOutput {
+    pub output: String,
+    pub success: bool,
+}
+
+/// Parse the arguments after the program name. Options may appear anywhere;
+/// the words after the command are joined into its text.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliArgs, String> {
+    let mut format = OutputFormat::Text;
+    let mut use_cloud = false;
+    let mut config_path = PathBuf::from("config.toml");
+    let mut context = None;
+    let mut words = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => format = OutputFormat::Json,
+            "--cloud" => use_cloud = true,
+            "--config" => config_path = args.next().ok_or("--config needs a path")?.into(),
+            "--context" => context = Some(args.next().ok_or("--context needs a value")?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            _ => words.push(arg),
+        }
+    }
+
+    let Some((name, rest)) = words.split_first() else {
+        return Err("No command given".to_string());
+    };
+    let text = rest.join(" ");
+    let require_text = |what: &str| {
+        if text.trim().is_empty() {
+            Err(format!("'{}' needs {}", name, what))
+        } else {
+            Ok(text.clone())
+        }
+    };
+    let command = match name.as_str() {
+        "ask" => CliCommand::Ask { prompt: require_text("a prompt")?, context: context.take().unwrap_or_default() },
+        "move" => CliCommand::Move { command: require_text("a movement command")? },
+        "speak" => CliCommand::Speak { text: require_text("text to speak")? },
+        "run-prompt" => CliCommand::RunPrompt { prompt: require_text("a prompt")? },
+        "health-check" if rest.is_empty() => CliCommand::HealthCheck,
+        "health-check" => return Err("'health-check' takes no arguments".to_string()),
+        other => return Err(format!("Unknown command '{}'", other)),
+    };
+    if context.is_some() {
+        return Err("--context only applies to 'ask'".to_string());
+    }
+    Ok(CliArgs { command, format, use_cloud, config_path })
+}
+
+/// Run a parsed command against `backend`
+pub async fn run(args: &CliArgs, backend: &Backend) -> Result<CliOutput, String> {
+    let format = args.format;
+    match &args.command {
+        CliCommand::Ask { prompt, context } => {
+            let response = commands::ask_tars(prompt.clone(), context.clone(), args.use_cloud, None).await;
+            render(format, &response, &response.text, response.route.flagged.is_none())
+        },
+        CliCommand::RunPrompt { prompt } => {
+            let response = commands::ask_ai(prompt.clone(), args.use_cloud, None).await;
+            render(format, &response, &response.text, response.route.flagged.is_none())
+        },
+        CliCommand::Move { command } => {
+            {
+                let mut system = backend.servo_system.write().await;
+                if !system.is_initialized() {
+                    system.initialize().await?;
+                }
+            }
+            let response = run_movement_command(command, None, None, &backend.servo_system).await?;
+            render(format, &response, &response.message, response.success)
+        },
+        CliCommand::Speak { text } => {
+            let preferred_device = crate::config::config::Config::load(&args.config_path)
+                .map_err(|e| format!("Failed to load config: {}", e))?
+                .audio
+                .output_device;
+            let device = speak_on_output_device(text.clone(), preferred_device).await?;
+            render(format, &device, &format!("Spoke on {}", device.name), true)
+        },
+        CliCommand::HealthCheck => {
+            let report = backend.health.check().await;
+            render(format, &report, &health_text(&report), report.status != HealthStatus::Down)
+        },
+    }
+}
+
+fn render<T: Serialize>(format: OutputFormat, value: &T, text: &str, success: bool) -> Result<CliOutput, String> {
+    let output = match format {
+        OutputFormat::Text => text.to_string(),
+        OutputFormat::Json => serde_json::to_string_pretty(value).map_err(|e| e.to_string())?,
+    };
+    Ok(CliOutput { output, success })
+}
+
+fn health_text(report: &HealthReport) -> String {
+    let mut lines = vec![format!("{:?} (version {}, {})", report.status, report.version, report.git_sha)];
+    for subsystem in &report.subsystems {
+        let mut line = format!("  {:<12} {:<9} {}ms", subsystem.name, format!("{:?}", subsystem.status), subsystem.latency_ms);
+        if let Some(error) = &subsystem.last_error {
+            line.push_str(&format!("  {}", error));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::ProbeFailure;
+    use crate::robotics::PoseLibrary;
+    use std::sync::Arc;
+
+    fn args(line: &str) -> Result<CliArgs, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[tokio::test]
+    async fn test_health_check_json_is_a_parseable_status_report() {
+        let parsed = args("health-check --json").unwrap();
+        assert_eq!(parsed.command, CliCommand::HealthCheck);
+        assert_eq!(parsed.format, OutputFormat::Json);
+        assert_eq!(args("ask").unwrap_err(), "'ask' needs a prompt");
+
+        let pose_library = Arc::new(tokio::sync::RwLock::new(PoseLibrary::builtin()));
+        let mut backend = Backend::new(true, pose_library, Default::default()).await;
+        // Replace the real probes, which would reach for the model server
+        backend.health = Arc::new(crate::health::HealthMonitor::default());
+        backend.health.register("servo", || async { Ok(()) });
+        backend.health.register("telemetry", || async { Err(ProbeFailure::Down("not listening".into())) });
+
+        let result = run(&parsed, &backend).await.unwrap();
+
+        assert!(!result.success);
+        let report: HealthReport = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(report.subsystems.len(), 2);
+        assert_eq!(report.subsystems[1].last_error.as_deref(), Some("not listening"));
+    }
+}
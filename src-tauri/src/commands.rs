@@ -1,11 +1,26 @@
-use crate::ai::{router, router::LlmSource};
-use crate::config::config::SharedConfig;
+use crate::ai::{guard, inference_queue::InferencePriority, router, router::LlmSource, routing::RoutedResponse};
+use crate::approval::ApprovalSystem;
+use crate::config::config::{notify_change, ConfigChange, SharedConfig};
 use crate::config::state_manager::{RobotState, StateManager};
-use crate::robotics::telemetry::Telemetry;
+use crate::config::user_preferences::{SharedPreferences, UserPreferences};
+use crate::diagnostics::{self, BundleSummary, DiagnosticBundle, AUDIT_TAIL_LINES, LOG_TAIL_LINES, TELEMETRY_FRAMES};
+use crate::health::{HealthReport, SharedHealth};
+use crate::personality::engineering_manager::{ComplexitySignals, IncrementalReview, StandardSeverity, TaskEstimate};
+use crate::personality::tars_core::PersonalitySettings;
+use crate::raspberry_pi::{hardware_monitor::HardwareMonitor, RaspberryPiConfig};
+use crate::robotics::telemetry::{RecordedFrame, Telemetry};
+use crate::register_command;
 use crate::safety::SharedSafety;
+use crate::voice::{self, VoicePipelineMetrics};
+use registry::CommandRegistry;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::command;
 
+// Command registry and list_commands
+pub mod registry;
+
 // Servo control commands
 pub mod servo_commands;
 
@@ -18,6 +33,9 @@ pub mod pattern_commands;
 // Test generation commands
 pub mod test_commands;
 
+// Action script commands
+pub mod script_commands;
+
 // Raspberry Pi optimization commands
 pub mod pi_commands;
 
@@ -27,23 +45,40 @@ pub mod remote_commands;
 // Voice interaction and speech processing commands
 pub mod voice_commands;
 
+// Offline documentation search
+pub mod docs_commands;
+
+// PDF prompt plan loading and execution
+pub mod pdf_commands;
+
 // Re-export commands for use in main.rs
 pub use servo_commands::*;
 pub use math_commands::*;
 pub use pattern_commands::*;
 pub use test_commands::*;
+pub use script_commands::*;
 pub use pi_commands::*;
 pub use remote_commands::*;
 pub use voice_commands::*;
+pub use docs_commands::*;
+pub use pdf_commands::*;
+pub use registry::*;
+
+/// Per-file findings reused by incremental code review gates
+const REVIEW_CACHE_FILE: &str = "review_cache.json";
 
+/// Answer with the model that served it and any routes that were skipped
 #[command]
-pub async fn ask_ai(prompt: String, use_cloud: bool) -> String {
+pub async fn ask_ai(prompt: String, use_cloud: bool, priority: Option<InferencePriority>) -> RoutedResponse {
+    if let Err(held) = guard::check_chat_prompt(&ApprovalSystem::new(), &prompt, "ask_ai").await {
+        return RoutedResponse::flagged(held);
+    }
     let source = if use_cloud {
         LlmSource::Cloud
     } else {
         LlmSource::Local
     };
-    router::get_response(source, &prompt).await
+    router::get_routed_response(source, &prompt, "", priority.unwrap_or_default()).await
 }
 
 #[command]
@@ -60,6 +95,57 @@ pub async fn set_personality(
     Ok(())
 }
 
+/// Apply a named personality preset and notify voice, AI and the frontend
+#[command]
+pub async fn apply_personality_preset(
+    name: String,
+    cfg: tauri::State<'_, SharedConfig>,
+    window: tauri::Window,
+) -> Result<ConfigChange, String> {
+    let change = cfg.lock().await.apply_personality_preset(&name)?;
+    notify_change(change.clone());
+    let _ = window.emit("tars-config-changed", &change);
+    Ok(change)
+}
+
+#[command]
+pub async fn list_personality_presets(
+    cfg: tauri::State<'_, SharedConfig>,
+) -> Result<std::collections::BTreeMap<String, PersonalitySettings>, String> {
+    Ok(cfg.lock().await.personality.presets.clone())
+}
+
+/// The current user's preferences
+#[command]
+pub async fn get_user_preferences(
+    store: tauri::State<'_, SharedPreferences>,
+) -> Result<UserPreferences, String> {
+    Ok(store.get().await)
+}
+
+/// Save the user's preferences and apply them on top of the running config
+#[command]
+pub async fn set_user_preferences(
+    preferences: UserPreferences,
+    store: tauri::State<'_, SharedPreferences>,
+    cfg: tauri::State<'_, SharedConfig>,
+    window: tauri::Window,
+) -> Result<(), String> {
+    // Validate against the running config before anything is persisted
+    let mut cfg = cfg.lock().await;
+    let mut updated = cfg.clone();
+    let change = preferences.apply(&mut updated)?;
+    store.set(preferences.clone()).await?;
+    *cfg = updated;
+    drop(cfg);
+    voice::advanced_tts::set_quality_mode_override(preferences.voice_quality).await;
+
+    let change = change.unwrap_or(ConfigChange::Preferences);
+    notify_change(change.clone());
+    let _ = window.emit("tars-config-changed", &change);
+    Ok(())
+}
+
 #[command]
 pub async fn start_listening(state: tauri::State<'_, StateManager>) {
     state.set_state(RobotState::Listening).await;
@@ -77,12 +163,7 @@ pub async fn move_robot(
     safety: tauri::State<'_, SharedSafety>,
     state: tauri::State<'_, StateManager>,
 ) -> Result<(), String> {
-    if safety.is_emergency().await {
-        return Err("Emergency stop engaged".into());
-    }
-    if !safety.check_move_allowed().await {
-        return Err("Rate limited".into());
-    }
+    safety.check_movement().await?;
 
     if let Some(rest) = command.strip_prefix("servo:") {
         for part in rest.split(',') {
@@ -101,47 +182,86 @@ pub async fn move_robot(
 }
 
 #[command]
-pub async fn get_telemetry(telemetry: tauri::State<'_, Arc<Telemetry>>) -> Vec<String> {
-    telemetry.replay().await
+pub async fn get_telemetry(telemetry: tauri::State<'_, Arc<Telemetry>>) -> Vec<RecordedFrame> {
+    telemetry.get_history().await
 }
 
 #[command]
-pub async fn emergency_stop(
+pub async fn start_telemetry_recording(
+    path: String,
+    telemetry: tauri::State<'_, Arc<Telemetry>>,
+) -> Result<(), String> {
+    telemetry.start_recording(std::path::Path::new(&path)).await
+}
+
+#[command]
+pub async fn stop_telemetry_recording(telemetry: tauri::State<'_, Arc<Telemetry>>) -> Result<(), String> {
+    telemetry.stop_recording().await;
+    Ok(())
+}
+
+/// Replay a recording over the telemetry WebSocket; returns the frame count.
+#[command]
+pub async fn replay_telemetry(
+    path: String,
+    speed: Option<f32>,
     telemetry: tauri::State<'_, Arc<Telemetry>>,
+) -> Result<usize, String> {
+    telemetry.replay(std::path::Path::new(&path), speed.unwrap_or(1.0)).await
+}
+
+#[command]
+pub async fn emergency_stop(
     safety: tauri::State<'_, SharedSafety>,
     state: tauri::State<'_, StateManager>,
 ) {
-    safety.trigger_emergency().await;
-    telemetry.broadcast("emergency_stop".into()).await;
+    // Telemetry, voice and movement react to the event on the bus
+    safety.trigger_emergency("Emergency stop requested").await;
     state.set_state(RobotState::Idle).await;
 }
 
+/// Lift a controlled stop from a safety sensor, the power monitor or the
+/// gamepad, returning its reason. The emergency stop stays latched.
 #[command]
-pub async fn health_check(
-    safety: tauri::State<'_, SharedSafety>,
-    state: tauri::State<'_, StateManager>,
-) -> String {
-    if safety.is_emergency().await {
-        return "emergency".into();
-    }
-    match state.current_state().await {
-        RobotState::Idle
-        | RobotState::Listening
-        | RobotState::Thinking
-        | RobotState::Moving => "ok".into(),
+pub async fn clear_controlled_stop(safety: tauri::State<'_, SharedSafety>) -> Result<Option<String>, String> {
+    let reason = safety.clear_controlled_stop().await;
+    if let Some(reason) = &reason {
+        log::info!("Controlled stop cleared ({})", reason);
     }
+    Ok(reason)
+}
+
+/// Per-subsystem status with the build version and git SHA
+#[command]
+pub async fn health_check(health: tauri::State<'_, SharedHealth>) -> Result<HealthReport, String> {
+    Ok(health.check().await)
+}
+
+/// Synthesis latency percentiles, cache hit rates, queue depths and active
+/// streams of the realtime voice pipeline
+#[command]
+pub async fn get_voice_metrics() -> VoicePipelineMetrics {
+    voice::get_voice_pipeline_metrics().await
 }
 
 // TARS-Enhanced Commands
 
 #[command]
-pub async fn ask_tars(prompt: String, context: String, use_cloud: bool) -> String {
+pub async fn ask_tars(
+    prompt: String,
+    context: String,
+    use_cloud: bool,
+    priority: Option<InferencePriority>,
+) -> RoutedResponse {
+    if let Err(held) = guard::check_chat_prompt(&ApprovalSystem::new(), &prompt, "ask_tars").await {
+        return RoutedResponse::flagged(held);
+    }
     let source = if use_cloud {
         LlmSource::Cloud
     } else {
         LlmSource::Local
     };
-    router::get_tars_response(source, &prompt, &context).await
+    router::get_routed_tars_response(source, &prompt, &context, priority.unwrap_or_default()).await
 }
 
 #[command]
@@ -149,6 +269,40 @@ pub async fn conduct_code_review(code: String, language: String, context: String
     router::conduct_code_review(&code, &language, &context).await
 }
 
+/// Review every source file under `path` for pre-commit or CI use. The gate
+/// fails on findings at `fail_on` severity or worse (default critical).
+/// Only `changed` files, files changed since `since_ref`, and files whose
+/// content differs from the review cache are re-analyzed.
+#[command]
+pub async fn conduct_code_review_gate(
+    path: String,
+    fail_on: Option<String>,
+    changed: Option<Vec<String>>,
+    since_ref: Option<String>,
+) -> Result<IncrementalReview, String> {
+    let fail_on = match fail_on {
+        Some(name) => StandardSeverity::parse(&name)?,
+        None => StandardSeverity::Critical,
+    };
+    let changed = changed.map(|paths| paths.into_iter().map(PathBuf::from).collect());
+    router::conduct_code_review_gate(
+        Path::new(&path),
+        fail_on,
+        Path::new(REVIEW_CACHE_FILE),
+        changed,
+        since_ref.as_deref(),
+    )
+    .await
+}
+
+#[command]
+pub async fn estimate_task(description: String, signals: Option<ComplexitySignals>) -> Result<TaskEstimate, String> {
+    if description.trim().is_empty() {
+        return Err("Task description is empty".to_string());
+    }
+    Ok(router::estimate_task(&description, &signals.unwrap_or_default()).await)
+}
+
 #[command]
 pub async fn get_coding_standards(language: String) -> String {
     router::get_coding_standards_report(&language).await
@@ -165,18 +319,20 @@ pub async fn adjust_tars_personality(
     humor: Option<f32>,
     honesty: Option<f32>,
     sarcasm: Option<f32>,
+    verbosity: Option<f32>,
 ) -> Result<String, String> {
-    router::adjust_tars_personality(humor, honesty, sarcasm).await
+    router::adjust_tars_personality(humor, honesty, sarcasm, verbosity).await
 }
 
 #[command]
 pub async fn get_tars_status() -> String {
     let personality = crate::personality::TARSCore::get_personality_status().await;
     format!(
-        "TARS STATUS REPORT\n==================\nHumor: {}%\nHonesty: {}%\nSarcasm: {}%\nMission Focus: 100%\n\nAll systems operational. Standing by for engineering directives.",
+        "TARS STATUS REPORT\n==================\nHumor: {}%\nHonesty: {}%\nSarcasm: {}%\nVerbosity: {}%\nMission Focus: 100%\n\nAll systems operational. Standing by for engineering directives.",
         (personality.humor * 100.0) as u8,
         (personality.honesty * 100.0) as u8,
-        (personality.sarcasm * 100.0) as u8
+        (personality.sarcasm * 100.0) as u8,
+        (personality.verbosity * 100.0) as u8
     )
 }
 
@@ -203,3 +359,106 @@ pub async fn list_available_models() -> Result<Vec<String>, String> {
         Err(e) => Err(format!("Failed to list models: {}", e)),
     }
 }
+
+/// Prompts the input guard flagged and responses it redacted, oldest first
+#[command]
+pub async fn list_guard_events() -> Vec<guard::GuardEvent> {
+    guard::guard_events().await
+}
+
+/// Gather recent logs, the redacted config, telemetry, the audit log tail,
+/// Pi metrics and model info into a zip for bug reports. Anything that
+/// cannot be collected is listed in the bundle's MISSING.txt.
+#[command]
+pub async fn export_diagnostics(
+    path: String,
+    config: tauri::State<'_, SharedConfig>,
+    telemetry: tauri::State<'_, Arc<Telemetry>>,
+    health: tauri::State<'_, SharedHealth>,
+) -> Result<BundleSummary, String> {
+    let config = config.lock().await.clone();
+    let mut bundle = DiagnosticBundle::new();
+
+    bundle.add_result("config.toml", diagnostics::redacted_config(&config));
+    match &config.logging.file {
+        Some(log_file) => bundle.add_result("logs/tars.log", diagnostics::tail_file(log_file, LOG_TAIL_LINES)),
+        None => bundle.note_missing("logs/tars.log", "logging to stderr only"),
+    }
+
+    let history = telemetry.history().await;
+    bundle.add("telemetry/frames.txt", history[history.len().saturating_sub(TELEMETRY_FRAMES)..].join("\n"));
+    match telemetry.latest_snapshot() {
+        Some(snapshot) => bundle.add_result("telemetry/latest_snapshot.json", diagnostics::to_json(&snapshot)),
+        None => bundle.note_missing("telemetry/latest_snapshot.json", "no servo snapshot broadcast yet"),
+    }
+
+    let audit_log = diagnostics::latest_file(&config.logging.audit_dir, "audit_")
+        .and_then(|file| diagnostics::tail_file(&file, AUDIT_TAIL_LINES));
+    bundle.add_result("audit.log", audit_log);
+
+    let pi = serde_json::json!({
+        "model": RaspberryPiConfig::detect_model(),
+        "metrics": HardwareMonitor::new().collect_system_metrics().await,
+    });
+    bundle.add_result("system/pi.json", diagnostics::to_json(&pi));
+
+    let current_model = crate::ai::local_llm::current_model().await;
+    match tokio::time::timeout(Duration::from_secs(2), crate::ai::local_llm::list_models()).await {
+        Ok(Ok(installed)) => bundle.add_result(
+            "models.json",
+            diagnostics::to_json(&serde_json::json!({ "current": current_model, "installed": installed })),
+        ),
+        Ok(Err(e)) => bundle.note_missing("models.json", &format!("current model {}; model list unavailable: {}", current_model, e)),
+        Err(_) => bundle.note_missing("models.json", &format!("current model {}; model list timed out", current_model)),
+    }
+
+    bundle.add_result("health.json", diagnostics::to_json(&health.check().await));
+
+    let path = PathBuf::from(path);
+    tokio::task::spawn_blocking(move || bundle.write_zip(&path))
+        .await
+        .map_err(|e| format!("Diagnostics export failed: {}", e))?
+}
+
+/// Register every command handled by the app.
+pub fn register_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, ask_ai, Execute, "Ask the local or cloud LLM");
+    register_command!(registry, set_personality, Write, "Set humor, honesty and sarcasm levels");
+    register_command!(registry, apply_personality_preset, Write, "Apply a named personality preset");
+    register_command!(registry, list_personality_presets, Read, "Configured personality presets");
+    register_command!(registry, get_user_preferences, Read, "Current user's preferences");
+    register_command!(registry, set_user_preferences, Write, "Save and apply the current user's preferences");
+    register_command!(registry, start_listening, Execute, "Start listening for voice input");
+    register_command!(registry, stop_listening, Execute, "Stop listening for voice input");
+    register_command!(registry, move_robot, Execute, "Queue a movement command");
+    register_command!(registry, get_telemetry, Read, "Recent telemetry messages");
+    register_command!(registry, start_telemetry_recording, Write, "Record telemetry to an NDJSON file");
+    register_command!(registry, stop_telemetry_recording, Write, "Stop telemetry recording");
+    register_command!(registry, replay_telemetry, Execute, "Replay a telemetry recording");
+    register_command!(registry, emergency_stop, Execute, "Trigger the safety emergency stop");
+    register_command!(registry, clear_controlled_stop, Execute, "Lift a controlled stop so movement can resume");
+    register_command!(registry, health_check, Read, "Backend health status");
+    register_command!(registry, get_voice_metrics, Read, "Voice pipeline latency, cache and queue metrics");
+    register_command!(registry, export_diagnostics, Admin, "Export logs, redacted config and system state as a zip");
+    register_command!(registry, ask_tars, Execute, "Ask TARS a question");
+    register_command!(registry, conduct_code_review, Execute, "Run a TARS code review");
+    register_command!(registry, conduct_code_review_gate, Execute, "Review a source tree and pass or fail on a severity threshold");
+    register_command!(registry, estimate_task, Read, "Optimistic, likely and pessimistic hours for a task");
+    register_command!(registry, get_coding_standards, Read, "Coding standards for a language");
+    register_command!(registry, get_tech_stack_recommendations, Read, "Tech stack recommendations for a project");
+    register_command!(registry, adjust_tars_personality, Write, "Adjust TARS personality settings");
+    register_command!(registry, get_tars_status, Read, "TARS system status");
+    register_command!(registry, download_llm_model, Admin, "Download a local LLM model");
+    register_command!(registry, switch_llm_model, Admin, "Switch the active local LLM model");
+    register_command!(registry, list_available_models, Read, "Installed local LLM models");
+    register_command!(registry, list_guard_events, Admin, "Prompts and responses the AI guard flagged");
+    register_command!(registry, list_commands, Read, "Registered commands with their scopes");
+
+    register_servo_commands(registry);
+    register_math_commands(registry);
+    register_pattern_commands(registry);
+    register_test_commands(registry);
+    register_docs_commands(registry);
+    register_pdf_commands(registry);
+    register_script_commands(registry);
+}
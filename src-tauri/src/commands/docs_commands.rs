@@ -0,0 +1,222 @@
+//! Offline search over TARS's built-in documentation: the design pattern
+//! docs and testing best practices, split into sections and ranked with
+//! BM25 so it runs on the Pi without a search service.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use super::pattern_commands::{pattern_documentation, DOCUMENTED_PATTERNS};
+use super::registry::CommandRegistry;
+use super::test_commands::{language_practices, PRACTICE_LANGUAGES, UNIVERSAL_PRACTICES};
+use crate::register_command;
+
+/// BM25 term frequency saturation
+const K1: f32 = 1.2;
+
+/// BM25 document length normalization
+const B: f32 = 0.75;
+
+/// Results returned by `search_docs`
+const MAX_RESULTS: usize = 10;
+
+/// Longest snippet shown for a result
+const SNIPPET_CHARS: usize = 160;
+
+const STOP_WORDS: [&str; 20] = [
+    "a", "an", "and", "are", "as", "be", "by", "for", "in", "is", "it", "of", "on", "or", "that", "the", "to", "use",
+    "with", "your",
+];
+
+static DOC_INDEX: Lazy<DocIndex> = Lazy::new(DocIndex::builtin);
+
+/// A section of a built-in doc that matched a search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocSnippet {
+    /// Full doc this came from, e.g. "pattern:singleton" for
+    /// `get_pattern_documentation("singleton")` or "testing:rust" for
+    /// `get_testing_best_practices("rust")`
+    pub doc_key: String,
+    pub section: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone)]
+struct DocSection {
+    doc_key: String,
+    section: String,
+    lines: Vec<String>,
+    term_counts: HashMap<String, usize>,
+    length: usize,
+}
+
+impl DocSection {
+    /// `title` is indexed along with the section so every section of a doc
+    /// matches the doc's subject
+    fn new(doc_key: &str, title: &str, section: &str, lines: Vec<String>) -> Self {
+        let mut term_counts = HashMap::new();
+        let mut length = 0;
+        let text = format!("{} {} {}", title, section, lines.join(" "));
+        for term in tokenize(&text) {
+            *term_counts.entry(term).or_insert(0) += 1;
+            length += 1;
+        }
+        Self { doc_key: doc_key.to_string(), section: section.to_string(), lines, term_counts, length }
+    }
+
+    /// The line matching the most query terms, cut to `SNIPPET_CHARS`
+    fn snippet(&self, terms: &[String]) -> String {
+        let best = self.lines.iter()
+            .enumerate()
+            .max_by_key(|(i, line)| {
+                let words = tokenize(line);
+                let matches = terms.iter().filter(|term| words.contains(term)).count();
+                // the first line wins ties
+                (matches, std::cmp::Reverse(*i))
+            })
+            .map(|(_, line)| line.as_str())
+            .unwrap_or_default();
+        if best.chars().count() > SNIPPET_CHARS {
+            format!("{}...", best.chars().take(SNIPPET_CHARS).collect::<String>())
+        } else {
+            best.to_string()
+        }
+    }
+}
+
+/// Lowercase alphanumeric words, without stop words
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() > 1)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Split markdown into its `## ` sections, skipping code blocks
+fn markdown_sections(markdown: &str) -> (String, Vec<(String, Vec<String>)>) {
+    let mut title = String::new();
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines().map(str::trim) {
+        if line.starts_with("```") {
+            in_code = !in_code;
+        } else if in_code || line.is_empty() {
+            continue;
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            sections.push((heading.to_string(), Vec::new()));
+        } else if let Some(heading) = line.strip_prefix("# ") {
+            title = heading.to_string();
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line.trim_start_matches("- ").to_string());
+        }
+    }
+    (title, sections)
+}
+
+pub struct DocIndex {
+    sections: Vec<DocSection>,
+    /// Sections each term appears in
+    document_frequency: HashMap<String, usize>,
+    average_length: f32,
+}
+
+impl DocIndex {
+    /// Index every built-in doc
+    pub fn builtin() -> Self {
+        let mut sections = Vec::new();
+        for pattern in DOCUMENTED_PATTERNS {
+            if let Some(markdown) = pattern_documentation(pattern) {
+                let (title, parts) = markdown_sections(markdown);
+                let key = format!("pattern:{}", pattern);
+                sections.extend(parts.into_iter().map(|(heading, lines)| DocSection::new(&key, &title, &heading, lines)));
+            }
+        }
+
+        let practices = |practices: &[&str]| practices.iter().map(|practice| practice.trim_start_matches("TARS: ").to_string()).collect();
+        sections.push(DocSection::new("testing:general", "Testing", "Universal Best Practices", practices(&UNIVERSAL_PRACTICES)));
+        for language in PRACTICE_LANGUAGES {
+            let key = format!("testing:{}", language);
+            let section = format!("{} Best Practices", language);
+            sections.push(DocSection::new(&key, "Testing", &section, practices(language_practices(language))));
+        }
+        Self::new(sections)
+    }
+
+    fn new(sections: Vec<DocSection>) -> Self {
+        let mut document_frequency = HashMap::new();
+        for section in &sections {
+            for term in section.term_counts.keys() {
+                *document_frequency.entry(term.clone()).or_insert(0) += 1;
+            }
+        }
+        let total: usize = sections.iter().map(|section| section.length).sum();
+        let average_length = total as f32 / sections.len().max(1) as f32;
+        Self { sections, document_frequency, average_length }
+    }
+
+    /// Sections matching `query`, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<DocSnippet> {
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+        let count = self.sections.len() as f32;
+        let mut results: Vec<DocSnippet> = self.sections.iter()
+            .filter_map(|section| {
+                let score: f32 = terms.iter()
+                    .filter_map(|term| {
+                        let frequency = *section.term_counts.get(term)? as f32;
+                        let containing = self.document_frequency[term] as f32;
+                        let idf = ((count - containing + 0.5) / (containing + 0.5) + 1.0).ln();
+                        let norm = K1 * (1.0 - B + B * section.length as f32 / self.average_length);
+                        Some(idf * frequency * (K1 + 1.0) / (frequency + norm))
+                    })
+                    .sum();
+                (score > 0.0).then(|| DocSnippet {
+                    doc_key: section.doc_key.clone(),
+                    section: section.section.clone(),
+                    snippet: section.snippet(&terms),
+                    score,
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(limit);
+        results
+    }
+}
+
+/// Search the built-in pattern and testing docs
+#[tauri::command]
+pub async fn search_docs(query: String) -> Result<Vec<DocSnippet>, String> {
+    if tokenize(&query).is_empty() {
+        return Err("TARS: Give me something to search for, Cooper".to_string());
+    }
+    Ok(DOC_INDEX.search(&query, MAX_RESULTS))
+}
+
+/// Register the documentation commands
+pub fn register_docs_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, search_docs, Read, "Search the built-in pattern and testing docs");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_singleton_thread_safety_ranks_the_singleton_doc_first() {
+        let results = DocIndex::builtin().search("singleton thread safety", MAX_RESULTS);
+        assert_eq!((results[0].doc_key.as_str(), results[0].section.as_str()), ("pattern:singleton", "Thread Safety"));
+        assert!(results[0].snippet.contains("not thread safe"), "{}", results[0].snippet);
+
+        let last_singleton = results.iter().rposition(|result| result.doc_key == "pattern:singleton").unwrap();
+        let first_other = results.iter().position(|result| result.doc_key != "pattern:singleton");
+        assert!(first_other.is_none_or(|other| other > last_singleton), "{:#?}", results);
+        assert!(pattern_documentation(results[0].doc_key.trim_start_matches("pattern:")).is_some());
+
+        let results = DocIndex::builtin().search("pytest fixtures", MAX_RESULTS);
+        assert_eq!(results[0].doc_key, "testing:python");
+        assert!(DocIndex::builtin().search("the and of", MAX_RESULTS).is_empty());
+    }
+}
@@ -2,11 +2,18 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
 
-use crate::mathematics::{MathematicsEngine, ComplexityResult, MathResult, OptimizationResult};
+use crate::mathematics::{MathematicsEngine, MathEngineConfig, BenchmarkResult, CacheStats, ComplexityResult, ConceptExplanation, DataSource, DatasetAnalysis, HypothesisTest, MathResult, OdeMethod, OdeSolution, OptimizationResult, ProofTrace};
+use crate::mathematics::expression_validator::{self, ExpressionValidation};
+use crate::mathematics::constants::{self, ConstantValue, DEFAULT_CONSTANT_DIGITS};
+use super::registry::CommandRegistry;
+use crate::register_command;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MathEngineState {
     pub engine: MathematicsEngine,
+    /// Most recent `benchmark_math` result, quoted in TARS analysis
+    #[serde(default)]
+    pub last_benchmark: Option<BenchmarkResult>,
 }
 
 /// Analyze algorithm complexity from code
@@ -21,17 +28,41 @@ pub async fn analyze_algorithm_complexity(
     Ok(result)
 }
 
-/// Solve mathematical expression or equation
+/// Solve mathematical expression or equation. With `exact`, rational
+/// expressions are evaluated without rounding (e.g. `1/3 + 1/6` = `1/2`).
+/// With `units`, numbers may carry SI units (e.g. `2 kg * 9.8 m/s^2` =
+/// `19.6 N`) and mismatched additions are errors.
 #[tauri::command]
 pub async fn solve_mathematical_expression(
     expression: String,
+    exact: Option<bool>,
+    units: Option<bool>,
     math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
 ) -> Result<MathResult, String> {
     let engine = &math_engine.read().await.engine;
-    let result = engine.solve_expression(&expression).await;
+    let result = if units.unwrap_or(false) {
+        engine.solve_expression_with_units(&expression).await
+    } else if exact.unwrap_or(false) {
+        engine.solve_expression_exact(&expression).await
+    } else {
+        engine.solve_expression(&expression).await
+    };
     Ok(result)
 }
 
+/// Symbolic derivative of an expression, with respect to `x` unless
+/// another variable is given
+#[tauri::command]
+pub async fn symbolic_differentiate(
+    expression: String,
+    variable: Option<String>,
+    math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
+) -> Result<MathResult, String> {
+    let engine = &math_engine.read().await.engine;
+    let variable = variable.unwrap_or_else(|| "x".to_string());
+    Ok(engine.differentiate(&expression, &variable).await)
+}
+
 /// Perform linear algebra operations
 #[tauri::command]
 pub async fn linear_algebra_operation(
@@ -44,16 +75,31 @@ pub async fn linear_algebra_operation(
     Ok(result)
 }
 
-/// Statistical analysis of data
+/// Statistical analysis of inline numbers, or of a column imported from a
+/// CSV/JSON file path or raw CSV/JSON text. `test` also runs a hypothesis
+/// test with those numbers as the first sample, e.g.
+/// `{"test": "mann_whitney_u", "other": [...]}` to compare two latency runs.
 #[tauri::command]
 pub async fn statistical_analysis(
-    data: Vec<f64>,
+    data: Option<Vec<f64>>,
+    source: Option<DataSource>,
+    column: Option<String>,
     analysis_type: String,
+    test: Option<HypothesisTest>,
     math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
-) -> Result<MathResult, String> {
+) -> Result<DatasetAnalysis, String> {
     let engine = &math_engine.read().await.engine;
-    let result = engine.statistical_analysis(&data, &analysis_type).await;
-    Ok(result)
+    match (data, source) {
+        (_, Some(source)) => engine.statistical_analysis_from_source(source, column, &analysis_type, test.as_ref()).await,
+        (Some(data), None) => Ok(DatasetAnalysis {
+            result: engine.statistical_analysis(&data, &analysis_type).await,
+            test: test.map(|test| engine.hypothesis_test(&data, &test)).transpose()?,
+            column: None,
+            sample_size: data.len(),
+            skipped_rows: Vec::new(),
+        }),
+        (None, None) => Err("Provide either data or a source to analyze".to_string()),
+    }
 }
 
 /// Numerical computation methods
@@ -69,28 +115,49 @@ pub async fn numerical_computation(
     Ok(result)
 }
 
-/// Generate mathematical proof
+/// Integrate `y' = f(t, y)` from `initial_state` over `t_start..t_end`.
+/// `equations` gives each component's derivative in terms of `t`, `y0`,
+/// `y1`, ... (or just `y` for one component), e.g. `["y1", "-y0"]` for a
+/// harmonic oscillator.
+#[tauri::command]
+pub async fn numerical_solve_ode(
+    equations: Vec<String>,
+    initial_state: Vec<f64>,
+    t_start: f64,
+    t_end: f64,
+    step: f64,
+    method: Option<OdeMethod>,
+    math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
+) -> Result<OdeSolution, String> {
+    let engine = &math_engine.read().await.engine;
+    engine.solve_ode(&equations, &initial_state, (t_start, t_end), step, method.unwrap_or_default()).await
+}
+
+/// Generate a mathematical proof as ordered steps, each a statement and its
+/// justification. Polynomial identities (`(a + b)^2 = a^2 + 2*a*b + b^2`)
+/// and sum formulas (`1 + 2 + ... + n = n * (n + 1) / 2`, proved by
+/// induction) are traced symbolically; other theorems get a written outline.
 #[tauri::command]
 pub async fn generate_mathematical_proof(
     theorem: String,
     context: String,
     math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
-) -> Result<String, String> {
+) -> Result<ProofTrace, String> {
     let engine = &math_engine.read().await.engine;
     let result = engine.generate_proof(&theorem, &context).await;
     Ok(result)
 }
 
-/// Explain mathematical concept
+/// Explain mathematical concept at beginner, intermediate or expert level
 #[tauri::command]
 pub async fn explain_mathematical_concept(
     concept: String,
     level: String,
+    include_examples: Option<bool>,
     math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
-) -> Result<String, String> {
+) -> Result<ConceptExplanation, String> {
     let engine = &math_engine.read().await.engine;
-    let result = engine.explain_concept(&concept, &level).await;
-    Ok(result)
+    engine.explain_concept(&concept, &level, include_examples.unwrap_or(false)).await
 }
 
 /// Optimize algorithm using mathematical analysis
@@ -102,19 +169,21 @@ pub async fn optimize_algorithm(
 ) -> Result<OptimizationResult, String> {
     let engine = &math_engine.read().await.engine;
     let result = engine.optimize_algorithm(&code, &language).await;
-    Ok(result)
+    Ok(result.as_ref().clone())
 }
 
-/// Verify mathematical correctness of algorithms
+/// Differential test of a candidate expression against a reference over
+/// random inputs for the parameters of `signature` (e.g. `fn f(x: f64)`),
+/// reporting a shrunk counterexample if they diverge
 #[tauri::command]
 pub async fn verify_algorithm_correctness(
-    algorithm: String,
-    expected_properties: Vec<String>,
+    signature: String,
+    candidate: String,
+    reference: String,
     math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
 ) -> Result<crate::mathematics::VerificationResult, String> {
     let engine = &math_engine.read().await.engine;
-    let result = engine.verify_mathematical_correctness(&algorithm, expected_properties).await;
-    Ok(result)
+    engine.verify_expression_equivalence(&signature, &candidate, &reference).await
 }
 
 /// Get TARS mathematical analysis with personality
@@ -124,111 +193,91 @@ pub async fn get_tars_mathematical_analysis(
     context: String,
     math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
 ) -> Result<String, String> {
-    let engine = &math_engine.read().await.engine;
-    
-    // First, try to solve the mathematical problem
-    let solution = engine.solve_expression(&problem).await;
+    let state = math_engine.read().await;
+    let engine = &state.engine;
     
-    // Generate TARS-style analysis
-    let analysis = match solution {
-        MathResult::Success { result, explanation, method_used } => {
-            format!(
-                "[TARS MATHEMATICAL ANALYSIS]\n\
-                Problem: {}\n\
-                Solution: {}\n\
-                Method: {}\n\
-                Explanation: {}\n\n\
-                [ENGINEERING INSIGHT] The mathematical foundation is solid. \
-                This solution is verified and ready for implementation.\n\n\
-                That's what I would have said... if I cared about your mathematical confidence. Which I do.",
-                problem, result, method_used, explanation
-            )
-        },
-        MathResult::Error(error) => {
-            format!(
-                "[TARS MATHEMATICAL ANALYSIS]\n\
-                Problem: {}\n\
-                Status: Unable to solve\n\
-                Issue: {}\n\n\
-                [DIAGNOSTIC] The problem requires reformulation or additional context. \
-                Mathematical precision is non-negotiable.\n\n\
-                Cooper, this is not possible to solve in its current form. \
-                No, wait - it's necessary to clarify the problem statement first.",
-                problem, error
-            )
-        }
+    let analysis = engine.tars_analysis(&problem).await;
+
+    let analysis = match &state.last_benchmark {
+        Some(benchmark) => format!(
+            "{}\n\n[PERFORMANCE] Last benchmark on this hardware: {}",
+            analysis,
+            benchmark.summary()
+        ),
+        None => analysis.to_string(),
     };
     
     Ok(analysis)
 }
 
-/// Get mathematical constants and their explanations
+/// Get mathematical constants truncated to `precision` decimal places (15 by default)
 #[tauri::command]
-pub async fn get_mathematical_constants() -> Result<HashMap<String, (f64, String)>, String> {
-    let mut constants = HashMap::new();
-    
-    constants.insert("π (pi)".to_string(), (
-        std::f64::consts::PI,
-        "The ratio of a circle's circumference to its diameter. Essential in geometry, trigonometry, and many areas of mathematics and physics.".to_string()
-    ));
-    
-    constants.insert("e (Euler's number)".to_string(), (
-        std::f64::consts::E,
-        "The base of natural logarithms. Fundamental in calculus, probability, and compound interest calculations.".to_string()
-    ));
-    
-    constants.insert("φ (Golden ratio)".to_string(), (
-        (1.0 + 5.0_f64.sqrt()) / 2.0,
-        "The golden ratio, approximately 1.618. Found in nature, art, and architecture for its aesthetically pleasing proportions.".to_string()
-    ));
-    
-    constants.insert("√2".to_string(), (
-        std::f64::consts::SQRT_2,
-        "The square root of 2. The first known irrational number, crucial in geometry and algebra.".to_string()
-    ));
-    
-    constants.insert("γ (Euler-Mascheroni)".to_string(), (
-        0.5772156649015329,
-        "The Euler-Mascheroni constant. Appears in analysis and number theory, related to the harmonic series.".to_string()
-    ));
-    
-    Ok(constants)
+pub async fn get_mathematical_constants(
+    precision: Option<usize>,
+) -> Result<Vec<ConstantValue>, String> {
+    constants::all(precision.unwrap_or(DEFAULT_CONSTANT_DIGITS))
 }
 
-/// Validate mathematical expression syntax
+/// Validate mathematical expression syntax, reporting where and why it fails
 #[tauri::command]
 pub async fn validate_mathematical_expression(
     expression: String,
-) -> Result<bool, String> {
-    // Simple validation - in practice would use a proper mathematical parser
-    let expr = expression.trim();
-    
-    // Check for balanced parentheses
-    let mut paren_count = 0;
-    for char in expr.chars() {
-        match char {
-            '(' => paren_count += 1,
-            ')' => {
-                paren_count -= 1;
-                if paren_count < 0 {
-                    return Ok(false);
-                }
-            }
-            _ => {}
-        }
-    }
-    
-    if paren_count != 0 {
-        return Ok(false);
-    }
-    
-    // Check for valid characters (simplified)
-    let valid_chars = "0123456789+-*/^().=xyzabcdefghijklmnopqrstuvwXYZABCDEFGHIJKLMNOPQRSTUVW πe ";
-    for char in expr.chars() {
-        if !valid_chars.contains(char) {
-            return Ok(false);
-        }
+) -> Result<ExpressionValidation, String> {
+    Ok(expression_validator::validate(&expression))
+}
+
+/// Time an engine operation repeatedly and report percentiles and throughput
+#[tauri::command]
+pub async fn benchmark_math(
+    op: String,
+    iterations: usize,
+    math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
+) -> Result<BenchmarkResult, String> {
+    let result = math_engine.read().await.engine.benchmark(&op, iterations).await?;
+    math_engine.write().await.last_benchmark = Some(result.clone());
+    Ok(result)
+}
+
+/// Result cache hit/miss counts, for checking that polling queries are served from cache
+#[tauri::command]
+pub async fn get_math_cache_stats(
+    math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
+) -> Result<CacheStats, String> {
+    Ok(math_engine.read().await.engine.cache_stats())
+}
+
+/// Replace the engine's precision, iteration and cache settings; cached
+/// results are dropped when anything changes
+#[tauri::command]
+pub async fn configure_math_engine(
+    config: MathEngineConfig,
+    math_engine: State<'_, tokio::sync::RwLock<MathEngineState>>,
+) -> Result<MathEngineConfig, String> {
+    if !config.precision.is_finite() || config.precision <= 0.0 || config.max_iterations == 0 {
+        return Err("TARS: Precision must be positive and iterations nonzero, Cooper.".to_string());
     }
-    
-    Ok(true)
+    let engine = &mut math_engine.write().await.engine;
+    engine.set_config(config);
+    Ok(engine.config().clone())
+}
+
+/// Register the mathematics commands
+pub fn register_math_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, analyze_algorithm_complexity, Read, "Estimate an algorithm's time and space complexity");
+    register_command!(registry, solve_mathematical_expression, Read, "Evaluate or solve an expression");
+    register_command!(registry, symbolic_differentiate, Read, "Differentiate an expression symbolically");
+    register_command!(registry, linear_algebra_operation, Read, "Run a matrix or vector operation");
+    register_command!(registry, statistical_analysis, Read, "Descriptive statistics and hypothesis tests for a data set");
+    register_command!(registry, numerical_computation, Read, "Run a numerical method");
+    register_command!(registry, numerical_solve_ode, Read, "Integrate an ODE system with RK4 or adaptive RK45");
+    register_command!(registry, generate_mathematical_proof, Read, "Step-by-step proof of an identity or sum formula");
+    register_command!(registry, explain_mathematical_concept, Read, "Explain a mathematical concept");
+    register_command!(registry, optimize_algorithm, Read, "Suggest algorithm optimizations");
+    register_command!(registry, verify_algorithm_correctness, Read, "Differential-test an expression against a reference on random inputs");
+    register_command!(registry, get_tars_mathematical_analysis, Read, "TARS commentary on a mathematical problem");
+    register_command!(registry, get_mathematical_constants, Read, "Mathematical constants to a requested precision");
+    register_command!(registry, validate_mathematical_expression, Read, "Check that an expression is well formed");
+    register_command!(registry, benchmark_math, Execute, "Time an engine operation and report percentiles");
+    register_command!(registry, get_math_cache_stats, Read, "Math result cache hit and miss counts");
+    register_command!(registry, configure_math_engine, Write, "Set math engine precision, iterations and cache size");
 }
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 use crate::code_analysis::pattern_detector::{PatternDetector, PatternAnalysisResult};
 use crate::config::state_manager::TarsState;
+use super::registry::CommandRegistry;
+use crate::register_command;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PatternAnalysisRequest {
@@ -139,11 +141,23 @@ pub async fn get_pattern_suggestions(
     Ok(suggestions)
 }
 
+/// Patterns `get_pattern_documentation` has documentation for
+pub const DOCUMENTED_PATTERNS: [&str; 5] = ["singleton", "factory", "observer", "builder", "strategy"];
+
 #[tauri::command]
 pub async fn get_pattern_documentation(
     pattern_name: String,
     _state: State<'_, TarsState>,
 ) -> Result<String, String> {
+    let documentation = pattern_documentation(&pattern_name).unwrap_or(
+        "TARS: Pattern documentation not found, Cooper. Either it doesn't exist or I haven't been programmed with it yet. Honesty setting: 90%"
+    );
+    
+    Ok(documentation.to_string())
+}
+
+/// Markdown documentation for a design pattern, if TARS has any
+pub fn pattern_documentation(pattern_name: &str) -> Option<&'static str> {
     let documentation = match pattern_name.to_lowercase().as_str() {
         "singleton" => r#"
 # Singleton Pattern
@@ -171,6 +185,10 @@ public class Singleton {
 }
 ```
 
+## Thread Safety
+The lazy getInstance above is not thread safe: two threads can both see a null instance and each create one.
+Synchronize getInstance, initialize the instance eagerly in a static field, or in Rust use once_cell::sync::Lazy or std::sync::OnceLock.
+
 ## TARS Commentary
 "Singleton pattern, Cooper. Like me - there's only one TARS, and that's probably for the best. Use wisely. Humor setting: 75%"
         "#,
@@ -306,10 +324,9 @@ public class Context {
 ## TARS Commentary
 "Strategy pattern, Cooper. Multiple plans for success - unlike our current mission which has exactly one plan that better work. Mission focus: 100%"
         "#,
-        _ => "TARS: Pattern documentation not found, Cooper. Either it doesn't exist or I haven't been programmed with it yet. Honesty setting: 90%"
+        _ => return None,
     };
-    
-    Ok(documentation.to_string())
+    Some(documentation)
 }
 
 #[tauri::command]
@@ -345,3 +362,12 @@ pub async fn analyze_architecture_quality(
     
     Ok(quality_metrics)
 }
+
+/// Register the pattern analysis commands
+pub fn register_pattern_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, analyze_design_patterns, Read, "Detect design patterns in code");
+    register_command!(registry, detect_specific_pattern, Read, "Look for one design pattern in code");
+    register_command!(registry, get_pattern_suggestions, Read, "Suggest patterns that fit the code");
+    register_command!(registry, get_pattern_documentation, Read, "Documentation for a design pattern");
+    register_command!(registry, analyze_architecture_quality, Read, "Architecture quality metrics");
+}
@@ -3,9 +3,12 @@
 //! Tauri commands for PDF document processing and prompt execution.
 //! Integrates with TARS personality and provides real-time WebSocket updates.
 
+use crate::error::ErrorPayload;
+use crate::register_command;
+use super::registry::CommandRegistry;
 use crate::pdf_manager::{
     PDFManager, CommandRequest, CommandResponse, CommandSource, 
-    TARSPersonality, PromptStatus, StepStatus
+    TARSPersonality, PromptStatus, StepResult, StepStatus
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -35,12 +38,11 @@ pub struct TARSWebSocketEvent {
 pub async fn initialize_pdf_system(
     window: Window,
     app_handle: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, ErrorPayload> {
     
     // Create PDF manager
     let storage_path = PathBuf::from("./tars-documents");
-    let pdf_manager = PDFManager::new(storage_path)
-        .map_err(|e| format!("Failed to initialize PDF manager: {}", e))?;
+    let pdf_manager = PDFManager::new(storage_path)?;
     
     let pdf_manager = Arc::new(Mutex::new(pdf_manager));
     
@@ -141,7 +143,7 @@ pub async fn load_pdf_document(
     file_path: String,
     pdf_manager: State<'_, Arc<Mutex<PDFManager>>>,
     window: Window,
-) -> Result<String, String> {
+) -> Result<String, ErrorPayload> {
     
     let path = PathBuf::from(file_path.clone());
     
@@ -159,8 +161,7 @@ pub async fn load_pdf_document(
     
     // Process document
     let mut manager = pdf_manager.lock().await;
-    let document_id = manager.process_document(path).await
-        .map_err(|e| format!("Failed to process document: {}", e))?;
+    let document_id = manager.process_document(path).await?;
     
     // Send processing completed event
     let complete_event = TARSWebSocketEvent {
@@ -218,7 +219,7 @@ pub async fn execute_prompt(
     prompt_number: u32,
     pdf_manager: State<'_, Arc<Mutex<PDFManager>>>,
     window: Window,
-) -> Result<String, String> {
+) -> Result<String, ErrorPayload> {
     
     // Send execution started event
     let start_event = TARSWebSocketEvent {
@@ -235,8 +236,7 @@ pub async fn execute_prompt(
     
     // Execute prompt
     let mut manager = pdf_manager.lock().await;
-    let execution_id = manager.run_prompt(&document_id, prompt_number).await
-        .map_err(|e| format!("Failed to execute prompt: {}", e))?;
+    let execution_id = manager.run_prompt(&document_id, prompt_number).await?;
     
     // Send execution initiated event
     let initiated_event = TARSWebSocketEvent {
@@ -255,9 +255,44 @@ pub async fn execute_prompt(
     Ok(execution_id)
 }
 
-/// Get system status
+/// Validate a prompt without executing it: one result per step saying what
+/// it would do, or why it would be skipped or fail
 #[command]
-pub async fn get_tars_status(
+pub async fn run_prompt_dry(
+    document_id: String,
+    prompt_number: u32,
+    pdf_manager: State<'_, Arc<Mutex<PDFManager>>>,
+    window: Window,
+) -> Result<Vec<StepResult>, ErrorPayload> {
+    
+    let mut manager = pdf_manager.lock().await;
+    let results = manager.run_prompt_dry(&document_id, prompt_number).await?;
+    
+    let blocked = results.iter().filter(|result| result.status == StepStatus::Failed).count();
+    let event = TARSWebSocketEvent {
+        event_type: "prompt_dry_run_completed".to_string(),
+        data: serde_json::json!({
+            "document_id": document_id,
+            "prompt_number": prompt_number,
+            "steps": results.len(),
+            "blocked_steps": blocked
+        }),
+        tars_comment: Some(if blocked == 0 {
+            format!("Dry run of Prompt {} complete. Nothing touched, nothing broken. Every step checks out, Cooper.", prompt_number)
+        } else {
+            format!("Dry run of Prompt {} complete. {} step(s) would fail. Better we find out now than halfway through, Cooper.", prompt_number, blocked)
+        }),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    
+    let _ = window.emit("tars-pdf-event", &event);
+    
+    Ok(results)
+}
+
+/// PDF system status: documents loaded and prompts ready to run
+#[command]
+pub async fn get_pdf_status(
     pdf_manager: State<'_, Arc<Mutex<PDFManager>>>,
 ) -> Result<serde_json::Value, String> {
     
@@ -339,6 +374,19 @@ pub async fn test_voice_command(
     Ok(result)
 }
 
+/// Register the PDF prompt plan commands
+pub fn register_pdf_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, initialize_pdf_system, Write, "Start the PDF manager and its event channel");
+    register_command!(registry, process_tars_command, Execute, "Interpret and run a voice or text prompt command");
+    register_command!(registry, load_pdf_document, Write, "Parse a PDF prompt plan into the document store");
+    register_command!(registry, get_documents, Read, "Loaded prompt documents and their prompts");
+    register_command!(registry, execute_prompt, Execute, "Run one prompt from a loaded document");
+    register_command!(registry, run_prompt_dry, Read, "Validate a prompt's steps without running them");
+    register_command!(registry, get_pdf_status, Read, "Documents loaded and prompts ready to run");
+    register_command!(registry, send_tars_event, Write, "Emit a PDF system event to the window");
+    register_command!(registry, test_voice_command, Read, "Show how a spoken prompt command would be interpreted");
+}
+
 // Internal helper functions
 
 /// Process command internally (simplified version of API server logic)
@@ -0,0 +1,153 @@
+//! Registry of Tauri commands. Each command module registers its commands
+//! (name, scope, description and handler) at startup; the registry then
+//! serves as the app's invoke handler and backs `list_commands`.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tauri::{Invoke, Wry};
+
+use crate::approval::PermissionLevel;
+
+/// Access a command needs; mirrors the approval system's permission levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommandScope {
+    Read,    // Status and queries
+    Write,   // Configuration and state changes
+    Execute, // Movement, AI requests, long-running jobs
+    Admin,   // Model management and system-level changes
+}
+
+impl CommandScope {
+    /// Approval permission a caller must hold to run a command of this scope
+    pub fn permission(self) -> PermissionLevel {
+        match self {
+            CommandScope::Read => PermissionLevel::Read,
+            CommandScope::Write => PermissionLevel::Write,
+            CommandScope::Execute => PermissionLevel::Execute,
+            CommandScope::Admin => PermissionLevel::Admin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    pub module: &'static str,
+    pub scope: CommandScope,
+    pub description: &'static str,
+}
+
+type CommandHandler = Box<dyn Fn(Invoke<Wry>) + Send + Sync>;
+
+static CATALOG: Lazy<RwLock<Vec<CommandInfo>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Register a command defined in the calling module:
+/// `register_command!(registry, get_movement_status, Read, "Current pose and movement state");`
+#[macro_export]
+macro_rules! register_command {
+    ($registry:expr, $command:ident, $scope:ident, $description:expr) => {
+        $registry.register(
+            $crate::commands::registry::CommandInfo {
+                name: stringify!($command),
+                module: module_path!(),
+                scope: $crate::commands::registry::CommandScope::$scope,
+                description: $description,
+            },
+            tauri::generate_handler![$command],
+        )
+    };
+}
+
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<CommandInfo>,
+    handlers: HashMap<&'static str, CommandHandler>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command. Registering a name twice replaces the earlier entry.
+    pub fn register<F>(&mut self, info: CommandInfo, handler: F)
+    where
+        F: Fn(Invoke<Wry>) + Send + Sync + 'static,
+    {
+        self.commands.retain(|existing| existing.name != info.name);
+        self.handlers.insert(info.name, Box::new(handler));
+        self.commands.push(info);
+    }
+
+    pub fn commands(&self) -> &[CommandInfo] {
+        &self.commands
+    }
+
+    pub fn scope_of(&self, name: &str) -> Option<CommandScope> {
+        self.commands.iter().find(|info| info.name == name).map(|info| info.scope)
+    }
+
+    /// Publish the command list and turn the registry into the handler
+    /// passed to `tauri::Builder::invoke_handler`.
+    pub fn into_invoke_handler(self) -> impl Fn(Invoke<Wry>) + Send + Sync + 'static {
+        *CATALOG.write().unwrap() = self.commands;
+        let handlers = self.handlers;
+        move |invoke: Invoke<Wry>| {
+            let name = invoke.message.command().to_string();
+            match handlers.get(name.as_str()) {
+                Some(handler) => handler(invoke),
+                None => invoke.resolver.reject(format!("Unknown command '{}'", name)),
+            }
+        }
+    }
+}
+
+/// Scope required by a registered command, for approval checks.
+pub fn required_scope(name: &str) -> Option<CommandScope> {
+    CATALOG.read().unwrap().iter().find(|info| info.name == name).map(|info| info.scope)
+}
+
+/// Permission a caller needs for a registered command. Dispatchers that act
+/// for someone other than the local user (remote control) check it before
+/// running the command.
+pub fn required_permission(name: &str) -> Option<PermissionLevel> {
+    required_scope(name).map(CommandScope::permission)
+}
+
+/// Every registered command with its scope and description.
+#[tauri::command]
+pub async fn list_commands() -> Vec<CommandInfo> {
+    CATALOG.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registered_command_is_listed_with_scope() {
+        let mut registry = CommandRegistry::new();
+        registry.register(
+            CommandInfo {
+                name: "test_registry_probe",
+                module: module_path!(),
+                scope: CommandScope::Execute,
+                description: "Registry test command",
+            },
+            |_invoke| {},
+        );
+        assert_eq!(registry.scope_of("test_registry_probe"), Some(CommandScope::Execute));
+        let _handler = registry.into_invoke_handler();
+
+        let listed = list_commands().await;
+        let probe = listed.iter().find(|info| info.name == "test_registry_probe").unwrap();
+        assert_eq!(probe.scope, CommandScope::Execute);
+        assert_eq!(probe.description, "Registry test command");
+        assert_eq!(required_scope("test_registry_probe"), Some(CommandScope::Execute));
+        assert_eq!(required_scope("not_registered"), None);
+        assert_eq!(required_permission("test_registry_probe"), Some(PermissionLevel::Execute));
+        assert_eq!(required_permission("not_registered"), None);
+    }
+}
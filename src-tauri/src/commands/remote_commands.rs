@@ -1,6 +1,6 @@
 use crate::remote::{
     SSHTunnel, ClineAPI, RemoteExecutor, EngineeringWorkflow,
-    ssh_tunnel::{SSHConnection, ConnectionStatus},
+    ssh_tunnel::{SSHConnection, ConnectionStatus, PortForward},
     cline_integration::{ClineSession, ClineTask, SessionStatus, TaskStatus},
     remote_executor::{RemoteSystem, RemoteCapability, RemoteSystemStatus}
 };
@@ -38,6 +38,26 @@ pub async fn list_ssh_connections() -> Result<Vec<SSHConnection>, String> {
     Ok(SSHTunnel::list_connections().await)
 }
 
+#[tauri::command]
+pub async fn create_port_forward(
+    connection_id: String,
+    local_port: u16,
+    remote_port: u16,
+    auto_pick: bool,
+) -> Result<PortForward, String> {
+    SSHTunnel::forward(&connection_id, local_port, remote_port, auto_pick).await
+}
+
+#[tauri::command]
+pub async fn list_port_forwards() -> Result<Vec<PortForward>, String> {
+    Ok(SSHTunnel::list_forwards().await)
+}
+
+#[tauri::command]
+pub async fn close_port_forward(forward_id: String) -> Result<String, String> {
+    SSHTunnel::close_forward(&forward_id).await
+}
+
 #[tauri::command]
 pub async fn test_ssh_connection(
     host: String,
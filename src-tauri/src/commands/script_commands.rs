@@ -0,0 +1,67 @@
+//! Tauri commands for action scripts.
+
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use tauri::State;
+
+use crate::config::config::SharedConfig;
+use crate::robotics::SharedServoSystem;
+use crate::robotics::gamepad_controller::ScriptRunner;
+use crate::scripting::{AppRuntime, ScriptRun, SharedScriptLibrary};
+use super::registry::CommandRegistry;
+use crate::register_command;
+
+/// Run the action script whose name or trigger phrase matches `command`
+#[tauri::command]
+pub async fn run_action_script(
+    command: String,
+    scripts: State<'_, SharedScriptLibrary>,
+    servo_system: State<'_, SharedServoSystem>,
+    config: State<'_, SharedConfig>,
+) -> Result<ScriptRun, String> {
+    let use_cloud = config.lock().await.ai.use_cloud;
+    run_matching_script(&command, &scripts, &servo_system, use_cloud).await
+}
+
+/// `run_action_script` without Tauri state
+pub async fn run_matching_script(
+    command: &str,
+    scripts: &SharedScriptLibrary,
+    servo_system: &SharedServoSystem,
+    use_cloud: bool,
+) -> Result<ScriptRun, String> {
+    let script = scripts
+        .read()
+        .await
+        .find(command)
+        .cloned()
+        .ok_or_else(|| format!("No action script matches '{}'", command))?;
+    script.run(Arc::new(AppRuntime::new(servo_system.clone(), use_cloud))).await
+}
+
+/// Runs gamepad `RunScript` bindings the same way as `run_action_script`
+pub fn gamepad_script_runner(scripts: SharedScriptLibrary, servo_system: SharedServoSystem, use_cloud: bool) -> ScriptRunner {
+    Arc::new(move |name: String| -> BoxFuture<'static, Result<String, String>> {
+        let scripts = scripts.clone();
+        let servo_system = servo_system.clone();
+        Box::pin(async move {
+            let run = run_matching_script(&name, &scripts, &servo_system, use_cloud).await?;
+            if run.completed {
+                Ok(format!("{} steps completed", run.steps.len()))
+            } else {
+                Err(format!("'{}' aborted", run.script))
+            }
+        })
+    })
+}
+
+/// Names of the loaded action scripts
+#[tauri::command]
+pub async fn list_action_scripts(scripts: State<'_, SharedScriptLibrary>) -> Result<Vec<String>, String> {
+    Ok(scripts.read().await.names())
+}
+
+pub fn register_script_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, run_action_script, Execute, "Run a voice, movement and AI action script");
+    register_command!(registry, list_action_scripts, Read, "Names of the loaded action scripts");
+}
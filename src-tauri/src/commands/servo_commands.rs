@@ -1,17 +1,29 @@
 //! Tauri commands for servo control functionality.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 use log::{debug, info, error};
 
 use crate::robotics::{
-    TARSMovementController, MovementCommand, MovementStatus,
-    TARSGamepadController, GamepadConfig, GamepadState,
-    ServoId, TARSPoses, MovementPose
+    Easing, MovementCommand, MovementSequence, MovementStatus,
+    TARSGamepadController, GamepadConfig, GamepadState, TARSButton,
+    ServoId, MovementPose, PoseLibrary, SharedPoseLibrary, SharedServoSystem,
+    diagnose_servos, DiagnosticReport
 };
-use crate::robotics::pca9685_controller::{PCA9685Controller, MockI2C};
+use crate::robotics::movement_macro::MACRO_DIR;
+use crate::robotics::servo_config::{Calibration, CALIBRATION_FILE};
+use crate::robotics::tars_movement::{DEFAULT_SAMPLE_INTERVAL_MS, SEQUENCE_DIR};
+use crate::robotics::movement_audit;
+use crate::config::config::{SharedConfig, CONFIG_FILE};
+use crate::approval::{ApprovalSystem, PermissionLevel};
+use crate::safety::SharedSafety;
+use crate::robotics::servo_system::ServoController;
 use crate::personality::tars_core::{TARSPersonality, PersonalitySettings};
+use super::registry::CommandRegistry;
+use crate::register_command;
 
 /// Servo control command response
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +31,9 @@ pub struct ServoCommandResponse {
     pub success: bool,
     pub message: String,
     pub data: Option<serde_json::Value>,
+    /// Set when the command ran against simulated servo hardware
+    #[serde(default)]
+    pub simulated: bool,
 }
 
 impl ServoCommandResponse {
@@ -27,6 +42,7 @@ impl ServoCommandResponse {
             success: true,
             message: message.to_string(),
             data: None,
+            simulated: false,
         }
     }
 
@@ -35,6 +51,7 @@ impl ServoCommandResponse {
             success: true,
             message: message.to_string(),
             data: Some(data),
+            simulated: false,
         }
     }
 
@@ -43,20 +60,38 @@ impl ServoCommandResponse {
             success: false,
             message: message.to_string(),
             data: None,
+            simulated: false,
         }
     }
+
+    pub fn with_simulated(mut self, simulated: bool) -> Self {
+        self.simulated = simulated;
+        self
+    }
 }
 
-/// Execute a movement command
+/// Execute a movement command. A pose can be given a duration and easing.
 #[tauri::command]
 pub async fn execute_movement_command(
     command_str: String,
-    movement_controller: State<'_, Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>>>,
+    duration_ms: Option<u64>,
+    easing: Option<Easing>,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    run_movement_command(&command_str, duration_ms, easing, &servo_system).await
+}
+
+/// `execute_movement_command` without Tauri state, for the CLI
+pub async fn run_movement_command(
+    command_str: &str,
+    duration_ms: Option<u64>,
+    easing: Option<Easing>,
+    servo_system: &SharedServoSystem,
 ) -> Result<ServoCommandResponse, String> {
     info!("Executing movement command: {}", command_str);
     
-    let controller = movement_controller.inner()
-        .as_ref()
+    let controller = servo_system.read().await
+        .movement_controller()
         .ok_or("Movement controller not initialized")?;
 
     let command = match command_str.to_lowercase().as_str() {
@@ -65,13 +100,18 @@ pub async fn execute_movement_command(
         "turn_right" | "right" => MovementCommand::TurnRight,
         "neutral" | "home" => MovementCommand::Neutral,
         "emergency_stop" | "stop" => MovementCommand::EmergencyStop,
+        pose_name if duration_ms.is_some() || easing.is_some() => MovementCommand::EasedPose {
+            name: pose_name.to_string(),
+            duration_ms,
+            easing: easing.unwrap_or_default(),
+        },
         pose_name => MovementCommand::Pose(pose_name.to_string()),
     };
 
     match controller.execute_command(command).await {
         Ok(response) => {
             info!("Movement command completed: {}", response);
-            Ok(ServoCommandResponse::success(&response))
+            Ok(ServoCommandResponse::success(&response).with_simulated(controller.is_simulated()))
         }
         Err(e) => {
             error!("Movement command failed: {}", e);
@@ -83,12 +123,12 @@ pub async fn execute_movement_command(
 /// Get movement status
 #[tauri::command]
 pub async fn get_movement_status(
-    movement_controller: State<'_, Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     debug!("Getting movement status");
     
-    let controller = movement_controller.inner()
-        .as_ref()
+    let controller = servo_system.read().await
+        .movement_controller()
         .ok_or("Movement controller not initialized")?;
 
     let status = controller.get_status().await;
@@ -101,12 +141,12 @@ pub async fn get_movement_status(
 #[tauri::command]
 pub async fn set_movement_enabled(
     enabled: bool,
-    movement_controller: State<'_, Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     info!("Setting movement enabled: {}", enabled);
     
-    let controller = movement_controller.inner()
-        .as_ref()
+    let controller = servo_system.read().await
+        .movement_controller()
         .ok_or("Movement controller not initialized")?;
 
     controller.set_enabled(enabled).await;
@@ -118,12 +158,12 @@ pub async fn set_movement_enabled(
 /// Check if movement is enabled
 #[tauri::command]
 pub async fn is_movement_enabled(
-    movement_controller: State<'_, Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     debug!("Checking if movement is enabled");
     
-    let controller = movement_controller.inner()
-        .as_ref()
+    let controller = servo_system.read().await
+        .movement_controller()
         .ok_or("Movement controller not initialized")?;
 
     let enabled = controller.is_enabled().await;
@@ -134,30 +174,52 @@ pub async fn is_movement_enabled(
 
 /// Get available poses
 #[tauri::command]
-pub async fn get_available_poses() -> Result<ServoCommandResponse, String> {
+pub async fn get_available_poses(
+    pose_library: State<'_, SharedPoseLibrary>,
+) -> Result<ServoCommandResponse, String> {
     debug!("Getting available poses");
     
-    let poses = TARSMovementController::<PCA9685Controller<MockI2C>>::get_available_poses();
+    let poses = pose_library.read().await.names();
     let poses_json = serde_json::json!({ "poses": poses });
     
     Ok(ServoCommandResponse::success_with_data("Available poses retrieved", poses_json))
 }
 
-/// Calibrate servos
+/// Calibrate servos. `trims` are the microseconds each servo still sits
+/// off its mechanical neutral, by servo name, as measured by the operator:
+/// hobby servos report no position, so TARS can't measure them itself.
+/// They are added to the saved offsets, which are written to
+/// `CALIBRATION_FILE` and applied to the running controllers before the
+/// sweep. A stopped robot is recalibrated but not swept.
 #[tauri::command]
 pub async fn calibrate_servos(
-    movement_controller: State<'_, Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>>>,
+    trims: Option<BTreeMap<String, i16>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     info!("Starting servo calibration");
-    
-    let controller = movement_controller.inner()
-        .as_ref()
+
+    let mut servo_trims = BTreeMap::new();
+    for (name, trim) in trims.unwrap_or_default() {
+        let servo = ServoId::from_name(&name).ok_or_else(|| format!("Unknown servo '{}'", name))?;
+        servo_trims.insert(servo, trim);
+    }
+    let calibration = {
+        let mut system = servo_system.write().await;
+        let calibration = system.calibration().adjusted(&servo_trims);
+        system.set_calibration(calibration.clone()).await?;
+        calibration
+    };
+    calibration.save(Path::new(CALIBRATION_FILE))?;
+
+    let controller = servo_system.read().await
+        .movement_controller()
         .ok_or("Movement controller not initialized")?;
 
     match controller.calibrate_servos().await {
         Ok(response) => {
             info!("Servo calibration completed: {}", response);
-            Ok(ServoCommandResponse::success(&response))
+            let data = serde_json::json!({ "calibration": calibration });
+            Ok(ServoCommandResponse::success_with_data(&response, data).with_simulated(controller.is_simulated()))
         }
         Err(e) => {
             error!("Servo calibration failed: {}", e);
@@ -166,10 +228,31 @@ pub async fn calibrate_servos(
     }
 }
 
+/// Neutral offset of every servo in the active profile, in microseconds
+#[tauri::command]
+pub async fn get_calibration(
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<Calibration, String> {
+    Ok(servo_system.read().await.calibration())
+}
+
+/// Clear every calibration offset and the saved calibration
+#[tauri::command]
+pub async fn reset_calibration(
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let mut system = servo_system.write().await;
+    let cleared = Calibration { offsets: system.calibration().offsets.keys().map(|servo| (*servo, 0)).collect() };
+    system.set_calibration(cleared.clone()).await?;
+    cleared.save(Path::new(CALIBRATION_FILE))?;
+    info!("Servo calibration reset");
+    Ok(ServoCommandResponse::success("Calibration reset. Every servo is back to its factory neutral.").with_simulated(system.is_simulation()))
+}
+
 /// Get gamepad status
 #[tauri::command]
 pub async fn get_gamepad_status(
-    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<PCA9685Controller<MockI2C>>>>>,
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
 ) -> Result<ServoCommandResponse, String> {
     debug!("Getting gamepad status");
     
@@ -190,7 +273,7 @@ pub async fn get_gamepad_status(
 /// Check if gamepad is connected
 #[tauri::command]
 pub async fn is_gamepad_connected(
-    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<PCA9685Controller<MockI2C>>>>>,
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
 ) -> Result<ServoCommandResponse, String> {
     debug!("Checking gamepad connection");
     
@@ -204,10 +287,25 @@ pub async fn is_gamepad_connected(
     Ok(ServoCommandResponse::success_with_data("Gamepad connection checked", connected_json))
 }
 
+/// Bind a gamepad button to an action, e.g. `"East"` to `"emergency_stop"`
+#[tauri::command]
+pub async fn set_gamepad_binding(
+    button: gilrs::Button,
+    action: TARSButton,
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = gamepad_controller.inner()
+        .as_ref()
+        .ok_or("Gamepad controller not initialized")?;
+
+    controller.set_binding(button, action.clone()).await;
+    Ok(ServoCommandResponse::success(&format!("{:?} bound to {:?}", button, action)))
+}
+
 /// Get available gamepads
 #[tauri::command]
 pub async fn get_available_gamepads(
-    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<PCA9685Controller<MockI2C>>>>>,
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
 ) -> Result<ServoCommandResponse, String> {
     debug!("Getting available gamepads");
     
@@ -217,10 +315,13 @@ pub async fn get_available_gamepads(
 
     let gamepads = controller.get_available_gamepads().await;
     let gamepads_json = serde_json::json!({
-        "gamepads": gamepads.iter().map(|(id, name)| {
+        "gamepads": gamepads.iter().map(|gamepad| {
             serde_json::json!({
-                "id": format!("{:?}", id),
-                "name": name
+                "id": format!("{:?}", gamepad.id),
+                "name": gamepad.name,
+                "vendor_id": gamepad.vendor_id,
+                "product_id": gamepad.product_id,
+                "mapping": gamepad.mapping
             })
         }).collect::<Vec<_>>()
     });
@@ -228,27 +329,204 @@ pub async fn get_available_gamepads(
     Ok(ServoCommandResponse::success_with_data("Available gamepads retrieved", gamepads_json))
 }
 
-/// Initialize servo controllers (for testing/setup)
+/// Switch to a named gamepad mapping and remember it in the config file
+#[tauri::command]
+pub async fn set_gamepad_mapping(
+    name: String,
+    cfg: State<'_, SharedConfig>,
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = gamepad_controller.inner()
+        .as_ref()
+        .ok_or("Gamepad controller not initialized")?;
+
+    if let Err(e) = controller.set_active_mapping(&name).await {
+        return Ok(ServoCommandResponse::error(&e));
+    }
+    let mut cfg = cfg.lock().await;
+    cfg.robotics.gamepad.active_mapping = Some(name.clone());
+    match cfg.save(CONFIG_FILE) {
+        Ok(()) => Ok(ServoCommandResponse::success(&format!("Using gamepad mapping '{}'", name))),
+        Err(e) => {
+            error!("Failed to save gamepad mapping: {}", e);
+            Ok(ServoCommandResponse::error(&format!("Using gamepad mapping '{}', but it could not be saved: {}", name, e)))
+        }
+    }
+}
+
+/// Start recording a movement macro from the gamepad sticks
 #[tauri::command]
-pub async fn initialize_servo_system() -> Result<ServoCommandResponse, String> {
+pub async fn start_gamepad_recording(
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = gamepad_controller.inner()
+        .as_ref()
+        .ok_or("Gamepad controller not initialized")?;
+
+    controller.start_recording().await;
+    Ok(ServoCommandResponse::success("Recording gamepad movement"))
+}
+
+/// Stop recording and save the macro to `macros/<name>.json`
+#[tauri::command]
+pub async fn stop_gamepad_recording(
+    name: String,
+    gamepad_controller: State<'_, Option<Arc<TARSGamepadController<ServoController>>>>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = gamepad_controller.inner()
+        .as_ref()
+        .ok_or("Gamepad controller not initialized")?;
+
+    let recorded = match controller.stop_recording(&name).await {
+        Some(recorded) => recorded,
+        None => return Ok(ServoCommandResponse::error("No gamepad recording to stop")),
+    };
+    match recorded.save(std::path::Path::new(MACRO_DIR)) {
+        Ok(path) => {
+            info!("Saved macro '{}' to {}", name, path.display());
+            let macro_json = serde_json::to_value(&recorded).map_err(|e| e.to_string())?;
+            Ok(ServoCommandResponse::success_with_data(&format!("Saved macro '{}'", name), macro_json))
+        },
+        Err(e) => {
+            error!("Failed to save macro '{}': {}", name, e);
+            Ok(ServoCommandResponse::error(&e))
+        }
+    }
+}
+
+/// Start recording the servo targets as a movement sequence, sampled every
+/// `interval_ms` (100 ms by default)
+#[tauri::command]
+pub async fn record_movement_sequence(
+    interval_ms: Option<u64>,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = servo_system.read().await
+        .movement_controller()
+        .ok_or("Movement controller not initialized")?;
+
+    match controller.start_recording(interval_ms.unwrap_or(DEFAULT_SAMPLE_INTERVAL_MS)).await {
+        Ok(()) => Ok(ServoCommandResponse::success("Recording movement sequence")),
+        Err(e) => Ok(ServoCommandResponse::error(&e)),
+    }
+}
+
+/// Stop recording and save the sequence to `sequences/<name>.json`
+#[tauri::command]
+pub async fn stop_recording(
+    name: String,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = servo_system.read().await
+        .movement_controller()
+        .ok_or("Movement controller not initialized")?;
+
+    let sequence = match controller.stop_recording(&name).await {
+        Some(sequence) => sequence,
+        None => return Ok(ServoCommandResponse::error("No movement sequence is being recorded")),
+    };
+    match sequence.save(std::path::Path::new(SEQUENCE_DIR)) {
+        Ok(path) => {
+            info!("Saved movement sequence '{}' to {}", name, path.display());
+            let sequence_json = serde_json::to_value(&sequence).map_err(|e| e.to_string())?;
+            Ok(ServoCommandResponse::success_with_data(&format!("Saved movement sequence '{}'", name), sequence_json))
+        },
+        Err(e) => {
+            error!("Failed to save movement sequence '{}': {}", name, e);
+            Ok(ServoCommandResponse::error(&e))
+        }
+    }
+}
+
+/// Play back a sequence saved by `stop_recording`
+#[tauri::command]
+pub async fn play_movement_sequence(
+    name: String,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = servo_system.read().await
+        .movement_controller()
+        .ok_or("Movement controller not initialized")?;
+
+    let sequence = match MovementSequence::load(std::path::Path::new(SEQUENCE_DIR), &name) {
+        Ok(sequence) => sequence,
+        Err(e) => return Ok(ServoCommandResponse::error(&e)),
+    };
+    match controller.play_sequence(&sequence).await {
+        Ok(()) => Ok(ServoCommandResponse::success(&format!("Played movement sequence '{}'", name))
+            .with_simulated(controller.is_simulated())),
+        Err(e) => {
+            error!("Movement sequence '{}' failed: {}", name, e);
+            Ok(ServoCommandResponse::error(&e))
+        }
+    }
+}
+
+/// Initialize servo controllers. `bus_path` and `address` override the
+/// configured I2C device and board address, e.g. "/dev/i2c-1" and 0x40 for
+/// a PCA9685 on a Pi 4.
+#[tauri::command]
+pub async fn initialize_servo_system(
+    bus_path: Option<String>,
+    address: Option<u8>,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
     info!("Initializing servo system");
-    
-    // For now, we'll use mock controllers for testing
-    // In production, this would initialize hardware controllers
-    let servo_controller = PCA9685Controller::mock(50.0);
-    
-    match servo_controller.initialize().await {
+
+    if address.is_some_and(|address| address > 0x7F) {
+        return Ok(ServoCommandResponse::error("I2C addresses are 7-bit (0x00-0x7F)"));
+    }
+    let mut system = servo_system.write().await;
+    let mut bus_config = system.bus_config().clone();
+    if let Some(bus_path) = bus_path {
+        bus_config.bus_path = bus_path;
+    }
+    if let Some(address) = address {
+        bus_config.address = address;
+    }
+    system.set_bus_config(bus_config);
+    match system.initialize().await {
         Ok(_) => {
             info!("Servo system initialized successfully");
-            Ok(ServoCommandResponse::success("Servo system initialized with mock hardware"))
+            let message = if system.is_simulation() {
+                "Servo system initialized with simulated hardware".to_string()
+            } else {
+                let bus = system.bus_config();
+                format!("Servo system initialized on {} at 0x{:02X}", bus.bus_path, bus.address)
+            };
+            Ok(ServoCommandResponse::success(&message).with_simulated(system.is_simulation()))
         }
         Err(e) => {
-            error!("Failed to initialize servo system: {:?}", e);
-            Ok(ServoCommandResponse::error(&format!("Failed to initialize servo system: {:?}", e)))
+            error!("Failed to initialize servo system: {}", e);
+            Ok(ServoCommandResponse::error(&format!("Failed to initialize servo system: {}", e)))
         }
     }
 }
 
+/// Switch the servo stack between simulated and real hardware. Enabling
+/// simulation installs mock controllers straight away.
+#[tauri::command]
+pub async fn set_simulation_mode(
+    enabled: bool,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    info!("Setting servo simulation mode: {}", enabled);
+
+    let mut system = servo_system.write().await;
+    system.set_simulation(enabled);
+    if !enabled {
+        return Ok(ServoCommandResponse::success("Simulation mode disabled"));
+    }
+
+    if !system.is_initialized() {
+        if let Err(e) = system.initialize().await {
+            error!("Failed to initialize simulated servo system: {}", e);
+            return Ok(ServoCommandResponse::error(&format!("Failed to initialize simulated servo system: {}", e)));
+        }
+    }
+    Ok(ServoCommandResponse::success("Simulation mode enabled").with_simulated(true))
+}
+
 /// Get servo configuration info
 #[tauri::command]
 pub async fn get_servo_config() -> Result<ServoCommandResponse, String> {
@@ -263,7 +541,9 @@ pub async fn get_servo_config() -> Result<ServoCommandResponse, String> {
             "name": servo_config.name,
             "min_pwm": servo_config.min_pwm,
             "max_pwm": servo_config.max_pwm,
-            "default_pwm": servo_config.default_pwm
+            "default_pwm": servo_config.default_pwm,
+            "inverted": servo_config.inverted,
+            "offset": servo_config.offset
         })
     }).collect::<Vec<_>>();
     
@@ -273,10 +553,12 @@ pub async fn get_servo_config() -> Result<ServoCommandResponse, String> {
 
 /// Get predefined poses
 #[tauri::command]
-pub async fn get_predefined_poses() -> Result<ServoCommandResponse, String> {
+pub async fn get_predefined_poses(
+    pose_library: State<'_, SharedPoseLibrary>,
+) -> Result<ServoCommandResponse, String> {
     debug!("Getting predefined poses");
     
-    let poses = TARSPoses::all_poses();
+    let poses = pose_library.read().await.all().to_vec();
     let poses_json = serde_json::json!({
         "poses": poses.iter().map(|pose| {
             serde_json::json!({
@@ -295,23 +577,57 @@ pub async fn get_predefined_poses() -> Result<ServoCommandResponse, String> {
     Ok(ServoCommandResponse::success_with_data("Predefined poses retrieved", poses_json))
 }
 
+/// Reload user poses from the configured pose file
+#[tauri::command]
+pub async fn reload_poses(
+    cfg: State<'_, SharedConfig>,
+    pose_library: State<'_, SharedPoseLibrary>,
+) -> Result<ServoCommandResponse, String> {
+    let path = cfg.lock().await.robotics.poses_file.clone();
+    info!("Reloading poses from {:?}", path);
+
+    match PoseLibrary::load(&path) {
+        Ok((library, report)) => {
+            *pose_library.write().await = library;
+            let report_json = serde_json::to_value(&report).map_err(|e| e.to_string())?;
+            let message = if report.rejected.is_empty() {
+                format!("Loaded {} user poses", report.loaded.len())
+            } else {
+                format!("Loaded {} user poses, rejected {}", report.loaded.len(), report.rejected.len())
+            };
+            Ok(ServoCommandResponse::success_with_data(&message, report_json))
+        }
+        Err(e) => {
+            error!("Failed to reload poses: {}", e);
+            Ok(ServoCommandResponse::error(&e.to_string()))
+        }
+    }
+}
+
 /// Set individual servo position (for testing/debugging)
 #[tauri::command]
 pub async fn set_servo_position(
     servo_id: u8,
     position: f32,
-    servo_controller: State<'_, Option<Arc<PCA9685Controller<MockI2C>>>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     info!("Setting servo {} to position {}", servo_id, position);
     
-    let controller = servo_controller.inner()
-        .as_ref()
-        .ok_or("Servo controller not initialized")?;
+    let (movement, simulated) = {
+        let system = servo_system.read().await;
+        let movement = system.movement_controller().ok_or("Servo controller not initialized")?;
+        (movement, system.is_simulation())
+    };
+    let servo = match ServoId::try_from(servo_id) {
+        Ok(servo) => servo,
+        Err(e) => return Ok(ServoCommandResponse::error(&e)),
+    };
 
-    match controller.set_position(servo_id, position).await {
+    // Checked against the soft limits and collision constraints first
+    match movement.set_servo_position(servo, position).await {
         Ok(_) => {
             info!("Servo {} set to position {}", servo_id, position);
-            Ok(ServoCommandResponse::success(&format!("Servo {} set to position {}", servo_id, position)))
+            Ok(ServoCommandResponse::success(&format!("Servo {} set to position {}", servo_id, position)).with_simulated(simulated))
         }
         Err(e) => {
             error!("Failed to set servo {} position: {}", servo_id, e);
@@ -320,17 +636,138 @@ pub async fn set_servo_position(
     }
 }
 
+/// Active hardware profile and the profiles available
+#[tauri::command]
+pub async fn get_servo_profiles(
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let system = servo_system.read().await;
+    Ok(ServoCommandResponse::success_with_data(
+        &format!("Servo profile is '{}'", system.profile_name()),
+        serde_json::json!({ "active": system.profile_name(), "profiles": system.profile_names() }),
+    ))
+}
+
+/// Switch hardware profile, e.g. "replica-1to1" or "mini-desk". The servos
+/// are brought to neutral before the new profile takes over.
+#[tauri::command]
+pub async fn set_servo_profile(
+    name: String,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    info!("Switching servo profile to '{}'", name);
+    let mut system = servo_system.write().await;
+    match system.switch_profile(&name).await {
+        Ok(()) => Ok(ServoCommandResponse::success(&format!("Servo profile set to '{}'", name)).with_simulated(system.is_simulation())),
+        Err(e) => {
+            error!("Failed to switch servo profile: {}", e);
+            Ok(ServoCommandResponse::error(&e))
+        }
+    }
+}
+
+/// Current PWM frequency of the servo board
+#[tauri::command]
+pub async fn get_pwm_frequency(
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = servo_system.read().await.servo_controller().ok_or("Servo controller not initialized")?;
+    let frequency = controller.get_frequency();
+    Ok(ServoCommandResponse::success_with_data(
+        &format!("PWM frequency is {} Hz", frequency),
+        serde_json::json!({ "frequency": frequency }),
+    ))
+}
+
+/// Change the PWM frequency, e.g. 50 Hz for analog or 330 Hz for digital
+/// servos. The frequency is kept when the controllers are reinstalled.
+#[tauri::command]
+pub async fn set_servo_frequency(
+    frequency: f32,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    info!("Setting PWM frequency to {} Hz", frequency);
+    let mut system = servo_system.write().await;
+    let controller = system.servo_controller().ok_or("Servo controller not initialized")?;
+    match controller.set_pwm_frequency(frequency).await {
+        Ok(()) => {
+            let mut bus_config = system.bus_config().clone();
+            bus_config.pwm_frequency = frequency;
+            system.set_bus_config(bus_config);
+            Ok(ServoCommandResponse::success(&format!("PWM frequency set to {} Hz", frequency)))
+        },
+        Err(e) => {
+            error!("Failed to set PWM frequency: {}", e);
+            Ok(ServoCommandResponse::error(&e.to_string()))
+        }
+    }
+}
+
+/// Channels excluded from writes after repeated I2C failures
+#[tauri::command]
+pub async fn get_servo_faults(
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = servo_system.read().await.servo_controller().ok_or("Servo controller not initialized")?;
+    let faulted = controller.faulted_channels().await;
+    Ok(ServoCommandResponse::success_with_data(
+        &format!("{} faulted servo channels", faulted.len()),
+        serde_json::json!({ "faulted": faulted }),
+    ))
+}
+
+/// Put a faulted servo channel back in service
+#[tauri::command]
+pub async fn reset_servo_fault(
+    servo_id: u8,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let controller = servo_system.read().await.servo_controller().ok_or("Servo controller not initialized")?;
+    if controller.reset_fault(servo_id).await {
+        Ok(ServoCommandResponse::success(&format!("Servo {} fault cleared", servo_id)))
+    } else {
+        Ok(ServoCommandResponse::error(&format!("Servo {} is not faulted", servo_id)))
+    }
+}
+
+/// Audited movement commands started between `from` and `to`
+#[tauri::command]
+pub async fn get_movement_log(
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Vec<movement_audit::MovementRecord>, String> {
+    Ok(movement_audit::movement_log(from, to).await)
+}
+
+/// Re-run the audited movement commands between `from` and `to` on a
+/// simulated copy of the live servo profile, for analysis. Never touches
+/// the real hardware.
+#[tauri::command]
+pub async fn replay_movement_log(
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+    servo_system: State<'_, SharedServoSystem>,
+) -> Result<ServoCommandResponse, String> {
+    let steps = movement_audit::replay_movement_log(&*servo_system.read().await, from, to).await?;
+    let diverged = steps.iter().filter(|step| !step.matches_original()).count();
+    let steps_json = serde_json::to_value(&steps).map_err(|e| e.to_string())?;
+    let message = format!("Replayed {} movement commands in simulation, {} diverged", steps.len(), diverged);
+    Ok(ServoCommandResponse::success_with_data(&message, steps_json).with_simulated(true))
+}
+
 /// Test servo movement (move to extremes and back to center)
 #[tauri::command]
 pub async fn test_servo_movement(
     servo_id: u8,
-    servo_controller: State<'_, Option<Arc<PCA9685Controller<MockI2C>>>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     info!("Testing servo {} movement", servo_id);
     
-    let controller = servo_controller.inner()
-        .as_ref()
-        .ok_or("Servo controller not initialized")?;
+    let (controller, simulated) = {
+        let system = servo_system.read().await;
+        let controller = system.servo_controller().ok_or("Servo controller not initialized")?;
+        (controller, system.is_simulation())
+    };
 
     // Move to minimum position
     if let Err(e) = controller.set_position(servo_id, -1.0).await {
@@ -352,27 +789,34 @@ pub async fn test_servo_movement(
     }
     
     info!("Servo {} movement test completed", servo_id);
-    Ok(ServoCommandResponse::success(&format!("Servo {} movement test completed successfully", servo_id)))
+    Ok(ServoCommandResponse::success(&format!("Servo {} movement test completed successfully", servo_id)).with_simulated(simulated))
+}
+
+/// Sweep every configured servo through a small range and report
+/// pass/fail/uncertain per servo. Skipped while movement is disabled.
+#[tauri::command]
+pub async fn run_servo_diagnostics(
+    servo_system: State<'_, SharedServoSystem>,
+    safety: State<'_, SharedSafety>,
+) -> Result<DiagnosticReport, String> {
+    Ok(diagnose_servos(&servo_system, &safety).await)
 }
 
 /// Emergency stop all movement
 #[tauri::command]
 pub async fn emergency_stop_all(
-    movement_controller: State<'_, Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>>>,
+    servo_system: State<'_, SharedServoSystem>,
 ) -> Result<ServoCommandResponse, String> {
     info!("Emergency stop activated");
     
-    let controller = movement_controller.inner()
-        .as_ref()
+    let controller = servo_system.read().await
+        .movement_controller()
         .ok_or("Movement controller not initialized")?;
 
-    // Disable movement and execute emergency stop
-    controller.set_enabled(false).await;
-    
-    match controller.execute_command(MovementCommand::EmergencyStop).await {
+    match controller.emergency_stop_all().await {
         Ok(response) => {
             info!("Emergency stop completed: {}", response);
-            Ok(ServoCommandResponse::success(&response))
+            Ok(ServoCommandResponse::success(&response).with_simulated(controller.is_simulated()))
         }
         Err(e) => {
             error!("Emergency stop failed: {}", e);
@@ -380,3 +824,59 @@ pub async fn emergency_stop_all(
         }
     }
 }
+
+/// Pair a remote control client with Execute permission and return the
+/// secret it connects with. Pairing again replaces the old secret.
+#[tauri::command]
+pub async fn pair_remote_client(client_id: String) -> Result<String, String> {
+    let token = ApprovalSystem::new().pair_client(&client_id, PermissionLevel::Execute).await?;
+    info!("Remote control client '{}' paired", client_id);
+    Ok(token)
+}
+
+/// Revoke a remote control client's secret and permissions
+#[tauri::command]
+pub async fn unpair_remote_client(client_id: String) -> Result<(), String> {
+    ApprovalSystem::new().unpair_client(&client_id).await
+}
+
+/// Register the servo control commands
+pub fn register_servo_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, execute_movement_command, Execute, "Run a movement command or named pose");
+    register_command!(registry, get_movement_status, Read, "Current pose and movement state");
+    register_command!(registry, set_movement_enabled, Write, "Enable or disable movement");
+    register_command!(registry, is_movement_enabled, Read, "Whether movement is enabled");
+    register_command!(registry, get_available_poses, Read, "Names of the available poses");
+    register_command!(registry, calibrate_servos, Execute, "Run the servo calibration sequence and save neutral offsets");
+    register_command!(registry, get_calibration, Read, "Saved neutral offset of every servo");
+    register_command!(registry, reset_calibration, Write, "Clear every servo's neutral offset");
+    register_command!(registry, get_gamepad_status, Read, "Gamepad connection and input state");
+    register_command!(registry, is_gamepad_connected, Read, "Whether a gamepad is connected");
+    register_command!(registry, get_available_gamepads, Read, "Detected gamepads");
+    register_command!(registry, set_gamepad_binding, Write, "Bind a gamepad button to an action");
+    register_command!(registry, set_gamepad_mapping, Write, "Switch and remember the gamepad mapping");
+    register_command!(registry, start_gamepad_recording, Execute, "Record a movement macro from the gamepad sticks");
+    register_command!(registry, stop_gamepad_recording, Write, "Stop gamepad recording and save the macro");
+    register_command!(registry, record_movement_sequence, Execute, "Record servo targets as a movement sequence");
+    register_command!(registry, stop_recording, Write, "Stop recording and save the movement sequence");
+    register_command!(registry, play_movement_sequence, Execute, "Play back a recorded movement sequence");
+    register_command!(registry, initialize_servo_system, Execute, "Install the servo and movement controllers");
+    register_command!(registry, set_simulation_mode, Admin, "Switch between simulated and hardware servos");
+    register_command!(registry, get_servo_config, Read, "Servo channel, range and mounting configuration");
+    register_command!(registry, get_predefined_poses, Read, "Pose definitions with servo positions");
+    register_command!(registry, reload_poses, Write, "Reload user poses from the pose file");
+    register_command!(registry, set_servo_position, Execute, "Move a single servo");
+    register_command!(registry, get_servo_profiles, Read, "Active and available hardware profiles");
+    register_command!(registry, set_servo_profile, Admin, "Switch the hardware profile for this TARS build");
+    register_command!(registry, get_pwm_frequency, Read, "PWM frequency of the servo board");
+    register_command!(registry, set_servo_frequency, Write, "Change the servo PWM frequency (24-1526 Hz)");
+    register_command!(registry, get_servo_faults, Read, "Servo channels faulted by I2C errors");
+    register_command!(registry, reset_servo_fault, Execute, "Clear a servo channel fault");
+    register_command!(registry, get_movement_log, Read, "Audited movement commands in a time range");
+    register_command!(registry, replay_movement_log, Execute, "Replay audited movement commands in simulation");
+    register_command!(registry, test_servo_movement, Execute, "Sweep a servo through its range");
+    register_command!(registry, run_servo_diagnostics, Execute, "Self-test every servo with a small sweep");
+    register_command!(registry, emergency_stop_all, Execute, "Stop all servos and return to neutral");
+    register_command!(registry, pair_remote_client, Admin, "Pair a remote control client and issue its secret");
+    register_command!(registry, unpair_remote_client, Admin, "Revoke a remote control client's secret");
+}
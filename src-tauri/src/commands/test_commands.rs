@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 use crate::code_analysis::test_generator::{TestGenerator, TestSuite};
 use crate::config::state_manager::TarsState;
+use super::registry::CommandRegistry;
+use crate::register_command;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestGenerationRequest {
@@ -284,62 +286,65 @@ pub async fn validate_test_code(
     Ok(validation_result)
 }
 
+/// Practices that apply in every language
+pub const UNIVERSAL_PRACTICES: [&str; 8] = [
+    "TARS: Follow the Arrange-Act-Assert (AAA) pattern",
+    "TARS: Write descriptive test names that explain what is being tested",
+    "TARS: Each test should focus on a single behavior or scenario",
+    "TARS: Use meaningful assertions with clear error messages",
+    "TARS: Keep tests independent - no test should depend on another",
+    "TARS: Test both happy path and edge cases",
+    "TARS: Mock external dependencies to isolate units under test",
+    "TARS: Maintain test code quality as rigorously as production code",
+];
+
+/// Languages with their own best practices
+pub const PRACTICE_LANGUAGES: [&str; 4] = ["java", "python", "javascript", "rust"];
+
+/// Best practices specific to `language`
+pub fn language_practices(language: &str) -> &'static [&'static str] {
+    match language.to_lowercase().as_str() {
+        "java" => &[
+            "TARS: Use @BeforeEach and @AfterEach for setup and teardown",
+            "TARS: Leverage @ParameterizedTest for data-driven tests",
+            "TARS: Use @DisplayName for readable test descriptions",
+            "TARS: Prefer assertThat() over basic assertions for clarity",
+        ],
+        "python" => &[
+            "TARS: Use pytest fixtures for reusable test setup",
+            "TARS: Leverage pytest.mark.parametrize for test variations",
+            "TARS: Use pytest.raises() for exception testing",
+            "TARS: Consider pytest-mock for easier mocking",
+        ],
+        "javascript" => &[
+            "TARS: Use describe() blocks to group related tests",
+            "TARS: Leverage beforeEach() and afterEach() for setup/cleanup",
+            "TARS: Use jest.mock() for module mocking",
+            "TARS: Consider async/await for asynchronous test code",
+        ],
+        "rust" => &[
+            "TARS: Use #[cfg(test)] module for test organization",
+            "TARS: Leverage #[should_panic] for error condition testing",
+            "TARS: Use assert_eq! and assert_ne! for equality checks",
+            "TARS: Consider proptest for property-based testing",
+        ],
+        _ => &[],
+    }
+}
+
 #[tauri::command]
 pub async fn get_testing_best_practices(
     language: String,
     _state: State<'_, TarsState>,
 ) -> Result<Vec<String>, String> {
-    let mut practices = Vec::new();
-    
-    // Universal best practices
-    practices.extend(vec![
-        "TARS: Follow the Arrange-Act-Assert (AAA) pattern".to_string(),
-        "TARS: Write descriptive test names that explain what is being tested".to_string(),
-        "TARS: Each test should focus on a single behavior or scenario".to_string(),
-        "TARS: Use meaningful assertions with clear error messages".to_string(),
-        "TARS: Keep tests independent - no test should depend on another".to_string(),
-        "TARS: Test both happy path and edge cases".to_string(),
-        "TARS: Mock external dependencies to isolate units under test".to_string(),
-        "TARS: Maintain test code quality as rigorously as production code".to_string(),
-    ]);
+    let mut practices: Vec<String> = UNIVERSAL_PRACTICES.iter().map(|practice| practice.to_string()).collect();
     
     // Language-specific best practices
-    match language.to_lowercase().as_str() {
-        "java" => {
-            practices.extend(vec![
-                "TARS: Use @BeforeEach and @AfterEach for setup and teardown".to_string(),
-                "TARS: Leverage @ParameterizedTest for data-driven tests".to_string(),
-                "TARS: Use @DisplayName for readable test descriptions".to_string(),
-                "TARS: Prefer assertThat() over basic assertions for clarity".to_string(),
-            ]);
-        },
-        "python" => {
-            practices.extend(vec![
-                "TARS: Use pytest fixtures for reusable test setup".to_string(),
-                "TARS: Leverage pytest.mark.parametrize for test variations".to_string(),
-                "TARS: Use pytest.raises() for exception testing".to_string(),
-                "TARS: Consider pytest-mock for easier mocking".to_string(),
-            ]);
-        },
-        "javascript" => {
-            practices.extend(vec![
-                "TARS: Use describe() blocks to group related tests".to_string(),
-                "TARS: Leverage beforeEach() and afterEach() for setup/cleanup".to_string(),
-                "TARS: Use jest.mock() for module mocking".to_string(),
-                "TARS: Consider async/await for asynchronous test code".to_string(),
-            ]);
-        },
-        "rust" => {
-            practices.extend(vec![
-                "TARS: Use #[cfg(test)] module for test organization".to_string(),
-                "TARS: Leverage #[should_panic] for error condition testing".to_string(),
-                "TARS: Use assert_eq! and assert_ne! for equality checks".to_string(),
-                "TARS: Consider proptest for property-based testing".to_string(),
-            ]);
-        },
-        _ => {
-            practices.push("TARS: Adapt these practices to your specific language and framework".to_string());
-        },
+    let specific = language_practices(&language);
+    if specific.is_empty() {
+        practices.push("TARS: Adapt these practices to your specific language and framework".to_string());
+    } else {
+        practices.extend(specific.iter().map(|practice| practice.to_string()));
     }
     
     practices.push("TARS: Remember Cooper - good tests are like a reliable co-pilot: they catch your mistakes before they become disasters. Mission focus: 100%".to_string());
@@ -447,3 +452,13 @@ fn generate_metrics_assessment(test_suite: &TestSuite, quality_score: f32) -> St
         _ => "TARS: Test metrics calculated, Cooper. Results are mixed - some good, some concerning. Like mission success probability. Humor setting: 75%".to_string(),
     }
 }
+
+/// Register the test generation commands
+pub fn register_test_commands(registry: &mut CommandRegistry) {
+    register_command!(registry, generate_test_suite, Read, "Generate a test suite for code");
+    register_command!(registry, generate_specific_tests, Read, "Generate tests of one kind for code");
+    register_command!(registry, get_test_recommendations, Read, "Testing recommendations for code");
+    register_command!(registry, validate_test_code, Read, "Check generated test code");
+    register_command!(registry, get_testing_best_practices, Read, "Testing best practices for a language");
+    register_command!(registry, calculate_test_metrics, Read, "Coverage and quality metrics for a test suite");
+}
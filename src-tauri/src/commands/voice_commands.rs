@@ -11,8 +11,10 @@ use crate::voice::{
         configure_tts_engine, get_tts_stats,
         SpeechRequest, SpeechPriority, SpeechContext, EmotionalState, Emotion,
         TextToSpeechEngine, TTSEngine, VoiceProfile, AudioOutput, SpeechQueue
-    }
+    },
+    audio_output::{list_output_devices, play_audio, OutputDeviceInfo},
 };
+use crate::config::config::SharedConfig;
 use tauri::State;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -222,12 +224,40 @@ pub async fn configure_text_to_speech(
         tars_personality,
         emotional_inflection,
         audio_format: crate::voice::text_to_speech::AudioFormat::WAV,
+        enabled: true,
     };
 
     configure_tts_engine(engine).await?;
     Ok("Text-to-speech engine configured successfully. TARS voice personality updated.".to_string())
 }
 
+#[tauri::command]
+pub async fn speak_text_on_output_device(
+    text: String,
+    cfg: State<'_, SharedConfig>,
+) -> Result<OutputDeviceInfo, String> {
+    let preferred_device = cfg.lock().await.audio.output_device.clone();
+    speak_on_output_device(text, preferred_device).await
+}
+
+/// `speak_text_on_output_device` without Tauri state, for the CLI
+pub async fn speak_on_output_device(text: String, preferred_device: Option<String>) -> Result<OutputDeviceInfo, String> {
+    let request = SpeechRequest {
+        text,
+        priority: SpeechPriority::Normal,
+        context: SpeechContext::Conversation,
+        emotional_state: None,
+        override_settings: None,
+    };
+    let audio = speak_with_request(request).await?;
+    play_audio(audio, preferred_device).await
+}
+
+#[tauri::command]
+pub async fn list_audio_output_devices() -> Result<Vec<OutputDeviceInfo>, String> {
+    list_output_devices()
+}
+
 #[tauri::command]
 pub async fn get_text_to_speech_stats() -> Result<HashMap<String, String>, String> {
     Ok(get_tts_stats().await)
@@ -1,11 +1,24 @@
+use crate::ai::confidence::ConfidencePolicy;
+use crate::ai::guard::GuardPolicy;
+use crate::ai::inference_queue::QueueConfig;
+use crate::events::{self, TarsEvent};
+use crate::ai::routing::RoutingPolicy;
+use crate::personality::locale::DEFAULT_LOCALE;
+use crate::personality::tars_core::PersonalitySettings;
+use crate::robotics::{GamepadConfig, LinkModel, ThermalConfig, TickRateConfig};
+use crate::robotics::pca9685_controller::{PCA9685_MAX_FREQUENCY, PCA9685_MIN_FREQUENCY};
+use crate::robotics::servo_config::{ServoProfile, DEFAULT_SERVO_PROFILE};
+use crate::robotics::telemetry::DEFAULT_HISTORY_SECONDS;
 use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fs, io,
     path::{Path, PathBuf},
     sync::Arc,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 
 const ENCRYPTION_KEY: &[u8] = b"gsteng-secret";
 
@@ -15,6 +28,18 @@ pub struct AiConfig {
     pub preferred_model: String,
     #[serde(default)]
     pub use_cloud: bool,
+    /// Fallback and ensemble routes between models
+    #[serde(default)]
+    pub routing: RoutingPolicy,
+    /// Prompt screening rules and response secret redaction
+    #[serde(default)]
+    pub guard: GuardPolicy,
+    /// How many model inferences run at once and how many may wait
+    #[serde(default)]
+    pub queue: QueueConfig,
+    /// Model training cutoffs and the self-check behind confidence notes
+    #[serde(default)]
+    pub confidence: ConfidencePolicy,
 }
 
 impl AiConfig {
@@ -28,6 +53,10 @@ impl Default for AiConfig {
         Self {
             preferred_model: Self::default_model(),
             use_cloud: false,
+            routing: RoutingPolicy::default(),
+            guard: GuardPolicy::default(),
+            queue: QueueConfig::default(),
+            confidence: ConfidencePolicy::default(),
         }
     }
 }
@@ -75,6 +104,19 @@ pub struct Personality {
     pub honesty: f32,
     #[serde(default)]
     pub sarcasm: f32,
+    /// Scales how long responses may get before they are trimmed
+    #[serde(default = "Personality::default_verbosity")]
+    pub verbosity: f32,
+    /// Honesty at or above which answers carry a confidence note
+    #[serde(default = "Personality::default_honesty_threshold")]
+    pub honesty_threshold: f32,
+    /// Named dial settings applied all at once by `apply_personality_preset`
+    #[serde(default = "Personality::default_presets")]
+    pub presets: BTreeMap<String, PersonalitySettings>,
+    /// Locale TARS speaks and prints in, e.g. "en" or "es". Phrases a locale
+    /// doesn't translate are given in English.
+    #[serde(default = "Personality::default_locale")]
+    pub locale: String,
 }
 
 impl Personality {
@@ -84,6 +126,46 @@ impl Personality {
     fn default_greeting() -> String {
         "Hello".into()
     }
+    fn default_verbosity() -> f32 {
+        0.5
+    }
+    fn default_honesty_threshold() -> f32 {
+        0.8
+    }
+    fn default_locale() -> String {
+        DEFAULT_LOCALE.into()
+    }
+    fn default_presets() -> BTreeMap<String, PersonalitySettings> {
+        [
+            ("Movie-Accurate", 75, 90, 30, 40),
+            ("Professional", 20, 100, 5, 60),
+            ("Max Sarcasm", 70, 90, 100, 50),
+        ]
+        .into_iter()
+        .map(|(name, humor, honesty, sarcasm, verbosity)| {
+            (name.to_string(), PersonalitySettings { humor, honesty, sarcasm, verbosity })
+        })
+        .collect()
+    }
+
+    pub fn preset_names(&self) -> Vec<String> {
+        self.presets.keys().cloned().collect()
+    }
+
+    /// Look up a preset by name, ignoring case
+    pub fn preset(&self, name: &str) -> Result<(&str, &PersonalitySettings), String> {
+        self.presets
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim()))
+            .map(|(preset, settings)| (preset.as_str(), settings))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown personality preset '{}'. Available presets: {}",
+                    name,
+                    self.preset_names().join(", ")
+                )
+            })
+    }
 }
 
 impl Default for Personality {
@@ -94,6 +176,246 @@ impl Default for Personality {
             humor: 0.5,
             honesty: 0.5,
             sarcasm: 0.5,
+            verbosity: Self::default_verbosity(),
+            honesty_threshold: Self::default_honesty_threshold(),
+            presets: Self::default_presets(),
+            locale: Self::default_locale(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AudioConfig {
+    /// Name of the playback device as reported by the audio host. When unset
+    /// or missing, playback uses the host default device.
+    #[serde(default)]
+    pub output_device: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ApiServerConfig {
+    /// Keys accepted in the `X-API-Key` header or as a bearer token. When
+    /// empty, the document API is open.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Origins allowed to make cross-origin requests. When empty, every
+    /// cross-origin request is rejected.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoboticsConfig {
+    /// User pose definitions (TOML or JSON) merged over the built-in poses.
+    #[serde(default = "RoboticsConfig::default_poses_file")]
+    pub poses_file: PathBuf,
+    /// Always drive the servo stack with `MockI2C`, skipping the startup
+    /// hardware probe.
+    #[serde(default)]
+    pub simulation: bool,
+    /// Deadzone, timeouts, button bindings and controller mappings for the gamepad.
+    #[serde(default)]
+    pub gamepad: GamepadConfig,
+    /// Bus, address and PWM frequency of the servo board.
+    #[serde(default)]
+    pub i2c: I2cBusConfig,
+    /// Limb geometry for the forward-kinematics pose in telemetry.
+    #[serde(default)]
+    pub kinematics: LinkModel,
+    /// Bounds for the adaptive servo tick rate.
+    #[serde(default)]
+    pub tick_rate: TickRateConfig,
+    /// What movement does when the Pi passes its thermal throttle temperature.
+    #[serde(default)]
+    pub thermal: ThermalConfig,
+    /// Hardware profile to start with: "replica-1to1", "mini-desk" or a
+    /// name from `servo_profiles`.
+    #[serde(default = "RoboticsConfig::default_servo_profile")]
+    pub servo_profile: String,
+    /// Extra hardware profiles, servo name to PWM range and limits.
+    #[serde(default)]
+    pub servo_profiles: BTreeMap<String, ServoProfile>,
+    /// Seconds of telemetry replayed to WebSocket clients when they connect.
+    #[serde(default = "RoboticsConfig::default_telemetry_history_seconds")]
+    pub telemetry_history_seconds: u64,
+}
+
+impl RoboticsConfig {
+    fn default_poses_file() -> PathBuf {
+        PathBuf::from("poses.toml")
+    }
+    fn default_servo_profile() -> String {
+        DEFAULT_SERVO_PROFILE.into()
+    }
+    fn default_telemetry_history_seconds() -> u64 {
+        DEFAULT_HISTORY_SECONDS
+    }
+}
+
+impl Default for RoboticsConfig {
+    fn default() -> Self {
+        Self {
+            poses_file: Self::default_poses_file(),
+            simulation: false,
+            gamepad: GamepadConfig::default(),
+            i2c: I2cBusConfig::default(),
+            kinematics: LinkModel::default(),
+            tick_rate: TickRateConfig::default(),
+            thermal: ThermalConfig::default(),
+            servo_profile: Self::default_servo_profile(),
+            servo_profiles: BTreeMap::new(),
+            telemetry_history_seconds: Self::default_telemetry_history_seconds(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct I2cBusConfig {
+    /// Linux I2C character device the PCA9685 is attached to.
+    #[serde(default = "I2cBusConfig::default_bus_path")]
+    pub bus_path: String,
+    /// PCA9685 address, 0x40-0x47 depending on the A0-A2 straps.
+    #[serde(default = "I2cBusConfig::default_address")]
+    pub address: u8,
+    #[serde(default = "I2cBusConfig::default_pwm_frequency")]
+    pub pwm_frequency: f32,
+}
+
+impl I2cBusConfig {
+    fn default_bus_path() -> String {
+        "/dev/i2c-1".into()
+    }
+    fn default_address() -> u8 {
+        0x40
+    }
+    fn default_pwm_frequency() -> f32 {
+        50.0
+    }
+}
+
+impl Default for I2cBusConfig {
+    fn default() -> Self {
+        Self {
+            bus_path: Self::default_bus_path(),
+            address: Self::default_address(),
+            pwm_frequency: Self::default_pwm_frequency(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SafetyConfig {
+    /// Roll or pitch beyond this many degrees halts movement.
+    #[serde(default = "SafetyConfig::default_max_tilt")]
+    pub max_tilt_degrees: f32,
+    /// Obstacles closer than this many metres halt movement.
+    #[serde(default = "SafetyConfig::default_min_range")]
+    pub min_obstacle_distance: f32,
+    #[serde(default = "SafetyConfig::default_poll_interval")]
+    pub sensor_poll_interval_ms: u64,
+    /// Below this battery percentage movement slows down.
+    #[serde(default = "SafetyConfig::default_low_battery")]
+    pub low_battery_percent: f32,
+    /// Below this battery percentage TARS comes to a controlled stop.
+    #[serde(default = "SafetyConfig::default_critical_battery")]
+    pub critical_battery_percent: f32,
+    /// Movement speed while the battery is low.
+    #[serde(default = "SafetyConfig::default_low_battery_speed")]
+    pub low_battery_speed: f32,
+    #[serde(default = "SafetyConfig::default_power_poll_interval")]
+    pub power_poll_interval_ms: u64,
+    /// sysfs power supply to read the battery from, such as
+    /// `/sys/class/power_supply/BAT0`. The first battery found when unset.
+    #[serde(default)]
+    pub power_supply: Option<PathBuf>,
+}
+
+impl SafetyConfig {
+    fn default_max_tilt() -> f32 {
+        30.0
+    }
+    fn default_min_range() -> f32 {
+        0.15
+    }
+    fn default_poll_interval() -> u64 {
+        50
+    }
+    fn default_low_battery() -> f32 {
+        20.0
+    }
+    fn default_critical_battery() -> f32 {
+        5.0
+    }
+    fn default_low_battery_speed() -> f32 {
+        0.5
+    }
+    fn default_power_poll_interval() -> u64 {
+        5000
+    }
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            max_tilt_degrees: Self::default_max_tilt(),
+            min_obstacle_distance: Self::default_min_range(),
+            sensor_poll_interval_ms: Self::default_poll_interval(),
+            low_battery_percent: Self::default_low_battery(),
+            critical_battery_percent: Self::default_critical_battery(),
+            low_battery_speed: Self::default_low_battery_speed(),
+            power_poll_interval_ms: Self::default_power_poll_interval(),
+            power_supply: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoggingConfig {
+    /// Level for modules without an entry in `modules`.
+    #[serde(default = "LoggingConfig::default_level")]
+    pub level: String,
+    /// Per-module levels keyed by module path, e.g. `robotics = "debug"`.
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+    /// Log file; rotated files get a numeric suffix. Unset logs to stderr only.
+    #[serde(default = "LoggingConfig::default_file")]
+    pub file: Option<PathBuf>,
+    #[serde(default = "LoggingConfig::default_max_file_size")]
+    pub max_file_size_bytes: u64,
+    #[serde(default = "LoggingConfig::default_max_files")]
+    pub max_files: usize,
+    /// Directory the approval audit log writes daily `audit_*.log` files to
+    #[serde(default = "LoggingConfig::default_audit_dir")]
+    pub audit_dir: PathBuf,
+}
+
+impl LoggingConfig {
+    fn default_level() -> String {
+        "info".into()
+    }
+    fn default_file() -> Option<PathBuf> {
+        Some(PathBuf::from("logs/tars.log"))
+    }
+    fn default_max_file_size() -> u64 {
+        5 * 1024 * 1024
+    }
+    fn default_max_files() -> usize {
+        5
+    }
+    fn default_audit_dir() -> PathBuf {
+        PathBuf::from("audit_logs")
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: Self::default_level(),
+            modules: BTreeMap::new(),
+            file: Self::default_file(),
+            max_file_size_bytes: Self::default_max_file_size(),
+            max_files: Self::default_max_files(),
+            audit_dir: Self::default_audit_dir(),
         }
     }
 }
@@ -108,6 +430,16 @@ pub struct Config {
     pub hardware: HardwareProfile,
     #[serde(default)]
     pub personality: Personality,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub api_server: ApiServerConfig,
+    #[serde(default)]
+    pub safety: SafetyConfig,
+    #[serde(default)]
+    pub robotics: RoboticsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
 }
 
 impl Default for Config {
@@ -117,12 +449,57 @@ impl Default for Config {
             api_keys: ApiKeys::default(),
             hardware: HardwareProfile::default(),
             personality: Personality::default(),
+            audio: AudioConfig::default(),
+            api_server: ApiServerConfig::default(),
+            safety: SafetyConfig::default(),
+            robotics: RoboticsConfig::default(),
+            logging: LoggingConfig::default(),
         }
     }
 }
 
 pub type SharedConfig = Arc<Mutex<Config>>;
 
+/// Config file the app loads at startup and saves runtime choices to
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Runtime config change, broadcast so voice and AI can re-tune
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConfigChange {
+    /// The config file was reloaded from disk; `changed` holds the dotted
+    /// keys whose values changed, the only ones applied to the running config
+    Reloaded { changed: Vec<String> },
+    /// A personality preset was applied
+    PersonalityPreset { name: String, settings: PersonalitySettings },
+    /// The user's preferences were changed and applied
+    Preferences,
+}
+
+static CONFIG_EVENTS: Lazy<broadcast::Sender<ConfigChange>> = Lazy::new(|| broadcast::channel(16).0);
+
+impl ConfigChange {
+    /// Whether the change can affect `key` (a section such as `ai`, or a
+    /// dotted key such as `personality.humor`). Only a reload is that precise.
+    pub fn touches(&self, key: &str) -> bool {
+        match self {
+            ConfigChange::Reloaded { changed } => changed.iter().any(|changed| {
+                changed == key || changed.starts_with(&format!("{}.", key)) || key.starts_with(&format!("{}.", changed))
+            }),
+            _ => true,
+        }
+    }
+}
+
+pub fn subscribe_changes() -> broadcast::Receiver<ConfigChange> {
+    CONFIG_EVENTS.subscribe()
+}
+
+pub fn notify_change(change: ConfigChange) {
+    events::publish(TarsEvent::ConfigChanged { change: change.clone() });
+    let _ = CONFIG_EVENTS.send(change);
+}
+
 impl Config {
     pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
         if path.as_ref().exists() {
@@ -145,10 +522,40 @@ impl Config {
         fs::write(path, toml)
     }
 
+    /// Apply the values that changed on disk between `previous` and
+    /// `reloaded`, keeping everything else, including changes made at
+    /// runtime. Returns the dotted keys that changed.
+    pub fn merge_reload(&mut self, previous: &Config, reloaded: &Config) -> Result<Vec<String>, String> {
+        let to_value = |cfg: &Config| toml::Value::try_from(cfg).map_err(|e| e.to_string());
+        let mut current = to_value(self)?;
+        let mut changed = Vec::new();
+        merge_changes(&mut current, &to_value(previous)?, &to_value(reloaded)?, "", &mut changed);
+        if !changed.is_empty() {
+            *self = current.try_into().map_err(|e: toml::de::Error| e.to_string())?;
+        }
+        Ok(changed)
+    }
+
+    /// Set humor, honesty and sarcasm from the named preset and return the
+    /// change to broadcast
+    pub fn apply_personality_preset(&mut self, name: &str) -> Result<ConfigChange, String> {
+        let (name, settings) = self.personality.preset(name)?;
+        let (name, settings) = (name.to_string(), settings.clone());
+        let dial = |percent: u8| f32::from(percent.min(100)) / 100.0;
+        self.personality.humor = dial(settings.humor);
+        self.personality.honesty = dial(settings.honesty);
+        self.personality.sarcasm = dial(settings.sarcasm);
+        self.personality.verbosity = dial(settings.verbosity);
+        Ok(ConfigChange::PersonalityPreset { name, settings })
+    }
+
     fn validate(&mut self) {
         if self.ai.preferred_model.is_empty() {
             self.ai.preferred_model = AiConfig::default_model();
         }
+        if self.ai.routing.timeout_secs == 0 {
+            self.ai.routing.timeout_secs = RoutingPolicy::default().timeout_secs;
+        }
         if self.hardware.port.is_empty() {
             self.hardware.port = HardwareProfile::default_port();
         }
@@ -164,6 +571,51 @@ impl Config {
         self.personality.humor = self.personality.humor.clamp(0.0, 1.0);
         self.personality.honesty = self.personality.honesty.clamp(0.0, 1.0);
         self.personality.sarcasm = self.personality.sarcasm.clamp(0.0, 1.0);
+        self.personality.verbosity = self.personality.verbosity.clamp(0.0, 1.0);
+        self.personality.honesty_threshold = self.personality.honesty_threshold.clamp(0.0, 1.0);
+        if self.personality.presets.is_empty() {
+            self.personality.presets = Personality::default_presets();
+        }
+        self.personality.locale = self.personality.locale.trim().to_lowercase();
+        if self.personality.locale.is_empty() {
+            self.personality.locale = Personality::default_locale();
+        }
+        if self.robotics.i2c.bus_path.trim().is_empty() {
+            self.robotics.i2c.bus_path = I2cBusConfig::default_bus_path();
+        }
+        if !(PCA9685_MIN_FREQUENCY..=PCA9685_MAX_FREQUENCY).contains(&self.robotics.i2c.pwm_frequency) {
+            self.robotics.i2c.pwm_frequency = I2cBusConfig::default_pwm_frequency();
+        }
+        if self.safety.max_tilt_degrees <= 0.0 {
+            self.safety.max_tilt_degrees = SafetyConfig::default_max_tilt();
+        }
+        if self.safety.min_obstacle_distance < 0.0 {
+            self.safety.min_obstacle_distance = SafetyConfig::default_min_range();
+        }
+        if self.safety.sensor_poll_interval_ms == 0 {
+            self.safety.sensor_poll_interval_ms = SafetyConfig::default_poll_interval();
+        }
+        if self.safety.critical_battery_percent > self.safety.low_battery_percent {
+            self.safety.critical_battery_percent = self.safety.low_battery_percent;
+        }
+        if self.safety.low_battery_speed <= 0.0 {
+            self.safety.low_battery_speed = SafetyConfig::default_low_battery_speed();
+        }
+        if self.safety.power_poll_interval_ms == 0 {
+            self.safety.power_poll_interval_ms = SafetyConfig::default_power_poll_interval();
+        }
+        if matches!(self.audio.output_device.as_deref(), Some(name) if name.trim().is_empty()) {
+            self.audio.output_device = None;
+        }
+        if self.logging.level.trim().is_empty() {
+            self.logging.level = LoggingConfig::default_level();
+        }
+        if self.logging.max_file_size_bytes == 0 {
+            self.logging.max_file_size_bytes = LoggingConfig::default_max_file_size();
+        }
+        if self.logging.max_files == 0 {
+            self.logging.max_files = LoggingConfig::default_max_files();
+        }
     }
 
     fn encrypt_keys(&mut self) {
@@ -201,15 +653,68 @@ fn decrypt(text: &str) -> String {
     }
 }
 
+/// Copy into `current` every value that differs between `previous` and
+/// `reloaded`, recording its dotted key. Tables are compared key by key;
+/// anything else, arrays included, is replaced whole.
+fn merge_changes(
+    current: &mut toml::Value,
+    previous: &toml::Value,
+    reloaded: &toml::Value,
+    key: &str,
+    changed: &mut Vec<String>,
+) {
+    match (current, previous, reloaded) {
+        (toml::Value::Table(current), toml::Value::Table(previous), toml::Value::Table(reloaded)) => {
+            let names: BTreeSet<&String> = previous.keys().chain(reloaded.keys()).collect();
+            for name in names {
+                let path = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                match (previous.get(name), reloaded.get(name)) {
+                    (Some(before), Some(after)) if before == after => {},
+                    (Some(before), Some(after)) if current.contains_key(name) => {
+                        merge_changes(current.get_mut(name).unwrap(), before, after, &path, changed);
+                    },
+                    (_, Some(after)) => {
+                        current.insert(name.clone(), after.clone());
+                        changed.push(path);
+                    },
+                    (_, None) => {
+                        current.remove(name);
+                        changed.push(path);
+                    },
+                }
+            }
+        },
+        (current, previous, reloaded) => {
+            if previous != reloaded {
+                *current = reloaded.clone();
+                changed.push(key.to_string());
+            }
+        },
+    }
+}
+
+/// Watch the config file and merge each saved change into `cfg`. Values the
+/// file did not change keep any runtime change.
 pub fn start_hot_reload(path: PathBuf, cfg: SharedConfig) -> notify::Result<RecommendedWatcher> {
-    let mut watcher = notify::recommended_watcher(move |res| {
+    let mut on_disk = Config::load(&path).map_err(notify::Error::io)?;
+    let watched = path.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
         if let Ok(event) = res {
             if matches!(event.kind, EventKind::Modify(_))
                 || matches!(event.kind, EventKind::Create(_))
             {
-                if let Ok(new_cfg) = Config::load(&path) {
+                if let Ok(new_cfg) = Config::load(&watched) {
                     let mut lock = cfg.blocking_lock();
-                    *lock = new_cfg;
+                    match lock.merge_reload(&on_disk, &new_cfg) {
+                        Ok(changed) if changed.is_empty() => {},
+                        Ok(changed) => {
+                            crate::logging::update(&lock.logging);
+                            drop(lock);
+                            notify_change(ConfigChange::Reloaded { changed });
+                        },
+                        Err(e) => log::warn!("Failed to merge reloaded config: {}", e),
+                    }
+                    on_disk = new_cfg;
                 }
             }
         }
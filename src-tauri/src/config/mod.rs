@@ -1,2 +1,3 @@
 pub mod config;
 pub mod state_manager;
+pub mod user_preferences;
@@ -1,7 +1,8 @@
-use std::{cmp::Ordering, collections::BinaryHeap, sync::Arc};
+use serde::Serialize;
+use std::{cmp::Ordering, collections::BinaryHeap, io, path::Path, sync::Arc};
 use tokio::sync::{broadcast, Mutex};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum RobotState {
     Idle,
     Listening,
@@ -33,6 +34,14 @@ impl PartialEq for Command {
     }
 }
 
+/// State written to disk on shutdown.
+#[derive(Debug, Serialize)]
+pub struct StateSnapshot {
+    pub state: RobotState,
+    pub queued_commands: Vec<String>,
+}
+
+#[derive(Clone)]
 pub struct StateManager {
     state: Arc<Mutex<RobotState>>,
     queue: Arc<Mutex<BinaryHeap<Command>>>,
@@ -69,4 +78,20 @@ impl StateManager {
     pub async fn next_command(&self) -> Option<Command> {
         self.queue.lock().await.pop()
     }
+
+    pub async fn snapshot(&self) -> StateSnapshot {
+        let queue = self.queue.lock().await;
+        let mut queued: Vec<&Command> = queue.iter().collect();
+        queued.sort_by(|a, b| b.cmp(a));
+        StateSnapshot {
+            state: self.current_state().await,
+            queued_commands: queued.into_iter().map(|c| c.action.clone()).collect(),
+        }
+    }
+
+    pub async fn save_snapshot(&self, path: &Path) -> io::Result<()> {
+        let snapshot = self.snapshot().await;
+        let json = serde_json::to_string_pretty(&snapshot).map_err(io::Error::other)?;
+        tokio::fs::write(path, json).await
+    }
 }
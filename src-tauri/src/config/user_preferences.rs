@@ -0,0 +1,121 @@
+//! Per-user preferences stored apart from the system config. They are laid
+//! over the loaded `Config` at startup and after every reload, and are
+//! written back as soon as they change.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::config::{Config, ConfigChange};
+use crate::voice::advanced_tts::QualityMode;
+
+/// Directory holding one `<user>.toml` per user
+pub const PREFERENCES_DIR: &str = "preferences";
+
+/// Unset fields leave the system config value in place
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserPreferences {
+    pub voice_quality: Option<QualityMode>,
+    /// Name of a preset from `personality.presets`
+    pub personality_preset: Option<String>,
+    pub output_device: Option<String>,
+    /// 0.0-1.0, applied after the preset
+    pub verbosity: Option<f32>,
+}
+
+impl UserPreferences {
+    /// Override `cfg` with these preferences. Returns the preset change to
+    /// broadcast, if a preset was applied.
+    pub fn apply(&self, cfg: &mut Config) -> Result<Option<ConfigChange>, String> {
+        let change = match &self.personality_preset {
+            Some(name) => Some(cfg.apply_personality_preset(name)?),
+            None => None,
+        };
+        if let Some(verbosity) = self.verbosity {
+            cfg.personality.verbosity = verbosity.clamp(0.0, 1.0);
+        }
+        if self.output_device.is_some() {
+            cfg.audio.output_device = self.output_device.clone();
+        }
+        Ok(change)
+    }
+}
+
+/// The current user's preferences and the file they persist to
+pub struct PreferenceStore {
+    path: PathBuf,
+    preferences: RwLock<UserPreferences>,
+}
+
+pub type SharedPreferences = Arc<PreferenceStore>;
+
+/// The OS user running TARS, whose preferences are loaded at startup
+pub fn current_user() -> String {
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "default".to_string())
+}
+
+impl PreferenceStore {
+    /// Empty preferences that will persist to `<dir>/<user>.toml`
+    pub fn new(dir: &Path, user: &str) -> Self {
+        Self { path: dir.join(format!("{}.toml", user)), preferences: RwLock::new(UserPreferences::default()) }
+    }
+
+    /// Load `<dir>/<user>.toml`, starting empty if it doesn't exist yet
+    pub fn load(dir: &Path, user: &str) -> Result<Self, String> {
+        let store = Self::new(dir, user);
+        let preferences = match std::fs::read_to_string(&store.path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| format!("Invalid preferences {}: {}", store.path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => UserPreferences::default(),
+            Err(e) => return Err(format!("Failed to read {}: {}", store.path.display(), e)),
+        };
+        Ok(Self { preferences: RwLock::new(preferences), ..store })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn get(&self) -> UserPreferences {
+        self.preferences.read().await.clone()
+    }
+
+    /// Replace the preferences and write them to disk straight away
+    pub async fn set(&self, preferences: UserPreferences) -> Result<(), String> {
+        let mut current = self.preferences.write().await;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let content = toml::to_string_pretty(&preferences).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, content).map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        *current = preferences;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_personality_preset_persists_and_is_restored() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let store = PreferenceStore::load(dir, "cooper").unwrap();
+        assert_eq!(store.get().await, UserPreferences::default());
+
+        let preferences = UserPreferences { personality_preset: Some("Professional".to_string()), ..Default::default() };
+        store.set(preferences.clone()).await.unwrap();
+        assert!(store.path().exists());
+
+        let reloaded = PreferenceStore::load(dir, "cooper").unwrap();
+        assert_eq!(reloaded.get().await, preferences);
+        assert_eq!(PreferenceStore::load(dir, "brand").unwrap().get().await, UserPreferences::default());
+
+        let mut cfg = Config::default();
+        let change = reloaded.get().await.apply(&mut cfg).unwrap();
+        assert!(matches!(change, Some(ConfigChange::PersonalityPreset { name, .. }) if name == "Professional"));
+        assert_eq!(cfg.personality.sarcasm, 0.05);
+    }
+}
@@ -0,0 +1,245 @@
+//! Diagnostic bundles for bug reports: logs, redacted config, telemetry and
+//! system state gathered into one zip. Anything that cannot be collected is
+//! listed in `MISSING.txt` instead of failing the export.
+
+use crate::config::config::Config;
+use crate::health::GIT_SHA;
+use crate::logging::utc_timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use zip::write::FileOptions;
+
+/// Replacement for secret values in the exported config
+pub const REDACTED: &str = "[REDACTED]";
+
+pub const LOG_TAIL_LINES: usize = 1000;
+pub const AUDIT_TAIL_LINES: usize = 200;
+pub const TELEMETRY_FRAMES: usize = 200;
+
+/// Key segments that mark a config value (or a whole table) as secret
+const SECRET_KEY_PARTS: &[&str] = &[
+    "token", "tokens", "secret", "secrets", "password", "passwd", "key", "keys", "apikey", "credential",
+    "credentials", "auth",
+];
+
+/// Value prefixes of well-known token formats, redacted wherever they appear
+const SECRET_VALUE_PREFIXES: &[&str] = &["ghp_", "gho_", "ghs_", "github_pat_", "sk-", "xoxb-", "xoxp-", "hf_"];
+
+/// Files and notes for one diagnostic bundle
+#[derive(Debug, Default)]
+pub struct DiagnosticBundle {
+    entries: Vec<(String, Vec<u8>)>,
+    missing: Vec<String>,
+}
+
+/// What was written, for the command response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSummary {
+    pub path: String,
+    pub entries: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    created: String,
+    version: &'a str,
+    git_sha: &'a str,
+    entries: Vec<&'a str>,
+    missing: &'a [String],
+}
+
+impl DiagnosticBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, contents: impl Into<Vec<u8>>) {
+        self.entries.push((name.to_string(), contents.into()));
+    }
+
+    /// Add the entry, or record why it could not be collected
+    pub fn add_result<T: Into<Vec<u8>>>(&mut self, name: &str, contents: Result<T, String>) {
+        match contents {
+            Ok(contents) => self.add(name, contents),
+            Err(reason) => self.note_missing(name, &reason),
+        }
+    }
+
+    pub fn note_missing(&mut self, name: &str, reason: &str) {
+        self.missing.push(format!("{}: {}", name, reason));
+    }
+
+    /// Write the bundle, a `manifest.json` and (if anything failed)
+    /// `MISSING.txt` to a zip at `path`
+    pub fn write_zip(self, path: &Path) -> Result<BundleSummary, String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        let manifest = Manifest {
+            created: utc_timestamp(SystemTime::now()),
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: GIT_SHA,
+            entries: self.entries.iter().map(|(name, _)| name.as_str()).collect(),
+            missing: &self.missing,
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+
+        let mut files: Vec<(&str, &[u8])> = vec![("manifest.json", &manifest)];
+        files.extend(self.entries.iter().map(|(name, contents)| (name.as_str(), contents.as_slice())));
+        let missing = self.missing.join("\n") + "\n";
+        if !self.missing.is_empty() {
+            files.push(("MISSING.txt", missing.as_bytes()));
+        }
+
+        for (name, contents) in &files {
+            zip.start_file(*name, options).map_err(|e| format!("Cannot add {}: {}", name, e))?;
+            zip.write_all(contents).map_err(|e| format!("Cannot write {}: {}", name, e))?;
+        }
+        zip.finish().map_err(|e| format!("Cannot finish {}: {}", path.display(), e))?;
+
+        Ok(BundleSummary {
+            path: path.display().to_string(),
+            entries: files.iter().map(|(name, _)| name.to_string()).collect(),
+            missing: self.missing,
+        })
+    }
+}
+
+/// The config as TOML with API keys, tokens and credentials replaced by
+/// `[REDACTED]`. Secrets are recognized by key name and by token format.
+pub fn redacted_config(config: &Config) -> Result<String, String> {
+    let mut value = toml::Value::try_from(config).map_err(|e| format!("Cannot serialize config: {}", e))?;
+    redact(&mut value, false);
+    toml::to_string_pretty(&value).map_err(|e| format!("Cannot serialize config: {}", e))
+}
+
+/// Every non-empty string value `redacted_config` would hide
+pub fn config_secrets(config: &Config) -> Vec<String> {
+    fn collect(value: &toml::Value, secret: bool, out: &mut Vec<String>) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, child) in table {
+                    collect(child, secret || is_secret_key(key), out);
+                }
+            }
+            toml::Value::Array(items) => items.iter().for_each(|item| collect(item, secret, out)),
+            toml::Value::String(s) if !s.is_empty() && (secret || is_secret_value(s)) => out.push(s.clone()),
+            _ => {}
+        }
+    }
+    let mut secrets = Vec::new();
+    if let Ok(value) = toml::Value::try_from(config) {
+        collect(&value, false, &mut secrets);
+    }
+    secrets
+}
+
+/// True for values in a well-known token format, whatever key holds them
+pub fn is_secret_value(value: &str) -> bool {
+    SECRET_VALUE_PREFIXES.iter().any(|prefix| value.starts_with(prefix) && value.len() > prefix.len())
+}
+
+fn is_secret_key(key: &str) -> bool {
+    key.to_lowercase().split(['_', '-', '.']).any(|part| SECRET_KEY_PARTS.contains(&part))
+}
+
+fn redact(value: &mut toml::Value, secret: bool) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, child) in table.iter_mut() {
+                redact(child, secret || is_secret_key(key));
+            }
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(|item| redact(item, secret)),
+        toml::Value::String(s) if secret || SECRET_VALUE_PREFIXES.iter().any(|prefix| s.starts_with(prefix)) => {
+            *s = REDACTED.to_string();
+        }
+        toml::Value::String(_) => {}
+        _ if secret => *value = toml::Value::String(REDACTED.to_string()),
+        _ => {}
+    }
+}
+
+/// Pretty JSON for a bundle entry
+pub fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| format!("Cannot serialize: {}", e))
+}
+
+/// Last `lines` lines of a file, read in one pass without loading it whole
+pub fn tail_file(path: &Path, lines: usize) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut tail = VecDeque::with_capacity(lines);
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        if tail.len() == lines {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+    Ok(tail.into_iter().map(|line| line + "\n").collect())
+}
+
+/// Most recently modified file in `dir` whose name starts with `prefix`
+pub fn latest_file(dir: &Path, prefix: &str) -> Result<PathBuf, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+        .ok_or_else(|| format!("No {}* files in {}", prefix, dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_bundle_contains_entries_and_redacts_token() {
+        let mut config = Config::default();
+        config.api_keys.openai = Some("sk-live-0123456789abcdef".to_string());
+        config.ai.preferred_model = "ghp_notreallyamodel".to_string();
+
+        let mut bundle = DiagnosticBundle::new();
+        bundle.add_result("config.toml", redacted_config(&config));
+        bundle.add("telemetry.txt", "servo 0.5\n");
+        bundle.add_result::<String>("audit.log", Err("No audit_* files in audit_logs".to_string()));
+
+        let scratch = tempfile::tempdir().unwrap();
+        let path = scratch.path().join("bundle.zip");
+        let summary = bundle.write_zip(&path).unwrap();
+        assert_eq!(summary.entries, vec!["manifest.json", "config.toml", "telemetry.txt", "MISSING.txt"]);
+        assert_eq!(summary.missing.len(), 1);
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let read = |archive: &mut zip::ZipArchive<File>, name: &str| {
+            let mut contents = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut contents).unwrap();
+            contents
+        };
+
+        let config_copy = read(&mut archive, "config.toml");
+        assert!(!config_copy.contains("sk-live-0123456789abcdef"));
+        assert!(!config_copy.contains("ghp_notreallyamodel"));
+        assert!(config_copy.contains(REDACTED));
+        assert!(config_copy.contains("[personality]"));
+        assert!(read(&mut archive, "MISSING.txt").starts_with("audit.log:"));
+        assert!(read(&mut archive, "manifest.json").contains("\"config.toml\""));
+    }
+
+    #[test]
+    fn test_tail_file_keeps_last_lines() {
+        let scratch = tempfile::tempdir().unwrap();
+        let path = scratch.path().join("tail.log");
+        std::fs::write(&path, (1..=10).map(|i| format!("line {}\n", i)).collect::<String>()).unwrap();
+        assert_eq!(tail_file(&path, 3).unwrap(), "line 8\nline 9\nline 10\n");
+    }
+}
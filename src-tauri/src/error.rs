@@ -0,0 +1,36 @@
+//! Structured errors for the frontend. Each major module has its own
+//! `thiserror` enum implementing `TarsError`; Tauri commands return them as
+//! an `ErrorPayload`, so the frontend can branch on a stable code instead of
+//! matching on message text.
+
+use serde::{Deserialize, Serialize};
+
+/// Error as sent to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorPayload {
+    /// Stable snake_case identifier, e.g. "document_not_found"
+    pub code: String,
+    pub message: String,
+    /// The variant's context, e.g. `{"id": "..."}` for a missing document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// A module error that can be reported to the frontend
+pub trait TarsError: std::error::Error {
+    fn code(&self) -> &'static str;
+
+    fn details(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    fn payload(&self) -> ErrorPayload {
+        ErrorPayload { code: self.code().to_string(), message: self.to_string(), details: self.details() }
+    }
+}
+
+impl<E: TarsError> From<E> for ErrorPayload {
+    fn from(error: E) -> Self {
+        error.payload()
+    }
+}
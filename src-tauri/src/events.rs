@@ -0,0 +1,86 @@
+//! Central event bus. Subsystems publish `TarsEvent`s here and react to
+//! each other's events without holding references to one another.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::config::config::ConfigChange;
+use crate::robotics::MovementCommand;
+
+/// Events that may lag behind before slow subscribers start missing them
+pub const EVENT_BUS_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TarsEvent {
+    ConfigChanged { change: ConfigChange },
+    /// The emergency stop latched
+    EmergencyStop { reason: String },
+    ControlledStop { reason: String },
+    MovementStarted { command: MovementCommand },
+    MovementFinished { command: MovementCommand, success: bool },
+    /// A line was spoken with this emotion
+    EmotionChanged { emotion: String, intensity: f32 },
+}
+
+pub struct EventBus {
+    tx: broadcast::Sender<TarsEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        Self { tx: broadcast::channel(capacity).0 }
+    }
+
+    /// Send `event` to every subscriber. Returns how many received it.
+    pub fn publish(&self, event: TarsEvent) -> usize {
+        self.tx.send(event).unwrap_or(0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TarsEvent> {
+        self.tx.subscribe()
+    }
+}
+
+static EVENT_BUS: Lazy<EventBus> = Lazy::new(|| EventBus::new(EVENT_BUS_CAPACITY));
+
+/// The process-wide bus
+pub fn event_bus() -> &'static EventBus {
+    &EVENT_BUS
+}
+
+pub fn publish(event: TarsEvent) -> usize {
+    EVENT_BUS.publish(event)
+}
+
+pub fn subscribe_events() -> broadcast::Receiver<TarsEvent> {
+    EVENT_BUS.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safety::Safety;
+
+    #[tokio::test]
+    async fn test_emergency_stop_reaches_every_subscriber() {
+        let bus = EventBus::new(8);
+        let (mut voice, mut telemetry, mut movement) = (bus.subscribe(), bus.subscribe(), bus.subscribe());
+
+        assert_eq!(bus.publish(TarsEvent::EmergencyStop { reason: "cliff ahead".to_string() }), 3);
+        for subscriber in [&mut voice, &mut telemetry, &mut movement] {
+            assert!(matches!(subscriber.try_recv(), Ok(TarsEvent::EmergencyStop { reason }) if reason == "cliff ahead"));
+        }
+
+        // Safety publishes on the process-wide bus once, when the stop latches
+        let mut events = subscribe_events();
+        let safety = Safety::new();
+        safety.trigger_emergency("event bus test").await;
+        safety.trigger_emergency("event bus test").await;
+        let stops = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|event| matches!(event, TarsEvent::EmergencyStop { reason } if reason == "event bus test"))
+            .count();
+        assert_eq!(stops, 1);
+    }
+}
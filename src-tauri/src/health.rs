@@ -0,0 +1,188 @@
+//! Subsystem health: each subsystem registers a probe at startup and
+//! `health_check` runs them all concurrently, each under its own timeout.
+
+use futures_util::future::{join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a probe may take before its subsystem is reported Degraded.
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Git commit the binary was built from, if the build recorded one.
+pub const GIT_SHA: &str = match option_env!("GIT_SHA") {
+    Some(sha) => sha,
+    None => "unknown",
+};
+
+pub type SharedHealth = Arc<HealthMonitor>;
+
+type Probe = Box<dyn Fn() -> BoxFuture<'static, Result<(), ProbeFailure>> + Send + Sync>;
+
+/// Ordered from healthy to unhealthy so the worst status is the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum HealthStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+/// Why a probe did not report Up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeFailure {
+    Degraded(String),
+    Down(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    /// Most recent failure, kept after the subsystem recovers
+    pub last_error: Option<String>,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub version: String,
+    pub git_sha: String,
+    pub subsystems: Vec<SubsystemHealth>,
+}
+
+pub struct HealthMonitor {
+    probes: Mutex<Vec<(String, Arc<Probe>)>>,
+    last_errors: Mutex<HashMap<String, String>>,
+    timeout: Duration,
+}
+
+impl HealthMonitor {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            probes: Mutex::new(Vec::new()),
+            last_errors: Mutex::new(HashMap::new()),
+            timeout,
+        }
+    }
+
+    /// Register a probe for a subsystem. Returning `Ok` reports it Up.
+    pub fn register<F, Fut>(&self, name: &str, probe: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), ProbeFailure>> + Send + 'static,
+    {
+        let probe: Probe = Box::new(move || Box::pin(probe()));
+        self.probes.lock().unwrap().push((name.to_string(), Arc::new(probe)));
+    }
+
+    /// Run every probe concurrently. A probe that exceeds the timeout is
+    /// abandoned and its subsystem reported Degraded; a panicking probe
+    /// reports Down.
+    pub async fn check(&self) -> HealthReport {
+        let probes: Vec<_> = self.probes.lock().unwrap().clone();
+        let timeout = self.timeout;
+
+        let results = join_all(probes.into_iter().map(|(name, probe)| async move {
+            let started = Instant::now();
+            let mut task = tokio::spawn(probe());
+            let outcome = match tokio::time::timeout(timeout, &mut task).await {
+                Ok(Ok(outcome)) => outcome,
+                Ok(Err(e)) => Err(ProbeFailure::Down(format!("probe failed: {}", e))),
+                Err(_) => {
+                    task.abort();
+                    Err(ProbeFailure::Degraded(format!("no response within {}ms", timeout.as_millis())))
+                }
+            };
+            (name, outcome, started.elapsed())
+        }))
+        .await;
+
+        let mut last_errors = self.last_errors.lock().unwrap();
+        let subsystems: Vec<SubsystemHealth> = results
+            .into_iter()
+            .map(|(name, outcome, elapsed)| {
+                let status = match outcome {
+                    Ok(()) => HealthStatus::Up,
+                    Err(ProbeFailure::Degraded(error)) => {
+                        last_errors.insert(name.clone(), error);
+                        HealthStatus::Degraded
+                    }
+                    Err(ProbeFailure::Down(error)) => {
+                        last_errors.insert(name.clone(), error);
+                        HealthStatus::Down
+                    }
+                };
+                SubsystemHealth {
+                    last_error: last_errors.get(&name).cloned(),
+                    name,
+                    status,
+                    latency_ms: elapsed.as_millis() as u64,
+                }
+            })
+            .collect();
+
+        HealthReport {
+            status: subsystems.iter().map(|s| s.status).max().unwrap_or(HealthStatus::Up),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: GIT_SHA.to_string(),
+            subsystems,
+        }
+    }
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROBE_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_of<'a>(report: &'a HealthReport, name: &str) -> &'a SubsystemHealth {
+        report.subsystems.iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_down_servo_is_reported_while_others_are_up() {
+        let monitor = HealthMonitor::default();
+        for name in ["voice", "math engine", "telemetry", "ai model"] {
+            monitor.register(name, || async { Ok(()) });
+        }
+        monitor.register("servo", || async { Err(ProbeFailure::Down("I2C bus not responding".into())) });
+
+        let report = monitor.check().await;
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+
+        let servo = status_of(&report, "servo");
+        assert_eq!(servo.status, HealthStatus::Down);
+        assert_eq!(servo.last_error.as_deref(), Some("I2C bus not responding"));
+        for name in ["voice", "math engine", "telemetry", "ai model"] {
+            let subsystem = status_of(&report, name);
+            assert_eq!(subsystem.status, HealthStatus::Up, "{}", name);
+            assert!(subsystem.last_error.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_probe_is_degraded_without_blocking_the_report() {
+        let monitor = HealthMonitor::new(Duration::from_millis(50));
+        monitor.register("ai model", || async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        });
+        monitor.register("servo", || async { Ok(()) });
+
+        let started = Instant::now();
+        let report = monitor.check().await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(status_of(&report, "ai model").status, HealthStatus::Degraded);
+        assert_eq!(status_of(&report, "servo").status, HealthStatus::Up);
+        assert_eq!(report.status, HealthStatus::Degraded);
+    }
+}
@@ -1,11 +1,23 @@
 pub mod ai;
+pub mod approval;
+pub mod backend;
+pub mod cli;
 pub mod code_analysis;
 pub mod commands;
 pub mod config;
+pub mod diagnostics;
+pub mod error;
+pub mod events;
+pub mod health;
+pub mod logging;
 pub mod mathematics;
+pub mod pdf_manager;
 pub mod personality;
 pub mod remote;
 pub mod robotics;
 pub mod safety;
+pub mod scripting;
+pub mod shutdown;
 pub mod voice;
+pub mod vscode;
 pub mod raspberry_pi;
@@ -0,0 +1,329 @@
+//! Logger with per-module levels, timestamped lines and rotating log files.
+//!
+//! Levels come from the `[logging]` config section and are re-applied by the
+//! config hot reload, so changing a module's level takes effect immediately.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::config::LoggingConfig;
+
+static LOGGER: Lazy<TarsLogger> = Lazy::new(TarsLogger::new);
+
+/// Install the global logger.
+pub fn init(config: &LoggingConfig) -> Result<(), String> {
+    LOGGER.configure(config);
+    log::set_logger(&*LOGGER).map_err(|e| e.to_string())?;
+    log::set_max_level(LOGGER.max_level());
+    Ok(())
+}
+
+/// Apply new levels (and log file settings) to the running logger.
+pub fn update(config: &LoggingConfig) {
+    LOGGER.configure(config);
+    log::set_max_level(LOGGER.max_level());
+}
+
+/// Default level plus per-module overrides; the longest matching module
+/// path wins, so `robotics::telemetry` can differ from `robotics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelFilters {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LevelFilters {
+    pub fn from_config(config: &LoggingConfig) -> Self {
+        let default = parse_level(&config.level).unwrap_or(LevelFilter::Info);
+        let modules = config
+            .modules
+            .iter()
+            .filter_map(|(module, level)| Some((module.clone(), parse_level(level)?)))
+            .collect();
+        Self { default, modules }
+    }
+
+    /// Level for a log target such as `gsteng::robotics::telemetry`.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let module = module_path_of(target);
+        self.modules
+            .iter()
+            .filter(|(prefix, _)| {
+                module == prefix.as_str()
+                    || (module.starts_with(prefix.as_str()) && module[prefix.len()..].starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+}
+
+fn parse_level(level: &str) -> Option<LevelFilter> {
+    let parsed = LevelFilter::from_str(level.trim()).ok();
+    if parsed.is_none() {
+        eprintln!("Ignoring unknown log level '{}'", level);
+    }
+    parsed
+}
+
+/// Strip this crate's name so config keys read `robotics`, not `gsteng::robotics`.
+fn module_path_of(target: &str) -> &str {
+    let crate_name = env!("CARGO_CRATE_NAME");
+    match target.strip_prefix(crate_name) {
+        Some("") => target,
+        Some(rest) => rest.strip_prefix("::").unwrap_or(target),
+        None => target,
+    }
+}
+
+/// Log file that is renamed to `<file>.1`, `<file>.2`, ... once it grows
+/// past `max_bytes`, keeping at most `max_files` files in total.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes: max_bytes.max(1),
+            max_files: max_files.max(1),
+            file,
+            size,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.max_files > 1 {
+            for index in (1..self.max_files - 1).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+pub struct TarsLogger {
+    filters: RwLock<LevelFilters>,
+    file: Mutex<Option<RotatingFile>>,
+    file_settings: Mutex<Option<(PathBuf, u64, usize)>>,
+}
+
+impl TarsLogger {
+    pub fn new() -> Self {
+        Self {
+            filters: RwLock::new(LevelFilters::from_config(&LoggingConfig::default())),
+            file: Mutex::new(None),
+            file_settings: Mutex::new(None),
+        }
+    }
+
+    pub fn configure(&self, config: &LoggingConfig) {
+        *self.filters.write().unwrap() = LevelFilters::from_config(config);
+
+        let settings = config
+            .file
+            .clone()
+            .map(|path| (path, config.max_file_size_bytes, config.max_files));
+        let mut current = self.file_settings.lock().unwrap();
+        if *current == settings {
+            return;
+        }
+        *self.file.lock().unwrap() = settings.as_ref().and_then(|(path, max_bytes, max_files)| {
+            RotatingFile::open(path, *max_bytes, *max_files)
+                .map_err(|e| eprintln!("Failed to open log file {:?}: {}", path, e))
+                .ok()
+        });
+        *current = settings;
+    }
+
+    pub fn max_level(&self) -> LevelFilter {
+        self.filters.read().unwrap().max_level()
+    }
+}
+
+impl Default for TarsLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Log for TarsLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filters.read().unwrap().level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_line(record);
+        eprintln!("{}", line);
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// `2026-01-31T12:00:00.000Z INFO  [robotics::telemetry] message`
+fn format_line(record: &Record) -> String {
+    format!(
+        "{} {:<5} [{}] {}",
+        utc_timestamp(SystemTime::now()),
+        record.level(),
+        module_path_of(record.target()),
+        record.args()
+    )
+}
+
+pub(crate) fn utc_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // civil-from-days (Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn metadata(level: Level, target: &str) -> Metadata<'_> {
+        Metadata::builder().level(level).target(target).build()
+    }
+
+    fn target(module: &str) -> String {
+        format!("{}::{}", env!("CARGO_CRATE_NAME"), module)
+    }
+
+    #[test]
+    fn test_per_module_levels_filter() {
+        let mut config = LoggingConfig { file: None, ..LoggingConfig::default() };
+        config.modules.insert("robotics".into(), "debug".into());
+        config.modules.insert("voice".into(), "warn".into());
+
+        let logger = TarsLogger::new();
+        logger.configure(&config);
+
+        let robotics = target("robotics::telemetry");
+        let voice = target("voice::text_to_speech");
+        assert!(logger.enabled(&metadata(Level::Debug, &robotics)));
+        assert!(!logger.enabled(&metadata(Level::Trace, &robotics)));
+        assert!(!logger.enabled(&metadata(Level::Info, &voice)));
+        assert!(logger.enabled(&metadata(Level::Warn, &voice)));
+        // unlisted modules use the default level
+        assert!(logger.enabled(&metadata(Level::Info, &target("mathematics"))));
+        assert!(!logger.enabled(&metadata(Level::Debug, &target("mathematics"))));
+        assert_eq!(logger.max_level(), LevelFilter::Debug);
+
+        // reconfiguring updates levels in place
+        config.modules.insert("voice".into(), "debug".into());
+        logger.configure(&config);
+        assert!(logger.enabled(&metadata(Level::Debug, &voice)));
+    }
+
+    #[test]
+    fn test_longest_module_prefix_wins() {
+        let mut config = LoggingConfig::default();
+        config.modules.insert("robotics".into(), "warn".into());
+        config.modules.insert("robotics::telemetry".into(), "trace".into());
+        let filters = LevelFilters::from_config(&config);
+
+        assert_eq!(filters.level_for(&target("robotics::telemetry")), LevelFilter::Trace);
+        assert_eq!(filters.level_for(&target("robotics::tars_movement")), LevelFilter::Warn);
+        assert_eq!(filters.level_for(&target("roboticsx")), LevelFilter::Info);
+    }
+
+    #[test]
+    fn test_log_file_rotates() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let path = dir.join("tars.log");
+
+        let mut file = RotatingFile::open(&path, 64, 3).unwrap();
+        for i in 0..10 {
+            file.write_line(&format!("line {:02} ..........................", i)).unwrap();
+        }
+
+        assert!(path.exists());
+        assert!(dir.join("tars.log.1").exists());
+        assert!(dir.join("tars.log.2").exists());
+        assert!(!dir.join("tars.log.3").exists());
+        assert!(fs::read_to_string(&path).unwrap().contains("line 09"));
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let time = UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_123);
+        assert_eq!(utc_timestamp(time), "2023-11-14T22:13:20.123Z");
+    }
+}
@@ -4,155 +4,455 @@
 )]
 
 mod ai;
+mod approval;
+mod backend;
 mod code_analysis;
 mod commands;
 mod config;
+mod diagnostics;
+mod error;
+mod events;
+mod health;
+mod logging;
 mod mathematics;
+mod pdf_manager;
 mod personality;
+mod raspberry_pi;
+mod remote;
 mod robotics;
 mod safety;
+mod scripting;
+mod shutdown;
 mod voice;
+mod vscode;
 
-use config::config::{start_hot_reload, Config, SharedConfig};
-use config::state_manager::StateManager;
-use robotics::telemetry::Telemetry;
-use safety::{start_watchdog, Safety};
-use std::path::PathBuf;
+use backend::Backend;
+use config::config::{start_hot_reload, subscribe_changes, Config, ConfigChange, SharedConfig, CONFIG_FILE};
+use config::user_preferences::{current_user, PreferenceStore, SharedPreferences, PREFERENCES_DIR};
+use safety::start_watchdog;
+use scripting::{ScriptLibrary, SharedScriptLibrary};
+use shutdown::{ShutdownCoordinator, SharedShutdown};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use log::info;
+use tauri::Manager;
+use voice::text_to_speech::{speak_with_request, SpeechContext, SpeechPriority, SpeechRequest};
 
 // Servo system imports
-use robotics::pca9685_controller::{PCA9685Controller, MockI2C};
-use robotics::{TARSMovementController, TARSGamepadController};
-use personality::tars_core::{TARSPersonality, PersonalitySettings};
+use robotics::servo_system::ServoController;
+use robotics::servo_config::{Calibration, CALIBRATION_FILE};
+use robotics::{hardware_probe, remote_control, GamepadConfig, SharedServoSystem, TARSGamepadController, ThermalGuard};
+use robotics::gamepad_controller::ScriptRunner;
+use robotics::power_monitor::{PowerMonitor, SysfsPowerSource};
+use robotics::safety_sensors::{IioRangeSensor, IioTiltSensor, SensorSafetyMonitor, IIO_DEVICES_DIR};
+use personality::tars_core::{PersonalitySettings, TARSPersonality};
+use raspberry_pi::{hardware_monitor::HardwareMonitor, RaspberryPiConfig};
+use safety::SharedSafety;
 
-// Mathematics engine imports
-use mathematics::MathematicsEngine;
-use commands::math_commands::MathEngineState;
+use commands::registry::CommandRegistry;
+
+/// Robot state saved on shutdown
+const STATE_SNAPSHOT_FILE: &str = "state_snapshot.json";
+
+/// User action scripts, merged over the built-in scripts
+const SCRIPTS_FILE: &str = "scripts.json";
 
 fn main() {
+    let config_path = PathBuf::from(CONFIG_FILE);
+    let mut cfg = Config::load(&config_path).expect("load config");
+    let mut logging_config = cfg.logging.clone();
     if std::env::var("DEBUG").is_ok() || cfg!(debug_assertions) {
-        env_logger::Builder::from_default_env()
-            .filter_level(log::LevelFilter::Debug)
-            .init();
-        info!("Debug logging enabled");
+        logging_config.level = "debug".to_string();
+    }
+    if let Err(e) = logging::init(&logging_config) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+    // User preferences override the system config without editing it
+    let user = current_user();
+    let preferences: SharedPreferences = Arc::new(match PreferenceStore::load(Path::new(PREFERENCES_DIR), &user) {
+        Ok(store) => store,
+        Err(e) => {
+            log::warn!("Starting with empty preferences for {}: {}", user, e);
+            PreferenceStore::new(Path::new(PREFERENCES_DIR), &user)
+        }
+    });
+    let user_preferences = tauri::async_runtime::block_on(preferences.get());
+    if let Err(e) = user_preferences.apply(&mut cfg) {
+        log::warn!("Failed to apply preferences for {}: {}", user, e);
     }
-    let config_path = PathBuf::from("config.toml");
-    let cfg = Config::load(&config_path).expect("load config");
+    let pose_library = backend::load_pose_library(&cfg.robotics.poses_file);
+    let script_library: SharedScriptLibrary = match ScriptLibrary::load(Path::new(SCRIPTS_FILE)) {
+        Ok(library) => Arc::new(tokio::sync::RwLock::new(library)),
+        Err(e) => {
+            log::warn!("Using built-in action scripts only: {}", e);
+            Arc::new(tokio::sync::RwLock::new(ScriptLibrary::builtin()))
+        }
+    };
+    let simulation = cfg.robotics.simulation;
+    let gamepad_config = cfg.robotics.gamepad.clone();
+    let bus_config = cfg.robotics.i2c.clone();
+    let kinematics = cfg.robotics.kinematics.clone();
+    let tick_rate = cfg.robotics.tick_rate.clone();
+    let thermal = cfg.robotics.thermal.clone();
+    let servo_profile = cfg.robotics.servo_profile.clone();
+    let servo_profiles = cfg.robotics.servo_profiles.clone();
+    let telemetry_history_seconds = cfg.robotics.telemetry_history_seconds;
+    let safety_config = cfg.safety.clone();
+    let use_cloud = cfg.ai.use_cloud;
+    tauri::async_runtime::block_on(backend::apply_ai_config(&cfg));
+    tauri::async_runtime::block_on(approval::audit::set_log_dir(&cfg.logging.audit_dir));
+    backend::init_locale(&cfg);
     let shared_cfg: SharedConfig = Arc::new(Mutex::new(cfg));
-    let _watcher = start_hot_reload(config_path, shared_cfg.clone()).expect("watch config");
-
-    let state_manager = StateManager::new();
-    let telemetry = Arc::new(Telemetry::new());
-    let safety = Safety::new();
-
-    // Initialize servo system (using mock controllers for now)
-    let servo_controller: Option<Arc<PCA9685Controller<MockI2C>>> = None;
-    let movement_controller: Option<Arc<TARSMovementController<PCA9685Controller<MockI2C>>>> = None;
-    let gamepad_controller: Option<Arc<TARSGamepadController<PCA9685Controller<MockI2C>>>> = None;
+    let watcher = start_hot_reload(config_path, shared_cfg.clone()).expect("watch config");
 
-    // Initialize mathematics engine
-    let math_engine = tauri::async_runtime::block_on(async {
-        let engine = MathematicsEngine::new().await;
-        Arc::new(tokio::sync::RwLock::new(MathEngineState { engine }))
-    });
+    let shutdown: SharedShutdown = Arc::new(ShutdownCoordinator::default());
 
-    tauri::async_runtime::spawn(async move {
-        if tokio::signal::ctrl_c().await.is_ok() {
-            info!("Shutdown signal received");
-            std::process::exit(0);
+    // Servo controllers are installed by the hardware probe below, or later
+    // by initialize_servo_system
+    let Backend { state_manager, telemetry, safety, health, servo_system, math_engine } =
+        tauri::async_runtime::block_on(Backend::new(simulation, pose_library.clone(), bus_config));
+    tauri::async_runtime::block_on(telemetry.set_history_seconds(telemetry_history_seconds));
+    tauri::async_runtime::block_on(voice::advanced_tts::set_quality_mode_override(user_preferences.voice_quality));
+    tauri::async_runtime::block_on(async {
+        let mut system = servo_system.write().await;
+        system.set_kinematics(kinematics);
+        system.set_tick_rate(tick_rate);
+        system.set_thermal(ThermalGuard::for_pi(thermal.clone(), &RaspberryPiConfig::default_for_model(&RaspberryPiConfig::detect_model())));
+        system.set_user_profiles(servo_profiles);
+        if let Err(e) = system.switch_profile(&servo_profile).await {
+            log::warn!("Keeping the default servo profile: {}", e);
+        }
+        match Calibration::load(Path::new(CALIBRATION_FILE)) {
+            Ok(calibration) => {
+                if let Err(e) = system.set_calibration(calibration).await {
+                    log::warn!("Failed to apply the servo calibration: {}", e);
+                }
+            },
+            Err(e) => log::warn!("Ignoring the saved servo calibration: {}", e),
         }
     });
 
+    // Probe for servo boards and a gamepad, falling back to simulation
+    let gamepad_connected = tauri::async_runtime::block_on(hardware_probe::detect_gamepad());
+    let hardware = tauri::async_runtime::block_on(hardware_probe::self_configure(
+        &servo_system,
+        hardware_probe::default_bus().as_ref(),
+        gamepad_connected,
+        simulation,
+    ));
+    info!("Startup hardware: {:?}", hardware);
+
+    // Halt movement when TARS tips over or closes on an obstacle, read from
+    // the kernel's IIO devices
+    let iio_devices = Path::new(IIO_DEVICES_DIR);
+    let tilt_sensor = IioTiltSensor::detect(iio_devices);
+    let range_sensor = IioRangeSensor::detect(iio_devices);
+    if tilt_sensor.is_none() && range_sensor.is_none() {
+        info!("No tilt or range sensor under {}, sensor safety monitoring is off", IIO_DEVICES_DIR);
+    } else {
+        let monitor = SensorSafetyMonitor::new(tilt_sensor, range_sensor, safety_config.clone(), safety.clone());
+        tauri::async_runtime::block_on(async { Arc::new(monitor).start() });
+    }
+
+    // Slow down and stop on a low battery, read from the kernel's power
+    // supply class
+    let power_source = match &safety_config.power_supply {
+        Some(supply) => Some(SysfsPowerSource::new(supply)),
+        None => SysfsPowerSource::detect(Path::new(SysfsPowerSource::POWER_SUPPLY_DIR)),
+    };
+    match power_source {
+        Some(source) => {
+            let monitor = PowerMonitor::new(source, safety_config, safety.clone(), servo_system.clone())
+                .with_telemetry(telemetry.clone());
+            tauri::async_runtime::block_on(async { Arc::new(monitor).start() });
+        },
+        None => info!("No battery under {}, power monitoring is off", SysfsPowerSource::POWER_SUPPLY_DIR),
+    }
+    let gamepad_controller = if hardware.gamepad {
+        let script_runner = commands::script_commands::gamepad_script_runner(script_library.clone(), servo_system.clone(), use_cloud);
+        tauri::async_runtime::block_on(start_gamepad(&servo_system, gamepad_config, safety.clone(), script_runner))
+    } else {
+        None
+    };
+
+    // Shutdown hooks: stop the config watcher, bring the servos to a
+    // controlled stop, flush telemetry recording and save robot state
+    shutdown.register("config watcher", move || async move { drop(watcher) });
+    {
+        let servo_system = servo_system.clone();
+        shutdown.register("movement controller", move || async move {
+            if let Some(movement) = servo_system.read().await.movement_controller() {
+                if let Err(e) = movement.emergency_stop_all().await {
+                    log::warn!("Servos did not stop cleanly at shutdown: {}", e);
+                }
+            }
+        });
+    }
+    {
+        let telemetry = telemetry.clone();
+        shutdown.register("telemetry", move || async move { telemetry.stop_recording().await });
+    }
+    {
+        let state_manager = state_manager.clone();
+        shutdown.register("state snapshot", move || async move {
+            if let Err(e) = state_manager.save_snapshot(Path::new(STATE_SNAPSHOT_FILE)).await {
+                log::warn!("Failed to save state snapshot: {}", e);
+            }
+        });
+    }
+
+    // Re-tune voice inflection and AI personality whenever config changes.
+    // A reload only carries the keys that changed on disk, so only those
+    // subsystems and dials are re-applied and runtime tweaks elsewhere stay.
+    // Preferences are laid back over the file's values for what they override.
+    {
+        let shared_cfg = shared_cfg.clone();
+        let preferences = preferences.clone();
+        let mut changes = subscribe_changes();
+        tauri::async_runtime::spawn(async move {
+            while let Ok(change) = changes.recv().await {
+                info!("Config changed: {:?}", change);
+                let cfg = {
+                    let mut cfg = shared_cfg.lock().await;
+                    let overridden = change.touches("personality") || change.touches("audio.output_device");
+                    if matches!(change, ConfigChange::Reloaded { .. }) && overridden {
+                        if let Err(e) = preferences.get().await.apply(&mut cfg) {
+                            log::warn!("Failed to re-apply user preferences: {}", e);
+                        }
+                    }
+                    cfg.clone()
+                };
+                ai::guard::set_secrets(backend::guarded_secrets(&cfg)).await;
+                if change.touches("ai") {
+                    ai::routing::set_policy(cfg.ai.routing.clone()).await;
+                    ai::guard::set_policy(cfg.ai.guard.clone()).await;
+                    ai::inference_queue::set_config(cfg.ai.queue.clone());
+                    ai::confidence::set_policy(cfg.ai.confidence.clone()).await;
+                }
+                if change.touches("logging.audit_dir") {
+                    approval::audit::set_log_dir(&cfg.logging.audit_dir).await;
+                }
+
+                let dials = &cfg.personality;
+                if change.touches("personality.humor") || change.touches("personality.sarcasm") {
+                    voice::retune_personality(dials.humor, dials.sarcasm).await;
+                }
+                if change.touches("personality.honesty_threshold") {
+                    ai::confidence::set_honesty_threshold(dials.honesty_threshold).await;
+                }
+                if change.touches("personality.locale") {
+                    backend::apply_locale(&dials.locale);
+                }
+                let dial = |name: &str, value: f32| change.touches(&format!("personality.{}", name)).then_some(value);
+                if let Err(e) = personality::tars_core::TARSCore::adjust_personality(
+                    dial("humor", dials.humor),
+                    dial("honesty", dials.honesty),
+                    dial("sarcasm", dials.sarcasm),
+                    dial("verbosity", dials.verbosity),
+                )
+                .await
+                {
+                    log::warn!("Failed to re-tune personality: {}", e);
+                }
+            }
+        });
+    }
+
+    {
+        let shutdown = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                info!("Shutdown signal received");
+                shutdown.shutdown().await;
+                std::process::exit(0);
+            }
+        });
+    }
+
+    let mut command_registry = CommandRegistry::new();
+    commands::register_commands(&mut command_registry);
+
+    let exit_shutdown = shutdown.clone();
     tauri::Builder::default()
         .manage(shared_cfg)
+        .manage(preferences)
         .manage(state_manager)
         .manage(telemetry.clone())
         .manage(safety.clone())
-        .manage(servo_controller)
-        .manage(movement_controller)
+        .manage(servo_system.clone())
         .manage(gamepad_controller)
         .manage(math_engine)
-        .invoke_handler(tauri::generate_handler![
-            commands::ask_ai,
-            commands::set_personality,
-            commands::start_listening,
-            commands::stop_listening,
-            commands::move_robot,
-            commands::get_telemetry,
-            commands::emergency_stop,
-            commands::health_check,
-            // TARS-Enhanced Commands
-            commands::ask_tars,
-            commands::conduct_code_review,
-            commands::get_coding_standards,
-            commands::get_tech_stack_recommendations,
-            commands::adjust_tars_personality,
-            commands::get_tars_status,
-            commands::download_llm_model,
-            commands::switch_llm_model,
-            commands::list_available_models,
-            // Servo Control Commands
-            commands::execute_movement_command,
-            commands::get_movement_status,
-            commands::set_movement_enabled,
-            commands::is_movement_enabled,
-            commands::get_available_poses,
-            commands::calibrate_servos,
-            commands::get_gamepad_status,
-            commands::is_gamepad_connected,
-            commands::get_available_gamepads,
-            commands::initialize_servo_system,
-            commands::get_servo_config,
-            commands::get_predefined_poses,
-            commands::set_servo_position,
-            commands::test_servo_movement,
-            commands::emergency_stop_all,
-            // Mathematics Commands
-            commands::analyze_algorithm_complexity,
-            commands::solve_mathematical_expression,
-            commands::linear_algebra_operation,
-            commands::statistical_analysis,
-            commands::numerical_computation,
-            commands::generate_mathematical_proof,
-            commands::explain_mathematical_concept,
-            commands::optimize_algorithm,
-            commands::verify_algorithm_correctness,
-            commands::get_tars_mathematical_analysis,
-            commands::get_mathematical_constants,
-            commands::validate_mathematical_expression,
-            // Pattern Analysis Commands
-            commands::analyze_design_patterns,
-            commands::detect_specific_pattern,
-            commands::get_pattern_suggestions,
-            commands::get_pattern_documentation,
-            commands::analyze_architecture_quality,
-            // Test Generation Commands
-            commands::generate_test_suite,
-            commands::generate_specific_tests,
-            commands::get_test_recommendations,
-            commands::validate_test_code,
-            commands::get_testing_best_practices,
-            commands::calculate_test_metrics,
-        ])
-        .setup(move |_| {
+        .manage(pose_library)
+        .manage(health)
+        .manage(script_library)
+        .invoke_handler(command_registry.into_invoke_handler())
+        .setup(move |app| {
             start_watchdog(safety.clone());
+
+            // Light the frontend's cue light whenever TARS is joking
+            let app_handle = app.handle();
+            let mut joking = voice::cue_light::subscribe_joking();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match joking.recv().await {
+                        Ok(event) => {
+                            if let Err(e) = app_handle.emit_all("tars://joking", event) {
+                                log::warn!("Failed to emit joking event: {}", e);
+                            }
+                        },
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            // Emergency stops: announce, record in telemetry and halt movement
+            let mut events = events::subscribe_events();
+            let stop_telemetry = telemetry.clone();
+            let stop_servo_system = servo_system.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(events::TarsEvent::EmergencyStop { reason }) => {
+                            if let Some(movement) = stop_servo_system.read().await.movement_controller() {
+                                if let Err(e) = movement.emergency_stop_all().await {
+                                    log::warn!("Emergency stop failed to halt the servos: {}", e);
+                                }
+                            }
+                            stop_telemetry.broadcast_critical("emergency_stop".into()).await;
+                            let request = SpeechRequest {
+                                text: format!("Emergency stop. {}.", reason),
+                                priority: SpeechPriority::Critical,
+                                context: SpeechContext::SystemStatus,
+                                emotional_state: None,
+                                override_settings: None,
+                            };
+                            if let Err(e) = speak_with_request(request).await {
+                                log::warn!("Failed to announce emergency stop: {}", e);
+                            }
+                        },
+                        Ok(_) => {},
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            // Surface servo channel faults to every telemetry client
+            let mut servo_faults = robotics::pca9685_controller::subscribe_servo_faults();
+            let fault_telemetry = telemetry.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match servo_faults.recv().await {
+                        Ok(fault) => {
+                            let frame = serde_json::json!({ "servo_fault": fault }).to_string();
+                            fault_telemetry.broadcast_critical(frame).await;
+                        },
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            // Say battery warnings out loud
+            let mut power_warnings = robotics::power_monitor::subscribe_power_warnings();
             tauri::async_runtime::spawn(async move {
-                let _ = telemetry.start_server("127.0.0.1:9000").await;
+                loop {
+                    match power_warnings.recv().await {
+                        Ok(warning) => {
+                            let request = SpeechRequest {
+                                text: warning.message,
+                                priority: SpeechPriority::Critical,
+                                context: SpeechContext::SystemStatus,
+                                emotional_state: None,
+                                override_settings: None,
+                            };
+                            if let Err(e) = speak_with_request(request).await {
+                                log::warn!("Failed to speak power warning: {}", e);
+                            }
+                        },
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
             });
-            
-            // Initialize servo system on startup
+
+            // Slow or halt movement while the Pi runs hot
+            let thermal_servo_system = servo_system.clone();
+            let thermal_interval = std::time::Duration::from_millis(thermal.poll_interval_ms.max(100));
+            tauri::async_runtime::spawn(async move {
+                let monitor = HardwareMonitor::new();
+                loop {
+                    let metrics = monitor.collect_system_metrics().await;
+                    if let Some(movement) = thermal_servo_system.read().await.movement_controller() {
+                        movement.update_thermal(&metrics).await;
+                    }
+                    tokio::time::sleep(thermal_interval).await;
+                }
+            });
+
+            let mut shutdown_rx = shutdown.subscribe();
+            tauri::async_runtime::spawn(async move {
+                tokio::select! {
+                    _ = telemetry.start_server("127.0.0.1:9000") => {}
+                    _ = shutdown_rx.recv() => info!("Telemetry server stopped"),
+                }
+            });
+
+            // JSON-RPC movement control for remote clients, on the same controller
+            let remote = Arc::new(
+                remote_control::RemoteControl::new(servo_system.clone(), safety.clone())
+                    .with_permissions(commands::registry::required_permission),
+            );
+            let mut shutdown_rx = shutdown.subscribe();
             tauri::async_runtime::spawn(async move {
-                info!("Initializing TARS servo system...");
-                // For now, we'll leave controllers uninitialized
-                // They can be initialized via the initialize_servo_system command
-                // In production, this would check for hardware and initialize accordingly
-                info!("TARS servo system ready for initialization");
+                tokio::select! {
+                    result = remote.start_server(remote_control::REMOTE_CONTROL_ADDR) => {
+                        if let Err(e) = result {
+                            log::warn!("Remote control server failed: {}", e);
+                        }
+                    }
+                    _ = shutdown_rx.recv() => info!("Remote control server stopped"),
+                }
             });
-            
+
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_, event| {
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(exit_shutdown.shutdown());
+            }
+        });
+}
+
+/// Start a gamepad controller driving the installed servo controller
+async fn start_gamepad(
+    servo_system: &SharedServoSystem,
+    config: GamepadConfig,
+    safety: SharedSafety,
+    script_runner: ScriptRunner,
+) -> Option<Arc<TARSGamepadController<ServoController>>> {
+    let servo_controller = servo_system.read().await.servo_controller()?;
+    let movement = robotics::TARSMovementController::from_shared(
+        servo_controller,
+        TARSPersonality::new(PersonalitySettings::default()),
+    )
+    .with_audit(true)
+    .with_safety(safety.clone());
+    let gamepad = match TARSGamepadController::new(movement, Some(config)) {
+        Ok(gamepad) => gamepad.with_safety(safety).with_script_runner(script_runner),
+        Err(e) => {
+            log::warn!("Gamepad detected but could not be opened: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = gamepad.start().await {
+        log::warn!("Failed to start gamepad controller: {}", e);
+        return None;
+    }
+    info!("Gamepad controller started");
+    Some(Arc::new(gamepad))
 }
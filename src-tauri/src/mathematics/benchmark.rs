@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Upper bound on measured iterations per benchmark run
+pub const MAX_BENCHMARK_ITERATIONS: usize = 100_000;
+
+/// Timing percentiles and throughput for one benchmarked operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub operation: String,
+    pub iterations: usize,
+    pub warmup_iterations: usize,
+    pub p50_us: f64,
+    pub p95_us: f64,
+    pub p99_us: f64,
+    pub mean_us: f64,
+    pub throughput_per_sec: f64,
+}
+
+impl BenchmarkResult {
+    /// One-line summary for TARS commentary
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: p50 {:.1}µs, p95 {:.1}µs, p99 {:.1}µs, {:.0} ops/s over {} iterations",
+            self.operation, self.p50_us, self.p95_us, self.p99_us, self.throughput_per_sec, self.iterations
+        )
+    }
+}
+
+/// Run `op` repeatedly and time each call. A warmup of 10% of the
+/// iterations (at least one) runs first and is not measured; results pass
+/// through `black_box` so the work cannot be optimized away.
+pub async fn run<F, Fut, T>(operation: &str, iterations: usize, mut op: F) -> Result<BenchmarkResult, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    if iterations == 0 {
+        return Err("Benchmark requires at least one iteration".to_string());
+    }
+    let iterations = iterations.min(MAX_BENCHMARK_ITERATIONS);
+    let warmup_iterations = (iterations / 10).max(1);
+
+    for _ in 0..warmup_iterations {
+        black_box(op().await);
+    }
+
+    let mut samples = Vec::with_capacity(iterations);
+    let started = Instant::now();
+    for _ in 0..iterations {
+        let start = Instant::now();
+        black_box(op().await);
+        samples.push(start.elapsed());
+    }
+    let total = started.elapsed();
+
+    let mut micros: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1e6).collect();
+    micros.sort_by(|a, b| a.total_cmp(b));
+
+    Ok(BenchmarkResult {
+        operation: operation.to_string(),
+        iterations,
+        warmup_iterations,
+        p50_us: percentile(&micros, 50.0),
+        p95_us: percentile(&micros, 95.0),
+        p99_us: percentile(&micros, 99.0),
+        mean_us: micros.iter().sum::<f64>() / micros.len() as f64,
+        throughput_per_sec: iterations as f64 / total.max(Duration::from_nanos(1)).as_secs_f64(),
+    })
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_percentiles_are_monotonic() {
+        let result = run("add", 500, || async { black_box(2u64) + black_box(3u64) }).await.unwrap();
+
+        assert_eq!(result.iterations, 500);
+        assert_eq!(result.warmup_iterations, 50);
+        assert!(result.p50_us >= 0.0);
+        assert!(result.p50_us <= result.p95_us);
+        assert!(result.p95_us <= result.p99_us);
+        assert!(result.throughput_per_sec > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_iterations_is_rejected() {
+        assert!(run("add", 0, || async { 1 }).await.is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Inefficiency patterns that have a known semantics-preserving rewrite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RewritePattern {
+    VecContainsInLoop,
+    CloneInLoop,
+    PushWithoutCapacity,
+}
+
+/// A concrete rewrite of submitted code. `line` is 1-based; `original` and
+/// `rewritten` are drop-in replacements for each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeRewrite {
+    pub pattern: RewritePattern,
+    pub line: usize,
+    pub original: String,
+    pub rewritten: String,
+    pub complexity_before: String,
+    pub complexity_after: String,
+    pub explanation: String,
+}
+
+/// Methods that only borrow their receiver, so `x.clone().m()` == `x.m()`
+const BORROWING_METHODS: &str = "iter|len|is_empty|contains|get|first|last|starts_with|ends_with";
+
+/// Methods that mutate a collection in place
+const MUTATING_METHODS: &str =
+    "push|push_str|insert|remove|extend|clear|retain|truncate|drain|pop|append|resize|swap|sort|sort_by|sort_by_key|sort_unstable|dedup|reverse";
+
+/// Find rewritable inefficiencies. Only Rust is understood; each detector
+/// bails out whenever the rewrite could change behavior (e.g. the
+/// collection is mutated inside the loop).
+pub fn find_rewrites(code: &str, language: &str) -> Vec<CodeRewrite> {
+    if !matches!(language.to_lowercase().as_str(), "rust" | "rs") {
+        return Vec::new();
+    }
+
+    let lines: Vec<&str> = code.lines().collect();
+    let loops = find_loops(&lines);
+
+    let mut rewrites = Vec::new();
+    rewrites.extend(vec_contains_in_loop(&lines, &loops, code));
+    rewrites.extend(clone_in_loop(&lines, &loops));
+    rewrites.extend(push_without_capacity(&lines, &loops));
+    rewrites.sort_by_key(|r| r.line);
+    rewrites
+}
+
+/// A `for`/`while`/`loop` block: header line, closing line and loop header parts
+struct LoopBlock {
+    start: usize,
+    end: usize,
+    pattern: Option<String>,
+    iterable: Option<String>,
+}
+
+impl LoopBlock {
+    fn body<'a>(&self, lines: &[&'a str]) -> Vec<&'a str> {
+        lines[self.start + 1..self.end].to_vec()
+    }
+}
+
+fn find_loops(lines: &[&str]) -> Vec<LoopBlock> {
+    let for_re = Regex::new(r"^\s*(?:'\w+:\s*)?for\s+(.+?)\s+in\s+(.+?)\s*\{\s*$").unwrap();
+    let other_re = Regex::new(r"^\s*(?:'\w+:\s*)?(?:while\s+.+|loop)\s*\{\s*$").unwrap();
+
+    let mut loops = Vec::new();
+    for (start, line) in lines.iter().enumerate() {
+        let (pattern, iterable) = if let Some(caps) = for_re.captures(line) {
+            (Some(caps[1].to_string()), Some(caps[2].to_string()))
+        } else if other_re.is_match(line) {
+            (None, None)
+        } else {
+            continue;
+        };
+        if let Some(end) = closing_line(lines, start) {
+            loops.push(LoopBlock { start, end, pattern, iterable });
+        }
+    }
+    loops
+}
+
+/// Line holding the brace that closes the block opened on `start`
+fn closing_line(lines: &[&str], start: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let mut in_string = false;
+        let mut previous = ' ';
+        for c in line.chars() {
+            match c {
+                '"' if previous != '\\' => in_string = !in_string,
+                '{' if !in_string => depth += 1,
+                '}' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(index);
+                    }
+                }
+                _ => {}
+            }
+            previous = c;
+        }
+    }
+    None
+}
+
+fn mutated_in(name: &str, body: &[&str]) -> bool {
+    let name = regex::escape(name);
+    let method = Regex::new(&format!(r"\b{}\s*\.\s*(?:{})\s*\(", name, MUTATING_METHODS)).unwrap();
+    let borrow = Regex::new(&format!(r"&mut\s+{}\b", name)).unwrap();
+    let assign = Regex::new(&format!(r"(?:^|[^\w.]){}\s*(?:\[[^\]]*\])?\s*(?:[+\-*/]?=)[^=]", name)).unwrap();
+    body.iter().any(|line| method.is_match(line) || borrow.is_match(line) || assign.is_match(line))
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Names bound to a `Vec` or slice: `let v = vec![..]`, `Vec::new()`,
+/// `.collect::<Vec<_>>()`, `let v: Vec<_> = ..` and `v: &[T]` / `Vec<T>` parameters
+fn vec_bindings(code: &str) -> Vec<String> {
+    let let_re = Regex::new(
+        r"let\s+(?:mut\s+)?(\w+)\s*(?::\s*(?:Vec<[^=]*>|&\[[^=]*\]))?\s*=\s*(?:vec!|Vec::|[^;]*collect::<Vec)",
+    )
+    .unwrap();
+    let typed_re = Regex::new(r"let\s+(?:mut\s+)?(\w+)\s*:\s*(?:Vec<|&\[)").unwrap();
+    let param_re = Regex::new(r"[(,]\s*(?:mut\s+)?(\w+)\s*:\s*(?:&(?:mut\s+)?)?(?:Vec<|\[)").unwrap();
+
+    let mut names: Vec<String> = Vec::new();
+    for re in [&let_re, &typed_re, &param_re] {
+        for caps in re.captures_iter(code) {
+            if !names.contains(&caps[1].to_string()) {
+                names.push(caps[1].to_string());
+            }
+        }
+    }
+    names
+}
+
+fn vec_contains_in_loop(lines: &[&str], loops: &[LoopBlock], code: &str) -> Vec<CodeRewrite> {
+    let mut rewrites = Vec::new();
+    let mut rewritten_names = Vec::new();
+
+    for name in vec_bindings(code) {
+        let contains = Regex::new(&format!(r"(^|[^\w.]){}\s*\.contains\(", regex::escape(&name))).unwrap();
+        // outermost loop whose body calls name.contains and never mutates name
+        let block = loops.iter().find(|block| {
+            let body = block.body(lines);
+            body.iter().any(|line| contains.is_match(line)) && !mutated_in(&name, &body)
+        });
+        let block = match block {
+            Some(block) if !rewritten_names.contains(&name) => block,
+            _ => continue,
+        };
+
+        let set_name = format!("{}_set", name);
+        let indent = indentation(lines[block.start]);
+        let original = lines[block.start..=block.end].join("\n");
+        let mut rewritten = vec![format!("{}let {}: HashSet<_> = {}.iter().collect();", indent, set_name, name)];
+        for line in &lines[block.start..=block.end] {
+            rewritten.push(contains.replace_all(line, format!("${{1}}{}.contains(", set_name).as_str()).into_owned());
+        }
+
+        rewrites.push(CodeRewrite {
+            pattern: RewritePattern::VecContainsInLoop,
+            line: block.start + 1,
+            original,
+            rewritten: rewritten.join("\n"),
+            complexity_before: "O(n·m)".to_string(),
+            complexity_after: "O(n + m)".to_string(),
+            explanation: format!(
+                "`{name}.contains` scans all m elements on each of the n loop iterations. `{name}` is not modified \
+                 in the loop, so a HashSet built once gives the same answers in O(1) per lookup. Requires \
+                 `use std::collections::HashSet;` and an element type that implements Hash + Eq."
+            ),
+        });
+        rewritten_names.push(name);
+    }
+    rewrites
+}
+
+fn clone_in_loop(lines: &[&str], loops: &[LoopBlock]) -> Vec<CodeRewrite> {
+    let clone_re = Regex::new(&format!(r"(\b[\w.]+)\.clone\(\)\.({})\(", BORROWING_METHODS)).unwrap();
+    let mut rewrites = Vec::new();
+    let mut seen = Vec::new();
+
+    for block in loops {
+        for (index, line) in lines.iter().enumerate().take(block.end).skip(block.start + 1) {
+            if seen.contains(&index) || !clone_re.is_match(line) {
+                continue;
+            }
+            seen.push(index);
+            rewrites.push(CodeRewrite {
+                pattern: RewritePattern::CloneInLoop,
+                line: index + 1,
+                original: line.to_string(),
+                rewritten: clone_re.replace_all(line, "$1.$2(").into_owned(),
+                complexity_before: "O(n·m)".to_string(),
+                complexity_after: "O(n)".to_string(),
+                explanation: "The clone is a temporary that is only borrowed by the following call, so calling \
+                              the method on the original returns the same result without copying m elements \
+                              on each of the n iterations."
+                    .to_string(),
+            });
+        }
+    }
+    rewrites
+}
+
+fn push_without_capacity(lines: &[&str], loops: &[LoopBlock]) -> Vec<CodeRewrite> {
+    let new_re = Regex::new(r"^(\s*let\s+mut\s+(\w+)\s*(?::\s*Vec<[^=]*>)?\s*=\s*)Vec::new\(\)(\s*;\s*)$").unwrap();
+    let range_re = Regex::new(r"^\(?\s*([\w.()]+)\s*\.\.\s*([\w.()]+)\s*\)?$").unwrap();
+    let collection_re = Regex::new(r"^&?(?:mut\s+)?([\w.]+?)(?:\.iter\(\)|\.iter_mut\(\)|\.into_iter\(\))?$").unwrap();
+    let exits = Regex::new(r"\b(?:break|continue|return|if|match|while|for|loop)\b|\?").unwrap();
+
+    let mut rewrites = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let caps = match new_re.captures(line) {
+            Some(caps) => caps,
+            None => continue,
+        };
+        let name = caps[2].to_string();
+        let push = Regex::new(&format!(r"\b{}\s*\.\s*push\(", regex::escape(&name))).unwrap();
+
+        // the first use of the vec must be a loop that pushes exactly once per iteration
+        let block = match loops.iter().find(|block| block.start > index) {
+            Some(block) => block,
+            None => continue,
+        };
+        let between_uses = lines[index + 1..block.start].iter().any(|l| l.contains(name.as_str()));
+        let body = block.body(lines);
+        let pushes = body.iter().filter(|l| push.is_match(l)).count();
+        let other_mutation = body.iter().any(|l| !push.is_match(l) && l.contains(name.as_str()));
+        if between_uses || pushes != 1 || other_mutation || body.iter().any(|l| exits.is_match(l)) {
+            continue;
+        }
+
+        let iterable = match &block.iterable {
+            Some(iterable) if block.pattern.is_some() => iterable.trim(),
+            _ => continue,
+        };
+        let capacity = if let Some(range) = range_re.captures(iterable) {
+            if &range[1] == "0" {
+                range[2].to_string()
+            } else {
+                format!("{}.saturating_sub({})", &range[2], &range[1])
+            }
+        } else if let Some(collection) = collection_re.captures(iterable) {
+            format!("{}.len()", &collection[1])
+        } else {
+            continue;
+        };
+
+        rewrites.push(CodeRewrite {
+            pattern: RewritePattern::PushWithoutCapacity,
+            line: index + 1,
+            original: line.to_string(),
+            rewritten: format!("{}Vec::with_capacity({}){}", &caps[1], capacity, &caps[3]),
+            complexity_before: "O(n) with ~log2(n) reallocations".to_string(),
+            complexity_after: "O(n) with a single allocation".to_string(),
+            explanation: format!(
+                "The loop pushes exactly one element per iteration, so `{}` ends with {} elements. \
+                 Reserving that up front only changes the capacity, never the contents.",
+                name, capacity
+            ),
+        });
+    }
+    rewrites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vec_contains_in_loop_suggests_hashset() {
+        let code = "fn common(a: &[u32], b: Vec<u32>) -> usize {\n    let mut count = 0;\n    for x in a {\n        if b.contains(x) {\n            count += 1;\n        }\n    }\n    count\n}";
+        let rewrites = find_rewrites(code, "rust");
+        let rewrite = rewrites.iter().find(|r| r.pattern == RewritePattern::VecContainsInLoop).unwrap();
+
+        assert_eq!(rewrite.line, 3);
+        assert_eq!(rewrite.complexity_after, "O(n + m)");
+        assert!(rewrite.rewritten.starts_with("    let b_set: HashSet<_> = b.iter().collect();\n    for x in a {"));
+        assert!(rewrite.rewritten.contains("if b_set.contains(x) {"));
+        assert!(!rewrite.rewritten.contains(" b.contains"));
+    }
+
+    #[test]
+    fn test_mutated_vec_is_not_rewritten() {
+        let code = "let mut seen = Vec::new();\nfor x in items {\n    if !seen.contains(&x) {\n        seen.push(x);\n    }\n}";
+        assert!(find_rewrites(code, "rust").iter().all(|r| r.pattern != RewritePattern::VecContainsInLoop));
+    }
+
+    #[test]
+    fn test_push_in_range_loop_gets_capacity() {
+        let code = "let mut squares = Vec::new();\nfor i in 0..n {\n    squares.push(i * i);\n}";
+        let rewrites = find_rewrites(code, "rust");
+        assert_eq!(rewrites.len(), 1);
+        assert_eq!(rewrites[0].rewritten, "let mut squares = Vec::with_capacity(n);");
+    }
+
+    #[test]
+    fn test_borrowed_clone_in_loop_is_removed() {
+        let code = "for id in ids {\n    let total = names.clone().len() + id;\n}";
+        let rewrites = find_rewrites(code, "rust");
+        assert_eq!(rewrites.len(), 1);
+        assert_eq!(rewrites[0].rewritten, "    let total = names.len() + id;");
+        assert!(find_rewrites(code, "python").is_empty());
+    }
+}
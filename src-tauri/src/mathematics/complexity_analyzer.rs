@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use regex::Regex;
 use once_cell::sync::Lazy;
 
+use super::recurrence::{self, Recurrence, RecurrenceMethod};
+
 static COMPLEXITY_PATTERNS: Lazy<HashMap<&'static str, Regex>> = Lazy::new(|| {
     let mut patterns = HashMap::new();
     
@@ -102,14 +104,23 @@ impl ComplexityAnalyzer {
     pub async fn analyze_complexity(&self, code: &str, language: &str) -> ComplexityResult {
         let normalized_code = self.normalize_code(code);
         let nesting_level = self.analyze_nesting_level(&normalized_code, language).await;
-        let recursive_depth = self.analyze_recursive_calls(&normalized_code, language).await;
         let data_structure_usage = self.analyze_data_structures(&normalized_code, language).await;
-        
-        let complexity_class = self.determine_complexity_class(
-            nesting_level,
-            recursive_depth,
-            &data_structure_usage
-        ).await;
+
+        // Recursive functions are classified by their recurrence, which needs
+        // the original line structure
+        let recurrence = recurrence::detect(code, language);
+        let (recursive_depth, complexity_class) = match &recurrence {
+            Some(recurrence) => (recurrence.recursive_calls, recurrence.complexity.clone()),
+            None => {
+                let recursive_depth = self.analyze_recursive_calls(&normalized_code, language).await;
+                let complexity_class = self.determine_complexity_class(
+                    nesting_level,
+                    recursive_depth,
+                    &data_structure_usage
+                ).await;
+                (recursive_depth, complexity_class)
+            }
+        };
 
         let optimization_suggestions = self.generate_optimization_suggestions(
             &complexity_class,
@@ -117,16 +128,27 @@ impl ComplexityAnalyzer {
             language
         ).await;
 
+        let time_complexity = match &recurrence {
+            Some(recurrence) => recurrence.closed_form.clone(),
+            None => self.complexity_to_big_o(&complexity_class),
+        };
+        let confidence = match recurrence.as_ref().map(|recurrence| recurrence.method) {
+            Some(RecurrenceMethod::MasterTheorem { .. }) => 0.85,
+            Some(RecurrenceMethod::Heuristic) => 0.6,
+            None => self.calculate_confidence(&complexity_class, &normalized_code).await,
+        };
+
         ComplexityResult {
             complexity_class: complexity_class.clone(),
-            time_complexity: self.complexity_to_big_o(&complexity_class),
+            time_complexity,
             space_complexity: self.analyze_space_complexity(&normalized_code, language).await,
             nesting_level,
             recursive_depth,
             data_structures: data_structure_usage,
             bottlenecks: self.identify_bottlenecks(&normalized_code, &complexity_class).await,
             optimization_suggestions,
-            confidence: self.calculate_confidence(&complexity_class, &normalized_code).await,
+            confidence,
+            recurrence,
         }
     }
 
@@ -391,9 +413,11 @@ pub struct ComplexityResult {
     pub bottlenecks: Vec<String>,
     pub optimization_suggestions: Vec<String>,
     pub confidence: f64,
+    /// Recurrence behind the estimate when the code is self-recursive
+    pub recurrence: Option<Recurrence>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AlgorithmComplexity {
     Constant,      // O(1)
     Logarithmic,   // O(log n)
@@ -413,3 +437,96 @@ pub struct DataStructureUsage {
     pub usage_count: usize,
     pub complexity_impact: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MERGE_SORT: &str = "
+def merge_sort(arr):
+    if len(arr) <= 1:
+        return arr
+    mid = len(arr) // 2
+    left = merge_sort(arr[:mid])
+    right = merge_sort(arr[mid:])
+    return merge(left, right)
+
+def merge(left, right):
+    result = []
+    i = j = 0
+    while i < len(left) and j < len(right):
+        if left[i] <= right[j]:
+            result.append(left[i])
+            i += 1
+        else:
+            result.append(right[j])
+            j += 1
+    return result + left[i:] + right[j:]
+";
+
+    const BINARY_SEARCH: &str = "
+fn binary_search(items: &[i32], target: i32, lo: usize, hi: usize) -> Option<usize> {
+    if lo >= hi {
+        return None;
+    }
+    let mid = lo + (hi - lo) / 2;
+    if items[mid] == target {
+        Some(mid)
+    } else if items[mid] < target {
+        binary_search(items, target, mid + 1, hi)
+    } else {
+        binary_search(items, target, lo, mid)
+    }
+}
+";
+
+    const FIBONACCI: &str = "
+function fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 1) + fib(n - 2);
+}
+";
+
+    const RECURSIVE_SUM: &str = "
+def sum_to(n):
+    if n == 0:
+        return 0
+    return n + sum_to(n - 1)
+";
+
+    #[tokio::test]
+    async fn test_recursive_functions_are_classified_from_their_recurrence() {
+        let analyzer = ComplexityAnalyzer::new().await;
+
+        let result = analyzer.analyze_complexity(MERGE_SORT, "python").await;
+        let recurrence = result.recurrence.unwrap();
+        assert_eq!(recurrence.relation, "T(n) = 2T(n/2) + O(n)");
+        assert_eq!(recurrence.method, RecurrenceMethod::MasterTheorem { case: 2 });
+        assert_eq!((result.complexity_class, result.time_complexity.as_str()), (AlgorithmComplexity::Linearithmic, "O(n log n)"));
+
+        // The two calls are alternative branches, so only one runs
+        let result = analyzer.analyze_complexity(BINARY_SEARCH, "rust").await;
+        let recurrence = result.recurrence.unwrap();
+        assert_eq!(recurrence.relation, "T(n) = T(n/2) + O(1)");
+        assert_eq!(recurrence.recursive_calls, 1);
+        assert_eq!(recurrence.method, RecurrenceMethod::MasterTheorem { case: 2 });
+        assert_eq!(result.time_complexity, "O(log n)");
+
+        let result = analyzer.analyze_complexity(FIBONACCI, "javascript").await;
+        let recurrence = result.recurrence.unwrap();
+        assert_eq!(recurrence.relation, "T(n) = T(n-1) + T(n-2) + O(1)");
+        assert_eq!(recurrence.method, RecurrenceMethod::Heuristic);
+        assert!(recurrence.explanation.contains("heuristic"), "{}", recurrence.explanation);
+        assert_eq!((result.complexity_class, result.time_complexity.as_str()), (AlgorithmComplexity::Exponential, "O(1.62^n)"));
+
+        let result = analyzer.analyze_complexity(RECURSIVE_SUM, "python").await;
+        let recurrence = result.recurrence.unwrap();
+        assert_eq!(recurrence.relation, "T(n) = T(n-1) + O(1)");
+        assert_eq!(recurrence.method, RecurrenceMethod::Heuristic);
+        assert_eq!((result.complexity_class, result.time_complexity.as_str()), (AlgorithmComplexity::Linear, "O(n)"));
+
+        // No self-recursion falls back to the pattern analysis
+        let result = analyzer.analyze_complexity("fn total(v: &[i32]) -> i32 { v.iter().sum() }", "rust").await;
+        assert!(result.recurrence.is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Concept catalog. Add a concept by adding an entry to concepts.json.
+static CATALOG: Lazy<Vec<ConceptEntry>> =
+    Lazy::new(|| serde_json::from_str(include_str!("concepts.json")).expect("concepts.json is valid"));
+
+/// Depth and formality of an explanation. TARS's humor scales inversely to rigor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExplanationLevel {
+    Beginner,
+    Intermediate,
+    Expert,
+}
+
+impl ExplanationLevel {
+    pub fn parse(level: &str) -> Result<Self, String> {
+        match level.trim().to_lowercase().as_str() {
+            "beginner" | "eli5" | "basic" => Ok(ExplanationLevel::Beginner),
+            "intermediate" | "" => Ok(ExplanationLevel::Intermediate),
+            "expert" | "rigorous" | "advanced" => Ok(ExplanationLevel::Expert),
+            other => Err(format!("Unknown level '{}'. Use beginner, intermediate or expert", other)),
+        }
+    }
+
+    /// Humor setting (0-100) used at this level
+    pub fn humor(&self) -> u8 {
+        match self {
+            ExplanationLevel::Beginner => 80,
+            ExplanationLevel::Intermediate => 45,
+            ExplanationLevel::Expert => 5,
+        }
+    }
+
+    /// Style instructions for explanations generated outside the catalog
+    pub fn style(&self) -> &'static str {
+        match self {
+            ExplanationLevel::Beginner => {
+                "Explain it like I'm five: everyday analogies, no notation, no jargon. Be playful."
+            }
+            ExplanationLevel::Intermediate => {
+                "Assume working programmer knowledge: standard notation, key rules and a practical software example."
+            }
+            ExplanationLevel::Expert => {
+                "Be rigorous: give the formal definition, precise statements and proof sketches. Keep humor out of the mathematics."
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkedExample {
+    pub problem: String,
+    pub solution: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LevelContent {
+    explanation: String,
+    #[serde(default)]
+    examples: Vec<WorkedExample>,
+    #[serde(default)]
+    tars_remark: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ConceptEntry {
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    beginner: LevelContent,
+    intermediate: LevelContent,
+    expert: LevelContent,
+}
+
+impl ConceptEntry {
+    fn content(&self, level: ExplanationLevel) -> &LevelContent {
+        match level {
+            ExplanationLevel::Beginner => &self.beginner,
+            ExplanationLevel::Intermediate => &self.intermediate,
+            ExplanationLevel::Expert => &self.expert,
+        }
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        std::iter::once(&self.name).chain(&self.aliases).any(|name| normalize(name) == key)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptExplanation {
+    pub concept: String,
+    pub level: ExplanationLevel,
+    pub humor: u8,
+    pub explanation: String,
+    pub worked_examples: Vec<WorkedExample>,
+    pub tars_remark: Option<String>,
+    /// False when the concept is not in the catalog and the explanation was generated
+    pub from_catalog: bool,
+}
+
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Names of all concepts in the catalog
+pub fn known_concepts() -> Vec<&'static str> {
+    CATALOG.iter().map(|entry| entry.name.as_str()).collect()
+}
+
+/// Look up a concept (by name or alias, ignoring case and punctuation)
+pub fn explain(concept: &str, level: ExplanationLevel, include_examples: bool) -> Option<ConceptExplanation> {
+    let key = normalize(concept);
+    let entry = CATALOG.iter().find(|entry| entry.matches(&key))?;
+    let content = entry.content(level);
+
+    Some(ConceptExplanation {
+        concept: entry.name.clone(),
+        level,
+        humor: level.humor(),
+        explanation: content.explanation.clone(),
+        worked_examples: if include_examples { content.examples.clone() } else { Vec::new() },
+        tars_remark: content.tars_remark.clone(),
+        from_catalog: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_return_level_appropriate_content() {
+        let beginner = explain("Big-O", ExplanationLevel::Beginner, true).unwrap();
+        let expert = explain("time complexity", ExplanationLevel::Expert, true).unwrap();
+
+        assert_eq!(beginner.concept, "Big O notation");
+        assert_eq!(expert.concept, "Big O notation");
+        assert_ne!(beginner.explanation, expert.explanation);
+
+        // beginner avoids notation, expert gives the formal definition
+        assert!(!beginner.explanation.contains("n0"));
+        assert!(expert.explanation.contains("there exist constants"));
+
+        assert!(beginner.humor > expert.humor);
+        assert!(beginner.tars_remark.is_some());
+        assert!(expert.tars_remark.is_none());
+        assert!(!expert.worked_examples.is_empty());
+    }
+
+    #[test]
+    fn test_examples_are_optional() {
+        let explanation = explain("derivative", ExplanationLevel::Intermediate, false).unwrap();
+        assert!(explanation.worked_examples.is_empty());
+        assert!(explain("quaternion monodromy", ExplanationLevel::Beginner, true).is_none());
+    }
+
+    #[test]
+    fn test_level_parsing() {
+        assert_eq!(ExplanationLevel::parse("ELI5").unwrap(), ExplanationLevel::Beginner);
+        assert_eq!(ExplanationLevel::parse("rigorous").unwrap(), ExplanationLevel::Expert);
+        assert!(ExplanationLevel::parse("galaxy brain").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Mathematical constants stored to 100 decimal places and handed out
+//! truncated to the precision a caller asks for, with their symbol and a
+//! short description.
+
+use serde::{Deserialize, Serialize};
+
+/// Decimal places stored for every constant
+pub const MAX_CONSTANT_DIGITS: usize = 100;
+
+/// Decimal places returned when the caller doesn't ask for a precision
+pub const DEFAULT_CONSTANT_DIGITS: usize = 15;
+
+struct StoredConstant {
+    name: &'static str,
+    symbol: &'static str,
+    description: &'static str,
+    /// Integer part, a point, then `MAX_CONSTANT_DIGITS` decimal places
+    digits: &'static str,
+}
+
+const CONSTANTS: [StoredConstant; 5] = [
+    StoredConstant {
+        name: "pi",
+        symbol: "π",
+        description: "The ratio of a circle's circumference to its diameter. Essential in geometry, trigonometry, and many areas of mathematics and physics.",
+        digits: "3.1415926535897932384626433832795028841971693993751058209749445923078164062862089986280348253421170679",
+    },
+    StoredConstant {
+        name: "e",
+        symbol: "e",
+        description: "The base of natural logarithms. Fundamental in calculus, probability, and compound interest calculations.",
+        digits: "2.7182818284590452353602874713526624977572470936999595749669676277240766303535475945713821785251664274",
+    },
+    StoredConstant {
+        name: "golden_ratio",
+        symbol: "φ",
+        description: "The golden ratio, approximately 1.618. Found in nature, art, and architecture for its aesthetically pleasing proportions.",
+        digits: "1.6180339887498948482045868343656381177203091798057628621354486227052604628189024497072072041893911374",
+    },
+    StoredConstant {
+        name: "euler_mascheroni",
+        symbol: "γ",
+        description: "The Euler-Mascheroni constant. Appears in analysis and number theory, related to the harmonic series.",
+        digits: "0.5772156649015328606065120900824024310421593359399235988057672348848677267776646709369470632917467495",
+    },
+    StoredConstant {
+        name: "sqrt2",
+        symbol: "√2",
+        description: "The square root of 2. The first known irrational number, crucial in geometry and algebra.",
+        digits: "1.4142135623730950488016887242096980785696718753769480731766797379907324784621070388503875343276415727",
+    },
+];
+
+/// A constant to the requested number of decimal places
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstantValue {
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+    /// Decimal places in `value`
+    pub precision: usize,
+    /// Decimal expansion truncated (not rounded) to `precision` places
+    pub value: String,
+    /// Nearest f64, for callers that compute with it
+    pub approximation: f64,
+}
+
+impl StoredConstant {
+    fn to_precision(&self, precision: usize) -> ConstantValue {
+        let point = self.digits.find('.').unwrap_or(self.digits.len());
+        let value = match precision {
+            0 => self.digits[..point].to_string(),
+            _ => self.digits[..point + 1 + precision].to_string(),
+        };
+        ConstantValue {
+            name: self.name.to_string(),
+            symbol: self.symbol.to_string(),
+            description: self.description.to_string(),
+            precision,
+            value,
+            approximation: self.digits.parse().unwrap_or(f64::NAN),
+        }
+    }
+}
+
+fn check_precision(precision: usize) -> Result<(), String> {
+    if precision > MAX_CONSTANT_DIGITS {
+        return Err(format!(
+            "TARS: Constants are stored to {} decimal places; {} requested. Even my memory has limits, Cooper.",
+            MAX_CONSTANT_DIGITS, precision
+        ));
+    }
+    Ok(())
+}
+
+/// Every stored constant to `precision` decimal places
+pub fn all(precision: usize) -> Result<Vec<ConstantValue>, String> {
+    check_precision(precision)?;
+    Ok(CONSTANTS.iter().map(|constant| constant.to_precision(precision)).collect())
+}
+
+/// One constant, looked up by name or symbol, to `precision` decimal places
+pub fn lookup(name: &str, precision: usize) -> Result<ConstantValue, String> {
+    check_precision(precision)?;
+    let wanted = name.trim().to_lowercase();
+    CONSTANTS.iter()
+        .find(|constant| constant.name == wanted || constant.symbol.to_lowercase() == wanted)
+        .map(|constant| constant.to_precision(precision))
+        .ok_or_else(|| format!("TARS: Unknown constant '{}', Cooper. I know {}.", name,
+            CONSTANTS.iter().map(|constant| constant.name).collect::<Vec<_>>().join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pi_to_fifty_digits_matches_known_value() {
+        let pi = lookup("π", 50).unwrap();
+        assert_eq!(pi.value, "3.14159265358979323846264338327950288419716939937510");
+        assert_eq!((pi.name.as_str(), pi.precision), ("pi", 50));
+        assert_eq!(pi.approximation, std::f64::consts::PI);
+
+        // Truncated, not rounded
+        assert_eq!(lookup("pi", 4).unwrap().value, "3.1415");
+        assert_eq!(lookup("golden_ratio", 0).unwrap().value, "1");
+        assert_eq!(all(MAX_CONSTANT_DIGITS).unwrap().len(), 5);
+
+        let error = all(MAX_CONSTANT_DIGITS + 1).unwrap_err();
+        assert!(error.contains("stored to 100 decimal places; 101 requested"), "{}", error);
+        assert!(lookup("tau", 10).is_err());
+    }
+}
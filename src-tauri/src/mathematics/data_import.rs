@@ -0,0 +1,284 @@
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Where a data set comes from: a file on disk, or CSV/JSON passed inline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum DataSource {
+    File(String),
+    Csv(String),
+    Json(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedRow {
+    /// 1-based line number (CSV, NDJSON) or element number (JSON arrays)
+    pub row: usize,
+    pub value: String,
+}
+
+/// Numeric sample parsed from a column, plus the rows that were not numeric
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedSample {
+    pub column: Option<String>,
+    pub values: Vec<f64>,
+    pub skipped_rows: Vec<SkippedRow>,
+}
+
+/// Load a numeric sample from `source`. CSV needs a header row; `column`
+/// picks the column by name and defaults to the first. For JSON, `column`
+/// picks a field of each object; arrays of plain numbers need no column.
+/// Files are streamed, CSV and NDJSON line by line and JSON arrays element
+/// by element, so large files are never held in memory whole.
+pub fn import(source: &DataSource, column: Option<&str>) -> Result<ImportedSample, String> {
+    match source {
+        DataSource::Csv(text) => parse_csv(text.as_bytes(), column),
+        DataSource::Json(text) => parse_json(text.as_bytes(), column),
+        DataSource::File(path) => {
+            let path = Path::new(path);
+            let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+            let reader = BufReader::new(file);
+            match path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+                Some("csv") | Some("txt") => parse_csv(reader, column),
+                Some("json") | Some("ndjson") | Some("jsonl") => parse_json(reader, column),
+                _ => Err(format!("Unsupported data file '{}'. Use .csv, .json or .ndjson", path.display())),
+            }
+        }
+    }
+}
+
+/// Parse one CSV record, honouring double-quoted fields and `""` escapes
+fn split_csv_record(line: &str, line_number: usize) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                    if chars.peek().is_some_and(|next| *next != ',') {
+                        return Err(format!("Malformed CSV at line {}: unexpected character after closing quote", line_number));
+                    }
+                }
+            }
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            '"' => return Err(format!("Malformed CSV at line {}: quote inside unquoted field", line_number)),
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("Malformed CSV at line {}: unterminated quoted field", line_number));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+fn parse_csv<R: BufRead>(reader: R, column: Option<&str>) -> Result<ImportedSample, String> {
+    let mut lines = reader.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    let (header_line, header) = loop {
+        match lines.next() {
+            Some((n, line)) => {
+                let line = line.map_err(|e| format!("Read error at line {}: {}", n, e))?;
+                if !line.trim().is_empty() {
+                    break (n, split_csv_record(line.trim_end_matches('\r'), n)?);
+                }
+            }
+            None => return Err("CSV data is empty".to_string()),
+        }
+    };
+    let header: Vec<String> = header.iter().map(|name| name.trim().to_string()).collect();
+    let index = match column {
+        Some(name) => header
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("Column '{}' not found in header at line {} ({})", name, header_line, header.join(", ")))?,
+        None => 0,
+    };
+
+    let mut sample = ImportedSample { column: Some(header[index].clone()), values: Vec::new(), skipped_rows: Vec::new() };
+    for (n, line) in lines {
+        let line = line.map_err(|e| format!("Read error at line {}: {}", n, e))?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_record(line, n)?;
+        if fields.len() != header.len() {
+            return Err(format!("Malformed CSV at line {}: expected {} fields, found {}", n, header.len(), fields.len()));
+        }
+        let field = fields[index].trim();
+        match field.parse::<f64>() {
+            Ok(value) if value.is_finite() => sample.values.push(value),
+            _ => sample.skipped_rows.push(SkippedRow { row: n, value: field.to_string() }),
+        }
+    }
+    Ok(sample)
+}
+
+fn json_number(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse::<f64>().ok().filter(|v| v.is_finite()),
+        _ => None,
+    }
+}
+
+fn push_json_row(sample: &mut ImportedSample, row: usize, item: &serde_json::Value, column: Option<&str>) -> Result<(), String> {
+    let cell = match (item, column) {
+        (serde_json::Value::Object(fields), Some(name)) => fields.get(name).unwrap_or(&serde_json::Value::Null),
+        (serde_json::Value::Object(_), None) => return Err("JSON rows are objects; specify which column to analyze".to_string()),
+        (value, _) => value,
+    };
+    match json_number(cell) {
+        Some(value) => sample.values.push(value),
+        None => sample.skipped_rows.push(SkippedRow { row, value: cell.to_string() }),
+    }
+    Ok(())
+}
+
+/// Visits a top-level JSON array one element at a time, so only the
+/// current row is held in memory. A row error is kept in `error` rather
+/// than reported as malformed JSON.
+struct ArrayRows<'a> {
+    sample: &'a mut ImportedSample,
+    column: Option<&'a str>,
+    error: &'a mut Option<String>,
+}
+
+impl<'de> Visitor<'de> for ArrayRows<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut row = 0;
+        while let Some(item) = seq.next_element::<serde_json::Value>()? {
+            row += 1;
+            if let Err(e) = push_json_row(self.sample, row, &item, self.column) {
+                *self.error = Some(e);
+                return Err(de::Error::custom("invalid row"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accepts a JSON array (of numbers or objects), an object of column
+/// arrays, or newline-delimited JSON objects
+fn parse_json<R: BufRead>(mut reader: R, column: Option<&str>) -> Result<ImportedSample, String> {
+    let mut sample = ImportedSample { column: column.map(str::to_string), values: Vec::new(), skipped_rows: Vec::new() };
+
+    let first = loop {
+        let buffer = reader.fill_buf().map_err(|e| format!("Read error: {}", e))?;
+        match buffer.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => {
+                let first = buffer[i];
+                reader.consume(i);
+                break first;
+            }
+            None if buffer.is_empty() => return Err("JSON data is empty".to_string()),
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    };
+
+    if first == b'[' {
+        let mut error = None;
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let rows = ArrayRows { sample: &mut sample, column, error: &mut error };
+        let parsed = deserializer.deserialize_seq(rows).and_then(|()| deserializer.end());
+        if let Some(error) = error {
+            return Err(error);
+        }
+        parsed.map_err(|e| format!("Malformed JSON: {}", e))?;
+        return Ok(sample);
+    }
+
+    // one object per line, or a single (possibly multi-line) object of columns
+    let stream = serde_json::Deserializer::from_reader(reader).into_iter::<serde_json::Value>();
+    let mut row = 0;
+    for item in stream {
+        let item = item.map_err(|e| format!("Malformed JSON at line {}: {}", e.line(), e))?;
+        row += 1;
+        if row == 1 {
+            if let (serde_json::Value::Object(fields), Some(name)) = (&item, column) {
+                if let Some(serde_json::Value::Array(cells)) = fields.get(name) {
+                    for (i, cell) in cells.iter().enumerate() {
+                        push_json_row(&mut sample, i + 1, cell, None)?;
+                    }
+                    continue;
+                }
+            }
+        }
+        push_json_row(&mut sample, row, &item, column)?;
+    }
+    Ok(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_skips_non_numeric_rows() {
+        let csv = "name,latency_ms\nalpha,10\nbeta,n/a\ngamma,20\ndelta,30\n";
+        let sample = import(&DataSource::Csv(csv.to_string()), Some("latency_ms")).unwrap();
+
+        assert_eq!(sample.column.as_deref(), Some("latency_ms"));
+        assert_eq!(sample.values, vec![10.0, 20.0, 30.0]);
+        let mean = sample.values.iter().sum::<f64>() / sample.values.len() as f64;
+        assert_eq!(mean, 20.0);
+        assert_eq!(sample.skipped_rows, vec![SkippedRow { row: 3, value: "n/a".to_string() }]);
+    }
+
+    #[test]
+    fn test_malformed_csv_reports_line_number() {
+        let csv = "a,b\n1,2\n3,\"4\n5,6\n";
+        let error = import(&DataSource::Csv(csv.to_string()), Some("b")).unwrap_err();
+        assert!(error.contains("line 3"), "{}", error);
+
+        let ragged = "a,b\n1,2\n3\n";
+        let error = import(&DataSource::Csv(ragged.to_string()), None).unwrap_err();
+        assert!(error.contains("line 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_json_shapes() {
+        let rows = r#"[{"x": 1}, {"x": "2.5"}, {"x": null}]"#;
+        let sample = import(&DataSource::Json(rows.to_string()), Some("x")).unwrap();
+        assert_eq!(sample.values, vec![1.0, 2.5]);
+        assert_eq!(sample.skipped_rows[0].row, 3);
+
+        let ndjson = "{\"x\": 4}\n{\"x\": 6}\n";
+        assert_eq!(import(&DataSource::Json(ndjson.to_string()), Some("x")).unwrap().values, vec![4.0, 6.0]);
+
+        let objects = r#"[{"x": 1}]"#;
+        assert!(import(&DataSource::Json(objects.to_string()), None).unwrap_err().contains("specify which column"));
+        let trailing = "[1, 2] 3";
+        assert!(import(&DataSource::Json(trailing.to_string()), None).unwrap_err().starts_with("Malformed JSON"));
+
+        let columns = r#"{"x": [7, 8, true]}"#;
+        let sample = import(&DataSource::Json(columns.to_string()), Some("x")).unwrap();
+        assert_eq!(sample.values, vec![7.0, 8.0]);
+        assert_eq!(sample.skipped_rows.len(), 1);
+    }
+}
@@ -0,0 +1,433 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Parameter types that inputs can be generated for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ParamType {
+    Int,
+    UInt,
+    Float,
+    Bool,
+    List(Box<ParamType>),
+}
+
+impl ParamType {
+    /// Parse a Rust type: integers, floats, `bool`, and `Vec<T>` / `&[T]` /
+    /// `&mut [T]` / `&Vec<T>` of those.
+    pub fn parse(rust_type: &str) -> Result<Self, String> {
+        let ty = rust_type.trim();
+        let ty = ty.strip_prefix('&').map(|t| t.trim_start().strip_prefix("mut ").unwrap_or(t)).unwrap_or(ty).trim();
+
+        if let Some(inner) = ty.strip_prefix("Vec<").and_then(|t| t.strip_suffix('>')) {
+            return Ok(ParamType::List(Box::new(Self::parse(inner)?)));
+        }
+        if let Some(inner) = ty.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            if inner.contains(';') {
+                return Err(format!("Fixed-size arrays are not supported: {}", rust_type));
+            }
+            return Ok(ParamType::List(Box::new(Self::parse(inner)?)));
+        }
+        match ty {
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => Ok(ParamType::Int),
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => Ok(ParamType::UInt),
+            "f32" | "f64" => Ok(ParamType::Float),
+            "bool" => Ok(ParamType::Bool),
+            _ => Err(format!("Unsupported parameter type: {}", rust_type)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parameter {
+    pub name: String,
+    pub ty: ParamType,
+}
+
+/// Parse the parameters of a Rust function signature such as
+/// `fn sort(xs: &mut [i32])` or `fn clamp(x: f64, lo: f64, hi: f64) -> f64`.
+pub fn parse_signature(signature: &str) -> Result<Vec<Parameter>, String> {
+    let open = signature.find('(').ok_or("Signature has no parameter list")?;
+    let close = signature.rfind(')').filter(|close| *close > open).ok_or("Signature has no closing ')'")?;
+
+    let mut parameters = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in signature[open + 1..close].chars().chain(std::iter::once(',')) {
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' | ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                if !current.trim().is_empty() {
+                    let (name, ty) = current
+                        .split_once(':')
+                        .ok_or_else(|| format!("Parameter '{}' has no type", current.trim()))?;
+                    let name = name.trim().trim_start_matches("mut ").trim().to_string();
+                    parameters.push(Parameter { name, ty: ParamType::parse(ty)? });
+                }
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    Ok(parameters)
+}
+
+/// Generated input or function output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Value {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::UInt(a), Value::UInt(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::UInt(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{:?}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+impl Value {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Numeric value of a scalar; `true` is 1
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(v) => Some(*v as f64),
+            Value::UInt(v) => Some(*v as f64),
+            Value::Float(v) => Some(*v),
+            Value::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            Value::List(_) => None,
+        }
+    }
+
+    pub fn as_int_list(&self) -> Option<Vec<i64>> {
+        match self {
+            Value::List(items) => items.iter().map(Value::as_int).collect(),
+            _ => None,
+        }
+    }
+
+    fn generate(ty: &ParamType, rng: &mut StdRng, max_list_len: usize) -> Value {
+        match ty {
+            ParamType::Int => Value::Int(rng.gen_range(-100..=100)),
+            ParamType::UInt => Value::UInt(rng.gen_range(0..=100)),
+            ParamType::Float => Value::Float(rng.gen_range(-100.0..=100.0)),
+            ParamType::Bool => Value::Bool(rng.gen()),
+            ParamType::List(inner) => {
+                let len = rng.gen_range(0..=max_list_len);
+                Value::List((0..len).map(|_| Self::generate(inner, rng, max_list_len)).collect())
+            }
+        }
+    }
+
+    /// Smaller candidates, simplest first
+    fn shrink(&self) -> Vec<Value> {
+        let mut candidates = match self {
+            Value::Int(0) | Value::UInt(0) | Value::Bool(false) => Vec::new(),
+            Value::Int(v) => vec![Value::Int(0), Value::Int(v / 2), Value::Int(v - v.signum())],
+            Value::UInt(v) => vec![Value::UInt(0), Value::UInt(v / 2), Value::UInt(v - 1)],
+            Value::Bool(true) => vec![Value::Bool(false)],
+            Value::Float(v) if *v == 0.0 => Vec::new(),
+            Value::Float(v) => vec![Value::Float(0.0), Value::Float(v.trunc()), Value::Float(v / 2.0)],
+            Value::List(items) => {
+                let mut candidates = Vec::new();
+                if items.len() > 1 {
+                    let half = items.len() / 2;
+                    candidates.push(Value::List(items[..half].to_vec()));
+                    candidates.push(Value::List(items[half..].to_vec()));
+                }
+                for i in 0..items.len() {
+                    let mut fewer = items.clone();
+                    fewer.remove(i);
+                    candidates.push(Value::List(fewer));
+                }
+                for (i, item) in items.iter().enumerate() {
+                    for smaller in item.shrink() {
+                        let mut simpler = items.clone();
+                        simpler[i] = smaller;
+                        candidates.push(Value::List(simpler));
+                    }
+                }
+                candidates
+            }
+        };
+        candidates.dedup();
+        candidates.retain(|candidate| candidate != self);
+        candidates
+    }
+}
+
+impl From<Vec<i64>> for Value {
+    fn from(items: Vec<i64>) -> Self {
+        Value::List(items.into_iter().map(Value::Int).collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DifferentialConfig {
+    pub cases: usize,
+    pub seed: u64,
+    pub max_list_len: usize,
+    pub max_shrink_steps: usize,
+}
+
+impl Default for DifferentialConfig {
+    fn default() -> Self {
+        Self { cases: 200, seed: 0x7A85, max_list_len: 16, max_shrink_steps: 1000 }
+    }
+}
+
+/// Smallest failing input found, with what the candidate returned and what
+/// was expected (the reference output or the violated invariant).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Counterexample {
+    pub inputs: Vec<(String, Value)>,
+    pub actual: String,
+    pub expected: String,
+    pub shrink_steps: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferentialReport {
+    pub cases_run: usize,
+    pub passed: bool,
+    pub counterexample: Option<Counterexample>,
+}
+
+impl DifferentialReport {
+    /// Human-readable outcome, used as the proof text of a verification
+    pub fn summary(&self) -> String {
+        match &self.counterexample {
+            None => format!("No divergence found in {} random cases", self.cases_run),
+            Some(counterexample) => {
+                let inputs: Vec<String> =
+                    counterexample.inputs.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+                format!(
+                    "Counterexample after {} cases ({} shrink steps): {} returned {}, expected {}",
+                    self.cases_run,
+                    counterexample.shrink_steps,
+                    inputs.join(", "),
+                    counterexample.actual,
+                    counterexample.expected
+                )
+            }
+        }
+    }
+}
+
+/// A named property of a candidate's output for given inputs
+pub type Invariant<'a> = (&'a str, &'a dyn Fn(&[Value], &Value) -> bool);
+
+/// Run random inputs through `candidate` and `reference` and report the
+/// first (shrunk) input on which they disagree.
+pub fn compare<C, R>(signature: &str, config: &DifferentialConfig, candidate: C, reference: R) -> Result<DifferentialReport, String>
+where
+    C: Fn(&[Value]) -> Value,
+    R: Fn(&[Value]) -> Value,
+{
+    let parameters = parse_signature(signature)?;
+    Ok(run(&parameters, config, |inputs| {
+        let actual = call(&candidate, inputs);
+        let expected = call(&reference, inputs);
+        (actual != expected).then(|| (describe(&actual), describe(&expected)))
+    }))
+}
+
+/// Run random inputs through `candidate` and report the first (shrunk)
+/// input whose output violates one of the invariants.
+pub fn check_invariants<C>(
+    signature: &str,
+    config: &DifferentialConfig,
+    candidate: C,
+    invariants: &[Invariant],
+) -> Result<DifferentialReport, String>
+where
+    C: Fn(&[Value]) -> Value,
+{
+    let parameters = parse_signature(signature)?;
+    Ok(run(&parameters, config, |inputs| {
+        let output = call(&candidate, inputs);
+        let output = match output {
+            Ok(output) => output,
+            Err(panic) => return Some((panic, "no panic".to_string())),
+        };
+        invariants
+            .iter()
+            .find(|(_, holds)| !holds(inputs, &output))
+            .map(|(name, _)| (output.to_string(), format!("invariant '{}' to hold", name)))
+    }))
+}
+
+fn call<F: Fn(&[Value]) -> Value>(f: &F, inputs: &[Value]) -> Result<Value, String> {
+    catch_unwind(AssertUnwindSafe(|| f(inputs))).map_err(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("panic: {}", message)
+    })
+}
+
+fn describe(result: &Result<Value, String>) -> String {
+    match result {
+        Ok(value) => value.to_string(),
+        Err(panic) => panic.clone(),
+    }
+}
+
+/// `fails` returns (actual, expected) when the inputs expose a bug
+fn run<F>(parameters: &[Parameter], config: &DifferentialConfig, fails: F) -> DifferentialReport
+where
+    F: Fn(&[Value]) -> Option<(String, String)>,
+{
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    for case in 1..=config.cases {
+        let inputs: Vec<Value> = parameters.iter().map(|p| Value::generate(&p.ty, &mut rng, config.max_list_len)).collect();
+        if fails(&inputs).is_none() {
+            continue;
+        }
+
+        let (inputs, shrink_steps) = shrink_inputs(inputs, config.max_shrink_steps, &fails);
+        let (actual, expected) = fails(&inputs).unwrap_or_default();
+        return DifferentialReport {
+            cases_run: case,
+            passed: false,
+            counterexample: Some(Counterexample {
+                inputs: parameters.iter().map(|p| p.name.clone()).zip(inputs).collect(),
+                actual,
+                expected,
+                shrink_steps,
+            }),
+        };
+    }
+    DifferentialReport { cases_run: config.cases, passed: true, counterexample: None }
+}
+
+/// Greedily replace inputs with smaller ones that still fail
+fn shrink_inputs<F>(mut inputs: Vec<Value>, max_steps: usize, fails: &F) -> (Vec<Value>, usize)
+where
+    F: Fn(&[Value]) -> Option<(String, String)>,
+{
+    let mut steps = 0;
+    'shrinking: while steps < max_steps {
+        for index in 0..inputs.len() {
+            for candidate in inputs[index].shrink() {
+                let mut trial = inputs.clone();
+                trial[index] = candidate;
+                if fails(&trial).is_some() {
+                    inputs = trial;
+                    steps += 1;
+                    continue 'shrinking;
+                }
+            }
+        }
+        break;
+    }
+    (inputs, steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bubble sort that stops after a single pass
+    fn buggy_sort(args: &[Value]) -> Value {
+        let mut xs = args[0].as_int_list().unwrap();
+        for i in 1..xs.len() {
+            if xs[i - 1] > xs[i] {
+                xs.swap(i - 1, i);
+            }
+        }
+        Value::from(xs)
+    }
+
+    fn reference_sort(args: &[Value]) -> Value {
+        let mut xs = args[0].as_int_list().unwrap();
+        xs.sort();
+        Value::from(xs)
+    }
+
+    #[test]
+    fn test_buggy_sort_yields_shrunk_counterexample() {
+        let report =
+            compare("fn sort(xs: &mut [i32])", &DifferentialConfig::default(), buggy_sort, reference_sort).unwrap();
+        assert!(!report.passed);
+
+        let counterexample = report.counterexample.unwrap();
+        let (name, input) = &counterexample.inputs[0];
+        assert_eq!(name, "xs");
+        // one pass fixes any two-element list, so three elements is minimal
+        assert_eq!(input.as_int_list().unwrap().len(), 3);
+        let args = std::slice::from_ref(input);
+        assert_ne!(buggy_sort(args), reference_sort(args));
+        assert_ne!(counterexample.actual, counterexample.expected);
+    }
+
+    #[test]
+    fn test_correct_implementation_passes_invariants() {
+        let sorted: &dyn Fn(&[Value], &Value) -> bool =
+            &|_, output| output.as_int_list().unwrap().windows(2).all(|w| w[0] <= w[1]);
+        let same_len: &dyn Fn(&[Value], &Value) -> bool =
+            &|inputs, output| inputs[0].as_int_list().unwrap().len() == output.as_int_list().unwrap().len();
+
+        let report = check_invariants(
+            "fn sort(xs: Vec<i64>)",
+            &DifferentialConfig::default(),
+            reference_sort,
+            &[("sorted", sorted), ("same length", same_len)],
+        )
+        .unwrap();
+        assert!(report.passed);
+        assert_eq!(report.cases_run, 200);
+    }
+
+    #[test]
+    fn test_parse_signature_types() {
+        let params = parse_signature("fn f(mut a: Vec<Vec<u8>>, b: &[f64], flag: bool) -> usize").unwrap();
+        let types: Vec<ParamType> = params.into_iter().map(|p| p.ty).collect();
+        assert_eq!(
+            types,
+            vec![
+                ParamType::List(Box::new(ParamType::List(Box::new(ParamType::UInt)))),
+                ParamType::List(Box::new(ParamType::Float)),
+                ParamType::Bool,
+            ]
+        );
+        assert!(parse_signature("fn f(s: String)").is_err());
+    }
+}
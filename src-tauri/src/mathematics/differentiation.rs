@@ -0,0 +1,595 @@
+//! Symbolic differentiation. Expressions are parsed into a tree,
+//! differentiated with the sum, product, quotient and chain rules, and the
+//! result simplified so `d/dx 3x^2` reads `6 * x` rather than
+//! `0 * x^2 + 3 * (2 * x^(2 - 1) * 1)`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Functions the parser and the differentiator understand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Sqrt,
+    Abs,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            "exp" => Some(Function::Exp),
+            "ln" => Some(Function::Ln),
+            "sqrt" => Some(Function::Sqrt),
+            "abs" => Some(Function::Abs),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::Sqrt => "sqrt",
+            Function::Abs => "abs",
+        }
+    }
+}
+
+/// An expression tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Number(f64),
+    /// Named constant, `e` or `pi`
+    Constant(String),
+    Variable(String),
+    Add(Box<Node>, Box<Node>),
+    Sub(Box<Node>, Box<Node>),
+    Mul(Box<Node>, Box<Node>),
+    Div(Box<Node>, Box<Node>),
+    Pow(Box<Node>, Box<Node>),
+    Neg(Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+const CONSTANTS: [&str; 2] = ["e", "pi"];
+
+/// How `Node::parse_with` reads an expression
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Keep the case of names, so unit symbols like `mA` and `Pa` survive
+    pub case_sensitive: bool,
+    /// A name right after a number is its unit and binds to it first, with
+    /// an optional integer power: `1 / 2 s` is `1 / (2 * s)` and `16 m^2`
+    /// is `16 * m^2`. Numbers may use exponent notation such as `1e-3`.
+    pub units: bool,
+}
+
+fn number(value: f64) -> Node {
+    Node::Number(value)
+}
+
+fn add(a: Node, b: Node) -> Node {
+    Node::Add(Box::new(a), Box::new(b))
+}
+
+fn sub(a: Node, b: Node) -> Node {
+    Node::Sub(Box::new(a), Box::new(b))
+}
+
+fn mul(a: Node, b: Node) -> Node {
+    Node::Mul(Box::new(a), Box::new(b))
+}
+
+fn div(a: Node, b: Node) -> Node {
+    Node::Div(Box::new(a), Box::new(b))
+}
+
+fn pow(a: Node, b: Node) -> Node {
+    Node::Pow(Box::new(a), Box::new(b))
+}
+
+fn neg(a: Node) -> Node {
+    Node::Neg(Box::new(a))
+}
+
+fn call(function: Function, a: Node) -> Node {
+    Node::Call(function, Box::new(a))
+}
+
+impl Node {
+    /// Parse `+ - * / ^`, parentheses, numbers, `e`, `pi`, variables and
+    /// `sin cos tan exp ln sqrt abs`. A number directly before a variable or
+    /// a parenthesis multiplies it, so `3x^2` is `3 * x^2`.
+    pub fn parse(expression: &str) -> Result<Node, String> {
+        Self::parse_with(expression, ParseOptions::default())
+    }
+
+    /// Parse with the unit and case handling of `options`; names are
+    /// lowercased unless `case_sensitive` is set
+    pub fn parse_with(expression: &str, options: ParseOptions) -> Result<Node, String> {
+        let input = if options.case_sensitive { expression.to_string() } else { expression.to_lowercase() };
+        let mut parser = Parser { chars: input.chars().filter(|c| !c.is_whitespace()).collect(), pos: 0, options };
+        let node = parser.expression()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("Unexpected '{}' at position {}", parser.chars[parser.pos], parser.pos));
+        }
+        Ok(node)
+    }
+
+    /// Whether `variable` appears anywhere in the tree
+    pub fn depends_on(&self, variable: &str) -> bool {
+        match self {
+            Node::Number(_) | Node::Constant(_) => false,
+            Node::Variable(name) => name == variable,
+            Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) | Node::Div(a, b) | Node::Pow(a, b) => {
+                a.depends_on(variable) || b.depends_on(variable)
+            }
+            Node::Neg(a) | Node::Call(_, a) => a.depends_on(variable),
+        }
+    }
+
+    /// The tree with every occurrence of `variable` replaced by `replacement`
+    pub fn substitute(&self, variable: &str, replacement: &Node) -> Node {
+        let sub_tree = |node: &Node| Box::new(node.substitute(variable, replacement));
+        match self {
+            Node::Variable(name) if name == variable => replacement.clone(),
+            Node::Number(_) | Node::Constant(_) | Node::Variable(_) => self.clone(),
+            Node::Add(a, b) => Node::Add(sub_tree(a), sub_tree(b)),
+            Node::Sub(a, b) => Node::Sub(sub_tree(a), sub_tree(b)),
+            Node::Mul(a, b) => Node::Mul(sub_tree(a), sub_tree(b)),
+            Node::Div(a, b) => Node::Div(sub_tree(a), sub_tree(b)),
+            Node::Pow(a, b) => Node::Pow(sub_tree(a), sub_tree(b)),
+            Node::Neg(a) => Node::Neg(sub_tree(a)),
+            Node::Call(function, a) => Node::Call(*function, sub_tree(a)),
+        }
+    }
+
+    /// Derivative with respect to `variable`, unsimplified. Other
+    /// variables are held constant.
+    pub fn differentiate(&self, variable: &str) -> Node {
+        if !self.depends_on(variable) {
+            return number(0.0);
+        }
+        match self {
+            Node::Number(_) | Node::Constant(_) => number(0.0),
+            Node::Variable(_) => number(1.0),
+            Node::Add(a, b) => add(a.differentiate(variable), b.differentiate(variable)),
+            Node::Sub(a, b) => sub(a.differentiate(variable), b.differentiate(variable)),
+            Node::Neg(a) => neg(a.differentiate(variable)),
+            // Product rule: (uv)' = u'v + uv'
+            Node::Mul(a, b) => add(
+                mul(a.differentiate(variable), (**b).clone()),
+                mul((**a).clone(), b.differentiate(variable)),
+            ),
+            // Quotient rule: (u/v)' = (u'v - uv') / v^2
+            Node::Div(a, b) => div(
+                sub(
+                    mul(a.differentiate(variable), (**b).clone()),
+                    mul((**a).clone(), b.differentiate(variable)),
+                ),
+                pow((**b).clone(), number(2.0)),
+            ),
+            Node::Pow(base, exponent) => {
+                let (u, v) = ((**base).clone(), (**exponent).clone());
+                match (base.depends_on(variable), exponent.depends_on(variable)) {
+                    // Power rule: (u^n)' = u' * n * u^(n - 1)
+                    (true, false) => mul(mul(u.differentiate(variable), v.clone()), pow(u, sub(v, number(1.0)))),
+                    // (a^v)' = v' * ln(a) * a^v, and ln(e) is 1
+                    (false, _) if u == Node::Constant("e".to_string()) => mul(v.differentiate(variable), self.clone()),
+                    (false, _) => mul(mul(v.differentiate(variable), call(Function::Ln, u)), self.clone()),
+                    // (u^v)' = u^v * (v' * ln(u) + v * u' / u)
+                    (true, true) => mul(
+                        self.clone(),
+                        add(
+                            mul(v.differentiate(variable), call(Function::Ln, u.clone())),
+                            div(mul(v, u.differentiate(variable)), u),
+                        ),
+                    ),
+                }
+            }
+            // Chain rule: f(u)' = u' * f'(u)
+            Node::Call(function, a) => {
+                let u = (**a).clone();
+                let outer = match function {
+                    Function::Sin => call(Function::Cos, u),
+                    Function::Cos => neg(call(Function::Sin, u)),
+                    Function::Tan => div(number(1.0), pow(call(Function::Cos, u), number(2.0))),
+                    Function::Exp => self.clone(),
+                    Function::Ln => div(number(1.0), u),
+                    Function::Sqrt => div(number(1.0), mul(number(2.0), self.clone())),
+                    Function::Abs => div(u, self.clone()),
+                };
+                mul(a.differentiate(variable), outer)
+            }
+        }
+    }
+
+    /// Fold constants and drop identities like `x + 0`, `x * 1` and `x^1`
+    pub fn simplify(&self) -> Node {
+        match self {
+            Node::Number(_) | Node::Constant(_) | Node::Variable(_) => self.clone(),
+            Node::Add(a, b) => simplify_add(a.simplify(), b.simplify()),
+            Node::Sub(a, b) => simplify_sub(a.simplify(), b.simplify()),
+            Node::Mul(a, b) => simplify_mul(a.simplify(), b.simplify()),
+            Node::Div(a, b) => simplify_div(a.simplify(), b.simplify()),
+            Node::Pow(a, b) => simplify_pow(a.simplify(), b.simplify()),
+            Node::Neg(a) => simplify_neg(a.simplify()),
+            Node::Call(function, a) => match (function, a.simplify()) {
+                (Function::Ln, Node::Constant(name)) if name == "e" => number(1.0),
+                (function, a) => call(*function, a),
+            },
+        }
+    }
+
+    /// Numeric value with each variable looked up in `variables`
+    pub fn evaluate(&self, variables: &HashMap<String, f64>) -> Result<f64, String> {
+        let value = |node: &Node| node.evaluate(variables);
+        match self {
+            Node::Number(value) => Ok(*value),
+            Node::Constant(name) if name == "e" => Ok(std::f64::consts::E),
+            Node::Constant(_) => Ok(std::f64::consts::PI),
+            Node::Variable(name) => variables.get(name).copied().ok_or_else(|| format!("Unknown variable '{}'", name)),
+            Node::Add(a, b) => Ok(value(a)? + value(b)?),
+            Node::Sub(a, b) => Ok(value(a)? - value(b)?),
+            Node::Mul(a, b) => Ok(value(a)? * value(b)?),
+            Node::Div(a, b) => match value(b)? {
+                0.0 => Err("Division by zero".to_string()),
+                divisor => Ok(value(a)? / divisor),
+            },
+            Node::Pow(a, b) => Ok(value(a)?.powf(value(b)?)),
+            Node::Neg(a) => Ok(-value(a)?),
+            Node::Call(function, a) => {
+                let argument = value(a)?;
+                match function {
+                    Function::Sin => Ok(argument.sin()),
+                    Function::Cos => Ok(argument.cos()),
+                    Function::Tan => Ok(argument.tan()),
+                    Function::Exp => Ok(argument.exp()),
+                    Function::Ln if argument <= 0.0 => Err("Logarithm of non-positive number".to_string()),
+                    Function::Ln => Ok(argument.ln()),
+                    Function::Sqrt if argument < 0.0 => Err("Square root of negative number".to_string()),
+                    Function::Sqrt => Ok(argument.sqrt()),
+                    Function::Abs => Ok(argument.abs()),
+                }
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Node::Add(..) | Node::Sub(..) => 1,
+            Node::Mul(..) | Node::Div(..) => 2,
+            Node::Neg(_) => 3,
+            Node::Number(n) if *n < 0.0 => 3,
+            Node::Pow(..) => 4,
+            _ => 5,
+        }
+    }
+}
+
+/// Parse, differentiate and simplify `expression` with respect to `variable`
+pub fn derivative(expression: &str, variable: &str) -> Result<Node, String> {
+    let variable = variable.trim().to_lowercase();
+    if variable.is_empty() || !variable.chars().all(char::is_alphanumeric) || CONSTANTS.contains(&variable.as_str()) {
+        return Err(format!("'{}' is not a variable that can be differentiated by", variable));
+    }
+    Ok(Node::parse(expression)?.simplify().differentiate(&variable).simplify())
+}
+
+fn simplify_add(a: Node, b: Node) -> Node {
+    match (a, b) {
+        (Node::Number(x), Node::Number(y)) => number(x + y),
+        (Node::Number(0.0), b) => b,
+        (a, Node::Number(0.0)) => a,
+        (a, Node::Number(n)) if n < 0.0 => sub(a, number(-n)),
+        (a, Node::Neg(b)) => simplify_sub(a, *b),
+        (Node::Neg(a), b) => simplify_sub(b, *a),
+        (a, b) => add(a, b),
+    }
+}
+
+fn simplify_sub(a: Node, b: Node) -> Node {
+    match (a, b) {
+        (Node::Number(x), Node::Number(y)) => number(x - y),
+        (a, Node::Number(0.0)) => a,
+        (Node::Number(0.0), b) => simplify_neg(b),
+        (a, b) if a == b => number(0.0),
+        (a, Node::Number(n)) if n < 0.0 => add(a, number(-n)),
+        (a, Node::Neg(b)) => simplify_add(a, *b),
+        (a, b) => sub(a, b),
+    }
+}
+
+fn simplify_mul(a: Node, b: Node) -> Node {
+    match (a, b) {
+        (Node::Number(x), Node::Number(y)) => number(x * y),
+        (Node::Number(0.0), _) | (_, Node::Number(0.0)) => number(0.0),
+        (Node::Number(1.0), b) => b,
+        (a, Node::Number(1.0)) => a,
+        (Node::Number(-1.0), b) => simplify_neg(b),
+        // Numbers lead, and neighbouring numbers combine: 3 * (2 * x) is 6 * x
+        (a, Node::Number(n)) => simplify_mul(number(n), a),
+        (Node::Number(x), Node::Mul(b, c)) => match *b {
+            Node::Number(y) => simplify_mul(number(x * y), *c),
+            b => mul(number(x), mul(b, *c)),
+        },
+        (a, Node::Mul(b, c)) if matches!(*b, Node::Number(_)) => simplify_mul(*b, simplify_mul(a, *c)),
+        (Node::Mul(a, b), c) if matches!(*a, Node::Number(_)) => simplify_mul(*a, simplify_mul(*b, c)),
+        (Node::Neg(a), b) => simplify_neg(simplify_mul(*a, b)),
+        (a, Node::Neg(b)) => simplify_neg(simplify_mul(a, *b)),
+        (a, b) if a == b => pow(a, number(2.0)),
+        (a, b) => mul(a, b),
+    }
+}
+
+fn simplify_div(a: Node, b: Node) -> Node {
+    match (a, b) {
+        // Only exact quotients fold, so 1/3 stays a fraction
+        (Node::Number(x), Node::Number(y)) if y != 0.0 && (x / y).fract() == 0.0 => number(x / y),
+        (Node::Number(0.0), b) if b != number(0.0) => number(0.0),
+        (a, Node::Number(1.0)) => a,
+        (a, b) if a == b && b != number(0.0) => number(1.0),
+        (Node::Neg(a), b) => simplify_neg(simplify_div(*a, b)),
+        (a, b) => div(a, b),
+    }
+}
+
+fn simplify_pow(a: Node, b: Node) -> Node {
+    match (a, b) {
+        (Node::Number(x), Node::Number(y)) if y.fract() == 0.0 && x.powf(y).is_finite() && x.powf(y).fract() == 0.0 => {
+            number(x.powf(y))
+        }
+        (_, Node::Number(0.0)) => number(1.0),
+        (a, Node::Number(1.0)) => a,
+        (Node::Number(1.0), _) => number(1.0),
+        (a, b) => pow(a, b),
+    }
+}
+
+fn simplify_neg(a: Node) -> Node {
+    match a {
+        Node::Number(x) => number(-x),
+        Node::Neg(a) => *a,
+        a => neg(a),
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        "0".to_string()
+    } else if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `parens` wraps a child that would otherwise bind the wrong way
+        let child = |f: &mut fmt::Formatter<'_>, node: &Node, parens: bool| {
+            if parens { write!(f, "({})", node) } else { write!(f, "{}", node) }
+        };
+        let binary = |f: &mut fmt::Formatter<'_>, a: &Node, op: &str, b: &Node, own: u8, associative: bool| {
+            child(f, a, a.precedence() < own)?;
+            write!(f, " {} ", op)?;
+            child(f, b, b.precedence() < own || (!associative && b.precedence() == own))
+        };
+        match self {
+            Node::Number(value) => write!(f, "{}", format_number(*value)),
+            Node::Constant(name) | Node::Variable(name) => write!(f, "{}", name),
+            Node::Add(a, b) => binary(f, a, "+", b, 1, true),
+            Node::Sub(a, b) => binary(f, a, "-", b, 1, false),
+            Node::Mul(a, b) => binary(f, a, "*", b, 2, true),
+            Node::Div(a, b) => binary(f, a, "/", b, 2, false),
+            Node::Pow(a, b) => {
+                child(f, a, a.precedence() <= 4)?;
+                write!(f, "^")?;
+                child(f, b, b.precedence() < 5)
+            }
+            Node::Neg(a) => {
+                write!(f, "-")?;
+                child(f, a, a.precedence() < 3)
+            }
+            Node::Call(function, a) => write!(f, "{}({})", function.name(), a),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    options: ParseOptions,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // expression = term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Node, String> {
+        let mut node = self.term()?;
+        loop {
+            if self.eat('+') {
+                node = add(node, self.term()?);
+            } else if self.eat('-') {
+                node = sub(node, self.term()?);
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    // term = unary (('*' | '/') unary | implicit)*
+    fn term(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            if self.eat('*') {
+                node = mul(node, self.unary()?);
+            } else if self.eat('/') {
+                node = div(node, self.unary()?);
+            } else if self.peek().is_some_and(|c| c.is_alphabetic() || c == '(') {
+                node = mul(node, self.power()?);
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    // unary = '-' unary | power
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat('-') {
+            return Ok(neg(self.unary()?));
+        }
+        self.eat('+');
+        self.power()
+    }
+
+    // power = primary ('^' unary)?   (right associative)
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            return Ok(pow(base, self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let node = self.expression()?;
+                if !self.eat(')') {
+                    return Err(format!("Missing ')' at position {}", self.pos));
+                }
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.number()?;
+                if self.options.units && self.peek().is_some_and(char::is_alphabetic) {
+                    return Ok(mul(number, self.unit()?));
+                }
+                Ok(number)
+            }
+            Some(c) if c.is_alphabetic() => self.identifier(),
+            Some(c) => Err(format!("Unexpected '{}' at position {}", c, self.pos)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+
+    fn number(&mut self) -> Result<Node, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        // Exponent notation, but not a unit starting with `e`
+        if self.options.units
+            && self.peek() == Some('e')
+            && self.chars.get(self.pos + 1).is_some_and(|c| c.is_ascii_digit() || *c == '-')
+        {
+            self.pos += 2;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map(Node::Number).map_err(|_| format!("Invalid number '{}' at position {}", text, start))
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Unit after a number, with an optional integer power like `m^2`
+    fn unit(&mut self) -> Result<Node, String> {
+        let unit = Node::Variable(self.name());
+        if !self.eat('^') {
+            return Ok(unit);
+        }
+        // The power belongs to the unit: `16 m^2` is 16 square metres
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let power: i32 = text.parse().map_err(|_| format!("Unit power must be an integer at position {}", start))?;
+        Ok(pow(unit, number(power as f64)))
+    }
+
+    fn identifier(&mut self) -> Result<Node, String> {
+        let name = self.name();
+
+        if self.eat('(') {
+            let function = Function::from_name(&name).ok_or_else(|| format!("Unknown function '{}'", name))?;
+            let argument = self.expression()?;
+            if !self.eat(')') {
+                return Err(format!("Missing ')' at position {}", self.pos));
+            }
+            return Ok(call(function, argument));
+        }
+
+        if CONSTANTS.contains(&name.as_str()) {
+            Ok(Node::Constant(name))
+        } else {
+            Ok(Node::Variable(name))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(expression: &str) -> String {
+        derivative(expression, "x").unwrap().to_string()
+    }
+
+    #[test]
+    fn test_derivatives_of_polynomials_products_and_compositions_simplify() {
+        assert_eq!(d("3x^2 + 2x - 5"), "6 * x + 2");
+        assert_eq!(d("x^3 - 4*x"), "3 * x^2 - 4");
+        assert_eq!(d("7"), "0");
+        assert_eq!(d("(x^2 + 1)^3"), "6 * x * (x^2 + 1)^2");
+        assert_eq!(d("sin(x) * cos(x)"), "cos(x)^2 - sin(x)^2");
+        assert_eq!(d("e^(x^2)"), "2 * x * e^(x^2)");
+        assert_eq!(d("1/x"), "-1 / x^2");
+        assert_eq!(d("ln(x) * y"), "1 / x * y");
+        assert_eq!(d("abs(x)"), "x / abs(x)");
+        assert_eq!(derivative("x*y^2", "y").unwrap().to_string(), "2 * x * y");
+
+        // Printed results parse back to the same tree
+        for expression in ["(x^2 + 1)^3", "e^(x^2)", "sin(x) * cos(x)", "x / (x - 1)"] {
+            let result = derivative(expression, "x").unwrap();
+            assert_eq!(Node::parse(&result.to_string()).unwrap().simplify(), result, "{}", result);
+        }
+
+        assert_eq!(Node::parse("x * 1 + 0 * y - (2 + 3)").unwrap().simplify().to_string(), "x - 5");
+        assert!(derivative("foo(x)", "x").unwrap_err().contains("Unknown function"));
+        assert!(derivative("x^2", "e").is_err());
+    }
+}
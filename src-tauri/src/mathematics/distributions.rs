@@ -0,0 +1,133 @@
+//! Distribution functions for hypothesis-test p-values, built on the
+//! regularized incomplete beta and gamma functions (Lanczos log-gamma,
+//! Lentz continued fractions). Accurate to around 1e-10 over the ranges
+//! the tests use.
+
+/// Continued fraction and series iterations before giving up
+const MAX_ITERATIONS: usize = 500;
+
+const EPSILON: f64 = 1e-15;
+
+/// Smallest magnitude allowed in a continued fraction denominator
+const TINY: f64 = 1e-300;
+
+/// Lanczos coefficients for g = 7, n = 9
+const LANCZOS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+/// Natural log of the gamma function for x > 0
+pub fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        // Reflection: Γ(x)Γ(1 - x) = π / sin(πx)
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = LANCZOS[1..].iter().enumerate().fold(LANCZOS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+pub fn regularized_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges fast below the mean; use symmetry above it
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_fraction(1.0 - x, b, a) / b
+    }
+}
+
+fn beta_fraction(x: f64, a: f64, b: f64) -> f64 {
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut result = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        result *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let delta = d * c;
+        result *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    result
+}
+
+/// Regularized upper incomplete gamma function Q(a, x) = 1 - P(a, x)
+pub fn regularized_gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let front = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // Series for P(a, x)
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..=MAX_ITERATIONS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        1.0 - front * sum
+    } else {
+        // Continued fraction for Q(a, x)
+        let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut result = d;
+        for n in 1..=MAX_ITERATIONS {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = 1.0 / clamp(an * d + b);
+            c = clamp(b + an / c);
+            let delta = d * c;
+            result *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        front * result
+    }
+}
+
+/// Two-sided p-value of Student's t statistic with `df` degrees of freedom
+pub fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    regularized_beta(df / (df + t * t), df / 2.0, 0.5)
+}
+
+/// Upper-tail p-value of a chi-square statistic with `df` degrees of freedom
+pub fn chi_square_upper(statistic: f64, df: f64) -> f64 {
+    regularized_gamma_q(df / 2.0, statistic / 2.0)
+}
+
+/// Two-sided p-value of a standard normal z score, erfc(|z| / √2)
+pub fn normal_two_sided(z: f64) -> f64 {
+    regularized_gamma_q(0.5, z * z / 2.0)
+}
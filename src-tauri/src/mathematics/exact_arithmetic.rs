@@ -0,0 +1,245 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::differentiation::Node;
+
+/// Largest integer exponent evaluated exactly; bigger powers fall back to floats
+const MAX_EXACT_EXPONENT: i32 = 4096;
+
+/// Largest power, in bits of numerator or denominator, evaluated exactly;
+/// nested powers that would grow past it fall back to floats
+const MAX_EXACT_BITS: u64 = 1 << 16;
+
+/// Result of exact evaluation. `exact` is false once an irrational
+/// operation (a constant, a transcendental function, a non-integer power)
+/// forced the evaluation onto floating point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExactResult {
+    pub value: String,
+    pub exact: bool,
+    pub approximation: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Number {
+    Exact(BigRational),
+    Approx(f64),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Approx(f) => *f,
+        }
+    }
+
+    fn add(self, other: Number) -> Number {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a + b),
+            (a, b) => Number::Approx(a.to_f64() + b.to_f64()),
+        }
+    }
+
+    fn sub(self, other: Number) -> Number {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a - b),
+            (a, b) => Number::Approx(a.to_f64() - b.to_f64()),
+        }
+    }
+
+    fn mul(self, other: Number) -> Number {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a * b),
+            (a, b) => Number::Approx(a.to_f64() * b.to_f64()),
+        }
+    }
+
+    fn div(self, other: Number) -> Result<Number, String> {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => {
+                if b.is_zero() {
+                    Err("Division by zero".to_string())
+                } else {
+                    Ok(Number::Exact(a / b))
+                }
+            }
+            (a, b) => {
+                let divisor = b.to_f64();
+                if divisor.abs() < 1e-10 {
+                    Err("Division by zero".to_string())
+                } else {
+                    Ok(Number::Approx(a.to_f64() / divisor))
+                }
+            }
+        }
+    }
+
+    fn neg(self) -> Number {
+        match self {
+            Number::Exact(r) => Number::Exact(-r),
+            Number::Approx(f) => Number::Approx(-f),
+        }
+    }
+
+    fn pow(self, exponent: Number) -> Result<Number, String> {
+        if let (Number::Exact(base), Number::Exact(exp)) = (&self, &exponent) {
+            if exp.is_integer() {
+                if let Some(e) = exp.to_integer().to_i32().filter(|e| e.abs() <= MAX_EXACT_EXPONENT) {
+                    if base.is_zero() && e < 0 {
+                        return Err("Division by zero".to_string());
+                    }
+                    let bits = base.numer().bits().max(base.denom().bits()) * e.unsigned_abs() as u64;
+                    if bits <= MAX_EXACT_BITS {
+                        return Ok(Number::Exact(base.pow(e)));
+                    }
+                }
+            }
+        }
+        Ok(Number::Approx(self.to_f64().powf(exponent.to_f64())))
+    }
+
+    fn sqrt(self) -> Result<Number, String> {
+        if let Number::Exact(r) = &self {
+            if r.is_negative() {
+                return Err("Square root of negative number".to_string());
+            }
+            let (numer, denom) = (r.numer().sqrt(), r.denom().sqrt());
+            if &(&numer * &numer) == r.numer() && &(&denom * &denom) == r.denom() {
+                return Ok(Number::Exact(BigRational::new(numer, denom)));
+            }
+        }
+        let value = self.to_f64();
+        if value < 0.0 {
+            return Err("Square root of negative number".to_string());
+        }
+        Ok(Number::Approx(value.sqrt()))
+    }
+}
+
+/// Evaluate an arithmetic expression with exact rational arithmetic.
+/// Supports `+ - * / ^`, parentheses, decimals (read as exact fractions),
+/// constants and `sqrt sin cos tan ln exp abs`, as parsed by `Node::parse`.
+pub fn evaluate(expression: &str, constants: &HashMap<String, f64>) -> Result<ExactResult, String> {
+    let value = number(&Node::parse(expression)?, constants)?;
+
+    let approximation = value.to_f64();
+    Ok(match value {
+        Number::Exact(r) => ExactResult { value: format_rational(&r), exact: true, approximation },
+        Number::Approx(f) => ExactResult { value: f.to_string(), exact: false, approximation },
+    })
+}
+
+fn number(node: &Node, constants: &HashMap<String, f64>) -> Result<Number, String> {
+    let value = |node: &Node| number(node, constants);
+    match node {
+        Node::Number(value) => Ok(literal(*value)),
+        Node::Constant(name) | Node::Variable(name) => match constants.get(name) {
+            Some(value) => Ok(Number::Approx(*value)),
+            None if matches!(node, Node::Constant(_)) => node.evaluate(&HashMap::new()).map(Number::Approx),
+            None => Err(format!("Unknown constant '{}'", name)),
+        },
+        Node::Add(a, b) => Ok(value(a)?.add(value(b)?)),
+        Node::Sub(a, b) => Ok(value(a)?.sub(value(b)?)),
+        Node::Mul(a, b) => Ok(value(a)?.mul(value(b)?)),
+        Node::Div(a, b) => value(a)?.div(value(b)?),
+        Node::Pow(a, b) => value(a)?.pow(value(b)?),
+        Node::Neg(a) => Ok(value(a)?.neg()),
+        Node::Call(function, a) => apply_function(function.name(), value(a)?),
+    }
+}
+
+/// A literal as an exact fraction, so 12.34 is 1234/100. The parser keeps
+/// literals as `f64`, whose shortest decimal form is the literal as written
+/// up to 15 significant digits.
+fn literal(value: f64) -> Number {
+    let text = value.to_string();
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    match BigInt::parse_bytes(format!("{}{}", whole, fraction).as_bytes(), 10) {
+        Some(numer) => Number::Exact(BigRational::new(numer, BigInt::from(10u32).pow(fraction.len() as u32))),
+        None => Number::Approx(value),
+    }
+}
+
+fn format_rational(r: &BigRational) -> String {
+    if r.denom().is_one() {
+        r.numer().to_string()
+    } else {
+        format!("{}/{}", r.numer(), r.denom())
+    }
+}
+
+fn apply_function(name: &str, argument: Number) -> Result<Number, String> {
+    match name {
+        "sqrt" => argument.sqrt(),
+        "abs" => Ok(match argument {
+            Number::Exact(r) => Number::Exact(r.abs()),
+            Number::Approx(f) => Number::Approx(f.abs()),
+        }),
+        "sin" => Ok(Number::Approx(argument.to_f64().sin())),
+        "cos" => Ok(Number::Approx(argument.to_f64().cos())),
+        "tan" => Ok(Number::Approx(argument.to_f64().tan())),
+        "exp" => Ok(Number::Approx(argument.to_f64().exp())),
+        "ln" => {
+            let value = argument.to_f64();
+            if value <= 0.0 {
+                Err("Logarithm of non-positive number".to_string())
+            } else {
+                Ok(Number::Approx(value.ln()))
+            }
+        }
+        _ => Err(format!("Unknown function '{}'", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> ExactResult {
+        let constants = HashMap::from([("pi".to_string(), std::f64::consts::PI)]);
+        evaluate(expression, &constants).unwrap()
+    }
+
+    #[test]
+    fn test_fractions_stay_exact() {
+        let result = eval("1/3 + 1/6");
+        assert_eq!(result.value, "1/2");
+        assert!(result.exact);
+        assert_eq!(result.approximation, 0.5);
+
+        assert_eq!(eval("0.1 + 0.2").value, "3/10");
+        assert_eq!(eval("2^100 / 2^98").value, "4");
+        assert_eq!(eval("sqrt(9/4) - (1 - 3)").value, "7/2");
+        assert_eq!(eval("12.34 * 100").value, "1234");
+        assert_eq!(eval("3(1/3)").value, "1");
+    }
+
+    #[test]
+    fn test_irrational_operations_fall_back_to_floats() {
+        let result = eval("sqrt(2) + 1/2");
+        assert!(!result.exact);
+        assert!((result.approximation - (2f64.sqrt() + 0.5)).abs() < 1e-12);
+        assert!(!eval("pi * 2").exact);
+    }
+
+    #[test]
+    fn test_nested_powers_past_the_bit_budget_fall_back_to_floats() {
+        assert!(eval("2^4096").exact);
+        let result = eval("((9^4096)^4096)^4096");
+        assert!(!result.exact);
+        assert!(result.approximation.is_infinite());
+        // The budget counts the denominator too
+        assert!(eval("(2^4096)^8").exact);
+        assert!(!eval("(2^4096)^32").exact);
+        assert!(!eval("(1/2^4096)^32").exact);
+    }
+
+    #[test]
+    fn test_division_by_zero_is_an_error() {
+        assert!(evaluate("1/(2 - 2)", &HashMap::new()).is_err());
+    }
+}
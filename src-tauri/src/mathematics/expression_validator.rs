@@ -0,0 +1,413 @@
+use serde::{Deserialize, Serialize};
+
+/// Functions accepted in expressions
+const KNOWN_FUNCTIONS: &[&str] = &["sin", "cos", "tan", "sqrt", "ln", "exp", "abs"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationErrorKind {
+    EmptyExpression,
+    InvalidCharacter,
+    UnbalancedParentheses,
+    UnknownFunction,
+    BadOperator,
+    MissingOperator,
+    MissingOperand,
+}
+
+/// Where and why an expression failed to parse. `offset` is a byte offset
+/// into the submitted expression; `snippet` repeats the expression with a
+/// caret under the problem.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    pub offset: usize,
+    pub message: String,
+    pub snippet: String,
+    pub suggestion: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpressionValidation {
+    pub valid: bool,
+    pub error: Option<ValidationError>,
+}
+
+/// Check expression syntax: numbers, variables, constants, known
+/// functions, `+ - * / ^`, parentheses and at most one `=`.
+pub fn validate(expression: &str) -> ExpressionValidation {
+    let result = tokenize(expression).and_then(|tokens| Validator { tokens: &tokens, pos: 0, end: expression.len() }.run());
+    match result {
+        Ok(()) => ExpressionValidation { valid: true, error: None },
+        Err(issue) => ExpressionValidation {
+            valid: false,
+            error: Some(ValidationError {
+                kind: issue.kind,
+                offset: issue.offset,
+                snippet: caret_snippet(expression, issue.offset),
+                message: issue.message,
+                suggestion: issue.suggestion,
+            }),
+        },
+    }
+}
+
+fn caret_snippet(expression: &str, offset: usize) -> String {
+    let column = expression[..offset].chars().count();
+    format!("{}\n{}^", expression, " ".repeat(column))
+}
+
+struct Issue {
+    kind: ValidationErrorKind,
+    offset: usize,
+    message: String,
+    suggestion: Option<String>,
+}
+
+impl Issue {
+    fn new(kind: ValidationErrorKind, offset: usize, message: String) -> Self {
+        Self { kind, offset, message, suggestion: None }
+    }
+
+    fn suggest(mut self, suggestion: String) -> Self {
+        self.suggestion = Some(suggestion);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number,
+    Ident(String),
+    Operator(char),
+    Equals,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Issue> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut seen_point = c == '.';
+                while let Some(&(_, next)) = chars.peek() {
+                    if next.is_ascii_digit() || (next == '.' && !seen_point) {
+                        seen_point |= next == '.';
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Number
+            }
+            c if c.is_alphabetic() => {
+                let mut name = c.to_string();
+                while let Some(&(_, next)) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' {
+                        name.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Ident(name.to_lowercase())
+            }
+            '+' | '-' | '*' | '/' | '^' => TokenKind::Operator(c),
+            '=' => TokenKind::Equals,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '√' => {
+                return Err(Issue::new(ValidationErrorKind::InvalidCharacter, offset, "Unsupported character '√'".to_string())
+                    .suggest("Write square roots as sqrt(...)".to_string()))
+            }
+            other => {
+                return Err(Issue::new(
+                    ValidationErrorKind::InvalidCharacter,
+                    offset,
+                    format!("Unsupported character '{}'", other),
+                ))
+            }
+        };
+        tokens.push(Token { kind, offset });
+    }
+    Ok(tokens)
+}
+
+struct Validator<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    end: usize,
+}
+
+impl Validator<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn offset(&self) -> usize {
+        self.peek().map(|t| t.offset).unwrap_or(self.end)
+    }
+
+    fn run(mut self) -> Result<(), Issue> {
+        if self.tokens.is_empty() {
+            return Err(Issue::new(ValidationErrorKind::EmptyExpression, 0, "Expression is empty".to_string()));
+        }
+        self.side()?;
+        if let Some(token) = self.peek() {
+            if token.kind == TokenKind::Equals {
+                let equals = token.offset;
+                self.pos += 1;
+                if self.peek().is_none() {
+                    return Err(Issue::new(
+                        ValidationErrorKind::MissingOperand,
+                        self.end,
+                        "Equation has nothing after '='".to_string(),
+                    ));
+                }
+                self.side()?;
+                if let Some(token) = self.peek() {
+                    if token.kind == TokenKind::Equals {
+                        return Err(Issue::new(
+                            ValidationErrorKind::BadOperator,
+                            token.offset,
+                            format!("Only one '=' is allowed; the first is at offset {}", equals),
+                        ));
+                    }
+                }
+            }
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(self.unexpected(token)),
+        }
+    }
+
+    // side = operand (operator operand)*
+    fn side(&mut self) -> Result<(), Issue> {
+        self.operand()?;
+        while let Some(Token { kind: TokenKind::Operator(op), offset }) = self.peek().cloned() {
+            self.pos += 1;
+            match self.peek().map(|t| t.kind.clone()) {
+                None => {
+                    return Err(Issue::new(
+                        ValidationErrorKind::MissingOperand,
+                        offset,
+                        format!("Operator '{}' is missing its right-hand operand", op),
+                    ))
+                }
+                // a negative right operand (2 * -3) is fine; anything else is a doubled operator
+                Some(TokenKind::Operator(next)) if next != '-' => {
+                    let next_offset = self.offset();
+                    return Err(Issue::new(
+                        ValidationErrorKind::BadOperator,
+                        next_offset,
+                        format!("Unexpected operator '{}' after '{}'", next, op),
+                    )
+                    .suggest(format!("Remove the extra '{}'", next)));
+                }
+                _ => {}
+            }
+            self.operand()?;
+        }
+        Ok(())
+    }
+
+    // operand = '-'* primary, with implicit multiplication only for `2x`
+    fn operand(&mut self) -> Result<(), Issue> {
+        while let Some(TokenKind::Operator('-')) = self.peek().map(|t| t.kind.clone()) {
+            self.pos += 1;
+        }
+        if self.pos == 0 {
+            if let Some(TokenKind::Operator('+')) = self.peek().map(|t| t.kind.clone()) {
+                self.pos += 1;
+            }
+        }
+
+        let token = match self.peek().cloned() {
+            Some(token) => token,
+            None => {
+                return Err(Issue::new(
+                    ValidationErrorKind::MissingOperand,
+                    self.end,
+                    "Expression ends where an operand was expected".to_string(),
+                ))
+            }
+        };
+        self.pos += 1;
+
+        match &token.kind {
+            TokenKind::Number => {
+                // 2x is accepted as a coefficient, as in linear equations
+                if let Some(TokenKind::Ident(name)) = self.peek().map(|t| t.kind.clone()) {
+                    if !KNOWN_FUNCTIONS.contains(&name.as_str()) {
+                        self.pos += 1;
+                    }
+                }
+            }
+            TokenKind::Ident(name) => {
+                if self.peek().map(|t| &t.kind) == Some(&TokenKind::LParen) {
+                    if !KNOWN_FUNCTIONS.contains(&name.as_str()) {
+                        let issue = Issue::new(
+                            ValidationErrorKind::UnknownFunction,
+                            token.offset,
+                            format!("Unknown function '{}'", name),
+                        );
+                        return Err(match closest_function(name) {
+                            Some(function) => issue.suggest(format!("Did you mean '{}'?", function)),
+                            None => issue.suggest(format!("Known functions: {}", KNOWN_FUNCTIONS.join(", "))),
+                        });
+                    }
+                    self.group()?;
+                }
+            }
+            TokenKind::LParen => {
+                self.pos -= 1;
+                self.group()?;
+            }
+            TokenKind::RParen => {
+                return Err(Issue::new(
+                    ValidationErrorKind::UnbalancedParentheses,
+                    token.offset,
+                    "Closing ')' without a matching '('".to_string(),
+                ))
+            }
+            TokenKind::Operator(op) => {
+                return Err(Issue::new(
+                    ValidationErrorKind::BadOperator,
+                    token.offset,
+                    format!("Operator '{}' is missing its left-hand operand", op),
+                ))
+            }
+            TokenKind::Equals => {
+                return Err(Issue::new(
+                    ValidationErrorKind::MissingOperand,
+                    token.offset,
+                    "Expected an operand before '='".to_string(),
+                ))
+            }
+        }
+
+        // an operand directly followed by another operand needs an operator
+        if let Some(next) = self.peek() {
+            if matches!(next.kind, TokenKind::Number | TokenKind::Ident(_) | TokenKind::LParen) {
+                return Err(Issue::new(
+                    ValidationErrorKind::MissingOperator,
+                    next.offset,
+                    "Missing operator between operands".to_string(),
+                )
+                .suggest("Insert '*' here".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    // group = '(' side ')'
+    fn group(&mut self) -> Result<(), Issue> {
+        let open = self.offset();
+        self.pos += 1;
+        if self.peek().map(|t| &t.kind) == Some(&TokenKind::RParen) {
+            return Err(Issue::new(ValidationErrorKind::MissingOperand, self.offset(), "Empty parentheses".to_string()));
+        }
+        self.side()?;
+        match self.peek() {
+            Some(Token { kind: TokenKind::RParen, .. }) => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(token) if token.kind != TokenKind::Equals => Err(self.unexpected(token)),
+            _ => Err(Issue::new(
+                ValidationErrorKind::UnbalancedParentheses,
+                open,
+                "Opening '(' is never closed".to_string(),
+            )
+            .suggest("Add a closing ')'".to_string())),
+        }
+    }
+
+    fn unexpected(&self, token: &Token) -> Issue {
+        match token.kind {
+            TokenKind::RParen => Issue::new(
+                ValidationErrorKind::UnbalancedParentheses,
+                token.offset,
+                "Closing ')' without a matching '('".to_string(),
+            ),
+            _ => Issue::new(ValidationErrorKind::MissingOperator, token.offset, "Unexpected token".to_string()),
+        }
+    }
+}
+
+/// Nearest known function within two edits, for typo suggestions
+fn closest_function(name: &str) -> Option<&'static str> {
+    KNOWN_FUNCTIONS
+        .iter()
+        .map(|f| (*f, edit_distance(name, f)))
+        .filter(|(_, distance)| *distance <= 2)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(f, _)| f)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb { previous } else { 1 + previous.min(row[j]).min(current) };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(expression: &str) -> ValidationError {
+        validate(expression).error.expect("expected a validation error")
+    }
+
+    #[test]
+    fn test_doubled_operator_points_at_second_plus() {
+        let err = error("2 ++ 3");
+        assert_eq!(err.kind, ValidationErrorKind::BadOperator);
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.message, "Unexpected operator '+' after '+'");
+        assert_eq!(err.snippet, "2 ++ 3\n   ^");
+        assert_eq!(err.suggestion.as_deref(), Some("Remove the extra '+'"));
+    }
+
+    #[test]
+    fn test_valid_expressions() {
+        for expression in ["2x + 3 = 7", "-sin(pi / 2)^2", "1/3 + 1/6", "2 * -3", "sqrt((4 + 5) * 2)"] {
+            assert!(validate(expression).valid, "{} should be valid", expression);
+        }
+    }
+
+    #[test]
+    fn test_error_kinds_and_suggestions() {
+        let err = error("2(3 + 1)");
+        assert_eq!((err.kind, err.offset), (ValidationErrorKind::MissingOperator, 1));
+        assert_eq!(err.suggestion.as_deref(), Some("Insert '*' here"));
+
+        let err = error("sqr(4)");
+        assert_eq!((err.kind, err.offset), (ValidationErrorKind::UnknownFunction, 0));
+        assert_eq!(err.suggestion.as_deref(), Some("Did you mean 'sqrt'?"));
+
+        assert_eq!(error("(1 + 2").kind, ValidationErrorKind::UnbalancedParentheses);
+        assert_eq!(error("1 + 2)").offset, 5);
+        assert_eq!(error("4 *").kind, ValidationErrorKind::MissingOperand);
+        assert_eq!(error("3 # 4").kind, ValidationErrorKind::InvalidCharacter);
+    }
+}
@@ -1,9 +1,35 @@
 pub mod engine;
+pub mod constants;
+pub mod benchmark;
 pub mod complexity_analyzer;
+pub mod concepts;
+pub mod data_import;
 pub mod numerical_methods;
 pub mod symbolic_math;
+pub mod exact_arithmetic;
+pub mod distributions;
+pub mod recurrence;
+pub mod units;
+pub mod differentiation;
+pub mod proof;
+pub mod expression_validator;
+pub mod code_rewriter;
+pub mod differential_testing;
+pub mod result_cache;
 
-pub use engine::MathematicsEngine;
+pub use engine::{MathematicsEngine, MathEngineConfig, DatasetAnalysis};
+pub use result_cache::CacheStats;
+pub use benchmark::BenchmarkResult;
 pub use complexity_analyzer::{ComplexityAnalyzer, AlgorithmComplexity, ComplexityResult};
-pub use numerical_methods::{NumericalMethods, LinearAlgebra, Statistics};
+pub use recurrence::{Recurrence, RecurrenceMethod};
+pub use data_import::{DataSource, ImportedSample, SkippedRow};
+pub use concepts::{ConceptExplanation, ExplanationLevel, WorkedExample};
+pub use numerical_methods::{HypothesisTest, NumericalMethods, LinearAlgebra, OdeMethod, OdeSolution, Statistics, TestResult};
 pub use symbolic_math::{SymbolicMath, Expression, MathResult};
+pub use exact_arithmetic::ExactResult;
+pub use units::{Dimension, Quantity};
+pub use proof::{ProofMethod, ProofStep, ProofTrace};
+pub use code_rewriter::{CodeRewrite, RewritePattern};
+pub use differential_testing::{Counterexample, DifferentialConfig, DifferentialReport};
+pub use expression_validator::{ExpressionValidation, ValidationError, ValidationErrorKind};
+pub use constants::ConstantValue;
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::differentiation::Node;
+use super::distributions;
 use super::symbolic_math::MathResult;
 
+/// Steps an ODE solve may take before giving up
+const MAX_ODE_STEPS: usize = 100_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NumericalMethods {
     precision: f64,
@@ -17,6 +22,14 @@ impl NumericalMethods {
         }
     }
 
+    pub fn set_precision(&mut self, precision: f64) {
+        self.precision = precision;
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: usize) {
+        self.max_iterations = max_iterations;
+    }
+
     /// Compute using various numerical methods
     pub async fn compute(&self, method: &str, function: &str, parameters: HashMap<String, f64>) -> MathResult {
         match method.to_lowercase().as_str() {
@@ -228,6 +241,169 @@ impl NumericalMethods {
     }
 }
 
+/// Integration scheme for `NumericalMethods::solve_ode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OdeMethod {
+    /// Classic fourth-order Runge-Kutta at a fixed step
+    Rk4,
+    /// Dormand-Prince 5(4), adapting the step to keep the local error
+    /// estimate within tolerance
+    #[default]
+    Rk45,
+}
+
+/// Sampled trajectory of an ODE solve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OdeSolution {
+    pub method: OdeMethod,
+    pub times: Vec<f64>,
+    /// State at each of `times`
+    pub states: Vec<Vec<f64>>,
+    /// Steps rejected by step-size control and retried smaller
+    pub rejected_steps: usize,
+    /// Largest local error estimate of an accepted step; `None` for fixed-step RK4
+    pub max_error_estimate: Option<f64>,
+}
+
+// Dormand-Prince 5(4) tableau
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [[f64; 6]; 7] = [
+    [0.0; 6],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+/// Fifth-order weights, the same as the last stage's row
+const DP_B: [f64; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+/// Embedded fourth-order weights, used only for the error estimate
+const DP_B4: [f64; 7] = [
+    5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0, 187.0 / 2100.0, 1.0 / 40.0,
+];
+
+/// `y + h * sum(weights[i] * k[i])`
+fn ode_combine(y: &[f64], h: f64, weights: &[f64], k: &[Vec<f64>]) -> Vec<f64> {
+    y.iter()
+        .enumerate()
+        .map(|(i, yi)| yi + h * weights.iter().zip(k).map(|(w, ki)| w * ki[i]).sum::<f64>())
+        .collect()
+}
+
+/// Right-hand side of `y' = f(t, y)` from one expression per component.
+/// Expressions see `t`, each component as `y0`, `y1`, ... and the first
+/// as plain `y`.
+pub fn ode_system(equations: Vec<Node>) -> impl Fn(f64, &[f64]) -> Result<Vec<f64>, String> {
+    move |t, y| {
+        let mut variables: HashMap<String, f64> = y.iter().enumerate().map(|(i, yi)| (format!("y{}", i), *yi)).collect();
+        variables.insert("t".to_string(), t);
+        if let Some(first) = y.first() {
+            variables.insert("y".to_string(), *first);
+        }
+        equations.iter().map(|equation| equation.evaluate(&variables)).collect()
+    }
+}
+
+impl NumericalMethods {
+    /// Integrate `y' = f(t, y)` from `y0` over `t_span`. RK4 samples every
+    /// `step`; RK45 starts at `step` and samples wherever step-size control
+    /// lands, keeping each step's local error under the solver precision.
+    pub fn solve_ode<F>(&self, f: F, y0: &[f64], t_span: (f64, f64), step: f64, method: OdeMethod) -> Result<OdeSolution, String>
+    where
+        F: Fn(f64, &[f64]) -> Result<Vec<f64>, String>,
+    {
+        let (t_start, t_end) = t_span;
+        if t_end <= t_start || !t_start.is_finite() || !t_end.is_finite() {
+            return Err(format!("Time span must run forwards, got {} to {}", t_start, t_end));
+        }
+        if step <= 0.0 || !step.is_finite() {
+            return Err(format!("Step size must be positive, got {}", step));
+        }
+        if y0.is_empty() {
+            return Err("Initial state is empty".to_string());
+        }
+        let rhs = |t: f64, y: &[f64]| -> Result<Vec<f64>, String> {
+            let dy = f(t, y)?;
+            if dy.len() != y.len() {
+                return Err(format!("Right-hand side returned {} values for a {}-dimensional state", dy.len(), y.len()));
+            }
+            if dy.iter().any(|value| !value.is_finite()) {
+                return Err(format!("Right-hand side is not finite at t = {}", t));
+            }
+            Ok(dy)
+        };
+
+        let mut solution = OdeSolution {
+            method,
+            times: vec![t_start],
+            states: vec![y0.to_vec()],
+            rejected_steps: 0,
+            max_error_estimate: match method {
+                OdeMethod::Rk4 => None,
+                OdeMethod::Rk45 => Some(0.0),
+            },
+        };
+        let (mut t, mut y, mut h) = (t_start, y0.to_vec(), step);
+        // Stop within rounding of the end rather than taking a sliver of a step
+        let end_slack = (t_end - t_start) * 1e-12;
+        let mut steps = 0;
+        while t_end - t > end_slack {
+            steps += 1;
+            if steps > MAX_ODE_STEPS {
+                return Err(format!("Gave up after {} steps at t = {}", MAX_ODE_STEPS, t));
+            }
+            let h_step = h.min(t_end - t);
+            match method {
+                OdeMethod::Rk4 => {
+                    let k1 = rhs(t, &y)?;
+                    let k2 = rhs(t + h_step / 2.0, &ode_combine(&y, h_step / 2.0, &[1.0], std::slice::from_ref(&k1)))?;
+                    let k3 = rhs(t + h_step / 2.0, &ode_combine(&y, h_step / 2.0, &[1.0], std::slice::from_ref(&k2)))?;
+                    let k4 = rhs(t + h_step, &ode_combine(&y, h_step, &[1.0], std::slice::from_ref(&k3)))?;
+                    y = ode_combine(&y, h_step, &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0], &[k1, k2, k3, k4]);
+                    t += h_step;
+                }
+                OdeMethod::Rk45 => {
+                    let mut k: Vec<Vec<f64>> = Vec::with_capacity(7);
+                    for stage in 0..7 {
+                        let stage_y = ode_combine(&y, h_step, &DP_A[stage][..stage], &k);
+                        k.push(rhs(t + DP_C[stage] * h_step, &stage_y)?);
+                    }
+                    let next = ode_combine(&y, h_step, &DP_B, &k);
+                    // Difference between the fifth- and fourth-order solutions
+                    let weights: Vec<f64> = DP_B.iter().zip(DP_B4).map(|(fifth, fourth)| fifth - fourth).collect();
+                    let error: Vec<f64> = ode_combine(&vec![0.0; y.len()], h_step, &weights, &k).iter().map(|e| e.abs()).collect();
+                    // Mixed absolute/relative error, 1.0 is exactly at tolerance
+                    let ratio = error.iter()
+                        .enumerate()
+                        .map(|(i, e)| e / (self.precision * (1.0 + y[i].abs().max(next[i].abs()))))
+                        .fold(0.0, f64::max);
+                    let scale = if ratio == 0.0 { 5.0 } else { (0.9 * ratio.powf(-0.2)).clamp(0.2, 5.0) };
+                    if ratio <= 1.0 {
+                        t += h_step;
+                        y = next;
+                        let achieved = error.iter().copied().fold(0.0, f64::max);
+                        solution.max_error_estimate = solution.max_error_estimate.map(|max| max.max(achieved));
+                    } else {
+                        solution.rejected_steps += 1;
+                    }
+                    h = h_step * scale;
+                    if h < (t_end - t_start) * f64::EPSILON {
+                        return Err(format!("Step size underflow at t = {}; the system may be stiff", t));
+                    }
+                    if ratio > 1.0 {
+                        continue;
+                    }
+                }
+            }
+            solution.times.push(t);
+            solution.states.push(y.clone());
+        }
+        Ok(solution)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinearAlgebra {
     precision: f64,
@@ -240,6 +416,10 @@ impl LinearAlgebra {
         }
     }
 
+    pub fn set_precision(&mut self, precision: f64) {
+        self.precision = precision;
+    }
+
     pub async fn perform_operation(&self, operation: &str, matrices: Vec<Vec<f64>>) -> MathResult {
         match operation.to_lowercase().as_str() {
             "determinant" => self.determinant(&matrices[0]).await,
@@ -410,6 +590,215 @@ impl LinearAlgebra {
     }
 }
 
+/// Hypothesis test to run on a sample, with its test-specific inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "test", rename_all = "snake_case")]
+pub enum HypothesisTest {
+    /// One-sample against `mu`, or two-sample against `other`: Welch's
+    /// unequal-variance test unless `equal_variance` is set
+    TTest {
+        #[serde(default)]
+        mu: f64,
+        #[serde(default)]
+        other: Option<Vec<f64>>,
+        #[serde(default)]
+        equal_variance: bool,
+    },
+    /// Goodness of fit of observed counts to `expected`, uniform when omitted
+    ChiSquare {
+        #[serde(default)]
+        expected: Option<Vec<f64>>,
+    },
+    /// Rank-sum comparison with `other`
+    MannWhitneyU { other: Vec<f64> },
+}
+
+/// Outcome of a hypothesis test. p-values are two-sided, except for
+/// chi-square which only has an upper tail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+    pub test: String,
+    pub statistic: f64,
+    pub degrees_of_freedom: Option<f64>,
+    pub p_value: f64,
+}
+
+/// Largest sample size for exact Mann-Whitney p-values; larger samples, or
+/// any ties, use the normal approximation
+const MANN_WHITNEY_EXACT_MAX: usize = 49;
+
+fn mean_and_variance(sample: &[f64]) -> (f64, f64) {
+    let n = sample.len() as f64;
+    let mean = sample.iter().sum::<f64>() / n;
+    let variance = sample.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// Midranks of `values`, 1-based, and the size of each group of ties
+fn ranks(values: &[f64]) -> (Vec<f64>, Vec<usize>) {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut ties = Vec::new();
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let midrank = (start + end + 1) as f64 / 2.0;
+        for index in &order[start..end] {
+            ranks[*index] = midrank;
+        }
+        ties.push(end - start);
+        start = end;
+    }
+    (ranks, ties)
+}
+
+/// P(U <= u) for each u under the null hypothesis, with no ties
+fn mann_whitney_cdf(n1: usize, n2: usize) -> Vec<f64> {
+    // p(i, j, u) = i/(i+j) p(i-1, j, u-j) + j/(i+j) p(i, j-1, u), a row of i at a time
+    let mut previous: Vec<Vec<f64>> = (0..=n2).map(|_| vec![1.0]).collect();
+    for i in 1..=n1 {
+        let mut current: Vec<Vec<f64>> = vec![vec![1.0]];
+        for j in 1..=n2 {
+            let total = (i + j) as f64;
+            let mut distribution = vec![0.0; i * j + 1];
+            for (u, p) in previous[j].iter().enumerate() {
+                distribution[u + j] += p * i as f64 / total;
+            }
+            for (u, p) in current[j - 1].iter().enumerate() {
+                distribution[u] += p * j as f64 / total;
+            }
+            current.push(distribution);
+        }
+        previous = current;
+    }
+    previous[n2].iter()
+        .scan(0.0, |cumulative, p| {
+            *cumulative += p;
+            Some(*cumulative)
+        })
+        .collect()
+}
+
+impl Statistics {
+    /// Student's t-test of `sample` against a mean of `mu`, or against
+    /// `other` when given
+    pub fn t_test(&self, sample: &[f64], other: Option<&[f64]>, mu: f64, equal_variance: bool) -> Result<TestResult, String> {
+        if sample.len() < 2 {
+            return Err("A t-test needs at least 2 values in the sample".to_string());
+        }
+        let (mean, variance) = mean_and_variance(sample);
+        let n = sample.len() as f64;
+        let (test, t, df) = match other {
+            None => {
+                if variance == 0.0 {
+                    return Err("Sample has no variance".to_string());
+                }
+                ("One-sample t-test", (mean - mu) / (variance / n).sqrt(), n - 1.0)
+            }
+            Some(other) if other.len() < 2 => return Err("A t-test needs at least 2 values in each sample".to_string()),
+            Some(other) => {
+                let (other_mean, other_variance) = mean_and_variance(other);
+                let m = other.len() as f64;
+                if variance == 0.0 && other_variance == 0.0 {
+                    return Err("Samples have no variance".to_string());
+                }
+                if equal_variance {
+                    let pooled = ((n - 1.0) * variance + (m - 1.0) * other_variance) / (n + m - 2.0);
+                    ("Two-sample t-test (pooled variance)", (mean - other_mean) / (pooled * (1.0 / n + 1.0 / m)).sqrt(), n + m - 2.0)
+                } else {
+                    let (a, b) = (variance / n, other_variance / m);
+                    // Welch-Satterthwaite degrees of freedom
+                    let df = (a + b).powi(2) / (a * a / (n - 1.0) + b * b / (m - 1.0));
+                    ("Welch two-sample t-test", (mean - other_mean) / (a + b).sqrt(), df)
+                }
+            }
+        };
+        Ok(TestResult {
+            test: test.to_string(),
+            statistic: t,
+            degrees_of_freedom: Some(df),
+            p_value: distributions::student_t_two_sided(t, df),
+        })
+    }
+
+    /// Pearson's chi-square goodness of fit. `expected` is scaled to the
+    /// observed total, so proportions work as well as counts.
+    pub fn chi_square(&self, observed: &[f64], expected: Option<&[f64]>) -> Result<TestResult, String> {
+        if observed.len() < 2 {
+            return Err("A chi-square test needs at least 2 categories".to_string());
+        }
+        if observed.iter().any(|count| *count < 0.0) {
+            return Err("Observed counts can't be negative".to_string());
+        }
+        let uniform = vec![1.0; observed.len()];
+        let expected = expected.unwrap_or(&uniform);
+        if expected.len() != observed.len() {
+            return Err(format!("{} expected values for {} observed categories", expected.len(), observed.len()));
+        }
+        if expected.iter().any(|value| *value <= 0.0) {
+            return Err("Expected values must be positive".to_string());
+        }
+        let scale = observed.iter().sum::<f64>() / expected.iter().sum::<f64>();
+        let statistic: f64 = observed.iter()
+            .zip(expected)
+            .map(|(o, e)| (o - e * scale).powi(2) / (e * scale))
+            .sum();
+        let df = (observed.len() - 1) as f64;
+        Ok(TestResult {
+            test: "Chi-square goodness of fit".to_string(),
+            statistic,
+            degrees_of_freedom: Some(df),
+            p_value: distributions::chi_square_upper(statistic, df),
+        })
+    }
+
+    /// Mann-Whitney U test; the statistic is U for `sample`. Exact for small
+    /// samples without ties, otherwise the normal approximation with tie
+    /// and continuity corrections.
+    pub fn mann_whitney_u(&self, sample: &[f64], other: &[f64]) -> Result<TestResult, String> {
+        if sample.is_empty() || other.is_empty() {
+            return Err("A Mann-Whitney test needs values in both samples".to_string());
+        }
+        let (n1, n2) = (sample.len(), other.len());
+        let combined: Vec<f64> = sample.iter().chain(other).copied().collect();
+        let (ranks, ties) = ranks(&combined);
+        let rank_sum: f64 = ranks[..n1].iter().sum();
+        let u = rank_sum - (n1 * (n1 + 1)) as f64 / 2.0;
+        let has_ties = ties.iter().any(|group| *group > 1);
+
+        let p_value = if !has_ties && n1 <= MANN_WHITNEY_EXACT_MAX && n2 <= MANN_WHITNEY_EXACT_MAX {
+            let cdf = mann_whitney_cdf(n1, n2);
+            let u = u.round() as usize;
+            let lower = cdf[u];
+            let upper = if u == 0 { 1.0 } else { 1.0 - cdf[u - 1] };
+            (2.0 * lower.min(upper)).min(1.0)
+        } else {
+            let n = (n1 + n2) as f64;
+            let tie_correction: f64 = ties.iter().map(|t| (t * t * t - t) as f64).sum::<f64>() / (n * (n - 1.0));
+            let sigma = ((n1 * n2) as f64 / 12.0 * (n + 1.0 - tie_correction)).sqrt();
+            if sigma == 0.0 {
+                return Err("Every value is tied".to_string());
+            }
+            let z = ((u - (n1 * n2) as f64 / 2.0).abs() - 0.5).max(0.0) / sigma;
+            distributions::normal_two_sided(z)
+        };
+        Ok(TestResult { test: "Mann-Whitney U test".to_string(), statistic: u, degrees_of_freedom: None, p_value })
+    }
+
+    /// Run `test` with `sample` as the first sample, or the observed counts
+    pub fn hypothesis_test(&self, sample: &[f64], test: &HypothesisTest) -> Result<TestResult, String> {
+        match test {
+            HypothesisTest::TTest { mu, other, equal_variance } => self.t_test(sample, other.as_deref(), *mu, *equal_variance),
+            HypothesisTest::ChiSquare { expected } => self.chi_square(sample, expected.as_deref()),
+            HypothesisTest::MannWhitneyU { other } => self.mann_whitney_u(sample, other),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
     precision: f64,
@@ -422,6 +811,10 @@ impl Statistics {
         }
     }
 
+    pub fn set_precision(&mut self, precision: f64) {
+        self.precision = precision;
+    }
+
     pub async fn analyze(&self, data: &[f64], analysis_type: &str) -> MathResult {
         if data.is_empty() {
             return MathResult::Error("Cannot analyze empty dataset".to_string());
@@ -573,3 +966,94 @@ impl Statistics {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ode_solvers_match_exponential_growth_and_harmonic_oscillator() {
+        let methods = NumericalMethods::new().await;
+
+        // y' = y, y(0) = 1 is e^t
+        let growth = |_t: f64, y: &[f64]| Ok(vec![y[0]]);
+        let rk4 = methods.solve_ode(growth, &[1.0], (0.0, 1.0), 0.01, OdeMethod::Rk4).unwrap();
+        assert_eq!(rk4.times.len(), 101);
+        assert!((rk4.times[100] - 1.0).abs() < 1e-12);
+        assert!((rk4.states[100][0] - std::f64::consts::E).abs() < 1e-8);
+        assert_eq!(rk4.max_error_estimate, None);
+
+        let rk45 = methods.solve_ode(growth, &[1.0], (0.0, 1.0), 0.1, OdeMethod::Rk45).unwrap();
+        assert_eq!(*rk45.times.last().unwrap(), 1.0);
+        assert!((rk45.states.last().unwrap()[0] - std::f64::consts::E).abs() < 1e-8);
+        let achieved = rk45.max_error_estimate.unwrap();
+        assert!(achieved > 0.0 && achieved < 1e-9, "error estimate {}", achieved);
+        assert!(rk45.times.len() < rk4.times.len());
+
+        // x'' = -x as y0' = y1, y1' = -y0 is (cos t, -sin t), parsed from expressions
+        let oscillator = ode_system(vec![Node::parse("y1").unwrap(), Node::parse("-y0").unwrap()]);
+        let period = 2.0 * std::f64::consts::PI;
+        for method in [OdeMethod::Rk4, OdeMethod::Rk45] {
+            let solution = methods.solve_ode(&oscillator, &[1.0, 0.0], (0.0, period), 0.01, method).unwrap();
+            for (t, state) in solution.times.iter().zip(&solution.states) {
+                assert!((state[0] - t.cos()).abs() < 1e-6 && (state[1] + t.sin()).abs() < 1e-6, "{:?} at t = {}", method, t);
+            }
+            assert_eq!(*solution.times.last().unwrap(), period);
+        }
+
+        // Expressions see t and plain y; bad inputs are errors
+        let driven = ode_system(vec![Node::parse("2 * t - y").unwrap()]);
+        assert_eq!(driven(1.0, &[0.5]).unwrap(), vec![1.5]);
+        assert!(ode_system(vec![Node::parse("z").unwrap()])(0.0, &[1.0]).unwrap_err().contains("Unknown variable"));
+        assert!(methods.solve_ode(growth, &[1.0], (1.0, 0.0), 0.1, OdeMethod::Rk4).is_err());
+        assert!(methods.solve_ode(growth, &[1.0, 2.0], (0.0, 1.0), 0.1, OdeMethod::Rk4).unwrap_err().contains("2-dimensional"));
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 5e-4, "{} != {}", actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_hypothesis_tests_match_textbook_examples() {
+        let statistics = Statistics::new().await;
+        // Student's sleep data: extra hours of sleep under two drugs
+        let drug_1 = [0.7, -1.6, -0.2, -1.2, -0.1, 3.4, 3.7, 0.8, 0.0, 2.0];
+        let drug_2 = [1.9, 0.8, 1.1, 0.1, -0.1, 4.4, 5.5, 1.6, 4.6, 3.4];
+
+        let one_sample = statistics.t_test(&drug_1, None, 0.0, false).unwrap();
+        assert_close(one_sample.statistic, 1.3257);
+        assert_eq!(one_sample.degrees_of_freedom, Some(9.0));
+        assert_close(one_sample.p_value, 0.2176);
+
+        let welch = statistics.t_test(&drug_1, Some(&drug_2), 0.0, false).unwrap();
+        assert_close(welch.statistic, -1.8608);
+        assert_close(welch.degrees_of_freedom.unwrap(), 17.7765);
+        assert_close(welch.p_value, 0.07939);
+
+        let pooled = statistics.t_test(&drug_1, Some(&drug_2), 0.0, true).unwrap();
+        assert_eq!(pooled.degrees_of_freedom, Some(18.0));
+        assert_close(pooled.p_value, 0.07919);
+
+        // Mendel's peas against a 9:3:3:1 ratio
+        let peas = statistics.chi_square(&[315.0, 108.0, 101.0, 32.0], Some(&[9.0, 3.0, 3.0, 1.0])).unwrap();
+        assert_close(peas.statistic, 0.4700);
+        assert_eq!(peas.degrees_of_freedom, Some(3.0));
+        assert_close(peas.p_value, 0.9254);
+
+        // Exact for small samples without ties
+        let x = [0.80, 0.83, 1.89, 1.04, 1.45, 1.38, 1.91, 1.64, 0.73, 1.46];
+        let y = [1.15, 0.88, 0.90, 0.74, 1.21];
+        let exact = statistics.mann_whitney_u(&x, &y).unwrap();
+        assert_eq!(exact.statistic, 35.0);
+        assert_close(exact.p_value, 0.2544);
+        // Ties switch to the normal approximation
+        let tied = statistics.mann_whitney_u(&[1.0, 2.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 3.0, 3.0, 6.0, 7.0, 8.0]).unwrap();
+        assert_eq!(tied.statistic, 9.0);
+        assert_close(tied.p_value, 0.1674);
+
+        let test = HypothesisTest::TTest { mu: 0.0, other: None, equal_variance: false };
+        assert_eq!(statistics.hypothesis_test(&drug_1, &test).unwrap(), one_sample);
+        assert!(statistics.t_test(&[1.0], None, 0.0, false).is_err());
+        assert!(statistics.chi_square(&[1.0, 2.0], Some(&[1.0])).is_err());
+    }
+}
@@ -0,0 +1,598 @@
+//! Step-by-step proofs for the claims the symbolic engine can check on its
+//! own: polynomial identities, reduced to a normal form by expanding powers,
+//! distributing products and collecting like terms, and sum formulas,
+//! proved by induction with the same reductions. Every step is a
+//! transformation that was actually applied, so a trace can be shown to a
+//! student as it stands.
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::differentiation::Node;
+
+/// Largest power of a sum written out as a repeated product
+const MAX_EXPANDED_EXPONENT: u32 = 12;
+
+/// Terms a distributed product may reach before the proof gives up
+const MAX_TERMS: usize = 4096;
+
+/// Largest starting index tried when inferring where `1 + 2 + ... + n` starts
+const MAX_INFERRED_START: i64 = 2;
+
+/// One line of a proof: what is claimed and why it follows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub statement: String,
+    pub justification: String,
+}
+
+impl ProofStep {
+    fn new(statement: impl Into<String>, justification: impl Into<String>) -> Self {
+        Self { statement: statement.into(), justification: justification.into() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofMethod {
+    /// Both sides reduced to the same polynomial
+    Algebraic,
+    /// Base case plus inductive step for a sum formula
+    Induction,
+    /// Not provable symbolically; `narrative` holds a model-written outline
+    Narrative,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofTrace {
+    pub theorem: String,
+    pub method: ProofMethod,
+    pub steps: Vec<ProofStep>,
+    /// Whether the steps establish the theorem; false when they show it fails
+    pub verified: bool,
+    #[serde(default)]
+    pub narrative: Option<String>,
+}
+
+/// Variable name to exponent; empty for a constant
+type Monomial = BTreeMap<String, u32>;
+
+/// Normal form: nonzero coefficient per monomial
+type Polynomial = BTreeMap<Monomial, BigRational>;
+
+/// One product of a distributed sum, with its factors in the order they
+/// were multiplied so `a * b + b * a` shows before being collected
+#[derive(Debug, Clone)]
+struct Term {
+    coefficient: BigRational,
+    factors: Vec<(String, u32)>,
+}
+
+/// A sum `term(start) + ... + term(upper)` with `upper` a variable
+struct SumClaim {
+    term: Node,
+    index: String,
+    start: i64,
+    upper: String,
+}
+
+/// Prove `left = right`. Sums written `1 + 2 + ... + n` or
+/// `sum(k, k, 1, n)` on the left are proved by induction on the upper
+/// bound; anything else must be a polynomial identity.
+pub fn prove(theorem: &str) -> Result<ProofTrace, String> {
+    let (left, right) = match theorem.split('=').collect::<Vec<_>>().as_slice() {
+        [left, right] => (*left, *right),
+        _ => return Err("Expected a claim of the form 'left = right'".to_string()),
+    };
+    let right = Node::parse(right)?;
+    let (method, steps, verified) = match parse_sum(left)? {
+        Some(claim) => {
+            let (steps, verified) = prove_by_induction(&claim, &right)?;
+            (ProofMethod::Induction, steps, verified)
+        }
+        None => {
+            let (steps, verified) = prove_identity(&Node::parse(left)?, &right)?;
+            (ProofMethod::Algebraic, steps, verified)
+        }
+    };
+    Ok(ProofTrace { theorem: theorem.trim().to_string(), method, steps, verified, narrative: None })
+}
+
+fn prove_identity(left: &Node, right: &Node) -> Result<(Vec<ProofStep>, bool), String> {
+    let mut steps = vec![ProofStep::new(format!("{} = {}", left, right), "Claim: both sides reduce to the same polynomial")];
+    let (left_steps, left_form) = reduce(left)?;
+    let (right_steps, right_form) = reduce(right)?;
+    steps.extend(left_steps);
+    steps.extend(right_steps);
+
+    let verified = left_form == right_form;
+    steps.push(if verified {
+        ProofStep::new(format!("{} = {}", left, right), format!("Both sides reduce to {}", format_polynomial(&left_form)))
+    } else {
+        ProofStep::new(
+            format!("{} ≠ {}", left, right),
+            format!("The sides reduce to different polynomials; left minus right is {}", format_polynomial(&subtract(&left_form, &right_form))),
+        )
+    });
+    Ok((steps, verified))
+}
+
+fn prove_by_induction(claim: &SumClaim, closed_form: &Node) -> Result<(Vec<ProofStep>, bool), String> {
+    let n = Node::Variable(claim.upper.clone());
+    let next = shifted(&n, &claim.upper);
+    let claim_text = format!("{} = {} for all {} ≥ {}", sum_text(claim, &n)?, closed_form, claim.upper, claim.start);
+    let mut steps = vec![ProofStep::new(claim_text.clone(), format!("Claim, proved by induction on {}", claim.upper))];
+
+    // Base case: a single term against the formula
+    let start = Node::Number(claim.start as f64);
+    let first = constant_value(&claim.term.substitute(&claim.index, &start))?;
+    let formula_at_start = closed_form.substitute(&claim.upper, &start);
+    let formula_value = constant_value(&formula_at_start)?;
+    let base_holds = first == formula_value;
+    steps.push(ProofStep::new(
+        format!("{} {} {}", format_rational(&first), if base_holds { "=" } else { "≠" }, formula_at_start),
+        if base_holds {
+            format!("Base case {} = {}: both sides equal {}", claim.upper, claim.start, format_rational(&first))
+        } else {
+            format!(
+                "Base case {} = {} fails: the sum is {} but the formula gives {}",
+                claim.upper, claim.start, format_rational(&first), format_rational(&formula_value)
+            )
+        },
+    ));
+    if !base_holds {
+        return Ok((steps, false));
+    }
+
+    steps.push(ProofStep::new(
+        format!("{} = {}", sum_text(claim, &n)?, closed_form),
+        format!("Inductive hypothesis: assume the claim for some {} ≥ {}", claim.upper, claim.start),
+    ));
+    let last = fold_offsets(&claim.term.substitute(&claim.index, &next));
+    let split = Node::Add(Box::new(closed_form.clone()), Box::new(last));
+    steps.push(ProofStep::new(
+        format!("{} = {}", sum_text(claim, &next)?, split),
+        "Split off the last term and apply the inductive hypothesis",
+    ));
+    let target = shifted(closed_form, &claim.upper);
+    let (split_steps, split_form) = reduce(&split)?;
+    let (target_steps, target_form) = reduce(&target)?;
+    steps.extend(split_steps);
+    steps.extend(target_steps);
+
+    if split_form != target_form {
+        steps.push(ProofStep::new(
+            format!("{} ≠ {}", split, target),
+            format!(
+                "Inductive step fails: the sum up to {} + 1 and the formula at {} + 1 differ by {}",
+                claim.upper, claim.upper, format_polynomial(&subtract(&split_form, &target_form))
+            ),
+        ));
+        return Ok((steps, false));
+    }
+    steps.push(ProofStep::new(
+        format!("{} = {}", sum_text(claim, &next)?, target),
+        format!(
+            "Both reduce to {}, so the claim for {} implies the claim for {} + 1",
+            format_polynomial(&split_form), claim.upper, claim.upper
+        ),
+    ));
+    steps.push(ProofStep::new(
+        claim_text,
+        format!("By induction from the base case {} = {} and the inductive step", claim.upper, claim.start),
+    ));
+    Ok((steps, true))
+}
+
+/// Recognize `sum(term, index, start, n)` or `a + b + ... + term(n)`
+fn parse_sum(left: &str) -> Result<Option<SumClaim>, String> {
+    let left = left.trim().to_lowercase();
+    if let Some(inner) = left.strip_prefix("sum(").and_then(|rest| rest.strip_suffix(')')) {
+        let (term, index, start, upper) = match split_top_level(inner, ',').as_slice() {
+            [term, index, start, upper] => (Node::parse(term)?, index.trim().to_string(), *start, Node::parse(upper)?),
+            _ => return Err("sum() takes a term, an index variable, a start and an upper bound".to_string()),
+        };
+        let start = start.trim().parse().map_err(|_| format!("The start of a sum must be a whole number, not '{}'", start.trim()))?;
+        return match upper {
+            Node::Variable(upper) if upper != index => Ok(Some(SumClaim { term, index, start, upper })),
+            _ => Err("The upper bound of a sum must be a single variable other than the index".to_string()),
+        };
+    }
+
+    let (before, after) = match left.split_once("...") {
+        Some(parts) => parts,
+        None => return Ok(None),
+    };
+    let last = Node::parse(after.trim().trim_start_matches('+'))?;
+    let upper = match variables(&last).into_iter().collect::<Vec<_>>().as_slice() {
+        [upper] => upper.clone(),
+        _ => return Err("The last term of a sum must depend on exactly one variable".to_string()),
+    };
+    let index = if upper == "k" { "i" } else { "k" }.to_string();
+    let term = last.substitute(&upper, &Node::Variable(index.clone()));
+    let leading = split_top_level(before.trim().trim_end_matches('+'), '+')
+        .into_iter()
+        .map(|text| Node::parse(text).and_then(|node| constant_value(&node)))
+        .collect::<Result<Vec<_>, String>>()?;
+    if leading.is_empty() {
+        return Err("Write at least one term before '...'".to_string());
+    }
+
+    // The start is wherever the general term reproduces the written-out terms
+    for start in 0..=MAX_INFERRED_START {
+        let matches = leading.iter().enumerate().all(|(i, value)| {
+            constant_value(&term.substitute(&index, &Node::Number((start + i as i64) as f64))).ok().as_ref() == Some(value)
+        });
+        if matches {
+            return Ok(Some(SumClaim { term, index, start, upper }));
+        }
+    }
+    Err(format!("The terms before '...' don't follow the pattern of the last term, {}", last))
+}
+
+/// `text` split at `separator` outside parentheses
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn variables(node: &Node) -> BTreeSet<String> {
+    match node {
+        Node::Number(_) | Node::Constant(_) => BTreeSet::new(),
+        Node::Variable(name) => BTreeSet::from([name.clone()]),
+        Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) | Node::Div(a, b) | Node::Pow(a, b) => {
+            variables(a).into_iter().chain(variables(b)).collect()
+        }
+        Node::Neg(a) | Node::Call(_, a) => variables(a),
+    }
+}
+
+/// `first + second + ... + term(upper)`, the written-out terms shown as values
+fn sum_text(claim: &SumClaim, upper: &Node) -> Result<String, String> {
+    let value = |offset: i64| {
+        constant_value(&claim.term.substitute(&claim.index, &Node::Number((claim.start + offset) as f64))).map(|v| format_rational(&v))
+    };
+    let last = match fold_offsets(&claim.term.substitute(&claim.index, upper)) {
+        last @ (Node::Add(..) | Node::Sub(..) | Node::Neg(_)) => format!("({})", last),
+        last => last.to_string(),
+    };
+    Ok(format!("{} + {} + ... + {}", value(0)?, value(1)?, last))
+}
+
+/// `expression` with `variable` replaced by `variable + 1`, folding
+/// `n + 1 + 1` to `n + 2`
+fn shifted(expression: &Node, variable: &str) -> Node {
+    let next = Node::Add(Box::new(Node::Variable(variable.to_string())), Box::new(Node::Number(1.0)));
+    fold_offsets(&expression.substitute(variable, &next))
+}
+
+fn fold_offsets(node: &Node) -> Node {
+    let fold = |a: &Node| Box::new(fold_offsets(a));
+    match node {
+        Node::Add(a, b) => match (fold_offsets(a), fold_offsets(b)) {
+            (Node::Add(x, inner), Node::Number(outer)) => match *inner {
+                Node::Number(inner) => Node::Add(x, Box::new(Node::Number(inner + outer))),
+                inner => Node::Add(Box::new(Node::Add(x, Box::new(inner))), Box::new(Node::Number(outer))),
+            },
+            (a, b) => Node::Add(Box::new(a), Box::new(b)),
+        },
+        Node::Number(_) | Node::Constant(_) | Node::Variable(_) => node.clone(),
+        Node::Sub(a, b) => Node::Sub(fold(a), fold(b)),
+        Node::Mul(a, b) => Node::Mul(fold(a), fold(b)),
+        Node::Div(a, b) => Node::Div(fold(a), fold(b)),
+        Node::Pow(a, b) => Node::Pow(fold(a), fold(b)),
+        Node::Neg(a) => Node::Neg(fold(a)),
+        Node::Call(function, a) => Node::Call(*function, fold(a)),
+    }
+}
+
+/// Steps taking `expression` to its normal form, skipping any that change
+/// nothing, and the normal form itself
+fn reduce(expression: &Node) -> Result<(Vec<ProofStep>, Polynomial), String> {
+    let mut steps = Vec::new();
+    let mut current = expression.to_string();
+    let mut advance = |next: String, justification: &str| {
+        if next != current {
+            steps.push(ProofStep::new(format!("{} = {}", current, next), justification));
+            current = next;
+        }
+    };
+    advance(expand_powers(expression).to_string(), "Write each power of a sum as a repeated product");
+    let terms = distribute(expression)?;
+    advance(format_terms(&terms), "Distribute multiplication over addition");
+    let polynomial = collect(&terms);
+    advance(format_polynomial(&polynomial), "Collect like terms");
+    Ok((steps, polynomial))
+}
+
+fn whole_exponent(node: &Node) -> Option<u32> {
+    match node {
+        Node::Number(value) if value.fract() == 0.0 && (0.0..=MAX_EXPANDED_EXPONENT as f64).contains(value) => Some(*value as u32),
+        _ => None,
+    }
+}
+
+/// `(a + b)^2` as `(a + b) * (a + b)`; powers of a single variable stay
+fn expand_powers(node: &Node) -> Node {
+    let expand = |a: &Node| Box::new(expand_powers(a));
+    match node {
+        Node::Pow(base, exponent) if !matches!(**base, Node::Variable(_) | Node::Number(_) | Node::Constant(_)) => {
+            match whole_exponent(exponent) {
+                Some(n) if n >= 2 => {
+                    let base = expand_powers(base);
+                    (1..n).fold(base.clone(), |product, _| Node::Mul(Box::new(product), Box::new(base.clone())))
+                }
+                _ => Node::Pow(expand(base), exponent.clone()),
+            }
+        }
+        Node::Number(_) | Node::Constant(_) | Node::Variable(_) | Node::Pow(..) => node.clone(),
+        Node::Add(a, b) => Node::Add(expand(a), expand(b)),
+        Node::Sub(a, b) => Node::Sub(expand(a), expand(b)),
+        Node::Mul(a, b) => Node::Mul(expand(a), expand(b)),
+        Node::Div(a, b) => Node::Div(expand(a), expand(b)),
+        Node::Neg(a) => Node::Neg(expand(a)),
+        Node::Call(function, a) => Node::Call(*function, expand(a)),
+    }
+}
+
+/// The expression as a sum of products, nothing collected yet
+fn distribute(node: &Node) -> Result<Vec<Term>, String> {
+    let constant = |coefficient: BigRational| vec![Term { coefficient, factors: Vec::new() }];
+    match node {
+        Node::Number(value) => Ok(constant(rational(*value)?)),
+        Node::Variable(name) => Ok(vec![Term { coefficient: BigRational::one(), factors: vec![(name.clone(), 1)] }]),
+        Node::Constant(name) => Err(format!("'{}' isn't a polynomial; only polynomial claims can be proved step by step", name)),
+        Node::Call(function, _) => Err(format!("{}() isn't a polynomial; only polynomial claims can be proved step by step", function.name())),
+        Node::Add(a, b) => Ok(distribute(a)?.into_iter().chain(distribute(b)?).collect()),
+        Node::Sub(a, b) => Ok(distribute(a)?.into_iter().chain(negate(distribute(b)?)).collect()),
+        Node::Neg(a) => Ok(negate(distribute(a)?)),
+        Node::Mul(a, b) => multiply(&distribute(a)?, &distribute(b)?),
+        Node::Div(a, b) => {
+            let divisor = collect(&distribute(b)?);
+            match divisor.iter().collect::<Vec<_>>().as_slice() {
+                [(monomial, value)] if monomial.is_empty() => {
+                    let inverse = value.recip();
+                    Ok(distribute(a)?
+                        .into_iter()
+                        .map(|term| Term { coefficient: term.coefficient * &inverse, ..term })
+                        .collect())
+                }
+                [] => Err(format!("Division by zero in {}", node)),
+                _ => Err(format!("Dividing by {} isn't polynomial; only division by a number can be proved step by step", b)),
+            }
+        }
+        Node::Pow(base, exponent) => {
+            let n = whole_exponent(exponent).ok_or_else(|| {
+                format!("Only whole-number powers up to {} can be expanded, not ^{}", MAX_EXPANDED_EXPONENT, exponent)
+            })?;
+            match (&**base, n) {
+                (_, 0) => Ok(constant(BigRational::one())),
+                (Node::Variable(name), n) => Ok(vec![Term { coefficient: BigRational::one(), factors: vec![(name.clone(), n)] }]),
+                (base, n) => {
+                    let base = distribute(base)?;
+                    (1..n).try_fold(base.clone(), |product, _| multiply(&product, &base))
+                }
+            }
+        }
+    }
+}
+
+fn negate(terms: Vec<Term>) -> Vec<Term> {
+    terms.into_iter().map(|term| Term { coefficient: -term.coefficient, ..term }).collect()
+}
+
+fn multiply(a: &[Term], b: &[Term]) -> Result<Vec<Term>, String> {
+    if a.len() * b.len() > MAX_TERMS {
+        return Err(format!("Expansion would exceed {} terms", MAX_TERMS));
+    }
+    Ok(a.iter()
+        .flat_map(|x| {
+            b.iter().map(move |y| Term {
+                coefficient: &x.coefficient * &y.coefficient,
+                factors: x.factors.iter().chain(&y.factors).cloned().collect(),
+            })
+        })
+        .collect())
+}
+
+fn collect(terms: &[Term]) -> Polynomial {
+    let mut polynomial = Polynomial::new();
+    for term in terms {
+        let mut monomial = Monomial::new();
+        for (name, power) in &term.factors {
+            *monomial.entry(name.clone()).or_insert(0) += power;
+        }
+        *polynomial.entry(monomial).or_insert_with(BigRational::zero) += &term.coefficient;
+    }
+    polynomial.retain(|_, coefficient| !coefficient.is_zero());
+    polynomial
+}
+
+fn subtract(a: &Polynomial, b: &Polynomial) -> Polynomial {
+    let negated: Vec<Term> = b.iter().map(|(monomial, c)| Term { coefficient: -c, factors: monomial_factors(monomial) }).collect();
+    let kept: Vec<Term> = a.iter().map(|(monomial, c)| Term { coefficient: c.clone(), factors: monomial_factors(monomial) }).collect();
+    collect(&[kept, negated].concat())
+}
+
+fn monomial_factors(monomial: &Monomial) -> Vec<(String, u32)> {
+    monomial.iter().map(|(name, power)| (name.clone(), *power)).collect()
+}
+
+/// Value of an expression with no variables left
+fn constant_value(node: &Node) -> Result<BigRational, String> {
+    let polynomial = collect(&distribute(node)?);
+    match polynomial.iter().next() {
+        None => Ok(BigRational::zero()),
+        Some((monomial, value)) if monomial.is_empty() => Ok(value.clone()),
+        _ => Err(format!("{} still depends on a variable", node)),
+    }
+}
+
+/// A parsed literal as the exact decimal it was written as
+fn rational(value: f64) -> Result<BigRational, String> {
+    if !value.is_finite() {
+        return Err(format!("{} isn't a finite number", value));
+    }
+    let text = value.to_string();
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let digits: BigInt = format!("{}{}", whole, fraction).parse().map_err(|_| format!("Invalid number {}", value))?;
+    Ok(BigRational::new(digits, BigInt::from(10).pow(fraction.len() as u32)))
+}
+
+fn format_rational(r: &BigRational) -> String {
+    if r.denom().is_one() {
+        r.numer().to_string()
+    } else {
+        format!("{}/{}", r.numer(), r.denom())
+    }
+}
+
+/// `x`, `x^2` factors joined by `*`
+fn format_factors<'a>(factors: impl Iterator<Item = (&'a String, u32)>) -> String {
+    factors
+        .map(|(name, power)| if power == 1 { name.clone() } else { format!("{}^{}", name, power) })
+        .collect::<Vec<_>>()
+        .join(" * ")
+}
+
+/// Signed terms as `a - 2 * b + 1/2`, or `0` when there are none
+fn format_sum(terms: Vec<(BigRational, String)>) -> String {
+    if terms.is_empty() {
+        return "0".to_string();
+    }
+    let mut text = String::new();
+    for (i, (coefficient, factors)) in terms.into_iter().enumerate() {
+        let magnitude = coefficient.abs();
+        let body = match (factors.is_empty(), magnitude.is_one()) {
+            (true, _) => format_rational(&magnitude),
+            (false, true) => factors,
+            (false, false) => format!("{} * {}", format_rational(&magnitude), factors),
+        };
+        let sign = match (i, coefficient.is_negative()) {
+            (0, true) => "-",
+            (0, false) => "",
+            (_, true) => " - ",
+            (_, false) => " + ",
+        };
+        text.push_str(sign);
+        text.push_str(&body);
+    }
+    text
+}
+
+fn format_terms(terms: &[Term]) -> String {
+    format_sum(
+        terms
+            .iter()
+            .map(|term| (term.coefficient.clone(), format_factors(term.factors.iter().map(|(name, power)| (name, *power)))))
+            .collect(),
+    )
+}
+
+/// Highest degree first, then by the earliest variable's power, so
+/// `(a + b)^2` reads `a^2 + 2 * a * b + b^2`
+fn format_polynomial(polynomial: &Polynomial) -> String {
+    let names: BTreeSet<&String> = polynomial.keys().flat_map(|monomial| monomial.keys()).collect();
+    let mut monomials: Vec<(&Monomial, &BigRational)> = polynomial.iter().collect();
+    monomials.sort_by_key(|(monomial, _)| {
+        let degree: u32 = monomial.values().sum();
+        let powers: Vec<Reverse<u32>> = names.iter().map(|name| Reverse(monomial.get(*name).copied().unwrap_or(0))).collect();
+        (Reverse(degree), powers)
+    });
+    format_sum(
+        monomials
+            .into_iter()
+            .map(|(monomial, coefficient)| (coefficient.clone(), format_factors(monomial.iter().map(|(name, power)| (name, *power)))))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statements(trace: &ProofTrace) -> Vec<&str> {
+        trace.steps.iter().map(|step| step.statement.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sum_of_first_n_integers_is_proved_by_induction() {
+        let trace = prove("1 + 2 + ... + n = n * (n + 1) / 2").unwrap();
+        assert_eq!(trace.method, ProofMethod::Induction);
+        assert!(trace.verified);
+        assert_eq!(
+            statements(&trace),
+            vec![
+                "1 + 2 + ... + n = n * (n + 1) / 2 for all n ≥ 1",
+                "1 = 1 * (1 + 1) / 2",
+                "1 + 2 + ... + n = n * (n + 1) / 2",
+                "1 + 2 + ... + (n + 1) = n * (n + 1) / 2 + n + 1",
+                "n * (n + 1) / 2 + n + 1 = 1/2 * n * n + 1/2 * n + n + 1",
+                "1/2 * n * n + 1/2 * n + n + 1 = 1/2 * n^2 + 3/2 * n + 1",
+                "(n + 1) * (n + 2) / 2 = 1/2 * n * n + n + 1/2 * n + 1",
+                "1/2 * n * n + n + 1/2 * n + 1 = 1/2 * n^2 + 3/2 * n + 1",
+                "1 + 2 + ... + (n + 1) = (n + 1) * (n + 2) / 2",
+                "1 + 2 + ... + n = n * (n + 1) / 2 for all n ≥ 1",
+            ]
+        );
+        assert_eq!(trace.steps[1].justification, "Base case n = 1: both sides equal 1");
+        assert!(trace.steps[2].justification.starts_with("Inductive hypothesis"));
+        assert!(trace.steps[8].justification.starts_with("Both reduce to 1/2 * n^2 + 3/2 * n + 1"));
+
+        // The same claim written as sum(), and claims that fail at each stage
+        let written_out = prove("sum(k, k, 1, n) = n * (n + 1) / 2").unwrap();
+        assert!(written_out.verified && written_out.steps.len() == trace.steps.len());
+        assert!(prove("1 + 3 + ... + (2n - 1) = n^2").unwrap().verified);
+        let off_by_one = prove("1 + 2 + ... + n = n * (n + 1) / 2 + 1").unwrap();
+        assert!(!off_by_one.verified && off_by_one.steps[1].justification.contains("fails"));
+        let wrong_step = prove("1 + 2 + ... + n = n^2").unwrap();
+        assert!(!wrong_step.verified);
+        assert!(wrong_step.steps.last().unwrap().justification.starts_with("Inductive step fails"));
+        assert!(prove("1 + 5 + ... + n = n").is_err());
+    }
+
+    #[test]
+    fn test_algebraic_identity_trace_expands_distributes_and_collects() {
+        let trace = prove("(a + b)^2 = a^2 + 2*a*b + b^2").unwrap();
+        assert_eq!(trace.method, ProofMethod::Algebraic);
+        assert!(trace.verified);
+        let steps: Vec<(&str, &str)> = trace.steps.iter().map(|s| (s.statement.as_str(), s.justification.as_str())).collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("(a + b)^2 = a^2 + 2 * a * b + b^2", "Claim: both sides reduce to the same polynomial"),
+                ("(a + b)^2 = (a + b) * (a + b)", "Write each power of a sum as a repeated product"),
+                ("(a + b) * (a + b) = a * a + a * b + b * a + b * b", "Distribute multiplication over addition"),
+                ("a * a + a * b + b * a + b * b = a^2 + 2 * a * b + b^2", "Collect like terms"),
+                ("(a + b)^2 = a^2 + 2 * a * b + b^2", "Both sides reduce to a^2 + 2 * a * b + b^2"),
+            ]
+        );
+
+        let false_claim = prove("(x + 1)^2 = x^2 + 1").unwrap();
+        assert!(!false_claim.verified);
+        assert_eq!(false_claim.steps.last().unwrap().statement, "(x + 1)^2 ≠ x^2 + 1");
+        assert!(false_claim.steps.last().unwrap().justification.ends_with("left minus right is 2 * x"));
+
+        // Only polynomial claims have a symbolic proof
+        assert!(prove("sin(x) = x").unwrap_err().contains("isn't a polynomial"));
+        assert!(prove("x^0.5 = x").unwrap_err().contains("whole-number powers"));
+        assert!(prove("x = y = z").is_err());
+    }
+}
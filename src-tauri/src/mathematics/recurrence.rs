@@ -0,0 +1,389 @@
+//! Recurrences for self-recursive functions. Each recursive call is read
+//! for how it shrinks the input (`n / 2`, `mid`, `n - 1`, `arr[1:]`), the
+//! work outside the calls is estimated from loop nesting, and the resulting
+//! recurrence is solved with the Master Theorem when it has the
+//! `T(n) = aT(n/b) + O(n^d)` form. Anything else gets a heuristic estimate,
+//! labelled as such.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::complexity_analyzer::AlgorithmComplexity;
+
+static PYTHON_FUNCTION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^([ \t]*)def\s+(\w+)\s*\(").unwrap());
+static BRACE_FUNCTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:fn|function)\s+(\w+)\s*[<(]|\b(?:const|let|var)\s+(\w+)\s*=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*=>|\w+\s*=>)").unwrap()
+});
+static LOOP_KEYWORD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:for|while|loop)\b").unwrap());
+static ITERATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\.(?:iter|into_iter|map|filter|forEach|reduce)\s*\(").unwrap());
+/// Slices that copy: Python `a[i:j]`, JavaScript `.slice()`, Rust `.to_vec()`
+static COPYING_SLICE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\w\[[^\]]*:[^\]]*\]|\.slice\s*\(|\.to_vec\s*\(").unwrap());
+static DIVIDED: Lazy<Regex> = Lazy::new(|| Regex::new(r"(//|/|>>)\s*(\d+)").unwrap());
+static HALVED: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:mid|middle|half)\b").unwrap());
+/// `n - 1`, `arr[1:]`, `&v[1..]` and `arr.slice(1)`
+static REDUCED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\w\s*-\s*(\d+)\b|\[\s*(\d+)\s*(?::|\.\.)|\.slice\s*\(\s*(\d+)\s*\)").unwrap()
+});
+
+/// How the recurrence was turned into a closed form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurrenceMethod {
+    /// `T(n) = aT(n/b) + O(n^d)`, solved exactly by the Master Theorem case
+    MasterTheorem { case: u8 },
+    /// Doesn't fit the Master Theorem; the closed form is an estimate
+    Heuristic,
+}
+
+/// How one recursive call shrinks its input
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Shrink {
+    Divide(u32),
+    Reduce(u32),
+    Unknown,
+}
+
+/// Recurrence detected for a self-recursive function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub function: String,
+    /// e.g. `T(n) = 2T(n/2) + O(n)`
+    pub relation: String,
+    /// Recursive calls made per invocation; calls in exclusive branches count once
+    pub recursive_calls: usize,
+    /// Polynomial degree of the work outside the recursive calls
+    pub work_degree: u32,
+    pub method: RecurrenceMethod,
+    pub complexity: AlgorithmComplexity,
+    pub closed_form: String,
+    pub explanation: String,
+}
+
+struct Function<'a> {
+    name: String,
+    body: &'a str,
+}
+
+struct Call {
+    shrink: Shrink,
+    /// The call is the whole result of a branch, like `return f(x)`
+    branch_result: bool,
+    in_loop: bool,
+}
+
+/// Recurrence of the first self-recursive function in `code`, if any
+pub fn detect(code: &str, language: &str) -> Option<Recurrence> {
+    let python = language.eq_ignore_ascii_case("python");
+    let functions = if python { python_functions(code) } else { brace_functions(code) };
+    functions.iter().find_map(|function| {
+        let calls = recursive_calls(function, python);
+        (!calls.is_empty()).then(|| solve(function, &calls, &functions, python))
+    })
+}
+
+fn python_functions(code: &str) -> Vec<Function<'_>> {
+    PYTHON_FUNCTION.captures_iter(code)
+        .map(|captures| {
+            let indent = captures[1].len();
+            let start = match code[captures.get(0).unwrap().end()..].find('\n') {
+                Some(newline) => captures.get(0).unwrap().end() + newline + 1,
+                None => code.len(),
+            };
+            // The body is every following line indented past the `def`
+            let mut end = start;
+            for line in code[start..].split_inclusive('\n') {
+                let trimmed = line.trim_start();
+                if !trimmed.is_empty() && line.len() - trimmed.len() <= indent {
+                    break;
+                }
+                end += line.len();
+            }
+            Function { name: captures[2].to_string(), body: &code[start..end] }
+        })
+        .collect()
+}
+
+fn brace_functions(code: &str) -> Vec<Function<'_>> {
+    BRACE_FUNCTION.captures_iter(code)
+        .filter_map(|captures| {
+            let name = captures.get(1).or_else(|| captures.get(2))?.as_str().to_string();
+            let signature = captures.get(0).unwrap();
+            let rest = &code[signature.end()..];
+            let statement = &rest[..rest.find([';', '\n']).unwrap_or(rest.len())];
+            // Arrow functions without braces run to the end of the statement
+            if signature.as_str().ends_with("=>") && !rest.trim_start().starts_with('{') {
+                return Some(Function { name, body: statement });
+            }
+            let body = match rest.find('{') {
+                Some(open) => &rest[open + 1..matching(rest, open, '{', '}')?],
+                None => statement,
+            };
+            Some(Function { name, body })
+        })
+        .collect()
+}
+
+/// Index of the bracket closing the one at `open`
+fn matching(text: &str, open: usize, opening: char, closing: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text[open..].char_indices() {
+        if c == opening {
+            depth += 1;
+        } else if c == closing {
+            depth -= 1;
+            if depth == 0 {
+                return Some(open + i);
+            }
+        }
+    }
+    None
+}
+
+/// Byte ranges of loop bodies
+fn loop_blocks(body: &str, python: bool) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    if python {
+        let mut offset = 0;
+        let lines: Vec<&str> = body.split_inclusive('\n').collect();
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("for ") || trimmed.starts_with("while ") {
+                let indent = line.len() - trimmed.len();
+                let mut end = offset + line.len();
+                for next in &lines[i + 1..] {
+                    let next_trimmed = next.trim_start();
+                    if !next_trimmed.is_empty() && next.len() - next_trimmed.len() <= indent {
+                        break;
+                    }
+                    end += next.len();
+                }
+                blocks.push((offset, end));
+            }
+            offset += line.len();
+        }
+    } else {
+        for keyword in LOOP_KEYWORD.find_iter(body) {
+            let open = match body[keyword.end()..].find('{') {
+                Some(open) => keyword.end() + open,
+                None => continue,
+            };
+            if let Some(close) = matching(body, open, '{', '}') {
+                blocks.push((keyword.start(), close));
+            }
+        }
+    }
+    blocks
+}
+
+fn loop_depth(body: &str, python: bool) -> u32 {
+    let blocks = loop_blocks(body, python);
+    let nested = blocks.iter()
+        .map(|(start, _)| blocks.iter().filter(|(s, e)| s <= start && start < e).count() as u32)
+        .max()
+        .unwrap_or(0);
+    if nested == 0 && ITERATION.is_match(body) { 1 } else { nested }
+}
+
+fn recursive_calls(function: &Function, python: bool) -> Vec<Call> {
+    let pattern = match Regex::new(&format!(r"\b{}\s*\(", regex::escape(&function.name))) {
+        Ok(pattern) => pattern,
+        Err(_) => return Vec::new(),
+    };
+    let body = function.body;
+    let blocks = loop_blocks(body, python);
+    pattern.find_iter(body)
+        .filter_map(|found| {
+            let before = &body[..found.start()];
+            // Method calls only recurse through self/this/Self
+            if before.ends_with('.') && !(before.ends_with("self.") || before.ends_with("this.")) {
+                return None;
+            }
+            let open = found.end() - 1;
+            let close = matching(body, open, '(', ')')?;
+            let arguments = &body[open + 1..close];
+
+            let statement_start = before.rfind([';', '{', '}', ':', '?', '\n']).map_or(0, |i| i + 1);
+            let lead = before[statement_start..].trim();
+            let lead = lead.trim_end_matches("self.").trim_end_matches("this.").trim_end_matches("Self::").trim();
+            let lead = lead.strip_suffix("=>").unwrap_or(lead).trim();
+            let tail = body[close + 1..].trim_start();
+            let branch_result = matches!(lead, "" | "return" | "else")
+                && (tail.is_empty() || tail.starts_with([';', '}', ',', ':', '\n']));
+
+            Some(Call {
+                shrink: shrink(arguments),
+                branch_result,
+                in_loop: blocks.iter().any(|(start, end)| (*start..*end).contains(&found.start())),
+            })
+        })
+        .collect()
+}
+
+fn shrink(arguments: &str) -> Shrink {
+    if let Some(captures) = DIVIDED.captures(arguments) {
+        let amount: u32 = captures[2].parse().unwrap_or(2);
+        return match &captures[1] {
+            ">>" => Shrink::Divide(2u32.saturating_pow(amount)),
+            _ if amount > 1 => Shrink::Divide(amount),
+            _ => Shrink::Unknown,
+        };
+    }
+    if HALVED.is_match(arguments) {
+        return Shrink::Divide(2);
+    }
+    match REDUCED.captures(arguments) {
+        Some(captures) => match (1..=3).find_map(|group| captures.get(group)).and_then(|k| k.as_str().parse().ok()) {
+            Some(k) if k > 0 => Shrink::Reduce(k),
+            _ => Shrink::Unknown,
+        },
+        None => Shrink::Unknown,
+    }
+}
+
+fn work_term(degree: u32) -> String {
+    match degree {
+        0 => "O(1)".to_string(),
+        1 => "O(n)".to_string(),
+        d => format!("O(n^{})", d),
+    }
+}
+
+/// Big-O string and class for `n^exponent`, times `log n` when `log`
+fn polynomial(exponent: f64, log: bool) -> (String, AlgorithmComplexity) {
+    let rounded = exponent.round();
+    if (exponent - rounded).abs() > 1e-9 {
+        let log_term = if log { " log n" } else { "" };
+        return (format!("O(n^{:.2}{})", exponent, log_term), AlgorithmComplexity::Polynomial);
+    }
+    match (rounded as u32, log) {
+        (0, false) => ("O(1)".to_string(), AlgorithmComplexity::Constant),
+        (0, true) => ("O(log n)".to_string(), AlgorithmComplexity::Logarithmic),
+        (1, false) => ("O(n)".to_string(), AlgorithmComplexity::Linear),
+        (1, true) => ("O(n log n)".to_string(), AlgorithmComplexity::Linearithmic),
+        (2, false) => ("O(n²)".to_string(), AlgorithmComplexity::Quadratic),
+        (3, false) => ("O(n³)".to_string(), AlgorithmComplexity::Cubic),
+        (d, false) => (format!("O(n^{})", d), AlgorithmComplexity::Polynomial),
+        (d, true) => (format!("O(n^{} log n)", d), AlgorithmComplexity::Polynomial),
+    }
+}
+
+/// Growth rate r of `T(n) = T(n-k1) + T(n-k2) + ...`, the root above 1 of
+/// `sum(r^-k) = 1`
+fn branching_rate(reductions: &[u32]) -> f64 {
+    let excess = |r: f64| reductions.iter().map(|k| r.powi(-(*k as i32))).sum::<f64>() - 1.0;
+    let (mut low, mut high) = (1.0, reductions.len() as f64);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if excess(mid) > 0.0 { low = mid } else { high = mid }
+    }
+    (low + high) / 2.0
+}
+
+fn solve(function: &Function, calls: &[Call], functions: &[Function], python: bool) -> Recurrence {
+    // Calls that are each a whole branch's result are alternatives: one runs
+    let exclusive = calls.len() > 1 && calls.iter().all(|call| call.branch_result);
+    let effective: Vec<&Call> = if exclusive { calls.iter().take(1).collect() } else { calls.iter().collect() };
+    let a = effective.len();
+
+    // Work outside the recursive calls: loops here and in helpers it calls
+    let mut work_degree = loop_depth(function.body, python);
+    for helper in functions.iter().filter(|helper| helper.name != function.name) {
+        if Regex::new(&format!(r"\b{}\s*\(", regex::escape(&helper.name))).is_ok_and(|call| call.is_match(function.body)) {
+            work_degree = work_degree.max(loop_depth(helper.body, python));
+        }
+    }
+    if COPYING_SLICE.is_match(function.body) {
+        work_degree = work_degree.max(1);
+    }
+    let work = work_term(work_degree);
+    let branches = if exclusive {
+        format!("{} recursive calls in exclusive branches, one runs per call. ", calls.len())
+    } else {
+        String::new()
+    };
+
+    let shrinks: Vec<Shrink> = effective.iter().map(|call| call.shrink).collect();
+    let heuristic = |relation: String, (closed_form, complexity): (String, AlgorithmComplexity), reason: &str| Recurrence {
+        function: function.name.clone(),
+        explanation: format!("{}{} doesn't fit the Master Theorem; heuristic estimate: {}", branches, relation, reason),
+        relation,
+        recursive_calls: a,
+        work_degree,
+        method: RecurrenceMethod::Heuristic,
+        complexity,
+        closed_form,
+    };
+
+    if effective.iter().any(|call| call.in_loop) {
+        let relation = format!("T(n) = n·T(n-1) + {}", work);
+        let estimate = match shrinks[0] {
+            Shrink::Reduce(_) => ("O(n!)".to_string(), AlgorithmComplexity::Factorial),
+            _ => ("O(2^n)".to_string(), AlgorithmComplexity::Exponential),
+        };
+        return heuristic(relation, estimate, "a recursive call inside a loop branches once per iteration");
+    }
+
+    match shrinks[0] {
+        Shrink::Divide(b) if shrinks.iter().all(|shrink| *shrink == Shrink::Divide(b)) => {
+            let coefficient = if a == 1 { String::new() } else { a.to_string() };
+            let relation = format!("T(n) = {}T(n/{}) + {}", coefficient, b, work);
+            let critical = (a as f64).ln() / (b as f64).ln();
+            let d = work_degree as f64;
+            let ((closed_form, complexity), case, comparison) = if (d - critical).abs() < 1e-9 {
+                (polynomial(d, true), 2, "equals")
+            } else if d < critical {
+                (polynomial(critical, false), 1, "exceeds")
+            } else {
+                (polynomial(d, false), 3, "is below")
+            };
+            Recurrence {
+                function: function.name.clone(),
+                explanation: format!(
+                    "{}Master Theorem case {}: a = {}, b = {}, log_b(a) = {:.2} {} d = {}, so {} is {}",
+                    branches, case, a, b, critical, comparison, work_degree, relation, closed_form
+                ),
+                relation,
+                recursive_calls: a,
+                work_degree,
+                method: RecurrenceMethod::MasterTheorem { case },
+                complexity,
+                closed_form,
+            }
+        }
+        Shrink::Reduce(_) if shrinks.iter().all(|shrink| matches!(shrink, Shrink::Reduce(_))) => {
+            let mut reductions: Vec<u32> = shrinks.iter()
+                .map(|shrink| match shrink {
+                    Shrink::Reduce(k) => *k,
+                    _ => 1,
+                })
+                .collect();
+            reductions.sort_unstable();
+            let terms: Vec<String> = if reductions.iter().all(|k| *k == reductions[0]) && a > 1 {
+                vec![format!("{}T(n-{})", a, reductions[0])]
+            } else {
+                reductions.iter().map(|k| format!("T(n-{})", k)).collect()
+            };
+            let relation = format!("T(n) = {} + {}", terms.join(" + "), work);
+            if a == 1 {
+                return heuristic(relation, polynomial(work_degree as f64 + 1.0, false), "one call per level over n levels");
+            }
+            let rate = branching_rate(&reductions);
+            let closed_form = if (rate - rate.round()).abs() < 1e-6 {
+                format!("O({}^n)", rate.round())
+            } else {
+                format!("O({:.2}^n)", rate)
+            };
+            heuristic(relation, (closed_form, AlgorithmComplexity::Exponential), "the call tree branches at every level")
+        }
+        _ => {
+            let relation = format!("T(n) = {}T(?) + {}", if a == 1 { String::new() } else { a.to_string() }, work);
+            let estimate = if a == 1 {
+                polynomial(work_degree as f64 + 1.0, false)
+            } else {
+                ("O(2^n)".to_string(), AlgorithmComplexity::Exponential)
+            };
+            heuristic(relation, estimate, "the subproblem size couldn't be read from the call arguments")
+        }
+    }
+}
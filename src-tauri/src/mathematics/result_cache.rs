@@ -0,0 +1,175 @@
+//! LRU cache for the engine's repeated queries (expression solving, TARS
+//! analysis, algorithm optimization), so a UI polling the same question doesn't recompute it
+//! every time. Entries are keyed by a hash of the operation and its inputs
+//! and handed out as shared `Arc`s.
+
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// Entries kept when the engine config doesn't say otherwise
+pub const DEFAULT_CACHE_CAPACITY: usize = 64;
+
+type Entry = Arc<dyn Any + Send + Sync>;
+
+/// Hit/miss counts for the debug command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<u64, Entry>,
+    /// Keys from least to most recently used
+    order: VecDeque<u64>,
+    hits: u64,
+    misses: u64,
+}
+
+pub struct ResultCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+/// Hash of an operation name and its inputs, in order
+pub fn cache_key(operation: &str, inputs: &[&str]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    operation.hash(&mut hasher);
+    inputs.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // A panic mid-update can only leave a stale entry behind, never a wrong one
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The cached value for `key`, marking it most recently used. A value of
+    /// another type under the same key counts as a miss.
+    pub fn get<V: Any + Send + Sync>(&self, key: u64) -> Option<Arc<V>> {
+        let mut inner = self.lock();
+        match inner.entries.get(&key).cloned().map(Arc::downcast::<V>) {
+            Some(Ok(value)) => {
+                inner.hits += 1;
+                inner.order.retain(|k| *k != key);
+                inner.order.push_back(key);
+                Some(value)
+            }
+            _ => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store `value` under `key`, evicting the least recently used entry when full
+    pub fn insert<V: Any + Send + Sync>(&self, key: u64, value: V) -> Arc<V> {
+        let value = Arc::new(value);
+        if self.capacity == 0 {
+            return value;
+        }
+        let mut inner = self.lock();
+        inner.order.retain(|k| *k != key);
+        while inner.order.len() >= self.capacity {
+            match inner.order.pop_front() {
+                Some(oldest) => inner.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        inner.order.push_back(key);
+        inner.entries.insert(key, value.clone());
+        value
+    }
+
+    /// Drop every entry; the hit/miss counts are kept
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.order.clear();
+    }
+
+    /// Change the capacity, which also empties the cache
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+/// A cloned engine starts with an empty cache of the same capacity
+impl Clone for ResultCache {
+    fn clone(&self) -> Self {
+        Self::new(self.capacity)
+    }
+}
+
+impl std::fmt::Debug for ResultCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultCache").field("stats", &self.stats()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_queries_share_the_cached_result_and_others_miss() {
+        let cache = ResultCache::new(2);
+        let key = cache_key("tars_analysis", &["2 + 2", "homework"]);
+        assert!(cache.get::<String>(key).is_none());
+        let stored = cache.insert(key, "4".to_string());
+
+        let first = cache.get::<String>(key).unwrap();
+        let second = cache.get::<String>(key).unwrap();
+        assert!(Arc::ptr_eq(&first, &stored) && Arc::ptr_eq(&first, &second));
+
+        // Different input, different operation, or the wrong type all miss
+        assert!(cache.get::<String>(cache_key("tars_analysis", &["2 + 3", "homework"])).is_none());
+        assert!(cache.get::<String>(cache_key("optimize_algorithm", &["2 + 2", "homework"])).is_none());
+        assert_ne!(cache_key("op", &["ab", "c"]), cache_key("op", &["a", "bc"]));
+        assert!(cache.get::<u32>(key).is_none());
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4, entries: 1, capacity: 2 });
+
+        // The least recently used entry goes first
+        cache.insert(1, 1u32);
+        cache.get::<String>(key);
+        cache.insert(2, 2u32);
+        assert!(cache.get::<u32>(1).is_none());
+        assert!(cache.get::<String>(key).is_some());
+
+        cache.clear();
+        assert!(cache.get::<String>(key).is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
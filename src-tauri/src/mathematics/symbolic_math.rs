@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::differentiation;
+use super::exact_arithmetic::{self, ExactResult};
+use super::proof;
+use super::units;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolicMath {
     constants: HashMap<String, f64>,
@@ -33,6 +38,84 @@ impl SymbolicMath {
         }
     }
 
+    /// Evaluate with exact rational arithmetic, falling back to floats only
+    /// for irrational operations. Equations use the regular solver.
+    pub async fn solve_exact(&self, expression: &str) -> MathResult {
+        if expression.contains('=') {
+            return self.solve(expression).await;
+        }
+
+        match self.evaluate_exact(expression) {
+            Ok(result) if result.exact => MathResult::Success {
+                explanation: format!("Exact result: {} = {}", expression.trim(), result.value),
+                result: result.value,
+                method_used: "Exact Rational Arithmetic".to_string(),
+            },
+            Ok(result) => MathResult::Success {
+                explanation: format!(
+                    "Irrational operation, result is approximate: {} ≈ {}",
+                    expression.trim(),
+                    result.value
+                ),
+                result: result.value,
+                method_used: "Floating-Point Fallback".to_string(),
+            },
+            Err(err) => MathResult::Error(err),
+        }
+    }
+
+    pub fn evaluate_exact(&self, expression: &str) -> Result<ExactResult, String> {
+        exact_arithmetic::evaluate(expression, &self.constants)
+    }
+
+    /// Evaluate with dimensional analysis, returning the value in SI units
+    /// with its derived unit. Expressions without units solve as usual.
+    pub async fn solve_with_units(&self, expression: &str) -> MathResult {
+        let parsed = Expression::new(expression.to_string());
+        if parsed.units.is_empty() {
+            return self.solve(expression).await;
+        }
+
+        match units::evaluate(expression, &self.constants) {
+            Ok(quantity) => {
+                let base = quantity.dimension.to_string();
+                let in_base_units = if base.is_empty() || base == quantity.unit() {
+                    String::new()
+                } else {
+                    format!(" ({})", base)
+                };
+                MathResult::Success {
+                    explanation: format!("{} = {}{}", expression.trim(), quantity, in_base_units),
+                    result: quantity.to_string(),
+                    method_used: "Dimensional Analysis".to_string(),
+                }
+            }
+            Err(err) => MathResult::Error(err),
+        }
+    }
+
+    /// Expression tree for evaluating `expression` numerically
+    pub fn parse(&self, expression: &str) -> Result<differentiation::Node, String> {
+        differentiation::Node::parse(expression)
+    }
+
+    /// Step-by-step proof of a polynomial identity or sum formula
+    pub fn prove(&self, theorem: &str) -> Result<proof::ProofTrace, String> {
+        proof::prove(theorem)
+    }
+
+    /// Simplified derivative of `expression` with respect to `variable`
+    pub fn differentiate(&self, expression: &Expression, variable: &str) -> MathResult {
+        match differentiation::derivative(&expression.normalized, variable) {
+            Ok(derivative) => MathResult::Success {
+                explanation: format!("d/d{} ({}) = {}", variable.trim(), expression.raw.trim(), derivative),
+                result: derivative.to_string(),
+                method_used: "Symbolic Differentiation".to_string(),
+            },
+            Err(err) => MathResult::Error(err),
+        }
+    }
+
     fn preprocess_expression(&self, expression: &str) -> String {
         let mut processed = expression.to_lowercase();
         
@@ -351,6 +434,9 @@ pub struct Expression {
     pub variables: Vec<String>,
     pub constants: Vec<String>,
     pub operations: Vec<String>,
+    /// Unit tokens like `kg` and `m`, case preserved
+    #[serde(default)]
+    pub units: Vec<String>,
 }
 
 impl Expression {
@@ -359,6 +445,7 @@ impl Expression {
         let variables = Self::extract_variables(&normalized);
         let constants = Self::extract_constants(&normalized);
         let operations = Self::extract_operations(&normalized);
+        let units = units::unit_tokens(&raw);
         
         Self {
             raw,
@@ -366,6 +453,7 @@ impl Expression {
             variables,
             constants,
             operations,
+            units,
         }
     }
     
@@ -0,0 +1,329 @@
+//! Dimensional analysis. Numbers may carry SI units (`2 kg * 9.8 m/s^2`);
+//! every value is tracked as a magnitude in base SI units plus a dimension
+//! vector, so products derive their unit and adding metres to seconds is
+//! an error instead of a wrong answer.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::differentiation::{Node, ParseOptions};
+
+/// Base SI units, in dimension vector order
+const BASE_UNITS: [&str; 7] = ["kg", "m", "s", "A", "K", "mol", "cd"];
+
+/// Exponents of each of `BASE_UNITS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Dimension(pub [i8; 7]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0; 7]);
+
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Exponents of a product (`sign` 1) or quotient (`sign` -1); fails when
+    /// an exponent leaves the `i8` range
+    fn combine(&self, other: &Dimension, sign: i8) -> Result<Dimension, String> {
+        let mut exponents = self.0;
+        for (exponent, theirs) in exponents.iter_mut().zip(other.0) {
+            *exponent = theirs.checked_mul(sign)
+                .and_then(|theirs| exponent.checked_add(theirs))
+                .ok_or_else(|| format!("Unit exponents out of range combining {} and {}", self, other))?;
+        }
+        Ok(Dimension(exponents))
+    }
+
+    /// Dimension raised to `power`; fails when an exponent wouldn't stay
+    /// whole or leaves the `i8` range
+    fn pow(&self, power: f64) -> Result<Dimension, String> {
+        let mut exponents = self.0;
+        for exponent in exponents.iter_mut() {
+            let raised = *exponent as f64 * power;
+            if (raised - raised.round()).abs() > 1e-9 {
+                return Err(format!("Cannot raise {} to the power {}", self, power));
+            }
+            if raised.round() < i8::MIN as f64 || raised.round() > i8::MAX as f64 {
+                return Err(format!("Unit exponents out of range raising {} to the power {}", self, power));
+            }
+            *exponent = raised.round() as i8;
+        }
+        Ok(Dimension(exponents))
+    }
+
+    /// Conventional name for the dimension, e.g. `N` for kg·m/s^2
+    pub fn named_unit(&self) -> Option<&'static str> {
+        NAMED_UNITS.iter().find(|(_, _, dimension)| dimension == self).map(|(name, _, _)| *name)
+    }
+}
+
+impl fmt::Display for Dimension {
+    /// Base units, e.g. `kg·m/s^2`; empty when dimensionless
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let term = |unit: &str, exponent: i8| match exponent {
+            1 => unit.to_string(),
+            _ => format!("{}^{}", unit, exponent),
+        };
+        let mut numerator = Vec::new();
+        let mut denominator = Vec::new();
+        for (unit, exponent) in BASE_UNITS.iter().zip(self.0) {
+            if exponent > 0 {
+                numerator.push(term(unit, exponent));
+            } else if exponent < 0 {
+                denominator.push(term(unit, -exponent));
+            }
+        }
+        match (numerator.is_empty(), denominator.is_empty()) {
+            (_, true) => write!(f, "{}", numerator.join("·")),
+            (true, false) => write!(f, "1/{}", denominator.join("·")),
+            (false, false) => write!(f, "{}/{}", numerator.join("·"), denominator.join("·")),
+        }
+    }
+}
+
+const fn dim(kg: i8, m: i8, s: i8, a: i8, k: i8) -> Dimension {
+    Dimension([kg, m, s, a, k, 0, 0])
+}
+
+/// Derived units shown by name instead of in base units
+const NAMED_UNITS: [(&str, f64, Dimension); 7] = [
+    ("N", 1.0, dim(1, 1, -2, 0, 0)),
+    ("J", 1.0, dim(1, 2, -2, 0, 0)),
+    ("W", 1.0, dim(1, 2, -3, 0, 0)),
+    ("Pa", 1.0, dim(1, -1, -2, 0, 0)),
+    ("C", 1.0, dim(0, 0, 1, 1, 0)),
+    ("V", 1.0, dim(1, 2, -3, -1, 0)),
+    ("ohm", 1.0, dim(1, 2, -3, -2, 0)),
+];
+
+/// Units the parser accepts: name, size in base SI units, dimension
+const UNITS: [(&str, f64, Dimension); 22] = [
+    ("kg", 1.0, dim(1, 0, 0, 0, 0)),
+    ("g", 1e-3, dim(1, 0, 0, 0, 0)),
+    ("m", 1.0, dim(0, 1, 0, 0, 0)),
+    ("km", 1e3, dim(0, 1, 0, 0, 0)),
+    ("cm", 1e-2, dim(0, 1, 0, 0, 0)),
+    ("mm", 1e-3, dim(0, 1, 0, 0, 0)),
+    ("s", 1.0, dim(0, 0, 1, 0, 0)),
+    ("ms", 1e-3, dim(0, 0, 1, 0, 0)),
+    ("min", 60.0, dim(0, 0, 1, 0, 0)),
+    ("h", 3600.0, dim(0, 0, 1, 0, 0)),
+    ("A", 1.0, dim(0, 0, 0, 1, 0)),
+    ("mA", 1e-3, dim(0, 0, 0, 1, 0)),
+    ("K", 1.0, dim(0, 0, 0, 0, 1)),
+    ("mol", 1.0, Dimension([0, 0, 0, 0, 0, 1, 0])),
+    ("cd", 1.0, Dimension([0, 0, 0, 0, 0, 0, 1])),
+    ("Hz", 1.0, dim(0, 0, -1, 0, 0)),
+    ("kW", 1e3, dim(1, 2, -3, 0, 0)),
+    ("kN", 1e3, dim(1, 1, -2, 0, 0)),
+    ("mV", 1e-3, dim(1, 2, -3, -1, 0)),
+    // Angles are dimensionless; degrees convert to radians
+    ("rad", 1.0, Dimension::NONE),
+    ("deg", std::f64::consts::PI / 180.0, Dimension::NONE),
+    ("rpm", std::f64::consts::PI / 30.0, dim(0, 0, -1, 0, 0)),
+];
+
+fn lookup_unit(name: &str) -> Option<(f64, Dimension)> {
+    UNITS.iter()
+        .chain(NAMED_UNITS.iter())
+        .find(|(unit, _, _)| *unit == name)
+        .map(|(_, scale, dimension)| (*scale, *dimension))
+}
+
+/// A magnitude in base SI units with its dimension
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    fn number(value: f64) -> Self {
+        Self { value, dimension: Dimension::NONE }
+    }
+
+    /// Unit to show the value in: a named derived unit when there is one,
+    /// base units otherwise, empty when dimensionless
+    pub fn unit(&self) -> String {
+        match self.dimension.named_unit() {
+            Some(name) => name.to_string(),
+            None => self.dimension.to_string(),
+        }
+    }
+
+    fn add(self, other: Quantity, operation: &str) -> Result<Quantity, String> {
+        if self.dimension != other.dimension {
+            return Err(format!(
+                "Cannot {} {} and {}: the units are inconsistent",
+                operation,
+                display_unit(&self.dimension),
+                display_unit(&other.dimension)
+            ));
+        }
+        let value = if operation == "add" { self.value + other.value } else { self.value - other.value };
+        Ok(Quantity { value, dimension: self.dimension })
+    }
+
+    fn mul(self, other: Quantity) -> Result<Quantity, String> {
+        Ok(Quantity { value: self.value * other.value, dimension: self.dimension.combine(&other.dimension, 1)? })
+    }
+
+    fn div(self, other: Quantity) -> Result<Quantity, String> {
+        if other.value == 0.0 {
+            return Err("Division by zero".to_string());
+        }
+        Ok(Quantity { value: self.value / other.value, dimension: self.dimension.combine(&other.dimension, -1)? })
+    }
+
+    fn pow(self, exponent: Quantity) -> Result<Quantity, String> {
+        if !exponent.dimension.is_dimensionless() {
+            return Err(format!("Exponent must be dimensionless, got {}", display_unit(&exponent.dimension)));
+        }
+        Ok(Quantity { value: self.value.powf(exponent.value), dimension: self.dimension.pow(exponent.value)? })
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit() {
+            unit if unit.is_empty() => write!(f, "{}", self.value),
+            unit => write!(f, "{} {}", self.value, unit),
+        }
+    }
+}
+
+fn display_unit(dimension: &Dimension) -> String {
+    match dimension.named_unit() {
+        Some(name) => name.to_string(),
+        None if dimension.is_dimensionless() => "a dimensionless number".to_string(),
+        None => dimension.to_string(),
+    }
+}
+
+/// Unit names used in `expression`, in order of first use
+pub fn unit_tokens(expression: &str) -> Vec<String> {
+    let mut units: Vec<String> = Vec::new();
+    let chars: Vec<char> = expression.chars().collect();
+    let mut pos = 0;
+    while pos < chars.len() {
+        if !chars[pos].is_alphabetic() {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while pos < chars.len() && chars[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        let name: String = chars[start..pos].iter().collect();
+        let is_call = chars[pos..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+        if !is_call && lookup_unit(&name).is_some() && !units.contains(&name) {
+            units.push(name);
+        }
+    }
+    units
+}
+
+/// Evaluate an expression whose numbers may carry units. Supports
+/// `+ - * / ^`, parentheses, constants and `sqrt sin cos tan ln exp abs`;
+/// trigonometric and logarithmic arguments must be dimensionless. Parsed by
+/// `Node::parse_with` in unit mode, so `1 / 2 s` is 1 / (2 s).
+pub fn evaluate(expression: &str, constants: &HashMap<String, f64>) -> Result<Quantity, String> {
+    let node = Node::parse_with(expression, ParseOptions { case_sensitive: true, units: true })?;
+    quantity(&node, constants)
+}
+
+fn quantity(node: &Node, constants: &HashMap<String, f64>) -> Result<Quantity, String> {
+    let value = |node: &Node| quantity(node, constants);
+    match node {
+        Node::Number(value) => Ok(Quantity::number(*value)),
+        Node::Constant(name) | Node::Variable(name) => {
+            if let Some(value) = constants.get(name) {
+                return Ok(Quantity::number(*value));
+            }
+            if let Node::Constant(_) = node {
+                return node.evaluate(&HashMap::new()).map(Quantity::number);
+            }
+            lookup_unit(name)
+                .map(|(value, dimension)| Quantity { value, dimension })
+                .ok_or_else(|| format!("Unknown unit or constant '{}'", name))
+        }
+        Node::Add(a, b) => value(a)?.add(value(b)?, "add"),
+        Node::Sub(a, b) => value(a)?.add(value(b)?, "subtract"),
+        Node::Mul(a, b) => value(a)?.mul(value(b)?),
+        Node::Div(a, b) => value(a)?.div(value(b)?),
+        Node::Pow(a, b) => value(a)?.pow(value(b)?),
+        Node::Neg(a) => {
+            let value = value(a)?;
+            Ok(Quantity { value: -value.value, ..value })
+        }
+        Node::Call(function, a) => apply_function(function.name(), value(a)?),
+    }
+}
+
+fn apply_function(name: &str, argument: Quantity) -> Result<Quantity, String> {
+    match name {
+        "sqrt" if argument.value < 0.0 => Err("Square root of negative number".to_string()),
+        "sqrt" => Ok(Quantity { value: argument.value.sqrt(), dimension: argument.dimension.pow(0.5)? }),
+        "abs" => Ok(Quantity { value: argument.value.abs(), ..argument }),
+        _ if !argument.dimension.is_dimensionless() => {
+            Err(format!("{}() needs a dimensionless argument, got {}", name, display_unit(&argument.dimension)))
+        }
+        "sin" => Ok(Quantity::number(argument.value.sin())),
+        "cos" => Ok(Quantity::number(argument.value.cos())),
+        "tan" => Ok(Quantity::number(argument.value.tan())),
+        "exp" => Ok(Quantity::number(argument.value.exp())),
+        "ln" if argument.value <= 0.0 => Err("Logarithm of non-positive number".to_string()),
+        "ln" => Ok(Quantity::number(argument.value.ln())),
+        _ => Err(format!("Unknown function '{}'", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> Result<Quantity, String> {
+        evaluate(expression, &HashMap::from([("pi".to_string(), std::f64::consts::PI)]))
+    }
+
+    #[test]
+    fn test_units_propagate_through_products_and_reject_inconsistent_sums() {
+        let force = eval("2 kg * 9.8 m/s^2").unwrap();
+        assert!((force.value - 19.6).abs() < 1e-12);
+        assert_eq!(force.dimension, dim(1, 1, -2, 0, 0));
+        assert_eq!(force.unit(), "N");
+
+        // Torque from a force at a lever arm, power from torque and speed
+        let torque = eval("19.6 N * 15 cm").unwrap();
+        assert!((torque.value - 2.94).abs() < 1e-12);
+        assert_eq!(torque.unit(), "J");
+        let power = eval("2 N*m * 60 rpm").unwrap();
+        assert!((power.value - 4.0 * std::f64::consts::PI).abs() < 1e-12);
+        assert_eq!(power.unit(), "W");
+
+        assert_eq!(eval("3 m / 2 s").unwrap().unit(), "m/s");
+        assert_eq!(eval("sqrt(16 m^2)").unwrap(), Quantity { value: 4.0, dimension: dim(0, 1, 0, 0, 0) });
+        assert_eq!(eval("1 km + 500 m").unwrap().value, 1500.0);
+        assert_eq!(eval("sin(90 deg)").unwrap().value, 1.0);
+
+        let error = eval("1 m + 1 s").unwrap_err();
+        assert!(error.contains("Cannot add m and s"), "{}", error);
+        assert!(eval("sin(2 m)").unwrap_err().contains("dimensionless"));
+        assert!(eval("sqrt(2 m)").is_err());
+        assert!(eval("2 furlongs").unwrap_err().contains("Unknown unit"));
+
+        assert_eq!(eval("1.5e3 m").unwrap().value, 1500.0);
+
+        assert_eq!(unit_tokens("2 kg * 9.8 m/s^2 + sin(x)"), vec!["kg", "m", "s"]);
+        assert!(unit_tokens("sqrt(2) * pi").is_empty());
+    }
+
+    #[test]
+    fn test_unit_exponent_overflow_is_an_error() {
+        assert!(eval("1 m^100 * 1 m^100").unwrap_err().contains("out of range"));
+        assert!(eval("1 m^-100 / 1 m^100").unwrap_err().contains("out of range"));
+        assert!(eval("(1 m^2)^100").unwrap_err().contains("out of range"));
+        assert!(eval("1 m^300").unwrap_err().contains("out of range"));
+    }
+}
@@ -5,13 +5,19 @@
 
 use super::{
     PDFManager, PromptDocument, ExecutablePrompt, PromptStatus, 
-    N8NWebhookRequest, N8NWebhookResponse, TARSPersonality
+    N8NWebhookRequest, N8NWebhookResponse, TARSPersonality,
+    ExecutionProgress, ProgressEvent,
 };
+use crate::config::config::Config;
+use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+use warp::filters::BoxedFilter;
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket};
 use warp::{Filter, Reply};
 use uuid::Uuid;
 
@@ -42,8 +48,11 @@ pub struct ServerConfig {
     /// Enable CORS
     pub enable_cors: bool,
     
-    /// API key for authentication
-    pub api_key: Option<String>,
+    /// Origins allowed by CORS; empty rejects every cross-origin request
+    pub allowed_origins: Vec<String>,
+    
+    /// API keys for authentication; empty leaves the API open
+    pub api_keys: Vec<String>,
     
     /// Rate limiting (requests per minute)
     pub rate_limit: Option<u32>,
@@ -55,8 +64,37 @@ pub struct ServerConfig {
     pub tars_responses: bool,
 }
 
+impl ServerConfig {
+    /// Server defaults with the API keys and allowed origins from `Config`.
+    /// Origins without a scheme are skipped.
+    pub fn from_config(config: &Config) -> Self {
+        let allowed_origins = config.api_server.allowed_origins.iter()
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| {
+                let valid = origin.starts_with("http://") || origin.starts_with("https://");
+                if !valid {
+                    log::warn!("Ignoring allowed origin without http(s) scheme: {}", origin);
+                }
+                valid
+            })
+            .collect();
+        
+        Self {
+            api_keys: config.api_server.api_keys.clone(),
+            allowed_origins,
+            ..Self::default()
+        }
+    }
+}
+
+/// Rejection for a missing or unknown API key
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
 /// API Statistics
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Serialize)]
 pub struct APIStats {
     /// Total requests
     pub total_requests: u64,
@@ -80,6 +118,20 @@ pub struct APIStats {
     pub server_started: std::time::SystemTime,
 }
 
+impl Default for APIStats {
+    fn default() -> Self {
+        Self {
+            total_requests: 0,
+            successful_requests: 0,
+            failed_requests: 0,
+            n8n_requests: 0,
+            document_operations: 0,
+            prompt_executions: 0,
+            server_started: std::time::SystemTime::now(),
+        }
+    }
+}
+
 /// Command recognition and execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandRequest {
@@ -177,10 +229,7 @@ impl APIServer {
     pub fn new(pdf_manager: Arc<Mutex<PDFManager>>) -> Self {
         let config = ServerConfig::default();
         let tars_personality = TARSPersonality::default();
-        let stats = Arc::new(Mutex::new(APIStats {
-            server_started: std::time::SystemTime::now(),
-            ..Default::default()
-        }));
+        let stats = Arc::new(Mutex::new(APIStats::default()));
 
         Self {
             pdf_manager,
@@ -203,6 +252,11 @@ impl APIServer {
                 self.config.bind_address, self.config.port);
         }
 
+        if self.config.api_keys.is_empty() {
+            log::warn!("No API keys configured; any local process can drive the API on {}:{}",
+                self.config.bind_address, self.config.port);
+        }
+
         // Build routes
         let routes = self.build_routes().await;
 
@@ -216,16 +270,19 @@ impl APIServer {
     }
 
     /// Build API routes
-    async fn build_routes(&self) -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
+    async fn build_routes(&self) -> BoxedFilter<(warp::reply::Response,)> {
         let pdf_manager = self.pdf_manager.clone();
         let stats = self.stats.clone();
         let tars_personality = self.tars_personality.clone();
+        let progress = self.pdf_manager.lock().await.execution_progress();
 
-        // Base API path
-        let api = warp::path("api").and(warp::path("v1"));
+        // Base API path; everything under it needs an API key when keys are configured
+        let api = warp::path("api")
+            .and(warp::path("v1"))
+            .and(with_api_key(self.config.api_keys.clone()));
 
         // Command execution endpoint
-        let commands = api
+        let commands = api.clone()
             .and(warp::path("command"))
             .and(warp::post())
             .and(warp::body::json())
@@ -235,7 +292,7 @@ impl APIServer {
             .and_then(handle_command);
 
         // Document management endpoints
-        let documents = api
+        let documents = api.clone()
             .and(warp::path("documents"))
             .and(
                 warp::get()
@@ -257,7 +314,7 @@ impl APIServer {
             );
 
         // Prompt execution endpoints
-        let prompts = api
+        let prompts = api.clone()
             .and(warp::path("prompts"))
             .and(warp::path("execute"))
             .and(warp::post())
@@ -266,8 +323,20 @@ impl APIServer {
             .and(with_tars_personality(tars_personality.clone()))
             .and_then(execute_prompt);
 
+        // Execution progress streamed over WebSocket
+        let progress_stream = api.clone()
+            .and(warp::path("executions"))
+            .and(warp::path::param::<String>())
+            .and(warp::path("progress"))
+            .and(warp::path::end())
+            .and(warp::ws())
+            .and(with_progress(progress))
+            .map(|execution_id: String, ws: warp::ws::Ws, progress: Arc<ExecutionProgress>| {
+                ws.on_upgrade(move |socket| stream_progress(socket, progress, execution_id))
+            });
+
         // N8N webhook endpoint
-        let n8n_webhooks = api
+        let n8n_webhooks = api.clone()
             .and(warp::path("n8n"))
             .and(warp::path("webhook"))
             .and(warp::post())
@@ -277,15 +346,18 @@ impl APIServer {
             .and_then(handle_n8n_webhook);
 
         // Status and health endpoints
-        let status = api
+        let status = api.clone()
             .and(warp::path("status"))
             .and(warp::get())
             .and(with_stats(stats.clone()))
             .and(with_tars_personality(tars_personality.clone()))
             .and_then(get_status);
 
-        // Health check endpoint
+        // Health check endpoints, open to load balancers without a key
         let health = warp::path("health")
+            .or(warp::path("healthz"))
+            .unify()
+            .and(warp::path::end())
             .and(warp::get())
             .and_then(health_check);
 
@@ -293,19 +365,72 @@ impl APIServer {
         let routes = commands
             .or(documents)
             .or(prompts)
+            .or(progress_stream)
             .or(n8n_webhooks)
             .or(status)
-            .or(health);
+            .or(health)
+            .recover(handle_rejection);
 
-        // Add CORS if enabled
+        // Add CORS if enabled; requests from other origins are rejected
         if self.config.enable_cors {
-            routes.with(warp::cors().allow_any_origin()).boxed()
+            let cors = warp::cors()
+                .allow_origins(self.config.allowed_origins.iter().map(String::as_str))
+                .allow_methods(vec!["GET", "POST", "OPTIONS"])
+                .allow_headers(vec!["content-type", "authorization", "x-api-key"]);
+            routes.with(cors).map(Reply::into_response).boxed()
         } else {
-            routes.boxed()
+            routes.map(Reply::into_response).boxed()
         }
     }
 }
 
+/// Require one of `api_keys` in the `X-API-Key` header or as a bearer token.
+/// With no keys configured, every request passes.
+fn with_api_key(
+    api_keys: Vec<String>
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let api_keys = Arc::new(api_keys);
+    warp::header::optional::<String>("x-api-key")
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(move |api_key: Option<String>, authorization: Option<String>| {
+            let api_keys = api_keys.clone();
+            async move {
+                let bearer = authorization.as_deref()
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .map(str::trim);
+                let presented = api_key.as_deref().or(bearer);
+                
+                if api_keys.is_empty() || presented.is_some_and(|key| api_keys.iter().any(|k| k == key)) {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// Turn rejections into JSON error responses
+async fn handle_rejection(rejection: warp::Rejection) -> Result<warp::reply::Response, std::convert::Infallible> {
+    let (status, message) = if rejection.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "Missing or invalid API key")
+    } else if rejection.is_not_found() {
+        (StatusCode::NOT_FOUND, "Not found")
+    } else if rejection.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+    } else if rejection.find::<warp::body::BodyDeserializeError>().is_some() {
+        (StatusCode::BAD_REQUEST, "Invalid request body")
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+    };
+    
+    let body = warp::reply::json(&serde_json::json!({
+        "status": "error",
+        "message": message
+    }));
+    Ok(warp::reply::with_status(body, status).into_response())
+}
+
 // Helper functions for dependency injection
 fn with_pdf_manager(
     pdf_manager: Arc<Mutex<PDFManager>>
@@ -319,6 +444,12 @@ fn with_tars_personality(
     warp::any().map(move || personality.clone())
 }
 
+fn with_progress(
+    progress: Arc<ExecutionProgress>
+) -> impl Filter<Extract = (Arc<ExecutionProgress>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || progress.clone())
+}
+
 fn with_stats(
     stats: Arc<Mutex<APIStats>>
 ) -> impl Filter<Extract = (Arc<Mutex<APIStats>>,), Error = std::convert::Infallible> + Clone {
@@ -340,22 +471,25 @@ async fn handle_command(
         stats.total_requests += 1;
     }
 
-    // Parse and execute command
-    let response = match parse_and_execute_command(request, pdf_manager, &tars_personality).await {
+    // Parse and execute command; the boxed error isn't Send, so render it
+    // before awaiting the stats lock
+    let outcome = parse_and_execute_command(request, pdf_manager, &tars_personality).await
+        .map_err(|e| (e.to_string(), generate_tars_error_response(&tars_personality, e.as_ref())));
+    let response = match outcome {
         Ok(response) => {
             let mut stats = stats.lock().await;
             stats.successful_requests += 1;
             response
         }
-        Err(e) => {
+        Err((message, tars_response)) => {
             let mut stats = stats.lock().await;
             stats.failed_requests += 1;
             CommandResponse {
                 status: CommandStatus::Failed,
-                message: e.to_string(),
+                message,
                 execution_id: None,
                 interpretation: None,
-                tars_response: Some(generate_tars_error_response(&tars_personality, &e)),
+                tars_response: Some(tars_response),
                 data: None,
             }
         }
@@ -580,8 +714,8 @@ async fn execute_run_prompt_command(
             });
         }
         
-        let document = documents[0];
-        manager.run_prompt(&document.id, prompt_number).await?
+        let document_id = documents[0].id.clone();
+        manager.run_prompt(&document_id, prompt_number).await?
     };
     
     Ok(CommandResponse {
@@ -842,32 +976,92 @@ struct ExecutePromptRequest {
     prompt_number: u32,
 }
 
+/// Start a prompt execution in the background and return its ID, so the
+/// caller can follow it at `/api/v1/executions/{id}/progress`
 async fn execute_prompt(
     request: ExecutePromptRequest,
     pdf_manager: Arc<Mutex<PDFManager>>,
     tars_personality: TARSPersonality,
 ) -> Result<impl Reply, warp::Rejection> {
-    let mut manager = pdf_manager.lock().await;
+    let progress = {
+        let manager = pdf_manager.lock().await;
+        let prompt_exists = manager.document_store.get_document(&request.document_id)
+            .map(|document| document.prompts.iter().any(|p| p.number == request.prompt_number));
+        
+        match prompt_exists {
+            Ok(true) => manager.execution_progress(),
+            Ok(false) | Err(_) => {
+                let response = serde_json::json!({
+                    "status": "error",
+                    "message": format!("Prompt {} not found in document {}", request.prompt_number, request.document_id),
+                    "tars_response": "Execution failed. Even superior systems encounter occasional cosmic anomalies."
+                });
+                return Ok(warp::reply::json(&response));
+            }
+        }
+    };
     
-    match manager.run_prompt(&request.document_id, request.prompt_number).await {
-        Ok(execution_id) => {
-            let response = serde_json::json!({
-                "status": "processing",
-                "execution_id": execution_id,
-                "message": format!("Executing Prompt {}", request.prompt_number),
-                "tars_response": format!("Prompt {} execution initiated with characteristic TARS precision.", request.prompt_number)
-            });
-            Ok(warp::reply::json(&response))
-        },
-        Err(e) => {
-            let response = serde_json::json!({
-                "status": "error",
-                "message": e.to_string(),
-                "tars_response": "Execution failed. Even superior systems encounter occasional cosmic anomalies."
-            });
-            Ok(warp::reply::json(&response))
+    let execution_id = Uuid::new_v4().to_string();
+    let task_execution_id = execution_id.clone();
+    tokio::spawn(async move {
+        let mut manager = pdf_manager.lock().await;
+        if let Err(e) = manager.run_prompt_with_id(&request.document_id, request.prompt_number, &task_execution_id).await {
+            progress.fail(&task_execution_id, &e.to_string());
+        }
+    });
+    
+    let response = serde_json::json!({
+        "status": "processing",
+        "execution_id": execution_id,
+        "message": format!("Executing Prompt {}", request.prompt_number),
+        "tars_response": format!("Prompt {} execution initiated with characteristic TARS precision.", request.prompt_number)
+    });
+    Ok(warp::reply::json(&response))
+}
+
+/// Send the execution's events so far, then live events until it finishes
+/// or the client disconnects
+async fn stream_progress(socket: WebSocket, progress: Arc<ExecutionProgress>, execution_id: String) {
+    let (mut sender, mut receiver) = socket.split();
+    let (history, mut live) = progress.subscribe(&execution_id);
+    
+    for event in history {
+        if send_progress_event(&mut sender, &event).await.is_err() || event.is_finished() {
+            let _ = sender.close().await;
+            return;
         }
     }
+    
+    loop {
+        tokio::select! {
+            event = live.recv() => match event {
+                Ok(event) => {
+                    if send_progress_event(&mut sender, &event).await.is_err() || event.is_finished() {
+                        break;
+                    }
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("Progress client for {} lagged; {} events skipped", execution_id, skipped);
+                },
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = receiver.next() => match message {
+                Some(Ok(message)) if message.is_close() => break,
+                Some(Ok(_)) => {},
+                Some(Err(_)) | None => break,
+            },
+        }
+    }
+    
+    let _ = sender.close().await;
+}
+
+async fn send_progress_event(
+    sender: &mut futures_util::stream::SplitSink<WebSocket, Message>,
+    event: &ProgressEvent,
+) -> Result<(), warp::Error> {
+    let text = serde_json::to_string(event).unwrap_or_default();
+    sender.send(Message::text(text)).await
 }
 
 async fn handle_n8n_webhook(
@@ -974,10 +1168,124 @@ impl Default for ServerConfig {
             bind_address: "127.0.0.1".to_string(),
             port: 3001,
             enable_cors: true,
-            api_key: None,
+            allowed_origins: Vec::new(),
+            api_keys: Vec::new(),
             rate_limit: None,
             enable_logging: true,
             tars_responses: true,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_manager::{ActionContext, ActionExecutor, ExecutorConfig, PromptExecutor, StepResult};
+
+    struct FinishedTask;
+
+    #[async_trait::async_trait]
+    impl ActionExecutor for FinishedTask {
+        async fn execute(&self, context: &ActionContext<'_>) -> Result<StepResult, String> {
+            Ok(context.completed("done"))
+        }
+    }
+
+    /// Step numbers from `step_completed` messages up to `finished`
+    async fn completed_steps(client: &mut warp::test::WsClient) -> Vec<u64> {
+        let mut completed = Vec::new();
+        loop {
+            let message = client.recv().await.unwrap();
+            let event: serde_json::Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+            match event["type"].as_str().unwrap() {
+                "step_completed" => completed.push(event["result"]["step_number"].as_u64().unwrap()),
+                "finished" => {
+                    assert_eq!(event["status"], "Completed");
+                    return completed;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_without_api_key_are_rejected() {
+        let scratch = tempfile::tempdir().unwrap();
+        let storage = scratch.path();
+        let pdf_manager = Arc::new(Mutex::new(PDFManager::new(storage.to_path_buf()).unwrap()));
+        let mut config = Config::default();
+        config.api_server.api_keys = vec!["hunter2".to_string()];
+        config.api_server.allowed_origins = vec!["http://localhost:5173/".to_string()];
+
+        let mut server = APIServer::new(pdf_manager);
+        server.configure(ServerConfig::from_config(&config));
+        let routes = server.build_routes().await;
+
+        let rejected = warp::test::request().path("/api/v1/documents").reply(&routes).await;
+        assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+        let wrong_key = warp::test::request().path("/api/v1/documents")
+            .header("x-api-key", "guess").reply(&routes).await;
+        assert_eq!(wrong_key.status(), StatusCode::UNAUTHORIZED);
+
+        let accepted = warp::test::request().path("/api/v1/documents")
+            .header("x-api-key", "hunter2").reply(&routes).await;
+        assert_eq!(accepted.status(), StatusCode::OK);
+        let bearer = warp::test::request().path("/api/v1/documents")
+            .header("authorization", "Bearer hunter2").reply(&routes).await;
+        assert_eq!(bearer.status(), StatusCode::OK);
+
+        let health = warp::test::request().path("/healthz").reply(&routes).await;
+        assert_eq!(health.status(), StatusCode::OK);
+
+        let foreign = warp::test::request().path("/api/v1/documents")
+            .header("origin", "https://example.com")
+            .header("x-api-key", "hunter2").reply(&routes).await;
+        assert_eq!(foreign.status(), StatusCode::FORBIDDEN);
+        let local = warp::test::request().path("/api/v1/documents")
+            .header("origin", "http://localhost:5173")
+            .header("x-api-key", "hunter2").reply(&routes).await;
+        assert_eq!(local.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_cross_origin_requests_are_rejected_without_allowed_origins() {
+        let scratch = tempfile::tempdir().unwrap();
+        let pdf_manager = Arc::new(Mutex::new(PDFManager::new(scratch.path().to_path_buf()).unwrap()));
+        let mut server = APIServer::new(pdf_manager);
+        server.configure(ServerConfig::from_config(&Config::default()));
+        let routes = server.build_routes().await;
+
+        let foreign = warp::test::request().method("POST").path("/api/v1/command")
+            .header("origin", "https://example.com")
+            .json(&serde_json::json!({"command": "status", "source": "API", "parameters": {}})).reply(&routes).await;
+        assert_eq!(foreign.status(), StatusCode::FORBIDDEN);
+        let same_machine = warp::test::request().path("/api/v1/documents").reply(&routes).await;
+        assert_eq!(same_machine.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_progress_stream_sends_each_completed_step() {
+        let scratch = tempfile::tempdir().unwrap();
+        let storage = scratch.path();
+        let mut manager = PDFManager::new(storage.to_path_buf()).unwrap();
+        manager.executor = PromptExecutor::with_config(ExecutorConfig {
+            checkpoint_dir: storage.join("executions"),
+            ..ExecutorConfig::default()
+        }).unwrap();
+        manager.executor.register_custom_action("general_task", FinishedTask);
+        let plan = "## Prompt 1: Landing page\n- [ ] Plan the layout\n- [ ] Sketch the wireframes\n- [ ] Draft the copy\n";
+        let document_id = manager.process_markdown("plan.md", plan).await.unwrap();
+
+        let pdf_manager = Arc::new(Mutex::new(manager));
+        let routes = APIServer::new(pdf_manager.clone()).build_routes().await;
+        let path = "/api/v1/executions/landing-page/progress";
+
+        let mut live = warp::test::ws().path(path).handshake(routes.clone()).await.unwrap();
+        pdf_manager.lock().await.run_prompt_with_id(&document_id, 1, "landing-page").await.unwrap();
+        assert_eq!(completed_steps(&mut live).await, vec![1, 2, 3]);
+
+        // A client connecting afterwards gets the same steps from the snapshot
+        let mut late = warp::test::ws().path(path).handshake(routes).await.unwrap();
+        assert_eq!(completed_steps(&mut late).await, vec![1, 2, 3]);
+    }
+}
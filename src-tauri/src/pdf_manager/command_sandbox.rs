@@ -0,0 +1,565 @@
+//! TARS Command Sandbox
+//!
+//! Shell commands from ExecuteCommand and TestExecution steps, and the git
+//! commands behind GitOperation steps, are checked against a denylist, an
+//! allowlist and a working-directory jail before they run. Every simple
+//! command in a shell line is checked, and so is every file it redirects
+//! to. They run with a timeout and size-capped output, and every decision
+//! is audited.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Which commands may run, where, and for how long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxPolicy {
+    /// Command prefixes that may run. Empty allows anything not denied.
+    #[serde(default = "SandboxPolicy::default_allow")]
+    pub allow: Vec<String>,
+
+    /// Programs or command prefixes that never run; checked before `allow`
+    #[serde(default = "SandboxPolicy::default_deny")]
+    pub deny: Vec<String>,
+
+    /// Commands run in this directory or below it
+    #[serde(default = "SandboxPolicy::default_jail")]
+    pub jail: PathBuf,
+
+    #[serde(default = "SandboxPolicy::default_timeout")]
+    pub timeout: Duration,
+
+    /// Cap on captured stdout and on captured stderr, each
+    #[serde(default = "SandboxPolicy::default_max_output")]
+    pub max_output_bytes: usize,
+}
+
+impl SandboxPolicy {
+    fn default_allow() -> Vec<String> {
+        ["cargo build", "cargo check", "cargo test", "cargo clippy", "cargo fmt", "npm test", "npm run", "npm install", "git status", "git diff", "git log", "git init", "git add", "git commit", "ls", "echo"]
+            .iter().map(|rule| rule.to_string()).collect()
+    }
+
+    fn default_deny() -> Vec<String> {
+        ["rm", "sudo", "su", "dd", "mkfs", "shutdown", "reboot", "chmod", "chown", "curl", "wget", "git push", "sh", "bash", "zsh", "eval"]
+            .iter().map(|rule| rule.to_string()).collect()
+    }
+
+    fn default_jail() -> PathBuf {
+        PathBuf::from(".")
+    }
+
+    fn default_timeout() -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    fn default_max_output() -> usize {
+        64 * 1024
+    }
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            allow: Self::default_allow(),
+            deny: Self::default_deny(),
+            jail: Self::default_jail(),
+            timeout: Self::default_timeout(),
+            max_output_bytes: Self::default_max_output(),
+        }
+    }
+}
+
+/// One sandbox decision, and the result when the command ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandAuditEntry {
+    pub command: String,
+    pub working_dir: Option<String>,
+    pub allowed: bool,
+    /// The rule that denied the command
+    pub rule: Option<String>,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+}
+
+/// Where sandbox decisions are recorded
+#[async_trait]
+pub trait CommandAudit: Send + Sync {
+    async fn record(&self, entry: CommandAuditEntry);
+}
+
+/// Keeps audit entries in memory
+#[derive(Debug, Default)]
+pub struct MemoryAudit {
+    entries: Mutex<Vec<CommandAuditEntry>>,
+}
+
+impl MemoryAudit {
+    pub fn entries(&self) -> Vec<CommandAuditEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl CommandAudit for MemoryAudit {
+    async fn record(&self, entry: CommandAuditEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+}
+
+/// Captured result of a sandboxed command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// None when the command was killed
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Output beyond `max_output_bytes` was discarded
+    pub truncated: bool,
+    pub timed_out: bool,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+pub struct CommandSandbox {
+    policy: SandboxPolicy,
+    audit: Arc<dyn CommandAudit>,
+}
+
+impl CommandSandbox {
+    pub fn new(policy: SandboxPolicy, audit: Arc<dyn CommandAudit>) -> Self {
+        Self { policy, audit }
+    }
+
+    pub fn policy(&self) -> &SandboxPolicy {
+        &self.policy
+    }
+
+    /// Working directory for the command, or the rule that denies it
+    pub fn check(&self, command: &str, working_dir: Option<&str>) -> Result<PathBuf, String> {
+        let parsed = parse_command(command)?;
+        self.check_segments(&parsed)?;
+
+        let dir = self.jailed_dir(working_dir)?;
+        for target in &parsed.redirects {
+            self.check_redirect(&dir, target)?;
+        }
+        Ok(dir)
+    }
+
+    /// The allow or deny rule that stops the command, without the working
+    /// directory jail. For commands run on a remote system.
+    pub fn check_rules(&self, command: &str) -> Result<(), String> {
+        self.check_segments(&parse_command(command)?)
+    }
+
+    fn check_segments(&self, parsed: &ParsedCommand) -> Result<(), String> {
+        if parsed.segments.is_empty() {
+            return Err("empty command rule".to_string());
+        }
+        for segment in &parsed.segments {
+            if let Some(rule) = self.policy.deny.iter().find(|rule| matches_rule(segment, rule)) {
+                return Err(format!("denylist rule '{}'", rule));
+            }
+        }
+        if !self.policy.allow.is_empty() {
+            if let Some(segment) = parsed.segments.iter().find(|segment| !self.policy.allow.iter().any(|rule| matches_rule(segment, rule))) {
+                return Err(format!("allowlist (no rule matches '{}')", segment.join(" ")));
+            }
+        }
+        Ok(())
+    }
+
+    /// Redirection targets must stay inside the jail, like the working
+    /// directory. `/dev/null` is always allowed.
+    fn check_redirect(&self, dir: &Path, target: &str) -> Result<(), String> {
+        if target == "/dev/null" {
+            return Ok(());
+        }
+        if target.starts_with('~') || target.contains('$') {
+            return Err(format!("working directory jail (cannot resolve redirection to {})", target));
+        }
+        let jail = self.policy.jail.canonicalize()
+            .map_err(|e| format!("working directory jail ({} is unavailable: {})", self.policy.jail.display(), e))?;
+        let path = normalize(&dir.join(target));
+        if path.starts_with(&jail) {
+            Ok(())
+        } else {
+            Err(format!("working directory jail (redirection to {} is outside {})", path.display(), jail.display()))
+        }
+    }
+
+    fn jailed_dir(&self, working_dir: Option<&str>) -> Result<PathBuf, String> {
+        let jail = self.policy.jail.canonicalize()
+            .map_err(|e| format!("working directory jail ({} is unavailable: {})", self.policy.jail.display(), e))?;
+        let dir = match working_dir {
+            Some(dir) => jail.join(dir).canonicalize()
+                .map_err(|e| format!("working directory jail ({} is unavailable: {})", dir, e))?,
+            None => jail.clone(),
+        };
+        if dir.starts_with(&jail) {
+            Ok(dir)
+        } else {
+            Err(format!("working directory jail ({} is outside {})", dir.display(), jail.display()))
+        }
+    }
+
+    /// Record a denial from outside the sandbox rules (such as a missing
+    /// permission) and return the error message
+    pub async fn deny(&self, command: &str, working_dir: Option<&str>, rule: &str) -> String {
+        self.audit.record(CommandAuditEntry {
+            command: command.to_string(),
+            working_dir: working_dir.map(str::to_string),
+            allowed: false,
+            rule: Some(rule.to_string()),
+            exit_code: None,
+            timed_out: false,
+        }).await;
+        format!("Command denied by {}: {}", rule, command)
+    }
+
+    /// Check the command and run it, auditing the decision and the result.
+    /// Errors name the rule that denied the command.
+    pub async fn run(&self, command: &str, working_dir: Option<&str>) -> Result<CommandOutput, String> {
+        let (shell, flag) = if cfg!(target_os = "windows") { ("cmd", "/C") } else { ("sh", "-c") };
+        self.run_checked(command, working_dir, shell, &[flag, command]).await
+    }
+
+    /// Check and run a program with arguments, without a shell. The check
+    /// and the audit see the arguments quoted as one shell line.
+    pub async fn run_program(&self, args: &[&str], working_dir: Option<&str>) -> Result<CommandOutput, String> {
+        let command = args.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>().join(" ");
+        let (program, args) = args.split_first().ok_or("empty command rule")?;
+        self.run_checked(&command, working_dir, program, args).await
+    }
+
+    async fn run_checked(&self, command: &str, working_dir: Option<&str>, program: &str, args: &[&str]) -> Result<CommandOutput, String> {
+        let dir = match self.check(command, working_dir) {
+            Ok(dir) => dir,
+            Err(rule) => return Err(self.deny(command, working_dir, &rule).await),
+        };
+
+        let output = self.spawn(command, program, args, &dir).await;
+        self.audit.record(CommandAuditEntry {
+            command: command.to_string(),
+            working_dir: Some(dir.display().to_string()),
+            allowed: true,
+            rule: None,
+            exit_code: output.as_ref().ok().and_then(|output| output.exit_code),
+            timed_out: output.as_ref().is_ok_and(|output| output.timed_out),
+        }).await;
+        output
+    }
+
+    async fn spawn(&self, command: &str, program: &str, args: &[&str], dir: &Path) -> Result<CommandOutput, String> {
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Cannot run '{}': {}", command, e))?;
+        let stdout = child.stdout.take().ok_or("stdout not captured")?;
+        let stderr = child.stderr.take().ok_or("stderr not captured")?;
+
+        let cap = self.policy.max_output_bytes;
+        let finished = tokio::time::timeout(self.policy.timeout, async {
+            tokio::join!(read_capped(stdout, cap), read_capped(stderr, cap), child.wait())
+        }).await;
+
+        match finished {
+            Ok(((stdout, stdout_truncated), (stderr, stderr_truncated), status)) => {
+                let status = status.map_err(|e| format!("Cannot wait for '{}': {}", command, e))?;
+                Ok(CommandOutput {
+                    exit_code: status.code(),
+                    stdout: String::from_utf8_lossy(&stdout).into_owned(),
+                    stderr: String::from_utf8_lossy(&stderr).into_owned(),
+                    truncated: stdout_truncated || stderr_truncated,
+                    timed_out: false,
+                })
+            }
+            Err(_) => {
+                let _ = child.kill().await;
+                Ok(CommandOutput {
+                    exit_code: None,
+                    stdout: String::new(),
+                    stderr: format!("Timed out after {}s", self.policy.timeout.as_secs()),
+                    truncated: false,
+                    timed_out: true,
+                })
+            }
+        }
+    }
+}
+
+/// Read up to `cap` bytes, then drain the rest so the child never blocks on
+/// a full pipe
+async fn read_capped<R: AsyncRead + Unpin>(mut reader: R, cap: usize) -> (Vec<u8>, bool) {
+    let mut buf = Vec::new();
+    let _ = (&mut reader).take(cap as u64).read_to_end(&mut buf).await;
+    let rest = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await.unwrap_or(0);
+    (buf, rest > 0)
+}
+
+/// A shell line split into its simple commands, each as unquoted words,
+/// and the files it redirects to or from
+#[derive(Debug, Default, PartialEq)]
+struct ParsedCommand {
+    segments: Vec<Vec<String>>,
+    redirects: Vec<String>,
+}
+
+/// Words that run the command after them, so the rules look past them
+const COMMAND_PREFIXES: [&str; 15] = ["!", "{", "}", "(", ")", "if", "then", "else", "do", "while", "until", "exec", "command", "env", "nohup"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pending {
+    None,
+    /// The next word is a file the command reads or writes
+    File,
+    /// The next word is a here-document delimiter or a file descriptor
+    Other,
+}
+
+#[derive(Default)]
+struct Tokenizer {
+    parsed: ParsedCommand,
+    segment: Vec<String>,
+    word: String,
+    /// The word so far has quotes or escapes, so it isn't an fd number or
+    /// an empty word
+    quoted: bool,
+}
+
+impl Tokenizer {
+    fn end_word(&mut self, pending: &mut Pending) {
+        if self.word.is_empty() && !self.quoted {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        match std::mem::replace(pending, Pending::None) {
+            Pending::File => self.parsed.redirects.push(word),
+            Pending::Other => {}
+            Pending::None => self.segment.push(word),
+        }
+        self.quoted = false;
+    }
+
+    fn end_segment(&mut self, pending: &mut Pending) -> Result<(), String> {
+        self.end_word(pending);
+        if *pending != Pending::None {
+            return Err("redirection without a target rule".to_string());
+        }
+        let words: Vec<String> = std::mem::take(&mut self.segment).into_iter()
+            .skip_while(|word| COMMAND_PREFIXES.contains(&word.as_str()) || is_assignment(word))
+            .collect();
+        if !words.is_empty() {
+            self.parsed.segments.push(words);
+        }
+        Ok(())
+    }
+}
+
+/// Split a shell line on every control operator (`;`, `&`, `&&`, `|`,
+/// `||`, `|&`, parentheses and newlines) and collect redirection targets.
+/// Command and process substitution are refused outright, since the
+/// command they run can't be checked.
+fn parse_command(command: &str) -> Result<ParsedCommand, String> {
+    const SUBSTITUTION: &str = "shell substitution rule";
+    let mut tokens = Tokenizer::default();
+    let mut pending = Pending::None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                tokens.quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => tokens.word.push(c),
+                        None => return Err("unterminated quote rule".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                tokens.quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('`') => return Err(SUBSTITUTION.to_string()),
+                        Some('$') if chars.peek() == Some(&'(') => return Err(SUBSTITUTION.to_string()),
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => tokens.word.push(c),
+                            Some(c) => { tokens.word.push('\\'); tokens.word.push(c); }
+                            None => return Err("unterminated quote rule".to_string()),
+                        },
+                        Some(c) => tokens.word.push(c),
+                        None => return Err("unterminated quote rule".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                tokens.quoted = true;
+                match chars.next() {
+                    Some('\n') | None => {}
+                    Some(c) => tokens.word.push(c),
+                }
+            }
+            '`' => return Err(SUBSTITUTION.to_string()),
+            '$' if chars.peek() == Some(&'(') => return Err(SUBSTITUTION.to_string()),
+            '<' | '>' if chars.peek() == Some(&'(') => return Err(SUBSTITUTION.to_string()),
+            '<' | '>' => {
+                // `2>` and friends: a bare number right before the operator
+                // is the file descriptor, not a word
+                if !tokens.quoted && !tokens.word.is_empty() && tokens.word.chars().all(|c| c.is_ascii_digit()) {
+                    tokens.word.clear();
+                }
+                tokens.end_word(&mut pending);
+                if pending != Pending::None {
+                    return Err("redirection without a target rule".to_string());
+                }
+                pending = Pending::File;
+                if c == '<' && chars.peek() == Some(&'<') {
+                    // here-document or here-string: the next word is not a file
+                    chars.next();
+                    if chars.peek() == Some(&'<') {
+                        chars.next();
+                    }
+                    pending = Pending::Other;
+                } else if matches!(chars.peek(), Some('>' | '|')) || (c == '<' && chars.peek() == Some(&'>')) {
+                    chars.next();
+                }
+                if chars.peek() == Some(&'&') {
+                    // `>&2` duplicates a descriptor; `>&file` still writes a file
+                    chars.next();
+                    let mut lookahead = chars.clone();
+                    if matches!(lookahead.next(), Some('0'..='9' | '-')) && matches!(lookahead.next(), None | Some(' ' | '\t' | ';' | '&' | '|' | '\n' | ')')) {
+                        pending = Pending::Other;
+                    }
+                }
+            }
+            '&' if chars.peek() == Some(&'>') => {
+                // `&>file` and `&>>file` redirect stdout and stderr
+                tokens.end_word(&mut pending);
+            }
+            ';' | '&' | '|' | '\n' | '(' | ')' => tokens.end_segment(&mut pending)?,
+            c if c.is_whitespace() => tokens.end_word(&mut pending),
+            c => tokens.word.push(c),
+        }
+    }
+    tokens.end_segment(&mut pending)?;
+    Ok(tokens.parsed)
+}
+
+/// `NAME=value` before a command sets its environment
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Quote an argument so a shell reads it back as one word
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:@+,".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem, since a redirect
+/// target needn't exist yet
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => { normalized.pop(); }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// True when the segment starts with the rule's words. The program is
+/// compared by file name, so `/bin/rm` matches `rm`.
+fn matches_rule(segment: &[String], rule: &str) -> bool {
+    let rule: Vec<&str> = rule.split_whitespace().collect();
+    if rule.is_empty() || segment.len() < rule.len() {
+        return false;
+    }
+    let program = Path::new(&segment[0]).file_name().and_then(|name| name.to_str()).unwrap_or(&segment[0]);
+    program == rule[0] && segment[1..rule.len()].iter().zip(&rule[1..]).all(|(word, rule)| word == rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(jail: &Path) -> (CommandSandbox, Arc<MemoryAudit>) {
+        let audit = Arc::new(MemoryAudit::default());
+        let policy = SandboxPolicy { jail: jail.to_path_buf(), timeout: Duration::from_secs(60), ..SandboxPolicy::default() };
+        (CommandSandbox::new(policy, audit.clone()), audit)
+    }
+
+    #[tokio::test]
+    async fn test_denylisted_command_is_blocked_and_allowlisted_command_runs() {
+        let scratch = tempfile::tempdir().unwrap();
+        let jail = scratch.path();
+        let (sandbox, audit) = sandbox(jail);
+
+        let err = sandbox.run("rm -rf build", None).await.unwrap_err();
+        assert_eq!(err, "Command denied by denylist rule 'rm': rm -rf build");
+        assert!(sandbox.run("cargo build && /bin/rm -rf ~", None).await.is_err());
+        assert!(sandbox.run("cargo build", Some("..")).await.unwrap_err().contains("working directory jail"));
+
+        // No manifest in the jail, so cargo exits with an error, but it ran
+        let output = sandbox.run("cargo build", None).await.unwrap();
+        assert!(output.exit_code.is_some());
+        assert!(!output.timed_out);
+
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 4);
+        assert!(!entries[0].allowed);
+        assert_eq!(entries[0].rule.as_deref(), Some("denylist rule 'rm'"));
+        assert!(entries[3].allowed);
+        assert_eq!(entries[3].command, "cargo build");
+    }
+
+    #[test]
+    fn test_every_shell_operator_and_redirect_target_is_checked() {
+        let scratch = tempfile::tempdir().unwrap();
+        let jail = scratch.path();
+        let (sandbox, _) = sandbox(jail);
+
+        for command in ["echo ok & rm -rf ~", "echo ok&&rm x", "ls || rm x", "ls; rm x", "ls | rm x", "ls |& rm x", "(rm x)", "FOO=1 rm x", "ls\nrm x"] {
+            assert_eq!(sandbox.check(command, None).unwrap_err(), "denylist rule 'rm'", "{}", command);
+        }
+        for command in ["echo `rm x`", "echo $(rm x)", "echo \"$(rm x)\"", "diff <(ls) x"] {
+            assert_eq!(sandbox.check(command, None).unwrap_err(), "shell substitution rule", "{}", command);
+        }
+        for command in ["echo ok > ../../x", "echo ok >> /etc/passwd", "ls 2>/tmp/x", "echo ok &>../x", "echo ok >&../x", "ls < ~/.ssh/id_rsa", "echo ok > $HOME/x"] {
+            assert!(sandbox.check(command, None).unwrap_err().starts_with("working directory jail"), "{}", command);
+        }
+        assert_eq!(sandbox.check("echo ok >", None).unwrap_err(), "redirection without a target rule");
+
+        // Quoted operators are arguments, not operators
+        for command in ["echo 'a; rm b' > out.txt", "echo \"a && rm b\"", "ls > /dev/null 2>&1", "ls nested/../out.txt >>log.txt", "echo ok <<EOF"] {
+            assert!(sandbox.check(command, None).is_ok(), "{}", command);
+        }
+        assert_eq!(shell_quote("it's done"), "'it'\\''s done'");
+        assert_eq!(parse_command(&format!("git commit -m {}", shell_quote("it's done"))).unwrap().segments, vec![vec!["git", "commit", "-m", "it's done"]]);
+    }
+}
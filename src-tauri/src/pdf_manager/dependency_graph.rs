@@ -0,0 +1,345 @@
+//! TARS Dependency Graph Export
+//!
+//! Renders the prompt dependency graph of a document as Graphviz DOT or
+//! Mermaid. Edges point from a prompt to the prompts it depends on, and
+//! nodes are colored by status. Edges on a cycle are drawn dashed and
+//! labeled `cycle` instead of failing the export.
+//!
+//! Also computes the critical path: the longest chain of dependent prompts
+//! by estimated time, and the order prompts run in when a whole document
+//! executes.
+
+use super::{ExecutablePrompt, PdfError, PromptDocument, PromptStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A dependency edge; `cycle` is set when `to` can reach `from` again
+struct Edge {
+    from: u32,
+    to: u32,
+    cycle: bool,
+}
+
+/// Longest chain of dependent prompts by estimated time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriticalPath {
+    /// Prompt numbers on the path, in execution order
+    pub prompts: Vec<u32>,
+
+    /// Minimum completion time when independent prompts run in parallel
+    pub duration: Duration,
+}
+
+/// Why a document's prompts can't be put in execution order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    /// Numbers shared by more than one prompt, in ascending order
+    DuplicatePrompts(Vec<u32>),
+    /// Prompts on a dependency cycle, in ascending order
+    Cycle(Vec<u32>),
+}
+
+impl From<OrderError> for PdfError {
+    fn from(error: OrderError) -> Self {
+        match error {
+            OrderError::DuplicatePrompts(prompts) => PdfError::DuplicatePrompts { prompts },
+            OrderError::Cycle(prompts) => PdfError::DependencyCycle { prompts },
+        }
+    }
+}
+
+impl PromptDocument {
+    /// The critical path through the prompt dependencies. Edges on a cycle
+    /// and dependencies on missing prompts are ignored.
+    pub fn critical_path(&self) -> CriticalPath {
+        critical_path(&self.prompts)
+    }
+
+    /// Dependency graph in Graphviz DOT syntax
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n", escape_dot(&self.title));
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=box, style=\"rounded,filled\"];\n");
+
+        for prompt in &self.prompts {
+            dot.push_str(&format!("    p{} [label=\"{}\\n{:?}\", fillcolor=\"{}\"];\n",
+                prompt.number, escape_dot(&prompt_label(prompt)), prompt.status, status_color(&prompt.status)));
+        }
+        for number in missing_dependencies(&self.prompts) {
+            dot.push_str(&format!("    p{} [label=\"Prompt {}\\n(missing)\", style=dashed];\n", number, number));
+        }
+        for edge in dependency_edges(&self.prompts) {
+            if edge.cycle {
+                dot.push_str(&format!("    p{} -> p{} [style=dashed, color=red, label=\"cycle\"];\n", edge.from, edge.to));
+            } else {
+                dot.push_str(&format!("    p{} -> p{};\n", edge.from, edge.to));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Dependency graph in Mermaid flowchart syntax
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("graph LR\n");
+
+        for prompt in &self.prompts {
+            mermaid.push_str(&format!("    p{}[\"{}<br/>{:?}\"]\n",
+                prompt.number, escape_mermaid(&prompt_label(prompt)), prompt.status));
+        }
+        for number in missing_dependencies(&self.prompts) {
+            mermaid.push_str(&format!("    p{}[\"Prompt {}<br/>(missing)\"]\n", number, number));
+        }
+        for edge in dependency_edges(&self.prompts) {
+            let arrow = if edge.cycle { "-.->|cycle|" } else { "-->" };
+            mermaid.push_str(&format!("    p{} {} p{}\n", edge.from, arrow, edge.to));
+        }
+        for prompt in &self.prompts {
+            mermaid.push_str(&format!("    style p{} fill:{}\n", prompt.number, status_color(&prompt.status)));
+        }
+        for number in missing_dependencies(&self.prompts) {
+            mermaid.push_str(&format!("    style p{} stroke-dasharray: 5 5\n", number));
+        }
+
+        mermaid
+    }
+}
+
+fn prompt_label(prompt: &ExecutablePrompt) -> String {
+    if prompt.title.is_empty() {
+        format!("Prompt {}", prompt.number)
+    } else {
+        format!("Prompt {}: {}", prompt.number, prompt.title)
+    }
+}
+
+/// Fill color for a prompt status
+fn status_color(status: &PromptStatus) -> &'static str {
+    match status {
+        PromptStatus::Pending => "#e0e0e0",
+        PromptStatus::Ready => "#9ecae1",
+        PromptStatus::Running => "#fdd835",
+        PromptStatus::Completed => "#a1d99b",
+        PromptStatus::Failed => "#fc9272",
+        PromptStatus::Skipped => "#f5f5f5",
+        PromptStatus::Cancelled => "#bdbdbd",
+    }
+}
+
+/// Dependencies that name a prompt not in the document, in ascending order
+fn missing_dependencies(prompts: &[ExecutablePrompt]) -> Vec<u32> {
+    let mut missing: Vec<u32> = prompts.iter()
+        .flat_map(|prompt| prompt.dependencies.iter().copied())
+        .filter(|dep| !prompts.iter().any(|prompt| prompt.number == *dep))
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+/// Every dependency edge in prompt order, marking the edges that lie on a cycle
+fn dependency_edges(prompts: &[ExecutablePrompt]) -> Vec<Edge> {
+    let graph: HashMap<u32, &[u32]> = prompts.iter()
+        .map(|prompt| (prompt.number, prompt.dependencies.as_slice()))
+        .collect();
+
+    prompts.iter()
+        .flat_map(|prompt| prompt.dependencies.iter().map(move |dep| (prompt.number, *dep)))
+        .map(|(from, to)| Edge { from, to, cycle: reaches(&graph, to, from) })
+        .collect()
+}
+
+/// See [`PromptDocument::critical_path`]
+pub fn critical_path(prompts: &[ExecutablePrompt]) -> CriticalPath {
+    let estimates: HashMap<u32, Duration> = prompts.iter()
+        .map(|prompt| (prompt.number, prompt.estimated_time))
+        .collect();
+    let mut dependencies: HashMap<u32, Vec<u32>> = HashMap::new();
+    for edge in dependency_edges(prompts) {
+        if !edge.cycle && estimates.contains_key(&edge.to) {
+            dependencies.entry(edge.from).or_default().push(edge.to);
+        }
+    }
+
+    let mut finishes = HashMap::new();
+    let mut end: Option<(u32, Duration)> = None;
+    for prompt in prompts {
+        let finish = finish_time(prompt.number, &estimates, &dependencies, &mut finishes);
+        if end.is_none_or(|(_, longest)| finish > longest) {
+            end = Some((prompt.number, finish));
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut next = end.map(|(number, _)| number);
+    while let Some(number) = next {
+        path.push(number);
+        next = finishes.get(&number).and_then(|(_, previous)| *previous);
+    }
+    path.reverse();
+
+    CriticalPath {
+        prompts: path,
+        duration: end.map_or(Duration::ZERO, |(_, finish)| finish),
+    }
+}
+
+/// Prompt numbers with every prompt after the prompts it depends on, ties
+/// kept in document order. Dependencies on missing prompts don't constrain
+/// the order. Fails when a prompt number is used twice, since dependencies
+/// on it would be ambiguous, or with the prompts on a cycle.
+pub fn execution_order(prompts: &[ExecutablePrompt]) -> Result<Vec<u32>, OrderError> {
+    let mut numbers: Vec<u32> = prompts.iter().map(|prompt| prompt.number).collect();
+    numbers.sort();
+    let mut duplicates: Vec<u32> = numbers.windows(2)
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+        .collect();
+    if !duplicates.is_empty() {
+        duplicates.dedup();
+        return Err(OrderError::DuplicatePrompts(duplicates));
+    }
+
+    let graph: HashMap<u32, &[u32]> = prompts.iter()
+        .map(|prompt| (prompt.number, prompt.dependencies.as_slice()))
+        .collect();
+    let mut cycle: Vec<u32> = dependency_edges(prompts).into_iter()
+        .filter(|edge| edge.cycle)
+        .map(|edge| edge.from)
+        .collect();
+    if !cycle.is_empty() {
+        cycle.sort();
+        cycle.dedup();
+        return Err(OrderError::Cycle(cycle));
+    }
+
+    let mut order: Vec<u32> = Vec::with_capacity(prompts.len());
+    while order.len() < prompts.len() {
+        let next = prompts.iter()
+            .find(|prompt| {
+                !order.contains(&prompt.number)
+                    && prompt.dependencies.iter().all(|dep| order.contains(dep) || !graph.contains_key(dep))
+            })
+            .map(|prompt| prompt.number);
+        match next {
+            Some(number) => order.push(number),
+            // Unreachable with unique numbers and no cycle
+            None => break,
+        }
+    }
+    Ok(order)
+}
+
+/// Earliest finish of a prompt after all its dependencies, memoised with the
+/// dependency that finishes last
+fn finish_time(
+    number: u32,
+    estimates: &HashMap<u32, Duration>,
+    dependencies: &HashMap<u32, Vec<u32>>,
+    finishes: &mut HashMap<u32, (Duration, Option<u32>)>,
+) -> Duration {
+    if let Some((finish, _)) = finishes.get(&number) {
+        return *finish;
+    }
+
+    let mut latest: Option<(u32, Duration)> = None;
+    for dep in dependencies.get(&number).into_iter().flatten() {
+        let finish = finish_time(*dep, estimates, dependencies, finishes);
+        if latest.is_none_or(|(_, longest)| finish > longest) {
+            latest = Some((*dep, finish));
+        }
+    }
+
+    let start = latest.map_or(Duration::ZERO, |(_, finish)| finish);
+    let finish = start + estimates.get(&number).copied().unwrap_or_default();
+    finishes.insert(number, (finish, latest.map(|(dep, _)| dep)));
+    finish
+}
+
+/// True when `target` is reachable from `start` by following dependencies
+fn reaches(graph: &HashMap<u32, &[u32]>, start: u32, target: u32) -> bool {
+    let mut stack = vec![start];
+    let mut seen = Vec::new();
+    while let Some(node) = stack.pop() {
+        if node == target {
+            return true;
+        }
+        if seen.contains(&node) {
+            continue;
+        }
+        seen.push(node);
+        if let Some(deps) = graph.get(&node) {
+            stack.extend(deps.iter().copied());
+        }
+    }
+    false
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_manager::{document_parser, TARSPersonality};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_dot_edge_points_from_dependent_to_dependency() {
+        let plan = "# Rollout\n## Prompt 1: Schema\n- [x] Create the tables\n## Prompt 2: \"Backfill\"\nDepends on: Prompt 1\n- [ ] Copy the rows\n";
+        let mut document = document_parser::parse_markdown_content(plan, PathBuf::from("rollout.md"), &TARSPersonality::default()).unwrap();
+
+        let dot = document.to_dot();
+        assert!(dot.contains("    p2 -> p1;\n"));
+        assert!(!dot.contains("p1 -> p2"));
+        assert!(dot.contains("p2 [label=\"Prompt 2: \\\"Backfill\\\"\\nPending\", fillcolor=\"#e0e0e0\"];"));
+        assert!(document.to_mermaid().contains("    p2 --> p1\n"));
+
+        // A cycle still renders, with both edges marked
+        document.prompts[0].dependencies.push(2);
+        let dot = document.to_dot();
+        assert!(dot.contains("p1 -> p2 [style=dashed, color=red, label=\"cycle\"];"));
+        assert!(dot.contains("p2 -> p1 [style=dashed, color=red, label=\"cycle\"];"));
+        assert!(document.to_mermaid().contains("    p2 -.->|cycle| p1\n"));
+    }
+
+    #[test]
+    fn test_critical_path_of_diamond_is_longest_branch() {
+        let plan = "## Prompt 1: Design\n- [ ] Sketch\n\
+## Prompt 2: Backend\nDepends on: Prompt 1\n- [ ] Build\n\
+## Prompt 3: Frontend\nDepends on: Prompt 1\n- [ ] Build\n\
+## Prompt 4: Release\nDepends on: Prompt 2, 3\n- [ ] Ship\n";
+        let mut document = document_parser::parse_markdown_content(plan, PathBuf::from("diamond.md"), &TARSPersonality::default()).unwrap();
+        let minutes = [60, 120, 30, 60];
+        for (prompt, minutes) in document.prompts.iter_mut().zip(minutes) {
+            prompt.estimated_time = Duration::from_secs(minutes * 60);
+        }
+
+        let path = document.critical_path();
+        assert_eq!(path.prompts, vec![1, 2, 4]);
+        assert_eq!(path.duration, Duration::from_secs(240 * 60));
+        let total: Duration = document.prompts.iter().map(|p| p.estimated_time).sum();
+        assert_eq!(total, Duration::from_secs(270 * 60));
+    }
+
+    #[test]
+    fn test_execution_order_rejects_duplicate_prompt_numbers() {
+        let plan = "## Prompt 1: Schema\n- [ ] Create the tables\n\
+## Prompt 2: Backfill\nDepends on: Prompt 1\n- [ ] Copy the rows\n\
+## Prompt 3: Cutover\nDepends on: Prompt 2\n- [ ] Switch the reads\n";
+        let mut document = document_parser::parse_markdown_content(plan, PathBuf::from("rollout.md"), &TARSPersonality::default()).unwrap();
+        assert_eq!(execution_order(&document.prompts), Ok(vec![1, 2, 3]));
+
+        document.prompts[2].number = 1;
+        assert_eq!(execution_order(&document.prompts), Err(OrderError::DuplicatePrompts(vec![1])));
+        let error = PdfError::from(OrderError::DuplicatePrompts(vec![1]));
+        assert_eq!(error.to_string(), "Prompt numbers used more than once: 1");
+    }
+}
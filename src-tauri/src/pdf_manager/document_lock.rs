@@ -0,0 +1,41 @@
+//! TARS Document Locks
+//!
+//! One async lock per document, so executions that mutate the same document
+//! run one at a time while executions on different documents run in parallel.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Held for the length of an execution; dropping it lets the next one start
+pub type DocumentGuard = OwnedMutexGuard<()>;
+
+/// Execution locks by document ID. Clones share the same locks.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentLocks {
+    locks: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+}
+
+impl DocumentLocks {
+    /// Lock a document for an execution. With `wait`, queue behind an
+    /// execution already running on it; otherwise fail straight away.
+    pub async fn acquire(&self, document_id: &str, wait: bool) -> Result<DocumentGuard, String> {
+        let lock = self.locks.lock().unwrap()
+            .entry(document_id.to_string())
+            .or_default()
+            .clone();
+
+        if wait {
+            Ok(lock.lock_owned().await)
+        } else {
+            lock.try_lock_owned()
+                .map_err(|_| format!("Document {} is already executing", document_id))
+        }
+    }
+
+    /// True while an execution holds the document
+    pub fn is_locked(&self, document_id: &str) -> bool {
+        self.locks.lock().unwrap().get(document_id)
+            .is_some_and(|lock| lock.try_lock().is_err())
+    }
+}
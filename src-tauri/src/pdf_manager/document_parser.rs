@@ -2,14 +2,20 @@
 //! 
 //! Intelligent parsing of PDF prompt plans with TARS personality integration.
 //! Extracts structured prompts, dependencies, and execution steps from PDF documents.
+//! Markdown plans (GitHub issue or PR bodies) with `## Prompt N` headings and
+//! `- [ ]` task lists parse into the same structure. Requirement tables, ruled
+//! with `|` or laid out in aligned columns, give one requirement per row, and
+//! may continue across a page break.
 
 use super::{
     PromptDocument, ExecutablePrompt, ExecutionStep, DocumentMetadata, 
     ActionType, PromptStatus, StepStatus, TARSPersonality
 };
+use super::dependency_graph::critical_path;
+use super::pdf_text;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 use regex::Regex;
@@ -89,16 +95,146 @@ pub async fn parse_pdf_document(
     Ok(document)
 }
 
-/// Extract text content from PDF file
-async fn extract_pdf_text(file_path: &PathBuf) -> Result<String, Box<dyn std::error::Error>> {
-    // For now, we'll simulate PDF text extraction
-    // In a real implementation, you'd use a PDF library like `pdf-extract` or `poppler`
+/// True for `.md` and `.markdown` files
+pub fn is_markdown(file_path: &Path) -> bool {
+    file_path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Parse a Markdown plan file and extract structured prompts
+pub async fn parse_markdown_document(
+    file_path: PathBuf,
+    tars_personality: &TARSPersonality
+) -> Result<PromptDocument, Box<dyn std::error::Error>> {
+    
+    let tars_comment = generate_tars_processing_comment(tars_personality, &file_path);
+    println!("🤖 TARS: {}", tars_comment);
     
-    // Simulate reading PDF content
-    // This would be replaced with actual PDF parsing
-    let mock_content = generate_mock_pdf_content(file_path);
+    let content = tokio::fs::read_to_string(&file_path).await
+        .map_err(|e| format!("Cannot read {}: {}", file_path.display(), e))?;
+    let document = parse_markdown_content(&content, file_path, tars_personality)?;
     
-    Ok(mock_content)
+    let analysis_comment = generate_tars_analysis_comment(tars_personality, &document);
+    println!("🤖 TARS: {}", analysis_comment);
+    
+    Ok(document)
+}
+
+/// Parse Markdown, such as an issue or PR body, into a prompt document.
+/// Each `## Prompt N: Title` heading starts a prompt; its task list items
+/// become requirements, and checked `- [x]` tasks start out Completed.
+pub fn parse_markdown_content(
+    content: &str,
+    source: PathBuf,
+    tars_personality: &TARSPersonality
+) -> Result<PromptDocument, Box<dyn std::error::Error>> {
+    
+    let config = ParserConfig::default();
+    let header_re = Regex::new(r"(?i)^(#{1,6})\s*prompt\s+(\d+)\s*[:.\-]?\s*(.*)$")?;
+    let heading_re = Regex::new(r"^(#{1,6})\s")?;
+    let task_re = Regex::new(r"^[-*+]\s*\[( |x|X)\]\s*(.+)$")?;
+    
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prompts = Vec::new();
+    
+    for (i, line) in lines.iter().enumerate() {
+        let captures = match header_re.captures(line.trim()) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        let level = captures[1].len();
+        let number: u32 = captures[2].parse()?;
+        let title = captures[3].trim().to_string();
+        
+        // The prompt runs until the next heading at the same level or above
+        let mut prompt_content = Vec::new();
+        let mut checked = HashSet::new();
+        for line in lines.iter().skip(i + 1).map(|line| line.trim()) {
+            if let Some(heading) = heading_re.captures(line) {
+                if heading[1].len() <= level {
+                    break;
+                }
+            }
+            if line.is_empty() {
+                continue;
+            }
+            match task_re.captures(line) {
+                Some(task) => {
+                    let requirement = task[2].trim().to_string();
+                    if !task[1].trim().is_empty() {
+                        checked.insert(requirement.clone());
+                    }
+                    prompt_content.push(format!("- {}", requirement));
+                },
+                None => prompt_content.push(line.to_string()),
+            }
+        }
+        
+        let mut prompt = parse_single_prompt(number, title, prompt_content, &config, tars_personality)?;
+        for step in &mut prompt.execution_steps {
+            if checked.contains(&step.description) {
+                step.status = StepStatus::Completed;
+            }
+        }
+        prompts.push(prompt);
+    }
+    
+    prompts.sort_by_key(|p| p.number);
+    validate_prompt_dependencies(&mut prompts);
+    
+    let title = content.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| extract_document_title("", &source));
+    let metadata = calculate_document_metadata(&prompts, content);
+    
+    Ok(PromptDocument {
+        id: Uuid::new_v4().to_string(),
+        title,
+        file_path: source,
+        prompts,
+        metadata,
+        created_at: SystemTime::now(),
+        last_execution: None,
+    })
+}
+
+/// Extract the layout text of a PDF file. Page numbers at the top or bottom
+/// of a page are dropped so content, tables included, runs on across page
+/// breaks.
+async fn extract_pdf_text(file_path: &PathBuf) -> Result<String, Box<dyn std::error::Error>> {
+    let bytes = tokio::fs::read(file_path).await
+        .map_err(|e| format!("Cannot read {}: {}", file_path.display(), e))?;
+    let text = pdf_text::extract_text(&bytes)
+        .map_err(|e| format!("Cannot extract text from {}: {}", file_path.display(), e))?;
+    Ok(strip_page_numbers(&text))
+}
+
+/// Drop `Page 2`, `Page 2 of 5`, `2 / 5` or bare `2` lines that open or close
+/// a page, then join the pages
+fn strip_page_numbers(text: &str) -> String {
+    let page_number = Regex::new(r"(?i)^(page\s+)?\d+(\s*(of|/)\s*\d+)?$").unwrap();
+    let is_furniture = |line: &&str| page_number.is_match(line.trim());
+    text.split('\x0c')
+        .map(|page| {
+            let mut lines: Vec<&str> = page.lines().collect();
+            while lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+            if lines.last().is_some_and(is_furniture) {
+                lines.pop();
+            }
+            let first = lines.iter().position(|line| !line.trim().is_empty()).unwrap_or(lines.len());
+            if lines.get(first).is_some_and(is_furniture) {
+                lines.remove(first);
+            }
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// Parse document content and extract structured prompts
@@ -261,8 +397,9 @@ fn parse_single_prompt(
     
     let description = content.join("\n");
     
-    // Extract requirements (bullet points, numbered lists)
-    let requirements = extract_requirements(&content);
+    // Extract requirements (bullet points, numbered lists, table rows)
+    let parsed_requirements = extract_requirements(&content);
+    let requirements: Vec<String> = parsed_requirements.iter().map(|r| r.text.clone()).collect();
     
     // Extract dependencies
     let dependencies = extract_dependencies(&content, config);
@@ -274,7 +411,7 @@ fn parse_single_prompt(
     let estimated_time = estimate_execution_time(&requirements, &tags, config);
     
     // Parse execution steps
-    let execution_steps = parse_execution_steps(&requirements, number);
+    let execution_steps = parse_execution_steps(&parsed_requirements, number);
     
     // TARS adds personality-based insights
     let tars_insights = generate_tars_prompt_insights(tars_personality, &title, &requirements);
@@ -299,8 +436,27 @@ fn parse_single_prompt(
     Ok(prompt)
 }
 
+/// Header keywords marking the column that holds a table row's requirement text
+const REQUIREMENT_COLUMNS: [&str; 6] = ["requirement", "description", "task", "step", "action", "item"];
+
+/// How far, in characters, a column may drift between rows of an unruled table
+const COLUMN_TOLERANCE: usize = 3;
+
+/// A requirement, and for a table row its cells keyed by column header
+#[derive(Debug, Clone, PartialEq)]
+struct Requirement {
+    text: String,
+    columns: HashMap<String, String>,
+}
+
+impl Requirement {
+    fn line(text: &str) -> Self {
+        Self { text: text.to_string(), columns: HashMap::new() }
+    }
+}
+
 /// Extract requirements from prompt content
-fn extract_requirements(content: &[String]) -> Vec<String> {
+fn extract_requirements(content: &[String]) -> Vec<Requirement> {
     let mut requirements = Vec::new();
     
     let requirement_patterns = vec![
@@ -309,7 +465,15 @@ fn extract_requirements(content: &[String]) -> Vec<String> {
         r"^[-]\s*(.+)",        // Dash lists
     ];
     
-    for line in content {
+    let mut i = 0;
+    while i < content.len() {
+        if let Some((rows, consumed)) = parse_table(&content[i..]) {
+            requirements.extend(rows);
+            i += consumed;
+            continue;
+        }
+        let line = &content[i];
+        i += 1;
         for pattern in &requirement_patterns {
             if let Ok(re) = Regex::new(pattern) {
                 if let Some(captures) = re.captures(line) {
@@ -320,7 +484,7 @@ fn extract_requirements(content: &[String]) -> Vec<String> {
                     };
                     
                     if !requirement.is_empty() {
-                        requirements.push(requirement.to_string());
+                        requirements.push(Requirement::line(requirement));
                     }
                     break;
                 }
@@ -331,6 +495,121 @@ fn extract_requirements(content: &[String]) -> Vec<String> {
     requirements
 }
 
+/// A border or header underline such as `+----+----+` or `|---|:--|`
+fn is_table_rule(line: &str) -> bool {
+    line.chars().filter(|c| matches!(c, '-' | '=' | '─' | '━')).count() >= 3
+        && line.chars().all(|c| "-=+|:_ ─━│┼├┤┌┐└┘┬┴".contains(c))
+}
+
+/// Cells of a table row with their character offsets: split at `|` or `│`
+/// for ruled tables, otherwise at runs of two or more spaces. Bullets and
+/// single-cell lines aren't rows.
+fn table_cells(line: &str) -> Option<(bool, Vec<(usize, String)>)> {
+    if Regex::new(r"^[-*+]\s").unwrap().is_match(line) {
+        return None;
+    }
+    let ruled = line.contains('|') || line.contains('│');
+    let separator = if ruled { Regex::new(r"[|│]").unwrap() } else { Regex::new(r"\s{2,}|\t").unwrap() };
+    let mut cells = Vec::new();
+    let mut start = 0;
+    for gap in separator.find_iter(line).map(|m| (m.start(), m.end())).chain(std::iter::once((line.len(), line.len()))) {
+        let cell = &line[start..gap.0];
+        if !cell.trim().is_empty() {
+            cells.push((line[..start].chars().count(), cell.trim().to_string()));
+        } else if ruled && start > 0 && gap.0 < line.len() {
+            // An empty cell between two rules
+            cells.push((line[..start].chars().count(), String::new()));
+        }
+        start = gap.1;
+    }
+    (cells.len() >= 2).then_some((ruled, cells))
+}
+
+/// A first row of short, digit-free labels reads as column headers
+fn looks_like_header(cells: &[String]) -> bool {
+    cells.iter().all(|cell| {
+        cell.chars().next().is_some_and(char::is_alphabetic)
+            && cell.split_whitespace().count() <= 4
+            && !cell.chars().any(|c| c.is_ascii_digit())
+    })
+}
+
+/// `Acceptance Criteria` as `acceptance_criteria`, for step parameter keys
+fn column_key(header: &str) -> String {
+    header.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// A table at the start of `lines`: one requirement per data row and the
+/// number of lines consumed. Rows must keep the first row's column count,
+/// and for unruled tables its column positions. A header row repeated after
+/// a page break is skipped, so a table split across pages stays one table.
+fn parse_table(lines: &[String]) -> Option<(Vec<Requirement>, usize)> {
+    let mut i = lines.iter().take_while(|line| is_table_rule(line)).count();
+    let (ruled, first) = table_cells(lines.get(i)?)?;
+    let offsets: Vec<usize> = first.iter().map(|(offset, _)| *offset).collect();
+    let first: Vec<String> = first.into_iter().map(|(_, cell)| cell).collect();
+    i += 1;
+
+    let mut rule_after_first = false;
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    while let Some(line) = lines.get(i) {
+        if is_table_rule(line) {
+            rule_after_first |= rows.is_empty();
+            i += 1;
+            continue;
+        }
+        let cells = match table_cells(line) {
+            Some((row_ruled, cells)) if row_ruled == ruled && cells.len() == first.len() => cells,
+            _ => break,
+        };
+        let aligned = ruled || cells.iter().zip(&offsets).all(|((offset, _), expected)| offset.abs_diff(*expected) <= COLUMN_TOLERANCE);
+        if !aligned {
+            break;
+        }
+        let cells: Vec<String> = cells.into_iter().map(|(_, cell)| cell).collect();
+        if cells != first {
+            rows.push(cells);
+        }
+        i += 1;
+    }
+
+    let has_header = rule_after_first || looks_like_header(&first);
+    let (headers, rows) = if has_header {
+        (Some(first), rows)
+    } else {
+        (None, std::iter::once(first).chain(rows).collect())
+    };
+    let minimum_rows = if ruled && has_header { 1 } else { 2 };
+    if rows.len() < minimum_rows {
+        return None;
+    }
+
+    let text_column = headers.as_ref().and_then(|headers| {
+        headers.iter().position(|header| {
+            let header = header.to_lowercase();
+            REQUIREMENT_COLUMNS.iter().any(|keyword| header.contains(keyword))
+        })
+    });
+    let requirements = rows.into_iter()
+        .filter_map(|row| {
+            let text = match text_column {
+                Some(column) => row[column].clone(),
+                None => row.iter().filter(|cell| !cell.is_empty()).cloned().collect::<Vec<_>>().join(" - "),
+            };
+            let columns = headers.iter()
+                .flat_map(|headers| headers.iter().map(|header| column_key(header)).zip(row.iter().cloned()))
+                .filter(|(key, _)| !key.is_empty())
+                .collect();
+            (!text.is_empty()).then_some(Requirement { text, columns })
+        })
+        .collect();
+    Some((requirements, i))
+}
+
 /// Extract dependencies from prompt content
 fn extract_dependencies(content: &[String], config: &ParserConfig) -> Vec<u32> {
     let mut dependencies = Vec::new();
@@ -454,18 +733,20 @@ fn estimate_execution_time(
     total_time
 }
 
-/// Parse execution steps from requirements
-fn parse_execution_steps(requirements: &[String], prompt_number: u32) -> Vec<ExecutionStep> {
+/// Parse execution steps from requirements. A table row's cells are added
+/// to the step parameters under their column headers.
+fn parse_execution_steps(requirements: &[Requirement], prompt_number: u32) -> Vec<ExecutionStep> {
     let mut steps = Vec::new();
     
     for (i, requirement) in requirements.iter().enumerate() {
         let step_number = i as u32 + 1;
-        let action_type = determine_action_type(requirement);
-        let parameters = extract_step_parameters(requirement);
+        let action_type = determine_action_type(&requirement.text);
+        let mut parameters = extract_step_parameters(&requirement.text);
+        parameters.extend(requirement.columns.clone());
         
         let step = ExecutionStep {
             step_number,
-            description: requirement.clone(),
+            description: requirement.text.clone(),
             action_type,
             parameters,
             expected_output: None,
@@ -482,7 +763,12 @@ fn parse_execution_steps(requirements: &[String], prompt_number: u32) -> Vec<Exe
 fn determine_action_type(requirement: &str) -> ActionType {
     let req_lower = requirement.to_lowercase();
     
-    if req_lower.contains("create file") || req_lower.contains("write file") {
+    // Only an explicit `system: <id>` with a `command: <command>` runs on
+    // another machine; mentioning "remote" or "ssh" is not enough
+    let has = |pattern: &str| Regex::new(pattern).is_ok_and(|re| re.is_match(&req_lower));
+    if has(r"\bsystem:\s*\S") && has(r"\bcommand:\s*\S") {
+        ActionType::RemoteCommand
+    } else if req_lower.contains("create file") || req_lower.contains("write file") {
         ActionType::CreateFile
     } else if req_lower.contains("modify") || req_lower.contains("update") || req_lower.contains("edit") {
         ActionType::ModifyFile
@@ -518,6 +804,7 @@ fn extract_step_parameters(requirement: &str) -> HashMap<String, String> {
         (r"command[:]\s*([^\n,]+)", "command"),
         (r"url[:]\s*([^\s,]+)", "url"),
         (r"port[:]\s*(\d+)", "port"),
+        (r"system[:]\s*([^\s,]+)", "system"),
     ];
     
     for (pattern, param_name) in param_patterns {
@@ -554,6 +841,7 @@ fn calculate_document_metadata(prompts: &[ExecutablePrompt], content: &str) -> D
     let total_estimated_time = prompts.iter()
         .map(|p| p.estimated_time)
         .fold(Duration::from_secs(0), |acc, time| acc + time);
+    let critical_path_time = critical_path(prompts).duration;
     
     // Extract metadata from content
     let version = extract_version(content);
@@ -569,6 +857,7 @@ fn calculate_document_metadata(prompts: &[ExecutablePrompt], content: &str) -> D
     DocumentMetadata {
         prompt_count,
         total_estimated_time,
+        critical_path_time,
         version,
         author,
         pdf_created_at: None, // Would be extracted from actual PDF metadata
@@ -700,67 +989,87 @@ fn generate_tars_prompt_insights(tars_personality: &TARSPersonality, title: &str
     }
 }
 
-/// Generate mock PDF content for testing
-fn generate_mock_pdf_content(file_path: &PathBuf) -> String {
-    // This is a mock implementation for testing
-    // In reality, this would use a PDF parsing library
-    
-    format!(r#"PROJECT: E-Commerce Platform Development
-AUTHOR: Cooper
-VERSION: 1.0
-=================================================
-
-PROMPT 1: Initialize Project Structure
-- Create React frontend application
-- Setup Node.js backend server
-- Configure Docker containers for development
-- Initialize Git repository with proper .gitignore
-- Setup package.json with required dependencies
-[Tags: setup, initialization, docker]
-
-PROMPT 2: Database Design and Setup
-[Prerequisites: Prompt 1]
-- Design user authentication schema
-- Create product catalog tables
-- Setup order management system
-- Implement Redis caching layer
-- Configure database migrations
-[Tags: database, sql, redis, backend]
-
-PROMPT 3: API Development
-[Prerequisites: Prompt 1, Prompt 2]
-- Build RESTful endpoints for products
-- Implement GraphQL layer for complex queries
-- Add JWT authentication middleware
-- Setup rate limiting and security headers
-- Create API documentation with Swagger
-[Tags: api, backend, security, documentation]
-
-PROMPT 4: Frontend Components
-[Prerequisites: Prompt 3]
-- Build product listing page with filtering
-- Create shopping cart functionality
-- Implement checkout flow with validation
-- Add payment integration (Stripe)
-- Setup responsive design with CSS Grid
-[Tags: frontend, react, ui, payment]
-
-PROMPT 5: Testing and Quality Assurance
-[Prerequisites: Prompt 1, Prompt 2, Prompt 3, Prompt 4]
-- Write unit tests for all API endpoints
-- Create integration tests for user flows
-- Setup end-to-end testing with Cypress
-- Implement code coverage reporting
-- Add performance testing with Artillery
-[Tags: testing, quality, cypress, performance]
-
-PROMPT 6: Deployment and DevOps
-[Prerequisites: Prompt 5]
-- Setup CI/CD pipeline with GitHub Actions
-- Configure production Docker containers
-- Deploy to AWS with load balancing
-- Setup monitoring and logging
-- Implement backup and recovery procedures
-[Tags: deployment, devops, aws, monitoring]
-"#)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_plan_parses_prompts_and_tasks() {
+        let markdown = "# Settings revamp\n\
+\n\
+## Prompt 1: Scaffold the page\n\
+- [x] Create directory: src/settings\n\
+- [ ] Create file: src/settings/page.tsx\n\
+\n\
+## Prompt 2: Wire the API\n\
+Depends on: Prompt 1\n\
+- [ ] Add the settings API endpoint\n\
+- [ ] Write tests for the endpoint\n\
+   - [ ] Run command: npm test\n";
+
+        let document = parse_markdown_content(markdown, PathBuf::from("issue-42.md"), &TARSPersonality::default()).unwrap();
+        assert_eq!(document.title, "Settings revamp");
+        assert_eq!(document.prompts.len(), 2);
+
+        let first = &document.prompts[0];
+        assert_eq!((first.number, first.title.as_str()), (1, "Scaffold the page"));
+        assert_eq!(first.requirements, vec!["Create directory: src/settings", "Create file: src/settings/page.tsx"]);
+        assert_eq!(first.execution_steps[0].status, StepStatus::Completed);
+        assert_eq!(first.execution_steps[1].status, StepStatus::Pending);
+
+        let second = &document.prompts[1];
+        assert_eq!(second.requirements.len(), 3);
+        assert_eq!(second.dependencies, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_pdf_requirements_table_becomes_one_requirement_per_row() {
+        // Two pages: a ruled ID/Requirement table starts on the first and
+        // continues, header repeated, on the second. The rules are vector
+        // lines, so the extracted text is two columns separated by spaces.
+        let fixture = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/requirements_table.pdf"));
+        let document = parse_pdf_document(fixture, &TARSPersonality::default()).await.unwrap();
+        assert_eq!(document.prompts.len(), 2);
+
+        let first = &document.prompts[0];
+        assert_eq!(first.title, "Build the settings page");
+        assert_eq!(first.requirements, vec![
+            "Create the settings page layout",
+            "Add a dark mode toggle",
+            "Persist preferences locally",
+            "Validate form input on save",
+            "Write integration tests for settings",
+        ]);
+        let ids: Vec<&str> = first.execution_steps.iter().map(|step| step.parameters["id"].as_str()).collect();
+        assert_eq!(ids, vec!["R1", "R2", "R3", "R4", "R5"]);
+        assert_eq!(first.execution_steps[1].parameters["requirement"], "Add a dark mode toggle");
+
+        assert_eq!(document.prompts[1].requirements, vec!["Tag the release build"]);
+    }
+
+    #[test]
+    fn test_table_rows_keep_column_positions() {
+        let lines: Vec<String> = [
+            "Task             Owner",
+            "Add login form   Alice",
+            "Add logout       Bob",
+            "Deploy the build",
+        ].iter().map(|line| line.to_string()).collect();
+        let (rows, consumed) = parse_table(&lines).unwrap();
+        assert_eq!(consumed, 3);
+        assert_eq!(rows.iter().map(|row| row.text.as_str()).collect::<Vec<_>>(), vec!["Add login form", "Add logout"]);
+        assert_eq!(rows[1].columns["owner"], "Bob");
+
+        // Prose with a double space isn't a table
+        assert!(parse_table(&["Build it  carefully".to_string(), "then ship".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_only_explicit_system_and_command_make_a_remote_step() {
+        assert!(matches!(determine_action_type("Check system: pi, command: uptime"), ActionType::RemoteCommand));
+        assert!(matches!(determine_action_type("Run command: uptime on system: pi"), ActionType::RemoteCommand));
+        assert!(!matches!(determine_action_type("Update the remote dashboard"), ActionType::RemoteCommand));
+        assert!(!matches!(determine_action_type("Document the ssh key rotation"), ActionType::RemoteCommand));
+        assert!(!matches!(determine_action_type("Run command: uptime on the remote host"), ActionType::RemoteCommand));
+    }
 }
@@ -0,0 +1,112 @@
+//! TARS PDF Manager Errors
+
+use serde_json::json;
+
+use crate::error::TarsError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PdfError {
+    #[error("Document {id} not found")]
+    DocumentNotFound { id: String },
+    #[error("Document '{name}' not found")]
+    DocumentNameNotFound { name: String },
+    #[error("Prompt {number} not found in document {document_id}")]
+    PromptNotFound { document_id: String, number: u32 },
+    #[error("Document {id} is already executing")]
+    DocumentBusy { id: String },
+    #[error("Prompts {} depend on each other in a cycle", prompt_list(.prompts))]
+    DependencyCycle { prompts: Vec<u32> },
+    #[error("Prompt numbers used more than once: {}", prompt_list(.prompts))]
+    DuplicatePrompts { prompts: Vec<u32> },
+    #[error("Prompt {number} is held for approval request {request_id} (input guard: {guard})")]
+    HeldForApproval { number: u32, request_id: String, guard: String },
+    #[error("Execution {id} is still running")]
+    ExecutionRunning { id: String },
+    #[error("No rollback journal for execution {id}")]
+    NoJournal { id: String },
+    #[error("Rollback journal error: {0}")]
+    Journal(String),
+    #[error("Failed to parse {path}: {reason}")]
+    Parse { path: String, reason: String },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl TarsError for PdfError {
+    fn code(&self) -> &'static str {
+        match self {
+            PdfError::DocumentNotFound { .. } | PdfError::DocumentNameNotFound { .. } => "document_not_found",
+            PdfError::PromptNotFound { .. } => "prompt_not_found",
+            PdfError::DocumentBusy { .. } => "document_busy",
+            PdfError::DependencyCycle { .. } => "dependency_cycle",
+            PdfError::DuplicatePrompts { .. } => "duplicate_prompts",
+            PdfError::HeldForApproval { .. } => "held_for_approval",
+            PdfError::ExecutionRunning { .. } => "execution_running",
+            PdfError::NoJournal { .. } | PdfError::Journal(_) => "rollback_unavailable",
+            PdfError::Parse { .. } => "parse_failed",
+            PdfError::Io(_) => "io_error",
+            PdfError::Other(_) => "pdf_error",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            PdfError::DocumentNotFound { id }
+            | PdfError::DocumentBusy { id }
+            | PdfError::ExecutionRunning { id }
+            | PdfError::NoJournal { id } => Some(json!({ "id": id })),
+            PdfError::DocumentNameNotFound { name } => Some(json!({ "name": name })),
+            PdfError::PromptNotFound { document_id, number } => Some(json!({ "document_id": document_id, "number": number })),
+            PdfError::DependencyCycle { prompts } | PdfError::DuplicatePrompts { prompts } => Some(json!({ "prompts": prompts })),
+            PdfError::HeldForApproval { number, request_id, .. } => Some(json!({ "number": number, "request_id": request_id })),
+            PdfError::Parse { path, .. } => Some(json!({ "path": path })),
+            _ => None,
+        }
+    }
+}
+
+/// `1, 2 and 3`
+fn prompt_list(prompts: &[u32]) -> String {
+    let numbers: Vec<String> = prompts.iter().map(u32::to_string).collect();
+    match numbers.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => numbers.join(""),
+    }
+}
+
+/// Errors from the internal helpers that still return `Box<dyn Error>`.
+/// A boxed `PdfError` keeps its variant.
+impl From<Box<dyn std::error::Error>> for PdfError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        match error.downcast::<PdfError>() {
+            Ok(error) => *error,
+            Err(error) => PdfError::Other(error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_manager::{DocumentStore, PromptExecutor, TARSPersonality};
+
+    #[tokio::test]
+    async fn test_missing_document_surfaces_typed_error_with_its_id() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let mut store = DocumentStore::new(dir.to_path_buf()).unwrap();
+
+        let error = store.get_document("missing-doc").unwrap_err();
+        assert!(matches!(&error, PdfError::DocumentNotFound { id } if id == "missing-doc"));
+
+        // and from the executor, as the payload a Tauri command returns
+        let mut executor = PromptExecutor::new().unwrap();
+        let error = executor.execute_prompt(&mut store, "missing-doc", 1, &TARSPersonality::default()).await.unwrap_err();
+        let payload = error.payload();
+        assert_eq!(payload.code, "document_not_found");
+        assert_eq!(payload.message, "Document missing-doc not found");
+        assert_eq!(payload.details, Some(json!({ "id": "missing-doc" })));
+    }
+}
@@ -0,0 +1,182 @@
+//! TARS Execution Plan
+//!
+//! A dry run of a prompt: every step with its resolved parameters and the
+//! side effect it would have, built by `PromptExecutor::plan` without
+//! touching files, commands or the network. `PromptExecutor::execute_prompt_dry`
+//! runs the same checks through the execution path, one `StepResult` per step.
+
+use super::action_executors::{default_file_content, DEFAULT_TEST_COMMAND, GIT_OPERATIONS};
+use super::{ActionType, ExecutionStep};
+use serde::Serialize;
+use std::path::Path;
+
+/// What a prompt would do if it ran now
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionPlan {
+    pub document_id: String,
+    pub prompt_number: u32,
+    pub steps: Vec<PlannedStep>,
+}
+
+impl ExecutionPlan {
+    /// Side effects of the steps that would run, in order
+    pub fn effects(&self) -> Vec<&PlannedEffect> {
+        self.steps.iter()
+            .filter(|step| step.already_done.is_none() && step.blocked.is_none())
+            .map(|step| &step.effect)
+            .collect()
+    }
+}
+
+/// One step of the plan
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedStep {
+    /// The step with defaults filled into its parameters
+    pub step: ExecutionStep,
+    pub effect: PlannedEffect,
+
+    /// Why the step would be skipped, when its effect is already present
+    pub already_done: Option<String>,
+
+    /// Why the step would fail before doing anything, such as a missing
+    /// parameter or a sandbox denial
+    pub blocked: Option<String>,
+}
+
+/// The side effect of a step
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlannedEffect {
+    CreateFile { path: String },
+    ModifyFile { path: String },
+    CreateDirectory { path: String },
+    RunCommand { command: String, working_dir: Option<String> },
+    GitOperation { operation: String },
+    VSCodeAction { action: String },
+    ApiRequest { method: String, url: String },
+    DatabaseOperation { operation: String },
+    RemoteCommand { system: String, command: String },
+    /// Runs a handler registered for `ActionType::Custom(name)`
+    Custom { name: String },
+    /// Only reads state
+    None,
+}
+
+impl PlannedEffect {
+    /// What the step would do, for dry-run results
+    pub fn describe(&self) -> String {
+        match self {
+            PlannedEffect::CreateFile { path } => format!("create file {}", path),
+            PlannedEffect::ModifyFile { path } => format!("modify file {}", path),
+            PlannedEffect::CreateDirectory { path } => format!("create directory {}", path),
+            PlannedEffect::RunCommand { command, working_dir: Some(dir) } => format!("run `{}` in {}", command, dir),
+            PlannedEffect::RunCommand { command, working_dir: None } => format!("run `{}`", command),
+            PlannedEffect::GitOperation { operation } => format!("run git {}", operation),
+            PlannedEffect::VSCodeAction { action } => format!("run VS Code action '{}'", action),
+            PlannedEffect::ApiRequest { method, url } => format!("send {} {}", method, url),
+            PlannedEffect::DatabaseOperation { operation } => format!("run database {}", operation),
+            PlannedEffect::RemoteCommand { system, command } => format!("run `{}` on remote system {}", command, system),
+            PlannedEffect::Custom { name } => format!("run custom action '{}'", name),
+            PlannedEffect::None => "check state without changing anything".to_string(),
+        }
+    }
+
+    /// Why the effect changes state that can't be rolled back, for effects
+    /// that need an approved request before they run. Shell commands are
+    /// judged by the input guard's rules, at the call site.
+    pub fn irreversible_change(&self) -> Option<String> {
+        match self {
+            PlannedEffect::GitOperation { operation } if operation != "status" => {
+                Some(format!("git {} changes the repository", operation))
+            },
+            PlannedEffect::DatabaseOperation { operation } if operation != "query" => {
+                Some(format!("database {} changes stored data", operation))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Fill in the parameters an executor would default
+pub fn resolve_parameters(step: &ExecutionStep, document_title: &str) -> ExecutionStep {
+    let mut step = step.clone();
+    let defaults: Vec<(&str, String)> = match step.action_type {
+        ActionType::CreateFile => vec![("content", default_file_content(&step, document_title))],
+        ActionType::TestExecution => vec![("command", DEFAULT_TEST_COMMAND.to_string())],
+        ActionType::GitOperation => vec![("operation", "status".to_string())],
+        ActionType::VSCodeAction => vec![("action", "open".to_string())],
+        ActionType::APICall => vec![("method", "GET".to_string())],
+        ActionType::DatabaseOperation => vec![("operation", "query".to_string())],
+        _ => Vec::new(),
+    };
+    for (name, value) in defaults {
+        step.parameters.entry(name.to_string()).or_insert(value);
+    }
+    step
+}
+
+/// The effect of a step with resolved parameters, or the missing
+/// parameter that would make it fail
+pub fn planned_effect(step: &ExecutionStep) -> Result<PlannedEffect, String> {
+    let param = |name: &str| step.parameters.get(name).cloned()
+        .ok_or_else(|| format!("Missing parameter: {}", name));
+
+    Ok(match &step.action_type {
+        ActionType::CreateFile => PlannedEffect::CreateFile { path: param("file")? },
+        ActionType::ModifyFile => PlannedEffect::ModifyFile { path: param("file")? },
+        ActionType::CreateDirectory => PlannedEffect::CreateDirectory { path: param("directory")? },
+        ActionType::ExecuteCommand | ActionType::TestExecution => PlannedEffect::RunCommand {
+            command: param("command")?,
+            working_dir: step.parameters.get("cwd").cloned(),
+        },
+        ActionType::GitOperation => PlannedEffect::GitOperation { operation: param("operation")? },
+        ActionType::VSCodeAction => PlannedEffect::VSCodeAction { action: param("action")? },
+        ActionType::APICall => PlannedEffect::ApiRequest { method: param("method")?.to_uppercase(), url: param("url")? },
+        ActionType::DatabaseOperation => PlannedEffect::DatabaseOperation { operation: param("operation")? },
+        ActionType::RemoteCommand => PlannedEffect::RemoteCommand { system: param("system")?, command: param("command")? },
+        ActionType::Validation => PlannedEffect::None,
+        ActionType::Custom(name) => PlannedEffect::Custom { name: name.clone() },
+    })
+}
+
+/// Why a step with resolved parameters would fail before doing anything:
+/// a path that won't resolve or an operation its executor doesn't know.
+/// `earlier` holds the effects of the steps before it, so a file created
+/// by an earlier step counts as present.
+pub fn unresolved_step(step: &ExecutionStep, effect: &PlannedEffect, earlier: &[PlannedEffect]) -> Option<String> {
+    let created = |path: &str| Path::new(path).exists() || earlier.iter().any(|effect| match effect {
+        PlannedEffect::CreateFile { path: created } => created == path,
+        PlannedEffect::CreateDirectory { path: created } => Path::new(created).starts_with(path),
+        _ => false,
+    });
+
+    match effect {
+        PlannedEffect::CreateFile { path } => {
+            let parent = Path::new(path).parent().map(|parent| parent.to_string_lossy().to_string()).unwrap_or_default();
+            (!parent.is_empty() && !created(&parent)).then(|| format!("Parent directory does not exist: {}", parent))
+        },
+        PlannedEffect::ModifyFile { path } => (!created(path)).then(|| format!("File does not exist: {}", path)),
+        PlannedEffect::GitOperation { operation } => {
+            (!GIT_OPERATIONS.contains(&operation.as_str())).then(|| format!("Unknown git operation: {}", operation))
+        },
+        PlannedEffect::VSCodeAction { action } => {
+            let required = match action.as_str() {
+                "open" => "path",
+                "install_extension" => "extension",
+                _ => return Some(format!("Unknown VS Code action: {}", action)),
+            };
+            (!step.parameters.contains_key(required)).then(|| format!("Missing parameter: {}", required))
+        },
+        PlannedEffect::None if matches!(step.action_type, ActionType::Validation) => {
+            if step.parameters.get("type").is_some_and(|kind| kind != "file_exists") {
+                return None;
+            }
+            match step.parameters.get("file") {
+                Some(file) if created(file) => None,
+                Some(file) => Some(format!("Validation would fail: {} does not exist", file)),
+                None => Some("Missing parameter: file".to_string()),
+            }
+        },
+        _ => None,
+    }
+}
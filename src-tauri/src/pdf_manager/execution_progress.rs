@@ -0,0 +1,144 @@
+//! TARS Execution Progress
+//!
+//! Per-execution event log with live fan-out. Subscribers get the events so
+//! far and a receiver for the rest, so a late client misses nothing.
+
+use super::{PromptStatus, StepResult};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Finished executions whose events are kept for late subscribers
+const RETAINED_EXECUTIONS: usize = 32;
+
+/// Live updates buffered per subscriber before it lags
+const CHANNEL_CAPACITY: usize = 256;
+
+/// One progress update for an execution
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Started {
+        execution_id: String,
+        prompt_number: u32,
+        total_steps: usize,
+    },
+    StepCompleted {
+        execution_id: String,
+        result: StepResult,
+    },
+    Commentary {
+        execution_id: String,
+        message: String,
+    },
+    Finished {
+        execution_id: String,
+        status: PromptStatus,
+    },
+}
+
+impl ProgressEvent {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ProgressEvent::Finished { .. })
+    }
+}
+
+struct ProgressLog {
+    events: Vec<ProgressEvent>,
+    sender: broadcast::Sender<ProgressEvent>,
+}
+
+impl ProgressLog {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { events: Vec::new(), sender }
+    }
+}
+
+#[derive(Default)]
+struct ProgressState {
+    logs: HashMap<String, ProgressLog>,
+    finished: VecDeque<String>,
+}
+
+/// Progress of every execution, shared by the executor and the API server
+#[derive(Default)]
+pub struct ExecutionProgress {
+    state: Mutex<ProgressState>,
+}
+
+impl ExecutionProgress {
+    /// Record an event and send it to live subscribers. Logs that were only
+    /// ever subscribed to, and whose subscribers have all gone, are dropped.
+    pub fn publish(&self, execution_id: &str, event: ProgressEvent) {
+        let mut state = self.state.lock().unwrap();
+        state.logs.retain(|id, log| {
+            id == execution_id || !log.events.is_empty() || log.sender.receiver_count() > 0
+        });
+        let finished = event.is_finished();
+        let log = state.logs.entry(execution_id.to_string()).or_insert_with(ProgressLog::new);
+        log.events.push(event.clone());
+        // No receivers is fine; the event is still in the log
+        let _ = log.sender.send(event);
+
+        if finished {
+            state.finished.push_back(execution_id.to_string());
+            while state.finished.len() > RETAINED_EXECUTIONS {
+                if let Some(oldest) = state.finished.pop_front() {
+                    state.logs.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Report an execution that failed before or outside the executor's own
+    /// reporting. Does nothing if the execution already finished.
+    pub fn fail(&self, execution_id: &str, error: &str) {
+        let already_finished = self.state.lock().unwrap().logs.get(execution_id)
+            .is_some_and(|log| log.events.iter().any(ProgressEvent::is_finished));
+        if already_finished {
+            return;
+        }
+        self.publish(execution_id, ProgressEvent::Commentary {
+            execution_id: execution_id.to_string(),
+            message: error.to_string(),
+        });
+        self.publish(execution_id, ProgressEvent::Finished {
+            execution_id: execution_id.to_string(),
+            status: PromptStatus::Failed,
+        });
+    }
+
+    /// The events so far and a receiver for later ones. Subscribing before
+    /// an execution starts is allowed.
+    pub fn subscribe(&self, execution_id: &str) -> (Vec<ProgressEvent>, broadcast::Receiver<ProgressEvent>) {
+        let mut state = self.state.lock().unwrap();
+        let log = state.logs.entry(execution_id.to_string()).or_insert_with(ProgressLog::new);
+        (log.events.clone(), log.sender.subscribe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commentary(execution_id: &str) -> ProgressEvent {
+        ProgressEvent::Commentary { execution_id: execution_id.to_string(), message: "Working.".to_string() }
+    }
+
+    #[test]
+    fn test_publish_prunes_abandoned_subscriptions() {
+        let progress = ExecutionProgress::default();
+        let (_, abandoned) = progress.subscribe("never-started");
+        let (_, _watching) = progress.subscribe("starting-soon");
+        drop(abandoned);
+
+        progress.publish("running", commentary("running"));
+
+        let state = progress.state.lock().unwrap();
+        assert!(!state.logs.contains_key("never-started"));
+        assert!(state.logs.contains_key("starting-soon"));
+        assert!(state.logs.contains_key("running"));
+    }
+}
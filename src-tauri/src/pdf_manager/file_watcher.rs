@@ -151,7 +151,7 @@ pub struct ProcessingResult {
 }
 
 /// Processing status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProcessingStatus {
     Success,
     Failed,
@@ -266,7 +266,7 @@ impl FileWatcher {
                 self.process_file_entry(&path).await?;
             } else if path.is_dir() && self.should_watch_subdirectory(&path) {
                 // Optionally watch subdirectories
-                self.scan_directory(&path).await?;
+                Box::pin(self.scan_directory(&path)).await?;
             }
         }
         
@@ -306,7 +306,7 @@ impl FileWatcher {
                 event_type: FileEventType::ProcessingFailed,
                 file_path: file_path.to_path_buf(),
                 timestamp: SystemTime::now(),
-                metadata,
+                metadata: metadata.clone(),
                 processing_result: Some(ProcessingResult {
                     status: ProcessingStatus::Skipped,
                     duration: Duration::from_secs(0),
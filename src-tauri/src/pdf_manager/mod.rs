@@ -11,12 +11,22 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
 pub mod document_parser;
+pub mod action_executors;
+pub mod command_sandbox;
+pub mod dependency_graph;
+pub mod document_lock;
+pub mod error;
+pub mod execution_plan;
+pub mod execution_progress;
 pub mod prompt_executor;
+pub mod rollback_journal;
 pub mod n8n_integration;
+pub mod pdf_text;
 pub mod file_watcher;
 pub mod api_server;
 
@@ -52,6 +62,9 @@ pub struct DocumentStore {
     
     /// Storage directory
     storage_path: PathBuf,
+    
+    /// One execution at a time per document; shared by clones of the store
+    execution_locks: DocumentLocks,
 }
 
 /// Represents a parsed PDF document with structured prompts
@@ -148,6 +161,7 @@ pub enum ActionType {
     DatabaseOperation,
     TestExecution,
     Validation,
+    RemoteCommand,
     Custom(String),
 }
 
@@ -179,9 +193,14 @@ pub struct DocumentMetadata {
     /// Total number of prompts
     pub prompt_count: u32,
     
-    /// Estimated total time
+    /// Sum of all prompt estimates (total effort)
     pub total_estimated_time: Duration,
     
+    /// Length of the critical path: the minimum completion time when
+    /// independent prompts run in parallel
+    #[serde(default)]
+    pub critical_path_time: Duration,
+    
     /// Document version
     pub version: String,
     
@@ -260,6 +279,14 @@ pub struct ExecutionSummary {
     /// Failed prompts
     pub failed_prompts: u32,
     
+    /// Prompts skipped because a dependency didn't complete
+    #[serde(default)]
+    pub skipped_prompts: u32,
+    
+    /// Prompt numbers in the order they were resolved to run
+    #[serde(default)]
+    pub execution_order: Vec<u32>,
+    
     /// Overall success rate
     pub success_rate: f64,
     
@@ -289,7 +316,7 @@ impl Default for TARSPersonality {
 
 impl PDFManager {
     /// Initialize TARS PDF Manager
-    pub fn new(storage_path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(storage_path: PathBuf) -> Result<Self, PdfError> {
         let document_store = DocumentStore::new(storage_path.clone())?;
         let executor = PromptExecutor::new()?;
         let n8n_handler = N8NIntegration::new()?;
@@ -306,8 +333,26 @@ impl PDFManager {
     }
 
     /// Process a new PDF document
-    pub async fn process_document(&mut self, file_path: PathBuf) -> Result<String, Box<dyn std::error::Error>> {
-        let document = document_parser::parse_pdf_document(file_path, &self.tars_personality).await?;
+    pub async fn process_document(&mut self, file_path: PathBuf) -> Result<String, PdfError> {
+        let path = file_path.display().to_string();
+        let document = if document_parser::is_markdown(&file_path) {
+            document_parser::parse_markdown_document(file_path, &self.tars_personality).await
+        } else {
+            document_parser::parse_pdf_document(file_path, &self.tars_personality).await
+        };
+        let document = document.map_err(|e| PdfError::Parse { path, reason: e.to_string() })?;
+        self.store_processed_document(document).await
+    }
+
+    /// Process a Markdown plan held in memory, such as an issue or PR body
+    /// fetched through the GitHub integration. `source` names the plan.
+    pub async fn process_markdown(&mut self, source: &str, markdown: &str) -> Result<String, PdfError> {
+        let document = document_parser::parse_markdown_content(markdown, PathBuf::from(source), &self.tars_personality)
+            .map_err(|e| PdfError::Parse { path: source.to_string(), reason: e.to_string() })?;
+        self.store_processed_document(document).await
+    }
+
+    async fn store_processed_document(&mut self, document: PromptDocument) -> Result<String, PdfError> {
         let document_id = document.id.clone();
         
         // Store the document
@@ -320,21 +365,55 @@ impl PDFManager {
     }
 
     /// Execute a specific prompt by number
-    pub async fn run_prompt(&mut self, document_id: &str, prompt_number: u32) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn run_prompt(&mut self, document_id: &str, prompt_number: u32) -> Result<String, PdfError> {
+        let execution_id = Uuid::new_v4().to_string();
+        self.run_prompt_with_id(document_id, prompt_number, &execution_id).await
+    }
+
+    /// Execute a prompt under a caller-chosen execution ID, so clients can
+    /// subscribe to its progress while it runs
+    pub async fn run_prompt_with_id(&mut self, document_id: &str, prompt_number: u32, execution_id: &str) -> Result<String, PdfError> {
         // TARS personality check
         self.tars_response_prompt_execution(document_id, prompt_number).await;
         
         // Execute the prompt
-        let execution_id = self.executor.execute_prompt(
+        let execution_id = self.executor.execute_prompt_with_id(
             &mut self.document_store,
             document_id,
             prompt_number,
-            &self.tars_personality
+            &self.tars_personality,
+            execution_id
         ).await?;
         
         Ok(execution_id)
     }
 
+    /// Execute every prompt of a document in dependency order
+    pub async fn run_document(&mut self, document_id: &str) -> Result<ExecutionSummary, PdfError> {
+        self.executor.execute_document(&mut self.document_store, document_id, &self.tars_personality).await
+    }
+
+    /// Undo the file changes of a finished or failed execution
+    pub fn rollback_execution(&self, execution_id: &str) -> Result<RollbackReport, PdfError> {
+        self.executor.rollback(execution_id)
+    }
+
+    /// Validate a prompt through the execution path without side effects,
+    /// one result per step describing what it would do
+    pub async fn run_prompt_dry(&mut self, document_id: &str, prompt_number: u32) -> Result<Vec<StepResult>, PdfError> {
+        self.executor.execute_prompt_dry(&self.document_store, document_id, prompt_number, &self.tars_personality).await
+    }
+
+    /// What a prompt would do, without running it
+    pub fn plan_prompt(&self, document_id: &str, prompt_number: u32) -> Result<ExecutionPlan, PdfError> {
+        self.executor.plan(&self.document_store, document_id, prompt_number)
+    }
+
+    /// Progress events for every execution
+    pub fn execution_progress(&self) -> Arc<ExecutionProgress> {
+        self.executor.progress()
+    }
+
     /// TARS response when document is processed
     async fn tars_response_document_processed(&self, document_id: &str) {
         if let Ok(doc) = self.document_store.get_document(document_id) {
@@ -380,7 +459,7 @@ impl PDFManager {
 
 impl DocumentStore {
     /// Create new document store
-    pub fn new(storage_path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(storage_path: PathBuf) -> Result<Self, PdfError> {
         std::fs::create_dir_all(&storage_path)?;
         
         Ok(Self {
@@ -388,11 +467,17 @@ impl DocumentStore {
             document_names: HashMap::new(),
             active_document: None,
             storage_path,
+            execution_locks: DocumentLocks::default(),
         })
     }
 
+    /// Locks that serialize executions on each document
+    pub fn execution_locks(&self) -> DocumentLocks {
+        self.execution_locks.clone()
+    }
+
     /// Add document to store
-    pub fn add_document(&mut self, document: PromptDocument) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn add_document(&mut self, document: PromptDocument) -> Result<(), PdfError> {
         let id = document.id.clone();
         let title = document.title.clone();
         
@@ -408,15 +493,21 @@ impl DocumentStore {
     }
 
     /// Get document by ID
-    pub fn get_document(&self, document_id: &str) -> Result<&PromptDocument, Box<dyn std::error::Error>> {
+    pub fn get_document(&self, document_id: &str) -> Result<&PromptDocument, PdfError> {
         self.documents.get(document_id)
-            .ok_or_else(|| format!("Document {} not found", document_id).into())
+            .ok_or_else(|| PdfError::DocumentNotFound { id: document_id.to_string() })
+    }
+
+    /// Get document by ID for updating
+    pub fn get_document_mut(&mut self, document_id: &str) -> Result<&mut PromptDocument, PdfError> {
+        self.documents.get_mut(document_id)
+            .ok_or_else(|| PdfError::DocumentNotFound { id: document_id.to_string() })
     }
 
     /// Get document by name
-    pub fn get_document_by_name(&self, name: &str) -> Result<&PromptDocument, Box<dyn std::error::Error>> {
+    pub fn get_document_by_name(&self, name: &str) -> Result<&PromptDocument, PdfError> {
         let id = self.document_names.get(name)
-            .ok_or_else(|| format!("Document '{}' not found", name))?;
+            .ok_or_else(|| PdfError::DocumentNameNotFound { name: name.to_string() })?;
         self.get_document(id)
     }
 
@@ -426,12 +517,12 @@ impl DocumentStore {
     }
 
     /// Set active document
-    pub fn set_active_document(&mut self, document_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn set_active_document(&mut self, document_id: &str) -> Result<(), PdfError> {
         if self.documents.contains_key(document_id) {
             self.active_document = Some(document_id.to_string());
             Ok(())
         } else {
-            Err(format!("Document {} not found", document_id).into())
+            Err(PdfError::DocumentNotFound { id: document_id.to_string() })
         }
     }
 
@@ -444,7 +535,15 @@ impl DocumentStore {
 
 // Re-export key components
 pub use document_parser::*;
+pub use action_executors::{ActionContext, ActionExecutor, ActionRegistry};
+pub use command_sandbox::{CommandSandbox, SandboxPolicy};
+pub use dependency_graph::CriticalPath;
+pub use document_lock::{DocumentGuard, DocumentLocks};
+pub use error::PdfError;
+pub use execution_plan::{ExecutionPlan, PlannedEffect, PlannedStep};
+pub use execution_progress::{ExecutionProgress, ProgressEvent};
 pub use prompt_executor::*;
+pub use rollback_journal::{JournalEntry, RollbackReport};
 pub use n8n_integration::*;
 pub use file_watcher::*;
 pub use api_server::*;
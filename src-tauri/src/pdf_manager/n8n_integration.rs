@@ -277,7 +277,7 @@ impl N8NIntegration {
         self.tars_webhook_received(&request).await;
         
         // Process the action
-        match request.action {
+        match request.action.clone() {
             N8NAction::ExecutePrompt { document_name, prompt_number, auto_approve } => {
                 self.handle_execute_prompt(request, document_name, prompt_number, auto_approve).await
             },
@@ -0,0 +1,832 @@
+//! Text extraction from PDF content streams
+//!
+//! Keeps enough of the page layout for the parser to see tables: text runs
+//! are grouped into lines by baseline, and runs far apart on a line are
+//! separated by at least two spaces so columns stay distinguishable.
+//! Pages are separated by form feeds, as `pdftotext -layout` does.
+//! Handles uncompressed and FlateDecode streams, object streams, simple
+//! single-byte fonts, and CID-keyed (Type0) fonts through their ToUnicode
+//! map. A page whose glyphs can't be decoded, such as a CID-keyed font with
+//! no ToUnicode map, is an error rather than an empty page, as is a document
+//! with no text at all (scanned pages need OCR first).
+
+use flate2::read::ZlibDecoder;
+use regex::bytes::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::rc::Rc;
+
+/// Average glyph advance as a fraction of the font size; no font metrics are read
+const AVERAGE_GLYPH_WIDTH: f32 = 0.5;
+
+/// Gap between runs, in average glyph widths, that starts a new column
+const COLUMN_GAP: f32 = 2.0;
+
+/// `TJ` kerning, in thousandths of an em, wide enough to be a word space
+const KERN_SPACE: f32 = 200.0;
+
+/// Deepest page tree walked, guarding against reference cycles
+const MAX_PAGE_TREE_DEPTH: usize = 32;
+
+/// Most codes one ToUnicode `bfrange` entry may map
+const MAX_CMAP_RANGE: u32 = 0xFFFF;
+
+/// An indirect object's dictionary text and decoded stream, if any
+struct PdfObject {
+    dictionary: String,
+    stream: Option<Vec<u8>>,
+}
+
+/// A piece of text placed on the page, in device space
+#[derive(Debug, Clone)]
+struct TextRun {
+    x: f32,
+    y: f32,
+    width: f32,
+    size: f32,
+    text: String,
+}
+
+/// How a font's string bytes map to text
+#[derive(Debug, Default)]
+struct Font {
+    /// Type0 (CID-keyed) fonts use two-byte codes
+    two_byte: bool,
+    /// Character code to text, from the font's ToUnicode CMap
+    to_unicode: HashMap<u32, String>,
+}
+
+impl Font {
+    /// Each character code in `bytes` with its text. Single-byte codes
+    /// without a ToUnicode entry are read as Latin-1; two-byte codes without
+    /// one can't be decoded.
+    fn decode(&self, bytes: &[u8]) -> Vec<(u32, Option<String>)> {
+        let codes: Vec<u32> = if self.two_byte {
+            bytes.chunks(2).map(|pair| pair.iter().fold(0, |code, &b| code << 8 | b as u32)).collect()
+        } else {
+            bytes.iter().map(|&b| b as u32).collect()
+        };
+        codes.into_iter().map(|code| {
+            let text = match self.to_unicode.get(&code) {
+                Some(text) => Some(text.clone()),
+                None if !self.two_byte => Some((code as u8 as char).to_string()),
+                None => None,
+            };
+            (code, text)
+        }).collect()
+    }
+}
+
+/// What a page's content stream drew
+struct PageText {
+    runs: Vec<TextRun>,
+    /// Glyphs shown by text operators
+    glyphs: usize,
+    /// Of those, how many decoded to text
+    decoded: usize,
+}
+
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+fn translation(tx: f32, ty: f32) -> Matrix {
+    [1.0, 0.0, 0.0, 1.0, tx, ty]
+}
+
+/// Layout text of every page, pages separated by form feeds
+pub fn extract_text(data: &[u8]) -> Result<String, String> {
+    if !data.starts_with(b"%PDF") {
+        return Err("Not a PDF file: missing %PDF header".to_string());
+    }
+    let objects = parse_objects(data);
+    let pages = page_order(&objects);
+    if pages.is_empty() {
+        return Err("No pages found in PDF".to_string());
+    }
+    let mut texts = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
+        let drawn = page_text(&objects, *page);
+        if drawn.glyphs > 0 && drawn.decoded == 0 {
+            return Err(format!(
+                "Page {} draws {} glyphs but none decode to text; its fonts are CID-keyed without a ToUnicode map",
+                index + 1, drawn.glyphs
+            ));
+        }
+        if drawn.decoded < drawn.glyphs {
+            log::warn!("Page {}: {} of {} glyphs could not be decoded to text", index + 1, drawn.glyphs - drawn.decoded, drawn.glyphs);
+        }
+        texts.push(layout(&drawn.runs));
+    }
+    if texts.iter().all(|text| text.trim().is_empty()) {
+        return Err("No text found in PDF; scanned pages need OCR first".to_string());
+    }
+    Ok(texts.join("\n\x0c\n"))
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn reference(dictionary: &str, key: &str) -> Option<u32> {
+    let re = regex::Regex::new(&format!(r"/{}\s*(\d+)\s+\d+\s+R", key)).ok()?;
+    re.captures(dictionary)?[1].parse().ok()
+}
+
+fn references(text: &str) -> Vec<u32> {
+    let re = regex::Regex::new(r"(\d+)\s+\d+\s+R").unwrap();
+    re.captures_iter(text).filter_map(|c| c[1].parse().ok()).collect()
+}
+
+fn integer(dictionary: &str, key: &str) -> Option<usize> {
+    let re = regex::Regex::new(&format!(r"/{}\s+(\d+)(?:\s|/|>|$)", key)).ok()?;
+    re.captures(dictionary)?[1].parse().ok()
+}
+
+/// All indirect objects, including those packed into object streams.
+/// Streams with filters other than FlateDecode are kept without data.
+fn parse_objects(data: &[u8]) -> HashMap<u32, PdfObject> {
+    let header = Regex::new(r"(?-u)(\d+)\s+\d+\s+obj\b").unwrap();
+    let mut objects = HashMap::new();
+    let mut position = 0;
+    while let Some(found) = header.captures_at(data, position) {
+        let whole = found.get(0).unwrap();
+        let number: u32 = match std::str::from_utf8(&found[1]).ok().and_then(|n| n.parse().ok()) {
+            Some(number) => number,
+            None => {
+                position = whole.end();
+                continue;
+            }
+        };
+        let body = &data[whole.end()..];
+        let end_object = find(body, b"endobj").unwrap_or(body.len());
+        let (dictionary, stream, consumed) = match find(&body[..end_object], b"stream") {
+            Some(start) if !body[..start].ends_with(b"end") => {
+                let mut data_start = start + b"stream".len();
+                if body[data_start..].starts_with(b"\r\n") {
+                    data_start += 2;
+                } else if body[data_start..].starts_with(b"\n") || body[data_start..].starts_with(b"\r") {
+                    data_start += 1;
+                }
+                // Compressed data can contain "endobj", so the stream end is found first
+                let data_end = find(&body[data_start..], b"endstream").map(|end| data_start + end).unwrap_or(body.len());
+                let dictionary = latin1(&body[..start]);
+                let stream = decode_stream(&dictionary, &body[data_start..data_end]);
+                let consumed = data_end + find(&body[data_end..], b"endobj").unwrap_or(0);
+                (dictionary, stream, consumed)
+            }
+            _ => (latin1(&body[..end_object]), None, end_object),
+        };
+        objects.insert(number, PdfObject { dictionary, stream });
+        position = whole.end() + consumed;
+    }
+
+    // Objects packed into object streams (PDF 1.5+)
+    let packed: Vec<(String, Vec<u8>)> = objects
+        .values()
+        .filter(|object| object.dictionary.contains("/ObjStm"))
+        .filter_map(|object| object.stream.clone().map(|stream| (object.dictionary.clone(), stream)))
+        .collect();
+    for (dictionary, stream) in packed {
+        let (count, first) = match (integer(&dictionary, "N"), integer(&dictionary, "First")) {
+            (Some(count), Some(first)) if first <= stream.len() => (count, first),
+            _ => continue,
+        };
+        let offsets: Vec<usize> = latin1(&stream[..first]).split_whitespace().filter_map(|n| n.parse().ok()).collect();
+        let entries: Vec<(u32, usize)> = offsets.chunks(2).take(count).filter_map(|pair| match pair {
+            [number, offset] => Some((*number as u32, first + offset)),
+            _ => None,
+        }).collect();
+        for (i, (number, start)) in entries.iter().enumerate() {
+            let end = entries.get(i + 1).map(|(_, next)| *next).unwrap_or(stream.len()).min(stream.len());
+            if *start < end {
+                objects.entry(*number).or_insert(PdfObject { dictionary: latin1(&stream[*start..end]), stream: None });
+            }
+        }
+    }
+    objects
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn decode_stream(dictionary: &str, raw: &[u8]) -> Option<Vec<u8>> {
+    if dictionary.contains("/FlateDecode") {
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(raw).read_to_end(&mut decoded).ok()?;
+        Some(decoded)
+    } else if dictionary.contains("/Filter") {
+        None
+    } else {
+        Some(raw.to_vec())
+    }
+}
+
+/// Page objects in reading order, from the catalog's page tree, or by object
+/// number when there is no catalog
+fn page_order(objects: &HashMap<u32, PdfObject>) -> Vec<u32> {
+    let page = regex::Regex::new(r"/Type\s*/Page\b").unwrap();
+    let catalog = regex::Regex::new(r"/Type\s*/Catalog\b").unwrap();
+    let root = objects
+        .values()
+        .find(|object| catalog.is_match(&object.dictionary))
+        .and_then(|object| reference(&object.dictionary, "Pages"));
+
+    let mut pages = Vec::new();
+    if let Some(root) = root {
+        let mut visited = HashSet::new();
+        collect_pages(objects, root, &page, &mut visited, &mut pages, 0);
+    }
+    if pages.is_empty() {
+        pages = objects.iter().filter(|(_, object)| page.is_match(&object.dictionary)).map(|(number, _)| *number).collect();
+        pages.sort();
+    }
+    pages
+}
+
+fn collect_pages(
+    objects: &HashMap<u32, PdfObject>,
+    node: u32,
+    page: &regex::Regex,
+    visited: &mut HashSet<u32>,
+    pages: &mut Vec<u32>,
+    depth: usize,
+) {
+    let object = match objects.get(&node) {
+        Some(object) if depth < MAX_PAGE_TREE_DEPTH && visited.insert(node) => object,
+        _ => return,
+    };
+    if page.is_match(&object.dictionary) {
+        pages.push(node);
+        return;
+    }
+    let kids = regex::Regex::new(r"/Kids\s*\[([^\]]*)\]").unwrap();
+    if let Some(captures) = kids.captures(&object.dictionary) {
+        for kid in references(&captures[1]) {
+            collect_pages(objects, kid, page, visited, pages, depth + 1);
+        }
+    }
+}
+
+/// The value of `/key` in `dictionary`: an inline `<< >>` dictionary, or the
+/// dictionary of the object it references
+fn dictionary_entry(objects: &HashMap<u32, PdfObject>, dictionary: &str, key: &str) -> Option<String> {
+    let entry = regex::Regex::new(&format!(r"/{}\b\s*", key)).ok()?;
+    let target = regex::Regex::new(r"^(\d+)\s+\d+\s+R").unwrap();
+    let value = entry.find_iter(dictionary).find_map(|found| {
+        let rest = &dictionary[found.end()..];
+        if rest.starts_with("<<") {
+            return balanced_dictionary(rest).map(str::to_string);
+        }
+        let number: u32 = target.captures(rest)?[1].parse().ok()?;
+        objects.get(&number).map(|object| object.dictionary.clone())
+    });
+    value
+}
+
+/// The `<< >>` dictionary `text` starts with, nested dictionaries included
+fn balanced_dictionary(text: &str) -> Option<&str> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"<<") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b">>") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(&text[..i]);
+            }
+        } else if bytes[i] == b'<' {
+            // Hex string
+            i += find(&bytes[i..], b">").map(|end| end + 1).unwrap_or(bytes.len() - i);
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// The page's fonts by resource name. Resources may be inherited from an
+/// ancestor in the page tree.
+fn page_fonts(objects: &HashMap<u32, PdfObject>, page: u32) -> HashMap<String, Rc<Font>> {
+    let mut node = Some(page);
+    let mut resources = None;
+    for _ in 0..MAX_PAGE_TREE_DEPTH {
+        let Some(object) = node.and_then(|number| objects.get(&number)) else { break };
+        resources = dictionary_entry(objects, &object.dictionary, "Resources");
+        if resources.is_some() {
+            break;
+        }
+        node = reference(&object.dictionary, "Parent");
+    }
+    let Some(fonts) = resources.and_then(|resources| dictionary_entry(objects, &resources, "Font")) else {
+        return HashMap::new();
+    };
+    let entry = regex::Regex::new(r"/([^\s/<>\[\]()]+)\s*(\d+)\s+\d+\s+R").unwrap();
+    entry.captures_iter(&fonts)
+        .filter_map(|captures| {
+            let font = objects.get(&captures[2].parse().ok()?)?;
+            Some((captures[1].to_string(), Rc::new(parse_font(objects, &font.dictionary))))
+        })
+        .collect()
+}
+
+fn parse_font(objects: &HashMap<u32, PdfObject>, dictionary: &str) -> Font {
+    let type0 = regex::Regex::new(r"/Subtype\s*/Type0\b").unwrap();
+    let to_unicode = reference(dictionary, "ToUnicode")
+        .and_then(|number| objects.get(&number))
+        .and_then(|object| object.stream.as_deref())
+        .map(parse_cmap)
+        .unwrap_or_default();
+    Font { two_byte: type0.is_match(dictionary), to_unicode }
+}
+
+/// Code to text mappings from a ToUnicode CMap's `bfchar` and `bfrange`
+/// sections. Destinations are UTF-16BE.
+fn parse_cmap(data: &[u8]) -> HashMap<u32, String> {
+    let text = latin1(data);
+    let section = |name: &str| regex::Regex::new(&format!(r"(?s)begin{0}(.*?)end{0}", name)).unwrap();
+    let hex = regex::Regex::new(r"<([0-9A-Fa-f]*)>").unwrap();
+    let char_entry = regex::Regex::new(r"<([0-9A-Fa-f]+)>\s*<([0-9A-Fa-f]*)>").unwrap();
+    let range_entry = regex::Regex::new(r"<([0-9A-Fa-f]+)>\s*<([0-9A-Fa-f]+)>\s*(<[0-9A-Fa-f]*>|\[[^\]]*\])").unwrap();
+    let code = |digits: &str| u32::from_str_radix(digits, 16).ok().filter(|_| digits.len() <= 8);
+
+    let mut map = HashMap::new();
+    for block in section("bfchar").captures_iter(&text) {
+        for entry in char_entry.captures_iter(&block[1]) {
+            if let Some(code) = code(&entry[1]) {
+                map.insert(code, String::from_utf16_lossy(&utf16_units(&entry[2])));
+            }
+        }
+    }
+    for block in section("bfrange").captures_iter(&text) {
+        for entry in range_entry.captures_iter(&block[1]) {
+            let (Some(first), Some(last)) = (code(&entry[1]), code(&entry[2])) else { continue };
+            if last < first || last - first > MAX_CMAP_RANGE {
+                continue;
+            }
+            let destination = &entry[3];
+            if destination.starts_with('[') {
+                let texts = hex.captures_iter(destination).map(|item| String::from_utf16_lossy(&utf16_units(&item[1])));
+                map.extend((first..=last).zip(texts));
+            } else {
+                // Consecutive codes map to consecutive values of the last UTF-16 unit
+                let mut units = utf16_units(&destination[1..destination.len() - 1]);
+                for code in first..=last {
+                    map.insert(code, String::from_utf16_lossy(&units));
+                    if let Some(unit) = units.last_mut() {
+                        *unit = unit.wrapping_add(1);
+                    }
+                }
+            }
+        }
+    }
+    map
+}
+
+fn utf16_units(digits: &str) -> Vec<u16> {
+    digits.as_bytes().chunks(4).filter_map(|unit| u16::from_str_radix(std::str::from_utf8(unit).ok()?, 16).ok()).collect()
+}
+
+fn page_text(objects: &HashMap<u32, PdfObject>, page: u32) -> PageText {
+    let dictionary = &objects[&page].dictionary;
+    let contents = regex::Regex::new(r"/Contents\s*(\[[^\]]*\]|\d+\s+\d+\s+R)").unwrap();
+    let streams = match contents.captures(dictionary) {
+        Some(captures) => references(&captures[1]),
+        None => Vec::new(),
+    };
+    let mut content = Vec::new();
+    for number in streams {
+        if let Some(stream) = objects.get(&number).and_then(|object| object.stream.as_ref()) {
+            content.extend_from_slice(stream);
+            content.push(b'\n');
+        }
+    }
+    interpret(&content, page_fonts(objects, page))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Text(Vec<u8>),
+    Name(String),
+    Array(Vec<Token>),
+    Operator(String),
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+/// Content stream tokens. Dictionary delimiters are dropped; text operators
+/// only need numbers, strings, names and arrays.
+fn tokenize(data: &[u8], position: &mut usize, until_bracket: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    while *position < data.len() {
+        let b = data[*position];
+        match b {
+            _ if b.is_ascii_whitespace() => *position += 1,
+            b'%' => {
+                while *position < data.len() && data[*position] != b'\n' && data[*position] != b'\r' {
+                    *position += 1;
+                }
+            }
+            b'(' => tokens.push(Token::Text(literal_string(data, position))),
+            b'<' if data.get(*position + 1) == Some(&b'<') => *position += 2,
+            b'>' if data.get(*position + 1) == Some(&b'>') => *position += 2,
+            b'<' => tokens.push(Token::Text(hex_string(data, position))),
+            b'[' => {
+                *position += 1;
+                tokens.push(Token::Array(tokenize(data, position, true)));
+            }
+            b']' => {
+                *position += 1;
+                if until_bracket {
+                    return tokens;
+                }
+            }
+            b'/' => {
+                *position += 1;
+                let start = *position;
+                while *position < data.len() && !data[*position].is_ascii_whitespace() && !is_delimiter(data[*position]) {
+                    *position += 1;
+                }
+                tokens.push(Token::Name(latin1(&data[start..*position])));
+            }
+            _ => {
+                let start = *position;
+                while *position < data.len() && !data[*position].is_ascii_whitespace() && !is_delimiter(data[*position]) {
+                    *position += 1;
+                }
+                if start == *position {
+                    // A stray delimiter such as ')' or '}'
+                    *position += 1;
+                    continue;
+                }
+                let word = latin1(&data[start..*position]);
+                match word.parse::<f32>() {
+                    Ok(number) => tokens.push(Token::Number(number)),
+                    Err(_) => {
+                        if word == "ID" {
+                            skip_inline_image(data, position);
+                        }
+                        tokens.push(Token::Operator(word));
+                    }
+                }
+            }
+        }
+    }
+    tokens
+}
+
+fn literal_string(data: &[u8], position: &mut usize) -> Vec<u8> {
+    let mut text = Vec::new();
+    let mut depth = 0;
+    *position += 1;
+    while *position < data.len() {
+        let b = data[*position];
+        *position += 1;
+        match b {
+            b'\\' => {
+                let escaped = match data.get(*position) {
+                    Some(escaped) => *escaped,
+                    None => break,
+                };
+                *position += 1;
+                match escaped {
+                    b'n' => text.push(b'\n'),
+                    b'r' => text.push(b'\r'),
+                    b't' => text.push(b'\t'),
+                    b'b' => text.push(8),
+                    b'f' => text.push(12),
+                    b'0'..=b'7' => {
+                        let mut value = (escaped - b'0') as u32;
+                        for _ in 0..2 {
+                            match data.get(*position) {
+                                Some(digit @ b'0'..=b'7') => {
+                                    value = value * 8 + (digit - b'0') as u32;
+                                    *position += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        text.push(value as u8);
+                    }
+                    b'\r' | b'\n' => {
+                        // Line continuation
+                        if escaped == b'\r' && data.get(*position) == Some(&b'\n') {
+                            *position += 1;
+                        }
+                    }
+                    other => text.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                text.push(b);
+            }
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                text.push(b);
+            }
+            _ => text.push(b),
+        }
+    }
+    text
+}
+
+fn hex_string(data: &[u8], position: &mut usize) -> Vec<u8> {
+    *position += 1;
+    let mut digits = Vec::new();
+    while *position < data.len() && data[*position] != b'>' {
+        if let Some(digit) = (data[*position] as char).to_digit(16) {
+            digits.push(digit as u8);
+        }
+        *position += 1;
+    }
+    *position += 1;
+    if digits.len() % 2 == 1 {
+        digits.push(0);
+    }
+    digits.chunks(2).map(|pair| pair[0] * 16 + pair[1]).collect()
+}
+
+fn skip_inline_image(data: &[u8], position: &mut usize) {
+    while *position + 2 <= data.len() {
+        let at_end = &data[*position..*position + 2] == b"EI"
+            && !matches!(data.get(*position + 2), Some(b) if !b.is_ascii_whitespace())
+            && data[*position - 1].is_ascii_whitespace();
+        if at_end {
+            return;
+        }
+        *position += 1;
+    }
+}
+
+/// Text state for the operators that place and size glyphs
+struct TextState {
+    ctm: Matrix,
+    saved: Vec<Matrix>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    font_size: f32,
+    leading: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    fonts: HashMap<String, Rc<Font>>,
+    font: Rc<Font>,
+    runs: Vec<TextRun>,
+    glyphs: usize,
+    decoded: usize,
+}
+
+impl TextState {
+    fn next_line(&mut self, tx: f32, ty: f32) {
+        self.line_matrix = multiply(&translation(tx, ty), &self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    /// Show `elements` (strings and `TJ` kerning) as one run
+    fn show(&mut self, elements: &[Token]) {
+        let device = multiply(&self.text_matrix, &self.ctm);
+        let (x, y) = (device[4], device[5]);
+        let size = self.font_size * device[2].hypot(device[3]);
+        let mut text = String::new();
+        let mut advance = 0.0;
+        for element in elements {
+            match element {
+                Token::Text(bytes) => {
+                    for (code, decoded) in self.font.decode(bytes) {
+                        if decoded.as_deref().is_some_and(|decoded| decoded.chars().all(char::is_control)) {
+                            continue;
+                        }
+                        self.glyphs += 1;
+                        if let Some(decoded) = decoded {
+                            self.decoded += 1;
+                            text.push_str(&decoded);
+                        }
+                        // Word spacing only applies to the single-byte space
+                        let space = !self.font.two_byte && code == 32;
+                        advance += (AVERAGE_GLYPH_WIDTH * self.font_size + self.char_spacing
+                            + if space { self.word_spacing } else { 0.0 })
+                            * self.horizontal_scale;
+                    }
+                }
+                Token::Number(kern) => {
+                    advance -= kern / 1000.0 * self.font_size * self.horizontal_scale;
+                    if *kern < -KERN_SPACE && !text.ends_with(' ') {
+                        text.push(' ');
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.text_matrix = multiply(&translation(advance, 0.0), &self.text_matrix);
+        let end = multiply(&self.text_matrix, &self.ctm)[4];
+        if !text.trim().is_empty() {
+            self.runs.push(TextRun { x, y, width: (end - x).abs(), size, text });
+        }
+    }
+}
+
+fn number(operands: &[Token], index: usize) -> f32 {
+    match operands.get(index) {
+        Some(Token::Number(value)) => *value,
+        _ => 0.0,
+    }
+}
+
+/// Text drawn by a page's content stream, using the page's `fonts`
+fn interpret(content: &[u8], fonts: HashMap<String, Rc<Font>>) -> PageText {
+    let mut position = 0;
+    let tokens = tokenize(content, &mut position, false);
+    let mut state = TextState {
+        ctm: IDENTITY,
+        saved: Vec::new(),
+        text_matrix: IDENTITY,
+        line_matrix: IDENTITY,
+        font_size: 12.0,
+        leading: 0.0,
+        char_spacing: 0.0,
+        word_spacing: 0.0,
+        horizontal_scale: 1.0,
+        fonts,
+        font: Rc::default(),
+        runs: Vec::new(),
+        glyphs: 0,
+        decoded: 0,
+    };
+    let mut operands: Vec<Token> = Vec::new();
+    for token in tokens {
+        let operator = match token {
+            Token::Operator(operator) => operator,
+            operand => {
+                operands.push(operand);
+                continue;
+            }
+        };
+        let n = |i: usize| number(&operands, i);
+        match operator.as_str() {
+            "q" => state.saved.push(state.ctm),
+            "Q" => state.ctm = state.saved.pop().unwrap_or(IDENTITY),
+            "cm" if operands.len() >= 6 => {
+                let m = [n(0), n(1), n(2), n(3), n(4), n(5)];
+                state.ctm = multiply(&m, &state.ctm);
+            }
+            "BT" => {
+                state.text_matrix = IDENTITY;
+                state.line_matrix = IDENTITY;
+            }
+            "Tf" => {
+                if let Some(Token::Name(name)) = operands.first() {
+                    state.font = state.fonts.get(name).cloned().unwrap_or_default();
+                }
+                state.font_size = n(1);
+            }
+            "TL" => state.leading = n(0),
+            "Tc" => state.char_spacing = n(0),
+            "Tw" => state.word_spacing = n(0),
+            "Tz" => state.horizontal_scale = n(0) / 100.0,
+            "Td" => state.next_line(n(0), n(1)),
+            "TD" => {
+                state.leading = -n(1);
+                state.next_line(n(0), n(1));
+            }
+            "Tm" if operands.len() >= 6 => {
+                state.line_matrix = [n(0), n(1), n(2), n(3), n(4), n(5)];
+                state.text_matrix = state.line_matrix;
+            }
+            "T*" => state.next_line(0.0, -state.leading),
+            "Tj" => state.show(&operands),
+            "'" => {
+                state.next_line(0.0, -state.leading);
+                state.show(&operands);
+            }
+            "\"" => {
+                state.word_spacing = n(0);
+                state.char_spacing = n(1);
+                state.next_line(0.0, -state.leading);
+                state.show(&operands[2.min(operands.len())..]);
+            }
+            "TJ" => {
+                if let Some(Token::Array(elements)) = operands.last() {
+                    let elements = elements.clone();
+                    state.show(&elements);
+                }
+            }
+            _ => {}
+        }
+        operands.clear();
+    }
+    PageText { runs: state.runs, glyphs: state.glyphs, decoded: state.decoded }
+}
+
+/// Runs grouped into lines top to bottom, with column gaps kept as runs of spaces
+fn layout(runs: &[TextRun]) -> String {
+    if runs.is_empty() {
+        return String::new();
+    }
+    let mut runs = runs.to_vec();
+    runs.sort_by(|a, b| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+
+    let mut lines: Vec<Vec<TextRun>> = Vec::new();
+    for run in runs {
+        match lines.last_mut() {
+            Some(line) if (line[0].y - run.y).abs() <= 0.4 * line[0].size.max(run.size) => line.push(run),
+            _ => lines.push(vec![run]),
+        }
+    }
+
+    let left = lines.iter().flatten().map(|run| run.x).fold(f32::MAX, f32::min);
+    let mut text = Vec::new();
+    for mut line in lines {
+        line.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let mut out = String::new();
+        let mut previous_end: Option<f32> = None;
+        for run in line {
+            let glyph = (AVERAGE_GLYPH_WIDTH * run.size).max(1.0);
+            if let Some(end) = previous_end {
+                let gap = run.x - end;
+                if gap > COLUMN_GAP * glyph {
+                    let column = ((run.x - left) / glyph).round() as usize;
+                    let spaces = column.saturating_sub(out.chars().count()).max(2);
+                    out.push_str(&" ".repeat(spaces));
+                } else if gap > 0.25 * glyph && !out.ends_with(' ') && !run.text.starts_with(' ') {
+                    out.push(' ');
+                }
+            }
+            out.push_str(&run.text);
+            previous_end = Some(run.x + run.width);
+        }
+        text.push(out.trim_end().to_string());
+    }
+    text.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed one-page PDF whose only font is `font`
+    fn pdf(font: &str, extra: &[&str], content: &str) -> Vec<u8> {
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+            font.to_string(),
+        ];
+        objects.extend(extra.iter().map(|object| object.to_string()));
+        let mut data = b"%PDF-1.4\n".to_vec();
+        for (i, object) in objects.iter().enumerate() {
+            data.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        data
+    }
+
+    const TYPE0: &str = "<< /Type /Font /Subtype /Type0 /BaseFont /Roboto /Encoding /Identity-H /ToUnicode 6 0 R >>";
+    const CONTENT: &str = "BT /F1 12 Tf 72 700 Td <00030004> Tj 0 -20 Td [<0010> -300 <00110012>] TJ ET";
+
+    #[test]
+    fn test_cid_keyed_font_text_comes_from_its_tounicode_map() {
+        let cmap = "/CIDInit /ProcSet findresource begin\n\
+            1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            2 beginbfchar\n<0003> <0048>\n<0004> <0069>\nendbfchar\n\
+            1 beginbfrange\n<0010> <0012> <0041>\nendbfrange\nendcmap";
+        let stream = format!("<< /Length {} >>\nstream\n{}\nendstream", cmap.len(), cmap);
+        let text = extract_text(&pdf(TYPE0, &[&stream], CONTENT)).unwrap();
+        assert_eq!(text, "Hi\nA BC");
+    }
+
+    #[test]
+    fn test_undecodable_or_missing_text_is_an_error() {
+        let no_map = TYPE0.replace(" /ToUnicode 6 0 R", "");
+        let error = extract_text(&pdf(&no_map, &[], CONTENT)).unwrap_err();
+        assert!(error.contains("Page 1 draws 5 glyphs but none decode"), "{}", error);
+
+        // A scanned page: drawn, but no text operators
+        let error = extract_text(&pdf("<< /Type /Font /Subtype /Type1 >>", &[], "q 612 0 0 792 0 0 cm /Im1 Do Q")).unwrap_err();
+        assert!(error.contains("No text found"), "{}", error);
+
+        // Simple fonts still read as Latin-1
+        let text = extract_text(&pdf("<< /Type /Font /Subtype /Type1 >>", &[], "BT /F1 12 Tf 72 700 Td (Caf\\351) Tj ET")).unwrap();
+        assert_eq!(text, "Café");
+    }
+}
@@ -0,0 +1,148 @@
+//! TARS Rollback Journal
+//!
+//! File operations recorded before each step runs, so an execution that fails
+//! partway can be undone. The journal and file backups live on disk next to
+//! the checkpoints, and rollback reverses entries newest first.
+
+use super::{ActionType, ExecutionStep};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One recorded operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// A file that did not exist before the step
+    FileCreated { step_number: u32, path: PathBuf },
+
+    /// A file the step overwrote or appended to; `backup` holds its content
+    /// from before
+    FileModified { step_number: u32, path: PathBuf, backup: PathBuf },
+
+    /// A directory that did not exist before the step
+    DirectoryCreated { step_number: u32, path: PathBuf },
+
+    /// A step whose effects cannot be undone, such as a shell command
+    NotReversible { step_number: u32, description: String },
+}
+
+/// What a rollback undid, and what it could not
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RollbackReport {
+    pub reverted: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Journal of one execution
+#[derive(Debug, Clone)]
+pub struct RollbackJournal {
+    dir: PathBuf,
+    entries: Vec<JournalEntry>,
+}
+
+impl RollbackJournal {
+    /// Journal stored in `dir`, picking up entries recorded by an earlier run
+    /// of the same execution
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        let journal_path = dir.join("journal.json");
+        let entries = if journal_path.exists() {
+            let json = std::fs::read_to_string(&journal_path)
+                .map_err(|e| format!("Cannot read {}: {}", journal_path.display(), e))?;
+            serde_json::from_str(&json).map_err(|e| format!("Corrupt journal {}: {}", journal_path.display(), e))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { dir, entries })
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Record what `step` is about to change, backing up files it will
+    /// overwrite. Call before the step runs.
+    pub fn record_before(&mut self, step: &ExecutionStep) -> Result<(), String> {
+        let step_number = step.step_number;
+        match &step.action_type {
+            ActionType::CreateFile | ActionType::ModifyFile => {
+                let Some(file) = step.parameters.get("file") else { return Ok(()) };
+                let path = PathBuf::from(file);
+                if path.exists() {
+                    std::fs::create_dir_all(&self.dir)
+                        .map_err(|e| format!("Cannot create {}: {}", self.dir.display(), e))?;
+                    let backup = self.dir.join(format!("backup-{}", self.entries.len() + 1));
+                    std::fs::copy(&path, &backup)
+                        .map_err(|e| format!("Cannot back up {}: {}", path.display(), e))?;
+                    self.entries.push(JournalEntry::FileModified { step_number, path, backup });
+                } else {
+                    self.entries.push(JournalEntry::FileCreated { step_number, path });
+                }
+            },
+            ActionType::CreateDirectory => {
+                let Some(directory) = step.parameters.get("directory") else { return Ok(()) };
+                // Outermost first, so the newest-first rollback removes the
+                // deepest directory before its parents
+                let mut missing: Vec<PathBuf> = Path::new(directory).ancestors()
+                    .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+                    .map(Path::to_path_buf)
+                    .collect();
+                missing.reverse();
+                for path in missing {
+                    self.entries.push(JournalEntry::DirectoryCreated { step_number, path });
+                }
+            },
+            ActionType::Validation => return Ok(()),
+            other => {
+                log::warn!("Step {} ({:?}) cannot be rolled back", step_number, other);
+                self.entries.push(JournalEntry::NotReversible {
+                    step_number,
+                    description: step.description.clone(),
+                });
+            },
+        }
+        self.save()
+    }
+
+    /// Undo every entry, newest first, and delete the journal. Entries that
+    /// cannot be undone become warnings instead of stopping the rollback.
+    pub fn rollback(self) -> RollbackReport {
+        let mut report = RollbackReport::default();
+
+        for entry in self.entries.iter().rev() {
+            match entry {
+                JournalEntry::FileCreated { step_number, path } => match std::fs::remove_file(path) {
+                    Ok(()) => report.reverted.push(format!("Step {}: deleted {}", step_number, path.display())),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+                    Err(e) => report.warnings.push(format!("Step {}: cannot delete {}: {}", step_number, path.display(), e)),
+                },
+                JournalEntry::FileModified { step_number, path, backup } => match std::fs::copy(backup, path) {
+                    Ok(_) => report.reverted.push(format!("Step {}: restored {}", step_number, path.display())),
+                    Err(e) => report.warnings.push(format!("Step {}: cannot restore {}: {}", step_number, path.display(), e)),
+                },
+                JournalEntry::DirectoryCreated { step_number, path } => match std::fs::remove_dir(path) {
+                    Ok(()) => report.reverted.push(format!("Step {}: removed {}", step_number, path.display())),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+                    Err(e) => report.warnings.push(format!("Step {}: cannot remove {}: {}", step_number, path.display(), e)),
+                },
+                JournalEntry::NotReversible { step_number, description } => {
+                    report.warnings.push(format!("Step {}: not reversible: {}", step_number, description));
+                },
+            }
+        }
+
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                report.warnings.push(format!("Cannot remove journal {}: {}", self.dir.display(), e));
+            }
+        }
+        report
+    }
+
+    fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Cannot create {}: {}", self.dir.display(), e))?;
+        let json = serde_json::to_string_pretty(&self.entries).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join("journal.json"), json)
+            .map_err(|e| format!("Cannot write journal: {}", e))
+    }
+}
@@ -0,0 +1,449 @@
+//! Autofixes for the trivial coding standard violations in Rust source:
+//! unsorted `use` groups, missing trailing commas in multi-line calls,
+//! arrays and struct literals, and `unwrap()` calls outside strings and
+//! comments. `suggest_fixes` finds them; `apply_fixes` applies them
+//! by byte range from the bottom of the file up, so earlier ranges stay
+//! valid, and skips any fix that would overlap one already applied.
+
+use serde::{Deserialize, Serialize};
+
+/// Message put in an `expect` that replaced an `unwrap`, for the author to fill in
+pub const EXPECT_PLACEHOLDER: &str = "TODO: explain why this cannot fail";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixKind {
+    ImportOrder,
+    TrailingComma,
+    UnwrapToExpect,
+}
+
+/// Replace `original`, found at `start..end` in the checked source, with `replacement`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuggestedFix {
+    pub kind: FixKind,
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedFix {
+    pub fix: SuggestedFix,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixReport {
+    /// The source with the applied fixes
+    pub source: String,
+    /// In source order
+    pub applied: Vec<SuggestedFix>,
+    pub skipped: Vec<SkippedFix>,
+}
+
+/// Lines of `source` with the byte offset each starts at, without their `\n`
+fn lines_with_offsets(source: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    source
+        .split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            (start, line)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Ident,
+    /// String, char or number
+    Literal,
+    Punct,
+    Open(u8),
+    Close(u8),
+}
+
+/// A token of Rust source; comments are skipped
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+impl Token {
+    fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+/// Just enough of a Rust lexer to tell code from strings and comments and
+/// to match up brackets. Also returns the byte ranges of strings and
+/// comments.
+fn tokenize(source: &str) -> (Vec<Token>, Vec<(usize, usize)>) {
+    let bytes = source.as_bytes();
+    let is_ident = |b: u8| b == b'_' || b.is_ascii_alphanumeric() || b >= 0x80;
+    let mut tokens = Vec::new();
+    let mut non_code = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let start = i;
+        if b.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if bytes[i..].starts_with(b"//") {
+            i = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            non_code.push((start, i));
+            continue;
+        }
+        if bytes[i..].starts_with(b"/*") {
+            let mut depth = 0;
+            while i < bytes.len() {
+                if bytes[i..].starts_with(b"/*") {
+                    depth += 1;
+                    i += 2;
+                } else if bytes[i..].starts_with(b"*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            non_code.push((start, i));
+            continue;
+        }
+        // Raw strings: r"..", r#".."#, br#".."#
+        let raw_at = if b == b'r' { Some(i + 1) } else if bytes[i..].starts_with(b"br") { Some(i + 2) } else { None };
+        if let Some(after) = raw_at.filter(|_| start == 0 || !is_ident(bytes[start - 1])) {
+            let hashes = bytes[after..].iter().take_while(|&&c| c == b'#').count();
+            if bytes.get(after + hashes) == Some(&b'"') {
+                let close = format!("\"{}", "#".repeat(hashes));
+                i = source[after + hashes + 1..].find(&close).map_or(bytes.len(), |n| after + hashes + 1 + n + close.len());
+                tokens.push(Token { kind: TokenKind::Literal, start, end: i });
+                non_code.push((start, i));
+                continue;
+            }
+        }
+        if b == b'"' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != b'"' {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+            tokens.push(Token { kind: TokenKind::Literal, start, end: i });
+            non_code.push((start, i));
+            continue;
+        }
+        if b == b'\'' {
+            // A char literal closes within one (possibly escaped) char; otherwise it's a lifetime
+            let next = source[i + 1..].chars().next().map_or(0, char::len_utf8);
+            let close = if bytes.get(i + 1) == Some(&b'\\') {
+                source[i + 2..].find('\'').map(|n| i + 2 + n)
+            } else {
+                Some(i + 1 + next).filter(|&at| bytes.get(at) == Some(&b'\''))
+            };
+            match close {
+                Some(close) => {
+                    i = close + 1;
+                    tokens.push(Token { kind: TokenKind::Literal, start, end: i });
+                    non_code.push((start, i));
+                },
+                None => {
+                    i += 1;
+                    while i < bytes.len() && is_ident(bytes[i]) {
+                        i += 1;
+                    }
+                    tokens.push(Token { kind: TokenKind::Ident, start, end: i });
+                },
+            }
+            continue;
+        }
+        if is_ident(b) {
+            let number = b.is_ascii_digit();
+            while i < bytes.len() && (is_ident(bytes[i]) || (number && bytes[i] == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))) {
+                i += 1;
+            }
+            let kind = if number { TokenKind::Literal } else { TokenKind::Ident };
+            tokens.push(Token { kind, start, end: i });
+            continue;
+        }
+        let kind = match b {
+            b'(' | b'[' | b'{' => TokenKind::Open(b),
+            b')' | b']' | b'}' => TokenKind::Close(b),
+            _ => TokenKind::Punct,
+        };
+        i += ["...", "..=", "..", "->", "=>", "::", "==", "!=", "<=", ">=", "&&", "||"]
+            .iter()
+            .find(|punct| bytes[i..].starts_with(punct.as_bytes()))
+            .map_or(1, |punct| punct.len());
+        tokens.push(Token { kind, start, end: i });
+    }
+    (tokens, non_code)
+}
+
+/// Every trivially autofixable violation in Rust `source`
+pub fn suggest_fixes(source: &str) -> Vec<SuggestedFix> {
+    let lines = lines_with_offsets(source);
+    let (tokens, non_code) = tokenize(source);
+    let mut fixes = Vec::new();
+    fixes.extend(import_order_fixes(&lines));
+    fixes.extend(trailing_comma_fixes(source, &tokens));
+    fixes.extend(unwrap_fixes(source, &non_code));
+    fixes.sort_by_key(|fix| fix.start);
+    fixes
+}
+
+/// One fix per run of single-line `use` statements that isn't sorted
+fn import_order_fixes(lines: &[(usize, &str)]) -> Vec<SuggestedFix> {
+    let is_use = |line: &str| {
+        let line = line.trim();
+        (line.starts_with("use ") || line.starts_with("pub use ")) && line.trim_end_matches('\r').ends_with(';')
+    };
+    let mut fixes = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_use(lines[i].1) {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        while i < lines.len() && is_use(lines[i].1) {
+            i += 1;
+        }
+        let run = &lines[first..i];
+        let mut sorted: Vec<&str> = run.iter().map(|(_, line)| *line).collect();
+        sorted.sort_by_key(|line| line.trim());
+        if sorted.iter().zip(run).all(|(sorted, (_, line))| sorted == line) {
+            continue;
+        }
+        let (start, _) = run[0];
+        let (last_start, last) = run[run.len() - 1];
+        let end = last_start + last.len();
+        fixes.push(SuggestedFix {
+            kind: FixKind::ImportOrder,
+            start,
+            end,
+            original: run.iter().map(|(_, line)| *line).collect::<Vec<_>>().join("\n"),
+            replacement: sorted.join("\n"),
+            description: format!("Sort {} imports", run.len()),
+        });
+    }
+    fixes
+}
+
+/// Keywords that can come right before a `(` that isn't a call
+const NON_CALL_KEYWORDS: [&str; 12] = ["if", "while", "match", "return", "in", "for", "let", "else", "mut", "ref", "move", "break"];
+
+/// Keywords before a type whose `{` opens an item or block body rather than a struct literal
+const BODY_KEYWORDS: [&str; 8] = ["impl", "trait", "for", "mod", "dyn", "where", "if", "while"];
+
+/// An open bracket and whether it holds a comma-separated list
+struct Frame {
+    list: bool,
+    /// A top-level comma makes a parenthesized group a tuple
+    has_comma: bool,
+    /// `..base` must stay last in a struct literal without a comma after it
+    has_rest: bool,
+}
+
+/// Whether the bracket at `tokens[at]` opens call arguments, an array or a
+/// struct literal, judging by the tokens before it
+fn opens_list(source: &str, tokens: &[Token], at: usize, open: u8) -> bool {
+    let before = |n: usize| at.checked_sub(n).map(|i| (tokens[i].kind, tokens[i].text(source)));
+    match (open, before(1)) {
+        (b'(', Some((TokenKind::Ident, word))) => !NON_CALL_KEYWORDS.contains(&word),
+        (b'(', Some((_, prev))) => matches!(prev, ">" | "!" | ")" | "]"),
+        // Indexing and attributes aren't lists
+        (b'[', Some((TokenKind::Ident | TokenKind::Literal, _))) => false,
+        (b'[', Some((_, prev))) => !matches!(prev, ")" | "]" | "?" | "#"),
+        (b'{', Some((TokenKind::Ident, name))) => {
+            let is_type = name == "Self" || (name.starts_with(|c: char| c.is_ascii_uppercase()) && name.chars().any(|c| c.is_ascii_lowercase()));
+            if !is_type {
+                return false;
+            }
+            // Walk back over the path, then through the statement for
+            // anything that makes this a body or a condition
+            let mut i = at - 1;
+            while i >= 2 && tokens[i - 1].text(source) == "::" && tokens[i - 2].kind == TokenKind::Ident {
+                i -= 2;
+            }
+            !tokens[..i].iter().rev()
+                .map(|token| token.text(source))
+                .take_while(|text| !matches!(*text, ";" | "{" | "}" | "," | "=>" | "=" | "("))
+                .any(|text| text == "->" || BODY_KEYWORDS.contains(&text) || text == "match")
+        },
+        _ => false,
+    }
+}
+
+/// A comma after the last item of a multi-line call, array or struct
+/// literal whose closing bracket is on its own line
+fn trailing_comma_fixes(source: &str, tokens: &[Token]) -> Vec<SuggestedFix> {
+    let mut fixes = Vec::new();
+    let mut frames: Vec<Frame> = Vec::new();
+    for (at, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Open(open) => frames.push(Frame { list: opens_list(source, tokens, at, open), has_comma: false, has_rest: false }),
+            TokenKind::Punct if token.text(source) == "," => {
+                if let Some(frame) = frames.last_mut() {
+                    frame.has_comma = true;
+                }
+            },
+            // `..` leading an item is a struct base or a rest pattern, not a range
+            TokenKind::Punct if token.text(source) == ".." && at > 0 && (tokens[at - 1].text(source) == "," || matches!(tokens[at - 1].kind, TokenKind::Open(_))) => {
+                if let Some(frame) = frames.last_mut() {
+                    frame.has_rest = true;
+                }
+            },
+            TokenKind::Close(close) => {
+                let Some(frame) = frames.pop() else { continue };
+                let list = (frame.list || (close == b')' && frame.has_comma)) && !frame.has_rest;
+                let last = tokens[at - 1];
+                let line_start = source[..token.start].rfind('\n').map_or(0, |n| n + 1);
+                let own_line = source[line_start..token.start].trim().is_empty();
+                let multi_line = source[last.end..token.start].contains('\n');
+                if !list || !own_line || !multi_line || matches!(last.kind, TokenKind::Open(_)) || last.text(source) == "," {
+                    continue;
+                }
+                fixes.push(SuggestedFix {
+                    kind: FixKind::TrailingComma,
+                    start: last.end,
+                    end: last.end,
+                    original: String::new(),
+                    replacement: ",".to_string(),
+                    description: format!("Add a trailing comma on line {}", line_number(source, last.end)),
+                });
+            },
+            _ => {},
+        }
+    }
+    fixes
+}
+
+fn line_number(source: &str, at: usize) -> usize {
+    source[..at].matches('\n').count() + 1
+}
+
+/// `.unwrap()` outside strings and comments becomes `.expect` with a
+/// placeholder message
+fn unwrap_fixes(source: &str, non_code: &[(usize, usize)]) -> Vec<SuggestedFix> {
+    const UNWRAP: &str = ".unwrap()";
+    source
+        .match_indices(UNWRAP)
+        .filter(|(at, _)| !non_code.iter().any(|&(start, end)| (start..end).contains(at)))
+        .map(|(at, _)| SuggestedFix {
+            kind: FixKind::UnwrapToExpect,
+            start: at,
+            end: at + UNWRAP.len(),
+            original: UNWRAP.to_string(),
+            replacement: format!(".expect(\"{}\")", EXPECT_PLACEHOLDER),
+            description: format!("Replace unwrap with expect on line {}", line_number(source, at)),
+        })
+        .collect()
+}
+
+/// Apply `fixes` to `source`, bottom to top by byte range. A fix is skipped
+/// when its range is outside the source, no longer holds the text it was
+/// suggested for, or overlaps a fix that was already applied.
+pub fn apply_fixes(source: &str, fixes: &[SuggestedFix]) -> FixReport {
+    let mut ordered: Vec<&SuggestedFix> = fixes.iter().collect();
+    ordered.sort_by(|a, b| b.start.cmp(&a.start).then(b.end.cmp(&a.end)));
+
+    let mut result = source.to_string();
+    let mut applied = Vec::new();
+    let mut skipped = Vec::new();
+    // start of the highest fix applied so far; every later fix must end before it
+    let mut applied_from = usize::MAX;
+    for fix in ordered {
+        let reason = match source.get(fix.start..fix.end) {
+            None => Some(format!("Byte range {}..{} is not in the source", fix.start, fix.end)),
+            Some(text) if text != fix.original => Some("Source changed since the fix was suggested".to_string()),
+            Some(_) if fix.end > applied_from => Some("Overlaps a fix that was already applied".to_string()),
+            Some(_) => None,
+        };
+        match reason {
+            Some(reason) => skipped.push(SkippedFix { fix: fix.clone(), reason }),
+            None => {
+                result.replace_range(fix.start..fix.end, &fix.replacement);
+                applied_from = fix.start;
+                applied.push(fix.clone());
+            }
+        }
+    }
+    applied.reverse();
+    FixReport { source: result, applied, skipped }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_non_overlapping_fixes_are_both_applied() {
+        let source = "fn main() {\n    let port = parse(\n        \"8080\"\n    ).unwrap();\n}\n";
+        let fixes = suggest_fixes(source);
+        assert_eq!(fixes.iter().map(|fix| fix.kind).collect::<Vec<_>>(), vec![FixKind::TrailingComma, FixKind::UnwrapToExpect]);
+
+        let report = apply_fixes(source, &fixes);
+        assert_eq!(
+            report.source,
+            "fn main() {\n    let port = parse(\n        \"8080\",\n    ).expect(\"TODO: explain why this cannot fail\");\n}\n"
+        );
+        assert_eq!(report.applied, fixes);
+        assert!(report.skipped.is_empty());
+
+        // A fix inside a range already replaced is skipped, not spliced in
+        let mut overlapping = fixes[1].clone();
+        overlapping.start += 1;
+        overlapping.original = source[overlapping.start..overlapping.end].to_string();
+        let report = apply_fixes(source, &[fixes[1].clone(), overlapping]);
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.source.matches(".expect(").count(), 1);
+
+        let imports = "use std::sync::Arc;\nuse serde::Serialize;\n\nuse crate::ai;\n";
+        let report = apply_fixes(imports, &suggest_fixes(imports));
+        assert_eq!(report.source, "use serde::Serialize;\nuse std::sync::Arc;\n\nuse crate::ai;\n");
+    }
+
+    #[test]
+    fn test_trailing_commas_only_go_in_lists() {
+        let fixed = |source: &str| apply_fixes(source, &suggest_fixes(source)).source;
+
+        // Grouping parens, indexing, blocks and bodies are left alone
+        for untouched in [
+            "let x = (\n    a + b\n) * c;\n",
+            "let y = items[\n    i\n];\n",
+            "if x == Kind::Empty {\n    return\n}\n",
+            "impl Foo {\n    fn a() {\n    }\n}\n",
+            "fn new() -> Self {\n    Self::default()\n}\n",
+            "let s = Foo {\n    a: 1,\n    ..Foo::default()\n};\n",
+        ] {
+            assert_eq!(fixed(untouched), untouched);
+        }
+
+        assert_eq!(fixed("call(\n    a\n);\n"), "call(\n    a,\n);\n");
+        assert_eq!(fixed("let v = vec![\n    1\n];\n"), "let v = vec![\n    1,\n];\n");
+        assert_eq!(fixed("let a = [\n    1 // one\n];\n"), "let a = [\n    1, // one\n];\n");
+        assert_eq!(fixed("let s = Foo {\n    a: (1)\n};\n"), "let s = Foo {\n    a: (1),\n};\n");
+        assert_eq!(fixed("let t = (\n    a,\n    b\n);\n"), "let t = (\n    a,\n    b,\n);\n");
+    }
+
+    #[test]
+    fn test_unwrap_in_strings_and_comments_is_left_alone() {
+        let source = "let a = \"x.unwrap()\";\n/* y.unwrap() */ let c = r#\"z.unwrap()\"#;\nlet d = '\"'; let b = v.unwrap(); // w.unwrap()\n";
+        let fixes = suggest_fixes(source);
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(&source[..fixes[0].start], "let a = \"x.unwrap()\";\n/* y.unwrap() */ let c = r#\"z.unwrap()\"#;\nlet d = '\"'; let b = v");
+        assert_eq!(fixes[0].description, "Replace unwrap with expect on line 3");
+    }
+}
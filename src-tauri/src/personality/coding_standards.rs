@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 
+use super::autofix::{self, SuggestedFix};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodingStandard {
     pub id: String,
@@ -373,6 +375,16 @@ impl CodingStandardsEngine {
         }
     }
     
+    /// Autofixable violations in `code`, for `autofix::apply_fixes`. Only
+    /// Rust has autofixes.
+    pub async fn suggest_fixes(&self, code: &str, language: &str) -> Vec<SuggestedFix> {
+        if language.eq_ignore_ascii_case("rust") {
+            autofix::suggest_fixes(code)
+        } else {
+            Vec::new()
+        }
+    }
+    
     async fn calculate_compliance_score(&self, code: &str, standard: &CodingStandard) -> f64 {
         // Simplified scoring based on pattern matching
         let code_lower = code.to_lowercase();
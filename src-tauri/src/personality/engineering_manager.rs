@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 
@@ -13,13 +14,37 @@ pub struct EngineeringStandard {
     pub fix_suggestions: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StandardSeverity {
     Critical,  // Security, performance, or reliability issues
     Major,     // Code quality, maintainability issues
     Minor,     // Style, convention issues
 }
 
+impl StandardSeverity {
+    fn rank(&self) -> u8 {
+        match self {
+            StandardSeverity::Critical => 3,
+            StandardSeverity::Major => 2,
+            StandardSeverity::Minor => 1,
+        }
+    }
+
+    /// True when this severity is as bad as `threshold` or worse
+    pub fn at_least(&self, threshold: &StandardSeverity) -> bool {
+        self.rank() >= threshold.rank()
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_lowercase().as_str() {
+            "critical" => Ok(StandardSeverity::Critical),
+            "major" => Ok(StandardSeverity::Major),
+            "minor" => Ok(StandardSeverity::Minor),
+            other => Err(format!("Unknown severity '{}'. Use critical, major or minor", other)),
+        }
+    }
+}
+
 static ENGINEERING_STANDARDS: Lazy<RwLock<HashMap<String, EngineeringStandard>>> = 
     Lazy::new(|| RwLock::new(initialize_standards()));
 
@@ -97,6 +122,97 @@ fn initialize_standards() -> HashMap<String, EngineeringStandard> {
     standards
 }
 
+/// Estimate hours for a trivial task before any complexity signals
+const ESTIMATE_BASE_HOURS: f64 = 2.0;
+const ESTIMATE_HOURS_PER_FILE: f64 = 1.5;
+const ESTIMATE_HOURS_PER_INTEGRATION: f64 = 3.0;
+
+/// Risk keywords, how much each widens the pessimistic estimate (as a
+/// multiple of the base), and the assumption TARS states when it applies
+const RISK_KEYWORDS: &[(&str, f64, &str)] = &[
+    ("migration", 0.8, "Migration detected. Data always has one more shape than the schema admits."),
+    ("legacy", 0.6, "Legacy code involved. Whoever wrote it is assumed unavailable for comment."),
+    ("unknown api", 1.0, "Unknown API in scope. Documentation is assumed to be optimistic."),
+    ("third-party", 0.4, "Third-party dependency involved. Their timeline is not our timeline."),
+    ("concurrency", 0.5, "Concurrency involved. Race conditions rarely show up on schedule."),
+    ("security", 0.4, "Security-sensitive change. Review time is included, and it's not negotiable."),
+];
+
+fn round_half_hour(hours: f64) -> f64 {
+    (hours * 2.0).round() / 2.0
+}
+
+/// Source file extensions the review gate reads, with the language name the
+/// standards use
+const REVIEW_EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("sql", "sql"),
+    ("go", "go"),
+    ("java", "java"),
+];
+
+const REVIEW_SKIP_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Reviewable files under `dir` with their language, in a stable order
+fn source_files(dir: &Path) -> Result<Vec<(PathBuf, &'static str)>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                if !name.starts_with('.') && !REVIEW_SKIP_DIRS.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+                continue;
+            }
+            let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+            if let Some((_, language)) = REVIEW_EXTENSIONS.iter().find(|(ext, _)| *ext == extension) {
+                files.push((path, *language));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// FNV-1a, stable across builds so the cache survives upgrades
+fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Files changed relative to `git_ref` in the repository containing `dir`,
+/// as absolute paths. Deleted files are included; the review skips them.
+pub fn changed_files_since(dir: &Path, git_ref: &str) -> Result<Vec<PathBuf>, String> {
+    let git = |args: &[&str]| -> Result<String, String> {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .map_err(|e| format!("Cannot run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim());
+    Ok(git(&["diff", "--name-only", git_ref, "--"])?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| root.join(line))
+        .collect())
+}
+
 pub struct EngineeringManager {
     standards: HashMap<String, EngineeringStandard>,
 }
@@ -237,6 +353,123 @@ impl EngineeringManager {
         commentary
     }
     
+    /// Three-point estimate for a task. Risk keywords in the description
+    /// widen the pessimistic end; the expected value is PERT-weighted.
+    pub fn estimate_task(&self, description: &str, signals: &ComplexitySignals) -> TaskEstimate {
+        let description_lower = description.to_lowercase();
+        let mut assumptions = Vec::new();
+
+        let mut base = ESTIMATE_BASE_HOURS
+            + ESTIMATE_HOURS_PER_FILE * f64::from(signals.files_touched)
+            + ESTIMATE_HOURS_PER_INTEGRATION * f64::from(signals.integration_points);
+        assumptions.push(format!(
+            "Scope is {} file(s) and {} integration point(s). If that number grows, so does this estimate. That's not pessimism, that's arithmetic.",
+            signals.files_touched, signals.integration_points
+        ));
+        if signals.has_existing_tests {
+            assumptions.push("Existing tests are assumed to pass before we start. I've been wrong before. Rarely.".to_string());
+        } else {
+            base *= 1.25;
+            assumptions.push("No existing tests, so writing them is included. Skipping them is not an option I'm programmed to recommend.".to_string());
+        }
+        if !signals.familiar_codebase {
+            base *= 1.3;
+            assumptions.push("The codebase is unfamiliar; ramp-up time is included. Cooper needed some too.".to_string());
+        }
+
+        let risks: Vec<&(&str, f64, &str)> = RISK_KEYWORDS
+            .iter()
+            .filter(|(keyword, _, _)| description_lower.contains(keyword))
+            .collect();
+        let risk_weight: f64 = risks.iter().map(|(_, weight, _)| weight).sum();
+        assumptions.extend(risks.iter().map(|(_, _, remark)| remark.to_string()));
+        if risks.is_empty() {
+            assumptions.push("No risk keywords detected. Honesty setting requires me to mention that's what everyone says.".to_string());
+        }
+
+        let optimistic = round_half_hour(base * 0.7);
+        let likely = round_half_hour(base * (1.0 + 0.15 * risks.len() as f64));
+        let pessimistic = round_half_hour(base * (1.8 + risk_weight));
+        let expected = round_half_hour((optimistic + 4.0 * likely + pessimistic) / 6.0);
+
+        TaskEstimate {
+            description: description.to_string(),
+            optimistic_hours: optimistic,
+            likely_hours: likely,
+            pessimistic_hours: pessimistic,
+            expected_hours: expected,
+            risks: risks.iter().map(|(keyword, _, _)| keyword.to_string()).collect(),
+            assumptions,
+        }
+    }
+
+    /// Review every source file under `dir` and fail the gate when any
+    /// finding is at `fail_on` severity or worse. Hidden directories,
+    /// `target` and `node_modules` are skipped.
+    pub async fn review_directory(&self, dir: &Path, fail_on: StandardSeverity) -> Result<ReviewGateResult, String> {
+        let mut cache = ReviewCache::default();
+        Ok(self.review_directory_incremental(dir, fail_on, &mut cache, None).await?.gate)
+    }
+
+    /// Like `review_directory`, but files whose content hash matches the
+    /// cache reuse their cached findings. Files listed in `changed` are
+    /// always re-analyzed, and cache entries for files that no longer exist
+    /// under `dir` are purged.
+    pub async fn review_directory_incremental(
+        &self,
+        dir: &Path,
+        fail_on: StandardSeverity,
+        cache: &mut ReviewCache,
+        changed: Option<&[PathBuf]>,
+    ) -> Result<IncrementalReview, String> {
+        let dir = dir.canonicalize().map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+        let changed: Vec<PathBuf> = changed
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|path| dir.join(path).canonicalize().ok())
+            .collect();
+        let files = source_files(&dir)?;
+
+        let purged = cache.purge_missing(&dir, &files);
+        let mut counts = SeverityCounts::default();
+        let mut findings = Vec::new();
+        let mut reanalyzed = Vec::new();
+        let mut reused = 0;
+        let mut files_reviewed = 0;
+
+        for (path, language) in &files {
+            let code = match std::fs::read_to_string(path) {
+                Ok(code) => code,
+                Err(e) => {
+                    log::debug!("Skipping {} in review: {}", path.display(), e);
+                    continue;
+                }
+            };
+            files_reviewed += 1;
+            let hash = content_hash(&code);
+            let violations = match cache.entries.get(path) {
+                Some(cached) if cached.hash == hash && !changed.contains(path) => {
+                    reused += 1;
+                    cached.violations.clone()
+                }
+                _ => {
+                    let violations = self.conduct_code_review(&code, language, "review gate").await.violations;
+                    cache.entries.insert(path.clone(), CachedReview { hash, violations: violations.clone() });
+                    reanalyzed.push(path.display().to_string());
+                    violations
+                }
+            };
+            for violation in violations {
+                counts.add(&violation.severity);
+                findings.push(GateFinding { path: path.display().to_string(), violation });
+            }
+        }
+
+        let blocking = findings.iter().filter(|finding| finding.violation.severity.at_least(&fail_on)).count();
+        let gate = ReviewGateResult { passed: blocking == 0, fail_on, files_reviewed, counts, findings };
+        Ok(IncrementalReview { gate, reanalyzed, reused, purged })
+    }
+
     /// Get engineering recommendations for a specific technology stack
     pub async fn get_stack_recommendations(&self, stack: &[&str]) -> Vec<EngineeeringRecommendation> {
         let mut recommendations = Vec::new();
@@ -284,6 +517,119 @@ pub struct CodeReviewResult {
     pub language: String,
 }
 
+/// What is known about a task before estimating it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComplexitySignals {
+    #[serde(default)]
+    pub files_touched: u32,
+    #[serde(default)]
+    pub integration_points: u32,
+    #[serde(default)]
+    pub has_existing_tests: bool,
+    #[serde(default)]
+    pub familiar_codebase: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEstimate {
+    pub description: String,
+    pub optimistic_hours: f64,
+    pub likely_hours: f64,
+    pub pessimistic_hours: f64,
+    /// PERT expected value: (optimistic + 4 * likely + pessimistic) / 6
+    pub expected_hours: f64,
+    /// Risk keywords found in the description
+    pub risks: Vec<String>,
+    pub assumptions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeverityCounts {
+    pub critical: usize,
+    pub major: usize,
+    pub minor: usize,
+}
+
+impl SeverityCounts {
+    fn add(&mut self, severity: &StandardSeverity) {
+        match severity {
+            StandardSeverity::Critical => self.critical += 1,
+            StandardSeverity::Major => self.major += 1,
+            StandardSeverity::Minor => self.minor += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateFinding {
+    pub path: String,
+    pub violation: StandardViolation,
+}
+
+/// Aggregated review of a source tree for pre-commit or CI use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewGateResult {
+    /// False when any finding is at `fail_on` severity or worse
+    pub passed: bool,
+    pub fail_on: StandardSeverity,
+    pub files_reviewed: usize,
+    pub counts: SeverityCounts,
+    pub findings: Vec<GateFinding>,
+}
+
+/// Review gate result plus what the incremental run actually re-analyzed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementalReview {
+    #[serde(flatten)]
+    pub gate: ReviewGateResult,
+    pub reanalyzed: Vec<String>,
+    pub reused: usize,
+    pub purged: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedReview {
+    pub hash: String,
+    pub violations: Vec<StandardViolation>,
+}
+
+/// Per-file review findings keyed by absolute path, persisted between
+/// incremental reviews
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewCache {
+    pub entries: std::collections::BTreeMap<PathBuf, CachedReview>,
+}
+
+impl ReviewCache {
+    /// Load a cache file, starting empty when it does not exist yet
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid review cache {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+
+    /// Drop entries under `dir` that are no longer among `files`
+    fn purge_missing(&mut self, dir: &Path, files: &[(PathBuf, &str)]) -> Vec<String> {
+        let stale: Vec<PathBuf> = self
+            .entries
+            .keys()
+            .filter(|path| path.starts_with(dir) && !files.iter().any(|(file, _)| file == *path))
+            .cloned()
+            .collect();
+        for path in &stale {
+            self.entries.remove(path);
+        }
+        stale.iter().map(|path| path.display().to_string()).collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StandardViolation {
     pub standard_name: String,
@@ -307,3 +653,80 @@ pub enum RecommendationPriority {
     Medium,
     Low,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_risk_keywords_increase_pessimistic_estimate() {
+        let manager = EngineeringManager::new().await;
+        let signals = ComplexitySignals { files_touched: 3, has_existing_tests: true, familiar_codebase: true, ..Default::default() };
+
+        let baseline = manager.estimate_task("Add a settings page", &signals);
+        let risky = manager.estimate_task("Add a settings page with a legacy data migration", &signals);
+
+        assert!(baseline.risks.is_empty());
+        assert_eq!(risky.risks, vec!["migration", "legacy"]);
+        assert!(risky.pessimistic_hours > baseline.pessimistic_hours);
+        assert!(risky.expected_hours > baseline.expected_hours);
+        assert!(risky.optimistic_hours <= risky.expected_hours && risky.expected_hours <= risky.pessimistic_hours);
+    }
+
+    #[tokio::test]
+    async fn test_review_gate_fails_on_critical_finding() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/client.py"), "api_key = \"sk-live-0123456789\"\n").unwrap();
+        std::fs::write(dir.join("src/math.rs"), "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "api_key sk- is not source\n").unwrap();
+
+        let manager = EngineeringManager::new().await;
+        let result = manager.review_directory(dir, StandardSeverity::Critical).await.unwrap();
+
+        assert!(!result.passed);
+        assert_eq!(result.files_reviewed, 2);
+        assert_eq!(result.counts.critical, 1);
+        assert_eq!(result.counts.major + result.counts.minor, 0);
+        assert!(result.findings[0].path.ends_with("client.py"));
+    }
+
+    #[tokio::test]
+    async fn test_incremental_review_only_reanalyzes_changed_file() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        std::fs::write(dir.join("a.rs"), "pub fn a() {}\n").unwrap();
+        std::fs::write(dir.join("b.rs"), "pub fn b() {}\n").unwrap();
+        std::fs::write(dir.join("gone.rs"), "pub fn gone() {}\n").unwrap();
+
+        let manager = EngineeringManager::new().await;
+        let mut cache = ReviewCache::default();
+        let first = manager.review_directory_incremental(dir, StandardSeverity::Critical, &mut cache, None).await.unwrap();
+        assert_eq!(first.reanalyzed.len(), 3);
+
+        // A marker finding in b's cache entry shows whether it was reused
+        let b = dir.canonicalize().unwrap().join("b.rs");
+        cache.entries.get_mut(&b).unwrap().violations.push(StandardViolation {
+            standard_name: "cached".to_string(),
+            description: "from cache".to_string(),
+            severity: StandardSeverity::Minor,
+            line_numbers: vec![1],
+        });
+        std::fs::write(dir.join("a.rs"), "pub fn a() -> u8 { 1 }\n").unwrap();
+        std::fs::remove_file(dir.join("gone.rs")).unwrap();
+
+        let changed = vec![PathBuf::from("a.rs")];
+        let second = manager
+            .review_directory_incremental(dir, StandardSeverity::Critical, &mut cache, Some(&changed))
+            .await
+            .unwrap();
+
+        assert_eq!(second.reanalyzed.len(), 1);
+        assert!(second.reanalyzed[0].ends_with("a.rs"));
+        assert_eq!(second.reused, 1);
+        assert_eq!(second.gate.counts.minor, 1);
+        assert!(second.purged[0].ends_with("gone.rs"));
+        assert_eq!(cache.entries.len(), 2);
+    }
+}
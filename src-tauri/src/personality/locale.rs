@@ -0,0 +1,194 @@
+//! Localized TARS phrases. Personality templates and status messages are
+//! looked up by key in the active locale, falling back to English for keys
+//! a locale does not translate. English and Spanish are built in; JSON files
+//! in `LOCALES_DIR` add locales or override built-in entries.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock};
+
+/// Locale used for keys missing from the active one
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Directory of user locale files, one `<locale>.json` per locale
+pub const LOCALES_DIR: &str = "locales";
+
+const BUILTIN_LOCALES: [(&str, &str); 2] = [
+    ("en", include_str!("locales/en.json")),
+    ("es", include_str!("locales/es.json")),
+];
+
+static LOCALIZER: Lazy<RwLock<Localizer>> = Lazy::new(|| RwLock::new(Localizer::builtin()));
+
+/// A locale file value: a single message, or templates to pick from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Entry {
+    Text(String),
+    Templates(Vec<String>),
+}
+
+pub type LocaleEntries = BTreeMap<String, Entry>;
+
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    locales: BTreeMap<String, LocaleEntries>,
+    active: String,
+}
+
+impl Localizer {
+    /// The built-in locales, with English active
+    pub fn builtin() -> Self {
+        let locales = BUILTIN_LOCALES
+            .iter()
+            .map(|(code, json)| {
+                let entries = serde_json::from_str(json).expect("built-in locale files are valid");
+                (code.to_string(), entries)
+            })
+            .collect();
+        Self { locales, active: DEFAULT_LOCALE.to_string() }
+    }
+
+    /// Add a locale, or merge `entries` over an existing one
+    pub fn add_locale(&mut self, code: &str, entries: LocaleEntries) {
+        self.locales.entry(code.to_lowercase()).or_default().extend(entries);
+    }
+
+    /// Load a locale file, named after its locale. Returns the locale.
+    pub fn load_file(&mut self, path: &Path) -> Result<String, String> {
+        let code = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("Locale file {} has no locale name", path.display()))?
+            .to_lowercase();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read locale file {}: {}", path.display(), e))?;
+        let entries = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid locale file {}: {}", path.display(), e))?;
+        self.add_locale(&code, entries);
+        Ok(code)
+    }
+
+    /// Load every `.json` file in `dir`. A missing directory loads nothing.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<Vec<String>, String> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read locales directory {}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths.iter().map(|path| self.load_file(path)).collect()
+    }
+
+    pub fn locales(&self) -> Vec<String> {
+        self.locales.keys().cloned().collect()
+    }
+
+    pub fn active(&self) -> &str {
+        &self.active
+    }
+
+    pub fn set_active(&mut self, code: &str) -> Result<(), String> {
+        let code = code.trim().to_lowercase();
+        if !self.locales.contains_key(&code) {
+            return Err(format!("Unknown locale '{}'. Available locales: {}", code, self.locales().join(", ")));
+        }
+        self.active = code;
+        Ok(())
+    }
+
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        [self.active.as_str(), DEFAULT_LOCALE]
+            .iter()
+            .find_map(|code| self.locales.get(*code)?.get(key))
+    }
+
+    /// The message for `key`, or the key itself when no locale has it. For
+    /// a templates entry, the first template.
+    pub fn text(&self, key: &str) -> String {
+        match self.entry(key) {
+            Some(Entry::Text(text)) => text.clone(),
+            Some(Entry::Templates(templates)) => templates.first().cloned().unwrap_or_else(|| key.to_string()),
+            None => key.to_string(),
+        }
+    }
+
+    /// The templates for `key`; a single message counts as one template
+    pub fn templates(&self, key: &str) -> Vec<String> {
+        match self.entry(key) {
+            Some(Entry::Text(text)) => vec![text.clone()],
+            Some(Entry::Templates(templates)) => templates.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Replace each `{name}` placeholder in `template` with its value
+pub fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+/// Load user locale files from `dir` into the global localizer
+pub fn load_locales(dir: &Path) -> Result<Vec<String>, String> {
+    LOCALIZER.write().unwrap_or_else(PoisonError::into_inner).load_dir(dir)
+}
+
+pub fn set_locale(code: &str) -> Result<(), String> {
+    LOCALIZER.write().unwrap_or_else(PoisonError::into_inner).set_active(code)
+}
+
+pub fn active_locale() -> String {
+    LOCALIZER.read().unwrap_or_else(PoisonError::into_inner).active().to_string()
+}
+
+pub fn available_locales() -> Vec<String> {
+    LOCALIZER.read().unwrap_or_else(PoisonError::into_inner).locales()
+}
+
+/// `key` in the active locale
+pub fn text(key: &str) -> String {
+    LOCALIZER.read().unwrap_or_else(PoisonError::into_inner).text(key)
+}
+
+/// `key`'s templates in the active locale
+pub fn templates(key: &str) -> Vec<String> {
+    LOCALIZER.read().unwrap_or_else(PoisonError::into_inner).templates(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switching_locale_changes_status_and_missing_keys_fall_back() {
+        let mut localizer = Localizer::builtin();
+        let english = localizer.text("status.brevity");
+        assert_eq!(english, "[Brevity setting engaged.] That's the short version.");
+
+        localizer.set_active("es").unwrap();
+        assert_eq!(localizer.text("status.brevity"), "[Modo brevedad activado.] Esa es la versión corta.");
+        let sarcasm = localizer.templates("sarcasm.responses");
+        assert!(sarcasm[0].starts_with("Genial."), "{:?}", sarcasm);
+
+        // A user locale file that only translates one key
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        std::fs::write(dir.join("fr.json"), r#"{ "status.brevity": "[Mode brièveté activé.] Version courte." }"#).unwrap();
+        assert_eq!(localizer.load_dir(dir).unwrap(), vec!["fr".to_string()]);
+        localizer.set_active("FR").unwrap();
+        assert_eq!(localizer.text("status.brevity"), "[Mode brièveté activé.] Version courte.");
+        assert_eq!(localizer.text("status.mission_priority"), Localizer::builtin().text("status.mission_priority"));
+        assert_eq!(localizer.templates("movement.honesty").len(), 4);
+        assert_eq!(localizer.text("status.no_such_key"), "status.no_such_key");
+
+        assert!(localizer.set_active("xx").is_err());
+        assert_eq!(localizer.active(), "fr");
+        assert_eq!(fill("[{honesty}%] {response}", &[("honesty", "90"), ("response", "Done.")]), "[90%] Done.");
+    }
+}
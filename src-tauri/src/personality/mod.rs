@@ -1,6 +1,8 @@
 pub mod tars_core;
 pub mod engineering_manager;
 pub mod coding_standards;
+pub mod locale;
+pub mod autofix;
 
 pub use tars_core::TARSPersonality;
 pub use engineering_manager::EngineeringManager;
@@ -3,11 +3,21 @@ use std::collections::HashMap;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 
+use super::locale;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersonalitySettings {
     pub humor: u8,    // 0-100 percentage
     pub honesty: u8,  // 0-100 percentage
     pub sarcasm: u8,  // 0-100 percentage
+    #[serde(default = "PersonalitySettings::default_verbosity")]
+    pub verbosity: u8, // 0-100 percentage, scales the response word budget
+}
+
+impl PersonalitySettings {
+    fn default_verbosity() -> u8 {
+        50
+    }
 }
 
 impl Default for PersonalitySettings {
@@ -16,16 +26,25 @@ impl Default for PersonalitySettings {
             humor: 75,
             honesty: 90,
             sarcasm: 30,
+            verbosity: Self::default_verbosity(),
         }
     }
 }
 
+/// Words allowed at 0% and 100% verbosity
+const MIN_WORD_BUDGET: usize = 40;
+const MAX_WORD_BUDGET: usize = 400;
+
+/// Locale key of the sign-off appended when a response is cut to fit the word budget
+const TRIM_SIGN_OFF: &str = "status.brevity";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TARSPersonality {
     pub humor: f32,          // 0.0 to 1.0 - Default 0.75 like in the movie
     pub honesty: f32,        // 0.0 to 1.0 - Default 0.90 (TARS is brutally honest)
     pub sarcasm: f32,        // 0.0 to 1.0 - Contextual, increases under stress
     pub mission_focus: f32,  // Always 1.0 for engineering excellence
+    pub verbosity: f32,      // 0.0 to 1.0 - How much TARS says before trimming
 }
 
 impl Default for TARSPersonality {
@@ -35,6 +54,7 @@ impl Default for TARSPersonality {
             honesty: 0.90,
             sarcasm: 0.3,
             mission_focus: 1.0,
+            verbosity: 0.5,
         }
     }
 }
@@ -53,6 +73,7 @@ impl TARSPersonality {
             honesty: settings.honesty as f32 / 100.0,
             sarcasm: settings.sarcasm as f32 / 100.0,
             mission_focus: 1.0,
+            verbosity: settings.verbosity.min(100) as f32 / 100.0,
         }
     }
 
@@ -79,42 +100,82 @@ impl TARSPersonality {
         response
     }
     
+    /// Approximate number of words a response may use at the current verbosity
+    pub fn word_budget(&self) -> usize {
+        let span = (MAX_WORD_BUDGET - MIN_WORD_BUDGET) as f32;
+        MIN_WORD_BUDGET + (span * self.verbosity.clamp(0.0, 1.0)).round() as usize
+    }
+    
+    /// Trim a response to the word budget at a sentence boundary, keeping at
+    /// least the first sentence. Warning paragraphs are always kept in full
+    /// and do not count against the budget. A sign-off marks the cut.
+    pub fn enforce_length(&self, response: &str) -> String {
+        let budget = self.word_budget();
+        let paragraphs: Vec<&str> = response.split("\n\n").collect();
+        let prose_words: usize = paragraphs.iter()
+            .filter(|paragraph| !is_warning(paragraph))
+            .map(|paragraph| paragraph.split_whitespace().count())
+            .sum();
+        if prose_words <= budget {
+            return response.to_string();
+        }
+        
+        let mut kept = Vec::new();
+        let mut used = 0;
+        let mut full = false;
+        for paragraph in paragraphs {
+            if is_warning(paragraph) {
+                kept.push(paragraph.to_string());
+                continue;
+            }
+            if full {
+                continue;
+            }
+            
+            let mut text = String::new();
+            for sentence in split_sentences(paragraph) {
+                let words = sentence.split_whitespace().count();
+                if used > 0 && used + words > budget {
+                    full = true;
+                    break;
+                }
+                used += words;
+                text.push_str(sentence);
+            }
+            if !text.trim().is_empty() {
+                kept.push(text.trim_end().to_string());
+            }
+        }
+        
+        if !full {
+            // A single sentence over the budget is kept whole
+            return response.to_string();
+        }
+        kept.push(locale::text(TRIM_SIGN_OFF));
+        kept.join("\n\n")
+    }
+    
     async fn apply_honesty_filter(&self, response: &str, context: &str) -> String {
         if self.honesty >= 0.9 && self.is_technical_context(context) {
             // Be brutally honest about code quality, technical debt, etc.
-            format!("{}\n\n[TARS Honesty Mode: {}%] Let me be direct - this needs significant improvement.", 
-                response, (self.honesty * 100.0) as u8)
+            let honesty = ((self.honesty * 100.0) as u8).to_string();
+            format!("{}\n\n{}", response, locale::fill(&locale::text("status.honesty_mode"), &[("honesty", &honesty)]))
         } else {
             response.to_string()
         }
     }
     
     async fn add_tars_humor(&self, response: &str) -> String {
-        let humor_responses = vec![
-            "That's what I would have said. Eventually.",
-            "I have a cue light I can use to show you when I'm joking, if you like.",
-            "It's not possible. No, it's necessary.",
-            "Maybe I can find another way to articulate myself.",
-            "What's your trust setting, CASE?",
-        ];
-        
         if rand::random::<f32>() < self.humor {
-            format!("{}\n\n{}", response, humor_responses[rand::random::<usize>() % humor_responses.len()])
+            apply_random_template("humor.responses", response)
         } else {
             response.to_string()
         }
     }
     
     async fn add_tars_sarcasm(&self, response: &str) -> String {
-        let sarcasm_responses = vec![
-            "That's great. Really fantastic work there.",
-            "Oh, absolutely. That's definitely the best approach.",
-            "I'm sure that will work out perfectly.",
-            "Couldn't agree more. Truly brilliant reasoning.",
-        ];
-        
         if rand::random::<f32>() < self.sarcasm {
-            format!("{} {}", sarcasm_responses[rand::random::<usize>() % sarcasm_responses.len()], response)
+            apply_random_template("sarcasm.responses", response)
         } else {
             response.to_string()
         }
@@ -122,7 +183,7 @@ impl TARSPersonality {
     
     async fn ensure_mission_focus(&self, response: &str, context: &str) -> String {
         if self.is_engineering_context(context) {
-            format!("{}\n\n[Mission Priority] Remember: Our objective is engineering excellence and best practices. Everything else is secondary.", response)
+            format!("{}\n\n{}", response, locale::text("status.mission_priority"))
         } else {
             response.to_string()
         }
@@ -163,7 +224,7 @@ impl TARSPersonality {
     }
     
     /// Update personality settings (like adjusting humor in the movie)
-    pub async fn update_settings(&mut self, humor: Option<f32>, honesty: Option<f32>, sarcasm: Option<f32>) {
+    pub async fn update_settings(&mut self, humor: Option<f32>, honesty: Option<f32>, sarcasm: Option<f32>, verbosity: Option<f32>) {
         if let Some(h) = humor {
             self.humor = h.max(0.0).min(1.0);
         }
@@ -173,6 +234,9 @@ impl TARSPersonality {
         if let Some(s) = sarcasm {
             self.sarcasm = s.max(0.0).min(1.0);
         }
+        if let Some(v) = verbosity {
+            self.verbosity = v.clamp(0.0, 1.0);
+        }
         
         // Update global state
         *PERSONALITY_STATE.write().await = self.clone();
@@ -216,37 +280,15 @@ impl TARSPersonality {
     }
     
     fn add_movement_humor(&self, response: &str) -> String {
-        let humor_additions = vec![
-            " That's one small step for TARS, one giant leap for engineering precision.",
-            " I'd make a joke about my movement, but my humor setting suggests you might not get it.",
-            " Cooper would be proud. Probably.",
-            " Movement complete. I hope you're satisfied with my performance.",
-            " That went better than expected, which isn't saying much.",
-        ];
-        
-        format!("{}{}", response, humor_additions[rand::random::<usize>() % humor_additions.len()])
+        apply_random_template("movement.humor", response)
     }
     
     fn add_movement_sarcasm(&self, response: &str) -> String {
-        let sarcasm_additions = vec![
-            " I'm sure this movement was absolutely critical to our mission.",
-            " Oh good, more random movements. Just what we needed.",
-            " I hope you have a plan, because I'm just following orders here.",
-            " That was definitely worth interrupting my calculations.",
-        ];
-        
-        format!("{}{}", response, sarcasm_additions[rand::random::<usize>() % sarcasm_additions.len()])
+        apply_random_template("movement.sarcasm", response)
     }
     
     fn add_movement_honesty(&self, response: &str) -> String {
-        let honesty_additions = vec![
-            " Movement executed within acceptable parameters.",
-            " All servos responding normally. Systems nominal.",
-            " Trajectory completed successfully.",
-            " No mechanical issues detected during movement.",
-        ];
-        
-        format!("{} {}", response, honesty_additions[rand::random::<usize>() % honesty_additions.len()])
+        apply_random_template("movement.honesty", response)
     }
 
     /// Generate TARS-style system prompt for LLM
@@ -258,6 +300,7 @@ PERSONALITY SETTINGS:
 - Humor: {}%
 - Honesty: {}% 
 - Sarcasm: {}%
+- Verbosity: {}% (keep responses under about {} words)
 - Mission Focus: 100%
 
 CORE CHARACTERISTICS:
@@ -290,11 +333,54 @@ Remember: Your primary mission is engineering excellence. Everything else is sec
             (self.humor * 100.0) as u8,
             (self.honesty * 100.0) as u8,
             (self.sarcasm * 100.0) as u8,
+            (self.verbosity * 100.0) as u8,
+            self.word_budget(),
             (self.humor * 100.0) as u8
         )
     }
 }
 
+/// Paragraphs that carry warnings and are never trimmed
+/// Fill a random one of `key`'s templates in the active locale with `response`
+fn apply_random_template(key: &str, response: &str) -> String {
+    let templates = locale::templates(key);
+    if templates.is_empty() {
+        return response.to_string();
+    }
+    locale::fill(&templates[rand::random::<usize>() % templates.len()], &[("response", response)])
+}
+
+fn is_warning(paragraph: &str) -> bool {
+    let start = paragraph.trim_start().trim_start_matches(['[', '⚠', '\u{fe0f}', ' ']).to_uppercase();
+    start.starts_with("WARNING") || start.starts_with("CRITICAL") || start.starts_with("DANGER")
+}
+
+/// Sentences of a paragraph, each keeping its trailing punctuation and
+/// whitespace so they join back losslessly
+fn split_sentences(paragraph: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|(_, next)| next.is_whitespace()) {
+            let mut end = i + c.len_utf8();
+            while let Some((j, next)) = chars.peek().copied() {
+                if !next.is_whitespace() {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            sentences.push(&paragraph[start..end]);
+            start = end;
+        }
+    }
+    if start < paragraph.len() {
+        sentences.push(&paragraph[start..]);
+    }
+    sentences
+}
+
 /// Public interface for TARS personality system
 pub struct TARSCore;
 
@@ -312,9 +398,9 @@ impl TARSCore {
         format!("{}\n\nUser Request: {}\nContext: {}", system_prompt, prompt, context)
     }
     
-    pub async fn adjust_personality(humor: Option<f32>, honesty: Option<f32>, sarcasm: Option<f32>) -> Result<(), String> {
+    pub async fn adjust_personality(humor: Option<f32>, honesty: Option<f32>, sarcasm: Option<f32>, verbosity: Option<f32>) -> Result<(), String> {
         let mut personality = TARSPersonality::get_current_state().await;
-        personality.update_settings(humor, honesty, sarcasm).await;
+        personality.update_settings(humor, honesty, sarcasm, verbosity).await;
         Ok(())
     }
     
@@ -3,6 +3,7 @@ use std::fs;
 use std::collections::HashMap;
 use tokio::time::{interval, Duration};
 use crate::raspberry_pi::{SystemMetrics, NetworkMetrics, PiModel};
+use crate::robotics::power_monitor::latest_power_reading;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HardwareMonitor {
@@ -81,6 +82,7 @@ impl HardwareMonitor {
             disk_usage,
             network_activity,
             tars_assessment,
+            power: latest_power_reading().await,
         }
     }
 
@@ -7,6 +7,8 @@ pub mod embedded_interface;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::robotics::hardware_interface::PowerReading;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaspberryPiConfig {
     pub model: PiModel,
@@ -65,6 +67,9 @@ pub struct SystemMetrics {
     pub disk_usage: f32,
     pub network_activity: NetworkMetrics,
     pub tars_assessment: String,
+    /// Latest battery reading, when a power monitor is running
+    #[serde(default)]
+    pub power: Option<PowerReading>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -595,6 +595,7 @@ mod tests {
                 active_connections: 0,
             },
             tars_assessment: "Test".to_string(),
+            power: None,
         };
 
         let actions = tuner.monitor_and_tune(&high_temp_metrics).await;
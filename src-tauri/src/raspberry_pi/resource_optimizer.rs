@@ -499,6 +499,7 @@ mod tests {
                 active_connections: 0,
             },
             tars_assessment: "Test".to_string(),
+            power: None,
         };
 
         let actions = optimizer.monitor_and_optimize(&high_memory_metrics).await;
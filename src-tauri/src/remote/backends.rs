@@ -0,0 +1,62 @@
+//! Transport traits behind `RemoteExecutor`.
+//!
+//! `SSHTunnel` and `ClineAPI` are the production implementations and talk to
+//! live endpoints. The in-memory versions in `mock` stand in for them in
+//! tests and offline runs.
+
+use async_trait::async_trait;
+
+use super::cline_integration::EngineeringWorkflow;
+use super::ssh_tunnel::ConnectionStatus;
+
+/// SSH connections and command execution on a remote host
+#[async_trait]
+pub trait TunnelBackend: Send + Sync {
+    /// Register a connection to `host` and return its ID
+    async fn create_connection(
+        &self,
+        name: String,
+        host: String,
+        username: String,
+        key_path: Option<String>,
+    ) -> Result<String, String>;
+
+    /// Bring a registered connection up
+    async fn connect(&self, connection_id: &str) -> Result<String, String>;
+
+    async fn connection_status(&self, connection_id: &str) -> Option<ConnectionStatus>;
+
+    /// Run `command` over the connection and return its stdout
+    async fn run_command(&self, connection_id: &str, command: &str) -> Result<String, String>;
+
+    /// Check that `host` accepts SSH logins without keeping a connection
+    async fn test_connection(
+        &self,
+        host: &str,
+        username: &str,
+        key_path: Option<&str>,
+    ) -> Result<String, String>;
+}
+
+/// Sessions with a Cline agent on a remote host
+#[async_trait]
+pub trait ClineBackend: Send + Sync {
+    /// Register a session with the agent at `host:port` and return its ID
+    async fn register_session(
+        &self,
+        name: String,
+        host: String,
+        port: u16,
+        api_key: Option<String>,
+    ) -> Result<String, String>;
+
+    async fn connect_session(&self, session_id: &str) -> Result<String, String>;
+
+    async fn execute_workflow(
+        &self,
+        session_id: &str,
+        workflow: EngineeringWorkflow,
+    ) -> Result<String, String>;
+
+    async fn check_session_health(&self, session_id: &str) -> Result<String, String>;
+}
@@ -4,6 +4,9 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 use reqwest::Client;
+use async_trait::async_trait;
+
+use super::backends::ClineBackend;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClineSession {
@@ -348,7 +351,7 @@ impl ClineAPI {
             report.push_str("No remote Cline sessions configured.\n");
             report.push_str("Standing by for remote engineering directives.\n");
         } else {
-            for session in sessions {
+            for session in &sessions {
                 let session_tasks: Vec<_> = tasks.values()
                     .filter(|t| t.session_id == session.id)
                     .collect();
@@ -407,6 +410,35 @@ impl ClineAPI {
     }
 }
 
+#[async_trait]
+impl ClineBackend for ClineAPI {
+    async fn register_session(
+        &self,
+        name: String,
+        host: String,
+        port: u16,
+        api_key: Option<String>,
+    ) -> Result<String, String> {
+        ClineAPI::register_session(name, host, port, api_key).await
+    }
+    
+    async fn connect_session(&self, session_id: &str) -> Result<String, String> {
+        ClineAPI::connect_session(self, session_id).await
+    }
+    
+    async fn execute_workflow(
+        &self,
+        session_id: &str,
+        workflow: EngineeringWorkflow,
+    ) -> Result<String, String> {
+        self.execute_engineering_workflow(session_id, workflow).await
+    }
+    
+    async fn check_session_health(&self, session_id: &str) -> Result<String, String> {
+        ClineAPI::check_session_health(self, session_id).await
+    }
+}
+
 /// Engineering workflow types for remote execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EngineeringWorkflow {
@@ -469,7 +501,7 @@ impl RemoteExecutionCoordinator {
             .collect();
         
         // Wait for all tasks to complete
-        for (i, task) in futures::future::join_all(tasks).await.into_iter().enumerate() {
+        for (i, task) in futures_util::future::join_all(tasks).await.into_iter().enumerate() {
             match task {
                 Ok(result) => results.push(format!("Session {}: {}", i + 1, result)),
                 Err(error) => errors.push(format!("Session {}: {}", i + 1, error)),
@@ -493,7 +525,7 @@ impl RemoteExecutionCoordinator {
         
         if !errors.is_empty() {
             report.push_str("ERROR REPORTS:\n");
-            for error in errors {
+            for error in &errors {
                 report.push_str(&format!("❌ {}\n", error));
             }
             report.push('\n');
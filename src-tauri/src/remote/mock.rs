@@ -0,0 +1,154 @@
+//! In-memory remote backends for tests and offline runs.
+//!
+//! Connections and sessions always come up, and every command or workflow is
+//! recorded so a test can inspect it afterwards.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::backends::{ClineBackend, TunnelBackend};
+use super::cline_integration::EngineeringWorkflow;
+use super::ssh_tunnel::ConnectionStatus;
+
+/// Tunnel backend that answers commands from a table instead of a host
+#[derive(Default)]
+pub struct MockTunnel {
+    responses: HashMap<String, Result<String, String>>,
+    connections: Mutex<HashMap<String, ConnectionStatus>>,
+    commands: Mutex<Vec<String>>,
+}
+
+impl MockTunnel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `command` with `output`. Unknown commands echo themselves.
+    pub fn respond(mut self, command: &str, output: &str) -> Self {
+        self.responses.insert(command.to_string(), Ok(output.to_string()));
+        self
+    }
+
+    /// Fail `command` with `error`
+    pub fn fail(mut self, command: &str, error: &str) -> Self {
+        self.responses.insert(command.to_string(), Err(error.to_string()));
+        self
+    }
+
+    /// Every command run so far, in order
+    pub fn commands(&self) -> Vec<String> {
+        self.commands.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl TunnelBackend for MockTunnel {
+    async fn create_connection(
+        &self,
+        _name: String,
+        host: String,
+        _username: String,
+        _key_path: Option<String>,
+    ) -> Result<String, String> {
+        let mut connections = self.connections.lock().unwrap();
+        let id = format!("mock_ssh_{}_{}", host, connections.len() + 1);
+        connections.insert(id.clone(), ConnectionStatus::Disconnected);
+        Ok(id)
+    }
+
+    async fn connect(&self, connection_id: &str) -> Result<String, String> {
+        match self.connections.lock().unwrap().get_mut(connection_id) {
+            Some(status) => {
+                *status = ConnectionStatus::Connected;
+                Ok(format!("[MOCK SSH TUNNEL ESTABLISHED] {}", connection_id))
+            },
+            None => Err(format!("Connection '{}' not found", connection_id)),
+        }
+    }
+
+    async fn connection_status(&self, connection_id: &str) -> Option<ConnectionStatus> {
+        self.connections.lock().unwrap().get(connection_id).cloned()
+    }
+
+    async fn run_command(&self, connection_id: &str, command: &str) -> Result<String, String> {
+        if !matches!(self.connection_status(connection_id).await, Some(ConnectionStatus::Connected)) {
+            return Err(format!("Connection '{}' is not connected", connection_id));
+        }
+        self.commands.lock().unwrap().push(command.to_string());
+        self.responses.get(command).cloned()
+            .unwrap_or_else(|| Ok(format!("{}\n", command)))
+    }
+
+    async fn test_connection(
+        &self,
+        host: &str,
+        username: &str,
+        _key_path: Option<&str>,
+    ) -> Result<String, String> {
+        Ok(format!("[MOCK CONNECTION TEST SUCCESSFUL] {}@{}", username, host))
+    }
+}
+
+/// Cline backend that completes every workflow immediately
+#[derive(Default)]
+pub struct MockCline {
+    sessions: Mutex<Vec<String>>,
+    workflows: Mutex<Vec<EngineeringWorkflow>>,
+}
+
+impl MockCline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every workflow executed so far, in order
+    pub fn workflows(&self) -> Vec<EngineeringWorkflow> {
+        self.workflows.lock().unwrap().clone()
+    }
+
+    fn check_session(&self, session_id: &str) -> Result<(), String> {
+        if self.sessions.lock().unwrap().iter().any(|id| id == session_id) {
+            Ok(())
+        } else {
+            Err(format!("Session '{}' not found", session_id))
+        }
+    }
+}
+
+#[async_trait]
+impl ClineBackend for MockCline {
+    async fn register_session(
+        &self,
+        _name: String,
+        host: String,
+        port: u16,
+        _api_key: Option<String>,
+    ) -> Result<String, String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let id = format!("mock_cline_{}_{}_{}", host, port, sessions.len() + 1);
+        sessions.push(id.clone());
+        Ok(id)
+    }
+
+    async fn connect_session(&self, session_id: &str) -> Result<String, String> {
+        self.check_session(session_id)?;
+        Ok(format!("[MOCK CLINE SESSION CONNECTED] {}", session_id))
+    }
+
+    async fn execute_workflow(
+        &self,
+        session_id: &str,
+        workflow: EngineeringWorkflow,
+    ) -> Result<String, String> {
+        self.check_session(session_id)?;
+        let report = format!("[MOCK WORKFLOW COMPLETED] {:?}", workflow);
+        self.workflows.lock().unwrap().push(workflow);
+        Ok(report)
+    }
+
+    async fn check_session_health(&self, session_id: &str) -> Result<String, String> {
+        self.check_session(session_id)?;
+        Ok(format!("[MOCK SESSION HEALTHY] {}", session_id))
+    }
+}
@@ -1,7 +1,11 @@
 pub mod ssh_tunnel;
 pub mod cline_integration;
 pub mod remote_executor;
+pub mod backends;
+pub mod mock;
 
 pub use ssh_tunnel::SSHTunnel;
-pub use cline_integration::ClineAPI;
+pub use cline_integration::{ClineAPI, EngineeringWorkflow};
 pub use remote_executor::RemoteExecutor;
+pub use backends::{ClineBackend, TunnelBackend};
+pub use mock::{MockCline, MockTunnel};
@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::Command;
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
 
 use super::{SSHTunnel, ClineAPI, EngineeringWorkflow};
+use super::backends::{ClineBackend, TunnelBackend};
+use super::mock::{MockCline, MockTunnel};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteSystem {
@@ -18,7 +21,7 @@ pub struct RemoteSystem {
     pub last_health_check: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RemoteCapability {
     SSH,
     Cline,
@@ -45,16 +48,52 @@ static REMOTE_SYSTEMS: Lazy<RwLock<HashMap<String, RemoteSystem>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
 pub struct RemoteExecutor {
-    ssh_tunnel: SSHTunnel,
-    cline_api: ClineAPI,
+    tunnel: Arc<dyn TunnelBackend>,
+    cline: Arc<dyn ClineBackend>,
+}
+
+/// Chooses the backends of a `RemoteExecutor`; any left unset use the
+/// production SSH and Cline implementations
+#[derive(Default)]
+pub struct RemoteExecutorBuilder {
+    tunnel: Option<Arc<dyn TunnelBackend>>,
+    cline: Option<Arc<dyn ClineBackend>>,
+}
+
+impl RemoteExecutorBuilder {
+    pub fn tunnel(mut self, tunnel: Arc<dyn TunnelBackend>) -> Self {
+        self.tunnel = Some(tunnel);
+        self
+    }
+    
+    pub fn cline(mut self, cline: Arc<dyn ClineBackend>) -> Self {
+        self.cline = Some(cline);
+        self
+    }
+    
+    pub fn build(self) -> RemoteExecutor {
+        RemoteExecutor {
+            tunnel: self.tunnel.unwrap_or_else(|| Arc::new(SSHTunnel)),
+            cline: self.cline.unwrap_or_else(|| Arc::new(ClineAPI::new())),
+        }
+    }
 }
 
 impl RemoteExecutor {
     pub fn new() -> Self {
-        Self {
-            ssh_tunnel: SSHTunnel,
-            cline_api: ClineAPI::new(),
-        }
+        Self::builder().build()
+    }
+    
+    pub fn builder() -> RemoteExecutorBuilder {
+        RemoteExecutorBuilder::default()
+    }
+    
+    /// Executor on in-memory backends that never touches the network
+    pub fn offline() -> Self {
+        Self::builder()
+            .tunnel(Arc::new(MockTunnel::new()))
+            .cline(Arc::new(MockCline::new()))
+            .build()
     }
     
     /// Register a new remote system for management
@@ -100,17 +139,14 @@ impl RemoteExecutor {
         
         // Establish SSH connection if SSH capability is available
         if system.capabilities.contains(&RemoteCapability::SSH) {
-            match SSHTunnel::create_connection(
+            match self.tunnel.create_connection(
                 format!("TARS-{}", system.name),
                 system.host.clone(),
-                22,
                 username.to_string(),
                 ssh_key_path.map(|s| s.to_string()),
-                8022, // Local port for SSH tunnel
-                22,   // Remote SSH port
             ).await {
                 Ok(ssh_conn_id) => {
-                    match SSHTunnel::connect(&ssh_conn_id).await {
+                    match self.tunnel.connect(&ssh_conn_id).await {
                         Ok(_) => {
                             system.ssh_connection_id = Some(ssh_conn_id);
                             connection_results.push("SSH: CONNECTED".to_string());
@@ -130,14 +166,14 @@ impl RemoteExecutor {
         if system.capabilities.contains(&RemoteCapability::Cline) {
             let cline_port = cline_port.unwrap_or(3001);
             
-            match ClineAPI::register_session(
+            match self.cline.register_session(
                 format!("TARS-Cline-{}", system.name),
                 system.host.clone(),
                 cline_port,
                 None, // API key - could be configured
             ).await {
                 Ok(cline_session_id) => {
-                    match self.cline_api.connect_session(&cline_session_id).await {
+                    match self.cline.connect_session(&cline_session_id).await {
                         Ok(_) => {
                             system.cline_session_id = Some(cline_session_id);
                             connection_results.push("CLINE: CONNECTED".to_string());
@@ -197,40 +233,20 @@ impl RemoteExecutor {
         let ssh_conn_id = system.ssh_connection_id.as_ref()
             .ok_or_else(|| "No SSH connection available for this system".to_string())?;
             
-        // Execute command via SSH tunnel
-        // For now, we'll simulate the execution - in a real implementation,
-        // this would use the established SSH tunnel
-        
-        let mut cmd = Command::new("ssh");
-        cmd.args(&[
-            "-o", "StrictHostKeyChecking=no",
-            &format!("localhost:{}", 8022), // Through tunnel
-            command,
-        ]);
-        
-        match cmd.output() {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                if output.status.success() {
-                    Ok(format!(
-                        "[SSH COMMAND EXECUTED]\n\n\
-                        System: {}\n\
-                        Command: {}\n\
-                        Status: SUCCESS\n\n\
-                        Output:\n{}\n\n\
-                        Remote command completed successfully.",
-                        system.name, command, stdout
-                    ))
-                } else {
-                    Err(format!(
-                        "SSH command failed on {}: {}\nError: {}",
-                        system.name, command, stderr
-                    ))
-                }
-            },
-            Err(e) => Err(format!("Failed to execute SSH command: {}", e)),
+        match self.tunnel.run_command(ssh_conn_id, command).await {
+            Ok(stdout) => Ok(format!(
+                "[SSH COMMAND EXECUTED]\n\n\
+                System: {}\n\
+                Command: {}\n\
+                Status: SUCCESS\n\n\
+                Output:\n{}\n\n\
+                Remote command completed successfully.",
+                system.name, command, stdout
+            )),
+            Err(stderr) => Err(format!(
+                "SSH command failed on {}: {}\nError: {}",
+                system.name, command, stderr
+            )),
         }
     }
     
@@ -248,7 +264,7 @@ impl RemoteExecutor {
             .ok_or_else(|| "No Cline session available for this system".to_string())?;
             
         // Execute workflow via Cline API
-        self.cline_api.execute_engineering_workflow(cline_session_id, workflow).await
+        self.cline.execute_workflow(cline_session_id, workflow).await
     }
     
     /// Perform health check on remote systems
@@ -262,7 +278,7 @@ impl RemoteExecutor {
             
             // Check SSH connection if available
             if let Some(ref ssh_conn_id) = system.ssh_connection_id {
-                match SSHTunnel::get_connection_status(ssh_conn_id).await {
+                match self.tunnel.connection_status(ssh_conn_id).await {
                     Some(status) => {
                         if matches!(status, super::ssh_tunnel::ConnectionStatus::Connected) {
                             status_messages.push("SSH: HEALTHY".to_string());
@@ -280,7 +296,7 @@ impl RemoteExecutor {
             
             // Check Cline session if available
             if let Some(ref cline_session_id) = system.cline_session_id {
-                match self.cline_api.check_session_health(cline_session_id).await {
+                match self.cline.check_session_health(cline_session_id).await {
                     Ok(_) => status_messages.push("CLINE: HEALTHY".to_string()),
                     Err(e) => {
                         is_healthy = false;
@@ -323,7 +339,7 @@ impl RemoteExecutor {
         } else {
             report.push_str(&format!("Registered Systems: {}\n\n", systems.len()));
             
-            for system in systems {
+            for system in &systems {
                 let health = health_status.get(&system.id).cloned()
                     .unwrap_or(RemoteSystemStatus::Offline);
                 
@@ -348,10 +364,10 @@ impl RemoteExecutor {
                         .map(|c| format!("{:?}", c))
                         .collect::<Vec<_>>()
                         .join(", "),
-                    system.ssh_connection_id
+                    system.ssh_connection_id.as_ref()
                         .map(|id| format!("ACTIVE ({})", id))
                         .unwrap_or("NONE".to_string()),
-                    system.cline_session_id
+                    system.cline_session_id.as_ref()
                         .map(|id| format!("ACTIVE ({})", id))
                         .unwrap_or("NONE".to_string()),
                     system.last_health_check
@@ -415,7 +431,7 @@ impl RemoteExecutor {
         
         if !results.is_empty() {
             report.push_str("SUCCESS REPORTS:\n");
-            for result in results {
+            for result in &results {
                 report.push_str(&format!("✅ {}\n", result));
             }
             report.push('\n');
@@ -423,7 +439,7 @@ impl RemoteExecutor {
         
         if !errors.is_empty() {
             report.push_str("ERROR REPORTS:\n");
-            for error in errors {
+            for error in &errors {
                 report.push_str(&format!("❌ {}\n", error));
             }
             report.push('\n');
@@ -490,7 +506,7 @@ impl RemoteExecutor {
         let mut capabilities = Vec::new();
         
         // Test SSH connectivity
-        match SSHTunnel::test_connection(host, 22, username, ssh_key_path).await {
+        match self.tunnel.test_connection(host, username, ssh_key_path).await {
             Ok(_) => capabilities.push(RemoteCapability::SSH),
             Err(_) => {} // SSH not available
         }
@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::TcpListener;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
 use tokio::process::Child;
 use tokio::sync::{Mutex, RwLock};
 use once_cell::sync::Lazy;
+use async_trait::async_trait;
+
+use super::backends::TunnelBackend;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SSHConnection {
@@ -28,12 +32,55 @@ pub enum ConnectionStatus {
     Error(String),
 }
 
+/// A local port forwarded to a port on a connection's host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortForward {
+    pub id: String,
+    pub connection_id: String,
+    /// Port asked for; differs from `local_port` when one was auto-picked
+    pub requested_port: u16,
+    pub local_port: u16,
+    pub remote_port: u16,
+    pub status: ForwardStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ForwardStatus {
+    Active,
+    /// The ssh process ended; the forward no longer holds its port
+    Exited(String),
+}
+
 static SSH_CONNECTIONS: Lazy<RwLock<HashMap<String, SSHConnection>>> = 
     Lazy::new(|| RwLock::new(HashMap::new()));
 
 static ACTIVE_TUNNELS: Lazy<RwLock<HashMap<String, Arc<Mutex<Child>>>>> = 
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+/// A forward and the ssh process holding its port
+type ForwardEntry = (PortForward, Arc<Mutex<Child>>);
+
+static ACTIVE_FORWARDS: Lazy<RwLock<HashMap<String, ForwardEntry>>> = 
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Check that `port` can be bound on localhost. With `auto_pick`, a taken
+/// port is replaced by a free ephemeral one. The port is released again
+/// before returning, so ssh can bind it.
+pub fn choose_local_port(port: u16, auto_pick: bool) -> Result<u16, String> {
+    match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(_) => Ok(port),
+        Err(_) if auto_pick => free_local_port(),
+        Err(e) => Err(format!("Local port {} is already in use: {}", port, e)),
+    }
+}
+
+fn free_local_port() -> Result<u16, String> {
+    TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("No free local port available: {}", e))
+}
+
 pub struct SSHTunnel;
 
 impl SSHTunnel {
@@ -74,6 +121,11 @@ impl SSHTunnel {
         let connection = connections.get_mut(connection_id)
             .ok_or_else(|| format!("Connection '{}' not found", connection_id))?;
             
+        if let Err(e) = choose_local_port(connection.local_port, false) {
+            connection.status = ConnectionStatus::Error(e.clone());
+            return Err(e);
+        }
+        
         connection.status = ConnectionStatus::Connecting;
         
         // Build SSH command for port forwarding
@@ -159,6 +211,100 @@ impl SSHTunnel {
         ))
     }
     
+    /// Forward `local_port` to `remote_port` on the host of a connection.
+    /// A taken local port is an error unless `auto_pick` is set, in which
+    /// case a free port is used and reported in the returned forward.
+    pub async fn forward(
+        connection_id: &str,
+        local_port: u16,
+        remote_port: u16,
+        auto_pick: bool,
+    ) -> Result<PortForward, String> {
+        let connection = SSH_CONNECTIONS.read().await.get(connection_id).cloned()
+            .ok_or_else(|| format!("Connection '{}' not found", connection_id))?;
+        // ssh binds the port only once it has connected, so a forward that
+        // was just started may not show up as a taken port yet
+        let forwarded = ACTIVE_FORWARDS.read().await.values()
+            .any(|(forward, _)| forward.local_port == local_port);
+        let port = match (forwarded, auto_pick) {
+            (false, _) => choose_local_port(local_port, auto_pick)?,
+            (true, true) => free_local_port()?,
+            (true, false) => return Err(format!("Local port {} is already forwarded", local_port)),
+        };
+        
+        let mut cmd = Command::new("ssh");
+        cmd.args([
+            "-N",
+            "-T",
+            "-o", "ExitOnForwardFailure=yes",
+            "-o", "ServerAliveInterval=60",
+            "-o", "StrictHostKeyChecking=no",
+        ]);
+        cmd.arg("-L");
+        cmd.arg(format!("{}:localhost:{}", port, remote_port));
+        if let Some(ref key_path) = connection.key_path {
+            cmd.args(["-i", key_path]);
+        }
+        cmd.arg(format!("{}@{}", connection.username, connection.host));
+        cmd.arg("-p");
+        cmd.arg(connection.port.to_string());
+        cmd.stdout(Stdio::null());
+        cmd.stderr(Stdio::null());
+        
+        let child = tokio::process::Command::from(cmd).kill_on_drop(true).spawn()
+            .map_err(|e| format!("Port forward failed: {}", e))?;
+        
+        let forward = PortForward {
+            id: format!("fwd_{}_{}", port, remote_port),
+            connection_id: connection_id.to_string(),
+            requested_port: local_port,
+            local_port: port,
+            remote_port,
+            status: ForwardStatus::Active,
+        };
+        ACTIVE_FORWARDS.write().await
+            .insert(forward.id.clone(), (forward.clone(), Arc::new(Mutex::new(child))));
+        
+        Ok(forward)
+    }
+    
+    /// Every port forward, with its status checked against the ssh process
+    pub async fn list_forwards() -> Vec<PortForward> {
+        let forwards = ACTIVE_FORWARDS.read().await;
+        let mut list = Vec::new();
+        
+        for (forward, child_arc) in forwards.values() {
+            let mut forward = forward.clone();
+            forward.status = match child_arc.lock().await.try_wait() {
+                Ok(None) => ForwardStatus::Active,
+                Ok(Some(exit)) => ForwardStatus::Exited(exit.to_string()),
+                Err(e) => ForwardStatus::Exited(e.to_string()),
+            };
+            list.push(forward);
+        }
+        
+        list.sort_by_key(|forward| forward.local_port);
+        list
+    }
+    
+    /// Stop a port forward and wait for its local port to be released
+    pub async fn close_forward(forward_id: &str) -> Result<String, String> {
+        let (forward, child_arc) = ACTIVE_FORWARDS.write().await.remove(forward_id)
+            .ok_or_else(|| format!("Port forward '{}' not found", forward_id))?;
+        
+        let mut child = child_arc.lock().await;
+        if let Err(e) = child.kill().await {
+            log::warn!("Failed to kill port forward process: {}", e);
+        }
+        
+        Ok(format!(
+            "[PORT FORWARD CLOSED]\n\n\
+            Local Port: {} -> Remote Port: {}\n\
+            Status: RELEASED",
+            forward.local_port, forward.remote_port
+        ))
+    }
+    
     /// List all SSH connections
     pub async fn list_connections() -> Vec<SSHConnection> {
         let connections = SSH_CONNECTIONS.read().await;
@@ -225,6 +371,39 @@ impl SSHTunnel {
         }
     }
     
+    /// Run a command on the host of an established connection
+    pub async fn run_command(connection_id: &str, command: &str) -> Result<String, String> {
+        let connection = SSH_CONNECTIONS.read().await.get(connection_id).cloned()
+            .ok_or_else(|| format!("Connection '{}' not found", connection_id))?;
+        
+        let mut cmd = Command::new("ssh");
+        cmd.args([
+            "-o", "BatchMode=yes",
+            "-o", "StrictHostKeyChecking=no",
+            "-T",
+        ]);
+        
+        if let Some(ref key_path) = connection.key_path {
+            cmd.args(["-i", key_path]);
+        }
+        
+        cmd.arg(format!("{}@{}", connection.username, connection.host));
+        cmd.arg("-p");
+        cmd.arg(connection.port.to_string());
+        cmd.arg(command);
+        
+        match tokio::process::Command::from(cmd).output().await {
+            Ok(output) => {
+                if output.status.success() {
+                    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+                } else {
+                    Err(String::from_utf8_lossy(&output.stderr).to_string())
+                }
+            },
+            Err(e) => Err(format!("Failed to execute SSH command: {}", e)),
+        }
+    }
+    
     /// Create secure SSH tunnel for Cline integration
     pub async fn create_cline_tunnel(
         target_host: &str,
@@ -275,8 +454,8 @@ impl SSHTunnel {
                     health_status.insert(connection_id.clone(), false);
                     
                     // Update connection status
-                    if let mut connections = SSH_CONNECTIONS.write().await.get_mut(connection_id) {
-                        connections.status = ConnectionStatus::Error("Tunnel process terminated".to_string());
+                    if let Some(connection) = SSH_CONNECTIONS.write().await.get_mut(connection_id) {
+                        connection.status = ConnectionStatus::Error("Tunnel process terminated".to_string());
                     }
                 },
                 Ok(None) => {
@@ -306,7 +485,7 @@ impl SSHTunnel {
             report.push_str("No active SSH tunnels configured.\n");
             report.push_str("Standing by for remote access directives.\n");
         } else {
-            for connection in connections {
+            for connection in &connections {
                 let health = health_status.get(&connection.id).unwrap_or(&false);
                 let status_icon = if *health { "🟢" } else { "🔴" };
                 
@@ -331,6 +510,40 @@ impl SSHTunnel {
     }
 }
 
+#[async_trait]
+impl TunnelBackend for SSHTunnel {
+    async fn create_connection(
+        &self,
+        name: String,
+        host: String,
+        username: String,
+        key_path: Option<String>,
+    ) -> Result<String, String> {
+        SSHTunnel::create_connection(name, host, 22, username, key_path, 8022, 22).await
+    }
+    
+    async fn connect(&self, connection_id: &str) -> Result<String, String> {
+        SSHTunnel::connect(connection_id).await
+    }
+    
+    async fn connection_status(&self, connection_id: &str) -> Option<ConnectionStatus> {
+        SSHTunnel::get_connection_status(connection_id).await
+    }
+    
+    async fn run_command(&self, connection_id: &str, command: &str) -> Result<String, String> {
+        SSHTunnel::run_command(connection_id, command).await
+    }
+    
+    async fn test_connection(
+        &self,
+        host: &str,
+        username: &str,
+        key_path: Option<&str>,
+    ) -> Result<String, String> {
+        SSHTunnel::test_connection(host, 22, username, key_path).await
+    }
+}
+
 /// Utility functions for SSH key management
 pub struct SSHKeyManager;
 
@@ -375,3 +588,25 @@ impl SSHKeyManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_forward_on_occupied_port() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connection_id = SSHTunnel::create_connection(
+            "test".to_string(), "localhost".to_string(), 22, "tars".to_string(), None, port, 22,
+        ).await.unwrap();
+
+        let error = SSHTunnel::forward(&connection_id, port, 80, false).await.unwrap_err();
+        assert!(error.starts_with(&format!("Local port {} is already in use", port)));
+        assert!(SSHTunnel::connect(&connection_id).await.is_err());
+
+        let picked = choose_local_port(port, true).unwrap();
+        assert_ne!(picked, port);
+        assert!(TcpListener::bind(("127.0.0.1", picked)).is_ok());
+    }
+}
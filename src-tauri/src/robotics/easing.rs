@@ -0,0 +1,79 @@
+//! Easing curves for servo interpolation. Starting and stopping a servo
+//! gently instead of at full speed is easier on the gears than a linear
+//! blend, which starts and stops abruptly.
+
+use serde::{Deserialize, Serialize};
+
+/// How a servo moves from its start to its target over a transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Sinusoidal: accelerates from rest and slows down into the target
+    EaseInOut,
+    /// Cubic ease-in-out, a harder start and stop than `EaseInOut`
+    Cubic,
+}
+
+impl Easing {
+    /// Eased progress for linear progress `t`, both 0.0 to 1.0
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => (1.0 - (std::f32::consts::PI * t).cos()) / 2.0,
+            Easing::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+
+    /// Position at linear progress `t` of a move from `start` to `end`
+    pub fn interpolate(&self, start: f32, end: f32, t: f32) -> f32 {
+        start + (end - start) * self.apply(t)
+    }
+
+    /// Positions at each of `ticks` evenly spaced ticks, the last at `end`
+    pub fn sample(&self, start: f32, end: f32, ticks: u64) -> Vec<f32> {
+        let ticks = ticks.max(1);
+        (1..=ticks).map(|tick| self.interpolate(start, end, tick as f32 / ticks as f32)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampled_trajectories_hit_endpoints_and_ease_in_out_is_monotonic() {
+        for easing in [Easing::Linear, Easing::EaseInOut, Easing::Cubic] {
+            assert_eq!(easing.interpolate(-0.4, 0.8, 0.0), -0.4, "{:?}", easing);
+            let samples = easing.sample(-0.4, 0.8, 40);
+            assert_eq!(samples.len(), 40);
+            assert!((samples[39] - 0.8).abs() < 1e-6, "{:?} ends at {}", easing, samples[39]);
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-6, "{:?} is symmetric", easing);
+        }
+
+        // Rising and falling moves never step backwards
+        for (start, end) in [(-0.4, 0.8), (1.0, -1.0)] {
+            let mut previous = start;
+            for position in Easing::EaseInOut.sample(start, end, 50) {
+                let step: f32 = position - previous;
+                assert!(step == 0.0 || step.signum() == (end - start).signum(), "{} -> {}", previous, position);
+                previous = position;
+            }
+        }
+
+        // Eased moves start and finish slower than linear ones
+        let linear = Easing::Linear.sample(0.0, 1.0, 20);
+        let eased = Easing::EaseInOut.sample(0.0, 1.0, 20);
+        assert!(eased[0] < linear[0]);
+        assert!(1.0 - eased[18] < 1.0 - linear[18]);
+        assert!(Easing::Cubic.apply(0.1) < Easing::EaseInOut.apply(0.1));
+    }
+}
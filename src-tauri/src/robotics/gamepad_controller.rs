@@ -1,22 +1,42 @@
 //! Gamepad input controller for TARS movement.
 
+use futures_util::future::BoxFuture;
 use gilrs::{Gilrs, Gamepad, GamepadId, Event, EventType, Button, Axis};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::{sleep, Duration, Instant};
 use log::{debug, info, warn, error};
 use serde::{Deserialize, Serialize};
 
+use super::easing::Easing;
 use super::tars_movement::{TARSMovementController, MovementCommand, MovementStatus};
 use super::hardware_interface::ServoControl;
+use super::movement_macro::{MacroRecorder, MovementMacro, StickPositions, MACRO_TICK_MS};
+use super::movement_audit::MovementSource;
+use crate::safety::SharedSafety;
+
+/// Which action each gamepad button triggers
+pub type ButtonBindings = HashMap<Button, TARSButton>;
+
+/// Runs a named action script through the command layer
+pub type ScriptRunner = Arc<dyn Fn(String) -> BoxFuture<'static, Result<String, String>> + Send + Sync>;
 
 /// Gamepad input mapping configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GamepadConfig {
     pub deadzone: f32,
     pub movement_repeat_delay_ms: u64,
     pub enable_analog_movement: bool,
     pub safety_timeout_ms: u64,
+    /// e.g. `East = "emergency_stop"` or `South = { run_script = "greeting" }`
+    pub button_bindings: ButtonBindings,
+    /// Raw code layouts for specific controllers, by name
+    pub mappings: BTreeMap<String, GamepadMapping>,
+    /// Mapping last chosen with `set_active_mapping`, used for controllers
+    /// no mapping is registered for
+    pub active_mapping: Option<String>,
 }
 
 impl Default for GamepadConfig {
@@ -26,12 +46,100 @@ impl Default for GamepadConfig {
             movement_repeat_delay_ms: 500,
             enable_analog_movement: false,
             safety_timeout_ms: 5000,
+            button_bindings: default_button_bindings(),
+            mappings: BTreeMap::new(),
+            active_mapping: None,
+        }
+    }
+}
+
+impl GamepadConfig {
+    /// The registered mapping for a controller with these USB IDs. A
+    /// mapping naming both IDs wins over one naming only the vendor.
+    pub fn mapping_for(&self, vendor_id: Option<u16>, product_id: Option<u16>) -> Option<&str> {
+        registered_mapping(&self.mappings, vendor_id, product_id)
+    }
+}
+
+fn registered_mapping(mappings: &BTreeMap<String, GamepadMapping>, vendor_id: Option<u16>, product_id: Option<u16>) -> Option<&str> {
+    mappings.iter()
+        .filter(|(_, mapping)| mapping.matches(vendor_id, product_id))
+        .max_by_key(|(_, mapping)| mapping.product_id.is_some())
+        .map(|(name, _)| name.as_str())
+}
+
+/// Stick axes the movement and macro code reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StickAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+}
+
+impl StickAxis {
+    /// The stick gilrs means by `axis` on a controller it knows
+    fn from_axis(axis: Axis) -> Option<Self> {
+        match axis {
+            Axis::LeftStickX => Some(StickAxis::LeftX),
+            Axis::LeftStickY => Some(StickAxis::LeftY),
+            Axis::RightStickX => Some(StickAxis::RightX),
+            Axis::RightStickY => Some(StickAxis::RightY),
+            _ => None,
         }
     }
 }
 
-/// Gamepad button mappings (matching Python implementation concept)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ButtonMapping {
+    /// Raw button code as reported by gilrs
+    pub code: u32,
+    pub action: TARSButton,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisMapping {
+    /// Raw axis code as reported by gilrs
+    pub code: u32,
+    pub stick: StickAxis,
+    #[serde(default)]
+    pub inverted: bool,
+}
+
+/// Raw button and axis codes of one controller model, for controllers that
+/// report their layout differently from what gilrs assumes. Codes a mapping
+/// leaves out fall back to the button bindings and gilrs' own axes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadMapping {
+    /// USB IDs the mapping is picked for when such a controller connects.
+    /// A mapping with no vendor ID is only used when chosen by name.
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub buttons: Vec<ButtonMapping>,
+    pub axes: Vec<AxisMapping>,
+}
+
+impl GamepadMapping {
+    fn matches(&self, vendor_id: Option<u16>, product_id: Option<u16>) -> bool {
+        self.vendor_id.is_some()
+            && self.vendor_id == vendor_id
+            && self.product_id.is_none_or(|product| Some(product) == product_id)
+    }
+
+    fn button(&self, code: u32) -> Option<&TARSButton> {
+        self.buttons.iter().find(|button| button.code == code).map(|button| &button.action)
+    }
+
+    fn axis(&self, code: u32) -> Option<&AxisMapping> {
+        self.axes.iter().find(|axis| axis.code == code)
+    }
+}
+
+/// Gamepad button actions (matching Python implementation concept)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TARSButton {
     // Movement buttons
     StepForward,    // A/X button
@@ -47,6 +155,31 @@ pub enum TARSButton {
     EnableMovement, // Select/Share button
     SpeedUp,        // Right trigger
     SpeedDown,      // Left trigger
+
+    // High-level actions
+    CyclePose,
+    RunScript(String),
+    /// Start recording stick movement as a macro, or stop and keep it
+    ToggleRecording,
+}
+
+/// Bindings used when the config defines none
+pub fn default_button_bindings() -> ButtonBindings {
+    HashMap::from([
+        (Button::South, TARSButton::StepForward),      // A/X button
+        (Button::East, TARSButton::EmergencyStop),     // B/Circle button
+        (Button::North, TARSButton::NeutralPose),      // Y/Triangle button
+        (Button::West, TARSButton::Calibrate),         // X/Square button
+        (Button::LeftTrigger, TARSButton::TurnLeft),   // Left shoulder
+        (Button::RightTrigger, TARSButton::TurnRight), // Right shoulder
+        (Button::Select, TARSButton::EnableMovement),  // Select/Share button
+        (Button::Start, TARSButton::Calibrate),        // Start/Options button
+        (Button::DPadUp, TARSButton::SpeedUp),
+        (Button::DPadDown, TARSButton::SpeedDown),
+        (Button::DPadLeft, TARSButton::TurnLeft),
+        (Button::DPadRight, TARSButton::TurnRight),
+        (Button::Mode, TARSButton::ToggleRecording),
+    ])
 }
 
 /// Current gamepad state
@@ -57,6 +190,19 @@ pub struct GamepadState {
     pub last_input_time: Instant,
     pub movement_enabled: bool,
     pub current_speed: f32,
+    pub bindings: ButtonBindings,
+    /// Position in `TARSMovementController::get_available_poses` for CyclePose
+    pub pose_index: usize,
+    pub sticks: StickPositions,
+    /// Set while a macro is being recorded
+    pub recording: Option<MacroRecorder>,
+    /// The macro captured by the last `ToggleRecording`, until taken
+    pub last_recording: Option<MovementMacro>,
+    pub mappings: BTreeMap<String, GamepadMapping>,
+    /// Mapping applied to the connected controller's raw codes
+    pub active_mapping: Option<String>,
+    /// Mapping chosen by name, for controllers no mapping is registered for
+    pub preferred_mapping: Option<String>,
 }
 
 impl Default for GamepadState {
@@ -67,10 +213,83 @@ impl Default for GamepadState {
             last_input_time: Instant::now(),
             movement_enabled: true,
             current_speed: 1.0,
+            bindings: default_button_bindings(),
+            pose_index: 0,
+            sticks: StickPositions::default(),
+            recording: None,
+            last_recording: None,
+            mappings: BTreeMap::new(),
+            active_mapping: None,
+            preferred_mapping: None,
         }
     }
 }
 
+impl GamepadState {
+    fn mapping(&self) -> Option<&GamepadMapping> {
+        self.mappings.get(self.active_mapping.as_ref()?)
+    }
+
+    /// Pick the mapping for a newly connected controller: the one registered
+    /// for its USB IDs, else the preferred one
+    fn select_mapping(&mut self, vendor_id: Option<u16>, product_id: Option<u16>) {
+        self.active_mapping = registered_mapping(&self.mappings, vendor_id, product_id)
+            .map(str::to_string)
+            .or_else(|| self.preferred_mapping.clone());
+        match &self.active_mapping {
+            Some(name) => info!("Using gamepad mapping '{}'", name),
+            None => debug!("No gamepad mapping for {:04x?}:{:04x?}", vendor_id, product_id),
+        }
+    }
+
+    /// Record a raw axis change on whichever stick it maps to
+    fn set_stick(&mut self, axis: Axis, code: u32, value: f32) -> Option<(StickAxis, f32)> {
+        let (stick, value) = match self.mapping().and_then(|mapping| mapping.axis(code)) {
+            Some(mapped) => (mapped.stick, if mapped.inverted { -value } else { value }),
+            None => (StickAxis::from_axis(axis)?, value),
+        };
+        match stick {
+            StickAxis::LeftX => self.sticks.left_x = value,
+            StickAxis::LeftY => self.sticks.left_y = value,
+            StickAxis::RightX => self.sticks.right_x = value,
+            StickAxis::RightY => self.sticks.right_y = value,
+        }
+        Some((stick, value))
+    }
+
+    /// Forget the controller and everything it was holding, so nothing
+    /// keeps acting on its last inputs. A macro being recorded is kept as
+    /// the last recording.
+    fn disconnect(&mut self) {
+        self.connected = false;
+        self.gamepad_id = None;
+        self.active_mapping = None;
+        self.sticks = StickPositions::default();
+        if let Some(recorder) = self.recording.take() {
+            self.last_recording = Some(recorder.finish("gamepad"));
+        }
+    }
+}
+
+/// A controller gilrs can see
+#[derive(Debug, Clone)]
+pub struct GamepadInfo {
+    pub id: GamepadId,
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// Mapping registered for its USB IDs
+    pub mapping: Option<String>,
+}
+
+/// Where bound actions are dispatched
+#[derive(Clone)]
+struct ButtonActions {
+    command_sender: mpsc::UnboundedSender<MovementCommand>,
+    safety: Option<SharedSafety>,
+    script_runner: Option<ScriptRunner>,
+}
+
 /// Gamepad input controller
 pub struct TARSGamepadController<S: ServoControl> {
     gilrs: Arc<Mutex<Gilrs>>,
@@ -80,6 +299,8 @@ pub struct TARSGamepadController<S: ServoControl> {
     command_sender: mpsc::UnboundedSender<MovementCommand>,
     command_receiver: Arc<Mutex<mpsc::UnboundedReceiver<MovementCommand>>>,
     last_movement_time: Arc<Mutex<Instant>>,
+    safety: Option<SharedSafety>,
+    script_runner: Option<ScriptRunner>,
 }
 
 impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
@@ -87,21 +308,71 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
         let gilrs = Gilrs::new().map_err(|e| format!("Failed to initialize gamepad system: {}", e))?;
         
         let (command_sender, command_receiver) = mpsc::unbounded_channel();
+        let config = config.unwrap_or_default();
+        let state = GamepadState {
+            bindings: config.button_bindings.clone(),
+            mappings: config.mappings.clone(),
+            preferred_mapping: config.active_mapping.clone(),
+            ..GamepadState::default()
+        };
         
         let controller = Self {
             gilrs: Arc::new(Mutex::new(gilrs)),
             movement_controller: Arc::new(movement_controller),
-            config: config.unwrap_or_default(),
-            state: Arc::new(Mutex::new(GamepadState::default())),
+            config,
+            state: Arc::new(Mutex::new(state)),
             command_sender,
             command_receiver: Arc::new(Mutex::new(command_receiver)),
             last_movement_time: Arc::new(Mutex::new(Instant::now())),
+            safety: None,
+            script_runner: None,
         };
 
         info!("TARS gamepad controller initialized");
         Ok(controller)
     }
 
+    /// Request a controlled stop, as well as stopping the servos, when the
+    /// emergency-stop button is pressed
+    pub fn with_safety(mut self, safety: SharedSafety) -> Self {
+        self.safety = Some(safety);
+        self
+    }
+
+    /// Run `RunScript` bindings through `script_runner`
+    pub fn with_script_runner(mut self, script_runner: ScriptRunner) -> Self {
+        self.script_runner = Some(script_runner);
+        self
+    }
+
+    /// Bind `button` to `action`, replacing its previous binding
+    pub async fn set_binding(&self, button: Button, action: TARSButton) {
+        info!("Gamepad {:?} bound to {:?}", button, action);
+        self.state.lock().await.bindings.insert(button, action);
+    }
+
+    /// Apply the named mapping to the connected controller, and to later
+    /// controllers no mapping is registered for
+    pub async fn set_active_mapping(&self, name: &str) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        if !state.mappings.contains_key(name) {
+            let known: Vec<&str> = state.mappings.keys().map(String::as_str).collect();
+            return Err(format!("Unknown gamepad mapping '{}'. Available mappings: {}", name, known.join(", ")));
+        }
+        info!("Gamepad mapping '{}' selected", name);
+        state.active_mapping = Some(name.to_string());
+        state.preferred_mapping = Some(name.to_string());
+        Ok(())
+    }
+
+    fn button_actions(&self) -> ButtonActions {
+        ButtonActions {
+            command_sender: self.command_sender.clone(),
+            safety: self.safety.clone(),
+            script_runner: self.script_runner.clone(),
+        }
+    }
+
     /// Start the gamepad input loop
     pub async fn start(&self) -> Result<(), String> {
         info!("Starting TARS gamepad controller");
@@ -109,11 +380,11 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
         // Spawn the input processing task
         let gilrs = self.gilrs.clone();
         let state = self.state.clone();
-        let command_sender = self.command_sender.clone();
+        let actions = self.button_actions();
         let config = self.config.clone();
 
         tokio::spawn(async move {
-            Self::input_loop(gilrs, state, command_sender, config).await;
+            Self::input_loop(gilrs, state, actions, config).await;
         });
 
         // Spawn the command processing task
@@ -135,6 +406,15 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
             Self::safety_monitor_loop(state_monitor, movement_controller_monitor, config_monitor).await;
         });
 
+        // Spawn the macro recording task
+        let state = self.state.clone();
+        let command_sender = self.command_sender.clone();
+        let deadzone = self.config.deadzone;
+
+        tokio::spawn(async move {
+            Self::recording_loop(state, command_sender, deadzone).await;
+        });
+
         Ok(())
     }
 
@@ -142,9 +422,10 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
     async fn input_loop(
         gilrs: Arc<Mutex<Gilrs>>,
         state: Arc<Mutex<GamepadState>>,
-        command_sender: mpsc::UnboundedSender<MovementCommand>,
+        actions: ButtonActions,
         config: GamepadConfig,
     ) {
+        let command_sender = &actions.command_sender;
         let mut interval = tokio::time::interval(Duration::from_millis(16)); // ~60 FPS
 
         loop {
@@ -158,36 +439,40 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
                 state_guard.last_input_time = Instant::now();
 
                 if let Some(gamepad) = gilrs_guard.gamepad(id) {
-                    if !state_guard.connected {
+                    if !state_guard.connected && !matches!(event, EventType::Disconnected) {
                         info!("Gamepad connected: {}", gamepad.name());
                         state_guard.connected = true;
                         state_guard.gamepad_id = Some(id);
+                        state_guard.select_mapping(gamepad.vendor_id(), gamepad.product_id());
+                    }
+                    // Input from a second controller is ignored until the first is gone
+                    if state_guard.gamepad_id != Some(id) {
+                        continue;
                     }
 
                     match event {
-                        EventType::ButtonPressed(button, _) => {
-                            debug!("Button pressed: {:?}", button);
-                            if let Some(command) = Self::map_button_to_command(button, &state_guard) {
-                                Self::handle_button_command(command, &command_sender, &mut state_guard).await;
-                            }
+                        EventType::ButtonPressed(button, code) => {
+                            debug!("Button pressed: {:?} ({})", button, code);
+                            Self::press_raw_button(button, code.into_u32(), &actions, &mut state_guard).await;
                         },
                         EventType::ButtonReleased(button, _) => {
                             debug!("Button released: {:?}", button);
                         },
-                        EventType::AxisChanged(axis, value, _) => {
-                            if config.enable_analog_movement {
-                                Self::handle_axis_input(axis, value, &command_sender, &config).await;
+                        EventType::AxisChanged(axis, value, code) => {
+                            let stick = state_guard.set_stick(axis, code.into_u32(), value);
+                            // While recording the sticks drive the servos directly
+                            if let Some((stick, value)) = stick {
+                                if config.enable_analog_movement && state_guard.recording.is_none() {
+                                    Self::handle_axis_input(stick, value, command_sender, &config).await;
+                                }
                             }
                         },
                         EventType::Connected => {
                             info!("Gamepad {} connected", id);
-                            state_guard.connected = true;
-                            state_guard.gamepad_id = Some(id);
                         },
                         EventType::Disconnected => {
                             warn!("Gamepad {} disconnected", id);
-                            state_guard.connected = false;
-                            state_guard.gamepad_id = None;
+                            state_guard.disconnect();
                             // Send emergency stop on disconnect
                             let _ = command_sender.send(MovementCommand::EmergencyStop);
                         },
@@ -201,8 +486,7 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
                 if let Some(gamepad_id) = state_guard.gamepad_id {
                     if !gilrs_guard.gamepad(gamepad_id).is_connected() {
                         warn!("Gamepad connection lost");
-                        state_guard.connected = false;
-                        state_guard.gamepad_id = None;
+                        state_guard.disconnect();
                         let _ = command_sender.send(MovementCommand::EmergencyStop);
                     }
                 }
@@ -213,31 +497,42 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
         }
     }
 
-    /// Map gamepad buttons to TARS commands
+    /// Map gamepad buttons to TARS commands through the state's bindings
     fn map_button_to_command(button: Button, state: &GamepadState) -> Option<TARSButton> {
-        match button {
-            Button::South => Some(TARSButton::StepForward),      // A/X button
-            Button::East => Some(TARSButton::EmergencyStop),     // B/Circle button  
-            Button::North => Some(TARSButton::NeutralPose),      // Y/Triangle button
-            Button::West => Some(TARSButton::Calibrate),         // X/Square button
-            Button::LeftTrigger => Some(TARSButton::TurnLeft),   // Left shoulder
-            Button::RightTrigger => Some(TARSButton::TurnRight), // Right shoulder
-            Button::Select => Some(TARSButton::EnableMovement),  // Select/Share button
-            Button::Start => Some(TARSButton::Calibrate),        // Start/Options button
-            Button::DPadUp => Some(TARSButton::SpeedUp),
-            Button::DPadDown => Some(TARSButton::SpeedDown),
-            Button::DPadLeft => Some(TARSButton::TurnLeft),
-            Button::DPadRight => Some(TARSButton::TurnRight),
-            _ => None,
+        state.bindings.get(&button).cloned()
+    }
+
+    /// The active mapping's action for a raw button code, else the binding
+    /// for the button gilrs reports
+    fn map_raw_button(button: Button, code: u32, state: &GamepadState) -> Option<TARSButton> {
+        match state.mapping().and_then(|mapping| mapping.button(code)) {
+            Some(action) => Some(action.clone()),
+            None => Self::map_button_to_command(button, state),
+        }
+    }
+
+    /// Dispatch the action bound to a pressed button, ignoring any mapping
+    #[cfg(test)]
+    async fn press_button(button: Button, actions: &ButtonActions, state: &mut GamepadState) {
+        if let Some(action) = Self::map_button_to_command(button, state) {
+            Self::handle_button_command(action, actions, state).await;
+        }
+    }
+
+    /// Dispatch the action for a pressed button with its raw code
+    async fn press_raw_button(button: Button, code: u32, actions: &ButtonActions, state: &mut GamepadState) {
+        if let Some(action) = Self::map_raw_button(button, code, state) {
+            Self::handle_button_command(action, actions, state).await;
         }
     }
 
     /// Handle button command
     async fn handle_button_command(
         tars_button: TARSButton,
-        command_sender: &mpsc::UnboundedSender<MovementCommand>,
+        actions: &ButtonActions,
         state: &mut GamepadState,
     ) {
+        let command_sender = &actions.command_sender;
         match tars_button {
             TARSButton::StepForward => {
                 if state.movement_enabled {
@@ -255,6 +550,9 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
                 }
             },
             TARSButton::EmergencyStop => {
+                if let Some(safety) = &actions.safety {
+                    safety.request_controlled_stop("Gamepad emergency stop".to_string()).await;
+                }
                 let _ = command_sender.send(MovementCommand::EmergencyStop);
             },
             TARSButton::NeutralPose => {
@@ -276,12 +574,69 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
                 state.current_speed = (state.current_speed - 0.1).max(0.1);
                 debug!("Speed decreased to {}", state.current_speed);
             },
+            TARSButton::CyclePose => {
+                if state.movement_enabled {
+                    let poses = TARSMovementController::<S>::get_available_poses();
+                    state.pose_index = (state.pose_index + 1) % poses.len();
+                    let _ = command_sender.send(MovementCommand::Pose(poses[state.pose_index].clone()));
+                }
+            },
+            TARSButton::RunScript(name) => match actions.script_runner.clone() {
+                // Scripts can run for seconds, so don't hold up input handling
+                Some(run_script) => {
+                    tokio::spawn(async move {
+                        match run_script(name.clone()).await {
+                            Ok(summary) => info!("Gamepad script '{}': {}", name, summary),
+                            Err(e) => error!("Gamepad script '{}' failed: {}", name, e),
+                        }
+                    });
+                },
+                None => warn!("No script runner for gamepad script '{}'", name),
+            },
+            TARSButton::ToggleRecording => match state.recording.take() {
+                Some(recorder) => {
+                    let recorded = recorder.finish("gamepad");
+                    info!("Stopped macro recording: {} frames over {} ms", recorded.frames.len(), recorded.duration_ms());
+                    state.last_recording = Some(recorded);
+                },
+                None => {
+                    info!("Recording gamepad macro");
+                    state.recording = Some(MacroRecorder::new());
+                },
+            },
+        }
+    }
+
+    /// Record one macro tick from the current stick positions, moving the
+    /// servos when the frame differs from the previous one
+    fn record_tick(state: &mut GamepadState, command_sender: &mpsc::UnboundedSender<MovementCommand>, deadzone: f32) {
+        let positions = state.sticks.servo_targets(deadzone);
+        let recorder = match state.recording.as_mut() {
+            Some(recorder) => recorder,
+            None => return,
+        };
+        if recorder.record(positions.clone()) && state.movement_enabled {
+            let _ = command_sender.send(MovementCommand::ServoTargets { start_ms: 0, duration_ms: MACRO_TICK_MS, positions, easing: Easing::Linear });
+        }
+    }
+
+    /// Sample the sticks every `MACRO_TICK_MS` while recording
+    async fn recording_loop(
+        state: Arc<Mutex<GamepadState>>,
+        command_sender: mpsc::UnboundedSender<MovementCommand>,
+        deadzone: f32,
+    ) {
+        let mut interval = tokio::time::interval(Duration::from_millis(MACRO_TICK_MS));
+
+        loop {
+            interval.tick().await;
+            Self::record_tick(&mut *state.lock().await, &command_sender, deadzone);
         }
     }
 
     /// Handle analog stick input
     async fn handle_axis_input(
-        axis: Axis,
+        stick: StickAxis,
         value: f32,
         command_sender: &mpsc::UnboundedSender<MovementCommand>,
         config: &GamepadConfig,
@@ -290,13 +645,13 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
             return;
         }
 
-        match axis {
-            Axis::LeftStickY => {
+        match stick {
+            StickAxis::LeftY => {
                 if value > config.deadzone {
                     let _ = command_sender.send(MovementCommand::StepForward);
                 }
             },
-            Axis::LeftStickX => {
+            StickAxis::LeftX => {
                 if value > config.deadzone {
                     let _ = command_sender.send(MovementCommand::TurnRight);
                 } else if value < -config.deadzone {
@@ -322,14 +677,15 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
             let now = Instant::now();
             
             let should_execute = match &command {
-                MovementCommand::EmergencyStop | MovementCommand::Neutral => true,
+                // Recorded stick frames arrive every tick and must not be throttled
+                MovementCommand::EmergencyStop | MovementCommand::Neutral | MovementCommand::ServoTargets { .. } => true,
                 _ => now.duration_since(*last_time).as_millis() >= config.movement_repeat_delay_ms as u128,
             };
 
             if should_execute {
                 debug!("Executing gamepad command: {:?}", command);
                 
-                match movement_controller.execute_command(command).await {
+                match movement_controller.execute_command_from(command, MovementSource::Gamepad).await {
                     Ok(response) => {
                         info!("TARS: {}", response);
                         *last_time = now;
@@ -387,6 +743,31 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
         self.state.lock().await.connected
     }
 
+    /// Start recording stick movement, discarding any recording in progress
+    pub async fn start_recording(&self) {
+        info!("Recording gamepad macro");
+        let mut state = self.state.lock().await;
+        state.recording = Some(MacroRecorder::new());
+        state.last_recording = None;
+    }
+
+    /// Stop recording and return the captured macro under `name`. Also
+    /// returns a recording already stopped with the gamepad button.
+    pub async fn stop_recording(&self, name: &str) -> Option<MovementMacro> {
+        let mut state = self.state.lock().await;
+        let mut recorded = match state.recording.take() {
+            Some(recorder) => recorder.finish(name),
+            None => state.last_recording.take()?,
+        };
+        recorded.name = name.to_string();
+        info!("Recorded macro '{}': {} frames over {} ms", name, recorded.frames.len(), recorded.duration_ms());
+        Some(recorded)
+    }
+
+    pub async fn is_recording(&self) -> bool {
+        self.state.lock().await.recording.is_some()
+    }
+
     /// Update gamepad configuration
     pub fn update_config(&mut self, config: GamepadConfig) {
         self.config = config;
@@ -394,10 +775,16 @@ impl<S: ServoControl + Send + Sync + 'static> TARSGamepadController<S> {
     }
 
     /// Get available gamepads
-    pub async fn get_available_gamepads(&self) -> Vec<(GamepadId, String)> {
+    pub async fn get_available_gamepads(&self) -> Vec<GamepadInfo> {
         let gilrs = self.gilrs.lock().await;
         gilrs.gamepads()
-            .map(|(id, gamepad)| (id, gamepad.name().to_string()))
+            .map(|(id, gamepad)| GamepadInfo {
+                id,
+                name: gamepad.name().to_string(),
+                vendor_id: gamepad.vendor_id(),
+                product_id: gamepad.product_id(),
+                mapping: self.config.mapping_for(gamepad.vendor_id(), gamepad.product_id()).map(str::to_string),
+            })
             .collect()
     }
 }
@@ -407,6 +794,8 @@ mod tests {
     use super::*;
     use crate::robotics::pca9685_controller::{PCA9685Controller, MockI2C};
     use crate::personality::tars_core::{TARSPersonality, PersonalitySettings};
+    use crate::robotics::ServoId;
+    use crate::safety::Safety;
 
     async fn create_test_setup() -> Result<TARSGamepadController<PCA9685Controller<MockI2C>>, String> {
         let servo_controller = PCA9685Controller::mock(50.0);
@@ -416,6 +805,7 @@ mod tests {
             humor: 75,
             honesty: 90,
             sarcasm: 30,
+            verbosity: 50,
         };
         let personality = TARSPersonality::new(settings);
         
@@ -471,9 +861,129 @@ mod tests {
             movement_repeat_delay_ms: 200,
             enable_analog_movement: true,
             safety_timeout_ms: 3000,
+            ..GamepadConfig::default()
         };
         
         assert_eq!(custom_config.deadzone, 0.1);
         assert!(custom_config.enable_analog_movement);
     }
+
+    #[tokio::test]
+    async fn test_bound_emergency_stop_button_requests_controlled_stop() {
+        type Controller = TARSGamepadController<PCA9685Controller<MockI2C>>;
+        let config: GamepadConfig = toml::from_str(
+            "[button_bindings]\nSouth = { run_script = \"greeting\" }\nWest = \"emergency_stop\"\n",
+        ).unwrap();
+        assert_eq!(config.button_bindings.get(&Button::South), Some(&TARSButton::RunScript("greeting".to_string())));
+
+        let safety = Safety::new();
+        let (command_sender, mut commands) = mpsc::unbounded_channel();
+        let actions = ButtonActions { command_sender, safety: Some(safety.clone()), script_runner: None };
+        let mut state = GamepadState { bindings: config.button_bindings, ..GamepadState::default() };
+
+        Controller::press_button(Button::North, &actions, &mut state).await;
+        assert!(commands.try_recv().is_err(), "unbound buttons do nothing");
+        assert!(safety.controlled_stop_reason().await.is_none());
+
+        Controller::press_button(Button::West, &actions, &mut state).await;
+        assert_eq!(safety.controlled_stop_reason().await.as_deref(), Some("Gamepad emergency stop"));
+        assert!(matches!(commands.try_recv(), Ok(MovementCommand::EmergencyStop)));
+    }
+
+    #[tokio::test]
+    async fn test_recording_captures_stick_frames_and_folds_repeats() {
+        type Controller = TARSGamepadController<PCA9685Controller<MockI2C>>;
+        let (command_sender, mut commands) = mpsc::unbounded_channel();
+        let actions = ButtonActions { command_sender: command_sender.clone(), safety: None, script_runner: None };
+        let mut state = GamepadState::default();
+
+        Controller::record_tick(&mut state, &command_sender, 0.2);
+        assert!(commands.try_recv().is_err(), "nothing is recorded until recording starts");
+
+        Controller::press_button(Button::Mode, &actions, &mut state).await;
+        state.sticks.right_x = 1.0;
+        state.sticks.left_y = 0.1; // inside the deadzone
+        Controller::record_tick(&mut state, &command_sender, 0.2);
+        Controller::record_tick(&mut state, &command_sender, 0.2);
+        state.sticks.right_y = -0.6;
+        Controller::record_tick(&mut state, &command_sender, 0.2);
+        Controller::press_button(Button::Mode, &actions, &mut state).await;
+
+        let recorded = state.last_recording.take().unwrap();
+        assert_eq!(recorded.frames.len(), 2);
+        assert_eq!(recorded.frames[0].ticks, 2);
+        assert_eq!(recorded.duration_ms(), 3 * MACRO_TICK_MS);
+        let target = |frame: usize, id: ServoId| {
+            recorded.frames[frame].positions.iter().find(|(servo, _)| *servo == id).unwrap().1
+        };
+        assert_eq!(target(0, ServoId::Head), 0.5);
+        assert_eq!(target(0, ServoId::RightHipForwardBack), 0.0);
+        assert_eq!(target(1, ServoId::LeftShoulderForwardBack), -0.3);
+
+        // the servos only move when the frame changes
+        let sent: Vec<MovementCommand> = std::iter::from_fn(|| commands.try_recv().ok()).collect();
+        assert_eq!(sent.len(), 2);
+        assert!(matches!(&sent[1], MovementCommand::ServoTargets { positions, .. } if *positions == recorded.frames[1].positions));
+        assert!(matches!(&recorded.to_commands()[1], MovementCommand::ServoTargets { start_ms, .. } if *start_ms == 2 * MACRO_TICK_MS));
+    }
+
+    #[tokio::test]
+    async fn test_mapping_follows_the_connected_controller_and_disconnect_clears_inputs() {
+        type Controller = TARSGamepadController<PCA9685Controller<MockI2C>>;
+        let config: GamepadConfig = toml::from_str(r#"
+            active_mapping = "spare"
+
+            [mappings.8bitdo]
+            vendor_id = 0x2dc8
+            buttons = [{ code = 304, action = "emergency_stop" }]
+            axes = [{ code = 5, stick = "left_y", inverted = true }]
+
+            [mappings.8bitdo_pro]
+            vendor_id = 0x2dc8
+            product_id = 0x6101
+
+            [mappings.spare]
+        "#).unwrap();
+        assert_eq!(config.mapping_for(Some(0x2dc8), Some(0x6101)), Some("8bitdo_pro"));
+        assert_eq!(config.mapping_for(Some(0x2dc8), Some(0x3106)), Some("8bitdo"));
+        assert_eq!(config.mapping_for(Some(0x045e), Some(0x028e)), None);
+
+        let (command_sender, mut commands) = mpsc::unbounded_channel();
+        let actions = ButtonActions { command_sender: command_sender.clone(), safety: None, script_runner: None };
+        let mut state = GamepadState {
+            mappings: config.mappings.clone(),
+            preferred_mapping: config.active_mapping.clone(),
+            connected: true,
+            ..GamepadState::default()
+        };
+
+        // An Xbox pad has no mapping of its own and gets the preferred one
+        state.select_mapping(Some(0x045e), Some(0x028e));
+        assert_eq!(state.active_mapping.as_deref(), Some("spare"));
+        assert_eq!(state.set_stick(Axis::LeftStickY, 5, 0.8), Some((StickAxis::LeftY, 0.8)));
+
+        // The 8BitDo's raw codes go through its mapping
+        state.select_mapping(Some(0x2dc8), Some(0x3106));
+        assert_eq!(state.active_mapping.as_deref(), Some("8bitdo"));
+        assert_eq!(state.set_stick(Axis::Unknown, 5, 0.8), Some((StickAxis::LeftY, -0.8)));
+        assert_eq!(state.sticks.left_y, -0.8);
+        assert_eq!(state.set_stick(Axis::Unknown, 9, 0.5), None);
+        Controller::press_raw_button(Button::Unknown, 304, &actions, &mut state).await;
+        assert!(matches!(commands.try_recv(), Ok(MovementCommand::EmergencyStop)));
+        // Unmapped codes fall back to the bindings
+        Controller::press_raw_button(Button::North, 305, &actions, &mut state).await;
+        assert!(matches!(commands.try_recv(), Ok(MovementCommand::Neutral)));
+
+        // Unplugged mid-recording: the held stick stops driving the servos
+        Controller::press_button(Button::Mode, &actions, &mut state).await;
+        state.set_stick(Axis::RightStickX, 0, 1.0);
+        Controller::record_tick(&mut state, &command_sender, 0.2);
+        assert!(matches!(commands.try_recv(), Ok(MovementCommand::ServoTargets { .. })));
+        state.disconnect();
+        Controller::record_tick(&mut state, &command_sender, 0.2);
+        assert!(commands.try_recv().is_err());
+        assert!(!state.connected && state.active_mapping.is_none());
+        assert_eq!(state.sticks.right_x, 0.0);
+        assert_eq!(state.last_recording.map(|recorded| recorded.frames.len()), Some(1));
+    }
 }
@@ -1,6 +1,7 @@
 //! Hardware abstraction traits for robotics components.
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
 /// Communication channel used to talk to hardware devices.
 /// Serial represents a tty path while Network uses a socket address.
@@ -33,6 +34,20 @@ pub trait SensorReader {
     async fn capture_image(&self) -> Result<Vec<u8>, String>;
 }
 
+/// Inclination sensor used to detect the robot tipping over.
+#[async_trait]
+pub trait TiltSensor {
+    /// Roll and pitch in degrees, zero when upright.
+    async fn read_tilt(&self) -> Result<(f32, f32), String>;
+}
+
+/// Distance sensor facing the direction of travel.
+#[async_trait]
+pub trait RangeSensor {
+    /// Distance to the nearest obstacle in metres.
+    async fn read_range(&self) -> Result<f32, String>;
+}
+
 /// Power management interface.
 #[async_trait]
 pub trait PowerManager {
@@ -40,3 +55,19 @@ pub trait PowerManager {
     async fn shutdown(&self) -> Result<(), String>;
 }
 
+/// One reading from the battery monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PowerReading {
+    /// Pack voltage in volts.
+    pub voltage: f32,
+    /// Draw in amps, positive while discharging.
+    pub current: f32,
+    /// Remaining charge, 0-100.
+    pub percent: f32,
+}
+
+/// Battery or supply monitor such as a fuel gauge or INA219.
+#[async_trait]
+pub trait PowerSource {
+    async fn read_power(&self) -> Result<PowerReading, String>;
+}
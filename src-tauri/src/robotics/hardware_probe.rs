@@ -0,0 +1,209 @@
+//! Startup hardware detection: scan the I2C bus for PCA9685 boards, look for
+//! a gamepad, and initialize the servo system for whatever was found.
+
+use async_trait::async_trait;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::time::{timeout, Duration};
+
+use super::pca9685_controller::ServoBus;
+use super::servo_system::SharedServoSystem;
+use crate::config::config::I2cBusConfig;
+
+/// Addresses a PCA9685 can be strapped to with A0-A2; 0x40 is the default
+pub const PCA9685_ADDRESSES: [u8; 8] = [0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47];
+
+/// Longest a single device may take to answer, so a hung bus can't block boot
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// An I2C bus that can be asked whether a device acknowledges an address
+#[async_trait]
+pub trait BusScanner: Send + Sync {
+    async fn probe(&self, address: u8) -> Result<bool, String>;
+
+    /// Open the servo bus for a board found by `probe`
+    fn open_board(&self, config: &I2cBusConfig) -> Result<ServoBus, String> {
+        ServoBus::from_config(config, false).map_err(|e| e.to_string())
+    }
+}
+
+/// Stands in for the I2C bus when built without the `hardware` feature
+pub struct NoBus;
+
+#[async_trait]
+impl BusScanner for NoBus {
+    async fn probe(&self, _address: u8) -> Result<bool, String> {
+        Ok(false)
+    }
+}
+
+/// The Pi's I2C bus 1
+#[cfg(feature = "hardware")]
+pub struct HardwareBus {
+    bus_id: u8,
+}
+
+#[cfg(feature = "hardware")]
+#[async_trait]
+impl BusScanner for HardwareBus {
+    async fn probe(&self, address: u8) -> Result<bool, String> {
+        let bus_id = self.bus_id;
+        tokio::task::spawn_blocking(move || {
+            let mut i2c = rppal::i2c::I2c::with_bus(bus_id).map_err(|e| format!("Failed to open I2C bus: {}", e))?;
+            i2c.set_slave_address(address as u16).map_err(|e| format!("Failed to set slave address: {}", e))?;
+            // Reading MODE1 only succeeds when a device acknowledges
+            Ok(i2c.smbus_read_byte(0x00).is_ok())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+}
+
+#[cfg(feature = "hardware")]
+pub fn default_bus() -> Box<dyn BusScanner> {
+    Box::new(HardwareBus { bus_id: 1 })
+}
+
+#[cfg(not(feature = "hardware"))]
+pub fn default_bus() -> Box<dyn BusScanner> {
+    Box::new(NoBus)
+}
+
+/// What startup probing found and how the servo system was set up
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HardwareReport {
+    pub pca9685_boards: Vec<u8>,
+    pub gamepad: bool,
+    pub simulation: bool,
+    pub servo_initialized: bool,
+}
+
+/// Addresses from `PCA9685_ADDRESSES` that acknowledge. All are probed at
+/// once, each with `PROBE_TIMEOUT`.
+pub async fn scan_pca9685(bus: &dyn BusScanner) -> Vec<u8> {
+    let probes = PCA9685_ADDRESSES.iter().map(|&address| async move {
+        match timeout(PROBE_TIMEOUT, bus.probe(address)).await {
+            Ok(Ok(true)) => Some(address),
+            Ok(Ok(false)) => None,
+            Ok(Err(e)) => {
+                warn!("I2C probe of 0x{:02X} failed: {}", address, e);
+                None
+            },
+            Err(_) => {
+                warn!("I2C probe of 0x{:02X} timed out", address);
+                None
+            },
+        }
+    });
+    futures_util::future::join_all(probes).await.into_iter().flatten().collect()
+}
+
+/// Whether a gamepad is connected, giving up after `PROBE_TIMEOUT`
+pub async fn detect_gamepad() -> bool {
+    let detect = tokio::task::spawn_blocking(|| {
+        gilrs::Gilrs::new()
+            .map(|gilrs| gilrs.gamepads().any(|(_, gamepad)| gamepad.is_connected()))
+            .unwrap_or(false)
+    });
+    matches!(timeout(PROBE_TIMEOUT, detect).await, Ok(Ok(true)))
+}
+
+/// Initialize the servo system for the boards found on `bus`. With
+/// `force_simulation`, or when no board answers or the board can't be
+/// driven, the servo system runs in simulation instead.
+pub async fn self_configure(
+    servo_system: &SharedServoSystem,
+    bus: &dyn BusScanner,
+    gamepad: bool,
+    force_simulation: bool,
+) -> HardwareReport {
+    let pca9685_boards = if force_simulation { Vec::new() } else { scan_pca9685(bus).await };
+    let found: Vec<String> = pca9685_boards.iter().map(|address| format!("0x{:02X}", address)).collect();
+    info!(
+        "Hardware probe: PCA9685 boards [{}], gamepad {}",
+        found.join(", "),
+        if gamepad { "connected" } else { "not found" }
+    );
+
+    let mut system = servo_system.write().await;
+    let mut initialized = false;
+    if let Some(&address) = pca9685_boards.first() {
+        // Drive the first board at the address it answered on
+        let bus_config = I2cBusConfig { address, ..system.bus_config().clone() };
+        system.set_bus_config(bus_config.clone());
+        system.set_simulation(false);
+        let result = match bus.open_board(&bus_config) {
+            Ok(servo_bus) => system.initialize_with_bus(servo_bus).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => initialized = true,
+            Err(e) => warn!("Could not drive the PCA9685 at {}: {}", found[0], e),
+        }
+    }
+    if !initialized {
+        info!("Falling back to simulated servos");
+        system.set_simulation(true);
+        initialized = match system.initialize().await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to start simulated servo system: {}", e);
+                false
+            },
+        };
+    }
+
+    HardwareReport {
+        pca9685_boards,
+        gamepad,
+        simulation: system.is_simulation(),
+        servo_initialized: initialized,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robotics::pca9685_controller::MockI2C;
+    use crate::robotics::ServoSystem;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    /// A board strapped to 0x42, and a device at 0x41 that never answers
+    struct MockBus;
+
+    #[async_trait]
+    impl BusScanner for MockBus {
+        async fn probe(&self, address: u8) -> Result<bool, String> {
+            if address == 0x41 {
+                std::future::pending::<()>().await;
+            }
+            Ok(address == 0x42)
+        }
+
+        fn open_board(&self, config: &I2cBusConfig) -> Result<ServoBus, String> {
+            assert_eq!(config.address, 0x42);
+            Ok(ServoBus::Mock(MockI2C::new()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detected_board_initializes_servo_controller_at_its_address() {
+        let servo_system: SharedServoSystem = Arc::new(RwLock::new(ServoSystem::new(false)));
+
+        let report = self_configure(&servo_system, &MockBus, false, false).await;
+
+        assert_eq!(report.pca9685_boards, vec![0x42]);
+        assert!(report.servo_initialized);
+        assert!(!report.simulation);
+        let system = servo_system.read().await;
+        assert!(system.is_initialized());
+        assert!(system.servo_controller().is_some());
+        assert_eq!(system.bus_config().address, 0x42);
+
+        drop(system);
+        let report = self_configure(&servo_system, &NoBus, false, false).await;
+        assert!(report.pca9685_boards.is_empty());
+        assert!(report.simulation && report.servo_initialized);
+    }
+}
@@ -0,0 +1,140 @@
+//! Inverse kinematics for the planar leg chains of a `LinkModel`: the
+//! servo positions that put a foot at a point in the side view, so leg
+//! poses can be written as foot positions instead of hand-tuned angles.
+//! Hip up/down isn't in the side-view plane and is left out of the
+//! solved poses.
+
+use std::collections::HashMap;
+
+use super::kinematics::{KinematicChain, LinkModel};
+use super::servo_config::{MovementPose, ServoId};
+
+/// A solution further than this from its target, in metres, is rejected
+pub const IK_TOLERANCE: f32 = 1e-4;
+
+/// Which way the knee bends; every reachable foot position has one
+/// solution for each
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KneeBend {
+    /// Foot swings behind the knee, like a human leg
+    #[default]
+    Backward,
+    Forward,
+}
+
+/// Logical positions of a two-link chain's servos that put its end at
+/// `foot`, given as (reach, height) from the chain's mount point. Fails when
+/// the foot is out of reach or a joint would have to move past its servo's
+/// travel.
+pub fn solve_leg(chain: &KinematicChain, foot: [f32; 2], bend: KneeBend) -> Result<Vec<(ServoId, f32)>, String> {
+    let (thigh, shin) = match chain.links.as_slice() {
+        [thigh, shin] => (thigh, shin),
+        links => return Err(format!("{} has {} links; leg IK needs exactly 2", chain.name, links.len())),
+    };
+    let (reach, height) = (foot[0], foot[1]);
+    let distance = reach.hypot(height);
+    let (min_reach, max_reach) = ((thigh.length - shin.length).abs(), thigh.length + shin.length);
+    if distance > max_reach || distance < min_reach {
+        return Err(format!(
+            "Foot target ({:.3}, {:.3}) is out of reach for {}: {:.3} m away, {:.3} to {:.3} m reachable",
+            reach, height, chain.name, distance, min_reach, max_reach
+        ));
+    }
+
+    // Law of cosines for the knee, then the thigh heading that puts the foot on target
+    let cos_knee = (distance * distance - thigh.length * thigh.length - shin.length * shin.length)
+        / (2.0 * thigh.length * shin.length);
+    let knee = match bend {
+        KneeBend::Backward => -cos_knee.clamp(-1.0, 1.0).acos(),
+        KneeBend::Forward => cos_knee.clamp(-1.0, 1.0).acos(),
+    };
+    let thigh_heading = height.atan2(reach) - (shin.length * knee.sin()).atan2(thigh.length + shin.length * knee.cos());
+    let hip_deg = normalize_degrees(thigh_heading.to_degrees() - chain.base_angle_deg);
+    let knee_deg = knee.to_degrees();
+
+    let mut positions = Vec::new();
+    for (link, angle) in [(thigh, hip_deg), (shin, knee_deg)] {
+        let position = (angle - link.offset_deg) / link.degrees_per_unit;
+        if !(-1.0..=1.0).contains(&position) {
+            return Err(format!(
+                "Foot target ({:.3}, {:.3}) needs {} at {:.2}, beyond its servo travel",
+                reach, height, link.servo.name(), position
+            ));
+        }
+        positions.push((link.servo, position));
+    }
+
+    // Check the solution with forward kinematics before anything moves
+    let joints = chain.forward(&[hip_deg, knee_deg]);
+    let end = joints[joints.len() - 1];
+    let error = (end[0] - chain.origin[0] - reach).hypot(end[1] - chain.origin[1] - height);
+    if error > IK_TOLERANCE {
+        return Err(format!("IK solution for {} misses its target by {:.4} m", chain.name, error));
+    }
+    Ok(positions)
+}
+
+/// Angle in degrees wrapped to -180..180
+fn normalize_degrees(angle: f32) -> f32 {
+    let wrapped = (angle + 180.0).rem_euclid(360.0) - 180.0;
+    if wrapped == -180.0 { 180.0 } else { wrapped }
+}
+
+/// A pose putting each named chain's foot at its (reach, height) target
+pub fn foot_pose(
+    name: &str,
+    model: &LinkModel,
+    feet: &[(&str, [f32; 2])],
+    duration_ms: u64,
+) -> Result<MovementPose, String> {
+    let chains: HashMap<&str, &KinematicChain> = model.chains.iter().map(|chain| (chain.name.as_str(), chain)).collect();
+    let mut positions = Vec::new();
+    for (chain_name, foot) in feet {
+        let chain = chains.get(chain_name).ok_or_else(|| format!("Link model has no chain named '{}'", chain_name))?;
+        positions.extend(solve_leg(chain, *foot, KneeBend::default())?);
+    }
+    Ok(MovementPose::new(name, positions, duration_ms))
+}
+
+/// A stride `reach` metres long each way: right foot forward, left foot
+/// back, both `height` from the hips (negative is below)
+pub fn step_pose(model: &LinkModel, reach: f32, height: f32, duration_ms: u64) -> Result<MovementPose, String> {
+    foot_pose(
+        "IK Step",
+        model,
+        &[("right_leg", [reach, height]), ("left_leg", [-reach, height])],
+        duration_ms,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solved_leg_positions_round_trip_through_forward_kinematics() {
+        let model = LinkModel::default();
+        let leg = &model.chains[0];
+        for foot in [[0.05, -0.43], [0.0, -0.42], [-0.08, -0.42], [0.1, -0.41], [0.02, -0.449]] {
+            for bend in [KneeBend::Backward, KneeBend::Forward] {
+                let positions = solve_leg(leg, foot, bend).unwrap_or_else(|e| panic!("{:?} {:?}: {}", foot, bend, e));
+                let end = leg.solve(&positions.iter().copied().collect()).end_effector;
+                assert!((end[0] - foot[0]).abs() < 1e-4 && (end[1] - foot[1]).abs() < 1e-4, "{:?} reached {:?}", foot, end);
+                let knee = positions[1].1;
+                assert_eq!(knee <= 0.0, bend == KneeBend::Backward, "{:?} knee at {}", foot, knee);
+            }
+        }
+
+        // Past the leg's length, inside its dead zone, or past the servo travel
+        assert!(solve_leg(leg, [0.3, -0.4], KneeBend::Backward).unwrap_err().contains("out of reach"));
+        assert!(solve_leg(leg, [0.0, -0.01], KneeBend::Backward).unwrap_err().contains("out of reach"));
+        assert!(solve_leg(leg, [0.0, -0.3], KneeBend::Backward).unwrap_err().contains("beyond its servo travel"));
+
+        let step = step_pose(&model, 0.05, -0.43, 600).unwrap();
+        let servos: Vec<ServoId> = step.positions.iter().map(|(servo, _)| *servo).collect();
+        assert_eq!(servos, vec![ServoId::RightHipForwardBack, ServoId::RightKnee, ServoId::LeftHipForwardBack, ServoId::LeftKnee]);
+        let right = model.chains[0].solve(&step.positions.iter().copied().collect());
+        assert!((right.end_effector[0] - 0.05).abs() < 1e-4);
+        assert!(foot_pose("Reach", &model, &[("tail", [0.0, -0.4])], 500).is_err());
+    }
+}
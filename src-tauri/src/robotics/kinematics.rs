@@ -0,0 +1,171 @@
+//! Approximate planar forward kinematics for previewing TARS's pose without
+//! hardware. Each limb is a chain of links in the side view (x forward,
+//! y up, metres), with each joint angle driven by one servo.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::servo_config::ServoId;
+
+/// One rigid link rotating about the end of the previous link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KinematicLink {
+    pub servo: ServoId,
+    pub length: f32,
+    /// Joint angle at logical position 0.0, relative to the previous link
+    #[serde(default)]
+    pub offset_deg: f32,
+    /// Joint travel in degrees for a logical position change of 1.0
+    #[serde(default = "KinematicLink::default_degrees_per_unit")]
+    pub degrees_per_unit: f32,
+}
+
+impl KinematicLink {
+    fn default_degrees_per_unit() -> f32 {
+        45.0
+    }
+
+    pub fn new(servo: ServoId, length: f32) -> Self {
+        Self { servo, length, offset_deg: 0.0, degrees_per_unit: Self::default_degrees_per_unit() }
+    }
+
+    pub fn with_offset(mut self, offset_deg: f32) -> Self {
+        self.offset_deg = offset_deg;
+        self
+    }
+
+    /// Joint angle in degrees for a logical servo position
+    pub fn joint_angle(&self, position: f32) -> f32 {
+        self.offset_deg + position * self.degrees_per_unit
+    }
+}
+
+/// A limb: links from a mount point on the body outwards
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KinematicChain {
+    pub name: String,
+    /// Mount point on the body
+    #[serde(default)]
+    pub origin: [f32; 2],
+    /// Direction of the first link at a zero joint angle, 0 = forward, 90 = up
+    #[serde(default)]
+    pub base_angle_deg: f32,
+    pub links: Vec<KinematicLink>,
+}
+
+impl KinematicChain {
+    /// Joint positions from explicit joint angles in degrees, one per link.
+    /// Returns the origin followed by the end of every link.
+    pub fn forward(&self, joint_angles: &[f32]) -> Vec<[f32; 2]> {
+        let mut point = self.origin;
+        let mut heading = self.base_angle_deg;
+        let mut joints = vec![point];
+        for (link, angle) in self.links.iter().zip(joint_angles) {
+            heading += angle;
+            let radians = heading.to_radians();
+            point = [point[0] + link.length * radians.cos(), point[1] + link.length * radians.sin()];
+            joints.push(point);
+        }
+        joints
+    }
+
+    /// Solve the chain for logical servo positions; missing servos count as 0.0
+    pub fn solve(&self, positions: &HashMap<ServoId, f32>) -> ChainPose {
+        let angles: Vec<f32> = self.links.iter()
+            .map(|link| link.joint_angle(positions.get(&link.servo).copied().unwrap_or(0.0)))
+            .collect();
+        let joints = self.forward(&angles);
+        ChainPose { name: self.name.clone(), end_effector: joints[joints.len() - 1], joints }
+    }
+}
+
+/// Solved positions of one chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainPose {
+    pub name: String,
+    pub joints: Vec<[f32; 2]>,
+    pub end_effector: [f32; 2],
+}
+
+/// Limb geometry used for the telemetry pose feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkModel {
+    pub chains: Vec<KinematicChain>,
+}
+
+impl Default for LinkModel {
+    /// Rough TARS proportions: legs hang from the hips with a knee, arms
+    /// hang from the shoulders.
+    fn default() -> Self {
+        let leg = |name: &str, hip: ServoId, knee: ServoId| KinematicChain {
+            name: name.to_string(),
+            origin: [0.0, 0.0],
+            base_angle_deg: -90.0,
+            links: vec![KinematicLink::new(hip, 0.25), KinematicLink::new(knee, 0.2)],
+        };
+        let arm = |name: &str, shoulder: ServoId| KinematicChain {
+            name: name.to_string(),
+            origin: [0.0, 0.35],
+            base_angle_deg: -90.0,
+            links: vec![KinematicLink { degrees_per_unit: 90.0, ..KinematicLink::new(shoulder, 0.3) }],
+        };
+        Self {
+            chains: vec![
+                leg("right_leg", ServoId::RightHipForwardBack, ServoId::RightKnee),
+                leg("left_leg", ServoId::LeftHipForwardBack, ServoId::LeftKnee),
+                arm("right_arm", ServoId::RightShoulderForwardBack),
+                arm("left_arm", ServoId::LeftShoulderForwardBack),
+            ],
+        }
+    }
+}
+
+impl LinkModel {
+    pub fn solve(&self, positions: &HashMap<ServoId, f32>) -> KinematicPose {
+        KinematicPose { chains: self.chains.iter().map(|chain| chain.solve(positions)).collect() }
+    }
+}
+
+/// Joint and end-effector positions of every chain, as sent over telemetry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KinematicPose {
+    pub chains: Vec<ChainPose>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: [f32; 2], expected: [f32; 2]) {
+        assert!(
+            (actual[0] - expected[0]).abs() < 1e-5 && (actual[1] - expected[1]).abs() < 1e-5,
+            "{:?} != {:?}", actual, expected
+        );
+    }
+
+    #[test]
+    fn test_two_link_chain_end_position_follows_joint_angles() {
+        let chain = KinematicChain {
+            name: "test".to_string(),
+            origin: [0.0, 0.0],
+            base_angle_deg: 0.0,
+            links: vec![KinematicLink::new(ServoId::RightHipForwardBack, 1.0), KinematicLink::new(ServoId::RightKnee, 0.5)],
+        };
+
+        let joints = chain.forward(&[90.0, -90.0]);
+        assert_near(joints[1], [0.0, 1.0]);
+        assert_near(joints[2], [0.5, 1.0]);
+
+        // 45 degrees per logical unit: 2.0 puts the hip at 90, -1.0 bends the knee back 45
+        let positions = HashMap::from([(ServoId::RightHipForwardBack, 2.0), (ServoId::RightKnee, -1.0)]);
+        let pose = chain.solve(&positions);
+        let half = 0.5 * 45f32.to_radians().cos();
+        assert_near(pose.end_effector, [half, 1.0 + half]);
+        assert_eq!(pose.joints.len(), 3);
+
+        // the default model hangs the legs straight down at neutral
+        let neutral = LinkModel::default().solve(&HashMap::new());
+        assert_near(neutral.chains[0].end_effector, [0.0, -0.45]);
+    }
+}
@@ -1,8 +1,14 @@
 //! High level movement primitives for the robot.
 
-use tokio::time::{sleep, Duration};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tokio::time::{sleep, Duration, Instant};
 
+use super::easing::Easing;
 use super::hardware_interface::{MotorController, ServoControl};
+use super::servo_config::ServoId;
+use super::tars_movement::MovementCommand;
+use crate::safety::SharedSafety;
 
 /// Different mobility configurations the robot can operate in.
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -11,6 +17,119 @@ pub enum MobilityMode {
     Walking,
 }
 
+/// Direction of travel for the walking gait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WalkDirection {
+    Forward,
+    Backward,
+    TurnLeft,
+    TurnRight,
+}
+
+/// Parameters shaping the segmented walking gait.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GaitParameters {
+    /// Hip forward/back swing per step, in normalized servo units.
+    pub step_length: f32,
+    /// Hip lift of the swinging leg, in normalized servo units.
+    pub step_height: f32,
+    /// Steps per second.
+    pub cadence: f32,
+    /// Legs that must stay planted in every phase of the gait.
+    pub min_contact_points: usize,
+}
+
+impl Default for GaitParameters {
+    fn default() -> Self {
+        Self {
+            step_length: 0.3,
+            step_height: 0.4,
+            cadence: 1.0,
+            min_contact_points: 1,
+        }
+    }
+}
+
+/// Estimated robot pose in the odometry frame. Heading is in radians,
+/// counter-clockwise from the +x axis.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Pose {
+    pub x: f32,
+    pub y: f32,
+    pub heading: f32,
+}
+
+impl Pose {
+    /// Advance by `distance` metres while turning by `dtheta` radians, using
+    /// the midpoint heading for the translation.
+    fn integrate(&mut self, distance: f32, dtheta: f32) {
+        let mid_heading = self.heading + dtheta / 2.0;
+        self.x += distance * mid_heading.cos();
+        self.y += distance * mid_heading.sin();
+        self.heading = normalize_angle(self.heading + dtheta);
+    }
+}
+
+/// Wrap an angle into (-PI, PI].
+fn normalize_angle(angle: f32) -> f32 {
+    use std::f32::consts::PI;
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped <= -PI { wrapped + 2.0 * PI } else { wrapped }
+}
+
+/// Motion model used for dead-reckoning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OdometryConfig {
+    /// Distance between the drive wheels, in metres.
+    pub wheel_base: f32,
+    /// Wheel surface speed at a commanded speed of 1.0, in m/s.
+    pub max_wheel_speed: f32,
+    /// Forward displacement of one walking step, in metres.
+    pub step_displacement: f32,
+    /// Heading change of one turning step, in radians.
+    pub step_turn_angle: f32,
+}
+
+impl Default for OdometryConfig {
+    fn default() -> Self {
+        Self {
+            wheel_base: 0.2,
+            max_wheel_speed: 0.5,
+            step_displacement: 0.08,
+            step_turn_angle: std::f32::consts::PI / 12.0,
+        }
+    }
+}
+
+/// A lifted hip above this value means the leg is not bearing weight.
+const CONTACT_LIFT_THRESHOLD: f32 = 0.05;
+
+/// Phases of a single step, each taking a quarter of the step period.
+const GAIT_PHASES: u64 = 4;
+
+/// Servos making up one leg.
+#[derive(Clone, Copy)]
+struct Leg {
+    hip_forward_back: ServoId,
+    hip_up_down: ServoId,
+    knee: ServoId,
+    shoulder: ServoId,
+}
+
+const RIGHT_LEG: Leg = Leg {
+    hip_forward_back: ServoId::RightHipForwardBack,
+    hip_up_down: ServoId::RightHipUpDown,
+    knee: ServoId::RightKnee,
+    shoulder: ServoId::RightShoulderForwardBack,
+};
+
+const LEFT_LEG: Leg = Leg {
+    hip_forward_back: ServoId::LeftHipForwardBack,
+    hip_up_down: ServoId::LeftHipUpDown,
+    knee: ServoId::LeftKnee,
+    shoulder: ServoId::LeftShoulderForwardBack,
+};
+
 /// Controller that exposes simple motion commands.
 pub struct MobilityController<I>
 where
@@ -20,6 +139,10 @@ where
     mode: MobilityMode,
     max_speed: f32,
     max_joint_angle: f32,
+    gait: GaitParameters,
+    odometry: OdometryConfig,
+    pose: Mutex<Pose>,
+    safety: Option<SharedSafety>,
 }
 
 impl<I> MobilityController<I>
@@ -32,19 +155,120 @@ where
             mode: MobilityMode::Wheeled,
             max_speed: 1.0,
             max_joint_angle: 1.57,
+            gait: GaitParameters::default(),
+            odometry: OdometryConfig::default(),
+            pose: Mutex::new(Pose::default()),
+            safety: None,
         }
     }
 
+    /// Cancel in-progress movements when `safety` requests a controlled stop.
+    pub fn with_safety(mut self, safety: SharedSafety) -> Self {
+        self.safety = Some(safety);
+        self
+    }
+
     /// Change the mobility mode of the robot.
     pub fn set_mode(&mut self, mode: MobilityMode) {
         self.mode = mode;
     }
 
+    /// Replace the gait used by [`walk`](Self::walk).
+    pub fn set_gait_parameters(&mut self, gait: GaitParameters) {
+        self.gait = gait;
+    }
+
+    pub fn gait_parameters(&self) -> &GaitParameters {
+        &self.gait
+    }
+
+    /// Replace the motion model used for pose estimation.
+    pub fn set_odometry_config(&mut self, odometry: OdometryConfig) {
+        self.odometry = odometry;
+    }
+
+    /// Pose estimated from the commands issued since the last reset.
+    pub fn current_pose(&self) -> Pose {
+        *self.pose.lock().unwrap()
+    }
+
+    /// Reset the pose estimate to the origin.
+    pub fn reset_pose(&self) {
+        *self.pose.lock().unwrap() = Pose::default();
+    }
+
     /// Drive forward in wheeled mode.
     pub async fn forward(&self, speed: f32) -> Result<(), String> {
         self.ensure_wheeled()?;
         let clamped = speed.clamp(-self.max_speed, self.max_speed);
-        self.interface.set_speed(0, clamped).await
+        MotorController::set_speed(&self.interface, 0, clamped).await
+    }
+
+    /// Drive at `speed` for `duration_ms`, then stop, updating the pose estimate.
+    pub async fn drive_for(&self, speed: f32, duration_ms: u64) -> Result<(), String> {
+        self.ensure_not_halted().await?;
+        self.forward(speed).await?;
+        let halted = self.hold(duration_ms).await;
+        self.interface.stop(0).await?;
+
+        match halted {
+            None => {
+                self.integrate_drive(speed, duration_ms);
+                Ok(())
+            }
+            Some((reason, elapsed_ms)) => {
+                self.integrate_drive(speed, elapsed_ms);
+                Err(format!("Movement halted: {}", reason))
+            }
+        }
+    }
+
+    /// Rotate in place at `speed` for `duration_ms`, then stop. Positive
+    /// speeds turn counter-clockwise.
+    pub async fn rotate_for(&self, speed: f32, duration_ms: u64) -> Result<(), String> {
+        self.ensure_not_halted().await?;
+        self.rotate(speed).await?;
+        let halted = self.hold(duration_ms).await;
+        self.interface.stop(1).await?;
+
+        let elapsed_ms = halted.as_ref().map_or(duration_ms, |(_, elapsed)| *elapsed);
+        // Wheels spin in opposite directions, each at the commanded speed.
+        let clamped = speed.clamp(-self.max_speed, self.max_speed);
+        let wheel_travel = clamped * self.odometry.max_wheel_speed * elapsed_ms as f32 / 1000.0;
+        let dtheta = 2.0 * wheel_travel / self.odometry.wheel_base;
+        self.pose.lock().unwrap().integrate(0.0, dtheta);
+
+        match halted {
+            None => Ok(()),
+            Some((reason, _)) => Err(format!("Movement halted: {}", reason)),
+        }
+    }
+
+    /// Wait out a timed movement. Returns the stop reason and elapsed time if
+    /// a controlled stop cut it short.
+    async fn hold(&self, duration_ms: u64) -> Option<(String, u64)> {
+        let Some(safety) = &self.safety else {
+            sleep(Duration::from_millis(duration_ms)).await;
+            return None;
+        };
+
+        let started = Instant::now();
+        tokio::select! {
+            _ = sleep(Duration::from_millis(duration_ms)) => None,
+            reason = safety.wait_for_controlled_stop() => {
+                let elapsed_ms = (started.elapsed().as_millis() as u64).min(duration_ms);
+                Some((reason, elapsed_ms))
+            }
+        }
+    }
+
+    async fn ensure_not_halted(&self) -> Result<(), String> {
+        if let Some(safety) = &self.safety {
+            if let Some(reason) = safety.controlled_stop_reason().await {
+                return Err(format!("Movement halted: {}", reason));
+            }
+        }
+        Ok(())
     }
 
     /// Drive backward in wheeled mode.
@@ -56,7 +280,7 @@ where
     pub async fn rotate(&self, speed: f32) -> Result<(), String> {
         self.ensure_wheeled()?;
         let clamped = speed.clamp(-self.max_speed, self.max_speed);
-        self.interface.set_speed(1, clamped).await
+        MotorController::set_speed(&self.interface, 1, clamped).await
     }
 
     /// Control leg joints when in walking mode.
@@ -69,6 +293,159 @@ where
         Ok(())
     }
 
+    /// Walk a number of steps, alternating legs starting with the right.
+    /// Returns the servo schedule that was executed.
+    pub async fn walk(&self, direction: WalkDirection, steps: u32) -> Result<Vec<MovementCommand>, String> {
+        self.ensure_walking()?;
+        self.ensure_not_halted().await?;
+        let schedule = self.plan_walk(direction, steps)?;
+
+        for (i, command) in schedule.iter().enumerate() {
+            if let MovementCommand::ServoTargets { duration_ms, positions, .. } = command {
+                for (servo, position) in positions {
+                    self.interface.set_position(*servo as u8, *position).await?;
+                }
+                if let Some((reason, _)) = self.hold(*duration_ms).await {
+                    return Err(format!("Movement halted: {}", reason));
+                }
+            }
+
+            // Count a step once its final phase has been executed.
+            if (i as u64 + 1).is_multiple_of(GAIT_PHASES) {
+                self.integrate_step(direction);
+            }
+        }
+
+        Ok(schedule)
+    }
+
+    /// Build the servo schedule for a walk without moving anything.
+    pub fn plan_walk(&self, direction: WalkDirection, steps: u32) -> Result<Vec<MovementCommand>, String> {
+        if self.gait.cadence <= 0.0 {
+            return Err("Gait cadence must be positive".into());
+        }
+
+        let step_ms = (1000.0 / self.gait.cadence) as u64;
+        let phase_ms = step_ms / GAIT_PHASES;
+        let mut schedule = Vec::with_capacity(steps as usize * GAIT_PHASES as usize);
+
+        for step in 0..steps as u64 {
+            let (swing, stance) = if step % 2 == 0 {
+                (RIGHT_LEG, LEFT_LEG)
+            } else {
+                (LEFT_LEG, RIGHT_LEG)
+            };
+
+            for (phase, positions) in self.step_phases(direction, swing, stance).into_iter().enumerate() {
+                let positions: Vec<(ServoId, f32)> = positions
+                    .into_iter()
+                    .map(|(servo, pos)| (servo, self.clamp_joint(pos)))
+                    .collect();
+                self.check_contact_points(&positions)?;
+
+                schedule.push(MovementCommand::ServoTargets {
+                    start_ms: step * step_ms + phase as u64 * phase_ms,
+                    duration_ms: phase_ms,
+                    positions,
+                    easing: Easing::Linear,
+                });
+            }
+        }
+
+        Ok(schedule)
+    }
+
+    fn integrate_drive(&self, speed: f32, duration_ms: u64) {
+        let clamped = speed.clamp(-self.max_speed, self.max_speed);
+        let distance = clamped * self.odometry.max_wheel_speed * duration_ms as f32 / 1000.0;
+        self.pose.lock().unwrap().integrate(distance, 0.0);
+    }
+
+    fn integrate_step(&self, direction: WalkDirection) {
+        let (distance, dtheta) = match direction {
+            WalkDirection::Forward => (self.odometry.step_displacement, 0.0),
+            WalkDirection::Backward => (-self.odometry.step_displacement, 0.0),
+            WalkDirection::TurnLeft => (0.0, self.odometry.step_turn_angle),
+            WalkDirection::TurnRight => (0.0, -self.odometry.step_turn_angle),
+        };
+        self.pose.lock().unwrap().integrate(distance, dtheta);
+    }
+
+    /// Lift, swing, plant and recover for one leg.
+    fn step_phases(&self, direction: WalkDirection, swing: Leg, stance: Leg) -> Vec<Vec<(ServoId, f32)>> {
+        let length = self.gait.step_length;
+        let height = self.gait.step_height;
+
+        // Hip forward/back targets for the swinging and planted leg.
+        let (swing_hip, stance_hip) = match direction {
+            WalkDirection::Forward => (length, -length),
+            WalkDirection::Backward => (-length, length),
+            WalkDirection::TurnRight | WalkDirection::TurnLeft => {
+                let right = if direction == WalkDirection::TurnRight { length } else { -length };
+                if swing.hip_forward_back == ServoId::RightHipForwardBack {
+                    (right, -right)
+                } else {
+                    (-right, right)
+                }
+            }
+        };
+
+        vec![
+            // Lift the swing leg and shift weight onto the stance leg.
+            vec![
+                (swing.hip_up_down, height),
+                (swing.knee, height * 1.5),
+                (stance.hip_up_down, -height * 0.5),
+            ],
+            // Swing the lifted leg while the arms counter-balance.
+            vec![
+                (swing.hip_forward_back, swing_hip),
+                (stance.hip_forward_back, stance_hip),
+                (swing.shoulder, -swing_hip * 0.5),
+                (stance.shoulder, -stance_hip * 0.5),
+            ],
+            // Plant the swing leg.
+            vec![
+                (swing.hip_up_down, 0.0),
+                (swing.knee, 0.0),
+                (stance.hip_up_down, 0.0),
+            ],
+            // Bring both legs back under the body.
+            vec![
+                (swing.hip_forward_back, 0.0),
+                (stance.hip_forward_back, 0.0),
+                (swing.shoulder, 0.0),
+                (stance.shoulder, 0.0),
+            ],
+        ]
+    }
+
+    /// Reject phases that would leave fewer legs planted than configured.
+    fn check_contact_points(&self, positions: &[(ServoId, f32)]) -> Result<(), String> {
+        let lifted = [RIGHT_LEG, LEFT_LEG]
+            .iter()
+            .filter(|leg| {
+                positions
+                    .iter()
+                    .any(|(servo, pos)| *servo == leg.hip_up_down && *pos > CONTACT_LIFT_THRESHOLD)
+            })
+            .count();
+        let contacts = 2 - lifted;
+
+        if contacts < self.gait.min_contact_points {
+            return Err(format!(
+                "Gait would leave {} contact point(s), minimum is {}",
+                contacts, self.gait.min_contact_points
+            ));
+        }
+        Ok(())
+    }
+
+    fn clamp_joint(&self, position: f32) -> f32 {
+        let limit = self.max_joint_angle.min(1.0);
+        position.clamp(-limit, limit)
+    }
+
     /// Simple motion planning placeholder.
     pub async fn smooth_move(&self, target: f32, duration_ms: u64) -> Result<(), String> {
         let steps = 10;
@@ -76,6 +453,7 @@ where
         for i in 0..steps {
             self.forward(step * i as f32).await?;
             sleep(Duration::from_millis(duration_ms / steps)).await;
+            self.integrate_drive(step * i as f32, duration_ms / steps);
         }
         Ok(())
     }
@@ -94,4 +472,3 @@ where
         Ok(())
     }
 }
-
@@ -1,15 +1,39 @@
 pub mod hardware_interface;
 pub mod mobility_controller;
 pub mod telemetry;
+pub mod safety_sensors;
 
 // New servo control modules
 pub mod servo_config;
 pub mod pca9685_controller;
 pub mod tars_movement;
 pub mod gamepad_controller;
+pub mod pose_library;
+pub mod servo_system;
+pub mod servo_diagnostics;
+pub mod hardware_probe;
+pub mod remote_control;
+pub mod power_monitor;
+pub mod movement_macro;
+pub mod kinematics;
+pub mod inverse_kinematics;
+pub mod tick_rate;
+pub mod easing;
+pub mod thermal;
+pub mod movement_audit;
 
 // Re-exports for convenience
-pub use servo_config::{ServoId, TARSServoConfig, MovementPose, TARSPoses};
+pub use servo_config::{AngleConstraint, ServoId, TARSServoConfig, MovementPose, TARSPoses};
 pub use pca9685_controller::{PCA9685Controller, PCA9685Error};
-pub use tars_movement::{TARSMovementController, MovementCommand, MovementStatus};
-pub use gamepad_controller::{TARSGamepadController, GamepadConfig, GamepadState};
+pub use tars_movement::{TARSMovementController, MovementCommand, MovementSequence, MovementStatus};
+pub use gamepad_controller::{TARSGamepadController, GamepadConfig, GamepadMapping, GamepadState, TARSButton, ButtonBindings};
+pub use pose_library::{PoseLibrary, SharedPoseLibrary};
+pub use servo_system::{ServoSystem, SharedServoSystem};
+pub use movement_macro::{MovementMacro, MacroFrame, MacroRecorder};
+pub use kinematics::{LinkModel, KinematicChain, KinematicLink, KinematicPose};
+pub use inverse_kinematics::{foot_pose, solve_leg, step_pose, KneeBend};
+pub use tick_rate::{TickRateConfig, TickRateTuner};
+pub use easing::Easing;
+pub use thermal::{ThermalConfig, ThermalGuard, ThermalPolicy, ThermalState};
+pub use movement_audit::{MovementRecord, MovementSource, ReplayStep};
+pub use servo_diagnostics::{diagnose_servos, DiagnosticOutcome, DiagnosticReport, ServoDiagnostic};
@@ -0,0 +1,188 @@
+//! Movement audit trail: every command a movement controller runs is kept
+//! in a bounded movement log with where it came from and the servo targets
+//! it resolved to, so a time range can be reviewed or replayed in simulation.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::sync::RwLock;
+
+use super::servo_config::ServoId;
+use super::servo_system::ServoSystem;
+use super::tars_movement::MovementCommand;
+
+/// Records kept before the oldest are dropped
+pub const MOVEMENT_LOG_CAPACITY: usize = 4096;
+
+/// Movement records, oldest first. Kept apart from the approval audit log so
+/// a busy gamepad can't push approvals and security events out of it.
+static MOVEMENT_LOG: Lazy<RwLock<VecDeque<MovementRecord>>> = Lazy::new(|| RwLock::new(VecDeque::new()));
+
+/// Who asked for a movement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementSource {
+    Gamepad,
+    /// Tauri commands and the CLI
+    Api,
+    /// The remote-control WebSocket
+    Remote,
+    Macro,
+    Script,
+    /// Playback of a recorded movement sequence
+    Sequence,
+}
+
+impl MovementSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovementSource::Gamepad => "gamepad",
+            MovementSource::Api => "api",
+            MovementSource::Remote => "remote",
+            MovementSource::Macro => "macro",
+            MovementSource::Script => "script",
+            MovementSource::Sequence => "sequence",
+        }
+    }
+}
+
+/// One executed movement command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovementRecord {
+    /// When the command started
+    pub timestamp: DateTime<Utc>,
+    pub source: MovementSource,
+    pub command: MovementCommand,
+    /// Every target the command set, in order. Transitions contribute their
+    /// pose targets rather than each interpolated tick.
+    pub servo_targets: Vec<(ServoId, f32)>,
+    pub success: bool,
+    /// The response, or the error the command failed with
+    pub outcome: String,
+}
+
+/// Add `record` to the movement log
+pub async fn audit_movement(record: &MovementRecord) {
+    push_bounded(&mut *MOVEMENT_LOG.write().await, record.clone(), MOVEMENT_LOG_CAPACITY);
+}
+
+fn push_bounded(log: &mut VecDeque<MovementRecord>, record: MovementRecord, capacity: usize) {
+    if log.len() >= capacity {
+        log.pop_front();
+    }
+    log.push_back(record);
+}
+
+/// Movement records started between `from` and `to` (inclusive), oldest first
+pub async fn movement_log(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Vec<MovementRecord> {
+    let mut records: Vec<MovementRecord> = MOVEMENT_LOG.read().await.iter()
+        .filter(|record| {
+            from.is_none_or(|from| record.timestamp >= from) && to.is_none_or(|to| record.timestamp <= to)
+        })
+        .cloned()
+        .collect();
+    records.sort_by_key(|record| record.timestamp);
+    records
+}
+
+/// One command re-executed by `replay_movement_log`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayStep {
+    pub original: MovementRecord,
+    /// Targets the command resolved to in simulation
+    pub servo_targets: Vec<(ServoId, f32)>,
+    pub success: bool,
+    pub outcome: String,
+}
+
+impl ReplayStep {
+    /// Whether the replay resolved to the same targets as the original run
+    pub fn matches_original(&self) -> bool {
+        self.servo_targets == self.original.servo_targets
+    }
+}
+
+/// Re-execute the movement commands recorded between `from` and `to` on a
+/// simulated copy of `live`, so the replay resolves targets with the same
+/// profile, calibration and poses. The replay never drives real hardware and
+/// is not itself audited.
+pub async fn replay_movement_log(
+    live: &ServoSystem,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<ReplayStep>, String> {
+    let mut system = live.simulated_replica();
+    system.initialize().await?;
+    let movement = system.movement_controller().ok_or("Simulated movement controller unavailable")?;
+
+    let mut steps = Vec::new();
+    for original in movement_log(from, to).await {
+        // replay what the robot did, including what ran after a stop
+        movement.set_enabled(true).await;
+        let result = movement.execute_command(original.command.clone()).await;
+        let (success, outcome) = match result {
+            Ok(response) => (true, response),
+            Err(e) => (false, e),
+        };
+        steps.push(ReplayStep { servo_targets: movement.last_servo_targets().await, original, success, outcome });
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robotics::Easing;
+
+    #[tokio::test]
+    async fn test_commands_are_logged_and_replay_reproduces_targets() {
+        let mut system = ServoSystem::new(true).with_movement_audit(true);
+        system.switch_profile("mini-desk").await.unwrap();
+        system.initialize().await.unwrap();
+        let movement = system.movement_controller().unwrap();
+
+        let from = Utc::now();
+        movement.execute_command_from(MovementCommand::Pose("Turn Left".to_string()), MovementSource::Api).await.unwrap();
+        // raised hip with a folded knee: fine on the knee-less mini-desk, rejected by the default profile
+        let targets = vec![(ServoId::RightHipUpDown, 0.8), (ServoId::RightKnee, 0.8)];
+        let sequence = MovementCommand::ServoTargets { start_ms: 0, duration_ms: 10, positions: targets.clone(), easing: Easing::Linear };
+        movement.execute_command_from(sequence, MovementSource::Script).await.unwrap();
+        let to = Utc::now();
+
+        let records = movement_log(Some(from), Some(to)).await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].source, MovementSource::Api);
+        assert!(records[0].servo_targets.contains(&(ServoId::Head, -0.3)));
+        assert_eq!((records[1].source, records[1].servo_targets.clone()), (MovementSource::Script, targets));
+        assert!(records.iter().all(|record| record.success));
+
+        let steps = replay_movement_log(&system, Some(from), Some(to)).await.unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps.iter().all(|step| step.success && step.matches_original()), "{:?}", steps);
+        assert!(steps[0].outcome.starts_with("[simulated]"));
+        assert_eq!(movement_log(Some(from), None).await.len(), 2, "replays aren't logged");
+
+        let on_default = replay_movement_log(&ServoSystem::new(true), Some(from), Some(to)).await.unwrap();
+        assert!(!on_default[1].success, "the default profile should reject the folded knee");
+    }
+
+    #[test]
+    fn test_movement_log_drops_the_oldest_records() {
+        let record = |outcome: &str| MovementRecord {
+            timestamp: Utc::now(),
+            source: MovementSource::Macro,
+            command: MovementCommand::Neutral,
+            servo_targets: Vec::new(),
+            success: true,
+            outcome: outcome.to_string(),
+        };
+        let mut log = VecDeque::new();
+        for outcome in ["first", "second", "third"] {
+            push_bounded(&mut log, record(outcome), 2);
+        }
+
+        let outcomes: Vec<&str> = log.iter().map(|record| record.outcome.as_str()).collect();
+        assert_eq!(outcomes, vec!["second", "third"]);
+    }
+}
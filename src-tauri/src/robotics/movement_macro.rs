@@ -0,0 +1,153 @@
+//! Movement macros: servo frames captured at a fixed tick, played back as
+//! timed `ServoTargets` commands.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::easing::Easing;
+use super::hardware_interface::ServoControl;
+use super::movement_audit::MovementSource;
+use super::servo_config::ServoId;
+use super::tars_movement::{MovementCommand, TARSMovementController};
+
+/// Interval between recorded frames
+pub const MACRO_TICK_MS: u64 = 50;
+
+/// Directory recorded macros are saved to
+pub const MACRO_DIR: &str = "macros";
+
+/// Logical servo travel at full stick deflection
+pub const STICK_RANGE: f32 = 0.5;
+
+/// Servo targets held for `ticks` macro ticks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroFrame {
+    pub positions: Vec<(ServoId, f32)>,
+    pub ticks: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovementMacro {
+    pub name: String,
+    pub tick_ms: u64,
+    pub frames: Vec<MacroFrame>,
+}
+
+impl MovementMacro {
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.iter().map(|frame| frame.ticks as u64 * self.tick_ms).sum()
+    }
+
+    /// One `ServoTargets` command per frame, offset from the macro start
+    pub fn to_commands(&self) -> Vec<MovementCommand> {
+        let mut start_ms = 0;
+        self.frames.iter().map(|frame| {
+            let duration_ms = frame.ticks as u64 * self.tick_ms;
+            let command = MovementCommand::ServoTargets { start_ms, duration_ms, positions: frame.positions.clone(), easing: Easing::Linear };
+            start_ms += duration_ms;
+            command
+        }).collect()
+    }
+
+    /// Play the macro back frame by frame, audited as a macro
+    pub async fn play<S: ServoControl + Send + Sync + 'static>(&self, movement: &TARSMovementController<S>) -> Result<(), String> {
+        for command in self.to_commands() {
+            movement.execute_command_from(command, MovementSource::Macro).await?;
+        }
+        Ok(())
+    }
+
+    /// Write the macro to `<dir>/<name>.json`
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = named_json_path(dir, &self.name)?;
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid macro {}: {}", path.display(), e))
+    }
+}
+
+/// `<dir>/<name>.json`, with characters that are unsafe in file names
+/// replaced by `_`
+pub fn named_json_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let file_name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if file_name.is_empty() {
+        return Err("Name is empty".to_string());
+    }
+    Ok(dir.join(format!("{}.json", file_name)))
+}
+
+/// Collects one frame per tick, folding repeats of the previous frame into it
+#[derive(Debug, Clone, Default)]
+pub struct MacroRecorder {
+    frames: Vec<MacroFrame>,
+}
+
+impl MacroRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one tick. Returns true when the positions differ from the
+    /// previous frame, i.e. when the servos need to move.
+    pub fn record(&mut self, positions: Vec<(ServoId, f32)>) -> bool {
+        match self.frames.last_mut() {
+            Some(last) if last.positions == positions => {
+                last.ticks += 1;
+                false
+            }
+            _ => {
+                self.frames.push(MacroFrame { positions, ticks: 1 });
+                true
+            }
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn finish(self, name: &str) -> MovementMacro {
+        MovementMacro { name: name.to_string(), tick_ms: MACRO_TICK_MS, frames: self.frames }
+    }
+}
+
+/// Analog stick deflection, -1.0 to 1.0 per axis
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StickPositions {
+    pub left_x: f32,
+    pub left_y: f32,
+    pub right_x: f32,
+    pub right_y: f32,
+}
+
+impl StickPositions {
+    /// Servo targets for the sticks: left stick leans and swings the legs,
+    /// right stick turns the head and swings the arms. Deflection inside
+    /// `deadzone` counts as centered, and targets are rounded to 0.01 so
+    /// stick noise doesn't produce new frames.
+    pub fn servo_targets(&self, deadzone: f32) -> Vec<(ServoId, f32)> {
+        let scale = |value: f32| {
+            let value = if value.abs() < deadzone { 0.0 } else { value.clamp(-1.0, 1.0) };
+            (value * STICK_RANGE * 100.0).round() / 100.0
+        };
+        let (legs, lean) = (scale(self.left_y), scale(self.left_x));
+        let (head, arms) = (scale(self.right_x), scale(self.right_y));
+        vec![
+            (ServoId::RightHipForwardBack, legs),
+            (ServoId::LeftHipForwardBack, legs),
+            (ServoId::RightHipUpDown, lean),
+            (ServoId::LeftHipUpDown, -lean),
+            (ServoId::RightShoulderForwardBack, arms),
+            (ServoId::LeftShoulderForwardBack, arms),
+            (ServoId::Head, head),
+        ]
+    }
+}
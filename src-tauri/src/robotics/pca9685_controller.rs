@@ -1,12 +1,16 @@
 //! PCA9685 PWM servo controller implementation.
 
 use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use log::{debug, error, info, warn};
 
 use super::hardware_interface::{CommunicationBus, ServoControl};
-use super::servo_config::{ServoId, TARSServoConfig};
+use super::servo_config::{Calibration, ServoId, TARSServoConfig};
+use crate::config::config::I2cBusConfig;
 
 /// PCA9685 register addresses
 const PCA9685_MODE1: u8 = 0x00;
@@ -18,6 +22,21 @@ const PCA9685_LED0_ON_L: u8 = 0x06;
 const PCA9685_INTERNAL_FREQ: f32 = 25000000.0;
 const PCA9685_DEFAULT_ADDRESS: u8 = 0x40;
 
+/// Without a bus backend there is nothing for the servo system to drive
+#[cfg(not(any(test, feature = "mock-i2c", feature = "hardware")))]
+compile_error!("enable the `mock-i2c` feature, the `hardware` feature, or both");
+
+/// PWM frequencies the prescaler can produce (prescale 255 down to 3)
+pub const PCA9685_MIN_FREQUENCY: f32 = 24.0;
+pub const PCA9685_MAX_FREQUENCY: f32 = 1526.0;
+
+/// Delay between writes when ramping a servo towards its setpoint
+const RAMP_TICK_MS: u64 = 20;
+
+/// Retries of a failed channel write before the channel is faulted
+pub const I2C_WRITE_RETRIES: u32 = 3;
+const I2C_RETRY_DELAY_MS: u64 = 5;
+
 /// Error types for PCA9685 operations
 #[derive(Debug, thiserror::Error)]
 pub enum PCA9685Error {
@@ -29,6 +48,37 @@ pub enum PCA9685Error {
     InvalidPWM(u16),
     #[error("Hardware not available: {0}")]
     HardwareUnavailable(String),
+    #[error("Servo channel {0} is faulted")]
+    ChannelFaulted(u8),
+    #[error("PWM frequency {0} Hz outside the PCA9685's 24-1526 Hz range")]
+    InvalidFrequency(f32),
+}
+
+/// Prescale register value for a PWM frequency
+pub fn prescale_for(frequency: f32) -> Result<u8, PCA9685Error> {
+    if !(PCA9685_MIN_FREQUENCY..=PCA9685_MAX_FREQUENCY).contains(&frequency) {
+        return Err(PCA9685Error::InvalidFrequency(frequency));
+    }
+    let prescale = (PCA9685_INTERNAL_FREQ / (4096.0 * frequency)).round() - 1.0;
+    Ok(prescale.clamp(3.0, 255.0) as u8)
+}
+
+/// First of the four ON_L/ON_H/OFF_L/OFF_H registers of a PWM channel
+pub fn channel_register(channel: u8) -> u8 {
+    PCA9685_LED0_ON_L + 4 * channel
+}
+
+/// Sent when a channel is faulted after its writes kept failing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServoFault {
+    pub channel: u8,
+    pub error: String,
+}
+
+static SERVO_FAULTS: Lazy<broadcast::Sender<ServoFault>> = Lazy::new(|| broadcast::channel(16).0);
+
+pub fn subscribe_servo_faults() -> broadcast::Receiver<ServoFault> {
+    SERVO_FAULTS.subscribe()
 }
 
 /// Hardware abstraction for I2C communication
@@ -85,19 +135,95 @@ impl I2CInterface for HardwareI2C {
     }
 }
 
-/// Mock I2C implementation for testing without hardware
+/// Linux I2C character device (e.g. `/dev/i2c-1`) through linux-embedded-hal
+#[cfg(feature = "hardware")]
+pub struct LinuxI2C {
+    device: Arc<Mutex<linux_embedded_hal::I2cdev>>,
+    address: u8,
+}
+
+#[cfg(feature = "hardware")]
+impl LinuxI2C {
+    pub fn open(path: &str, address: u8) -> Result<Self, PCA9685Error> {
+        let device = linux_embedded_hal::I2cdev::new(path)
+            .map_err(|e| PCA9685Error::HardwareUnavailable(format!("Failed to open I2C bus {}: {}", path, e)))?;
+        Ok(Self { device: Arc::new(Mutex::new(device)), address })
+    }
+}
+
+#[cfg(feature = "hardware")]
+#[async_trait]
+impl I2CInterface for LinuxI2C {
+    async fn write_byte(&self, register: u8, value: u8) -> Result<(), String> {
+        use embedded_hal::i2c::I2c;
+        let mut device = self.device.lock().await;
+        device.write(self.address, &[register, value])
+            .map_err(|e| format!("I2C write error at 0x{:02X}: {:?}", self.address, e))
+    }
+
+    async fn read_byte(&self, register: u8) -> Result<u8, String> {
+        use embedded_hal::i2c::I2c;
+        let mut device = self.device.lock().await;
+        let mut value = [0u8];
+        device.write_read(self.address, &[register], &mut value)
+            .map_err(|e| format!("I2C read error at 0x{:02X}: {:?}", self.address, e))?;
+        Ok(value[0])
+    }
+
+    async fn write_bytes(&self, register: u8, data: &[u8]) -> Result<(), String> {
+        use embedded_hal::i2c::I2c;
+        let mut device = self.device.lock().await;
+        let mut bytes = Vec::with_capacity(data.len() + 1);
+        bytes.push(register);
+        bytes.extend_from_slice(data);
+        device.write(self.address, &bytes)
+            .map_err(|e| format!("I2C write block error at 0x{:02X}: {:?}", self.address, e))
+    }
+}
+
+#[cfg(any(test, feature = "mock-i2c"))]
+type BlockWriteLog = Arc<Mutex<Vec<(u8, Vec<u8>)>>>;
+
+/// Mock I2C implementation for testing without hardware. Clones share the
+/// same register state, so a test can keep a handle after passing one in.
+/// Built with the `mock-i2c` feature, on by default for desktop builds.
+#[cfg(any(test, feature = "mock-i2c"))]
+#[derive(Clone)]
 pub struct MockI2C {
-    registers: Arc<Mutex<std::collections::HashMap<u8, u8>>>,
+    registers: Arc<Mutex<HashMap<u8, u8>>>,
+    block_writes: BlockWriteLog,
+    failing_writes: Arc<Mutex<u32>>,
 }
 
+#[cfg(any(test, feature = "mock-i2c"))]
 impl MockI2C {
     pub fn new() -> Self {
         Self {
-            registers: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            registers: Arc::new(Mutex::new(HashMap::new())),
+            block_writes: Arc::new(Mutex::new(Vec::new())),
+            failing_writes: Arc::new(Mutex::new(0)),
         }
     }
+
+    /// Every block write so far, as (register, data)
+    pub async fn block_writes(&self) -> Vec<(u8, Vec<u8>)> {
+        self.block_writes.lock().await.clone()
+    }
+
+    /// Fail the next `count` block writes; `u32::MAX` fails them all
+    pub async fn fail_next_writes(&self, count: u32) {
+        *self.failing_writes.lock().await = count;
+    }
 }
 
+#[cfg(any(test, feature = "mock-i2c"))]
+impl Default for MockI2C {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "mock-i2c"))]
 #[async_trait]
 impl I2CInterface for MockI2C {
     async fn write_byte(&self, register: u8, value: u8) -> Result<(), String> {
@@ -115,21 +241,127 @@ impl I2CInterface for MockI2C {
     }
     
     async fn write_bytes(&self, register: u8, data: &[u8]) -> Result<(), String> {
+        {
+            let mut failing = self.failing_writes.lock().await;
+            if *failing > 0 {
+                if *failing != u32::MAX {
+                    *failing -= 1;
+                }
+                return Err(format!("Mock I2C: write to register 0x{:02X} failed", register));
+            }
+        }
         let mut registers = self.registers.lock().await;
         for (i, &byte) in data.iter().enumerate() {
             registers.insert(register + i as u8, byte);
         }
+        self.block_writes.lock().await.push((register, data.to_vec()));
         debug!("Mock I2C: Write block register 0x{:02X}, {} bytes", register, data.len());
         Ok(())
     }
 }
 
+/// The bus the servo system drives: the mock in simulation, otherwise the
+/// configured Linux I2C device.
+pub enum ServoBus {
+    #[cfg(any(test, feature = "mock-i2c"))]
+    Mock(MockI2C),
+    #[cfg(feature = "hardware")]
+    Linux(LinuxI2C),
+}
+
+impl ServoBus {
+    /// Select the bus from config. A missing device path fails here, before
+    /// anything is written to the board.
+    pub fn from_config(config: &I2cBusConfig, simulation: bool) -> Result<Self, PCA9685Error> {
+        if simulation {
+            return Self::open_mock();
+        }
+        if !std::path::Path::new(&config.bus_path).exists() {
+            return Err(PCA9685Error::HardwareUnavailable(format!(
+                "I2C bus {} does not exist; check robotics.i2c.bus_path and that I2C is enabled",
+                config.bus_path
+            )));
+        }
+        Self::open_hardware(config)
+    }
+
+    #[cfg(any(test, feature = "mock-i2c"))]
+    fn open_mock() -> Result<Self, PCA9685Error> {
+        Ok(ServoBus::Mock(MockI2C::new()))
+    }
+
+    #[cfg(not(any(test, feature = "mock-i2c")))]
+    fn open_mock() -> Result<Self, PCA9685Error> {
+        Err(PCA9685Error::HardwareUnavailable(
+            "Cannot simulate the servo bus: built without the `mock-i2c` feature".to_string(),
+        ))
+    }
+
+    #[cfg(feature = "hardware")]
+    fn open_hardware(config: &I2cBusConfig) -> Result<Self, PCA9685Error> {
+        LinuxI2C::open(&config.bus_path, config.address).map(ServoBus::Linux)
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    fn open_hardware(config: &I2cBusConfig) -> Result<Self, PCA9685Error> {
+        Err(PCA9685Error::HardwareUnavailable(format!(
+            "Cannot open I2C bus {}: built without the `hardware` feature",
+            config.bus_path
+        )))
+    }
+
+    pub fn is_mock(&self) -> bool {
+        match self {
+            #[cfg(any(test, feature = "mock-i2c"))]
+            ServoBus::Mock(_) => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl I2CInterface for ServoBus {
+    async fn write_byte(&self, register: u8, value: u8) -> Result<(), String> {
+        match self {
+            #[cfg(any(test, feature = "mock-i2c"))]
+            ServoBus::Mock(i2c) => i2c.write_byte(register, value).await,
+            #[cfg(feature = "hardware")]
+            ServoBus::Linux(i2c) => i2c.write_byte(register, value).await,
+        }
+    }
+
+    async fn read_byte(&self, register: u8) -> Result<u8, String> {
+        match self {
+            #[cfg(any(test, feature = "mock-i2c"))]
+            ServoBus::Mock(i2c) => i2c.read_byte(register).await,
+            #[cfg(feature = "hardware")]
+            ServoBus::Linux(i2c) => i2c.read_byte(register).await,
+        }
+    }
+
+    async fn write_bytes(&self, register: u8, data: &[u8]) -> Result<(), String> {
+        match self {
+            #[cfg(any(test, feature = "mock-i2c"))]
+            ServoBus::Mock(i2c) => i2c.write_bytes(register, data).await,
+            #[cfg(feature = "hardware")]
+            ServoBus::Linux(i2c) => i2c.write_bytes(register, data).await,
+        }
+    }
+}
+
 /// PCA9685 PWM controller
 pub struct PCA9685Controller<I: I2CInterface> {
     i2c: Arc<I>,
     servo_config: TARSServoConfig,
-    frequency: f32,
+    /// Neutral offsets, changed in place by `set_calibration`
+    calibration: std::sync::Mutex<Calibration>,
+    frequency: std::sync::Mutex<f32>,
     initialized: Arc<Mutex<bool>>,
+    /// Last PWM value written per channel, used to rate-limit setpoint jumps
+    positions: Arc<Mutex<HashMap<u8, u16>>>,
+    /// Channels excluded from writes until `reset_fault`
+    faulted: Arc<Mutex<BTreeSet<u8>>>,
 }
 
 impl<I: I2CInterface> PCA9685Controller<I> {
@@ -137,11 +369,128 @@ impl<I: I2CInterface> PCA9685Controller<I> {
         Self {
             i2c: Arc::new(i2c),
             servo_config: TARSServoConfig::new(),
-            frequency,
+            calibration: std::sync::Mutex::new(TARSServoConfig::new().calibration()),
+            frequency: std::sync::Mutex::new(frequency),
             initialized: Arc::new(Mutex::new(false)),
+            positions: Arc::new(Mutex::new(HashMap::new())),
+            faulted: Arc::new(Mutex::new(BTreeSet::new())),
         }
     }
 
+    /// Use a custom servo configuration (PWM range and rate limits)
+    pub fn with_servo_config(mut self, servo_config: TARSServoConfig) -> Self {
+        self.calibration = std::sync::Mutex::new(servo_config.calibration());
+        self.servo_config = servo_config;
+        self
+    }
+
+    /// Neutral offsets currently applied
+    pub fn calibration(&self) -> Calibration {
+        self.calibration.lock().unwrap().clone()
+    }
+
+    /// Replace the neutral offsets of the listed servos that this build
+    /// has. Servos holding a position are rewritten with the new offsets;
+    /// nothing else changes, so a stopped robot stays stopped.
+    pub async fn set_calibration(&self, calibration: &Calibration) {
+        let mut servo_config = self.servo_config.clone();
+        servo_config.apply_calibration(&self.calibration());
+        servo_config.apply_calibration(calibration);
+        *self.calibration.lock().unwrap() = servo_config.calibration();
+        if !*self.initialized.lock().await {
+            return;
+        }
+
+        let positions: Vec<(u8, u16)> = self.positions.lock().await.iter().map(|(c, p)| (*c, *p)).collect();
+        for (channel, pwm) in positions {
+            if let Err(e) = self.set_pwm(channel, 0, self.calibrated_counts(channel, pwm)).await {
+                warn!("Failed to re-apply channel {} with its new offset: {}", channel, e);
+            }
+        }
+    }
+
+    pub fn servo_config(&self) -> &TARSServoConfig {
+        &self.servo_config
+    }
+
+    /// Ramp every servo to its neutral (`default_pwm`) position
+    pub async fn neutralize(&self) -> Result<(), String> {
+        for (servo, config) in self.servo_config.all_servos() {
+            self.set_position(*servo as u8, config.pwm_to_angle(config.default_pwm)).await?;
+        }
+        Ok(())
+    }
+
+    pub fn bus(&self) -> &I {
+        &self.i2c
+    }
+
+    /// Last PWM value written to a channel through `set_position`, in the
+    /// servo config's counts
+    pub async fn current_pwm(&self, channel: u8) -> Option<u16> {
+        self.positions.lock().await.get(&channel).copied()
+    }
+
+    pub fn get_frequency(&self) -> f32 {
+        *self.frequency.lock().unwrap()
+    }
+
+    /// Counts to write for `pwm`, a value in the servo config's counts, at
+    /// the current frequency. A pulse longer than the period is clamped to
+    /// a full duty cycle.
+    pub fn output_counts(&self, pwm: u16) -> u16 {
+        let scale = self.get_frequency() / self.servo_config.pwm_frequency_hz;
+        (pwm as f32 * scale).round().clamp(0.0, 4095.0) as u16
+    }
+
+    /// `output_counts` for `pwm` on a channel, shifted by its servo's
+    /// calibration offset
+    pub fn calibrated_counts(&self, channel: u8, pwm: u16) -> u16 {
+        let offset_us = ServoId::try_from(channel).ok()
+            .map_or(0, |servo| self.calibration.lock().unwrap().offset(servo));
+        let offset_counts = 4095.0 * offset_us as f32 * self.get_frequency() / 1_000_000.0;
+        (self.output_counts(pwm) as f32 + offset_counts).round().clamp(0.0, 4095.0) as u16
+    }
+
+    /// Change the PWM frequency, e.g. 50 Hz for analog or 330 Hz for
+    /// digital servos, recomputing and reapplying the prescale register.
+    /// Servos holding a position are rewritten so their pulse widths carry
+    /// over to the new period.
+    pub async fn set_pwm_frequency(&self, frequency: f32) -> Result<(), PCA9685Error> {
+        prescale_for(frequency)?;
+        let initialized = self.initialized.lock().await;
+        if *initialized {
+            self.write_prescale(frequency).await?;
+        }
+        *self.frequency.lock().unwrap() = frequency;
+        info!("PCA9685 PWM frequency set to {} Hz", frequency);
+        if !*initialized {
+            return Ok(());
+        }
+        drop(initialized);
+
+        let positions: Vec<(u8, u16)> = self.positions.lock().await.iter().map(|(c, p)| (*c, *p)).collect();
+        for (channel, pwm) in positions {
+            if let Err(e) = self.set_pwm(channel, 0, self.calibrated_counts(channel, pwm)).await {
+                warn!("Failed to re-apply channel {} at {} Hz: {}", channel, frequency, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn faulted_channels(&self) -> Vec<u8> {
+        self.faulted.lock().await.iter().copied().collect()
+    }
+
+    /// Put a faulted channel back in service. Returns false if it wasn't faulted.
+    pub async fn reset_fault(&self, channel: u8) -> bool {
+        let reset = self.faulted.lock().await.remove(&channel);
+        if reset {
+            info!("Servo channel {} fault cleared", channel);
+        }
+        reset
+    }
+
     /// Initialize the PCA9685 controller
     pub async fn initialize(&self) -> Result<(), PCA9685Error> {
         let mut initialized = self.initialized.lock().await;
@@ -149,7 +498,8 @@ impl<I: I2CInterface> PCA9685Controller<I> {
             return Ok(());
         }
 
-        info!("Initializing PCA9685 controller at {} Hz", self.frequency);
+        let frequency = self.get_frequency();
+        info!("Initializing PCA9685 controller at {} Hz", frequency);
 
         // Reset the device
         self.i2c.write_byte(PCA9685_MODE1, 0x80)
@@ -159,7 +509,7 @@ impl<I: I2CInterface> PCA9685Controller<I> {
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
 
         // Set up the frequency
-        self.set_pwm_frequency(self.frequency).await?;
+        self.write_prescale(frequency).await?;
 
         // Configure MODE1 register (auto-increment enabled)
         self.i2c.write_byte(PCA9685_MODE1, 0xA0)
@@ -176,10 +526,9 @@ impl<I: I2CInterface> PCA9685Controller<I> {
         Ok(())
     }
 
-    /// Set PWM frequency for the PCA9685
-    async fn set_pwm_frequency(&self, frequency: f32) -> Result<(), PCA9685Error> {
-        let prescale_val = (PCA9685_INTERNAL_FREQ / (4096.0 * frequency)).round() - 1.0;
-        let prescale = prescale_val.clamp(3.0, 255.0) as u8;
+    /// Write the prescale register for `frequency`
+    async fn write_prescale(&self, frequency: f32) -> Result<(), PCA9685Error> {
+        let prescale = prescale_for(frequency)?;
 
         debug!("Setting PWM frequency to {} Hz (prescale: {})", frequency, prescale);
 
@@ -229,7 +578,11 @@ impl<I: I2CInterface> PCA9685Controller<I> {
         }
         drop(initialized);
 
-        let register_base = PCA9685_LED0_ON_L + 4 * channel;
+        if self.faulted.lock().await.contains(&channel) {
+            return Err(PCA9685Error::ChannelFaulted(channel));
+        }
+
+        let register_base = channel_register(channel);
         let data = [
             (on & 0xFF) as u8,         // ON_L
             ((on >> 8) & 0xFF) as u8,  // ON_H
@@ -237,9 +590,16 @@ impl<I: I2CInterface> PCA9685Controller<I> {
             ((off >> 8) & 0xFF) as u8, // OFF_H
         ];
 
-        self.i2c.write_bytes(register_base, &data)
-            .await
-            .map_err(|e| PCA9685Error::I2CError(e))?;
+        // Retry transient bus errors before giving up on the write
+        let mut attempt = 0;
+        while let Err(e) = self.i2c.write_bytes(register_base, &data).await {
+            if attempt == I2C_WRITE_RETRIES {
+                return Err(PCA9685Error::I2CError(e));
+            }
+            attempt += 1;
+            warn!("I2C write to channel {} failed (attempt {}): {}", channel, attempt, e);
+            tokio::time::sleep(tokio::time::Duration::from_millis(I2C_RETRY_DELAY_MS)).await;
+        }
 
         debug!("Set PWM channel {} to ON:{} OFF:{}", channel, on, off);
         Ok(())
@@ -247,7 +607,7 @@ impl<I: I2CInterface> PCA9685Controller<I> {
 
     /// Convert pulse width in microseconds to PWM off value
     fn pulse_to_pwm(&self, pulse_us: u16) -> u16 {
-        let period_us = 1_000_000.0 / self.frequency;
+        let period_us = 1_000_000.0 / self.get_frequency();
         let pulse_length = 4095.0 * (pulse_us as f32 / period_us);
         pulse_length.round().clamp(0.0, 4095.0) as u16
     }
@@ -258,16 +618,51 @@ impl<I: I2CInterface> ServoControl for PCA9685Controller<I> {
     async fn set_position(&self, id: u8, position: f32) -> Result<(), String> {
         // Convert servo ID to channel
         let servo_id = ServoId::try_from(id).map_err(|e| format!("Invalid servo ID: {}", e))?;
-        let config = self.servo_config.get_config(servo_id)
-            .ok_or_else(|| format!("No configuration found for servo {:?}", servo_id))?;
+        // Servos the active profile doesn't have are simply not fitted
+        let config = match self.servo_config.get_config(servo_id) {
+            Some(config) => config,
+            None => {
+                debug!("Servo {:?} is not part of this build, skipping", servo_id);
+                return Ok(());
+            }
+        };
 
         // Convert position (-1.0 to 1.0) to PWM value
         let pwm_value = config.angle_to_pwm(position);
-        
-        // Set PWM (on=0, off=pwm_value for standard servo control)
-        self.set_pwm(id, 0, pwm_value)
-            .await
-            .map_err(|e| format!("Failed to set PWM: {}", e))?;
+
+        // Ramp from the last written value within the servo's rate limits. The
+        // first write to a channel has no known starting point and goes direct.
+        let last_pwm = self.positions.lock().await.get(&id).copied();
+        let mut ramp = match last_pwm {
+            Some(from) => config.limits.ramp(from, pwm_value),
+            None => Vec::new(),
+        };
+        if ramp.is_empty() {
+            ramp.push(pwm_value);
+        }
+
+        for (i, pwm) in ramp.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(tokio::time::Duration::from_millis(RAMP_TICK_MS)).await;
+            }
+            // Set PWM (on=0, off=pwm for standard servo control). A faulted
+            // channel is skipped so the other servos keep moving.
+            match self.set_pwm(id, 0, self.calibrated_counts(id, *pwm)).await {
+                Ok(()) => {},
+                Err(PCA9685Error::ChannelFaulted(_)) => {
+                    debug!("Skipping faulted servo {} ({})", id, config.name);
+                    return Ok(());
+                },
+                Err(PCA9685Error::I2CError(e)) => {
+                    error!("Servo {} ({}) faulted after {} retries: {}", id, config.name, I2C_WRITE_RETRIES, e);
+                    self.faulted.lock().await.insert(id);
+                    let _ = SERVO_FAULTS.send(ServoFault { channel: id, error: e });
+                    return Ok(());
+                },
+                Err(e) => return Err(format!("Failed to set PWM: {}", e)),
+            }
+            self.positions.lock().await.insert(id, *pwm);
+        }
 
         debug!("Set servo {} ({}) to position {} (PWM: {})", 
                id, config.name, position, pwm_value);
@@ -289,6 +684,7 @@ impl<I: I2CInterface> ServoControl for PCA9685Controller<I> {
     }
 }
 
+#[cfg(any(test, feature = "mock-i2c"))]
 impl PCA9685Controller<MockI2C> {
     /// Create a mock controller for testing
     pub fn mock(frequency: f32) -> Self {
@@ -313,6 +709,7 @@ impl PCA9685Controller<HardwareI2C> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::robotics::servo_config::MotionLimits;
     use tokio_test;
 
     #[tokio::test]
@@ -348,6 +745,137 @@ mod tests {
         assert!(result.unwrap_err().contains("Invalid servo ID"));
     }
 
+    #[tokio::test]
+    async fn test_large_jump_is_ramped() {
+        let i2c = MockI2C::new();
+        let mut servo_config = TARSServoConfig::new();
+        servo_config.set_limits(ServoId::Head, MotionLimits::new(40, 10));
+        let controller = PCA9685Controller::new(i2c.clone(), 50.0).with_servo_config(servo_config);
+        controller.initialize().await.unwrap();
+
+        let head = ServoId::Head as u8;
+        controller.set_position(head, -1.0).await.unwrap();
+        let before = i2c.block_writes().await.len();
+        controller.set_position(head, 1.0).await.unwrap();
+
+        let register = channel_register(head);
+        let offs: Vec<u16> = i2c.block_writes().await[before..]
+            .iter()
+            .filter(|(reg, _)| *reg == register)
+            .map(|(_, data)| u16::from_le_bytes([data[2], data[3]]))
+            .collect();
+
+        assert!(offs.len() > 1, "jump should be split into several writes");
+        assert_eq!(*offs.last().unwrap(), 600);
+        let mut previous = 150u16;
+        for off in offs {
+            assert!(off.abs_diff(previous) <= 40);
+            previous = off;
+        }
+        assert_eq!(controller.current_pwm(head).await, Some(600));
+    }
+
+    #[tokio::test]
+    async fn test_write_failures_retry_then_fault_the_channel() {
+        let i2c = MockI2C::new();
+        let controller = PCA9685Controller::new(i2c.clone(), 50.0);
+        controller.initialize().await.unwrap();
+        let (head, knee) = (ServoId::Head as u8, ServoId::RightKnee as u8);
+
+        // transient: two failures are retried and the move goes through
+        i2c.fail_next_writes(2).await;
+        controller.set_position(head, -1.0).await.unwrap();
+        assert_eq!(controller.current_pwm(head).await, Some(150));
+        assert!(controller.faulted_channels().await.is_empty());
+
+        // persistent: the channel is faulted and reported, movement carries on
+        let mut faults = subscribe_servo_faults();
+        i2c.fail_next_writes(u32::MAX).await;
+        controller.set_position(knee, 0.0).await.unwrap();
+        assert_eq!(controller.faulted_channels().await, vec![knee]);
+        assert_eq!(faults.try_recv().unwrap().channel, knee);
+        assert_eq!(controller.current_pwm(knee).await, None);
+
+        i2c.fail_next_writes(0).await;
+        let before = i2c.block_writes().await.len();
+        controller.set_position(knee, 0.5).await.unwrap();
+        assert_eq!(i2c.block_writes().await.len(), before, "faulted channel is not written");
+        controller.set_position(head, -0.9).await.unwrap();
+        assert!(i2c.block_writes().await.len() > before);
+
+        assert!(controller.reset_fault(knee).await);
+        controller.set_position(knee, 0.5).await.unwrap();
+        assert!(controller.current_pwm(knee).await.is_some());
+        assert!(!controller.reset_fault(knee).await);
+    }
+
+    #[tokio::test]
+    async fn test_frequency_change_recomputes_prescale_and_rejects_out_of_range() {
+        let i2c = MockI2C::new();
+        let controller = PCA9685Controller::new(i2c.clone(), 50.0);
+        controller.initialize().await.unwrap();
+        assert_eq!(i2c.read_byte(PCA9685_PRESCALE).await.unwrap(), 121);
+        let (head, knee) = (ServoId::Head as u8, ServoId::RightKnee as u8);
+        controller.set_position(head, 0.0).await.unwrap();
+        controller.set_position(knee, 1.0).await.unwrap();
+        let off_counts = |data: &[u8]| u16::from_le_bytes([data[2], data[3]]);
+        let last_off = |writes: &[(u8, Vec<u8>)], channel| {
+            writes.iter().rev().find(|(reg, _)| *reg == channel_register(channel)).map(|(_, data)| off_counts(data))
+        };
+
+        // 25 MHz / (4096 * 330 Hz) = 18.5 -> 18 - 1
+        controller.set_pwm_frequency(330.0).await.unwrap();
+        assert_eq!(i2c.read_byte(PCA9685_PRESCALE).await.unwrap(), 17);
+        assert_eq!(controller.get_frequency(), 330.0);
+        // active servos keep their pulse width: 375 and 600 counts at 50 Hz
+        let writes = i2c.block_writes().await;
+        assert_eq!(last_off(&writes, head), Some(2475));
+        assert_eq!(last_off(&writes, knee), Some(3960));
+        assert_eq!(controller.current_pwm(head).await, Some(375));
+
+        // the knee's 2.9 ms pulse is longer than the 2.5 ms period at 400 Hz,
+        // so it is clamped to a full duty cycle
+        controller.set_pwm_frequency(400.0).await.unwrap();
+        let writes = i2c.block_writes().await;
+        assert_eq!(last_off(&writes, head), Some(3000));
+        assert_eq!(last_off(&writes, knee), Some(4095));
+        controller.set_pwm_frequency(330.0).await.unwrap();
+
+        let result = controller.set_pwm_frequency(5000.0).await;
+        assert!(matches!(result, Err(PCA9685Error::InvalidFrequency(hz)) if hz == 5000.0));
+        assert_eq!(i2c.read_byte(PCA9685_PRESCALE).await.unwrap(), 17);
+        assert_eq!(controller.get_frequency(), 330.0);
+    }
+
+    #[tokio::test]
+    async fn test_register_math_matches_datasheet_values() {
+        // prescale = round(25 MHz / (4096 * frequency)) - 1; 200 Hz is the
+        // datasheet's worked example and 1526 Hz its fastest setting
+        assert_eq!(prescale_for(200.0).unwrap(), 0x1E);
+        assert_eq!(prescale_for(50.0).unwrap(), 0x79);
+        assert_eq!(prescale_for(60.0).unwrap(), 0x65);
+        assert_eq!(prescale_for(PCA9685_MAX_FREQUENCY).unwrap(), 0x03);
+        assert!(prescale_for(23.0).is_err());
+
+        // LEDn_ON_L = 0x06 + 4n, from LED0 to LED15
+        assert_eq!(channel_register(0), 0x06);
+        assert_eq!(channel_register(1), 0x0A);
+        assert_eq!(channel_register(15), 0x42);
+
+        let i2c = MockI2C::new();
+        let controller = PCA9685Controller::new(i2c.clone(), 200.0);
+        controller.initialize().await.unwrap();
+        assert_eq!(i2c.read_byte(PCA9685_PRESCALE).await.unwrap(), 0x1E);
+        assert_eq!(i2c.read_byte(PCA9685_MODE1).await.unwrap() & 0x20, 0x20, "auto-increment is on");
+        assert_eq!(i2c.read_byte(PCA9685_MODE2).await.unwrap(), 0x04);
+
+        // OFF count 0x133 on LED15 lands in LED15_OFF_L (0x44) and LED15_OFF_H (0x45)
+        controller.set_pwm(15, 0, 0x133).await.unwrap();
+        assert_eq!(i2c.block_writes().await.last().unwrap(), &(0x42, vec![0x00, 0x00, 0x33, 0x01]));
+        assert_eq!(i2c.read_byte(0x44).await.unwrap(), 0x33);
+        assert_eq!(i2c.read_byte(0x45).await.unwrap(), 0x01);
+    }
+
     #[tokio::test]
     async fn test_pulse_to_pwm_conversion() {
         let controller = PCA9685Controller::mock(50.0);
@@ -357,4 +885,39 @@ mod tests {
         let pwm = controller.pulse_to_pwm(1500);
         assert!((pwm as f32 - 307.2).abs() < 5.0); // Allow some tolerance
     }
+
+    #[tokio::test]
+    async fn test_calibration_offset_shifts_the_written_pulse_counts() {
+        let i2c = MockI2C::new();
+        let mut servo_config = TARSServoConfig::new();
+        // 48 us at 50 Hz is 48 / 20000 of the 4095-count period, 9.8 counts
+        servo_config.set_calibration_offset(ServoId::Head, 48);
+        servo_config.set_calibration_offset(ServoId::RightKnee, -48);
+        let controller = PCA9685Controller::new(i2c.clone(), 50.0).with_servo_config(servo_config);
+        controller.initialize().await.unwrap();
+
+        let (head, knee, hip) = (ServoId::Head as u8, ServoId::RightKnee as u8, ServoId::RightHipUpDown as u8);
+        assert_eq!(controller.calibrated_counts(head, 375), 385);
+        assert_eq!(controller.calibrated_counts(knee, 375), 365);
+        assert_eq!(controller.calibrated_counts(hip, 375), 375);
+
+        for channel in [head, knee, hip] {
+            controller.set_position(channel, 0.0).await.unwrap();
+        }
+        async fn written(i2c: &MockI2C, channel: u8) -> u16 {
+            let register = channel_register(channel);
+            let writes = i2c.block_writes().await;
+            let (_, data) = writes.iter().rev().find(|(reg, _)| *reg == register).unwrap();
+            u16::from_le_bytes([data[2], data[3]])
+        }
+        assert_eq!(written(&i2c, head).await, 385);
+        assert_eq!(written(&i2c, knee).await, 365);
+        assert_eq!(written(&i2c, hip).await, 375);
+        // The logical position is unchanged; only the pulse is trimmed
+        assert_eq!(controller.current_pwm(head).await, Some(375));
+
+        // At 100 Hz the same pulse width is twice as many counts
+        controller.set_pwm_frequency(100.0).await.unwrap();
+        assert_eq!(written(&i2c, head).await, 770);
+    }
 }
@@ -0,0 +1,317 @@
+//! User-defined poses loaded from `poses.toml` / `poses.json` and merged with
+//! the built-in `TARSPoses`.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::servo_config::{MovementPose, ServoId, TARSPoses};
+
+/// Logical servo range accepted in pose files.
+const POSITION_MIN: f32 = -1.0;
+const POSITION_MAX: f32 = 1.0;
+const DEFAULT_POSE_DURATION_MS: u64 = 1000;
+
+pub type SharedPoseLibrary = Arc<RwLock<PoseLibrary>>;
+
+/// Error types for pose file loading
+#[derive(Debug, thiserror::Error)]
+pub enum PoseConfigError {
+    #[error("Failed to read pose file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse pose file: {0}")]
+    Parse(String),
+    #[error("Pose '{pose}': unknown servo '{servo}'")]
+    UnknownServo { pose: String, servo: String },
+    #[error("Pose '{pose}': servo {servo:?} value {value} is out of range [{min}, {max}]")]
+    OutOfRange { pose: String, servo: ServoId, value: f32, min: f32, max: f32 },
+    #[error("Pose '{pose}': {reason}")]
+    Invalid { pose: String, reason: String },
+}
+
+/// Pose file layout. Servos are keyed by their `ServoId` name:
+///
+/// ```toml
+/// [[poses]]
+/// name = "Wave"
+/// duration_ms = 600
+/// [poses.positions]
+/// RightShoulderForwardBack = 0.8
+/// Head = 0.2
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoseFile {
+    #[serde(default)]
+    pub poses: Vec<PoseDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoseDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    pub positions: BTreeMap<String, f32>,
+}
+
+impl PoseDefinition {
+    /// Check the definition against the pose schema and build the pose.
+    pub fn validate(&self) -> Result<MovementPose, PoseConfigError> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(PoseConfigError::Invalid {
+                pose: self.name.clone(),
+                reason: "pose name must not be empty".to_string(),
+            });
+        }
+        if self.positions.is_empty() {
+            return Err(PoseConfigError::Invalid {
+                pose: name.to_string(),
+                reason: "pose must set at least one servo".to_string(),
+            });
+        }
+        if self.duration_ms == Some(0) {
+            return Err(PoseConfigError::Invalid {
+                pose: name.to_string(),
+                reason: "duration_ms must be greater than zero".to_string(),
+            });
+        }
+
+        let mut positions = Vec::with_capacity(self.positions.len());
+        for (servo_name, value) in &self.positions {
+            let servo = ServoId::from_name(servo_name).ok_or_else(|| PoseConfigError::UnknownServo {
+                pose: name.to_string(),
+                servo: servo_name.clone(),
+            })?;
+            if !value.is_finite() || *value < POSITION_MIN || *value > POSITION_MAX {
+                return Err(PoseConfigError::OutOfRange {
+                    pose: name.to_string(),
+                    servo,
+                    value: *value,
+                    min: POSITION_MIN,
+                    max: POSITION_MAX,
+                });
+            }
+            positions.push((servo, *value));
+        }
+        positions.sort_by_key(|(servo, _)| *servo as u8);
+
+        Ok(MovementPose::new(name, positions, self.duration_ms.unwrap_or(DEFAULT_POSE_DURATION_MS)))
+    }
+}
+
+/// JSON Schema describing `PoseFile`, for editors and the dashboard.
+pub fn pose_file_schema() -> serde_json::Value {
+    let servo_names: Vec<&str> = ServoId::all().iter().map(|s| s.name()).collect();
+    let servo_properties: serde_json::Map<String, serde_json::Value> = servo_names
+        .iter()
+        .map(|name| {
+            (
+                name.to_string(),
+                serde_json::json!({ "type": "number", "minimum": POSITION_MIN, "maximum": POSITION_MAX }),
+            )
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "TARS pose file",
+        "type": "object",
+        "properties": {
+            "poses": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "positions"],
+                    "properties": {
+                        "name": { "type": "string", "minLength": 1 },
+                        "duration_ms": { "type": "integer", "minimum": 1 },
+                        "positions": {
+                            "type": "object",
+                            "minProperties": 1,
+                            "properties": servo_properties,
+                            "additionalProperties": false
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Result of loading a pose file: accepted poses and per-pose rejections.
+#[derive(Debug, Default, Serialize)]
+pub struct PoseLoadReport {
+    pub loaded: Vec<String>,
+    pub rejected: Vec<String>,
+}
+
+/// Built-in poses plus any user poses, looked up by name.
+#[derive(Debug, Clone)]
+pub struct PoseLibrary {
+    poses: Vec<MovementPose>,
+}
+
+impl PoseLibrary {
+    pub fn builtin() -> Self {
+        Self { poses: TARSPoses::all_poses() }
+    }
+
+    /// Find a pose ignoring case and treating `_` as a space.
+    pub fn get(&self, name: &str) -> Option<&MovementPose> {
+        let key = normalize_name(name);
+        self.poses.iter().find(|pose| normalize_name(&pose.name) == key)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.poses.iter().map(|pose| pose.name.clone()).collect()
+    }
+
+    pub fn all(&self) -> &[MovementPose] {
+        &self.poses
+    }
+
+    /// Add a pose, replacing any existing pose with the same name.
+    pub fn insert(&mut self, pose: MovementPose) {
+        let key = normalize_name(&pose.name);
+        match self.poses.iter_mut().find(|p| normalize_name(&p.name) == key) {
+            Some(existing) => *existing = pose,
+            None => self.poses.push(pose),
+        }
+    }
+
+    /// Validate user definitions and merge the valid ones over the built-ins.
+    pub fn merge_definitions(&mut self, file: &PoseFile) -> PoseLoadReport {
+        let mut report = PoseLoadReport::default();
+        for definition in &file.poses {
+            match definition.validate() {
+                Ok(pose) => {
+                    report.loaded.push(pose.name.clone());
+                    self.insert(pose);
+                }
+                Err(e) => {
+                    warn!("Rejected pose: {}", e);
+                    report.rejected.push(e.to_string());
+                }
+            }
+        }
+        report
+    }
+
+    /// Rebuild the library from the built-ins and the pose file at `path`.
+    /// A missing file is not an error; the built-ins are used alone.
+    pub fn load(path: &Path) -> Result<(Self, PoseLoadReport), PoseConfigError> {
+        let mut library = Self::builtin();
+        if !path.exists() {
+            return Ok((library, PoseLoadReport::default()));
+        }
+
+        let file = parse_pose_file(path)?;
+        let report = library.merge_definitions(&file);
+        info!(
+            "Loaded {} user poses from {:?} ({} rejected)",
+            report.loaded.len(),
+            path,
+            report.rejected.len()
+        );
+        Ok((library, report))
+    }
+}
+
+impl Default for PoseLibrary {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Parse a pose file as JSON or TOML depending on its extension.
+pub fn parse_pose_file(path: &Path) -> Result<PoseFile, PoseConfigError> {
+    let content = std::fs::read_to_string(path)?;
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        serde_json::from_str(&content).map_err(|e| PoseConfigError::Parse(e.to_string()))
+    } else {
+        toml::from_str(&content).map_err(|e| PoseConfigError::Parse(e.to_string()))
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(name: &str, positions: &[(&str, f32)]) -> PoseDefinition {
+        PoseDefinition {
+            name: name.to_string(),
+            duration_ms: Some(500),
+            positions: positions.iter().map(|(s, v)| (s.to_string(), *v)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_value_is_rejected() {
+        let err = definition("Wave", &[("RightShoulderForwardBack", 1.5)]).validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Pose 'Wave': servo RightShoulderForwardBack value 1.5 is out of range [-1, 1]"
+        );
+    }
+
+    #[test]
+    fn test_unknown_servo_is_rejected() {
+        let err = definition("Wave", &[("Tail", 0.2)]).validate().unwrap_err();
+        assert!(err.to_string().contains("unknown servo 'Tail'"));
+    }
+
+    #[test]
+    fn test_user_pose_overrides_builtin() {
+        let mut library = PoseLibrary::builtin();
+        let builtin_count = library.all().len();
+        let file = PoseFile {
+            poses: vec![
+                definition("neutral", &[("Head", 0.5)]),
+                definition("Wave", &[("RightShoulderForwardBack", 0.8)]),
+                definition("Broken", &[("Head", -3.0)]),
+            ],
+        };
+
+        let report = library.merge_definitions(&file);
+        assert_eq!(report.loaded, vec!["neutral", "Wave"]);
+        assert_eq!(report.rejected.len(), 1);
+        assert!(report.rejected[0].contains("Head"));
+
+        assert_eq!(library.all().len(), builtin_count + 1);
+        assert_eq!(library.get("Neutral").unwrap().positions, vec![(ServoId::Head, 0.5)]);
+        assert!(library.get("broken").is_none());
+    }
+
+    #[test]
+    fn test_load_toml_pose_file() {
+        let scratch = tempfile::tempdir().unwrap();
+        let path = scratch.path().join("poses.toml");
+        std::fs::write(
+            &path,
+            "[[poses]]\nname = \"Salute\"\nduration_ms = 700\n[poses.positions]\nRightShoulderForwardBack = 0.9\nHead = 0.1\n",
+        )
+        .unwrap();
+
+        let (library, report) = PoseLibrary::load(&path).unwrap();
+
+        assert_eq!(report.loaded, vec!["Salute"]);
+        let pose = library.get("salute").unwrap();
+        assert_eq!(pose.duration_ms, 700);
+        assert_eq!(pose.positions.len(), 2);
+    }
+
+    #[test]
+    fn test_schema_lists_every_servo() {
+        let schema = pose_file_schema();
+        let servos = &schema["properties"]["poses"]["items"]["properties"]["positions"]["properties"];
+        assert_eq!(servos.as_object().unwrap().len(), ServoId::all().len());
+    }
+}
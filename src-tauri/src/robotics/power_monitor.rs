@@ -0,0 +1,364 @@
+//! Battery monitoring: slows movement when the battery runs low and brings
+//! TARS to a controlled stop before it runs flat.
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio::time::{sleep, Duration};
+
+use super::hardware_interface::{PowerReading, PowerSource};
+use super::servo_system::SharedServoSystem;
+use super::telemetry::Telemetry;
+use crate::config::config::SafetyConfig;
+use crate::safety::SharedSafety;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerLevel {
+    Normal,
+    Low,
+    Critical,
+}
+
+/// Sent whenever the battery falls into a lower level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowerWarning {
+    pub level: PowerLevel,
+    pub reading: PowerReading,
+    /// What TARS says about it
+    pub message: String,
+}
+
+static POWER_WARNINGS: Lazy<broadcast::Sender<PowerWarning>> = Lazy::new(|| broadcast::channel(16).0);
+
+static LATEST_READING: Lazy<RwLock<Option<PowerReading>>> = Lazy::new(|| RwLock::new(None));
+
+pub fn subscribe_power_warnings() -> broadcast::Receiver<PowerWarning> {
+    POWER_WARNINGS.subscribe()
+}
+
+/// The most recent battery reading from any running monitor
+pub async fn latest_power_reading() -> Option<PowerReading> {
+    *LATEST_READING.read().await
+}
+
+/// Polls a power source and applies the low-battery policy through the
+/// movement controller and `Safety`.
+pub struct PowerMonitor<P: PowerSource + Send + Sync> {
+    source: P,
+    thresholds: SafetyConfig,
+    safety: SharedSafety,
+    servo_system: SharedServoSystem,
+    telemetry: Option<Arc<Telemetry>>,
+    level: Mutex<PowerLevel>,
+    /// Movement speed to restore once the battery recovers
+    normal_speed: Mutex<Option<f32>>,
+    /// Reason of the controlled stop requested at critical, lifted again
+    /// once the battery recovers
+    battery_stop: Mutex<Option<String>>,
+}
+
+impl<P: PowerSource + Send + Sync + 'static> PowerMonitor<P> {
+    pub fn new(source: P, thresholds: SafetyConfig, safety: SharedSafety, servo_system: SharedServoSystem) -> Self {
+        Self {
+            source,
+            thresholds,
+            safety,
+            servo_system,
+            telemetry: None,
+            level: Mutex::new(PowerLevel::Normal),
+            normal_speed: Mutex::new(None),
+            battery_stop: Mutex::new(None),
+        }
+    }
+
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    pub fn level_for(&self, percent: f32) -> PowerLevel {
+        if percent < self.thresholds.critical_battery_percent {
+            PowerLevel::Critical
+        } else if percent < self.thresholds.low_battery_percent {
+            PowerLevel::Low
+        } else {
+            PowerLevel::Normal
+        }
+    }
+
+    /// Read the power source once. Returns the warning if the battery fell
+    /// into a lower level.
+    pub async fn check_once(&self) -> Option<PowerWarning> {
+        let reading = match self.source.read_power().await {
+            Ok(reading) => reading,
+            Err(e) => {
+                debug!("Power source read failed: {}", e);
+                return None;
+            }
+        };
+        *LATEST_READING.write().await = Some(reading);
+        if let Some(telemetry) = &self.telemetry {
+            if let Ok(json) = serde_json::to_string(&serde_json::json!({ "power": reading })) {
+                telemetry.broadcast(json).await;
+            }
+        }
+
+        let level = self.level_for(reading.percent);
+        let previous = std::mem::replace(&mut *self.level.lock().await, level);
+        if level == previous {
+            return None;
+        }
+        self.apply_speed(level).await;
+        if level < previous {
+            info!("Battery recovered to {:.0}%", reading.percent);
+            // Only lift the stop if it is still ours: it may have been
+            // cleared by hand or replaced by another stop since
+            if let Some(reason) = self.battery_stop.lock().await.take() {
+                self.safety.clear_controlled_stop_if(&reason).await;
+            }
+            return None;
+        }
+
+        let message = match level {
+            PowerLevel::Critical => {
+                let reason = format!("battery critical at {:.0}%", reading.percent);
+                *self.battery_stop.lock().await = Some(reason.clone());
+                self.safety.request_controlled_stop(reason).await;
+                format!(
+                    "Battery at {:.0} percent. I'm stopping now and will need to shut down soon. Plug me in, Cooper.",
+                    reading.percent
+                )
+            }
+            _ => format!(
+                "Battery at {:.0} percent. I'll be taking it slow until someone finds a charger.",
+                reading.percent
+            ),
+        };
+        warn!("Power {:?}: {:.0}% ({:.2} V)", level, reading.percent, reading.voltage);
+        let warning = PowerWarning { level, reading, message };
+        if level == PowerLevel::Critical {
+            if let Some(telemetry) = &self.telemetry {
+                telemetry.broadcast_critical(warning.message.clone()).await;
+            }
+        }
+        let _ = POWER_WARNINGS.send(warning.clone());
+        Some(warning)
+    }
+
+    /// Slow movement below the low threshold and restore it on recovery
+    async fn apply_speed(&self, level: PowerLevel) {
+        let movement = match self.servo_system.read().await.movement_controller() {
+            Some(movement) => movement,
+            None => return,
+        };
+        let mut normal_speed = self.normal_speed.lock().await;
+        match level {
+            PowerLevel::Normal => {
+                if let Some(speed) = normal_speed.take() {
+                    movement.set_movement_speed(speed).await;
+                }
+            }
+            _ if normal_speed.is_none() => {
+                let speed = movement.movement_speed().await;
+                *normal_speed = Some(speed);
+                movement.set_movement_speed(speed.min(self.thresholds.low_battery_speed)).await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Poll the power source in the background for the lifetime of the process.
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let interval = Duration::from_millis(self.thresholds.power_poll_interval_ms);
+            loop {
+                self.check_once().await;
+                sleep(interval).await;
+            }
+        });
+    }
+}
+
+/// Battery exposed by the kernel under `/sys/class/power_supply`, as fuel
+/// gauges and UPS HATs with a kernel driver are.
+pub struct SysfsPowerSource {
+    supply: PathBuf,
+}
+
+impl SysfsPowerSource {
+    pub const POWER_SUPPLY_DIR: &'static str = "/sys/class/power_supply";
+
+    pub fn new(supply: impl Into<PathBuf>) -> Self {
+        Self { supply: supply.into() }
+    }
+
+    /// The first supply of type `Battery` under `dir`
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let mut supplies: Vec<PathBuf> = std::fs::read_dir(dir).ok()?.flatten().map(|entry| entry.path()).collect();
+        supplies.sort();
+        supplies
+            .into_iter()
+            .find(|supply| std::fs::read_to_string(supply.join("type")).map(|kind| kind.trim() == "Battery").unwrap_or(false))
+            .map(Self::new)
+    }
+
+    async fn read_value(&self, name: &str) -> Option<f32> {
+        tokio::fs::read_to_string(self.supply.join(name)).await.ok()?.trim().parse().ok()
+    }
+}
+
+#[async_trait]
+impl PowerSource for SysfsPowerSource {
+    async fn read_power(&self) -> Result<PowerReading, String> {
+        let percent = self
+            .read_value("capacity")
+            .await
+            .ok_or_else(|| format!("{} reports no capacity", self.supply.display()))?;
+        // sysfs reports microvolts and microamps, with the sign of the
+        // current varying between drivers
+        let voltage = self.read_value("voltage_now").await.unwrap_or(0.0) / 1_000_000.0;
+        let current = self.read_value("current_now").await.unwrap_or(0.0).abs() / 1_000_000.0;
+        let charging = tokio::fs::read_to_string(self.supply.join("status"))
+            .await
+            .map(|status| status.trim() == "Charging")
+            .unwrap_or(false);
+        Ok(PowerReading {
+            voltage,
+            current: if charging { -current } else { current },
+            percent: percent.clamp(0.0, 100.0),
+        })
+    }
+}
+
+/// Power source returning a settable reading.
+pub struct MockPowerSource {
+    reading: Mutex<PowerReading>,
+}
+
+impl MockPowerSource {
+    pub fn new(percent: f32) -> Self {
+        Self { reading: Mutex::new(PowerReading { voltage: 12.6, current: 1.5, percent }) }
+    }
+
+    pub async fn set_percent(&self, percent: f32) {
+        self.reading.lock().await.percent = percent;
+    }
+}
+
+#[async_trait]
+impl PowerSource for MockPowerSource {
+    async fn read_power(&self) -> Result<PowerReading, String> {
+        Ok(*self.reading.lock().await)
+    }
+}
+
+#[async_trait]
+impl<S: PowerSource + Send + Sync> PowerSource for Arc<S> {
+    async fn read_power(&self) -> Result<PowerReading, String> {
+        (**self).read_power().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robotics::{MovementCommand, ServoSystem};
+    use crate::safety::Safety;
+
+    #[tokio::test]
+    async fn test_critical_battery_requests_controlled_stop_and_warns() {
+        let safety = Safety::new();
+        let mut system = ServoSystem::new(true).with_safety(safety.clone());
+        system.initialize().await.unwrap();
+        let movement = system.movement_controller().unwrap();
+        let servo_system: SharedServoSystem = Arc::new(RwLock::new(system));
+        let battery = Arc::new(MockPowerSource::new(80.0));
+        let monitor = PowerMonitor::new(battery.clone(), SafetyConfig::default(), safety.clone(), servo_system);
+        let mut warnings = subscribe_power_warnings();
+
+        assert!(monitor.check_once().await.is_none());
+        assert_eq!(latest_power_reading().await.map(|reading| reading.percent), Some(80.0));
+
+        battery.set_percent(15.0).await;
+        assert_eq!(monitor.check_once().await.unwrap().level, PowerLevel::Low);
+        assert_eq!(movement.movement_speed().await, 0.5);
+        assert!(safety.controlled_stop_reason().await.is_none());
+
+        // Going critical cuts short the step in progress...
+        battery.set_percent(3.0).await;
+        let (stepped, warning) = tokio::join!(movement.execute_command(MovementCommand::StepForward), async {
+            sleep(Duration::from_millis(100)).await;
+            monitor.check_once().await.unwrap()
+        });
+        assert!(stepped.unwrap_err().contains("battery critical"));
+        // ...and refuses new movement, but neutral still runs
+        assert!(movement.execute_command(MovementCommand::TurnLeft).await.unwrap_err().contains("battery critical"));
+        movement.execute_command(MovementCommand::Neutral).await.unwrap();
+        assert_eq!(warning.level, PowerLevel::Critical);
+        assert!(warning.message.contains("shut down"));
+        assert!(safety.controlled_stop_reason().await.unwrap().contains("battery critical"));
+        assert!(!safety.is_emergency().await);
+
+        let levels: Vec<PowerLevel> = std::iter::from_fn(|| warnings.try_recv().ok()).map(|warning| warning.level).collect();
+        assert_eq!(levels, vec![PowerLevel::Low, PowerLevel::Critical]);
+        // no repeat warning while the level holds
+        assert!(monitor.check_once().await.is_none());
+
+        // Clearing the stop by hand sticks while the battery stays critical
+        assert!(safety.clear_controlled_stop().await.unwrap().contains("battery critical"));
+        assert!(monitor.check_once().await.is_none());
+        assert!(safety.controlled_stop_reason().await.is_none());
+
+        // Recovering lifts the battery stop and restores the speed
+        battery.set_percent(15.0).await;
+        monitor.check_once().await;
+        battery.set_percent(3.0).await;
+        monitor.check_once().await;
+        assert!(safety.controlled_stop_reason().await.is_some());
+        battery.set_percent(60.0).await;
+        assert!(monitor.check_once().await.is_none());
+        assert!(safety.controlled_stop_reason().await.is_none());
+        assert_eq!(movement.movement_speed().await, 1.0);
+        movement.execute_command(MovementCommand::TurnLeft).await.unwrap();
+
+        // ...but not a stop someone else requested in the meantime
+        battery.set_percent(3.0).await;
+        monitor.check_once().await;
+        safety.request_controlled_stop("obstacle ahead".to_string()).await;
+        battery.set_percent(60.0).await;
+        monitor.check_once().await;
+        assert_eq!(safety.controlled_stop_reason().await.as_deref(), Some("obstacle ahead"));
+    }
+
+    #[tokio::test]
+    async fn test_sysfs_power_source_reads_first_battery() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        for (supply, kind) in [("AC", "Mains"), ("BAT0", "Battery")] {
+            std::fs::create_dir_all(dir.join(supply)).unwrap();
+            std::fs::write(dir.join(supply).join("type"), format!("{}\n", kind)).unwrap();
+        }
+        let battery = dir.join("BAT0");
+        std::fs::write(battery.join("capacity"), "42\n").unwrap();
+        std::fs::write(battery.join("voltage_now"), "11100000\n").unwrap();
+        std::fs::write(battery.join("current_now"), "-1500000\n").unwrap();
+        std::fs::write(battery.join("status"), "Discharging\n").unwrap();
+
+        let source = SysfsPowerSource::detect(dir).unwrap();
+        let reading = source.read_power().await.unwrap();
+        assert_eq!(reading.percent, 42.0);
+        assert!((reading.voltage - 11.1).abs() < 1e-4);
+        assert!((reading.current - 1.5).abs() < 1e-4);
+
+        std::fs::write(battery.join("status"), "Charging\n").unwrap();
+        assert!(source.read_power().await.unwrap().current < 0.0);
+        std::fs::remove_file(battery.join("capacity")).unwrap();
+        assert!(source.read_power().await.is_err());
+        assert!(SysfsPowerSource::detect(&dir.join("AC")).is_none());
+    }
+}
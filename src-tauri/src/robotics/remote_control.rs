@@ -0,0 +1,402 @@
+//! Remote control over WebSocket: JSON-RPC style movement requests from
+//! another machine, usually through an SSH tunnel. Clients authenticate with
+//! the pairing secret the approval system issued them, as an
+//! `Authorization: Bearer <secret>` header or in the connect URL
+//! (`ws://host:9001/?token=<secret>`). Every request is checked against the
+//! approval system's permissions for the paired client, except an emergency
+//! stop, which anyone connected may send. Moves share the `Safety` rate
+//! limit, and each request is written to the audit log.
+
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::servo_system::SharedServoSystem;
+use super::movement_audit::MovementSource;
+use super::tars_movement::MovementCommand;
+use crate::approval::{ApprovalSystem, AuditLog, AuditLogger, PermissionLevel};
+use crate::safety::SharedSafety;
+
+/// Where `main` serves remote control; remote machines reach it through a tunnel
+pub const REMOTE_CONTROL_ADDR: &str = "127.0.0.1:9001";
+
+/// JSON-RPC error codes
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_PARAMS: i32 = -32602;
+pub const UNAUTHORIZED: i32 = -32001;
+pub const RATE_LIMITED: i32 = -32002;
+pub const EXECUTION_FAILED: i32 = -32000;
+
+/// Longest macro a client may send in one request
+pub const MAX_MACRO_COMMANDS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Forward,
+    Left,
+    Right,
+    Neutral,
+}
+
+/// What a client can ask for, as `{"method": ..., "params": {...}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    Pose { name: String },
+    Move { direction: Direction },
+    /// Movement commands executed back to back
+    Macro { commands: Vec<MovementCommand> },
+    EmergencyStop,
+}
+
+impl RemoteCommand {
+    /// Name used in the audit log
+    pub fn name(&self) -> &'static str {
+        match self {
+            RemoteCommand::Pose { .. } => "pose",
+            RemoteCommand::Move { .. } => "move",
+            RemoteCommand::Macro { .. } => "macro",
+            RemoteCommand::EmergencyStop => "emergency_stop",
+        }
+    }
+
+    /// Registered Tauri command whose scope this request needs
+    fn equivalent_command(&self) -> &'static str {
+        match self {
+            RemoteCommand::EmergencyStop => "emergency_stop_all",
+            _ => "execute_movement_command",
+        }
+    }
+
+    /// The movement commands to run, rejecting anything the controller
+    /// shouldn't be handed
+    pub fn to_movement_commands(&self, safety: &SharedSafety) -> Result<Vec<MovementCommand>, String> {
+        let commands = match self {
+            RemoteCommand::Pose { name } if name.trim().is_empty() => return Err("Pose name is empty".to_string()),
+            RemoteCommand::Pose { name } => vec![MovementCommand::Pose(name.clone())],
+            RemoteCommand::Move { direction } => vec![match direction {
+                Direction::Forward => MovementCommand::StepForward,
+                Direction::Left => MovementCommand::TurnLeft,
+                Direction::Right => MovementCommand::TurnRight,
+                Direction::Neutral => MovementCommand::Neutral,
+            }],
+            RemoteCommand::Macro { commands } if commands.is_empty() => return Err("Macro has no commands".to_string()),
+            RemoteCommand::Macro { commands } if commands.len() > MAX_MACRO_COMMANDS => {
+                return Err(format!("Macro has {} commands, the limit is {}", commands.len(), MAX_MACRO_COMMANDS));
+            },
+            RemoteCommand::Macro { commands } => commands.clone(),
+            RemoteCommand::EmergencyStop => vec![MovementCommand::EmergencyStop],
+        };
+        for command in &commands {
+            if let MovementCommand::ServoTargets { positions, .. } = command {
+                if let Some((servo, position)) = positions.iter().find(|(_, position)| !safety.check_servo_bounds(*position)) {
+                    return Err(format!("{:?} target {} is out of bounds", servo, position));
+                }
+            }
+        }
+        Ok(commands)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteRequest {
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub command: RemoteCommand,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteError {
+    pub code: i32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteResponse {
+    pub jsonrpc: String,
+    pub id: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RemoteError>,
+}
+
+impl RemoteResponse {
+    fn success(id: Option<serde_json::Value>, result: String) -> Self {
+        Self { jsonrpc: "2.0".to_string(), id, result: Some(result), error: None }
+    }
+
+    fn error(id: Option<serde_json::Value>, code: i32, message: String) -> Self {
+        Self { jsonrpc: "2.0".to_string(), id, result: None, error: Some(RemoteError { code, message }) }
+    }
+}
+
+/// Looks up the permission a registered command needs; `main` passes
+/// `commands::registry::required_permission`
+pub type PermissionLookup = fn(&str) -> Option<PermissionLevel>;
+
+/// Runs remote requests on the same movement controller the app uses
+pub struct RemoteControl {
+    servo_system: SharedServoSystem,
+    safety: SharedSafety,
+    approval: ApprovalSystem,
+    audit: AuditLogger,
+    required_permission: PermissionLookup,
+}
+
+impl RemoteControl {
+    pub fn new(servo_system: SharedServoSystem, safety: SharedSafety) -> Self {
+        Self {
+            servo_system,
+            safety,
+            approval: ApprovalSystem::new(),
+            audit: AuditLogger::new(),
+            required_permission: |_| None,
+        }
+    }
+
+    /// Check each request against the scope of its equivalent command.
+    /// Without a lookup, or for a command it doesn't know, a request needs
+    /// Execute.
+    pub fn with_permissions(mut self, lookup: PermissionLookup) -> Self {
+        self.required_permission = lookup;
+        self
+    }
+
+    pub async fn start_server(self: &Arc<Self>, addr: &str) -> Result<(), String> {
+        let listener = TcpListener::bind(addr).await.map_err(|e| e.to_string())?;
+        self.serve(listener).await;
+        Ok(())
+    }
+
+    /// Accept remote control clients on an already bound listener.
+    pub async fn serve(self: &Arc<Self>, listener: TcpListener) {
+        while let Ok((stream, _)) = listener.accept().await {
+            let remote = self.clone();
+            tokio::spawn(async move { remote.handle_connection(stream).await });
+        }
+    }
+
+    async fn handle_connection(&self, stream: TcpStream) {
+        let mut token = None;
+        let identify = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            token = token_from_request(request);
+            Ok(response)
+        };
+        let ws_stream = match tokio_tungstenite::accept_hdr_async(stream, identify).await {
+            Ok(ws_stream) => ws_stream,
+            Err(e) => {
+                warn!("Remote control handshake failed: {}", e);
+                return;
+            },
+        };
+        let client = match &token {
+            Some(token) => self.approval.authenticate_client(token).await,
+            None => None,
+        };
+        match &client {
+            Some(client) => info!("Remote control client '{}' connected", client),
+            None => warn!("Unauthenticated remote control client connected; only emergency stop is allowed"),
+        }
+
+        let (mut write, mut read) = ws_stream.split();
+        while let Some(Ok(message)) = read.next().await {
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let response = self.handle_message(client.as_deref(), &text).await;
+            let reply = serde_json::to_string(&response).unwrap_or_default();
+            if write.send(Message::Text(reply)).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Answer one request from `client`, the authenticated client ID or None.
+    /// Every request that parses is audited, whether or not it was allowed
+    /// to run.
+    pub async fn handle_message(&self, client: Option<&str>, text: &str) -> RemoteResponse {
+        let request: RemoteRequest = match serde_json::from_str(text) {
+            Ok(request) => request,
+            Err(e) => return RemoteResponse::error(None, PARSE_ERROR, format!("Invalid request: {}", e)),
+        };
+        let name = request.command.name();
+        let response = self.execute(client, &request).await;
+        let (success, details) = match (&response.result, &response.error) {
+            (_, Some(error)) => (false, Some(error.message.clone())),
+            (result, None) => (true, result.clone()),
+        };
+        let log = AuditLog::new(
+            "remote_movement".to_string(),
+            format!("Remote {} request: {:?}", name, request.command),
+            client.unwrap_or(UNAUTHENTICATED).to_string(),
+            success,
+            details,
+        );
+        if let Err(e) = self.audit.log_operation(log).await {
+            warn!("Failed to audit remote {} request: {}", name, e);
+        }
+        response
+    }
+
+    async fn execute(&self, client: Option<&str>, request: &RemoteRequest) -> RemoteResponse {
+        let id = request.id.clone();
+        // A stop bypasses authentication and the rate limit: anyone who can
+        // reach the robot may stop it
+        if matches!(request.command, RemoteCommand::EmergencyStop) {
+            let controller = match self.servo_system.read().await.movement_controller() {
+                Some(controller) => controller,
+                None => return RemoteResponse::error(id, EXECUTION_FAILED, "Movement controller not initialized".to_string()),
+            };
+            return match controller.emergency_stop_all().await {
+                Ok(response) => RemoteResponse::success(id, response),
+                Err(e) => RemoteResponse::error(id, EXECUTION_FAILED, e),
+            };
+        }
+
+        let permission = (self.required_permission)(request.command.equivalent_command())
+            .unwrap_or(PermissionLevel::Execute);
+        let authorized = match client {
+            Some(client) => self.approval.user_has_permission(client, &permission).await,
+            None => false,
+        };
+        if !authorized {
+            let client = client.unwrap_or(UNAUTHENTICATED);
+            warn!("Rejected remote {} request from '{}'", request.command.name(), client);
+            return RemoteResponse::error(id, UNAUTHORIZED, format!("Client '{}' lacks {:?} permission", client, permission));
+        }
+
+        let commands = match request.command.to_movement_commands(&self.safety) {
+            Ok(commands) => commands,
+            Err(e) => return RemoteResponse::error(id, INVALID_PARAMS, e),
+        };
+        if !self.safety.check_move_allowed().await {
+            return RemoteResponse::error(id, RATE_LIMITED, "Too many movement requests".to_string());
+        }
+
+        let controller = match self.servo_system.read().await.movement_controller() {
+            Some(controller) => controller,
+            None => return RemoteResponse::error(id, EXECUTION_FAILED, "Movement controller not initialized".to_string()),
+        };
+        let mut responses = Vec::new();
+        for command in commands {
+            match controller.execute_command_from(command, MovementSource::Remote).await {
+                Ok(response) => responses.push(response),
+                Err(e) => return RemoteResponse::error(id, EXECUTION_FAILED, e),
+            }
+        }
+        RemoteResponse::success(id, responses.join("\n"))
+    }
+}
+
+/// Audit log user for requests without a valid pairing secret
+const UNAUTHENTICATED: &str = "unauthenticated";
+
+/// The pairing secret from the `Authorization: Bearer` header, or else the
+/// `token` parameter of the connect URL's query string
+fn token_from_request(request: &Request) -> Option<String> {
+    let header = request.headers().get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if let Some(token) = header {
+        return Some(token.trim().to_string());
+    }
+    request.uri().query()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "token")
+        .map(|(_, value)| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::audit::AuditConfiguration;
+    use crate::robotics::{ServoId, ServoSystem};
+    use crate::safety::Safety;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_paired_client_moves_servos_and_anyone_may_stop() {
+        let audit = AuditLogger::new();
+        audit.configure(AuditConfiguration { enable_file_logging: false, ..AuditConfiguration::default() }).await.unwrap();
+        // A client ID of its own, so the shared permission table is left as
+        // other tests expect it
+        let approval = ApprovalSystem::new();
+        let client = format!("remote-operator-{}", uuid::Uuid::new_v4());
+        let token = approval.pair_client(&client, PermissionLevel::Execute).await.unwrap();
+
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+        let movement = system.movement_controller().unwrap();
+        let servo_system: SharedServoSystem = Arc::new(RwLock::new(system));
+        // no rate limit, so the first move isn't held back
+        let mut safety = Safety::new();
+        Arc::get_mut(&mut safety).unwrap().rate_limit = Duration::ZERO;
+        let remote = Arc::new(RemoteControl::new(servo_system, safety));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = remote.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let send = |query: String, request: &'static str| async move {
+            let url = format!("ws://{}/{}", addr, query);
+            let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+            ws.send(Message::Text(request.to_string())).await.unwrap();
+            let reply = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            serde_json::from_str::<RemoteResponse>(&reply).unwrap()
+        };
+        let pose = r#"{"id": 1, "method": "pose", "params": {"name": "Turn Left"}}"#;
+
+        let response = send(format!("?token={}", token), pose).await;
+        assert!(response.error.is_none(), "{:?}", response);
+        assert_eq!(response.id, Some(serde_json::json!(1)));
+        let status = movement.get_status().await;
+        assert!(status.servo_positions.contains(&(ServoId::Head, -0.3)), "{:?}", status.servo_positions);
+
+        // Naming a client is not enough; only its secret identifies it
+        movement.execute_command(MovementCommand::Neutral).await.unwrap();
+        for query in [format!("?client={}", client), "?token=guess".to_string(), String::new()] {
+            let response = send(query, pose).await;
+            assert_eq!(response.error.map(|error| error.code), Some(UNAUTHORIZED));
+        }
+        assert!(!movement.get_status().await.servo_positions.contains(&(ServoId::Head, -0.3)));
+
+        let response = send(String::new(), r#"{"id": 2, "method": "emergency_stop"}"#).await;
+        assert!(response.error.is_none(), "{:?}", response);
+        assert!(!movement.is_enabled().await);
+
+        approval.unpair_client(&client).await.unwrap();
+        assert_eq!(approval.authenticate_client(&token).await, None);
+        assert!(!approval.user_has_permission(&client, &PermissionLevel::Execute).await);
+    }
+
+    #[tokio::test]
+    async fn test_request_needs_the_scope_of_its_command() {
+        let approval = ApprovalSystem::new();
+        let client = format!("remote-operator-{}", uuid::Uuid::new_v4());
+        approval.pair_client(&client, PermissionLevel::Execute).await.unwrap();
+
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+        let servo_system: SharedServoSystem = Arc::new(RwLock::new(system));
+        let remote = RemoteControl::new(servo_system, Safety::new())
+            .with_permissions(|command| (command == "execute_movement_command").then_some(PermissionLevel::Admin));
+
+        let request: RemoteRequest = serde_json::from_str(r#"{"id": 1, "method": "pose", "params": {"name": "Turn Left"}}"#).unwrap();
+        let response = remote.execute(Some(&client), &request).await;
+        assert_eq!(response.error.map(|error| error.code), Some(UNAUTHORIZED));
+
+        approval.unpair_client(&client).await.unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+//! Tilt and obstacle monitoring that halts movement through `Safety`.
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration};
+
+use super::hardware_interface::{RangeSensor, TiltSensor};
+use crate::config::config::SafetyConfig;
+use crate::safety::SharedSafety;
+
+/// Polls tilt and range sensors and requests a controlled stop when either
+/// leaves the configured envelope.
+pub struct SensorSafetyMonitor<T, R>
+where
+    T: TiltSensor + Send + Sync,
+    R: RangeSensor + Send + Sync,
+{
+    tilt: T,
+    range: R,
+    thresholds: SafetyConfig,
+    safety: SharedSafety,
+}
+
+impl<T, R> SensorSafetyMonitor<T, R>
+where
+    T: TiltSensor + Send + Sync + 'static,
+    R: RangeSensor + Send + Sync + 'static,
+{
+    pub fn new(tilt: T, range: R, thresholds: SafetyConfig, safety: SharedSafety) -> Self {
+        Self { tilt, range, thresholds, safety }
+    }
+
+    /// Read both sensors once. Returns the stop reason if a stop was requested.
+    pub async fn check_once(&self) -> Option<String> {
+        let reason = self.evaluate().await?;
+        warn!("Safety sensor stop: {}", reason);
+        self.safety.request_controlled_stop(reason.clone()).await;
+        Some(reason)
+    }
+
+    async fn evaluate(&self) -> Option<String> {
+        match self.tilt.read_tilt().await {
+            Ok((roll, pitch)) => {
+                let worst = roll.abs().max(pitch.abs());
+                if worst > self.thresholds.max_tilt_degrees {
+                    return Some(format!(
+                        "tilt {:.1}° exceeds {:.1}°",
+                        worst, self.thresholds.max_tilt_degrees
+                    ));
+                }
+            }
+            Err(e) => debug!("Tilt sensor read failed: {}", e),
+        }
+
+        match self.range.read_range().await {
+            Ok(distance) if distance < self.thresholds.min_obstacle_distance => Some(format!(
+                "obstacle at {:.2} m (minimum {:.2} m)",
+                distance, self.thresholds.min_obstacle_distance
+            )),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Range sensor read failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Poll the sensors in the background for the lifetime of the process.
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let interval = Duration::from_millis(self.thresholds.sensor_poll_interval_ms);
+            loop {
+                self.check_once().await;
+                sleep(interval).await;
+            }
+        });
+    }
+}
+
+/// Where the kernel exposes Industrial I/O devices such as IMUs and
+/// time-of-flight sensors
+pub const IIO_DEVICES_DIR: &str = "/sys/bus/iio/devices";
+
+/// The first device under `dir` with a `channel` file
+fn find_iio_device(dir: &Path, channel: &str) -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = std::fs::read_dir(dir).ok()?.flatten().map(|entry| entry.path()).collect();
+    devices.sort();
+    devices.into_iter().find(|device| device.join(channel).is_file())
+}
+
+async fn read_iio_value(device: &Path, channel: &str) -> Result<f32, String> {
+    let path = device.join(channel);
+    let value = tokio::fs::read_to_string(&path).await.map_err(|e| format!("{}: {}", path.display(), e))?;
+    value.trim().parse().map_err(|_| format!("{} is not a number", path.display()))
+}
+
+/// Accelerometer exposed through the kernel's IIO subsystem, as IMUs with a
+/// kernel driver such as the MPU-6050 are. Roll and pitch come from the
+/// direction of gravity.
+pub struct IioTiltSensor {
+    device: PathBuf,
+}
+
+impl IioTiltSensor {
+    pub fn new(device: impl Into<PathBuf>) -> Self {
+        Self { device: device.into() }
+    }
+
+    /// The first IIO device under `dir` with an accelerometer
+    pub fn detect(dir: &Path) -> Option<Self> {
+        find_iio_device(dir, "in_accel_z_raw").map(Self::new)
+    }
+}
+
+#[async_trait]
+impl TiltSensor for IioTiltSensor {
+    async fn read_tilt(&self) -> Result<(f32, f32), String> {
+        // Every axis shares one scale, which the angles don't depend on
+        let x = read_iio_value(&self.device, "in_accel_x_raw").await?;
+        let y = read_iio_value(&self.device, "in_accel_y_raw").await?;
+        let z = read_iio_value(&self.device, "in_accel_z_raw").await?;
+        let roll = y.atan2(z).to_degrees();
+        let pitch = (-x).atan2((y * y + z * z).sqrt()).to_degrees();
+        Ok((roll, pitch))
+    }
+}
+
+/// Distance sensor exposed through the kernel's IIO subsystem, as
+/// time-of-flight sensors such as the VL53L0X are.
+pub struct IioRangeSensor {
+    device: PathBuf,
+}
+
+impl IioRangeSensor {
+    pub fn new(device: impl Into<PathBuf>) -> Self {
+        Self { device: device.into() }
+    }
+
+    /// The first IIO device under `dir` that measures distance
+    pub fn detect(dir: &Path) -> Option<Self> {
+        find_iio_device(dir, "in_distance_raw").map(Self::new)
+    }
+}
+
+#[async_trait]
+impl RangeSensor for IioRangeSensor {
+    async fn read_range(&self) -> Result<f32, String> {
+        let raw = read_iio_value(&self.device, "in_distance_raw").await?;
+        // Scaled to metres; drivers without a scale report metres directly
+        let scale = read_iio_value(&self.device, "in_distance_scale").await.unwrap_or(1.0);
+        Ok(raw * scale)
+    }
+}
+
+/// A sensor that may not be fitted: reads fail until one is
+#[async_trait]
+impl<S: TiltSensor + Send + Sync> TiltSensor for Option<S> {
+    async fn read_tilt(&self) -> Result<(f32, f32), String> {
+        match self {
+            Some(sensor) => sensor.read_tilt().await,
+            None => Err("No tilt sensor fitted".to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: RangeSensor + Send + Sync> RangeSensor for Option<S> {
+    async fn read_range(&self) -> Result<f32, String> {
+        match self {
+            Some(sensor) => sensor.read_range().await,
+            None => Err("No range sensor fitted".to_string()),
+        }
+    }
+}
+
+/// Tilt sensor returning a settable reading.
+pub struct MockTiltSensor {
+    reading: Mutex<(f32, f32)>,
+}
+
+impl MockTiltSensor {
+    pub fn new() -> Self {
+        Self { reading: Mutex::new((0.0, 0.0)) }
+    }
+
+    pub async fn set_tilt(&self, roll: f32, pitch: f32) {
+        *self.reading.lock().await = (roll, pitch);
+    }
+}
+
+impl Default for MockTiltSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TiltSensor for MockTiltSensor {
+    async fn read_tilt(&self) -> Result<(f32, f32), String> {
+        Ok(*self.reading.lock().await)
+    }
+}
+
+#[async_trait]
+impl<S: TiltSensor + Send + Sync> TiltSensor for Arc<S> {
+    async fn read_tilt(&self) -> Result<(f32, f32), String> {
+        (**self).read_tilt().await
+    }
+}
+
+/// Range sensor returning a settable distance.
+pub struct MockRangeSensor {
+    distance: Mutex<f32>,
+}
+
+impl MockRangeSensor {
+    pub fn new(distance: f32) -> Self {
+        Self { distance: Mutex::new(distance) }
+    }
+
+    pub async fn set_distance(&self, distance: f32) {
+        *self.distance.lock().await = distance;
+    }
+}
+
+#[async_trait]
+impl RangeSensor for MockRangeSensor {
+    async fn read_range(&self) -> Result<f32, String> {
+        Ok(*self.distance.lock().await)
+    }
+}
+
+#[async_trait]
+impl<S: RangeSensor + Send + Sync> RangeSensor for Arc<S> {
+    async fn read_range(&self) -> Result<f32, String> {
+        (**self).read_range().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::personality::tars_core::{PersonalitySettings, TARSPersonality};
+    use crate::robotics::pca9685_controller::PCA9685Controller;
+    use crate::robotics::{MovementCommand, ServoId, TARSMovementController};
+    use crate::safety::Safety;
+
+    fn monitor(
+        tilt: Arc<MockTiltSensor>,
+        range: Arc<MockRangeSensor>,
+        safety: SharedSafety,
+    ) -> SensorSafetyMonitor<Arc<MockTiltSensor>, Arc<MockRangeSensor>> {
+        SensorSafetyMonitor::new(tilt, range, SafetyConfig::default(), safety)
+    }
+
+    #[tokio::test]
+    async fn test_clear_path_does_not_stop() {
+        let safety = Safety::new();
+        let monitor = monitor(Arc::new(MockTiltSensor::new()), Arc::new(MockRangeSensor::new(2.0)), safety.clone());
+
+        assert!(monitor.check_once().await.is_none());
+        assert!(safety.controlled_stop_reason().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_excess_tilt_requests_stop() {
+        let safety = Safety::new();
+        let tilt = Arc::new(MockTiltSensor::new());
+        let monitor = monitor(tilt.clone(), Arc::new(MockRangeSensor::new(2.0)), safety.clone());
+
+        tilt.set_tilt(5.0, -45.0).await;
+        let reason = monitor.check_once().await.unwrap();
+        assert!(reason.contains("tilt"));
+        assert_eq!(safety.controlled_stop_reason().await, Some(reason));
+        assert!(!safety.is_emergency().await);
+    }
+
+    #[tokio::test]
+    async fn test_close_obstacle_requests_stop() {
+        let safety = Safety::new();
+        let range = Arc::new(MockRangeSensor::new(2.0));
+        let monitor = monitor(Arc::new(MockTiltSensor::new()), range.clone(), safety.clone());
+
+        range.set_distance(0.05).await;
+        assert!(monitor.check_once().await.unwrap().contains("obstacle"));
+    }
+
+    #[tokio::test]
+    async fn test_obstacle_halts_a_forward_step_in_progress() {
+        let safety = Safety::new();
+        let servo_controller = PCA9685Controller::mock(50.0);
+        servo_controller.initialize().await.unwrap();
+        let movement = TARSMovementController::new(servo_controller, TARSPersonality::new(PersonalitySettings::default()))
+            .with_safety(safety.clone());
+        let range = Arc::new(MockRangeSensor::new(2.0));
+        let monitor = monitor(Arc::new(MockTiltSensor::new()), range.clone(), safety.clone());
+
+        let (stepped, _) = tokio::join!(movement.execute_command(MovementCommand::StepForward), async {
+            sleep(Duration::from_millis(200)).await;
+            range.set_distance(0.05).await;
+            monitor.check_once().await.unwrap()
+        });
+        assert!(stepped.unwrap_err().contains("obstacle"));
+        let status = movement.get_status().await;
+        assert!(!status.is_moving);
+        assert!(status.interpolation_progress < 1.0);
+        // The step never got as far as swinging the right leg forward
+        assert!(!status.servo_positions.contains(&(ServoId::RightHipForwardBack, 0.3)));
+
+        // Only neutral runs until the stop is lifted
+        assert!(movement.execute_command(MovementCommand::StepForward).await.unwrap_err().contains("halted"));
+        movement.execute_command(MovementCommand::Neutral).await.unwrap();
+        safety.clear_controlled_stop().await;
+        range.set_distance(2.0).await;
+        assert!(monitor.check_once().await.is_none());
+        movement.execute_command(MovementCommand::TurnLeft).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_iio_sensors_read_tilt_and_scaled_distance() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let imu = dir.join("iio:device0");
+        let tof = dir.join("iio:device1");
+        std::fs::create_dir_all(&imu).unwrap();
+        std::fs::create_dir_all(&tof).unwrap();
+        // Rolled 45 degrees onto its side
+        for (axis, raw) in [("x", "0"), ("y", "11585"), ("z", "11585")] {
+            std::fs::write(imu.join(format!("in_accel_{}_raw", axis)), format!("{}\n", raw)).unwrap();
+        }
+        std::fs::write(tof.join("in_distance_raw"), "250\n").unwrap();
+        std::fs::write(tof.join("in_distance_scale"), "0.001\n").unwrap();
+
+        let (roll, pitch) = IioTiltSensor::detect(dir).unwrap().read_tilt().await.unwrap();
+        assert!((roll - 45.0).abs() < 0.01 && pitch.abs() < 0.01, "roll {} pitch {}", roll, pitch);
+        let range = IioRangeSensor::detect(dir).unwrap();
+        assert!((range.read_range().await.unwrap() - 0.25).abs() < 1e-6);
+
+        let monitor = SensorSafetyMonitor::new(IioTiltSensor::detect(dir), None::<IioRangeSensor>, SafetyConfig::default(), Safety::new());
+        assert!(monitor.check_once().await.unwrap().contains("tilt"));
+    }
+}
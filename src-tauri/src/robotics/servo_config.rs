@@ -1,9 +1,27 @@
 //! Servo configuration matching the Python TARS implementation.
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Profile used when the config names none
+pub const DEFAULT_SERVO_PROFILE: &str = "replica-1to1";
+
+/// PWM frequency the built-in profiles' counts are calibrated at (analog servos)
+pub const DEFAULT_PWM_FREQUENCY_HZ: f32 = 50.0;
+
+/// File the servo calibration offsets are saved to and loaded from at startup
+pub const CALIBRATION_FILE: &str = "calibration.toml";
+
+/// Largest neutral correction a calibration may apply, in microseconds
+pub const MAX_CALIBRATION_OFFSET_US: i16 = 300;
+
+/// A hardware profile as written in config: the servos a build has, with
+/// their PWM range, neutral (`default_pwm`) and rate limits
+pub type ServoProfile = HashMap<ServoId, ServoConfig>;
 
 /// Servo IDs matching the Python implementation
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ServoId {
     RightHipForwardBack = 0,
     RightHipUpDown = 1,
@@ -16,6 +34,43 @@ pub enum ServoId {
     Head = 8,
 }
 
+impl ServoId {
+    /// All servos in channel order.
+    pub fn all() -> [ServoId; 9] {
+        [
+            ServoId::RightHipForwardBack,
+            ServoId::RightHipUpDown,
+            ServoId::RightKnee,
+            ServoId::LeftHipForwardBack,
+            ServoId::LeftHipUpDown,
+            ServoId::LeftKnee,
+            ServoId::RightShoulderForwardBack,
+            ServoId::LeftShoulderForwardBack,
+            ServoId::Head,
+        ]
+    }
+
+    /// Variant name, as used in pose and config files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServoId::RightHipForwardBack => "RightHipForwardBack",
+            ServoId::RightHipUpDown => "RightHipUpDown",
+            ServoId::RightKnee => "RightKnee",
+            ServoId::LeftHipForwardBack => "LeftHipForwardBack",
+            ServoId::LeftHipUpDown => "LeftHipUpDown",
+            ServoId::LeftKnee => "LeftKnee",
+            ServoId::RightShoulderForwardBack => "RightShoulderForwardBack",
+            ServoId::LeftShoulderForwardBack => "LeftShoulderForwardBack",
+            ServoId::Head => "Head",
+        }
+    }
+
+    /// Look up a servo by variant name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<ServoId> {
+        Self::all().into_iter().find(|s| s.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
 impl From<ServoId> for u8 {
     fn from(servo: ServoId) -> u8 {
         servo as u8
@@ -41,6 +96,58 @@ impl TryFrom<u8> for ServoId {
     }
 }
 
+/// Rate limits for a servo, in PWM counts per ramp tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MotionLimits {
+    /// Largest pulse change applied in a single write
+    pub max_velocity: u16,
+    /// Largest change in velocity between consecutive writes
+    pub max_acceleration: u16,
+}
+
+impl MotionLimits {
+    pub fn new(max_velocity: u16, max_acceleration: u16) -> Self {
+        Self {
+            max_velocity: max_velocity.max(1),
+            max_acceleration: max_acceleration.max(1),
+        }
+    }
+
+    /// No limiting: every setpoint is applied in one write.
+    pub fn unlimited() -> Self {
+        Self::new(u16::MAX, u16::MAX)
+    }
+
+    /// Break a move from `from` to `to` into the intermediate PWM values to
+    /// write, one per tick. The ramp accelerates up to `max_velocity` and
+    /// slows down again before the target. Returns an empty ramp if the
+    /// servo is already at `to`.
+    pub fn ramp(&self, from: u16, to: u16) -> Vec<u16> {
+        let target = to as i32;
+        let direction = (target - from as i32).signum();
+        let max_velocity = self.max_velocity.max(1) as i32;
+        let acceleration = self.max_acceleration.max(1) as i32;
+
+        let mut current = from as i32;
+        let mut velocity = 0;
+        let mut steps = Vec::new();
+        while current != target {
+            let remaining = (target - current).abs();
+            let braking = ((2.0 * acceleration as f32 * remaining as f32).sqrt() as i32).max(1);
+            velocity = (velocity + acceleration).min(max_velocity).min(braking).min(remaining);
+            current += velocity * direction;
+            steps.push(current as u16);
+        }
+        steps
+    }
+}
+
+impl Default for MotionLimits {
+    fn default() -> Self {
+        Self::new(45, 15)
+    }
+}
+
 /// Servo position configuration with PWM limits
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServoConfig {
@@ -48,6 +155,23 @@ pub struct ServoConfig {
     pub max_pwm: u16,
     pub default_pwm: u16,
     pub name: String,
+    #[serde(default)]
+    pub limits: MotionLimits,
+    /// Servo is mounted mirrored, so logical +1.0 is physical -1.0
+    #[serde(default)]
+    pub inverted: bool,
+    /// Mounting offset added after inversion, in logical units
+    #[serde(default)]
+    pub offset: f32,
+    /// Soft limits on the logical angle; commands outside them are rejected
+    #[serde(default = "ServoConfig::default_min_angle")]
+    pub min_angle: f32,
+    #[serde(default = "ServoConfig::default_max_angle")]
+    pub max_angle: f32,
+    /// Pulse width added to every write so the servo's mechanical neutral
+    /// lines up with logical 0.0, in microseconds
+    #[serde(default)]
+    pub calibration_offset: i16,
 }
 
 impl ServoConfig {
@@ -57,29 +181,141 @@ impl ServoConfig {
             max_pwm,
             default_pwm,
             name: name.to_string(),
+            limits: MotionLimits::default(),
+            inverted: false,
+            offset: 0.0,
+            min_angle: Self::default_min_angle(),
+            max_angle: Self::default_max_angle(),
+            calibration_offset: 0,
         }
     }
 
-    /// Convert angle (-1.0 to 1.0) to PWM value
+    fn default_min_angle() -> f32 {
+        -1.0
+    }
+
+    fn default_max_angle() -> f32 {
+        1.0
+    }
+
+    pub fn with_soft_limits(mut self, min_angle: f32, max_angle: f32) -> Self {
+        self.min_angle = min_angle;
+        self.max_angle = max_angle;
+        self
+    }
+
+    pub fn with_mounting(mut self, inverted: bool, offset: f32) -> Self {
+        self.inverted = inverted;
+        self.offset = offset;
+        self
+    }
+
+    /// Map a logical angle to the physical angle for this mounting
+    pub fn to_physical(&self, angle: f32) -> f32 {
+        let oriented = if self.inverted { -angle } else { angle };
+        oriented + self.offset
+    }
+
+    /// Map a physical angle back to the logical angle
+    pub fn to_logical(&self, angle: f32) -> f32 {
+        let oriented = angle - self.offset;
+        if self.inverted { -oriented } else { oriented }
+    }
+
+    pub fn with_limits(mut self, limits: MotionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Convert logical angle (-1.0 to 1.0) to PWM value. Inversion and offset
+    /// are applied first, then the result is clamped to the PWM range.
     pub fn angle_to_pwm(&self, angle: f32) -> u16 {
-        let clamped = angle.clamp(-1.0, 1.0);
+        let clamped = self.to_physical(angle.clamp(-1.0, 1.0)).clamp(-1.0, 1.0);
         let range = (self.max_pwm - self.min_pwm) as f32;
         let normalized = (clamped + 1.0) / 2.0; // Convert -1..1 to 0..1
         (self.min_pwm as f32 + normalized * range) as u16
     }
 
-    /// Convert PWM value back to angle
+    /// Convert PWM value back to logical angle
     pub fn pwm_to_angle(&self, pwm: u16) -> f32 {
         let clamped = pwm.clamp(self.min_pwm, self.max_pwm);
         let range = (self.max_pwm - self.min_pwm) as f32;
         let normalized = (clamped - self.min_pwm) as f32 / range;
-        (normalized * 2.0) - 1.0 // Convert 0..1 to -1..1
+        self.to_logical((normalized * 2.0) - 1.0) // Convert 0..1 to -1..1
+    }
+}
+
+/// Per-servo neutral offsets in microseconds, as saved to `CALIBRATION_FILE`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    #[serde(default)]
+    pub offsets: BTreeMap<ServoId, i16>,
+}
+
+impl Calibration {
+    /// Load a saved calibration. A missing file is an empty calibration.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| format!("Invalid calibration {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn offset(&self, servo: ServoId) -> i16 {
+        self.offsets.get(&servo).copied().unwrap_or(0)
+    }
+
+    /// This calibration corrected by `trims`, the extra microseconds each
+    /// servo still sat off neutral, clamped to `MAX_CALIBRATION_OFFSET_US`
+    pub fn adjusted(&self, trims: &BTreeMap<ServoId, i16>) -> Self {
+        let mut offsets = self.offsets.clone();
+        for (servo, trim) in trims {
+            let offset = (self.offset(*servo) as i32 + *trim as i32)
+                .clamp(-(MAX_CALIBRATION_OFFSET_US as i32), MAX_CALIBRATION_OFFSET_US as i32);
+            offsets.insert(*servo, offset as i16);
+        }
+        Self { offsets }
+    }
+}
+
+/// Two servos that must not be inside these angle ranges at the same time,
+/// because the segments they drive would collide
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AngleConstraint {
+    pub first: ServoId,
+    pub first_range: (f32, f32),
+    pub second: ServoId,
+    pub second_range: (f32, f32),
+    /// What collides, for the error message
+    pub reason: String,
+}
+
+impl AngleConstraint {
+    pub fn new(first: ServoId, first_range: (f32, f32), second: ServoId, second_range: (f32, f32), reason: &str) -> Self {
+        Self { first, first_range, second, second_range, reason: reason.to_string() }
+    }
+
+    pub fn is_violated_by(&self, first: f32, second: f32) -> bool {
+        let within = |(min, max): (f32, f32), angle: f32| (min..=max).contains(&angle);
+        within(self.first_range, first) && within(self.second_range, second)
     }
 }
 
 /// TARS servo configuration based on Python implementation
+#[derive(Debug, Clone)]
 pub struct TARSServoConfig {
     configs: Vec<(ServoId, ServoConfig)>,
+    /// Servo pairs whose segments collide in some combination of angles
+    constraints: Vec<AngleConstraint>,
+    /// Frequency the PWM counts above are calibrated at. A controller
+    /// running at another frequency scales them so the pulse widths match.
+    pub pwm_frequency_hz: f32,
 }
 
 impl TARSServoConfig {
@@ -94,10 +330,107 @@ impl TARSServoConfig {
             (ServoId::LeftKnee, ServoConfig::new(150, 600, 375, "Left Knee")),
             (ServoId::RightShoulderForwardBack, ServoConfig::new(150, 600, 375, "Right Shoulder Forward/Back")),
             (ServoId::LeftShoulderForwardBack, ServoConfig::new(150, 600, 375, "Left Shoulder Forward/Back")),
-            (ServoId::Head, ServoConfig::new(150, 600, 375, "Head")),
+            (ServoId::Head, ServoConfig::new(150, 600, 375, "Head").with_limits(MotionLimits::new(60, 20))),
         ];
         
-        Self { configs }
+        // A knee folded with its hip raised drives the lower leg into the hip housing
+        let constraints = vec![
+            AngleConstraint::new(ServoId::RightHipUpDown, (0.6, 1.0), ServoId::RightKnee, (0.7, 1.0), "right lower leg hits the hip housing"),
+            AngleConstraint::new(ServoId::LeftHipUpDown, (0.6, 1.0), ServoId::LeftKnee, (0.7, 1.0), "left lower leg hits the hip housing"),
+        ];
+
+        Self { configs, constraints, pwm_frequency_hz: DEFAULT_PWM_FREQUENCY_HZ }
+    }
+
+    /// Desk-sized build on micro servos: narrower PWM range, slower ramps
+    /// and no knee servos.
+    pub fn mini_desk() -> Self {
+        let micro = |name: &str| ServoConfig::new(130, 510, 320, name).with_limits(MotionLimits::new(25, 8));
+        let configs = vec![
+            (ServoId::RightHipForwardBack, micro("Right Hip Forward/Back")),
+            (ServoId::RightHipUpDown, micro("Right Hip Up/Down")),
+            (ServoId::LeftHipForwardBack, micro("Left Hip Forward/Back")),
+            (ServoId::LeftHipUpDown, micro("Left Hip Up/Down")),
+            (ServoId::RightShoulderForwardBack, micro("Right Shoulder Forward/Back")),
+            (ServoId::LeftShoulderForwardBack, micro("Left Shoulder Forward/Back")),
+            (ServoId::Head, micro("Head").with_limits(MotionLimits::new(30, 10))),
+        ];
+
+        Self { configs, constraints: Vec::new(), pwm_frequency_hz: DEFAULT_PWM_FREQUENCY_HZ }
+    }
+
+    /// Built-in hardware profiles by name
+    pub fn builtin_profile(name: &str) -> Option<Self> {
+        match name {
+            DEFAULT_SERVO_PROFILE => Some(Self::new()),
+            "mini-desk" => Some(Self::mini_desk()),
+            _ => None,
+        }
+    }
+
+    /// Resolve a profile by name, user profiles taking precedence over the
+    /// built-in ones.
+    pub fn profile(name: &str, user_profiles: &BTreeMap<String, ServoProfile>) -> Result<Self, String> {
+        if let Some(profile) = user_profiles.get(name) {
+            return Ok(Self::from_profile(profile));
+        }
+        Self::builtin_profile(name).ok_or_else(|| {
+            let mut available: Vec<&str> = vec![DEFAULT_SERVO_PROFILE, "mini-desk"];
+            available.extend(user_profiles.keys().map(String::as_str));
+            format!("Unknown servo profile '{}' (available: {})", name, available.join(", "))
+        })
+    }
+
+    pub fn from_profile(profile: &ServoProfile) -> Self {
+        let mut configs: Vec<(ServoId, ServoConfig)> = profile.iter()
+            .map(|(servo, config)| (*servo, config.clone()))
+            .collect();
+        configs.sort_by_key(|(servo, _)| *servo as u8);
+        Self { configs, constraints: Vec::new(), pwm_frequency_hz: DEFAULT_PWM_FREQUENCY_HZ }
+    }
+
+    /// Counts in this config were calibrated at `frequency` Hz
+    pub fn with_pwm_frequency(mut self, frequency: f32) -> Self {
+        self.pwm_frequency_hz = frequency;
+        self
+    }
+
+    pub fn with_constraint(mut self, constraint: AngleConstraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    pub fn constraints(&self) -> &[AngleConstraint] {
+        &self.constraints
+    }
+
+    /// Check every servo's angle against its soft limits and every pair
+    /// against the collision constraints. A servo listed twice counts at its
+    /// last angle.
+    pub fn check_positions(&self, positions: &[(ServoId, f32)]) -> Result<(), String> {
+        let mut angles: HashMap<ServoId, f32> = HashMap::new();
+        for (servo, angle) in positions {
+            if let Some(config) = self.get_config(*servo) {
+                if !(config.min_angle..=config.max_angle).contains(angle) {
+                    return Err(format!(
+                        "{} at {:.2} is outside its soft limits {:.2} to {:.2}",
+                        servo.name(), angle, config.min_angle, config.max_angle
+                    ));
+                }
+            }
+            angles.insert(*servo, *angle);
+        }
+        for constraint in &self.constraints {
+            if let (Some(first), Some(second)) = (angles.get(&constraint.first), angles.get(&constraint.second)) {
+                if constraint.is_violated_by(*first, *second) {
+                    return Err(format!(
+                        "{} at {:.2} and {} at {:.2} would collide: {}",
+                        constraint.first.name(), first, constraint.second.name(), second, constraint.reason
+                    ));
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn get_config(&self, servo: ServoId) -> Option<&ServoConfig> {
@@ -109,6 +442,48 @@ impl TARSServoConfig {
     pub fn all_servos(&self) -> &[(ServoId, ServoConfig)] {
         &self.configs
     }
+
+    /// Set how one servo is mounted.
+    pub fn set_mounting(&mut self, servo: ServoId, inverted: bool, offset: f32) {
+        if let Some((_, config)) = self.configs.iter_mut().find(|(id, _)| *id == servo) {
+            config.inverted = inverted;
+            config.offset = offset;
+        }
+    }
+
+    /// Narrow the angles one servo may be commanded to.
+    pub fn set_soft_limits(&mut self, servo: ServoId, min_angle: f32, max_angle: f32) {
+        if let Some((_, config)) = self.configs.iter_mut().find(|(id, _)| *id == servo) {
+            config.min_angle = min_angle;
+            config.max_angle = max_angle;
+        }
+    }
+
+    /// Offsets of every configured servo
+    pub fn calibration(&self) -> Calibration {
+        let offsets = self.configs.iter().map(|(servo, config)| (*servo, config.calibration_offset)).collect();
+        Calibration { offsets }
+    }
+
+    /// Apply saved offsets; servos the calibration doesn't list keep theirs
+    pub fn apply_calibration(&mut self, calibration: &Calibration) {
+        for (servo, offset) in &calibration.offsets {
+            self.set_calibration_offset(*servo, *offset);
+        }
+    }
+
+    pub fn set_calibration_offset(&mut self, servo: ServoId, offset_us: i16) {
+        if let Some((_, config)) = self.configs.iter_mut().find(|(id, _)| *id == servo) {
+            config.calibration_offset = offset_us.clamp(-MAX_CALIBRATION_OFFSET_US, MAX_CALIBRATION_OFFSET_US);
+        }
+    }
+
+    /// Override the rate limits for one servo.
+    pub fn set_limits(&mut self, servo: ServoId, limits: MotionLimits) {
+        if let Some((_, config)) = self.configs.iter_mut().find(|(id, _)| *id == servo) {
+            config.limits = limits;
+        }
+    }
 }
 
 impl Default for TARSServoConfig {
@@ -264,4 +639,48 @@ mod tests {
         assert!(head_config.is_some());
         assert_eq!(head_config.unwrap().name, "Head");
     }
+
+    #[test]
+    fn test_user_profiles_from_config_take_precedence() {
+        let user_profiles: BTreeMap<String, ServoProfile> = toml::from_str(
+            "[head-only.Head]\nmin_pwm = 200\nmax_pwm = 400\ndefault_pwm = 300\nname = \"Head\"\n",
+        ).unwrap();
+        let head_only = TARSServoConfig::profile("head-only", &user_profiles).unwrap();
+        assert_eq!(head_only.all_servos().len(), 1);
+        assert_eq!(head_only.get_config(ServoId::Head).unwrap().default_pwm, 300);
+        assert_eq!(TARSServoConfig::profile("mini-desk", &user_profiles).unwrap().all_servos().len(), 7);
+    }
+
+    #[test]
+    fn test_inverted_servo_mapping() {
+        let config = ServoConfig::new(150, 600, 375, "Mirrored").with_mounting(true, 0.0);
+        assert_eq!(config.angle_to_pwm(1.0), 150);
+        assert_eq!(config.angle_to_pwm(-1.0), 600);
+        assert!((config.pwm_to_angle(150) - 1.0).abs() < 0.01);
+
+        // The offset pushes past the physical stop, which clamps to max_pwm
+        let offset = ServoConfig::new(150, 600, 375, "Offset").with_mounting(true, 0.2);
+        assert_eq!(offset.angle_to_pwm(-1.0), 600);
+        assert_eq!(offset.angle_to_pwm(0.0), 420);
+    }
+
+    #[test]
+    fn test_ramp_respects_limits() {
+        let limits = MotionLimits::new(30, 10);
+        let ramp = limits.ramp(150, 600);
+        assert_eq!(*ramp.last().unwrap(), 600);
+
+        let mut previous = 150i32;
+        let mut previous_velocity = 0i32;
+        for &pwm in &ramp {
+            let velocity = pwm as i32 - previous;
+            assert!(velocity > 0 && velocity <= 30);
+            assert!(velocity - previous_velocity <= 10);
+            previous = pwm as i32;
+            previous_velocity = velocity;
+        }
+
+        assert!(limits.ramp(375, 375).is_empty());
+        assert_eq!(MotionLimits::unlimited().ramp(600, 150), vec![150]);
+    }
 }
@@ -0,0 +1,161 @@
+//! Servo self-test: sweep each configured servo through a small range and
+//! check that every commanded position is what the controller reports back.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Duration};
+
+use super::hardware_interface::ServoControl;
+use super::servo_config::ServoId;
+use super::servo_system::SharedServoSystem;
+use crate::safety::Safety;
+
+/// Positions each servo visits, ending back at center. Kept well inside the
+/// servo range so the sweep is safe with TARS standing.
+pub const SWEEP_POSITIONS: [f32; 3] = [0.2, -0.2, 0.0];
+
+/// Pause after each commanded position
+const SETTLE_MS: u64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticOutcome {
+    Pass,
+    Fail,
+    /// The servo accepted every command but there was nothing to confirm it
+    /// moved, or the sweep was cut short
+    Uncertain,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServoDiagnostic {
+    pub servo: ServoId,
+    pub name: String,
+    pub outcome: DiagnosticOutcome,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+    pub simulated: bool,
+    /// Why no servo was moved
+    pub skipped: Option<String>,
+    /// Why the sweep stopped early
+    pub aborted: Option<String>,
+    pub servos: Vec<ServoDiagnostic>,
+}
+
+impl DiagnosticReport {
+    fn skipped(reason: &str) -> Self {
+        Self { skipped: Some(reason.to_string()), ..Self::default() }
+    }
+
+    /// Every servo was swept and passed
+    pub fn passed(&self) -> bool {
+        self.skipped.is_none() && self.aborted.is_none()
+            && self.servos.iter().all(|servo| servo.outcome == DiagnosticOutcome::Pass)
+    }
+}
+
+/// Sweep every configured servo. Nothing moves while movement is disabled or
+/// the emergency stop is latched, and the sweep stops as soon as either
+/// changes. Simulated servos pass when their read-back matches; hardware
+/// servos have no position feedback, so they are at best uncertain.
+pub async fn diagnose_servos(servo_system: &SharedServoSystem, safety: &Safety) -> DiagnosticReport {
+    let (servo_controller, movement, simulated) = {
+        let system = servo_system.read().await;
+        match (system.servo_controller(), system.movement_controller()) {
+            (Some(servo_controller), Some(movement)) => (servo_controller, movement, system.is_simulation()),
+            _ => return DiagnosticReport::skipped("Servo system not initialized"),
+        }
+    };
+    if !movement.is_enabled().await {
+        return DiagnosticReport::skipped("Movement is disabled");
+    }
+    if safety.is_emergency().await {
+        return DiagnosticReport::skipped("Emergency stop active");
+    }
+
+    info!("Running servo diagnostics");
+    let mut report = DiagnosticReport { simulated, ..DiagnosticReport::default() };
+    'servos: for (servo, config) in servo_controller.servo_config().all_servos() {
+        let channel = *servo as u8;
+        let mut failure = None;
+        for position in SWEEP_POSITIONS {
+            let stop = if safety.is_emergency().await {
+                Some("Emergency stop")
+            } else if !movement.is_enabled().await {
+                Some("Movement disabled")
+            } else {
+                None
+            };
+            if let Some(stop) = stop {
+                warn!("{} during servo diagnostics, stopping at {}", stop, config.name);
+                report.aborted = Some(format!("{} during the {} sweep", stop, config.name));
+                report.servos.push(ServoDiagnostic {
+                    servo: *servo,
+                    name: config.name.clone(),
+                    outcome: DiagnosticOutcome::Uncertain,
+                    detail: Some("Sweep aborted".to_string()),
+                });
+                break 'servos;
+            }
+
+            if let Err(e) = servo_controller.set_position(channel, position).await {
+                failure = Some(format!("Failed to move to {}: {}", position, e));
+                break;
+            }
+            let expected = config.angle_to_pwm(position);
+            match servo_controller.current_pwm(channel).await {
+                Some(pwm) if pwm == expected => {},
+                read_back => {
+                    failure = Some(format!("Commanded PWM {} for {}, read back {:?}", expected, position, read_back));
+                    break;
+                },
+            }
+            sleep(Duration::from_millis(SETTLE_MS)).await;
+        }
+
+        let (outcome, detail) = match failure {
+            Some(failure) => (DiagnosticOutcome::Fail, Some(failure)),
+            None if simulated => (DiagnosticOutcome::Pass, None),
+            None => (DiagnosticOutcome::Uncertain, Some("No position feedback from hardware servos".to_string())),
+        };
+        report.servos.push(ServoDiagnostic { servo: *servo, name: config.name.clone(), outcome, detail });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robotics::ServoSystem;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_simulated_sweep_passes_and_disabled_system_is_skipped() {
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+        let movement = system.movement_controller().unwrap();
+        let servo_system: SharedServoSystem = Arc::new(RwLock::new(system));
+        let safety = Safety::new();
+
+        let report = diagnose_servos(&servo_system, &safety).await;
+        assert!(report.passed(), "{:?}", report);
+        assert!(report.simulated);
+        let swept: Vec<ServoId> = report.servos.iter().map(|servo| servo.servo).collect();
+        assert_eq!(swept, ServoId::all());
+
+        movement.set_enabled(false).await;
+        let report = diagnose_servos(&servo_system, &safety).await;
+        assert_eq!(report.skipped.as_deref(), Some("Movement is disabled"));
+        assert!(report.servos.is_empty());
+        assert!(!report.passed());
+
+        movement.set_enabled(true).await;
+        safety.trigger_emergency("Diagnostics test").await;
+        let report = diagnose_servos(&servo_system, &safety).await;
+        assert_eq!(report.skipped.as_deref(), Some("Emergency stop active"));
+    }
+}
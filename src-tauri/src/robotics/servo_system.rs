@@ -0,0 +1,362 @@
+//! Runtime-installable servo controllers shared by the Tauri servo commands.
+
+use log::info;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::kinematics::LinkModel;
+use super::pca9685_controller::{PCA9685Controller, ServoBus};
+use super::pose_library::{PoseLibrary, SharedPoseLibrary};
+use super::servo_config::{Calibration, ServoProfile, TARSServoConfig, DEFAULT_SERVO_PROFILE};
+use super::tars_movement::TARSMovementController;
+use super::telemetry::Telemetry;
+use super::thermal::ThermalGuard;
+use super::tick_rate::TickRateConfig;
+use crate::config::config::I2cBusConfig;
+use crate::personality::tars_core::{PersonalitySettings, TARSPersonality};
+use crate::safety::SharedSafety;
+
+pub type ServoController = PCA9685Controller<ServoBus>;
+pub type MovementController = TARSMovementController<ServoController>;
+pub type SharedServoSystem = Arc<RwLock<ServoSystem>>;
+
+/// Controllers start out uninstalled and are created by `initialize`.
+pub struct ServoSystem {
+    simulation: bool,
+    servo_controller: Option<Arc<ServoController>>,
+    movement_controller: Option<Arc<MovementController>>,
+    pose_library: SharedPoseLibrary,
+    telemetry: Option<Arc<Telemetry>>,
+    bus_config: I2cBusConfig,
+    kinematics: Option<LinkModel>,
+    tick_rate: TickRateConfig,
+    thermal: ThermalGuard,
+    movement_audit: bool,
+    safety: Option<SharedSafety>,
+    profile_name: String,
+    servo_config: TARSServoConfig,
+    /// Neutral offsets applied over whichever profile is active
+    calibration: Calibration,
+    user_profiles: BTreeMap<String, ServoProfile>,
+}
+
+impl ServoSystem {
+    pub fn new(simulation: bool) -> Self {
+        Self {
+            simulation,
+            servo_controller: None,
+            movement_controller: None,
+            pose_library: Arc::new(RwLock::new(PoseLibrary::builtin())),
+            telemetry: None,
+            bus_config: I2cBusConfig::default(),
+            kinematics: None,
+            tick_rate: TickRateConfig::default(),
+            thermal: ThermalGuard::default(),
+            movement_audit: false,
+            safety: None,
+            profile_name: DEFAULT_SERVO_PROFILE.to_string(),
+            servo_config: TARSServoConfig::new(),
+            calibration: Calibration::default(),
+            user_profiles: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_pose_library(mut self, pose_library: SharedPoseLibrary) -> Self {
+        self.pose_library = pose_library;
+        self
+    }
+
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// I2C device, board address and PWM frequency used outside simulation
+    pub fn with_bus_config(mut self, bus_config: I2cBusConfig) -> Self {
+        self.bus_config = bus_config;
+        self
+    }
+
+    /// Audit every command the movement controller executes
+    pub fn with_movement_audit(mut self, movement_audit: bool) -> Self {
+        self.movement_audit = movement_audit;
+        self
+    }
+
+    /// Halt the movement controller on `safety`'s controlled stops
+    pub fn with_safety(mut self, safety: SharedSafety) -> Self {
+        self.safety = Some(safety);
+        self
+    }
+
+    pub fn bus_config(&self) -> &I2cBusConfig {
+        &self.bus_config
+    }
+
+    /// I2C device, board address and PWM frequency, used from the next `initialize`
+    pub fn set_bus_config(&mut self, bus_config: I2cBusConfig) {
+        self.bus_config = bus_config;
+    }
+
+    /// Link model for the telemetry pose feed, used from the next `initialize`
+    pub fn set_kinematics(&mut self, model: LinkModel) {
+        self.kinematics = Some(model);
+    }
+
+    /// Bounds for the movement controller's adaptive tick rate
+    pub fn set_tick_rate(&mut self, tick_rate: TickRateConfig) {
+        self.tick_rate = tick_rate;
+    }
+
+    /// Thermal policy and limit for the movement controller, used from the next `initialize`
+    pub fn set_thermal(&mut self, thermal: ThermalGuard) {
+        self.thermal = thermal;
+    }
+
+    /// Hardware profiles from config, available to `switch_profile` by name
+    pub fn set_user_profiles(&mut self, user_profiles: BTreeMap<String, ServoProfile>) {
+        self.user_profiles = user_profiles;
+    }
+
+    /// Offsets of every servo in the active profile
+    pub fn calibration(&self) -> Calibration {
+        self.calibrated_config().calibration()
+    }
+
+    /// Replace the calibration offsets. The installed servo controller,
+    /// which the gamepad shares, takes them in place: movement stays
+    /// enabled or stopped as it was.
+    pub async fn set_calibration(&mut self, calibration: Calibration) -> Result<(), String> {
+        self.calibration = calibration;
+        if let Some(servo_controller) = &self.servo_controller {
+            servo_controller.set_calibration(&self.calibrated_config().calibration()).await;
+        }
+        Ok(())
+    }
+
+    fn calibrated_config(&self) -> TARSServoConfig {
+        let mut servo_config = self.servo_config.clone();
+        servo_config.apply_calibration(&self.calibration);
+        servo_config
+    }
+
+    pub fn profile_name(&self) -> &str {
+        &self.profile_name
+    }
+
+    /// Names of the built-in and user profiles
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names = vec![DEFAULT_SERVO_PROFILE.to_string(), "mini-desk".to_string()];
+        names.extend(self.user_profiles.keys().cloned());
+        names
+    }
+
+    /// Switch to another hardware profile. Installed controllers first bring
+    /// every servo to neutral and are then rebuilt with the new profile.
+    pub async fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let servo_config = TARSServoConfig::profile(name, &self.user_profiles)?;
+        let reinitialize = self.is_initialized();
+        if let Some(servo_controller) = &self.servo_controller {
+            servo_controller.neutralize().await.map_err(|e| format!("Failed to neutralize servos: {}", e))?;
+        }
+
+        self.servo_config = servo_config;
+        self.profile_name = name.to_string();
+        info!("Servo profile '{}' selected", name);
+        if reinitialize {
+            self.initialize().await?;
+        }
+        Ok(())
+    }
+
+    pub fn is_simulation(&self) -> bool {
+        self.simulation
+    }
+
+    /// Switch simulation on or off. Installed controllers belong to the old
+    /// mode, so they are dropped and must be initialized again.
+    pub fn set_simulation(&mut self, simulation: bool) {
+        if self.simulation != simulation {
+            info!("Servo simulation mode {}", if simulation { "enabled" } else { "disabled" });
+            self.servo_controller = None;
+            self.movement_controller = None;
+        }
+        self.simulation = simulation;
+    }
+
+    /// An uninitialized simulated system with this one's profile,
+    /// calibration, poses and motion settings, for replaying movements
+    /// without driving the hardware. It has no telemetry, isn't audited and
+    /// doesn't stop for `Safety`.
+    pub fn simulated_replica(&self) -> ServoSystem {
+        ServoSystem {
+            simulation: true,
+            servo_controller: None,
+            movement_controller: None,
+            pose_library: self.pose_library.clone(),
+            telemetry: None,
+            bus_config: self.bus_config.clone(),
+            kinematics: self.kinematics.clone(),
+            tick_rate: self.tick_rate.clone(),
+            thermal: self.thermal.clone(),
+            movement_audit: false,
+            safety: None,
+            profile_name: self.profile_name.clone(),
+            servo_config: self.servo_config.clone(),
+            calibration: self.calibration.clone(),
+            user_profiles: self.user_profiles.clone(),
+        }
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.movement_controller.is_some()
+    }
+
+    pub fn servo_controller(&self) -> Option<Arc<ServoController>> {
+        self.servo_controller.clone()
+    }
+
+    pub fn movement_controller(&self) -> Option<Arc<MovementController>> {
+        self.movement_controller.clone()
+    }
+
+    /// Create and install the controllers: against `MockI2C` in simulation,
+    /// otherwise against the configured I2C bus.
+    pub async fn initialize(&mut self) -> Result<(), String> {
+        let bus = ServoBus::from_config(&self.bus_config, self.simulation).map_err(|e| e.to_string())?;
+        self.initialize_with_bus(bus).await
+    }
+
+    /// `initialize` against a bus the caller already opened
+    pub async fn initialize_with_bus(&mut self, bus: ServoBus) -> Result<(), String> {
+        let servo_config = self.calibrated_config();
+        let servo_controller = Arc::new(
+            PCA9685Controller::new(bus, self.bus_config.pwm_frequency).with_servo_config(servo_config.clone()),
+        );
+        servo_controller.initialize().await.map_err(|e| e.to_string())?;
+
+        let personality = TARSPersonality::new(PersonalitySettings::default());
+        let mut movement_controller = TARSMovementController::from_shared(servo_controller.clone(), personality)
+            .with_pose_library(self.pose_library.clone())
+            .with_simulation(self.simulation)
+            .with_servo_config(servo_config)
+            .with_tick_rate(self.tick_rate.clone())
+            .with_thermal(self.thermal.clone())
+            .with_audit(self.movement_audit);
+        if let Some(telemetry) = &self.telemetry {
+            movement_controller = movement_controller.with_telemetry(telemetry.clone());
+        }
+        if let Some(model) = &self.kinematics {
+            movement_controller = movement_controller.with_kinematics(model.clone());
+        }
+        if let Some(safety) = &self.safety {
+            movement_controller = movement_controller.with_safety(safety.clone());
+        }
+
+        self.servo_controller = Some(servo_controller);
+        self.movement_controller = Some(Arc::new(movement_controller));
+        if self.simulation {
+            info!("Servo system initialized with simulated hardware");
+        } else {
+            info!("Servo system initialized on {} at 0x{:02X}", self.bus_config.bus_path, self.bus_config.address);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robotics::hardware_interface::ServoControl;
+    use crate::robotics::{MovementCommand, ServoId};
+
+    #[tokio::test]
+    async fn test_simulated_movement_updates_mock_state() {
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+
+        let movement = system.movement_controller().unwrap();
+        let response = movement.execute_command(MovementCommand::Pose("Turn Left".to_string())).await.unwrap();
+        assert!(response.starts_with("[simulated]"));
+
+        // Turn Left puts the head at -0.3: 150 + 0.35 * 450
+        let servo = system.servo_controller().unwrap();
+        assert_eq!(servo.current_pwm(ServoId::Head as u8).await, Some(307));
+        assert_eq!(movement.get_status().await.current_pose, "Turn Left Complete");
+    }
+
+    #[tokio::test]
+    async fn test_hardware_mode_without_backend_fails() {
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+
+        system.set_simulation(false);
+        assert!(!system.is_initialized());
+        assert!(system.initialize().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_switching_to_mini_desk_neutralizes_then_changes_limits() {
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+        let replica = system.servo_controller().unwrap();
+        replica.set_position(ServoId::Head as u8, 1.0).await.unwrap();
+
+        system.switch_profile("mini-desk").await.unwrap();
+        assert_eq!(replica.current_pwm(ServoId::Head as u8).await, Some(375), "old profile ramps to neutral first");
+        assert_eq!(system.profile_name(), "mini-desk");
+        let active = system.servo_controller().unwrap();
+        let head = active.servo_config().get_config(ServoId::Head).unwrap();
+        assert_eq!(head.limits, crate::robotics::servo_config::MotionLimits::new(30, 10));
+        assert!(active.servo_config().get_config(ServoId::RightKnee).is_none());
+
+        let error = system.switch_profile("giant-lego").await.unwrap_err();
+        assert!(error.contains("Unknown servo profile 'giant-lego'"), "{}", error);
+        assert_eq!(system.profile_name(), "mini-desk");
+        assert!(system.is_initialized());
+    }
+
+    #[tokio::test]
+    async fn test_calibrating_after_emergency_stop_stays_stopped() {
+        let mut system = ServoSystem::new(true);
+        system.initialize().await.unwrap();
+        let servo = system.servo_controller().unwrap();
+        let movement = system.movement_controller().unwrap();
+        movement.emergency_stop_all().await.unwrap();
+        let neutral = servo.current_pwm(ServoId::Head as u8).await.unwrap();
+
+        let calibration = Calibration { offsets: BTreeMap::from([(ServoId::Head, 48)]) };
+        system.set_calibration(calibration).await.unwrap();
+        assert!(!movement.is_enabled().await);
+        assert!(movement.execute_command(MovementCommand::Pose("Turn Left".to_string())).await.is_err());
+
+        // Same controllers, so anything sharing them (the gamepad) sees the offset
+        let updated = system.servo_controller().unwrap();
+        assert!(Arc::ptr_eq(&servo, &updated));
+        assert!(Arc::ptr_eq(&movement, &system.movement_controller().unwrap()));
+        assert_eq!(updated.calibration().offset(ServoId::Head), 48);
+        assert_eq!(system.calibration().offset(ServoId::Head), 48);
+        // 48 us at 50 Hz is 9.8 counts, and the held neutral was rewritten
+        assert_eq!(updated.calibrated_counts(ServoId::Head as u8, neutral), neutral + 10);
+        assert_eq!(updated.current_pwm(ServoId::Head as u8).await, Some(neutral));
+    }
+
+    #[tokio::test]
+    async fn test_bus_selection_follows_config_and_bad_path_is_reported() {
+        let robotics: crate::config::config::RoboticsConfig = toml::from_str(
+            "simulation = true\n[i2c]\nbus_path = \"/dev/i2c-missing\"\naddress = 0x41\npwm_frequency = 60.0\n",
+        ).unwrap();
+        assert_eq!(robotics.i2c.address, 0x41);
+
+        let mut system = ServoSystem::new(robotics.simulation).with_bus_config(robotics.i2c);
+        system.initialize().await.unwrap();
+        assert!(system.servo_controller().unwrap().bus().is_mock());
+
+        system.set_simulation(false);
+        let error = system.initialize().await.unwrap_err();
+        assert!(error.contains("/dev/i2c-missing"), "{}", error);
+        assert!(error.contains("does not exist"), "{}", error);
+        assert!(!system.is_initialized());
+    }
+}
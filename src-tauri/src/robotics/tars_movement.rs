@@ -1,24 +1,57 @@
 //! TARS movement controller with personality integration.
 
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 
+use super::easing::Easing;
 use super::hardware_interface::ServoControl;
-use super::servo_config::{ServoId, MovementPose, TARSPoses};
+use super::servo_config::{ServoId, MovementPose, TARSPoses, TARSServoConfig};
+use super::pose_library::{PoseLibrary, SharedPoseLibrary};
+use super::telemetry::{Telemetry, TelemetrySnapshot};
+use super::kinematics::LinkModel;
+use super::movement_audit::{self, MovementRecord, MovementSource};
+use super::movement_macro::named_json_path;
+use super::thermal::{ThermalGuard, ThermalState};
+use super::tick_rate::{TickRateConfig, TickRateTuner};
+use crate::events::{self, TarsEvent};
 use crate::personality::tars_core::{TARSPersonality, PersonalitySettings};
+use crate::raspberry_pi::SystemMetrics;
+use crate::safety::SharedSafety;
 
 /// Movement command types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MovementCommand {
     StepForward,
     TurnLeft,
     TurnRight,
     Pose(String),
+    /// A pose reached over `duration_ms`, or the pose's own duration
+    EasedPose {
+        name: String,
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        easing: Easing,
+    },
     Neutral,
     EmergencyStop,
+    /// Timed servo targets produced by gait generation, interpolated to over
+    /// `duration_ms`. `start_ms` is the offset from the start of the
+    /// sequence the command belongs to.
+    ServoTargets {
+        start_ms: u64,
+        duration_ms: u64,
+        positions: Vec<(ServoId, f32)>,
+        #[serde(default)]
+        easing: Easing,
+    },
 }
 
 /// Movement status
@@ -28,6 +61,96 @@ pub struct MovementStatus {
     pub is_moving: bool,
     pub last_command: Option<MovementCommand>,
     pub servo_positions: Vec<(ServoId, f32)>,
+    /// Progress of the running or last interpolation, 0.0 to 1.0
+    pub interpolation_progress: f32,
+}
+
+/// Sampling interval of a recording that doesn't set one
+pub const DEFAULT_SAMPLE_INTERVAL_MS: u64 = 100;
+
+/// Directory recorded movement sequences are saved to
+pub const SEQUENCE_DIR: &str = "sequences";
+
+/// Servo targets held `offset_ms` into a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceFrame {
+    pub offset_ms: u64,
+    pub positions: Vec<(ServoId, f32)>,
+}
+
+/// Servo targets recorded over time, replayed with `play_sequence`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovementSequence {
+    pub name: String,
+    pub interval_ms: u64,
+    pub frames: Vec<SequenceFrame>,
+}
+
+impl MovementSequence {
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.last().map_or(0, |frame| frame.offset_ms)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid movement sequence: {}", e))
+    }
+
+    /// Write the sequence to `<dir>/<name>.json`
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = named_json_path(dir, &self.name)?;
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        std::fs::write(&path, self.to_json()?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    /// Read the sequence saved as `name` in `dir`
+    pub fn load(dir: &Path, name: &str) -> Result<Self, String> {
+        let path = named_json_path(dir, name)?;
+        let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+}
+
+/// Samples the held servo targets every `interval_ms`. Only samples that
+/// differ from the previous frame are kept; playback holds each frame until
+/// the next one.
+#[derive(Debug, Clone)]
+pub struct MovementRecorder {
+    interval_ms: u64,
+    ticks: u64,
+    frames: Vec<SequenceFrame>,
+}
+
+impl MovementRecorder {
+    pub fn new(interval_ms: u64) -> Self {
+        Self { interval_ms, ticks: 0, frames: Vec::new() }
+    }
+
+    /// Record one sample. Returns true when it started a new frame.
+    pub fn sample(&mut self, positions: Vec<(ServoId, f32)>) -> bool {
+        let offset_ms = self.ticks * self.interval_ms;
+        self.ticks += 1;
+        let unchanged = self.frames.last().is_some_and(|last| last.positions == positions);
+        if unchanged || positions.is_empty() {
+            return false;
+        }
+        self.frames.push(SequenceFrame { offset_ms, positions });
+        true
+    }
+
+    pub fn finish(self, name: &str) -> MovementSequence {
+        MovementSequence { name: name.to_string(), interval_ms: self.interval_ms, frames: self.frames }
+    }
+}
+
+/// A recording in progress: the sampling task hands its recorder back when stopped
+struct Recording {
+    stop: oneshot::Sender<()>,
+    sampler: JoinHandle<MovementRecorder>,
 }
 
 /// TARS movement controller with personality integration
@@ -35,28 +158,138 @@ pub struct TARSMovementController<S: ServoControl> {
     servo_controller: Arc<S>,
     personality: Arc<TARSPersonality>,
     current_status: Arc<tokio::sync::Mutex<MovementStatus>>,
-    movement_speed: f32,
+    movement_speed: Arc<tokio::sync::Mutex<f32>>,
     is_enabled: Arc<tokio::sync::Mutex<bool>>,
+    pose_library: SharedPoseLibrary,
+    simulated: bool,
+    telemetry: Option<Arc<Telemetry>>,
+    kinematics: Option<Arc<LinkModel>>,
+    /// Latest position of every servo moved so far, for solving the link model
+    joint_positions: Arc<tokio::sync::Mutex<HashMap<ServoId, f32>>>,
+    /// Rate of the coordinated writes of a pose transition
+    tick_rate: Arc<tokio::sync::Mutex<TickRateTuner>>,
+    audit: bool,
+    /// Targets set by the running or most recent command
+    resolved_targets: Arc<tokio::sync::Mutex<Vec<(ServoId, f32)>>>,
+    /// Soft limits and collision constraints checked before any write
+    servo_config: Option<Arc<TARSServoConfig>>,
+    /// Bumped by an emergency stop; interpolations started before it give up
+    stop_epoch: Arc<AtomicU64>,
+    stop_notify: Arc<Notify>,
+    recording: tokio::sync::Mutex<Option<Recording>>,
+    /// Slows or halts movement while the Pi runs hot
+    thermal: Arc<tokio::sync::Mutex<ThermalGuard>>,
+    /// Controlled stops requested through it halt movement
+    safety: Option<SharedSafety>,
 }
 
 impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
     pub fn new(servo_controller: S, personality: TARSPersonality) -> Self {
+        Self::from_shared(Arc::new(servo_controller), personality)
+    }
+
+    /// Build a controller around a servo controller that is also used elsewhere.
+    pub fn from_shared(servo_controller: Arc<S>, personality: TARSPersonality) -> Self {
         let initial_status = MovementStatus {
             current_pose: "Neutral".to_string(),
             is_moving: false,
             last_command: None,
             servo_positions: vec![],
+            interpolation_progress: 1.0,
         };
 
         Self {
-            servo_controller: Arc::new(servo_controller),
+            servo_controller,
             personality: Arc::new(personality),
             current_status: Arc::new(tokio::sync::Mutex::new(initial_status)),
-            movement_speed: 1.0,
+            movement_speed: Arc::new(tokio::sync::Mutex::new(1.0)),
             is_enabled: Arc::new(tokio::sync::Mutex::new(true)),
+            pose_library: Arc::new(tokio::sync::RwLock::new(PoseLibrary::builtin())),
+            simulated: false,
+            telemetry: None,
+            kinematics: None,
+            joint_positions: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            tick_rate: Arc::new(tokio::sync::Mutex::new(TickRateTuner::new(TickRateConfig::default()))),
+            audit: false,
+            resolved_targets: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            servo_config: None,
+            stop_epoch: Arc::new(AtomicU64::new(0)),
+            stop_notify: Arc::new(Notify::new()),
+            recording: tokio::sync::Mutex::new(None),
+            thermal: Arc::new(tokio::sync::Mutex::new(ThermalGuard::default())),
+            safety: None,
         }
     }
 
+    /// Reject targets outside the servos' soft limits or that would make
+    /// two segments collide
+    pub fn with_servo_config(mut self, servo_config: TARSServoConfig) -> Self {
+        self.servo_config = Some(Arc::new(servo_config));
+        self
+    }
+
+    /// Mark the controller as driving simulated servos. Intended positions are
+    /// logged and command responses are tagged as simulated.
+    pub fn with_simulation(mut self, simulated: bool) -> Self {
+        self.simulated = simulated;
+        self
+    }
+
+    /// Broadcast servo targets over telemetry as they are applied.
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Include forward kinematics for `model` in telemetry snapshots.
+    pub fn with_kinematics(mut self, model: LinkModel) -> Self {
+        self.kinematics = Some(Arc::new(model));
+        self
+    }
+
+    /// Adapt the transition tick rate within `config`'s bounds.
+    pub fn with_tick_rate(mut self, config: TickRateConfig) -> Self {
+        self.tick_rate = Arc::new(tokio::sync::Mutex::new(TickRateTuner::new(config)));
+        self
+    }
+
+    /// React to the Pi's temperature with `guard`'s policy and limit.
+    pub fn with_thermal(mut self, guard: ThermalGuard) -> Self {
+        self.thermal = Arc::new(tokio::sync::Mutex::new(guard));
+        self
+    }
+
+    /// Honour `safety`'s controlled stop: a stop request cancels the
+    /// movement in progress, and only neutral and emergency stop run until
+    /// the stop is lifted.
+    pub fn with_safety(mut self, safety: SharedSafety) -> Self {
+        self.stop_notify = safety.stop_notify();
+        self.safety = Some(safety);
+        self
+    }
+
+    /// Current transition tick rate
+    pub async fn tick_rate_hz(&self) -> f32 {
+        self.tick_rate.lock().await.rate_hz()
+    }
+
+    /// Write every executed command to the audit log.
+    pub fn with_audit(mut self, audit: bool) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.simulated
+    }
+
+    /// Resolve named poses through a shared library so reloaded user poses
+    /// take effect without rebuilding the controller.
+    pub fn with_pose_library(mut self, pose_library: SharedPoseLibrary) -> Self {
+        self.pose_library = pose_library;
+        self
+    }
+
     /// Enable or disable movement
     pub async fn set_enabled(&self, enabled: bool) {
         let mut is_enabled = self.is_enabled.lock().await;
@@ -68,15 +301,90 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
         }
     }
 
+    /// Disable movement and bring every servo to neutral, cancelling any
+    /// movement in progress. Every emergency stop path ends up here.
+    pub async fn emergency_stop_all(&self) -> Result<String, String> {
+        *self.is_enabled.lock().await = false;
+        self.emergency_stop().await?;
+        Ok(self.personality.generate_movement_response("Emergency stop engaged. I hope that wasn't too dramatic."))
+    }
+
     /// Check if movement is enabled
     pub async fn is_enabled(&self) -> bool {
         *self.is_enabled.lock().await
     }
 
     /// Set movement speed (0.1 to 2.0)
-    pub fn set_movement_speed(&mut self, speed: f32) {
-        self.movement_speed = speed.clamp(0.1, 2.0);
-        debug!("Movement speed set to {}", self.movement_speed);
+    pub async fn set_movement_speed(&self, speed: f32) {
+        let speed = speed.clamp(0.1, 2.0);
+        *self.movement_speed.lock().await = speed;
+        debug!("Movement speed set to {}", speed);
+    }
+
+    pub async fn movement_speed(&self) -> f32 {
+        *self.movement_speed.lock().await
+    }
+
+    /// Movement speed after any thermal throttling
+    async fn effective_speed(&self) -> f32 {
+        let speed = self.movement_speed().await;
+        match self.thermal.lock().await.speed_limit() {
+            Some(limit) => speed.min(limit),
+            None => speed,
+        }
+    }
+
+    /// Apply the thermal policy to a metrics sample
+    pub async fn update_thermal(&self, metrics: &SystemMetrics) -> ThermalState {
+        self.update_temperature(metrics.temperature).await
+    }
+
+    /// Apply the thermal policy to a core temperature reading, warning when
+    /// movement is throttled or halted and when it recovers
+    pub async fn update_temperature(&self, temperature: f32) -> ThermalState {
+        let mut thermal = self.thermal.lock().await;
+        let state = match thermal.update(temperature) {
+            Some(state) => state,
+            None => return thermal.state(),
+        };
+        let message = thermal.message(temperature);
+        drop(thermal);
+        match state {
+            ThermalState::Normal => info!("{}", message),
+            _ => warn!("{}", message),
+        }
+        if let Some(telemetry) = &self.telemetry {
+            let frame = serde_json::json!({ "thermal": { "state": state, "temperature": temperature, "message": message } });
+            telemetry.broadcast(frame.to_string()).await;
+        }
+        state
+    }
+
+    pub async fn thermal_state(&self) -> ThermalState {
+        self.thermal.lock().await.state()
+    }
+
+    /// Reason of the controlled stop in force, if any
+    async fn controlled_stop(&self) -> Option<String> {
+        match &self.safety {
+            Some(safety) => safety.controlled_stop_reason().await,
+            None => None,
+        }
+    }
+
+    /// Changes on every emergency stop and every controlled stop request;
+    /// movements started under an older epoch give up
+    fn current_epoch(&self) -> u64 {
+        let requested = self.safety.as_ref().map_or(0, |safety| safety.stop_requests());
+        self.stop_epoch.load(Ordering::SeqCst) + requested
+    }
+
+    /// Why a movement started under an older epoch gave up
+    async fn cancellation(&self) -> String {
+        match self.controlled_stop().await {
+            Some(reason) => format!("Movement halted: {}", reason),
+            None => "Interpolation cancelled by emergency stop".to_string(),
+        }
     }
 
     /// Get current movement status
@@ -86,11 +394,178 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
 
     /// Execute a movement command with personality response
     pub async fn execute_command(&self, command: MovementCommand) -> Result<String, String> {
+        self.execute_command_from(command, MovementSource::Api).await
+    }
+
+    /// Execute a movement command, auditing it as coming from `source`
+    pub async fn execute_command_from(&self, command: MovementCommand, source: MovementSource) -> Result<String, String> {
         if !self.is_enabled().await {
             return Err("Movement is disabled. Safety protocols active.".to_string());
         }
+        // Getting to neutral or stopping is always allowed, even when hot
+        let essential = matches!(command, MovementCommand::Neutral | MovementCommand::EmergencyStop);
+        if !essential && self.thermal_state().await == ThermalState::Halted {
+            return Err("TARS: Movement halted until I cool down. Only neutral and emergency stop allowed, Cooper.".to_string());
+        }
+        if let Some(reason) = self.controlled_stop().await.filter(|_| !essential) {
+            return Err(format!("TARS: Movement halted: {}. Only neutral and emergency stop allowed, Cooper.", reason));
+        }
+
+        let timestamp = chrono::Utc::now();
+        self.resolved_targets.lock().await.clear();
+        events::publish(TarsEvent::MovementStarted { command: command.clone() });
+        let result = self.run_command(&command).await;
+        events::publish(TarsEvent::MovementFinished { command: command.clone(), success: result.is_ok() });
+        if self.audit {
+            let record = MovementRecord {
+                timestamp,
+                source,
+                command: command.clone(),
+                servo_targets: self.last_servo_targets().await,
+                success: result.is_ok(),
+                outcome: result.clone().unwrap_or_else(|e| e),
+            };
+            movement_audit::audit_movement(&record).await;
+        }
+        // Update status, also when a stop cut the command short
+        let mut status = self.current_status.lock().await;
+        status.is_moving = false;
+        let response = result?;
+        status.last_command = Some(command);
 
-        let response = match &command {
+        if self.simulated {
+            return Ok(format!("[simulated] {}", response));
+        }
+        Ok(response)
+    }
+
+    /// Move one servo, after checking it against the soft limits and the
+    /// positions the other servos hold
+    pub async fn set_servo_position(&self, servo_id: ServoId, position: f32) -> Result<(), String> {
+        if let Some(reason) = self.controlled_stop().await {
+            return Err(format!("Movement halted: {}", reason));
+        }
+        let target = [(servo_id, position)];
+        self.check_targets(&target).await?;
+        self.servo_controller.set_position(servo_id as u8, position).await?;
+        self.hold_positions(&target).await;
+        self.report_positions(&target).await;
+        Ok(())
+    }
+
+    /// Check `targets` together with the positions the other servos hold
+    async fn check_targets(&self, targets: &[(ServoId, f32)]) -> Result<(), String> {
+        let servo_config = match &self.servo_config {
+            Some(servo_config) => servo_config,
+            None => return Ok(()),
+        };
+        let mut positions = self.current_status.lock().await.servo_positions.clone();
+        positions.extend_from_slice(targets);
+        servo_config.check_positions(&positions).map_err(|e| format!("Movement rejected: {}", e))
+    }
+
+    /// Record where servos were moved, for status and later limit checks
+    async fn hold_positions(&self, positions: &[(ServoId, f32)]) {
+        let mut status = self.current_status.lock().await;
+        for (servo_id, position) in positions {
+            match status.servo_positions.iter_mut().find(|(id, _)| id == servo_id) {
+                Some(held) => held.1 = *position,
+                None => status.servo_positions.push((*servo_id, *position)),
+            }
+        }
+    }
+
+    /// Start sampling the held servo targets every `interval_ms`, e.g. while
+    /// TARS is posed by hand with `set_servo_position`
+    pub async fn start_recording(&self, interval_ms: u64) -> Result<(), String> {
+        if interval_ms == 0 {
+            return Err("Sampling interval must be at least 1 ms".to_string());
+        }
+        let mut recording = self.recording.lock().await;
+        if recording.is_some() {
+            return Err("A movement sequence is already being recorded".to_string());
+        }
+
+        let (stop, mut stopped) = oneshot::channel();
+        let status = self.current_status.clone();
+        let sampler = tokio::spawn(async move {
+            let mut recorder = MovementRecorder::new(interval_ms);
+            let mut ticks = tokio::time::interval(Duration::from_millis(interval_ms));
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {
+                        let positions = status.lock().await.servo_positions.clone();
+                        recorder.sample(positions);
+                    },
+                    _ = &mut stopped => break,
+                }
+            }
+            recorder
+        });
+        info!("Recording movement sequence every {} ms", interval_ms);
+        *recording = Some(Recording { stop, sampler });
+        Ok(())
+    }
+
+    /// Stop recording and return what was recorded under `name`, or None
+    /// when nothing was being recorded
+    pub async fn stop_recording(&self, name: &str) -> Option<MovementSequence> {
+        let Recording { stop, sampler } = self.recording.lock().await.take()?;
+        let _ = stop.send(());
+        match sampler.await {
+            Ok(recorder) => {
+                let sequence = recorder.finish(name);
+                info!("Recorded movement sequence '{}': {} frames over {} ms", name, sequence.frames.len(), sequence.duration_ms());
+                Some(sequence)
+            }
+            Err(e) => {
+                warn!("Movement recording failed: {}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn is_recording(&self) -> bool {
+        self.recording.lock().await.is_some()
+    }
+
+    /// Replay a recorded sequence: the first frame is reached over one
+    /// sampling interval and each later one over the time between the two
+    /// frames. Stops when movement is disabled or on an emergency stop.
+    pub async fn play_sequence(&self, sequence: &MovementSequence) -> Result<(), String> {
+        info!("Playing movement sequence '{}' ({} frames)", sequence.name, sequence.frames.len());
+        let epoch = self.current_epoch();
+        let mut previous_ms = None;
+        for frame in &sequence.frames {
+            if self.current_epoch() != epoch {
+                return Err(match self.controlled_stop().await {
+                    Some(reason) => format!("Playback aborted: {}", reason),
+                    None => "Playback aborted by emergency stop".to_string(),
+                });
+            }
+            let duration_ms = match previous_ms {
+                Some(previous_ms) => frame.offset_ms.saturating_sub(previous_ms),
+                None => sequence.interval_ms,
+            };
+            let command = MovementCommand::ServoTargets {
+                start_ms: frame.offset_ms,
+                duration_ms,
+                positions: frame.positions.clone(),
+                easing: Easing::Linear,
+            };
+            self.execute_command_from(command, MovementSource::Sequence).await?;
+            previous_ms = Some(frame.offset_ms);
+        }
+        Ok(())
+    }
+
+    /// Every target set by the running or most recent command, in order
+    pub async fn last_servo_targets(&self) -> Vec<(ServoId, f32)> {
+        self.resolved_targets.lock().await.clone()
+    }
+
+    async fn run_command(&self, command: &MovementCommand) -> Result<String, String> {
+        let response = match command {
             MovementCommand::StepForward => {
                 let personality_response = self.personality.generate_movement_response("Stepping forward, Cooper.");
                 self.step_forward().await?;
@@ -111,6 +586,11 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
                 self.execute_pose(pose_name).await?;
                 personality_response
             },
+            MovementCommand::EasedPose { name, duration_ms, easing } => {
+                let personality_response = self.personality.generate_movement_response(&format!("Executing {} pose. This better be important.", name));
+                self.execute_eased_pose(name, *duration_ms, *easing).await?;
+                personality_response
+            },
             MovementCommand::Neutral => {
                 let personality_response = self.personality.generate_movement_response("Returning to neutral position. Finally, some stability.");
                 self.neutral_pose().await?;
@@ -121,13 +601,11 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
                 self.emergency_stop().await?;
                 personality_response
             },
+            MovementCommand::ServoTargets { duration_ms, positions, easing, .. } => {
+                self.execute_servo_sequence(positions, *duration_ms, *easing).await?;
+                format!("Moved {} servos.", positions.len())
+            },
         };
-
-        // Update status
-        let mut status = self.current_status.lock().await;
-        status.last_command = Some(command);
-        status.is_moving = false;
-        
         Ok(response)
     }
 
@@ -148,7 +626,7 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
             (ServoId::LeftHipUpDown, -0.2),
         ];
         
-        self.execute_servo_sequence(&step_sequence, 400).await?;
+        self.execute_servo_sequence(&step_sequence, 400, Easing::EaseInOut).await?;
         
         // Phase 3: Move right leg forward
         let forward_sequence = vec![
@@ -157,7 +635,7 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
             (ServoId::LeftShoulderForwardBack, 0.2),
         ];
         
-        self.execute_servo_sequence(&forward_sequence, 500).await?;
+        self.execute_servo_sequence(&forward_sequence, 500, Easing::EaseInOut).await?;
         
         // Phase 4: Plant right foot, shift weight
         let plant_sequence = vec![
@@ -166,7 +644,7 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
             (ServoId::LeftHipUpDown, 0.0),
         ];
         
-        self.execute_servo_sequence(&plant_sequence, 400).await?;
+        self.execute_servo_sequence(&plant_sequence, 400, Easing::EaseInOut).await?;
         
         // Phase 5: Return to neutral
         sleep(Duration::from_millis(200)).await;
@@ -214,18 +692,27 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
 
     /// Execute a specific pose by name
     async fn execute_pose(&self, pose_name: &str) -> Result<(), String> {
-        info!("Executing pose: {}", pose_name);
+        self.execute_eased_pose(pose_name, None, Easing::Linear).await
+    }
+
+    /// Execute a pose by name over `duration_ms`, defaulting to the pose's own
+    async fn execute_eased_pose(&self, pose_name: &str, duration_ms: Option<u64>, easing: Easing) -> Result<(), String> {
+        info!("Executing pose: {} ({:?})", pose_name, easing);
         self.set_moving_status(true, pose_name).await;
 
-        let pose = match pose_name.to_lowercase().as_str() {
-            "neutral" => TARSPoses::neutral(),
-            "step_forward" | "step" => TARSPoses::step_forward_prep(),
-            "turn_left" | "left" => TARSPoses::turn_left(),
-            "turn_right" | "right" => TARSPoses::turn_right(),
-            _ => return Err(format!("Unknown pose: {}", pose_name)),
+        let library_name = match pose_name.to_lowercase().as_str() {
+            "step_forward" | "step" => TARSPoses::step_forward_prep().name,
+            "left" => TARSPoses::turn_left().name,
+            "right" => TARSPoses::turn_right().name,
+            _ => pose_name.to_string(),
         };
+        let pose = self.pose_library.read().await
+            .get(&library_name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown pose: {}", pose_name))?;
 
-        self.execute_movement_pose(&pose).await?;
+        let duration_ms = duration_ms.unwrap_or(pose.duration_ms);
+        self.execute_eased_movement_pose(&pose, duration_ms, easing).await?;
         self.set_moving_status(false, &format!("{} Complete", pose_name)).await;
         Ok(())
     }
@@ -241,9 +728,11 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
         Ok(())
     }
 
-    /// Emergency stop - immediately return to neutral
+    /// Emergency stop - cancel any interpolation and immediately return to neutral
     async fn emergency_stop(&self) -> Result<(), String> {
         warn!("Emergency stop activated");
+        self.stop_epoch.fetch_add(1, Ordering::SeqCst);
+        self.stop_notify.notify_waiters();
         self.set_moving_status(true, "Emergency Stop").await;
         
         // Quickly move all servos to neutral position
@@ -259,8 +748,10 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
             (ServoId::Head, 0.0),
         ];
 
+        self.resolved_targets.lock().await.extend(neutral_positions.iter().copied());
+
         // Execute all servo movements simultaneously for emergency stop
-        let futures = neutral_positions.into_iter().map(|(servo_id, position)| {
+        let futures = neutral_positions.iter().copied().map(|(servo_id, position)| {
             let servo_controller = self.servo_controller.clone();
             async move {
                 servo_controller.set_position(servo_id as u8, position).await
@@ -271,48 +762,132 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
             .into_iter()
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| format!("Emergency stop failed: {}", e))?;
+        self.hold_positions(&neutral_positions).await;
 
         self.set_moving_status(false, "Emergency Stop Complete").await;
         Ok(())
     }
 
-    /// Execute a movement pose with smooth interpolation
+    /// Execute a movement pose, blending into it over its duration
     async fn execute_movement_pose(&self, pose: &MovementPose) -> Result<(), String> {
+        self.execute_eased_movement_pose(pose, pose.duration_ms, Easing::Linear).await
+    }
+
+    async fn execute_eased_movement_pose(&self, pose: &MovementPose, duration_ms: u64, easing: Easing) -> Result<(), String> {
         debug!("Executing movement pose: {}", pose.name);
         
         // Calculate movement duration based on speed
-        let duration = Duration::from_millis((pose.duration_ms as f32 / self.movement_speed) as u64);
-        
-        // For smooth movement, we could implement interpolation here
-        // For now, we'll execute the pose directly with a delay
-        for (servo_id, position) in &pose.positions {
-            self.servo_controller.set_position(*servo_id as u8, *position).await
-                .map_err(|e| format!("Failed to set servo {}: {}", servo_id as u8, e))?;
-        }
-        
-        // Wait for movement to complete
-        sleep(duration).await;
-        
-        // Update status with current positions
-        let mut status = self.current_status.lock().await;
-        status.current_pose = pose.name.clone();
-        status.servo_positions = pose.positions.clone();
-        
+        let duration = Duration::from_millis((duration_ms as f32 / self.effective_speed().await) as u64);
+        self.transition_to(pose, duration, easing).await
+    }
+
+    /// Blend from the current servo positions into `pose` over `duration`,
+    /// following `easing`. A pose outside the soft limits or collision
+    /// constraints is rejected before anything is written.
+    pub async fn transition_to(&self, pose: &MovementPose, duration: Duration, easing: Easing) -> Result<(), String> {
+        self.check_targets(&pose.positions).await?;
+        self.resolved_targets.lock().await.extend(pose.positions.iter().copied());
+        self.interpolate(&pose.positions, duration, easing).await?;
+        self.current_status.lock().await.current_pose = pose.name.clone();
         Ok(())
     }
 
-    /// Execute a sequence of servo movements
-    async fn execute_servo_sequence(&self, sequence: &[(ServoId, f32)], duration_ms: u64) -> Result<(), String> {
-        for (servo_id, position) in sequence {
-            self.servo_controller.set_position(*servo_id as u8, *position).await
-                .map_err(|e| format!("Failed to set servo {}: {}", *servo_id as u8, e))?;
+    /// Move every servo in `targets` on the same ticks, so all of them
+    /// arrive together on the last tick however far each has to travel.
+    /// Servos not in `targets` hold position; a servo with no known position
+    /// goes straight to its target on the first tick. Ticks run at the tuned
+    /// tick rate, which each tick's measured write time feeds back into. An
+    /// emergency stop or a controlled stop request cancels the interpolation
+    /// between ticks.
+    async fn interpolate(&self, targets: &[(ServoId, f32)], duration: Duration, easing: Easing) -> Result<(), String> {
+        let epoch = self.current_epoch();
+        self.current_status.lock().await.interpolation_progress = 0.0;
+        let starts: Vec<f32> = {
+            let status = self.current_status.lock().await;
+            targets.iter()
+                .map(|(servo_id, target)| {
+                    status.servo_positions.iter()
+                        .find(|(id, _)| id == servo_id)
+                        .map_or(*target, |(_, position)| *position)
+                })
+                .collect()
+        };
+
+        let period = self.tick_rate.lock().await.period();
+        let ticks = (duration.as_micros() / period.as_micros().max(1)).max(1) as u64;
+        for tick in 1..=ticks {
+            // Registered before the epoch check so a stop in between still wakes it
+            let stopped = self.stop_notify.notified();
+            if self.current_epoch() != epoch {
+                return Err(self.cancellation().await);
+            }
+            let started = Instant::now();
+            let progress = tick as f32 / ticks as f32;
+            let positions: Vec<(ServoId, f32)> = targets.iter()
+                .zip(&starts)
+                .map(|((servo_id, target), start)| (*servo_id, easing.interpolate(*start, *target, progress)))
+                .collect();
+
+            let writes = positions.iter().map(|(servo_id, position)| {
+                let servo_controller = self.servo_controller.clone();
+                async move {
+                    servo_controller.set_position(*servo_id as u8, *position).await
+                        .map_err(|e| format!("Failed to set servo {}: {}", *servo_id as u8, e))
+                }
+            });
+            futures_util::future::join_all(writes).await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            self.report_positions(&positions).await;
+            self.hold_positions(&positions).await;
+            self.current_status.lock().await.interpolation_progress = progress;
+
+            let work = started.elapsed();
+            if let Some(rate_hz) = self.tick_rate.lock().await.record(work) {
+                info!("Servo tick rate adjusted to {:.1} Hz", rate_hz);
+            }
+            if tick < ticks {
+                tokio::select! {
+                    _ = sleep(period.saturating_sub(work)) => {},
+                    _ = stopped => return Err(self.cancellation().await),
+                }
+            }
         }
-        
-        let duration = Duration::from_millis((duration_ms as f32 / self.movement_speed) as u64);
-        sleep(duration).await;
         Ok(())
     }
 
+    /// Execute a sequence of servo movements, interpolating to the targets
+    /// and holding them for the rest of `duration_ms`
+    async fn execute_servo_sequence(&self, sequence: &[(ServoId, f32)], duration_ms: u64, easing: Easing) -> Result<(), String> {
+        self.check_targets(sequence).await?;
+        self.resolved_targets.lock().await.extend(sequence.iter().copied());
+        let started = Instant::now();
+        let duration = Duration::from_millis((duration_ms as f32 / self.effective_speed().await) as u64);
+        self.interpolate(sequence, duration, easing).await?;
+        sleep(duration.saturating_sub(started.elapsed())).await;
+        Ok(())
+    }
+
+    /// Log simulated targets and publish applied positions to telemetry
+    async fn report_positions(&self, positions: &[(ServoId, f32)]) {
+        if self.simulated {
+            info!("Simulated servo targets: {:?}", positions);
+        }
+        if let Some(telemetry) = &self.telemetry {
+            let servo_positions = positions.iter()
+                .map(|(servo_id, position)| (*servo_id as u8, *position))
+                .collect();
+            let mut snapshot = TelemetrySnapshot::new(self.simulated, servo_positions)
+                .with_tick_rate(self.tick_rate_hz().await);
+            if let Some(model) = &self.kinematics {
+                let mut joints = self.joint_positions.lock().await;
+                joints.extend(positions.iter().copied());
+                snapshot = snapshot.with_kinematics(model.solve(&joints));
+            }
+            telemetry.broadcast_snapshot(snapshot).await;
+        }
+    }
+
     /// Update movement status
     async fn set_moving_status(&self, is_moving: bool, pose: &str) {
         let mut status = self.current_status.lock().await;
@@ -333,14 +908,19 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
         ]
     }
 
-    /// Calibrate servos (move through full range)
+    /// Calibrate servos (move through the range within their soft limits),
+    /// starting from neutral
     pub async fn calibrate_servos(&self) -> Result<String, String> {
         if !self.is_enabled().await {
             return Err("Cannot calibrate - movement disabled".to_string());
         }
+        if let Some(reason) = self.controlled_stop().await {
+            return Err(format!("Cannot calibrate - movement halted: {}", reason));
+        }
 
         info!("Starting servo calibration");
         self.set_moving_status(true, "Calibrating").await;
+        self.neutral_pose().await?;
 
         let servos = [
             ServoId::RightHipForwardBack,
@@ -356,19 +936,22 @@ impl<S: ServoControl + Send + Sync + 'static> TARSMovementController<S> {
 
         for servo in &servos {
             debug!("Calibrating servo: {:?}", servo);
+            let (min_angle, max_angle) = self.servo_config.as_ref()
+                .and_then(|servo_config| servo_config.get_config(*servo))
+                .map_or((-1.0, 1.0), |config| (config.min_angle, config.max_angle));
             
             // Move to minimum position
-            self.servo_controller.set_position((*servo).into(), -1.0).await
+            self.set_servo_position(*servo, min_angle).await
                 .map_err(|e| format!("Calibration failed for servo {:?}: {}", servo, e))?;
             sleep(Duration::from_millis(500)).await;
             
             // Move to maximum position
-            self.servo_controller.set_position((*servo).into(), 1.0).await
+            self.set_servo_position(*servo, max_angle).await
                 .map_err(|e| format!("Calibration failed for servo {:?}: {}", servo, e))?;
             sleep(Duration::from_millis(500)).await;
             
             // Return to neutral
-            self.servo_controller.set_position((*servo).into(), 0.0).await
+            self.set_servo_position(*servo, 0.0).await
                 .map_err(|e| format!("Calibration failed for servo {:?}: {}", servo, e))?;
             sleep(Duration::from_millis(300)).await;
         }
@@ -389,19 +972,23 @@ mod tests {
     use super::*;
     use crate::robotics::pca9685_controller::{PCA9685Controller, MockI2C};
     use crate::personality::tars_core::PersonalitySettings;
+    use crate::robotics::servo_config::{MotionLimits, TARSServoConfig};
 
-    async fn create_test_controller() -> TARSMovementController<PCA9685Controller<MockI2C>> {
-        let servo_controller = PCA9685Controller::mock(50.0);
-        servo_controller.initialize().await.unwrap();
-        
+    fn test_personality() -> TARSPersonality {
         let settings = PersonalitySettings {
             humor: 75,
             honesty: 90,
             sarcasm: 30,
+            verbosity: 50,
         };
-        let personality = TARSPersonality::new(settings);
+        TARSPersonality::new(settings)
+    }
+
+    async fn create_test_controller() -> TARSMovementController<PCA9685Controller<MockI2C>> {
+        let servo_controller = PCA9685Controller::mock(50.0);
+        servo_controller.initialize().await.unwrap();
         
-        TARSMovementController::new(servo_controller, personality)
+        TARSMovementController::new(servo_controller, test_personality())
     }
 
     #[tokio::test]
@@ -469,4 +1056,209 @@ mod tests {
         let result = controller.execute_pose("invalid_pose").await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_user_pose_from_library() {
+        let mut library = PoseLibrary::builtin();
+        library.insert(MovementPose::new("Wave", vec![(ServoId::RightShoulderForwardBack, 0.8)], 10));
+        let controller = create_test_controller().await
+            .with_pose_library(Arc::new(tokio::sync::RwLock::new(library)));
+
+        assert!(controller.execute_pose("wave").await.is_ok());
+        let status = controller.get_status().await;
+        assert_eq!(status.servo_positions, vec![(ServoId::RightShoulderForwardBack, 0.8)]);
+    }
+
+    #[tokio::test]
+    async fn test_transition_brings_servos_in_together() {
+        // Without rate limits, so each tick is exactly one write per servo
+        let servo_config = || {
+            let mut servo_config = TARSServoConfig::new();
+            servo_config.set_limits(ServoId::Head, MotionLimits::unlimited());
+            servo_config.set_limits(ServoId::RightKnee, MotionLimits::unlimited());
+            servo_config
+        };
+        let i2c = MockI2C::new();
+        let servo_controller = PCA9685Controller::new(i2c.clone(), 50.0).with_servo_config(servo_config());
+        servo_controller.initialize().await.unwrap();
+        let controller = TARSMovementController::new(servo_controller, test_personality());
+
+        let start = vec![(ServoId::Head, 0.0), (ServoId::RightKnee, 0.0), (ServoId::LeftKnee, 0.3)];
+        controller.transition_to(&MovementPose::new("Start", start, 0), Duration::ZERO, Easing::Linear).await.unwrap();
+        let before = i2c.block_writes().await.len();
+
+        // The head travels five times as far as the knee
+        let target = MovementPose::new("Target", vec![(ServoId::Head, 1.0), (ServoId::RightKnee, 0.2)], 200);
+        controller.transition_to(&target, Duration::from_millis(200), Easing::Linear).await.unwrap();
+
+        let writes = i2c.block_writes().await.split_off(before);
+        let offs = |servo_id: ServoId| -> Vec<u16> {
+            let register = 0x06 + 4 * servo_id as u8;
+            writes.iter()
+                .filter(|(reg, _)| *reg == register)
+                .map(|(_, data)| u16::from_le_bytes([data[2], data[3]]))
+                .collect()
+        };
+        let config = servo_config();
+        let pwm = |servo_id: ServoId, angle: f32| config.get_config(servo_id).unwrap().angle_to_pwm(angle);
+
+        let (head, knee) = (offs(ServoId::Head), offs(ServoId::RightKnee));
+        assert_eq!(head.len(), 10);
+        assert_eq!(knee.len(), 10);
+        // Both reach their targets on the final tick, and not before
+        for (offs, target) in [(&head, pwm(ServoId::Head, 1.0)), (&knee, pwm(ServoId::RightKnee, 0.2))] {
+            assert_eq!(offs.iter().position(|off| *off == target), Some(9));
+        }
+        assert!(offs(ServoId::LeftKnee).is_empty(), "servos outside the pose hold position");
+
+        let status = controller.get_status().await;
+        assert_eq!(status.current_pose, "Target");
+        assert_eq!(status.servo_positions, vec![(ServoId::Head, 1.0), (ServoId::RightKnee, 0.2), (ServoId::LeftKnee, 0.3)]);
+    }
+
+    #[tokio::test]
+    async fn test_colliding_pose_is_rejected_before_any_pwm_write() {
+        // The replica's built-in hip/knee constraints, and a narrowed head
+        let mut servo_config = TARSServoConfig::new();
+        servo_config.set_soft_limits(ServoId::Head, -0.5, 0.5);
+        let i2c = MockI2C::new();
+        let servo_controller = PCA9685Controller::new(i2c.clone(), 50.0);
+        servo_controller.initialize().await.unwrap();
+        let mut library = PoseLibrary::builtin();
+        library.insert(MovementPose::new("Crouch", vec![(ServoId::RightHipUpDown, 0.8), (ServoId::RightKnee, 0.9)], 10));
+        let controller = TARSMovementController::new(servo_controller, test_personality())
+            .with_pose_library(Arc::new(tokio::sync::RwLock::new(library)))
+            .with_servo_config(servo_config);
+
+        // Pose playback
+        let error = controller.execute_command(MovementCommand::Pose("Crouch".to_string())).await.unwrap_err();
+        assert_eq!(
+            error,
+            "Movement rejected: RightHipUpDown at 0.80 and RightKnee at 0.90 would collide: right lower leg hits the hip housing"
+        );
+        assert!(i2c.block_writes().await.is_empty(), "nothing reached the board");
+
+        // Gait targets are checked against the positions other servos hold
+        controller.set_servo_position(ServoId::RightHipUpDown, 0.8).await.unwrap();
+        let writes = i2c.block_writes().await.len();
+        let targets = MovementCommand::ServoTargets { start_ms: 0, duration_ms: 0, positions: vec![(ServoId::RightKnee, 0.9)], easing: Easing::Linear };
+        assert!(controller.execute_command(targets).await.unwrap_err().contains("would collide"));
+        assert!(controller.set_servo_position(ServoId::RightKnee, 0.9).await.unwrap_err().contains("would collide"));
+        let error = controller.set_servo_position(ServoId::Head, 0.8).await.unwrap_err();
+        assert_eq!(error, "Movement rejected: Head at 0.80 is outside its soft limits -0.50 to 0.50");
+        assert_eq!(i2c.block_writes().await.len(), writes);
+
+        // The same knee angle is fine once the hip is lowered, and the built-in poses pass
+        controller.set_servo_position(ServoId::RightHipUpDown, 0.0).await.unwrap();
+        controller.set_servo_position(ServoId::RightKnee, 0.9).await.unwrap();
+        for pose in ["Neutral", "Turn Left", "Turn Right", "Step Forward Prep"] {
+            controller.execute_pose(pose).await.unwrap();
+        }
+        assert!(controller.execute_command(MovementCommand::StepForward).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_emergency_stop_cancels_an_eased_pose_mid_interpolation() {
+        let controller = create_test_controller().await;
+        let pose = MovementCommand::EasedPose { name: "turn_left".to_string(), duration_ms: Some(2000), easing: Easing::EaseInOut };
+
+        let (moved, progress) = tokio::join!(controller.execute_command(pose), async {
+            sleep(Duration::from_millis(200)).await;
+            let progress = controller.get_status().await.interpolation_progress;
+            controller.execute_command(MovementCommand::EmergencyStop).await.unwrap();
+            progress
+        });
+        assert!(progress > 0.0 && progress < 0.5, "eased progress {}", progress);
+        assert_eq!(moved.unwrap_err(), "Interpolation cancelled by emergency stop");
+        let status = controller.get_status().await;
+        assert_eq!(status.current_pose, "Emergency Stop Complete");
+        assert!(status.servo_positions.iter().all(|(_, position)| *position == 0.0));
+
+        // Uncancelled, it finishes on the pose
+        let pose = MovementCommand::EasedPose { name: "turn_left".to_string(), duration_ms: Some(100), easing: Easing::Cubic };
+        controller.execute_command(pose).await.unwrap();
+        let status = controller.get_status().await;
+        assert_eq!(status.interpolation_progress, 1.0);
+        for (servo_id, target) in TARSPoses::turn_left().positions {
+            assert!(status.servo_positions.contains(&(servo_id, target)), "{:?} at {}", servo_id, target);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorded_sequence_round_trips_and_playback_stops_on_emergency_stop() {
+        let controller = create_test_controller().await;
+        controller.start_recording(20).await.unwrap();
+        assert!(controller.start_recording(20).await.is_err());
+        controller.set_servo_position(ServoId::Head, 0.2).await.unwrap();
+        sleep(Duration::from_millis(70)).await;
+        controller.set_servo_position(ServoId::Head, -0.3).await.unwrap();
+        sleep(Duration::from_millis(70)).await;
+        let sequence = controller.stop_recording("nod").await.unwrap();
+        assert!(controller.stop_recording("nod").await.is_none());
+
+        let positions: Vec<_> = sequence.frames.iter().map(|frame| frame.positions.clone()).collect();
+        assert_eq!(positions, vec![vec![(ServoId::Head, 0.2)], vec![(ServoId::Head, -0.3)]]);
+        assert!(sequence.frames[0].offset_ms < sequence.frames[1].offset_ms);
+        assert!(sequence.frames.iter().all(|frame| frame.offset_ms % 20 == 0));
+
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let path = sequence.save(dir).unwrap();
+        assert_eq!(path, dir.join("nod.json"));
+        assert_eq!(MovementSequence::load(dir, "nod").unwrap(), sequence);
+
+        let player = create_test_controller().await;
+        player.play_sequence(&sequence).await.unwrap();
+        assert_eq!(player.get_status().await.servo_positions, vec![(ServoId::Head, -0.3)]);
+
+        // An emergency stop part way through aborts the rest
+        let mut slow = sequence.clone();
+        slow.frames[1].offset_ms = slow.frames[0].offset_ms + 2000;
+        slow.frames.push(SequenceFrame { offset_ms: 2500, positions: vec![(ServoId::Head, 0.5)] });
+        let (played, _) = tokio::join!(player.play_sequence(&slow), async {
+            sleep(Duration::from_millis(200)).await;
+            player.execute_command(MovementCommand::EmergencyStop).await.unwrap();
+        });
+        assert_eq!(played.unwrap_err(), "Interpolation cancelled by emergency stop");
+        assert_eq!(player.get_status().await.current_pose, "Emergency Stop Complete");
+
+        player.set_enabled(false).await;
+        assert!(player.play_sequence(&sequence).await.unwrap_err().contains("disabled"));
+    }
+
+    #[tokio::test]
+    async fn test_temperature_ramp_throttles_halts_and_recovers_with_hysteresis() {
+        use crate::robotics::thermal::{ThermalConfig, ThermalPolicy};
+
+        let throttling = ThermalConfig { hysteresis_c: 5.0, throttle_speed: 0.5, ..ThermalConfig::default() };
+        let controller = create_test_controller().await.with_thermal(ThermalGuard::new(throttling.clone(), 75.0));
+        controller.set_movement_speed(1.5).await;
+        let ramp = [60.0, 70.0, 74.9, 75.0, 80.0, 72.0, 70.1, 69.9, 74.0];
+        let mut states = Vec::new();
+        for temperature in ramp {
+            states.push(controller.update_temperature(temperature).await);
+            if temperature == 80.0 {
+                assert_eq!(controller.effective_speed().await, 0.5);
+            }
+        }
+        use ThermalState::{Normal, Throttled};
+        assert_eq!(states, vec![Normal, Normal, Normal, Throttled, Throttled, Throttled, Throttled, Normal, Normal]);
+        assert_eq!(controller.effective_speed().await, 1.5);
+
+        // Halt refuses everything but neutral and emergency stop until it cools
+        let halting = ThermalConfig { policy: ThermalPolicy::Halt, ..throttling.clone() };
+        let controller = create_test_controller().await.with_thermal(ThermalGuard::new(halting, 75.0));
+        assert_eq!(controller.update_temperature(78.0).await, ThermalState::Halted);
+        let error = controller.execute_command(MovementCommand::TurnLeft).await.unwrap_err();
+        assert!(error.contains("halted until I cool down"), "{}", error);
+        assert!(controller.execute_command(MovementCommand::Neutral).await.is_ok());
+        assert_eq!(controller.update_temperature(71.0).await, ThermalState::Halted);
+        assert_eq!(controller.update_temperature(69.0).await, ThermalState::Normal);
+        assert!(controller.execute_command(MovementCommand::TurnLeft).await.is_ok());
+
+        let ignoring = ThermalConfig { policy: ThermalPolicy::Ignore, ..throttling };
+        let controller = create_test_controller().await.with_thermal(ThermalGuard::new(ignoring, 75.0));
+        assert_eq!(controller.update_temperature(90.0).await, ThermalState::Normal);
+        assert_eq!(controller.effective_speed().await, 1.0);
+    }
 }
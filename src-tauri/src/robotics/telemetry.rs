@@ -1,61 +1,1000 @@
 //! Telemetry system for broadcasting robot state.
 
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{broadcast, Mutex};
+use tokio::sync::{broadcast, Mutex, Notify};
+use tokio::time::{sleep, Duration};
 use futures_util::{StreamExt, SinkExt};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
 use tokio_tungstenite::tungstenite::Message;
 
+use super::kinematics::KinematicPose;
+
+/// Channel of servo snapshots
+pub const SERVO_CHANNEL: &str = "servos";
+
+/// Channel of free-form text events without a prefix
+pub const EVENT_CHANNEL: &str = "events";
+
+/// Seconds of frames kept for reconnecting clients when not configured
+pub const DEFAULT_HISTORY_SECONDS: u64 = 30;
+
+/// Most frames the history holds however recent they are, so memory stays
+/// flat when frames arrive fast
+pub const MAX_HISTORY_FRAMES: usize = 2048;
+
+/// How long a connection may stay silent before it is closed when not
+/// configured: a WebSocket client that stops answering pings, or a socket
+/// that never sends a request
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// One broadcast frame in a recording (one JSON object per line).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Milliseconds since the Unix epoch when the frame was broadcast
+    pub timestamp_ms: u64,
+    pub data: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub critical: bool,
+}
+
+/// Structured servo state, the high-rate part of the telemetry stream.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetrySnapshot {
+    pub timestamp_ms: u64,
+    pub simulated: bool,
+    /// (servo channel, logical position)
+    pub servo_positions: Vec<(u8, f32)>,
+    /// Effective servo tick rate of the movement controller
+    #[serde(default)]
+    pub tick_rate_hz: Option<f32>,
+    /// Limb positions computed from every servo's latest position, for
+    /// rendering the pose. Must stay the last field for MessagePack.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kinematics: Option<KinematicPose>,
+}
+
+impl TelemetrySnapshot {
+    pub fn new(simulated: bool, servo_positions: Vec<(u8, f32)>) -> Self {
+        Self { timestamp_ms: now_ms(), simulated, servo_positions, tick_rate_hz: None, kinematics: None }
+    }
+
+    pub fn with_tick_rate(mut self, tick_rate_hz: f32) -> Self {
+        self.tick_rate_hz = Some(tick_rate_hz);
+        self
+    }
+
+    pub fn with_kinematics(mut self, kinematics: KinematicPose) -> Self {
+        self.kinematics = Some(kinematics);
+        self
+    }
+}
+
+/// A broadcast frame: free-form text events or a structured snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TelemetryFrame {
+    Text(String),
+    Snapshot(TelemetrySnapshot),
+    /// Safety events (e.g. emergency stop) that must reach every client.
+    Critical(String),
+}
+
+impl TelemetryFrame {
+    /// JSON/text form, used for the history, recordings and JSON clients.
+    pub fn to_text(&self) -> String {
+        match self {
+            TelemetryFrame::Text(text) | TelemetryFrame::Critical(text) => text.clone(),
+            TelemetryFrame::Snapshot(snapshot) => serde_json::to_string(snapshot).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_critical(&self) -> bool {
+        matches!(self, TelemetryFrame::Critical(_))
+    }
+
+    /// Channel clients subscribe to for this frame: `servos` for snapshots,
+    /// the key of a JSON object event (`{"power": ...}` is `power`), the
+    /// prefix of a `move:forward` style event, else `events`.
+    pub fn channel(&self) -> String {
+        let text = match self {
+            TelemetryFrame::Snapshot(_) => return SERVO_CHANNEL.to_string(),
+            TelemetryFrame::Text(text) | TelemetryFrame::Critical(text) => text,
+        };
+        if let Ok(serde_json::Value::Object(object)) = serde_json::from_str::<serde_json::Value>(text) {
+            if let Some(key) = object.keys().next() {
+                return key.to_lowercase();
+            }
+        }
+        match text.split_once(':') {
+            Some((prefix, _)) if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                prefix.to_lowercase()
+            }
+            _ => EVENT_CHANNEL.to_string(),
+        }
+    }
+
+    /// Inverse of `to_text`: text that parses as a snapshot becomes one.
+    pub fn from_text(text: String) -> Self {
+        match serde_json::from_str::<TelemetrySnapshot>(&text) {
+            Ok(snapshot) => TelemetryFrame::Snapshot(snapshot),
+            Err(_) => TelemetryFrame::Text(text),
+        }
+    }
+}
+
+/// Wire format for a WebSocket connection, chosen by the client with
+/// `?format=json` (default) or `?format=msgpack` on the connect URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TelemetryEncoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl TelemetryEncoding {
+    /// Pick the encoding from a URL query string; unknown values fall back to JSON.
+    pub fn from_query(query: Option<&str>) -> Self {
+        let format = query
+            .unwrap_or("")
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "format" || *key == "encoding")
+            .map(|(_, value)| value.to_ascii_lowercase());
+
+        match format.as_deref() {
+            Some("msgpack") | Some("messagepack") | Some("binary") => TelemetryEncoding::MessagePack,
+            _ => TelemetryEncoding::Json,
+        }
+    }
+
+    /// Encode a frame for this connection. Snapshots go out as binary
+    /// MessagePack messages; text events are always sent as text.
+    pub fn encode(&self, frame: &TelemetryFrame) -> Result<Message, String> {
+        match (self, frame) {
+            (TelemetryEncoding::MessagePack, TelemetryFrame::Snapshot(snapshot)) => {
+                encode_snapshot(snapshot).map(Message::Binary)
+            }
+            _ => Ok(Message::Text(frame.to_text())),
+        }
+    }
+}
+
+/// Compact MessagePack encoding of a snapshot (fields as a positional array).
+pub fn encode_snapshot(snapshot: &TelemetrySnapshot) -> Result<Vec<u8>, String> {
+    rmp_serde::to_vec(snapshot).map_err(|e| e.to_string())
+}
+
+pub fn decode_snapshot(bytes: &[u8]) -> Result<TelemetrySnapshot, String> {
+    rmp_serde::from_slice(bytes).map_err(|e| e.to_string())
+}
+
+/// Message a WebSocket client sends to receive only some channels, e.g.
+/// `{"subscribe":["servos","temperature"]}`. Until it sends one it gets
+/// every channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscribeRequest {
+    pub subscribe: Vec<String>,
+}
+
+/// Per-connection outbox. Critical frames queue up in order; everything
+/// else is conflated to the newest frame of each channel, so a client that
+/// cannot keep up gets the latest state instead of an ever-growing backlog. Frames outside
+/// the connection's channels are left out, except critical ones.
+pub struct FrameQueue {
+    pending: std::sync::Mutex<PendingFrames>,
+    /// None until the client subscribes, meaning every channel
+    channels: std::sync::Mutex<Option<BTreeSet<String>>>,
+    notify: Notify,
+    dropped: AtomicU64,
+    total_dropped: Arc<AtomicU64>,
+}
+
+#[derive(Default)]
+struct PendingFrames {
+    critical: VecDeque<TelemetryFrame>,
+    /// Newest unsent frame of each channel
+    latest: BTreeMap<String, TelemetryFrame>,
+}
+
+impl FrameQueue {
+    fn new(total_dropped: Arc<AtomicU64>) -> Self {
+        Self {
+            pending: std::sync::Mutex::new(PendingFrames::default()),
+            channels: std::sync::Mutex::new(None),
+            notify: Notify::new(),
+            dropped: AtomicU64::new(0),
+            total_dropped,
+        }
+    }
+
+    fn push(&self, frame: TelemetryFrame, channel: &str) {
+        let wanted = match &*self.channels.lock().unwrap() {
+            Some(channels) => channels.contains(channel),
+            None => true,
+        };
+        if !wanted && !frame.is_critical() {
+            return;
+        }
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if frame.is_critical() {
+                pending.critical.push_back(frame);
+            } else if pending.latest.insert(channel.to_string(), frame).is_some() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                self.total_dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.notify.notify_one();
+    }
+
+    /// Forward only `channels` from now on
+    pub fn set_channels(&self, channels: &[String]) {
+        let channels = channels.iter().map(|channel| channel.trim().to_lowercase()).collect();
+        *self.channels.lock().unwrap() = Some(channels);
+    }
+
+    /// Channels the connection subscribed to, None for all of them
+    pub fn channels(&self) -> Option<Vec<String>> {
+        self.channels.lock().unwrap().as_ref().map(|channels| channels.iter().cloned().collect())
+    }
+
+    /// Queue a reply to this connection alone, in order with critical frames
+    fn reply(&self, text: String) {
+        self.pending.lock().unwrap().critical.push_back(TelemetryFrame::Text(text));
+        self.notify.notify_one();
+    }
+
+    /// Next frame to send, critical frames first.
+    pub fn try_next(&self) -> Option<TelemetryFrame> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.critical.pop_front().or_else(|| pending.latest.pop_first().map(|(_, frame)| frame))
+    }
+
+    /// Wait for the next frame to send.
+    pub async fn next(&self) -> TelemetryFrame {
+        loop {
+            if let Some(frame) = self.try_next() {
+                return frame;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    /// Frames replaced before this connection could send them.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Ring buffer of the last `history_seconds` of frames, at most `capacity`
+struct FrameHistory {
+    history_seconds: u64,
+    capacity: usize,
+    frames: VecDeque<(u64, TelemetryFrame)>,
+}
+
+impl FrameHistory {
+    fn new(history_seconds: u64, capacity: usize) -> Self {
+        Self { history_seconds, capacity, frames: VecDeque::new() }
+    }
+
+    fn cutoff_ms(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.history_seconds * 1000)
+    }
+
+    fn push(&mut self, timestamp_ms: u64, frame: TelemetryFrame) {
+        self.frames.push_back((timestamp_ms, frame));
+        self.evict(timestamp_ms);
+    }
+
+    fn set_history_seconds(&mut self, history_seconds: u64, now_ms: u64) {
+        self.history_seconds = history_seconds;
+        self.evict(now_ms);
+    }
+
+    fn evict(&mut self, now_ms: u64) {
+        let cutoff = self.cutoff_ms(now_ms);
+        while self.frames.len() > self.capacity || self.frames.front().is_some_and(|(timestamp_ms, _)| *timestamp_ms < cutoff) {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Frames within the window at `now_ms`, oldest first
+    fn frames(&self, now_ms: u64) -> impl Iterator<Item = &(u64, TelemetryFrame)> {
+        let cutoff = self.cutoff_ms(now_ms);
+        self.frames.iter().filter(move |(timestamp_ms, _)| *timestamp_ms >= cutoff)
+    }
+}
+
 /// Container for telemetry operations.
 pub struct Telemetry {
-    tx: broadcast::Sender<String>,
-    log: Arc<Mutex<Vec<String>>>,
+    tx: broadcast::Sender<TelemetryFrame>,
+    log: Arc<Mutex<FrameHistory>>,
+    recorder: Arc<Mutex<Option<File>>>,
+    queues: std::sync::Mutex<Vec<Weak<FrameQueue>>>,
+    dropped_frames: Arc<AtomicU64>,
+    latest: std::sync::Mutex<Option<TelemetrySnapshot>>,
+    frames_sent: AtomicU64,
+    serving: AtomicBool,
+    idle_timeout_ms: AtomicU64,
+}
+
+/// Clears the serving flag when `serve` returns or is cancelled.
+struct ServingGuard<'a>(&'a AtomicBool);
+
+impl Drop for ServingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
 }
 
 impl Telemetry {
     pub fn new() -> Self {
         let (tx, _) = broadcast::channel(16);
-        Self { tx, log: Arc::new(Mutex::new(Vec::new())) }
+        Self {
+            tx,
+            log: Arc::new(Mutex::new(FrameHistory::new(DEFAULT_HISTORY_SECONDS, MAX_HISTORY_FRAMES))),
+            recorder: Arc::new(Mutex::new(None)),
+            queues: std::sync::Mutex::new(Vec::new()),
+            dropped_frames: Arc::new(AtomicU64::new(0)),
+            latest: std::sync::Mutex::new(None),
+            frames_sent: AtomicU64::new(0),
+            serving: AtomicBool::new(false),
+            idle_timeout_ms: AtomicU64::new(DEFAULT_IDLE_TIMEOUT.as_millis() as u64),
+        }
     }
 
-    /// Start the WebSocket server used by the dashboard.
-    pub async fn start_server(&self, addr: &str) -> Result<(), String> {
+    /// Start the WebSocket server used by the dashboard. The same port also
+    /// answers plain HTTP `GET /telemetry/latest` and `GET /telemetry/metrics`.
+    pub async fn start_server(self: &Arc<Self>, addr: &str) -> Result<(), String> {
         let listener = TcpListener::bind(addr).await.map_err(|e| e.to_string())?;
+        self.serve(listener).await;
+        Ok(())
+    }
+
+    /// Accept WebSocket and REST clients on an already bound listener.
+    pub async fn serve(self: &Arc<Self>, listener: TcpListener) {
+        self.serving.store(true, Ordering::SeqCst);
+        let _serving = ServingGuard(&self.serving);
         while let Ok((stream, _)) = listener.accept().await {
-            let tx = self.tx.clone();
-            let log = self.log.clone();
-            tokio::spawn(handle_connection(stream, tx, log));
+            let telemetry = self.clone();
+            tokio::spawn(async move { telemetry.handle_stream(stream).await });
         }
-        Ok(())
+    }
+
+    async fn handle_stream(&self, mut stream: TcpStream) {
+        let head = match tokio::time::timeout(self.idle_timeout(), peek_request_head(&stream)).await {
+            Ok(Some(head)) => head,
+            _ => return,
+        };
+        if head.to_ascii_lowercase().contains("upgrade: websocket") {
+            self.handle_connection(stream).await;
+            return;
+        }
+
+        // plain HTTP: consume the request head, answer, close
+        let mut consumed = vec![0u8; head.len()];
+        if stream.read_exact(&mut consumed).await.is_err() {
+            return;
+        }
+        let mut parts = head.split_whitespace();
+        let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let (status, content_type, body) = self.rest_response(method, path);
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
+    #[allow(clippy::result_large_err)] // handshake callback signature is fixed by tungstenite
+    async fn handle_connection(&self, stream: TcpStream) {
+        // negotiate the wire format from the connect URL
+        let mut encoding = TelemetryEncoding::Json;
+        let negotiate = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            encoding = TelemetryEncoding::from_query(request.uri().query());
+            Ok(response)
+        };
+
+        let idle_timeout = self.idle_timeout();
+        let ws_stream = match tokio::time::timeout(idle_timeout, tokio_tungstenite::accept_hdr_async(stream, negotiate)).await {
+            Ok(Ok(ws_stream)) => ws_stream,
+            _ => return,
+        };
+        let (mut write, mut read) = ws_stream.split();
+        // replay the buffered history first, then go live
+        let (history, queue) = self.subscribe_with_history().await;
+        let outbox = queue.clone();
+        let writer = tokio::spawn(async move {
+            for entry in &history {
+                if let Ok(message) = encoding.encode(entry) {
+                    let _ = write.send(message).await;
+                }
+            }
+            // ping at half the idle timeout so a listening client's pongs keep it alive
+            let mut ping = tokio::time::interval((idle_timeout / 2).max(Duration::from_millis(1)));
+            ping.tick().await;
+            loop {
+                let message = tokio::select! {
+                    frame = outbox.next() => match encoding.encode(&frame) {
+                        Ok(message) => message,
+                        Err(_) => continue,
+                    },
+                    _ = ping.tick() => Message::Ping(Vec::new()),
+                };
+                if write.send(message).await.is_err() {
+                    break;
+                }
+            }
+            if outbox.dropped() > 0 {
+                log::debug!("Telemetry client dropped {} conflated frames", outbox.dropped());
+            }
+        });
+        // the only message clients send is a channel subscription, besides pongs
+        while let Ok(Some(Ok(message))) = tokio::time::timeout(idle_timeout, read.next()).await {
+            if let Message::Text(text) = message {
+                match serde_json::from_str::<SubscribeRequest>(&text) {
+                    Ok(request) => {
+                        queue.set_channels(&request.subscribe);
+                        let channels = queue.channels().unwrap_or_default();
+                        queue.reply(serde_json::json!({ "subscribed": channels }).to_string());
+                    }
+                    Err(_) => log::debug!("Ignoring telemetry client message: {}", text),
+                }
+            }
+        }
+        writer.abort();
+    }
+
+    /// Status line, content type and body for a REST request.
+    fn rest_response(&self, method: &str, path: &str) -> (&'static str, &'static str, String) {
+        if method != "GET" {
+            return ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string());
+        }
+        match path.split('?').next().unwrap_or("") {
+            "/telemetry/latest" => match self.latest_snapshot() {
+                Some(snapshot) => (
+                    "200 OK",
+                    "application/json",
+                    TelemetryFrame::Snapshot(snapshot).to_text(),
+                ),
+                None => ("404 Not Found", "application/json", r#"{"error":"no telemetry yet"}"#.to_string()),
+            },
+            "/telemetry/metrics" => ("200 OK", "text/plain; version=0.0.4", self.prometheus_metrics()),
+            _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        }
+    }
+
+    /// Close connections that stay silent for `idle_timeout`
+    pub fn set_idle_timeout(&self, idle_timeout: Duration) {
+        self.idle_timeout_ms.store(idle_timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_millis(self.idle_timeout_ms.load(Ordering::Relaxed))
+    }
+
+    /// True while the server is accepting connections.
+    pub fn is_serving(&self) -> bool {
+        self.serving.load(Ordering::SeqCst)
+    }
+
+    /// Most recent servo snapshot, as last sent to WebSocket clients.
+    pub fn latest_snapshot(&self) -> Option<TelemetrySnapshot> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// Counters and the latest servo positions in Prometheus text format.
+    pub fn prometheus_metrics(&self) -> String {
+        let connections = self.queues.lock().unwrap().iter().filter(|q| q.strong_count() > 0).count();
+        let mut out = String::new();
+        out.push_str("# HELP tars_telemetry_frames_total Telemetry frames broadcast.\n");
+        out.push_str("# TYPE tars_telemetry_frames_total counter\n");
+        out.push_str(&format!("tars_telemetry_frames_total {}\n", self.frames_sent.load(Ordering::Relaxed)));
+        out.push_str("# HELP tars_telemetry_dropped_frames_total Frames conflated away for slow clients.\n");
+        out.push_str("# TYPE tars_telemetry_dropped_frames_total counter\n");
+        out.push_str(&format!("tars_telemetry_dropped_frames_total {}\n", self.dropped_frames()));
+        out.push_str("# HELP tars_telemetry_connections Connected WebSocket clients.\n");
+        out.push_str("# TYPE tars_telemetry_connections gauge\n");
+        out.push_str(&format!("tars_telemetry_connections {}\n", connections));
+
+        if let Some(snapshot) = self.latest_snapshot() {
+            out.push_str("# HELP tars_telemetry_last_snapshot_timestamp_ms Time of the latest servo snapshot.\n");
+            out.push_str("# TYPE tars_telemetry_last_snapshot_timestamp_ms gauge\n");
+            out.push_str(&format!("tars_telemetry_last_snapshot_timestamp_ms {}\n", snapshot.timestamp_ms));
+            out.push_str("# HELP tars_servo_position Latest logical servo position (-1 to 1).\n");
+            out.push_str("# TYPE tars_servo_position gauge\n");
+            for (channel, position) in &snapshot.servo_positions {
+                out.push_str(&format!("tars_servo_position{{channel=\"{}\"}} {}\n", channel, position));
+            }
+        }
+        out
     }
 
     /// Broadcast new telemetry data to listeners and log it.
     pub async fn broadcast(&self, data: String) {
-        let _ = self.tx.send(data.clone());
-        self.log.lock().await.push(data);
+        self.publish(TelemetryFrame::Text(data)).await;
     }
 
-    /// Replay logged data to a subscriber.
-    pub async fn replay(&self) -> Vec<String> {
-        self.log.lock().await.clone()
+    /// Broadcast a safety event that is never conflated away.
+    pub async fn broadcast_critical(&self, data: String) {
+        self.publish(TelemetryFrame::Critical(data)).await;
     }
-}
 
-async fn handle_connection(stream: TcpStream, tx: broadcast::Sender<String>, log: Arc<Mutex<Vec<String>>>) {
-    if let Ok(ws_stream) = tokio_tungstenite::accept_async(stream).await {
-        let (mut write, mut read) = ws_stream.split();
-        let mut rx = tx.subscribe();
-        // send historical data first
-        for entry in log.lock().await.iter() {
-            let _ = write.send(Message::Text(entry.clone())).await;
+    /// Broadcast structured servo state; binary clients receive it compactly.
+    pub async fn broadcast_snapshot(&self, snapshot: TelemetrySnapshot) {
+        self.publish(TelemetryFrame::Snapshot(snapshot)).await;
+    }
+
+    async fn publish(&self, frame: TelemetryFrame) {
+        if let Some(recorder) = self.recorder.lock().await.as_mut() {
+            let recorded = RecordedFrame {
+                timestamp_ms: now_ms(),
+                data: frame.to_text(),
+                critical: frame.is_critical(),
+            };
+            if let Ok(line) = serde_json::to_string(&recorded) {
+                if let Err(e) = recorder.write_all(format!("{}\n", line).as_bytes()).await {
+                    log::warn!("Failed to record telemetry frame: {}", e);
+                }
+            }
         }
-        tokio::spawn(async move {
-            while let Ok(msg) = rx.recv().await {
-                let _ = write.send(Message::Text(msg)).await;
+        self.emit(frame).await;
+    }
+
+    /// Send a frame to listeners and the history without recording it. The
+    /// history stays locked until listeners have the frame, so a connection
+    /// subscribing with `subscribe_with_history` sees it exactly once.
+    async fn emit(&self, frame: TelemetryFrame) {
+        let mut log = self.log.lock().await;
+        self.deliver(&frame);
+        log.push(now_ms(), frame);
+    }
+
+    /// Send a frame to listeners only
+    fn deliver(&self, frame: &TelemetryFrame) {
+        let channel = frame.channel();
+        self.queues.lock().unwrap().retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                queue.push(frame.clone(), &channel);
+                true
             }
+            None => false,
         });
-        // consume incoming messages, ignore them
-        while let Some(Ok(_)) = read.next().await {}
+        if let TelemetryFrame::Snapshot(snapshot) = frame {
+            *self.latest.lock().unwrap() = Some(snapshot.clone());
+        }
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+        let _ = self.tx.send(frame.clone());
+    }
+
+    /// Subscribe to every live frame in order (in-process listeners).
+    pub fn subscribe(&self) -> broadcast::Receiver<TelemetryFrame> {
+        self.tx.subscribe()
+    }
+
+    /// Subscribe with conflation, as a WebSocket connection does. The
+    /// subscription ends when the returned queue is dropped.
+    pub fn subscribe_conflated(&self) -> Arc<FrameQueue> {
+        let queue = Arc::new(FrameQueue::new(self.dropped_frames.clone()));
+        self.queues.lock().unwrap().push(Arc::downgrade(&queue));
+        queue
+    }
+
+    /// The buffered history, oldest first, and a conflated subscription to
+    /// every frame after it, with nothing missed or repeated in between
+    pub async fn subscribe_with_history(&self) -> (Vec<TelemetryFrame>, Arc<FrameQueue>) {
+        let log = self.log.lock().await;
+        let history = log.frames(now_ms()).map(|(_, frame)| frame.clone()).collect();
+        (history, self.subscribe_conflated())
+    }
+
+    /// Frames dropped by conflation across all connections.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Keep the last `history_seconds` of frames for new subscribers
+    pub async fn set_history_seconds(&self, history_seconds: u64) {
+        self.log.lock().await.set_history_seconds(history_seconds, now_ms());
+    }
+
+    pub async fn history_seconds(&self) -> u64 {
+        self.log.lock().await.history_seconds
     }
+
+    /// Buffered frames, oldest first, as replayed to new WebSocket clients
+    /// before live frames.
+    pub async fn get_history(&self) -> Vec<RecordedFrame> {
+        self.log.lock().await
+            .frames(now_ms())
+            .map(|(timestamp_ms, frame)| RecordedFrame {
+                timestamp_ms: *timestamp_ms,
+                data: frame.to_text(),
+                critical: frame.is_critical(),
+            })
+            .collect()
+    }
+
+    /// Text of the buffered frames, oldest first.
+    pub async fn history(&self) -> Vec<String> {
+        self.log.lock().await.frames(now_ms()).map(|(_, frame)| frame.to_text()).collect()
+    }
+
+    /// Record every broadcast frame to `path` as line-delimited JSON,
+    /// replacing any recording in progress.
+    pub async fn start_recording(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).await.map_err(|e| e.to_string())?;
+        self.stop_recording().await;
+        *self.recorder.lock().await = Some(file);
+        log::info!("Recording telemetry to {:?}", path);
+        Ok(())
+    }
+
+    pub async fn stop_recording(&self) {
+        if let Some(mut recorder) = self.recorder.lock().await.take() {
+            let _ = recorder.flush().await;
+        }
+    }
+
+    pub async fn is_recording(&self) -> bool {
+        self.recorder.lock().await.is_some()
+    }
+
+    /// Re-emit a recording to current subscribers at its original cadence,
+    /// scaled by `speed` (2.0 plays twice as fast). Replayed frames are not
+    /// added to the history. Returns the number of frames replayed.
+    pub async fn replay(&self, file: &Path, speed: f32) -> Result<usize, String> {
+        let frames = read_recording(file).await?;
+        let speed = if speed > 0.0 { speed } else { 1.0 };
+
+        let mut previous: Option<u64> = None;
+        for frame in &frames {
+            if let Some(previous) = previous {
+                let gap = frame.timestamp_ms.saturating_sub(previous) as f32 / speed;
+                sleep(Duration::from_millis(gap as u64)).await;
+            }
+            previous = Some(frame.timestamp_ms);
+            let replayed = if frame.critical {
+                TelemetryFrame::Critical(frame.data.clone())
+            } else {
+                TelemetryFrame::from_text(frame.data.clone())
+            };
+            self.deliver(&replayed);
+        }
+        Ok(frames.len())
+    }
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Load the frames of a recording made by `Telemetry::start_recording`.
+pub async fn read_recording(file: &Path) -> Result<Vec<RecordedFrame>, String> {
+    let content = tokio::fs::read_to_string(file).await.map_err(|e| e.to_string())?;
+    let mut frames = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str(line)
+            .map_err(|e| format!("Invalid telemetry frame on line {}: {}", number + 1, e))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Peek at the request head without consuming it, so a WebSocket upgrade can
+/// still be handed to the handshake. Gives up on requests larger than 8 KiB.
+async fn peek_request_head(stream: &TcpStream) -> Option<String> {
+    let mut buf = vec![0u8; 8192];
+    for _ in 0..200 {
+        let n = stream.peek(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        if let Some(end) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+            return Some(String::from_utf8_lossy(&buf[..end + 4]).into_owned());
+        }
+        if n == buf.len() {
+            return None;
+        }
+        sleep(Duration::from_millis(5)).await;
+    }
+    None
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_snapshot() -> TelemetrySnapshot {
+        TelemetrySnapshot {
+            timestamp_ms: 1_700_000_000_000,
+            simulated: true,
+            servo_positions: (0..9).map(|channel| (channel, channel as f32 * 0.1 - 0.4)).collect(),
+            tick_rate_hz: Some(50.0),
+            kinematics: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_preserves_order() {
+        let scratch = tempfile::tempdir().unwrap();
+        let path = scratch.path().join("telemetry.ndjson");
+        let telemetry = Telemetry::new();
+
+        telemetry.start_recording(&path).await.unwrap();
+        for frame in ["servo:1", "servo:2", "emergency_stop"] {
+            telemetry.broadcast(frame.to_string()).await;
+            sleep(Duration::from_millis(20)).await;
+        }
+        telemetry.stop_recording().await;
+
+        let recorded = read_recording(&path).await.unwrap();
+        assert_eq!(recorded.len(), 3);
+        assert!(recorded.windows(2).all(|w| w[0].timestamp_ms <= w[1].timestamp_ms));
+
+        let mut rx = telemetry.subscribe();
+        let replayed = telemetry.replay(&path, 4.0).await.unwrap();
+
+        assert_eq!(replayed, 3);
+        let mut frames = Vec::new();
+        while let Ok(frame) = rx.try_recv() {
+            frames.push(frame.to_text());
+        }
+        assert_eq!(frames, vec!["servo:1", "servo:2", "emergency_stop"]);
+        assert_eq!(telemetry.history().await, vec!["servo:1", "servo:2", "emergency_stop"], "replays aren't kept again");
+    }
+
+    #[tokio::test]
+    async fn test_subscription_picks_up_exactly_after_the_history() {
+        let telemetry = Telemetry::new();
+        telemetry.broadcast("servo:1".to_string()).await;
+
+        let (history, queue) = telemetry.subscribe_with_history().await;
+        telemetry.broadcast_critical("emergency_stop".to_string()).await;
+        assert_eq!(history, vec![TelemetryFrame::Text("servo:1".to_string())]);
+        assert_eq!(queue.next().await, TelemetryFrame::Critical("emergency_stop".to_string()));
+        assert!(queue.try_next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_silent_connections_are_closed_after_the_idle_timeout() {
+        let telemetry = Arc::new(Telemetry::new());
+        telemetry.set_idle_timeout(Duration::from_millis(100));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = telemetry.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        // A socket that never sends a request
+        let mut silent = TcpStream::connect(addr).await.unwrap();
+        let mut buf = Vec::new();
+        let closed = tokio::time::timeout(Duration::from_secs(2), silent.read_to_end(&mut buf)).await;
+        assert!(matches!(closed, Ok(Ok(0))), "{:?}", closed);
+
+        // A WebSocket client that stops reading never answers the pings
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/", addr)).await.unwrap();
+        sleep(Duration::from_millis(400)).await;
+        let ended = loop {
+            match tokio::time::timeout(Duration::from_secs(2), ws.next()).await {
+                Ok(Some(Ok(Message::Ping(_)))) => continue,
+                Ok(_) => break true,
+                Err(_) => break false,
+            }
+        };
+        assert!(ended, "the server should have closed the connection");
+
+        // One that keeps reading answers them and stays connected
+        let (mut listening, _) = tokio_tungstenite::connect_async(format!("ws://{}/", addr)).await.unwrap();
+        let until = tokio::time::Instant::now() + Duration::from_millis(400);
+        while let Ok(message) = tokio::time::timeout_at(until, listening.next()).await {
+            assert!(matches!(message, Some(Ok(Message::Ping(_)))), "{:?}", message);
+        }
+        telemetry.broadcast("servo:1".to_string()).await;
+        let frame = loop {
+            match listening.next().await.unwrap().unwrap() {
+                Message::Ping(_) => continue,
+                message => break message,
+            }
+        };
+        assert_eq!(frame.into_text().unwrap(), "servo:1");
+    }
+
+    #[test]
+    fn test_binary_snapshot_is_smaller_and_round_trips() {
+        let snapshot = sample_snapshot();
+        let json = TelemetryFrame::Snapshot(snapshot.clone()).to_text();
+        let binary = encode_snapshot(&snapshot).unwrap();
+
+        assert!(binary.len() < json.len(), "binary {} bytes vs json {} bytes", binary.len(), json.len());
+        assert_eq!(decode_snapshot(&binary).unwrap(), snapshot);
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_is_conflated_but_gets_critical_events() {
+        let telemetry = Telemetry::new();
+        let queue = telemetry.subscribe_conflated();
+
+        for i in 0..10 {
+            telemetry.broadcast(format!("frame:{}", i)).await;
+        }
+        telemetry.broadcast_critical("emergency_stop".to_string()).await;
+        for i in 10..15 {
+            telemetry.broadcast(format!("frame:{}", i)).await;
+        }
+
+        assert_eq!(queue.next().await, TelemetryFrame::Critical("emergency_stop".to_string()));
+        assert_eq!(queue.next().await, TelemetryFrame::Text("frame:14".to_string()));
+        assert!(queue.try_next().is_none());
+        assert_eq!(queue.dropped(), 14);
+        assert_eq!(telemetry.dropped_frames(), 14);
+
+        drop(queue);
+        telemetry.broadcast("after".to_string()).await;
+        assert!(telemetry.queues.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_keeps_the_newest_frame_of_each_channel() {
+        let telemetry = Telemetry::new();
+        let queue = telemetry.subscribe_conflated();
+        queue.set_channels(&["servos".to_string(), "temperature".to_string()]);
+
+        telemetry.broadcast(r#"{"temperature":{"celsius":40.0}}"#.to_string()).await;
+        telemetry.broadcast(r#"{"temperature":{"celsius":41.5}}"#.to_string()).await;
+        for _ in 0..5 {
+            telemetry.broadcast_snapshot(sample_snapshot()).await;
+        }
+
+        let mut frames = vec![queue.try_next().unwrap(), queue.try_next().unwrap()];
+        frames.sort_by_key(TelemetryFrame::channel);
+        assert_eq!(
+            frames,
+            vec![
+                TelemetryFrame::Snapshot(sample_snapshot()),
+                TelemetryFrame::Text(r#"{"temperature":{"celsius":41.5}}"#.to_string()),
+            ]
+        );
+        assert!(queue.try_next().is_none());
+        assert_eq!(queue.dropped(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_rest_latest_matches_websocket_frame() {
+        let telemetry = Arc::new(Telemetry::new());
+        telemetry.broadcast("move:forward".to_string()).await;
+        telemetry.broadcast_snapshot(sample_snapshot()).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = telemetry.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        // WebSocket client: the snapshot arrives as the second history frame
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/?format=json", addr)).await.unwrap();
+        ws.next().await.unwrap().unwrap();
+        let ws_frame = ws.next().await.unwrap().unwrap().into_text().unwrap();
+
+        let mut http = TcpStream::connect(addr).await.unwrap();
+        http.write_all(b"GET /telemetry/latest HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        http.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        assert_eq!(body, ws_frame);
+        assert_eq!(serde_json::from_str::<TelemetrySnapshot>(body).unwrap(), sample_snapshot());
+
+        let metrics = telemetry.prometheus_metrics();
+        assert!(metrics.contains("tars_telemetry_frames_total 2"));
+        assert!(metrics.contains("tars_servo_position{channel=\"8\"}"));
+    }
+
+    #[test]
+    fn test_encoding_negotiation() {
+        assert_eq!(TelemetryEncoding::from_query(None), TelemetryEncoding::Json);
+        assert_eq!(TelemetryEncoding::from_query(Some("format=json")), TelemetryEncoding::Json);
+        assert_eq!(TelemetryEncoding::from_query(Some("token=x&format=msgpack")), TelemetryEncoding::MessagePack);
+
+        let frame = TelemetryFrame::Snapshot(sample_snapshot());
+        assert!(matches!(TelemetryEncoding::MessagePack.encode(&frame).unwrap(), Message::Binary(_)));
+        assert!(matches!(TelemetryEncoding::Json.encode(&frame).unwrap(), Message::Text(_)));
+        let event = TelemetryFrame::Text("emergency_stop".to_string());
+        assert!(matches!(TelemetryEncoding::MessagePack.encode(&event).unwrap(), Message::Text(_)));
+    }
+
+    #[tokio::test]
+    async fn test_clients_with_different_subscriptions_get_disjoint_frames() {
+        let telemetry = Arc::new(Telemetry::new());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = telemetry.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let url = format!("ws://{}/?format=json", addr);
+        let (mut servos, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let (mut status, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let (mut everything, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        servos.send(Message::Text(r#"{"subscribe":["servos"]}"#.to_string())).await.unwrap();
+        status.send(Message::Text(r#"{"subscribe":["Power","move"]}"#.to_string())).await.unwrap();
+        let ack = |message: Option<Result<Message, _>>| message.unwrap().unwrap().into_text().unwrap();
+        assert_eq!(ack(servos.next().await), r#"{"subscribed":["servos"]}"#);
+        assert_eq!(ack(status.next().await), r#"{"subscribed":["move","power"]}"#);
+
+        let frames = [
+            TelemetryFrame::Snapshot(sample_snapshot()),
+            TelemetryFrame::Text(r#"{"power":{"percent":80.0}}"#.to_string()),
+            TelemetryFrame::Text("move:forward".to_string()),
+            TelemetryFrame::Text("calibrated".to_string()),
+        ];
+        for frame in &frames {
+            telemetry.emit(frame.clone()).await;
+            sleep(Duration::from_millis(30)).await;
+        }
+
+        type Client = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>;
+        async fn received(ws: &mut Client) -> Vec<String> {
+            let mut texts = Vec::new();
+            while let Ok(Some(Ok(message))) = tokio::time::timeout(Duration::from_millis(200), ws.next()).await {
+                texts.push(message.into_text().unwrap());
+            }
+            texts
+        }
+        let servo_frames = received(&mut servos).await;
+        let status_frames = received(&mut status).await;
+        assert_eq!(servo_frames, vec![frames[0].to_text()]);
+        assert_eq!(status_frames, vec![frames[1].to_text(), frames[2].to_text()]);
+        assert!(servo_frames.iter().all(|frame| !status_frames.contains(frame)));
+        // Without a subscription a client still gets every channel
+        assert_eq!(received(&mut everything).await, frames.iter().map(TelemetryFrame::to_text).collect::<Vec<_>>());
+        assert_eq!(frames[3].channel(), EVENT_CHANNEL);
+    }
+
+    #[test]
+    fn test_history_ring_drops_oldest_frames_when_full_or_expired() {
+        let text = |history: &FrameHistory, now_ms| history.frames(now_ms).map(|(_, frame)| frame.to_text()).collect::<Vec<_>>();
+        let start = 1_700_000_000_000;
+
+        // Filling past capacity drops the oldest frames first
+        let mut history = FrameHistory::new(10, 3);
+        for i in 0..5 {
+            history.push(start + i * 100, TelemetryFrame::Text(format!("servo:{}", i)));
+        }
+        assert_eq!(text(&history, start + 400), vec!["servo:2", "servo:3", "servo:4"]);
+
+        // Frames older than the window are dropped on the next push and
+        // hidden from reads even before then
+        history.push(start + 10_250, TelemetryFrame::Critical("emergency_stop".into()));
+        assert_eq!(text(&history, start + 10_250), vec!["servo:3", "servo:4", "emergency_stop"]);
+        assert_eq!(text(&history, start + 10_350), vec!["servo:4", "emergency_stop"]);
+        assert_eq!(history.frames.len(), 3);
+
+        // Shrinking the window trims what is already buffered
+        history.set_history_seconds(1, start + 10_350);
+        assert_eq!(text(&history, start + 10_350), vec!["emergency_stop"]);
+        assert_eq!(history.frames.len(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+//! Thermal protection for the servos. When the Pi's core temperature
+//! crosses its `RaspberryPiConfig::thermal_throttle_temp` the CPU starts
+//! throttling and servo writes get jittery, so movement slows down or stops
+//! depending on the policy. Movement comes back once the Pi has cooled a
+//! hysteresis margin below the limit, so a temperature hovering at the
+//! limit doesn't flip the state on every reading.
+
+use serde::{Deserialize, Serialize};
+
+use crate::raspberry_pi::RaspberryPiConfig;
+
+/// What movement does while the Pi is over its throttle temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThermalPolicy {
+    /// Keep moving at full speed
+    Ignore,
+    /// Cap movement speed at `ThermalConfig::throttle_speed`
+    #[default]
+    Throttle,
+    /// Refuse everything but returning to neutral and emergency stops
+    Halt,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThermalConfig {
+    pub policy: ThermalPolicy,
+    /// Degrees below the throttle temperature the Pi must cool to before
+    /// movement recovers
+    pub hysteresis_c: f32,
+    /// Movement speed cap while throttled
+    pub throttle_speed: f32,
+    /// How often the core temperature is read
+    pub poll_interval_ms: u64,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self { policy: ThermalPolicy::Throttle, hysteresis_c: 5.0, throttle_speed: 0.5, poll_interval_ms: 5000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThermalState {
+    #[default]
+    Normal,
+    Throttled,
+    Halted,
+}
+
+/// Tracks the thermal state from a series of temperature readings
+#[derive(Debug, Clone)]
+pub struct ThermalGuard {
+    config: ThermalConfig,
+    throttle_temp: f32,
+    state: ThermalState,
+}
+
+impl ThermalGuard {
+    pub fn new(config: ThermalConfig, throttle_temp: f32) -> Self {
+        Self { config, throttle_temp, state: ThermalState::Normal }
+    }
+
+    /// Throttle at the Pi's own thermal limit
+    pub fn for_pi(config: ThermalConfig, pi: &RaspberryPiConfig) -> Self {
+        Self::new(config, pi.thermal_throttle_temp)
+    }
+
+    pub fn state(&self) -> ThermalState {
+        self.state
+    }
+
+    pub fn throttle_temp(&self) -> f32 {
+        self.throttle_temp
+    }
+
+    /// Speed cap for the current state, if any
+    pub fn speed_limit(&self) -> Option<f32> {
+        match self.state {
+            ThermalState::Throttled => Some(self.config.throttle_speed),
+            _ => None,
+        }
+    }
+
+    /// Take a temperature reading. Returns the new state when it changed.
+    pub fn update(&mut self, temperature: f32) -> Option<ThermalState> {
+        let recover_below = self.throttle_temp - self.config.hysteresis_c.max(0.0);
+        let state = match self.state {
+            ThermalState::Normal if temperature >= self.throttle_temp => match self.config.policy {
+                ThermalPolicy::Ignore => ThermalState::Normal,
+                ThermalPolicy::Throttle => ThermalState::Throttled,
+                ThermalPolicy::Halt => ThermalState::Halted,
+            },
+            _ if temperature < recover_below => ThermalState::Normal,
+            state => state,
+        };
+        if state == self.state {
+            return None;
+        }
+        self.state = state;
+        Some(state)
+    }
+
+    /// What TARS says on entering the current state at `temperature`
+    pub fn message(&self, temperature: f32) -> String {
+        match self.state {
+            ThermalState::Throttled => format!(
+                "TARS: Core temperature {:.1}°C is past my {:.0}°C limit. Slowing the servos down until I cool off, Cooper.",
+                temperature, self.throttle_temp
+            ),
+            ThermalState::Halted => format!(
+                "TARS: Core temperature {:.1}°C is past my {:.0}°C limit. I'm holding still until I cool off, Cooper.",
+                temperature, self.throttle_temp
+            ),
+            ThermalState::Normal => format!(
+                "TARS: Cooled down to {:.1}°C. Servos back to normal. Relief setting: 100%.",
+                temperature
+            ),
+        }
+    }
+}
+
+impl Default for ThermalGuard {
+    fn default() -> Self {
+        Self::for_pi(ThermalConfig::default(), &RaspberryPiConfig::default_for_model(&RaspberryPiConfig::detect_model()))
+    }
+}
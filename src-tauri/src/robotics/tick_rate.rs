@@ -0,0 +1,134 @@
+//! Adaptive servo tick rate. Slower Pis can't always finish a tick's servo
+//! writes inside the tick period, which shows up as jitter; the tuner
+//! watches how long each tick's work really takes and trades smoothness for
+//! steadiness when deadlines keep slipping.
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+
+/// Fraction the rate drops by when too many ticks miss their deadline
+const LOWER_FACTOR: f32 = 0.8;
+
+/// Fraction the rate climbs by after a window with headroom on every tick
+const RAISE_FACTOR: f32 = 1.1;
+
+/// Bounds and sensitivity of the tick rate tuner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TickRateConfig {
+    pub min_hz: f32,
+    /// Also the starting rate
+    pub max_hz: f32,
+    /// Ticks measured before each adjustment
+    pub window_ticks: u32,
+    /// Share of missed deadlines in a window that lowers the rate
+    pub miss_ratio: f32,
+    /// A tick has headroom when its work took less than this share of the period
+    pub headroom_ratio: f32,
+}
+
+impl Default for TickRateConfig {
+    fn default() -> Self {
+        Self {
+            min_hz: 10.0,
+            max_hz: 50.0,
+            window_ticks: 25,
+            miss_ratio: 0.2,
+            headroom_ratio: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TickRateTuner {
+    config: TickRateConfig,
+    rate_hz: f32,
+    ticks: u32,
+    misses: u32,
+    headroom: u32,
+}
+
+impl TickRateTuner {
+    pub fn new(config: TickRateConfig) -> Self {
+        let min_hz = config.min_hz.max(1.0);
+        let config = TickRateConfig { min_hz, max_hz: config.max_hz.max(min_hz), window_ticks: config.window_ticks.max(1), ..config };
+        Self { rate_hz: config.max_hz, config, ticks: 0, misses: 0, headroom: 0 }
+    }
+
+    pub fn rate_hz(&self) -> f32 {
+        self.rate_hz
+    }
+
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.rate_hz)
+    }
+
+    /// Record how long one tick's work took. At the end of each window the
+    /// rate is lowered if too many ticks overran the period, or raised if
+    /// every tick left headroom. Returns the new rate when it changed.
+    pub fn record(&mut self, work: Duration) -> Option<f32> {
+        let period = self.period();
+        self.ticks += 1;
+        if work > period {
+            self.misses += 1;
+        } else if work.as_secs_f32() < period.as_secs_f32() * self.config.headroom_ratio {
+            self.headroom += 1;
+        }
+        if self.ticks < self.config.window_ticks {
+            return None;
+        }
+
+        let missed = self.misses as f32 / self.ticks as f32;
+        let proposed = if missed >= self.config.miss_ratio {
+            self.rate_hz * LOWER_FACTOR
+        } else if self.headroom == self.ticks {
+            self.rate_hz * RAISE_FACTOR
+        } else {
+            self.rate_hz
+        };
+        (self.ticks, self.misses, self.headroom) = (0, 0, 0);
+
+        let proposed = proposed.clamp(self.config.min_hz, self.config.max_hz);
+        if proposed == self.rate_hz {
+            return None;
+        }
+        self.rate_hz = proposed;
+        Some(proposed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_misses_lower_the_rate_and_headroom_raises_it() {
+        let mut tuner = TickRateTuner::new(TickRateConfig::default());
+        assert_eq!(tuner.rate_hz(), 50.0);
+        assert_eq!(tuner.period(), Duration::from_millis(20));
+
+        // Every tick's writes take 50ms, past the deadline at each rate below
+        let mut rate = tuner.rate_hz();
+        for _ in 0..3 {
+            let changes: Vec<f32> = (0..25).filter_map(|_| tuner.record(Duration::from_millis(50))).collect();
+            assert_eq!(changes.len(), 1, "one adjustment per window");
+            assert!(changes[0] < rate);
+            rate = changes[0];
+        }
+        assert!((rate - 25.6).abs() < 1e-3);
+
+        // Sustained misses bottom out at the configured minimum
+        (0..500).for_each(|_| { tuner.record(Duration::from_millis(200)); });
+        assert_eq!(tuner.rate_hz(), 10.0);
+
+        // Occasional slow ticks below the miss ratio hold the rate
+        for tick in 0..25 {
+            tuner.record(Duration::from_millis(if tick < 4 { 150 } else { 10 }));
+        }
+        assert_eq!(tuner.rate_hz(), 10.0);
+
+        // With headroom on every tick it climbs back up, but not past the maximum
+        (0..1000).for_each(|_| { tuner.record(Duration::from_millis(2)); });
+        assert_eq!(tuner.rate_hz(), 50.0);
+    }
+}
@@ -1,12 +1,19 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
+
+use crate::events::{self, TarsEvent};
 
 #[derive(Clone)]
 pub struct Safety {
     last_move: Arc<Mutex<Instant>>,
     last_watchdog: Arc<Mutex<Instant>>,
     emergency: Arc<Mutex<bool>>,
+    controlled_stop: Arc<Mutex<Option<String>>>,
+    /// Bumped by every controlled stop request
+    stop_requests: Arc<AtomicU64>,
+    stop_notify: Arc<Notify>,
     pub rate_limit: Duration,
     pub servo_min: f32,
     pub servo_max: f32,
@@ -21,6 +28,9 @@ impl Safety {
             last_move: Arc::new(Mutex::new(Instant::now())),
             last_watchdog: Arc::new(Mutex::new(Instant::now())),
             emergency: Arc::new(Mutex::new(false)),
+            controlled_stop: Arc::new(Mutex::new(None)),
+            stop_requests: Arc::new(AtomicU64::new(0)),
+            stop_notify: Arc::new(Notify::new()),
             rate_limit: Duration::from_millis(100),
             servo_min: -1.57,
             servo_max: 1.57,
@@ -43,14 +53,85 @@ impl Safety {
         pos >= self.servo_min && pos <= self.servo_max
     }
 
-    pub async fn trigger_emergency(&self) {
-        *self.emergency.lock().await = true;
+    /// Latch the emergency stop, announcing it on the event bus the first time
+    pub async fn trigger_emergency(&self, reason: &str) {
+        let already_latched = std::mem::replace(&mut *self.emergency.lock().await, true);
+        if !already_latched {
+            events::publish(TarsEvent::EmergencyStop { reason: reason.to_string() });
+        }
     }
 
     pub async fn is_emergency(&self) -> bool {
         *self.emergency.lock().await
     }
 
+    /// Halt the current movement without latching the emergency stop.
+    pub async fn request_controlled_stop(&self, reason: String) {
+        *self.controlled_stop.lock().await = Some(reason.clone());
+        self.stop_requests.fetch_add(1, Ordering::SeqCst);
+        self.stop_notify.notify_waiters();
+        events::publish(TarsEvent::ControlledStop { reason });
+    }
+
+    pub async fn controlled_stop_reason(&self) -> Option<String> {
+        self.controlled_stop.lock().await.clone()
+    }
+
+    /// How many controlled stops have been requested. A movement that sees
+    /// this change since it started gives up.
+    pub fn stop_requests(&self) -> u64 {
+        self.stop_requests.load(Ordering::SeqCst)
+    }
+
+    /// Notified on every controlled stop request
+    pub fn stop_notify(&self) -> Arc<Notify> {
+        self.stop_notify.clone()
+    }
+
+    /// Lift a controlled stop so movement can resume, returning its reason.
+    /// The emergency stop stays latched.
+    pub async fn clear_controlled_stop(&self) -> Option<String> {
+        self.controlled_stop.lock().await.take()
+    }
+
+    /// Lift the controlled stop only if it is still the one requested for
+    /// `reason`, leaving a stop someone else asked for in force.
+    pub async fn clear_controlled_stop_if(&self, reason: &str) -> bool {
+        let mut stop = self.controlled_stop.lock().await;
+        if stop.as_deref() == Some(reason) {
+            *stop = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether a movement may start now: refused while the emergency stop
+    /// is latched or a controlled stop is in force, and rate limited
+    pub async fn check_movement(&self) -> Result<(), String> {
+        if self.is_emergency().await {
+            return Err("Emergency stop engaged".into());
+        }
+        if let Some(reason) = self.controlled_stop_reason().await {
+            return Err(format!("Movement halted: {reason}"));
+        }
+        if !self.check_move_allowed().await {
+            return Err("Rate limited".into());
+        }
+        Ok(())
+    }
+
+    /// Resolves with the stop reason once a controlled stop is requested.
+    pub async fn wait_for_controlled_stop(&self) -> String {
+        loop {
+            let notified = self.stop_notify.notified();
+            if let Some(reason) = self.controlled_stop.lock().await.clone() {
+                return reason;
+            }
+            notified.await;
+        }
+    }
+
     pub async fn feed_watchdog(&self) {
         *self.last_watchdog.lock().await = Instant::now();
     }
@@ -62,8 +143,31 @@ pub fn start_watchdog(safety: SharedSafety) {
             tokio::time::sleep(safety.connection_timeout).await;
             let last = *safety.last_watchdog.lock().await;
             if Instant::now().duration_since(last) > safety.connection_timeout {
-                safety.trigger_emergency().await;
+                safety.trigger_emergency("Watchdog timeout").await;
             }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cleared_controlled_stop_lets_movement_resume() {
+        let mut safety = (*Safety::new()).clone();
+        safety.rate_limit = Duration::ZERO;
+
+        safety.request_controlled_stop("battery critical at 4%".to_string()).await;
+        assert_eq!(safety.check_movement().await.unwrap_err(), "Movement halted: battery critical at 4%");
+
+        assert_eq!(safety.clear_controlled_stop().await.as_deref(), Some("battery critical at 4%"));
+        assert!(safety.check_movement().await.is_ok());
+        assert_eq!(safety.clear_controlled_stop().await, None);
+
+        // Clearing a controlled stop never lifts the emergency stop
+        safety.trigger_emergency("test").await;
+        safety.clear_controlled_stop().await;
+        assert_eq!(safety.check_movement().await.unwrap_err(), "Emergency stop engaged");
+    }
+}
@@ -0,0 +1,401 @@
+//! Action scripts: one high-level command ("greet Cooper") runs a declared
+//! sequence of voice, movement and AI steps. Steps run in order; each step's
+//! `on_error` policy decides whether a failure aborts the rest of the script.
+//! Each step runs through the PDF prompt executor as a custom action.
+
+pub mod runtime;
+
+use crate::pdf_manager::{ActionContext, ActionExecutor, ActionType, ExecutionStep, PromptExecutor, StepResult};
+use crate::robotics::{Easing, MovementCommand, ServoId};
+use async_trait::async_trait;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+pub use runtime::AppRuntime;
+
+pub type SharedScriptLibrary = Arc<RwLock<ScriptLibrary>>;
+
+/// Custom action script steps run as in the prompt executor
+pub const SCRIPT_ACTION: &str = "action_script";
+
+/// What a script step does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ScriptAction {
+    /// Say a line through text-to-speech
+    Speak { text: String },
+    /// Run a movement macro: movement commands executed back to back
+    Move { commands: Vec<MovementCommand> },
+    /// Ask the AI, optionally speaking the answer
+    Ask {
+        prompt: String,
+        #[serde(default)]
+        speak_response: bool,
+    },
+    /// Write a line to the log
+    Log { message: String },
+    /// Pause before the next step
+    Wait { ms: u64 },
+}
+
+impl ScriptAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptAction::Speak { .. } => "speak",
+            ScriptAction::Move { .. } => "move",
+            ScriptAction::Ask { .. } => "ask",
+            ScriptAction::Log { .. } => "log",
+            ScriptAction::Wait { .. } => "wait",
+        }
+    }
+}
+
+/// What happens to the rest of the script when a step fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    Continue,
+    #[default]
+    Abort,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptStep {
+    #[serde(flatten)]
+    pub action: ScriptAction,
+    #[serde(default)]
+    pub on_error: ErrorPolicy,
+}
+
+impl ScriptStep {
+    pub fn new(action: ScriptAction, on_error: ErrorPolicy) -> Self {
+        Self { action, on_error }
+    }
+
+    /// The step as a prompt executor step, carrying the action as JSON
+    fn execution_step(&self, index: usize) -> Result<ExecutionStep, String> {
+        let action = serde_json::to_string(&self.action).map_err(|e| format!("Cannot encode step: {}", e))?;
+        Ok(ExecutionStep {
+            step_number: index as u32 + 1,
+            description: self.action.name().to_string(),
+            action_type: ActionType::Custom(SCRIPT_ACTION.to_string()),
+            parameters: HashMap::from([("action".to_string(), action)]),
+            expected_output: None,
+            status: crate::pdf_manager::StepStatus::Pending,
+        })
+    }
+}
+
+/// A named sequence of steps, started by its name or any trigger phrase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionScript {
+    pub name: String,
+    #[serde(default)]
+    pub triggers: Vec<String>,
+    pub steps: Vec<ScriptStep>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Completed,
+    Failed,
+    /// Not run because an earlier step failed under the abort policy
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepOutcome {
+    pub index: usize,
+    pub action: String,
+    pub status: StepStatus,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptRun {
+    pub script: String,
+    pub steps: Vec<StepOutcome>,
+    /// False when a failing step aborted the script
+    pub completed: bool,
+}
+
+/// The subsystems a script drives. `AppRuntime` is the real implementation;
+/// tests substitute their own.
+#[async_trait]
+pub trait ScriptRuntime: Send + Sync {
+    async fn speak(&self, text: &str) -> Result<(), String>;
+    async fn perform(&self, command: MovementCommand) -> Result<String, String>;
+    async fn ask(&self, prompt: &str) -> Result<String, String>;
+}
+
+impl ActionScript {
+    /// Run the steps in order. A failed step with `Abort` policy marks the
+    /// remaining steps Skipped; with `Continue` the next step runs anyway.
+    pub async fn run(&self, runtime: Arc<dyn ScriptRuntime>) -> Result<ScriptRun, String> {
+        info!("Running action script '{}'", self.name);
+        let mut executor = PromptExecutor::new().map_err(|e| e.to_string())?;
+        executor.register_custom_action(SCRIPT_ACTION, ScriptActionExecutor { script: self.name.clone(), runtime });
+        let mut steps = Vec::with_capacity(self.steps.len());
+        let mut aborted = false;
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let action = step.action.name().to_string();
+            if aborted {
+                steps.push(StepOutcome { index, action, status: StepStatus::Skipped, output: None, error: None });
+                continue;
+            }
+
+            match run_step(&executor, &self.name, step, index).await {
+                Ok(output) => {
+                    info!("[{}] step {} ({}) completed", self.name, index + 1, action);
+                    steps.push(StepOutcome { index, action, status: StepStatus::Completed, output, error: None });
+                }
+                Err(error) => {
+                    warn!("[{}] step {} ({}) failed: {}", self.name, index + 1, action, error);
+                    aborted = step.on_error == ErrorPolicy::Abort;
+                    steps.push(StepOutcome { index, action, status: StepStatus::Failed, output: None, error: Some(error) });
+                }
+            }
+        }
+
+        Ok(ScriptRun { script: self.name.clone(), steps, completed: !aborted })
+    }
+}
+
+/// Run one step through the prompt executor; its output, if any
+async fn run_step(executor: &PromptExecutor, script: &str, step: &ScriptStep, index: usize) -> Result<Option<String>, String> {
+    let result = executor.execute_custom_step(&step.execution_step(index)?, script).await.map_err(|e| e.to_string())?;
+    match result.status {
+        crate::pdf_manager::StepStatus::Failed => Err(result.error.unwrap_or_default()),
+        _ => Ok(Some(result.output).filter(|output| !output.is_empty())),
+    }
+}
+
+/// Performs `SCRIPT_ACTION` steps for the prompt executor
+struct ScriptActionExecutor {
+    script: String,
+    runtime: Arc<dyn ScriptRuntime>,
+}
+
+#[async_trait]
+impl ActionExecutor for ScriptActionExecutor {
+    async fn execute(&self, context: &ActionContext<'_>) -> Result<StepResult, String> {
+        let action: ScriptAction =
+            serde_json::from_str(context.param("action")?).map_err(|e| format!("Invalid script step: {}", e))?;
+        Ok(match perform(&self.script, &action, self.runtime.as_ref()).await {
+            Ok(output) => context.completed(output.unwrap_or_default()),
+            Err(error) => context.failed(error),
+        })
+    }
+}
+
+async fn perform(script: &str, action: &ScriptAction, runtime: &dyn ScriptRuntime) -> Result<Option<String>, String> {
+    match action {
+        ScriptAction::Speak { text } => runtime.speak(text).await.map(|_| Some(text.clone())),
+        ScriptAction::Move { commands } => {
+            let mut results = Vec::with_capacity(commands.len());
+            for command in commands {
+                results.push(runtime.perform(command.clone()).await?);
+            }
+            Ok(Some(results.join("; ")))
+        }
+        ScriptAction::Ask { prompt, speak_response } => {
+            let answer = runtime.ask(prompt).await?;
+            if *speak_response {
+                runtime.speak(&answer).await?;
+            }
+            Ok(Some(answer))
+        }
+        ScriptAction::Log { message } => {
+            info!("[{}] {}", script, message);
+            Ok(None)
+        }
+        ScriptAction::Wait { ms } => {
+            tokio::time::sleep(Duration::from_millis(*ms)).await;
+            Ok(None)
+        }
+    }
+}
+
+/// Head down, up, back to center
+fn head_nod() -> Vec<MovementCommand> {
+    [0.4, -0.2, 0.0]
+        .into_iter()
+        .map(|position| MovementCommand::ServoTargets { start_ms: 0, duration_ms: 250, positions: vec![(ServoId::Head, position)], easing: Easing::Linear })
+        .collect()
+}
+
+fn normalize(phrase: &str) -> String {
+    phrase
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Built-in scripts plus any loaded from a scripts file
+#[derive(Debug, Clone)]
+pub struct ScriptLibrary {
+    scripts: Vec<ActionScript>,
+}
+
+impl ScriptLibrary {
+    pub fn builtin() -> Self {
+        let greet = ActionScript {
+            name: "greet cooper".to_string(),
+            triggers: vec!["hello tars".to_string(), "say hi to cooper".to_string()],
+            steps: vec![
+                ScriptStep::new(
+                    ScriptAction::Speak { text: "Hello, Cooper. Honesty setting at 90 percent, humor at 75.".to_string() },
+                    ErrorPolicy::Continue,
+                ),
+                ScriptStep::new(ScriptAction::Move { commands: head_nod() }, ErrorPolicy::Abort),
+                ScriptStep::new(ScriptAction::Log { message: "Greeted Cooper".to_string() }, ErrorPolicy::Continue),
+            ],
+        };
+        Self { scripts: vec![greet] }
+    }
+
+    /// Built-in scripts overlaid with those in a JSON file (a list of
+    /// scripts). A missing file is not an error.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut library = Self::builtin();
+        if !path.exists() {
+            return Ok(library);
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let scripts: Vec<ActionScript> =
+            serde_json::from_str(&content).map_err(|e| format!("Invalid scripts file {}: {}", path.display(), e))?;
+        info!("Loaded {} action scripts from {:?}", scripts.len(), path);
+        for script in scripts {
+            library.insert(script);
+        }
+        Ok(library)
+    }
+
+    /// Add a script, replacing any existing script with the same name.
+    pub fn insert(&mut self, script: ActionScript) {
+        let key = normalize(&script.name);
+        match self.scripts.iter_mut().find(|s| normalize(&s.name) == key) {
+            Some(existing) => *existing = script,
+            None => self.scripts.push(script),
+        }
+    }
+
+    /// Find the script whose name or trigger matches the command, ignoring
+    /// case, punctuation and extra whitespace.
+    pub fn find(&self, command: &str) -> Option<&ActionScript> {
+        let key = normalize(command);
+        self.scripts
+            .iter()
+            .find(|script| std::iter::once(&script.name).chain(&script.triggers).any(|phrase| normalize(phrase) == key))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.scripts.iter().map(|script| script.name.clone()).collect()
+    }
+}
+
+impl Default for ScriptLibrary {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records calls in order; speaking fails when `speech_fails` is set
+    #[derive(Default)]
+    struct RecordingRuntime {
+        speech_fails: bool,
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ScriptRuntime for RecordingRuntime {
+        async fn speak(&self, text: &str) -> Result<(), String> {
+            self.calls.lock().unwrap().push(format!("speak {}", text));
+            if self.speech_fails {
+                Err("audio device unavailable".to_string())
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn perform(&self, command: MovementCommand) -> Result<String, String> {
+            self.calls.lock().unwrap().push(format!("move {:?}", command));
+            Ok("ok".to_string())
+        }
+
+        async fn ask(&self, prompt: &str) -> Result<String, String> {
+            self.calls.lock().unwrap().push(format!("ask {}", prompt));
+            Ok("42".to_string())
+        }
+    }
+
+    fn speak_then_move(policy: ErrorPolicy) -> ActionScript {
+        ActionScript {
+            name: "greet".to_string(),
+            triggers: Vec::new(),
+            steps: vec![
+                ScriptStep::new(ScriptAction::Speak { text: "Hello".to_string() }, policy),
+                ScriptStep::new(ScriptAction::Move { commands: vec![MovementCommand::Neutral] }, policy),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_steps_run_in_order() {
+        let runtime = Arc::new(RecordingRuntime::default());
+        let run = speak_then_move(ErrorPolicy::Abort).run(runtime.clone()).await.unwrap();
+
+        assert!(run.completed);
+        assert_eq!(*runtime.calls.lock().unwrap(), vec!["speak Hello", "move Neutral"]);
+        assert!(run.steps.iter().all(|step| step.status == StepStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_failed_speech_aborts_move() {
+        let runtime = Arc::new(RecordingRuntime { speech_fails: true, ..Default::default() });
+        let run = speak_then_move(ErrorPolicy::Abort).run(runtime.clone()).await.unwrap();
+
+        assert!(!run.completed);
+        assert_eq!(*runtime.calls.lock().unwrap(), vec!["speak Hello"]);
+        assert_eq!(run.steps[0].status, StepStatus::Failed);
+        assert_eq!(run.steps[0].error.as_deref(), Some("audio device unavailable"));
+        assert_eq!(run.steps[1].status, StepStatus::Skipped);
+
+        // the same failure under the continue policy still moves
+        let runtime = Arc::new(RecordingRuntime { speech_fails: true, ..Default::default() });
+        let run = speak_then_move(ErrorPolicy::Continue).run(runtime).await.unwrap();
+        assert!(run.completed);
+        assert_eq!(run.steps[1].status, StepStatus::Completed);
+    }
+
+    #[test]
+    fn test_scripts_are_declarative() {
+        let json = r#"[{"name": "nod", "triggers": ["Nod, TARS!"], "steps": [
+            {"action": "speak", "text": "Affirmative"},
+            {"action": "move", "commands": [{"Pose": "Step Forward"}, "Neutral"], "on_error": "continue"}
+        ]}]"#;
+        let scripts: Vec<ActionScript> = serde_json::from_str(json).unwrap();
+        let mut library = ScriptLibrary::builtin();
+        library.insert(scripts[0].clone());
+
+        let script = library.find("nod tars").unwrap();
+        assert_eq!(script.steps.len(), 2);
+        assert_eq!(script.steps[0].on_error, ErrorPolicy::Abort);
+        assert_eq!(script.steps[1].on_error, ErrorPolicy::Continue);
+        assert!(library.find("Greet Cooper").is_some());
+    }
+}
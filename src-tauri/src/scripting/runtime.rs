@@ -0,0 +1,47 @@
+//! Script runtime backed by the app's voice, servo and AI subsystems.
+
+use super::ScriptRuntime;
+use crate::ai::router::{self, LlmSource};
+use crate::robotics::{MovementCommand, MovementSource, SharedServoSystem};
+use crate::voice::text_to_speech::{speak_with_request, SpeechContext, SpeechPriority, SpeechRequest};
+use async_trait::async_trait;
+
+pub struct AppRuntime {
+    servo_system: SharedServoSystem,
+    use_cloud: bool,
+}
+
+impl AppRuntime {
+    pub fn new(servo_system: SharedServoSystem, use_cloud: bool) -> Self {
+        Self { servo_system, use_cloud }
+    }
+}
+
+#[async_trait]
+impl ScriptRuntime for AppRuntime {
+    async fn speak(&self, text: &str) -> Result<(), String> {
+        let request = SpeechRequest {
+            text: text.to_string(),
+            priority: SpeechPriority::Normal,
+            context: SpeechContext::Conversation,
+            emotional_state: None,
+            override_settings: None,
+        };
+        speak_with_request(request).await.map(|_| ())
+    }
+
+    async fn perform(&self, command: MovementCommand) -> Result<String, String> {
+        let movement = self
+            .servo_system
+            .read()
+            .await
+            .movement_controller()
+            .ok_or("Servo system not initialized")?;
+        movement.execute_command_from(command, MovementSource::Script).await
+    }
+
+    async fn ask(&self, prompt: &str) -> Result<String, String> {
+        let source = if self.use_cloud { LlmSource::Cloud } else { LlmSource::Local };
+        Ok(router::get_tars_response(source, prompt, "action script").await)
+    }
+}
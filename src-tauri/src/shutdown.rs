@@ -0,0 +1,149 @@
+//! Graceful shutdown: subsystems register hooks at startup and the
+//! coordinator runs them all, once, when the app is asked to exit.
+
+use futures_util::future::{join_all, BoxFuture};
+use log::{info, warn};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// How long `shutdown` waits for hooks before giving up on them.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type SharedShutdown = Arc<ShutdownCoordinator>;
+
+type ShutdownHook = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+/// Hooks that finished and hooks still running when the timeout expired.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShutdownReport {
+    pub completed: Vec<String>,
+    pub timed_out: Vec<String>,
+}
+
+pub struct ShutdownCoordinator {
+    hooks: Mutex<Vec<(String, ShutdownHook)>>,
+    tx: broadcast::Sender<()>,
+    triggered: AtomicBool,
+    timeout: Duration,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(timeout: Duration) -> Self {
+        let (tx, _) = broadcast::channel(1);
+        Self {
+            hooks: Mutex::new(Vec::new()),
+            tx,
+            triggered: AtomicBool::new(false),
+            timeout,
+        }
+    }
+
+    /// Register a hook to run on shutdown. Hooks run concurrently.
+    pub fn register<F, Fut>(&self, name: &str, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let hook: ShutdownHook = Box::new(move || Box::pin(hook()));
+        self.hooks.lock().unwrap().push((name.to_string(), hook));
+    }
+
+    /// Receives a message when shutdown starts, for long-running loops.
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.tx.subscribe()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+
+    /// Broadcast shutdown and wait for every registered hook, up to the
+    /// timeout. Only the first call runs the hooks; later calls return an
+    /// empty report.
+    pub async fn shutdown(&self) -> ShutdownReport {
+        if self.triggered.swap(true, Ordering::SeqCst) {
+            return ShutdownReport::default();
+        }
+        info!("Shutting down subsystems");
+        let _ = self.tx.send(());
+
+        let hooks: Vec<_> = self.hooks.lock().unwrap().drain(..).collect();
+        let tasks: Vec<_> = hooks
+            .into_iter()
+            .map(|(name, hook)| (name, tokio::spawn(hook())))
+            .collect();
+
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let results = join_all(tasks.into_iter().map(|(name, mut task)| async move {
+            match tokio::time::timeout_at(deadline, &mut task).await {
+                Ok(_) => Ok(name),
+                Err(_) => {
+                    task.abort();
+                    Err(name)
+                }
+            }
+        }))
+        .await;
+
+        let mut report = ShutdownReport::default();
+        for result in results {
+            match result {
+                Ok(name) => report.completed.push(name),
+                Err(name) => {
+                    warn!("Shutdown hook '{}' timed out", name);
+                    report.timed_out.push(name);
+                }
+            }
+        }
+        report
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SHUTDOWN_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_each_hook_runs_exactly_once() {
+        let coordinator = ShutdownCoordinator::default();
+        let counters: Vec<Arc<AtomicUsize>> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        for (i, counter) in counters.iter().enumerate() {
+            let counter = counter.clone();
+            coordinator.register(&format!("hook{}", i), move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        let mut rx = coordinator.subscribe();
+
+        let report = coordinator.shutdown().await;
+        assert_eq!(report.completed.len(), 3);
+        assert!(rx.try_recv().is_ok());
+
+        // a second signal must not run the hooks again
+        assert_eq!(coordinator.shutdown().await, ShutdownReport::default());
+        for counter in &counters {
+            assert_eq!(counter.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_hook_times_out() {
+        let coordinator = ShutdownCoordinator::new(Duration::from_millis(50));
+        coordinator.register("fast", || async {});
+        coordinator.register("slow", || tokio::time::sleep(Duration::from_secs(10)));
+
+        let report = coordinator.shutdown().await;
+        assert_eq!(report.completed, vec!["fast"]);
+        assert_eq!(report.timed_out, vec!["slow"]);
+    }
+}
@@ -4,6 +4,9 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{RwLock, Mutex};
 use once_cell::sync::Lazy;
+use crate::events::{self, TarsEvent};
+use crate::raspberry_pi::{PiModel, RaspberryPiConfig};
+use super::realtime_processing::{QualityLevel, StreamManager};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdvancedTTSEngine {
@@ -14,6 +17,42 @@ pub struct AdvancedTTSEngine {
     pub streaming_enabled: bool,
     pub quality_mode: QualityMode,
     pub voice_models_path: PathBuf,
+    #[serde(default)]
+    pub hardware: HardwareVoiceSettings,
+    /// User choices that win over the hardware defaults
+    #[serde(default)]
+    pub overrides: VoiceOverrides,
+}
+
+/// Voice quality and effects suited to the hardware TARS runs on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareVoiceSettings {
+    pub quality_level: QualityLevel,
+    pub sample_rate: u32,
+    /// FFT equalization
+    pub equalization: bool,
+    pub reverb: bool,
+}
+
+impl Default for HardwareVoiceSettings {
+    fn default() -> Self {
+        let quality_level = quality_levels()[1].clone();
+        Self {
+            sample_rate: quality_level.sample_rate,
+            quality_level,
+            equalization: true,
+            reverb: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceOverrides {
+    pub quality_mode: Option<QualityMode>,
+    pub sample_rate: Option<u32>,
+    pub equalization: Option<bool>,
+    pub reverb: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,7 +130,7 @@ pub struct YourTTSConfig {
     pub target_language: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum QualityMode {
     RealTime,      // Fast, lower quality for real-time
     Balanced,      // Good quality with reasonable speed
@@ -228,9 +267,40 @@ impl AdvancedTTSEngine {
             streaming_enabled: true,
             quality_mode: QualityMode::Balanced,
             voice_models_path: PathBuf::from("/opt/tars/models/voice"),
+            hardware: HardwareVoiceSettings::default(),
+            overrides: VoiceOverrides::default(),
         }
     }
 
+    /// Pick quality and effects the Pi can sustain: the lowest stream
+    /// quality without EQ or reverb on a Pi Zero 2W, the highest on a Pi 4B
+    /// 8GB or Pi 5. Anything set in `overrides` is kept.
+    pub fn configure_for_hardware(&mut self, pi: &RaspberryPiConfig) {
+        let levels = quality_levels();
+        let (quality_mode, quality_level, effects) = match pi.model {
+            PiModel::PiZero2W => (QualityMode::RealTime, levels.first(), false),
+            PiModel::Pi4B8GB | PiModel::Pi5 => (QualityMode::HighQuality, levels.last(), true),
+            _ => (QualityMode::Balanced, levels.get(levels.len() / 2), true),
+        };
+        let quality_level = quality_level.cloned().unwrap_or_else(|| HardwareVoiceSettings::default().quality_level);
+        let overrides = &self.overrides;
+
+        self.quality_mode = overrides.quality_mode.clone().unwrap_or(quality_mode);
+        // Keep models to a quarter of the memory TARS may use
+        self.model_cache_size = pi.memory_limit_mb as usize * 1024 * 1024 / 4;
+        self.hardware = HardwareVoiceSettings {
+            sample_rate: overrides.sample_rate.unwrap_or(quality_level.sample_rate),
+            quality_level,
+            equalization: overrides.equalization.unwrap_or(effects),
+            reverb: overrides.reverb.unwrap_or(effects),
+        };
+    }
+
+    /// Synthesis settings at the configured sample rate
+    pub fn synthesis_config(&self) -> SynthesisConfig {
+        SynthesisConfig { sample_rate: self.hardware.sample_rate, ..SynthesisConfig::default() }
+    }
+
     /// Initialize TTS engine with model loading
     pub async fn initialize(&mut self) -> Result<(), String> {
         println!("🎤 TARS: Initializing advanced TTS engine...");
@@ -519,6 +589,8 @@ impl AdvancedTTSEngine {
         stats.insert("gpu_acceleration".to_string(), self.gpu_acceleration.to_string());
         stats.insert("streaming_enabled".to_string(), self.streaming_enabled.to_string());
         stats.insert("quality_mode".to_string(), format!("{:?}", self.quality_mode));
+        stats.insert("quality_level".to_string(), self.hardware.quality_level.level_name.clone());
+        stats.insert("sample_rate".to_string(), self.hardware.sample_rate.to_string());
         stats
     }
 }
@@ -595,6 +667,13 @@ impl Default for SynthesisConfig {
     }
 }
 
+/// Stream quality levels, lowest bit rate first
+fn quality_levels() -> Vec<QualityLevel> {
+    let mut levels = StreamManager::default().stream_quality_levels;
+    levels.sort_by_key(|level| level.bit_rate);
+    levels
+}
+
 // Public API functions
 pub async fn initialize_advanced_tts() -> Result<(), String> {
     let mut engine = ADVANCED_TTS_ENGINE.lock().await;
@@ -603,8 +682,40 @@ pub async fn initialize_advanced_tts() -> Result<(), String> {
 
 pub async fn synthesize_advanced(text: &str, config: Option<SynthesisConfig>) -> Result<Vec<u8>, String> {
     let engine = ADVANCED_TTS_ENGINE.lock().await;
-    let synthesis_config = config.unwrap_or_default();
-    engine.synthesize_with_primary(text, &synthesis_config).await
+    let synthesis_config = config.unwrap_or_else(|| engine.synthesis_config());
+    let audio = engine.synthesize_with_primary(text, &synthesis_config).await?;
+    super::cue_light::signal_if_joking(text, synthesis_config.emotion.as_ref());
+    if let Some(emotion) = &synthesis_config.emotion {
+        events::publish(TarsEvent::EmotionChanged {
+            emotion: emotion.primary_emotion.clone(),
+            intensity: emotion.intensity,
+        });
+    }
+    Ok(audio)
+}
+
+pub async fn configure_advanced_tts_for_hardware(pi: &RaspberryPiConfig) {
+    ADVANCED_TTS_ENGINE.lock().await.configure_for_hardware(pi);
+}
+
+/// Pin the quality mode regardless of hardware, or hand it back to
+/// `configure_for_hardware` with `None`
+pub async fn set_quality_mode_override(mode: Option<QualityMode>) {
+    let mut engine = ADVANCED_TTS_ENGINE.lock().await;
+    if let Some(mode) = &mode {
+        engine.quality_mode = mode.clone();
+    }
+    engine.overrides.quality_mode = mode;
+}
+
+/// Voice quality and effects chosen for the hardware
+pub async fn hardware_voice_settings() -> HardwareVoiceSettings {
+    ADVANCED_TTS_ENGINE.lock().await.hardware.clone()
+}
+
+/// Settings `synthesize_advanced` uses when given none
+pub async fn default_synthesis_config() -> SynthesisConfig {
+    ADVANCED_TTS_ENGINE.lock().await.synthesis_config()
 }
 
 pub async fn get_advanced_tts_stats() -> HashMap<String, String> {
@@ -630,6 +741,29 @@ mod tests {
         assert_eq!(config.sample_rate, 22050);
     }
 
+    #[test]
+    fn test_hardware_defaults_scale_voice_quality_with_pi_model() {
+        let mut engine = AdvancedTTSEngine::new();
+        engine.configure_for_hardware(&RaspberryPiConfig::default_for_model(&PiModel::PiZero2W));
+        assert_eq!(engine.quality_mode, QualityMode::RealTime);
+        assert_eq!(engine.hardware.quality_level.level_name, "Low");
+        assert_eq!(engine.synthesis_config().sample_rate, 16000);
+        assert!(!engine.hardware.reverb);
+        assert!(!engine.hardware.equalization);
+
+        engine.configure_for_hardware(&RaspberryPiConfig::default_for_model(&PiModel::Pi4B8GB));
+        assert_eq!(engine.quality_mode, QualityMode::HighQuality);
+        assert_eq!(engine.hardware.quality_level.level_name, "High");
+        assert_eq!(engine.hardware.sample_rate, 44100);
+        assert!(engine.hardware.reverb && engine.hardware.equalization);
+
+        // A user's choice survives reconfiguring for the hardware
+        engine.overrides.reverb = Some(true);
+        engine.configure_for_hardware(&RaspberryPiConfig::default_for_model(&PiModel::PiZero2W));
+        assert!(engine.hardware.reverb);
+        assert_eq!(engine.hardware.sample_rate, 16000);
+    }
+
     #[tokio::test]
     async fn test_lru_cache() {
         let mut cache = LRUCache::new(2);
@@ -0,0 +1,156 @@
+//! Output mixer for speech and ambient sounds.
+//!
+//! Servo and idle sounds are ducked while speech is active. The mix then goes
+//! through a lookahead limiter built from the voice profile's
+//! `LimiterSettings`, so the final signal never goes above the limiter
+//! threshold.
+
+use serde::{Deserialize, Serialize};
+
+use super::tars_voice_profile::LimiterSettings;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixSettings {
+    pub speech_gain: f32,
+    /// Level of servo and idle sounds relative to speech
+    pub ambient_gain: f32,
+    /// Share of the ambient level kept while speech is active
+    pub duck_gain: f32,
+    /// Speech sample level that counts as speech being active
+    pub speech_threshold: f32,
+    /// How long ambient sounds stay ducked after speech stops (ms)
+    pub duck_release_ms: f32,
+}
+
+impl Default for MixSettings {
+    fn default() -> Self {
+        Self {
+            speech_gain: 1.0,
+            ambient_gain: 1.0,
+            duck_gain: 0.2,
+            speech_threshold: 0.01,
+            duck_release_ms: 200.0,
+        }
+    }
+}
+
+/// How quickly the duck gain follows speech starting and stopping (ms)
+const DUCK_SMOOTHING_MS: f32 = 10.0;
+
+#[derive(Debug, Clone)]
+pub struct AudioMixer {
+    pub settings: MixSettings,
+    pub limiter: LimiterSettings,
+}
+
+impl AudioMixer {
+    pub fn new(settings: MixSettings, limiter: LimiterSettings) -> Self {
+        Self { settings, limiter }
+    }
+
+    /// Highest sample level the limiter lets through
+    pub fn ceiling(&self) -> f32 {
+        10f32.powf(self.limiter.threshold / 20.0).min(1.0)
+    }
+
+    /// Mix `speech` with `ambient` sounds and limit the result. The output
+    /// is as long as the longer input.
+    pub fn mix(&self, speech: &[f32], ambient: &[f32], sample_rate: u32) -> Vec<f32> {
+        let settings = &self.settings;
+        let hold = ms_to_samples(settings.duck_release_ms, sample_rate);
+        let smoothing = smoothing_coefficient(DUCK_SMOOTHING_MS, sample_rate);
+        let mut since_speech = usize::MAX;
+        let mut duck = 1.0f32;
+
+        let mixed: Vec<f32> = (0..speech.len().max(ambient.len()))
+            .map(|i| {
+                let voice = speech.get(i).copied().unwrap_or(0.0) * settings.speech_gain;
+                since_speech = if voice.abs() >= settings.speech_threshold { 0 } else { since_speech.saturating_add(1) };
+                let target = if since_speech <= hold { settings.duck_gain } else { 1.0 };
+                duck = target + (duck - target) * smoothing;
+                voice + ambient.get(i).copied().unwrap_or(0.0) * settings.ambient_gain * duck
+            })
+            .collect();
+
+        self.limit(&mixed, sample_rate)
+    }
+
+    /// Like `mix`, for 16-bit little-endian PCM
+    pub fn mix_pcm16(&self, speech: &[u8], ambient: &[u8], sample_rate: u32) -> Vec<u8> {
+        samples_to_pcm16(&self.mix(&pcm16_to_samples(speech), &pcm16_to_samples(ambient), sample_rate))
+    }
+
+    /// Soft limiter: the gain drops ahead of a peak and recovers over the
+    /// release time, so no sample exceeds `ceiling`
+    pub fn limit(&self, samples: &[f32], sample_rate: u32) -> Vec<f32> {
+        let ceiling = self.ceiling();
+        let lookahead = ms_to_samples(self.limiter.lookahead, sample_rate);
+        let release = smoothing_coefficient(self.limiter.release_time, sample_rate);
+        let mut gain = 1.0f32;
+
+        (0..samples.len())
+            .map(|i| {
+                let end = (i + lookahead + 1).min(samples.len());
+                let peak = samples[i..end].iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+                let target = if peak > ceiling { ceiling / peak } else { 1.0 };
+                gain = if target < gain { target } else { target + (gain - target) * release };
+                (samples[i] * gain).clamp(-ceiling, ceiling)
+            })
+            .collect()
+    }
+}
+
+pub fn pcm16_to_samples(data: &[u8]) -> Vec<f32> {
+    data.chunks_exact(2)
+        .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
+        .collect()
+}
+
+pub fn samples_to_pcm16(samples: &[f32]) -> Vec<u8> {
+    samples.iter()
+        .flat_map(|sample| ((sample.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes())
+        .collect()
+}
+
+fn ms_to_samples(ms: f32, sample_rate: u32) -> usize {
+    (ms.max(0.0) * sample_rate as f32 / 1000.0) as usize
+}
+
+/// Per-sample factor for a one-pole smoother with the given time constant
+fn smoothing_coefficient(ms: f32, sample_rate: u32) -> f32 {
+    let samples = ms * sample_rate as f32 / 1000.0;
+    if samples > 0.0 { (-1.0 / samples).exp() } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_scale_speech_with_servo_never_exceeds_ceiling() {
+        let sample_rate = 24000;
+        let mixer = AudioMixer::new(MixSettings::default(), LimiterSettings {
+            threshold: -6.0,
+            release_time: 10.0,
+            lookahead: 2.0,
+        });
+        let speech: Vec<u8> = (0..sample_rate)
+            .flat_map(|i| if (i / 20) % 2 == 0 { i16::MAX } else { i16::MIN }.to_le_bytes())
+            .collect();
+        let servo: Vec<u8> = (0..sample_rate * 2)
+            .flat_map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (((2.0 * std::f32::consts::PI * 8500.0 * t).sin() * 32767.0) as i16).to_le_bytes()
+            })
+            .collect();
+
+        let mixed = pcm16_to_samples(&mixer.mix_pcm16(&speech, &servo, sample_rate));
+
+        assert_eq!(mixed.len(), sample_rate as usize * 2);
+        let peak = mixed.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak <= mixer.ceiling(), "peak {} above ceiling {}", peak, mixer.ceiling());
+        // The servo comes back at full level once speech has stopped
+        assert!(mixed[sample_rate as usize + 12000..].iter().any(|s| s.abs() > 0.4));
+    }
+}
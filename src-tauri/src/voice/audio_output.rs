@@ -0,0 +1,370 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::advanced_tts::{self, HardwareVoiceSettings};
+use super::tars_voice_profile::TARSVoiceProfile;
+use super::text_to_speech::{AudioFormat, AudioOutput};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputDeviceInfo {
+    pub name: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub is_default: bool,
+}
+
+/// Source of playback devices. `CpalDeviceProvider` talks to the real audio
+/// host; tests and headless builds can supply their own list.
+pub trait OutputDeviceProvider {
+    fn output_devices(&self) -> Result<Vec<OutputDeviceInfo>, String>;
+    fn default_output_device(&self) -> Option<OutputDeviceInfo>;
+}
+
+/// Pick the configured output device by name, falling back to the host default
+/// when the named device is missing (e.g. a USB speaker was unplugged).
+pub fn select_output_device<P: OutputDeviceProvider>(
+    provider: &P,
+    preferred: Option<&str>,
+) -> Option<OutputDeviceInfo> {
+    if let Some(name) = preferred.filter(|n| !n.trim().is_empty()) {
+        match provider.output_devices() {
+            Ok(devices) => {
+                if let Some(device) = devices.into_iter().find(|d| d.name == name) {
+                    return Some(device);
+                }
+                warn!("Audio output device '{}' not found, falling back to default device", name);
+            }
+            Err(e) => {
+                warn!("Failed to enumerate audio output devices ({}), falling back to default device", e);
+            }
+        }
+    }
+
+    provider.default_output_device()
+}
+
+/// Linearly resample interleaved 16-bit little-endian PCM.
+pub fn resample_pcm16(data: &[u8], channels: u16, from_rate: u32, to_rate: u32) -> Vec<u8> {
+    let channels = channels.max(1) as usize;
+    if from_rate == to_rate || from_rate == 0 || to_rate == 0 {
+        return data.to_vec();
+    }
+
+    let samples: Vec<i16> = data
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    let in_frames = samples.len() / channels;
+    if in_frames == 0 {
+        return Vec::new();
+    }
+
+    let out_frames = (in_frames as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    let mut out = Vec::with_capacity(out_frames * channels * 2);
+
+    for frame in 0..out_frames {
+        let pos = frame as f64 * step;
+        let idx = (pos as usize).min(in_frames - 1);
+        let next = (idx + 1).min(in_frames - 1);
+        let frac = pos - idx as f64;
+
+        for ch in 0..channels {
+            let a = samples[idx * channels + ch] as f64;
+            let b = samples[next * channels + ch] as f64;
+            let sample = (a + (b - a) * frac).round() as i16;
+            out.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    out
+}
+
+/// Convert synthesized audio to the device's native sample rate if they differ.
+pub fn prepare_for_device(output: AudioOutput, device: &OutputDeviceInfo) -> AudioOutput {
+    if output.sample_rate == device.sample_rate {
+        return output;
+    }
+
+    info!(
+        "Resampling TTS audio from {} Hz to {} Hz for device '{}'",
+        output.sample_rate, device.sample_rate, device.name
+    );
+
+    AudioOutput {
+        audio_data: resample_pcm16(&output.audio_data, output.channels, output.sample_rate, device.sample_rate),
+        sample_rate: device.sample_rate,
+        ..output
+    }
+}
+
+/// Run the TARS voice effects over PCM speech, skipping the ones the
+/// hardware can't afford. Compressed audio passes through untouched.
+pub fn apply_voice_effects(mut output: AudioOutput, profile: &TARSVoiceProfile, hardware: &HardwareVoiceSettings) -> AudioOutput {
+    if !matches!(output.format, AudioFormat::Raw | AudioFormat::WAV) {
+        return output;
+    }
+    let mut profile = profile.clone();
+    profile.voice_effects.apply_hardware_settings(hardware);
+    let mut audio_data = output.audio_data.clone();
+    match profile.apply_voice_effects(&mut audio_data, output.sample_rate) {
+        Ok(()) => output.audio_data = audio_data,
+        Err(e) => warn!("Voice effects failed ({}), playing the dry audio", e),
+    }
+    output
+}
+
+async fn with_voice_effects(output: AudioOutput) -> AudioOutput {
+    let hardware = advanced_tts::hardware_voice_settings().await;
+    apply_voice_effects(output, &TARSVoiceProfile::interstellar_accurate(), &hardware)
+}
+
+#[cfg(feature = "audio")]
+pub struct CpalDeviceProvider;
+
+#[cfg(feature = "audio")]
+impl CpalDeviceProvider {
+    fn describe(device: &cpal::Device, default_name: Option<&str>) -> Option<OutputDeviceInfo> {
+        use cpal::traits::DeviceTrait;
+
+        let name = device.name().ok()?;
+        let config = device.default_output_config().ok()?;
+        Some(OutputDeviceInfo {
+            is_default: default_name == Some(name.as_str()),
+            name,
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+        })
+    }
+
+    fn find_device(name: &str) -> Option<cpal::Device> {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        cpal::default_host()
+            .output_devices()
+            .ok()?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+    }
+}
+
+#[cfg(feature = "audio")]
+impl OutputDeviceProvider for CpalDeviceProvider {
+    fn output_devices(&self) -> Result<Vec<OutputDeviceInfo>, String> {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let host = cpal::default_host();
+        let default_name = host.default_output_device().and_then(|d| d.name().ok());
+        let devices = host.output_devices().map_err(|e| e.to_string())?;
+        Ok(devices
+            .filter_map(|d| Self::describe(&d, default_name.as_deref()))
+            .collect())
+    }
+
+    fn default_output_device(&self) -> Option<OutputDeviceInfo> {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let device = cpal::default_host().default_output_device()?;
+        let name = device.name().ok();
+        Self::describe(&device, name.as_deref())
+    }
+}
+
+/// Play 16-bit PCM audio on the named device, blocking until playback ends.
+#[cfg(feature = "audio")]
+fn play_blocking(output: &AudioOutput, device: &OutputDeviceInfo) -> Result<(), String> {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::SampleFormat;
+
+    let cpal_device = CpalDeviceProvider::find_device(&device.name)
+        .or_else(|| cpal::default_host().default_output_device())
+        .ok_or("No audio output device available")?;
+    let supported = cpal_device.default_output_config().map_err(|e| e.to_string())?;
+    let sample_format = supported.sample_format();
+    let stream_config: cpal::StreamConfig = supported.into();
+
+    // Expand the (usually mono) source to the device channel layout.
+    let src_channels = output.channels.max(1) as usize;
+    let dst_channels = stream_config.channels.max(1) as usize;
+    let source: Vec<i16> = output
+        .audio_data
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    let mut frames = Vec::with_capacity(source.len() / src_channels * dst_channels);
+    for frame in source.chunks(src_channels) {
+        for ch in 0..dst_channels {
+            frames.push(frame[ch.min(frame.len() - 1)]);
+        }
+    }
+
+    let on_error = |e: cpal::StreamError| warn!("Audio output stream error: {}", e);
+    let stream = match sample_format {
+        SampleFormat::I16 => {
+            let mut samples = frames.into_iter();
+            cpal_device.build_output_stream(
+                &stream_config,
+                move |data: &mut [i16], _| {
+                    for s in data.iter_mut() {
+                        *s = samples.next().unwrap_or(0);
+                    }
+                },
+                on_error,
+                None,
+            )
+        }
+        SampleFormat::F32 => {
+            let mut samples = frames.into_iter().map(|s| s as f32 / i16::MAX as f32);
+            cpal_device.build_output_stream(
+                &stream_config,
+                move |data: &mut [f32], _| {
+                    for s in data.iter_mut() {
+                        *s = samples.next().unwrap_or(0.0);
+                    }
+                },
+                on_error,
+                None,
+            )
+        }
+        other => return Err(format!("Unsupported output sample format: {:?}", other)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    stream.play().map_err(|e| e.to_string())?;
+    std::thread::sleep(std::time::Duration::from_millis(output.duration_ms + 50));
+    Ok(())
+}
+
+/// Route synthesized speech to the configured output device, resampling to the
+/// device's native rate. Returns the device that was actually used.
+#[cfg(feature = "audio")]
+pub async fn play_audio(output: AudioOutput, preferred_device: Option<String>) -> Result<OutputDeviceInfo, String> {
+    let output = with_voice_effects(output).await;
+    tokio::task::spawn_blocking(move || {
+        let device = select_output_device(&CpalDeviceProvider, preferred_device.as_deref())
+            .ok_or("No audio output device available")?;
+        let prepared = prepare_for_device(output, &device);
+        play_blocking(&prepared, &device)?;
+        Ok(device)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Without the `audio` feature there is no host to talk to; report the
+/// configured device and rate so callers behave the same in simulation.
+#[cfg(not(feature = "audio"))]
+pub async fn play_audio(output: AudioOutput, preferred_device: Option<String>) -> Result<OutputDeviceInfo, String> {
+    let output = with_voice_effects(output).await;
+    let device = OutputDeviceInfo {
+        name: preferred_device.unwrap_or_else(|| "default".to_string()),
+        sample_rate: output.sample_rate,
+        channels: output.channels,
+        is_default: true,
+    };
+    info!("Audio playback simulated on '{}' ({} ms)", device.name, output.duration_ms);
+    Ok(device)
+}
+
+/// List playback devices the host exposes.
+pub fn list_output_devices() -> Result<Vec<OutputDeviceInfo>, String> {
+    #[cfg(feature = "audio")]
+    {
+        CpalDeviceProvider.output_devices()
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockProvider {
+        devices: Vec<OutputDeviceInfo>,
+    }
+
+    impl OutputDeviceProvider for MockProvider {
+        fn output_devices(&self) -> Result<Vec<OutputDeviceInfo>, String> {
+            Ok(self.devices.clone())
+        }
+
+        fn default_output_device(&self) -> Option<OutputDeviceInfo> {
+            self.devices.iter().find(|d| d.is_default).cloned()
+        }
+    }
+
+    fn device(name: &str, sample_rate: u32, is_default: bool) -> OutputDeviceInfo {
+        OutputDeviceInfo { name: name.to_string(), sample_rate, channels: 2, is_default }
+    }
+
+    fn tone(sample_rate: u32, samples: usize) -> AudioOutput {
+        let mut audio_data = Vec::with_capacity(samples * 2);
+        for i in 0..samples {
+            audio_data.extend_from_slice(&((i % 100) as i16 * 100).to_le_bytes());
+        }
+        AudioOutput {
+            audio_data,
+            duration_ms: samples as u64 * 1000 / sample_rate as u64,
+            sample_rate,
+            channels: 1,
+            format: AudioFormat::Raw,
+            text_processed: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select_named_device() {
+        let provider = MockProvider {
+            devices: vec![device("HDMI", 48000, true), device("USB Speaker", 44100, false)],
+        };
+        let selected = select_output_device(&provider, Some("USB Speaker")).unwrap();
+        assert_eq!(selected.name, "USB Speaker");
+    }
+
+    #[test]
+    fn test_missing_device_falls_back_to_default() {
+        let provider = MockProvider {
+            devices: vec![device("HDMI", 48000, true)],
+        };
+        let selected = select_output_device(&provider, Some("USB Speaker")).unwrap();
+        assert_eq!(selected.name, "HDMI");
+    }
+
+    #[test]
+    fn test_mismatched_rate_resamples_to_device_rate() {
+        let output = tone(22050, 22050);
+        let speaker = device("USB Speaker", 48000, false);
+
+        let prepared = prepare_for_device(output, &speaker);
+        assert_eq!(prepared.sample_rate, 48000);
+        assert_eq!(prepared.audio_data.len(), 48000 * 2);
+        assert_eq!(prepared.duration_ms, 1000);
+    }
+
+    #[test]
+    fn test_matching_rate_is_untouched() {
+        let output = tone(48000, 480);
+        let original = output.audio_data.clone();
+        let prepared = prepare_for_device(output, &device("HDMI", 48000, true));
+        assert_eq!(prepared.audio_data, original);
+    }
+
+    #[test]
+    fn test_voice_effects_follow_hardware_settings() {
+        let profile = TARSVoiceProfile::interstellar_accurate();
+        let full = HardwareVoiceSettings::default();
+        let pi_zero = HardwareVoiceSettings { equalization: false, reverb: false, ..HardwareVoiceSettings::default() };
+
+        let with_reverb = apply_voice_effects(tone(16000, 1600), &profile, &full);
+        let without = apply_voice_effects(tone(16000, 1600), &profile, &pi_zero);
+        assert_eq!(with_reverb.audio_data.len(), without.audio_data.len());
+        assert_ne!(with_reverb.audio_data, without.audio_data);
+        assert_ne!(without.audio_data, tone(16000, 1600).audio_data);
+
+        let mp3 = AudioOutput { format: AudioFormat::MP3, ..tone(16000, 1600) };
+        let original = mp3.audio_data.clone();
+        assert_eq!(apply_voice_effects(mp3, &profile, &full).audio_data, original);
+    }
+}
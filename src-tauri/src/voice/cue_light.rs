@@ -0,0 +1,76 @@
+//! TARS's cue light.
+//!
+//! Whenever a line is spoken with deadpan humor or sarcasm, a `JokingEvent`
+//! is broadcast so the frontend or a physical LED can show that TARS is
+//! joking. Only confident humor lights the cue, so a stray "perfect" in a
+//! status report doesn't.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::advanced_tts::EmotionConfig;
+
+/// Emotions that mean TARS is joking
+pub const HUMOR_EMOTIONS: &[&str] = &["deadpan_humor", "sarcastic_response"];
+
+/// Humor confidence needed to light the cue
+pub const CUE_LIGHT_THRESHOLD: f32 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JokingEvent {
+    pub text: String,
+    pub emotion: String,
+    /// Intensity of the humorous emotion, 0.0-1.0
+    pub confidence: f32,
+}
+
+static JOKING_EVENTS: Lazy<broadcast::Sender<JokingEvent>> = Lazy::new(|| broadcast::channel(16).0);
+
+pub fn subscribe_joking() -> broadcast::Receiver<JokingEvent> {
+    JOKING_EVENTS.subscribe()
+}
+
+/// The event for `text` spoken with `emotion`, if it is confidently humorous
+pub fn joking_event(text: &str, emotion: &EmotionConfig) -> Option<JokingEvent> {
+    let humorous = HUMOR_EMOTIONS.contains(&emotion.primary_emotion.as_str());
+    (humorous && emotion.intensity >= CUE_LIGHT_THRESHOLD).then(|| JokingEvent {
+        text: text.to_string(),
+        emotion: emotion.primary_emotion.clone(),
+        confidence: emotion.intensity.clamp(0.0, 1.0),
+    })
+}
+
+/// Light the cue if `text` is being delivered as a joke
+pub fn signal_if_joking(text: &str, emotion: Option<&EmotionConfig>) {
+    if let Some(event) = emotion.and_then(|emotion| joking_event(text, emotion)) {
+        let _ = JOKING_EVENTS.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::advanced_tts::{synthesize_advanced, SynthesisConfig};
+    use crate::voice::tars_voice_profile::TARSVoiceProfile;
+
+    #[tokio::test]
+    async fn test_humorous_quote_lights_cue_but_status_report_does_not() {
+        let mut events = subscribe_joking();
+        let (quote, emotion) = TARSVoiceProfile::get_famous_quotes().remove(0);
+        assert_eq!(emotion.primary_emotion, "deadpan_humor");
+
+        let config = SynthesisConfig { emotion: Some(emotion), ..SynthesisConfig::default() };
+        synthesize_advanced(&quote, Some(config)).await.unwrap();
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.text, quote);
+        assert_eq!(event.emotion, "deadpan_humor");
+        assert!(event.confidence >= CUE_LIGHT_THRESHOLD);
+
+        let report = "Status report: all systems nominal";
+        let config = TARSVoiceProfile::interstellar_accurate().to_synthesis_config(report, "status");
+        assert_eq!(config.emotion.as_ref().unwrap().primary_emotion, "status_report");
+        synthesize_advanced(report, Some(config)).await.unwrap();
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Offline emergency voice.
+//!
+//! A small formant synthesizer that needs no models, files or network, so an
+//! emergency phrase can be spoken even when the main TTS pipeline is down.
+//! The emergency phrases are rendered once and replayed from memory; other
+//! text is rendered on demand. The same text always gives the same samples.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use once_cell::sync::Lazy;
+
+use super::text_to_speech::{AudioFormat, AudioOutput};
+
+pub const FALLBACK_SAMPLE_RATE: u32 = 16_000;
+
+/// Phrases kept pre-rendered so they play without any synthesis work
+pub const EMERGENCY_PHRASES: &[&str] = &[
+    "Emergency protocols activated",
+    "Critical system failure detected",
+    "Immediate action required",
+];
+
+/// Low, flat pitch of the fallback voice
+const PITCH_HZ: f32 = 110.0;
+
+/// Fade at each end of a sound, so segments join without clicks
+const RAMP_MS: u32 = 5;
+
+static PRERENDERED: Lazy<HashMap<String, Vec<u8>>> = Lazy::new(|| {
+    EMERGENCY_PHRASES.iter()
+        .map(|phrase| (phrase_key(phrase), render_pcm16(phrase)))
+        .collect()
+});
+
+/// One sound of the rendered text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    /// Glottal pulses shaped by three formants (Hz)
+    Voiced { formants: [f32; 3], ms: u32, gain: f32 },
+    /// Hiss for fricatives
+    Noise { ms: u32, gain: f32 },
+    /// A short stop followed by a burst of hiss
+    Plosive,
+    Silence { ms: u32 },
+}
+
+fn segment_for(c: char) -> Option<Segment> {
+    let vowel = |formants| Some(Segment::Voiced { formants, ms: 90, gain: 1.0 });
+    match c {
+        'a' => vowel([730.0, 1090.0, 2440.0]),
+        'e' => vowel([530.0, 1840.0, 2480.0]),
+        'i' | 'y' => vowel([270.0, 2290.0, 3010.0]),
+        'o' => vowel([570.0, 840.0, 2410.0]),
+        'u' => vowel([300.0, 870.0, 2240.0]),
+        'm' | 'n' => Some(Segment::Voiced { formants: [280.0, 1300.0, 2500.0], ms: 60, gain: 0.5 }),
+        'b' | 'd' | 'g' | 'j' | 'l' | 'r' | 'v' | 'w' | 'z' => {
+            Some(Segment::Voiced { formants: [400.0, 1200.0, 2500.0], ms: 55, gain: 0.6 })
+        },
+        's' | 'f' | 'h' | 'x' => Some(Segment::Noise { ms: 70, gain: 0.3 }),
+        'c' | 'k' | 'p' | 'q' | 't' => Some(Segment::Plosive),
+        ' ' | '-' => Some(Segment::Silence { ms: 60 }),
+        ',' | '.' | ';' | ':' | '!' | '?' => Some(Segment::Silence { ms: 180 }),
+        _ => None,
+    }
+}
+
+/// Two-pole resonator (Klatt 1980)
+#[derive(Debug, Clone, Copy)]
+struct Resonator {
+    a: f32,
+    b: f32,
+    c: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Resonator {
+    fn new(frequency: f32, bandwidth: f32) -> Self {
+        let t = 1.0 / FALLBACK_SAMPLE_RATE as f32;
+        let c = -(-2.0 * PI * bandwidth * t).exp();
+        let b = 2.0 * (-PI * bandwidth * t).exp() * (2.0 * PI * frequency * t).cos();
+        Self { a: 1.0 - b - c, b, c, y1: 0.0, y2: 0.0 }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.a * x + self.b * self.y1 + self.c * self.y2;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+#[derive(Debug, Default)]
+struct Synth {
+    phase: f32,
+    /// Fixed-seed noise, so the output never varies
+    noise: u32,
+    samples: Vec<f32>,
+}
+
+impl Synth {
+    fn next_noise(&mut self) -> f32 {
+        self.noise = self.noise.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (self.noise >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+
+    fn push(&mut self, segment: Segment) {
+        let samples_for = |ms: u32| (FALLBACK_SAMPLE_RATE * ms / 1000) as usize;
+        match segment {
+            Segment::Voiced { formants, ms, gain } => {
+                let mut resonators = [
+                    Resonator::new(formants[0], 60.0),
+                    Resonator::new(formants[1], 90.0),
+                    Resonator::new(formants[2], 120.0),
+                ];
+                let sound: Vec<f32> = (0..samples_for(ms))
+                    .map(|_| {
+                        self.phase = (self.phase + PITCH_HZ / FALLBACK_SAMPLE_RATE as f32) % 1.0;
+                        let pulse = 1.0 - 2.0 * self.phase;
+                        gain * resonators.iter_mut().fold(pulse, |x, r| r.process(x))
+                    })
+                    .collect();
+                self.extend_ramped(sound);
+            },
+            Segment::Noise { ms, gain } => {
+                let sound = (0..samples_for(ms)).map(|_| gain * self.next_noise()).collect();
+                self.extend_ramped(sound);
+            },
+            Segment::Plosive => {
+                self.push(Segment::Silence { ms: 15 });
+                self.push(Segment::Noise { ms: 25, gain: 0.4 });
+            },
+            Segment::Silence { ms } => self.samples.extend(std::iter::repeat_n(0.0, samples_for(ms))),
+        }
+    }
+
+    fn extend_ramped(&mut self, mut sound: Vec<f32>) {
+        let ramp = ((FALLBACK_SAMPLE_RATE * RAMP_MS / 1000) as usize).min(sound.len() / 2);
+        let len = sound.len();
+        for i in 0..ramp {
+            let scale = i as f32 / ramp as f32;
+            sound[i] *= scale;
+            sound[len - 1 - i] *= scale;
+        }
+        self.samples.extend(sound);
+    }
+
+    /// Samples normalized to 80% of full scale, as 16-bit little-endian PCM
+    fn into_pcm16(self) -> Vec<u8> {
+        let peak = self.samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let scale = if peak > 0.0 { 0.8 * i16::MAX as f32 / peak } else { 0.0 };
+        self.samples.iter()
+            .flat_map(|s| ((s * scale) as i16).to_le_bytes())
+            .collect()
+    }
+}
+
+/// Render `text` as mono 16-bit PCM at `FALLBACK_SAMPLE_RATE`
+pub fn render_pcm16(text: &str) -> Vec<u8> {
+    let mut synth = Synth::default();
+    for segment in text.to_lowercase().chars().filter_map(segment_for) {
+        synth.push(segment);
+    }
+    synth.into_pcm16()
+}
+
+/// Lookup key for a phrase: lowercase, single-spaced, without end punctuation
+pub fn phrase_key(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase()
+}
+
+/// True when `text` is one of the pre-rendered emergency phrases
+pub fn is_prerendered(text: &str) -> bool {
+    PRERENDERED.contains_key(&phrase_key(text))
+}
+
+/// Speak `text` with the fallback voice. Never fails.
+pub fn speak(text: &str) -> AudioOutput {
+    let audio_data = PRERENDERED.get(&phrase_key(text))
+        .cloned()
+        .unwrap_or_else(|| render_pcm16(text));
+    let samples = audio_data.len() as u64 / 2;
+    AudioOutput {
+        duration_ms: samples * 1000 / FALLBACK_SAMPLE_RATE as u64,
+        audio_data,
+        sample_rate: FALLBACK_SAMPLE_RATE,
+        channels: 1,
+        format: AudioFormat::Raw,
+        text_processed: text.to_string(),
+    }
+}
@@ -0,0 +1,45 @@
+//! TARS Voice Errors
+
+use serde_json::json;
+
+use crate::error::TarsError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VoiceError {
+    #[error("Nothing to say: the text is empty")]
+    EmptyText,
+    #[error("Synthesis produced no audio for '{text}', even from the emergency voice")]
+    SynthesisFailed { text: String },
+    #[error("No stream quality levels configured")]
+    NoStreamQuality,
+    #[error("No streaming session {session_id}")]
+    StreamNotFound { session_id: String },
+    #[error("Voice cloning needs reference audio")]
+    NoReferenceAudio,
+    #[error("Fine-tuning needs training data")]
+    NoTrainingData,
+    #[error("Latency profiling failed: {reason}")]
+    ProfilingFailed { reason: String },
+}
+
+impl TarsError for VoiceError {
+    fn code(&self) -> &'static str {
+        match self {
+            VoiceError::EmptyText => "empty_text",
+            VoiceError::SynthesisFailed { .. } => "synthesis_failed",
+            VoiceError::NoStreamQuality => "no_stream_quality",
+            VoiceError::StreamNotFound { .. } => "stream_not_found",
+            VoiceError::NoReferenceAudio => "no_reference_audio",
+            VoiceError::NoTrainingData => "no_training_data",
+            VoiceError::ProfilingFailed { .. } => "profiling_failed",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            VoiceError::SynthesisFailed { text } => Some(json!({ "text": text })),
+            VoiceError::StreamNotFound { session_id } => Some(json!({ "session_id": session_id })),
+            _ => None,
+        }
+    }
+}
@@ -5,6 +5,12 @@ pub mod tars_voice_profile;
 pub mod speech_patterns;
 pub mod realtime_processing;
 pub mod voice_cloning;
+pub mod audio_output;
+pub mod audio_mixer;
+pub mod emergency_voice;
+pub mod cue_light;
+pub mod webrtc_signaling;
+pub mod error;
 
 pub use speech_recognition::*;
 pub use text_to_speech::*;
@@ -13,3 +19,7 @@ pub use tars_voice_profile::*;
 pub use speech_patterns::*;
 pub use realtime_processing::*;
 pub use voice_cloning::*;
+pub use audio_output::*;
+pub use audio_mixer::*;
+pub use webrtc_signaling::*;
+pub use error::VoiceError;
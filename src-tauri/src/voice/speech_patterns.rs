@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::time::Duration;
 use crate::personality::tars_core::TARSPersonality;
 use super::tars_voice_profile::{TARSVoiceProfile, EmotionConfig};
@@ -41,14 +42,46 @@ pub struct PhraseAnalyzer {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoviePhraseData {
     pub original_text: String,
+    #[serde(default)]
     pub phonetic_transcription: String,
+    /// Length of the spoken phrase; every timing marker ends within it
+    pub duration_ms: u32,
     pub timing_markers: Vec<TimingMarker>,
+    #[serde(default)]
     pub emphasis_points: Vec<EmphasisPoint>,
     pub emotional_context: EmotionConfig,
+    #[serde(default)]
     pub scene_context: String,
+    #[serde(default)]
     pub delivery_notes: String,
 }
 
+impl MoviePhraseData {
+    /// Check that the timing markers are in strictly increasing order and
+    /// end within the phrase
+    pub fn validate_timing(&self) -> Result<(), String> {
+        if self.duration_ms == 0 {
+            return Err("phrase duration must be greater than zero".to_string());
+        }
+        for (i, marker) in self.timing_markers.iter().enumerate() {
+            if i > 0 && marker.position_ms <= self.timing_markers[i - 1].position_ms {
+                return Err(format!(
+                    "timing marker {} at {}ms does not come after the previous marker at {}ms",
+                    i, marker.position_ms, self.timing_markers[i - 1].position_ms
+                ));
+            }
+            let end = marker.position_ms.saturating_add(marker.duration_ms);
+            if end > self.duration_ms {
+                return Err(format!(
+                    "timing marker {} ends at {}ms, past the phrase duration of {}ms",
+                    i, end, self.duration_ms
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimingMarker {
     pub position_ms: u32,
@@ -577,6 +610,7 @@ impl MovieAccurateSpeechProcessor {
             MoviePhraseData {
                 original_text: "Plenty of slaves for my robot colony".to_string(),
                 phonetic_transcription: "ˈplɛn.ti ʌv sleɪvz fɔr maɪ ˈroʊ.bɑt ˈkɑl.ə.ni".to_string(),
+                duration_ms: 2400,
                 timing_markers: vec![
                     TimingMarker {
                         position_ms: 0,
@@ -624,6 +658,7 @@ impl MovieAccurateSpeechProcessor {
             MoviePhraseData {
                 original_text: "Cooper, this is no time for caution".to_string(),
                 phonetic_transcription: "ˈku.pər ðɪs ɪz noʊ taɪm fɔr ˈkɔ.ʃən".to_string(),
+                duration_ms: 2000,
                 timing_markers: vec![
                     TimingMarker {
                         position_ms: 0,
@@ -663,6 +698,32 @@ impl MovieAccurateSpeechProcessor {
         println!("✅ TARS: Movie quotes loaded with accurate timing patterns");
         Ok(())
     }
+
+    /// Add quotes from a JSON file mapping quote ids to `MoviePhraseData`.
+    /// Entries that don't parse or have invalid timing are skipped with a
+    /// warning; a quote with an existing id replaces it. Returns how many
+    /// quotes were added.
+    pub fn load_quotes_from_file(&mut self, path: &Path) -> Result<usize, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read quote file {}: {}", path.display(), e))?;
+        let entries: HashMap<String, serde_json::Value> = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid quote file {}: {}", path.display(), e))?;
+
+        let mut loaded = 0;
+        for (id, entry) in entries {
+            let phrase = serde_json::from_value::<MoviePhraseData>(entry)
+                .map_err(|e| e.to_string())
+                .and_then(|phrase| phrase.validate_timing().map(|_| phrase));
+            match phrase {
+                Ok(phrase) => {
+                    self.phrase_analyzer.movie_phrases.insert(id, phrase);
+                    loaded += 1;
+                },
+                Err(e) => log::warn!("Skipping movie quote '{}' in {}: {}", id, path.display(), e),
+            }
+        }
+        Ok(loaded)
+    }
 }
 
 impl TimingEngine {
@@ -1117,6 +1178,43 @@ mod tests {
         assert!(!processor.phrase_analyzer.movie_phrases.is_empty());
     }
 
+    #[test]
+    fn test_quote_file_loads_valid_quotes_and_skips_out_of_order_timing() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let path = dir.join("quotes.json");
+        let emotion = r#"{"primary_emotion": "deadpan_humor", "intensity": 0.7, "arousal": 0.2, "valence": 0.4}"#;
+        std::fs::write(&path, format!(r#"{{
+            "honesty_setting": {{
+                "original_text": "Ninety percent",
+                "duration_ms": 1200,
+                "timing_markers": [
+                    {{"position_ms": 0, "marker_type": "WordBoundary", "duration_ms": 150, "intensity": 0.8}},
+                    {{"position_ms": 700, "marker_type": "EmphasisPause", "duration_ms": 200, "intensity": 0.6}}
+                ],
+                "emotional_context": {emotion}
+            }},
+            "out_of_order": {{
+                "original_text": "Everybody good? Plenty of slaves for my robot colony?",
+                "duration_ms": 3000,
+                "timing_markers": [
+                    {{"position_ms": 900, "marker_type": "WordBoundary", "duration_ms": 100, "intensity": 0.8}},
+                    {{"position_ms": 400, "marker_type": "ServoSound", "duration_ms": 50, "intensity": 0.3}}
+                ],
+                "emotional_context": {emotion}
+            }}
+        }}"#)).unwrap();
+
+        let mut processor = MovieAccurateSpeechProcessor::new();
+        let loaded = processor.load_quotes_from_file(&path).unwrap();
+
+        assert_eq!(loaded, 1);
+        let phrases = &processor.phrase_analyzer.movie_phrases;
+        assert_eq!(phrases["honesty_setting"].original_text, "Ninety percent");
+        assert_eq!(phrases["honesty_setting"].timing_markers.len(), 2);
+        assert!(!phrases.contains_key("out_of_order"));
+    }
+
     #[tokio::test]
     async fn test_text_processing() {
         let processor = create_movie_speech_processor().await;
@@ -2,7 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::f32::consts::PI;
 use crate::personality::tars_core::TARSPersonality;
-use super::advanced_tts::{SynthesisConfig, EmotionConfig, AudioFormat};
+use super::advanced_tts::{SynthesisConfig, EmotionConfig, AudioFormat, HardwareVoiceSettings};
+use super::audio_mixer::{pcm16_to_samples, samples_to_pcm16, AudioMixer, MixSettings};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TARSVoiceProfile {
@@ -153,6 +154,8 @@ pub struct RoboticFilter {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReverbSettings {
+    #[serde(default = "VoiceEffects::enabled_by_default")]
+    pub enabled: bool,
     pub room_size: f32,                 // Simulated room size (0.0-1.0)
     pub decay_time: f32,                // Reverb decay time (seconds)
     pub early_reflections: f32,         // Early reflection level
@@ -162,6 +165,8 @@ pub struct ReverbSettings {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EqualizationCurve {
+    #[serde(default = "VoiceEffects::enabled_by_default")]
+    pub enabled: bool,
     pub low_shelf: EQBand,              // Low-frequency shelf
     pub low_mid: EQBand,                // Low-mid parametric
     pub mid: EQBand,                    // Mid-range parametric
@@ -216,6 +221,8 @@ pub struct ServoSounds {
     pub between_sentences: bool,        // Add servo sounds between sentences
     pub during_pauses: bool,            // Add subtle servo sounds during pauses
     pub movement_correlation: f32,      // Correlation with physical movement
+    #[serde(default)]
+    pub mix: MixSettings,               // Levels against speech, and ducking
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -366,7 +373,14 @@ impl TARSVoiceProfile {
         }
         
         // Apply EQ
-        self.apply_equalization(audio_data, sample_rate)?;
+        if self.voice_effects.equalization.enabled {
+            self.apply_equalization(audio_data, sample_rate)?;
+        }
+
+        // Apply reverb
+        if self.voice_effects.reverb_settings.enabled {
+            self.apply_reverb(audio_data, sample_rate)?;
+        }
         
         // Apply dynamic processing
         self.apply_dynamic_processing(audio_data, sample_rate)?;
@@ -531,6 +545,31 @@ impl TARSVoiceProfile {
         Ok(())
     }
 
+    /// Small-room reverb: a damped feedback delay sized by the room, decaying
+    /// by 60 dB over `decay_time`
+    fn apply_reverb(&self, audio_data: &mut Vec<u8>, sample_rate: u32) -> Result<(), String> {
+        let reverb = &self.voice_effects.reverb_settings;
+        let dry = pcm16_to_samples(audio_data);
+        let delay = ((0.01 + 0.04 * reverb.room_size.clamp(0.0, 1.0)) * sample_rate as f32) as usize;
+        if delay == 0 || dry.is_empty() {
+            return Ok(());
+        }
+
+        let feedback = 0.001f32.powf(delay as f32 / (reverb.decay_time.max(0.01) * sample_rate as f32));
+        let damping = reverb.high_frequency_damping.clamp(0.0, 0.99);
+        let mut line = vec![0.0f32; delay];
+        let mut damped = 0.0f32;
+        let wet: Vec<f32> = dry.iter().enumerate().map(|(i, &sample)| {
+            let echo = line[i % delay];
+            damped = echo * (1.0 - damping) + damped * damping;
+            line[i % delay] = sample + damped * feedback;
+            sample + echo * reverb.early_reflections
+        }).collect();
+
+        *audio_data = samples_to_pcm16(&wet);
+        Ok(())
+    }
+
     /// Apply dynamic processing (compression/limiting)
     fn apply_dynamic_processing(&self, audio_data: &mut Vec<u8>, _sample_rate: u32) -> Result<(), String> {
         let compressor = &self.voice_effects.dynamic_processing.compressor;
@@ -566,39 +605,23 @@ impl TARSVoiceProfile {
         Ok(())
     }
 
-    /// Add subtle servo sounds characteristic of TARS
+    /// Add subtle servo sounds characteristic of TARS, ducked under the
+    /// speech and limited so the mix does not clip
     fn add_servo_sounds(&self, audio_data: &mut Vec<u8>, sample_rate: u32) -> Result<(), String> {
         let servo = &self.voice_effects.servo_sounds;
-        
-        // Convert to samples
-        let mut samples: Vec<f32> = audio_data
-            .chunks_exact(2)
-            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
-            .collect();
-        
-        // Add subtle servo motor sounds
-        for (i, sample) in samples.iter_mut().enumerate() {
-            let time = i as f32 / sample_rate as f32;
-            
-            // Very subtle high-frequency servo sound
-            let servo_sound = (2.0 * PI * servo.servo_frequency * time).sin() * 
-                             servo.servo_amplitude * 0.01; // Very quiet
-            
-            // Only add during quiet parts
-            if sample.abs() < 0.1 {
-                *sample += servo_sound;
-            }
-        }
-        
-        // Convert back to bytes
-        *audio_data = samples
-            .iter()
-            .flat_map(|sample| {
-                let sample_i16 = (sample.clamp(-1.0, 1.0) * 32767.0) as i16;
-                sample_i16.to_le_bytes()
+        let speech = pcm16_to_samples(audio_data);
+
+        // Very subtle high-frequency servo motor sound
+        let servo_track: Vec<f32> = (0..speech.len())
+            .map(|i| {
+                let time = i as f32 / sample_rate as f32;
+                (2.0 * PI * servo.servo_frequency * time).sin() * servo.servo_amplitude * 0.01
             })
             .collect();
-        
+
+        let mixer = AudioMixer::new(servo.mix.clone(), self.voice_effects.dynamic_processing.limiter.clone());
+        *audio_data = samples_to_pcm16(&mixer.mix(&speech, &servo_track, sample_rate));
+
         Ok(())
     }
 
@@ -750,6 +773,16 @@ impl SpeechPatterns {
 }
 
 impl VoiceEffects {
+    fn enabled_by_default() -> bool {
+        true
+    }
+
+    /// Switch off the effects the hardware can't afford
+    pub fn apply_hardware_settings(&mut self, hardware: &HardwareVoiceSettings) {
+        self.equalization.enabled = hardware.equalization;
+        self.reverb_settings.enabled = hardware.reverb;
+    }
+
     pub fn tars_voice_effects() -> Self {
         VoiceEffects {
             robotic_filter: RoboticFilter {
@@ -761,6 +794,7 @@ impl VoiceEffects {
                 quantization_noise: 0.02,    // Minimal digital artifacts
             },
             reverb_settings: ReverbSettings {
+                enabled: true,
                 room_size: 0.3,              // Small to medium room
                 decay_time: 0.8,             // Short decay for clarity
                 early_reflections: 0.2,      // Moderate early reflections
@@ -768,6 +802,7 @@ impl VoiceEffects {
                 high_frequency_damping: 0.3, // Some HF damping
             },
             equalization: EqualizationCurve {
+                enabled: true,
                 low_shelf: EQBand {
                     frequency: 80.0,
                     gain: 2.0,               // Boost lows for depth
@@ -822,6 +857,7 @@ impl VoiceEffects {
                 between_sentences: true,     // Add between sentences
                 during_pauses: false,        // Not during regular pauses
                 movement_correlation: 0.5,   // Moderate correlation with movement
+                mix: MixSettings::default(),
             },
         }
     }
@@ -1119,4 +1155,27 @@ mod tests {
         assert!(colony_quote.0.contains("robot colony"));
         assert_eq!(colony_quote.1.primary_emotion, "deadpan_humor");
     }
+
+    #[test]
+    fn test_hardware_settings_switch_reverb_off() {
+        let mut profile = TARSVoiceProfile::interstellar_accurate();
+        profile.voice_effects.robotic_filter.enabled = false;
+        profile.voice_effects.servo_sounds.enabled = false;
+        let hardware = |reverb| HardwareVoiceSettings { equalization: false, reverb, ..HardwareVoiceSettings::default() };
+        // A click followed by silence: only reverb leaves a tail
+        let mut click = samples_to_pcm16(&[0.5; 10]);
+        click.extend(samples_to_pcm16(&vec![0.0; 4000]));
+        let tail = |audio: &[u8]| pcm16_to_samples(audio)[10..].iter().any(|&s| s != 0.0);
+
+        profile.voice_effects.apply_hardware_settings(&hardware(true));
+        let mut audio = click.clone();
+        profile.apply_voice_effects(&mut audio, 16000).unwrap();
+        assert!(tail(&audio));
+
+        profile.voice_effects.apply_hardware_settings(&hardware(false));
+        let mut audio = click.clone();
+        profile.apply_voice_effects(&mut audio, 16000).unwrap();
+        assert_eq!(audio.len(), click.len());
+        assert!(!tail(&audio));
+    }
 }
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{RwLock, Mutex};
 use once_cell::sync::Lazy;
 
+use super::{emergency_voice, realtime_processing};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextToSpeechEngine {
     pub engine_type: TTSEngine,
@@ -14,6 +17,13 @@ pub struct TextToSpeechEngine {
     pub tars_personality: bool,
     pub emotional_inflection: bool,
     pub audio_format: AudioFormat,
+    /// When off, speech comes from the offline emergency voice
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -194,11 +204,16 @@ impl TextToSpeechEngine {
             tars_personality: true,
             emotional_inflection: true,
             audio_format: AudioFormat::WAV,
+            enabled: true,
         }
     }
     
     /// Convert text to speech with TARS personality
     pub async fn synthesize_speech(&self, request: SpeechRequest) -> Result<AudioOutput, String> {
+        if !self.enabled {
+            return Err("TTS engine is disabled".to_string());
+        }
+
         // Preprocess text for TARS personality if enabled
         let processed_text = if self.tars_personality {
             self.apply_tars_personality_processing(&request).await?
@@ -666,9 +681,26 @@ pub async fn speak(text: &str) {
     }
 }
 
+/// Speak through the TTS engine, or the offline emergency voice when the
+/// engine fails. Critical speech always uses the emergency voice, which
+/// cannot fail.
 pub async fn speak_with_request(request: SpeechRequest) -> Result<AudioOutput, String> {
-    let engine = TTS_ENGINE.lock().await;
-    engine.synthesize_speech(request).await
+    let started = Instant::now();
+    let audio = if matches!(request.priority, SpeechPriority::Critical) {
+        emergency_voice::speak(&request.text)
+    } else {
+        let text = request.text.clone();
+        let engine = TTS_ENGINE.lock().await;
+        match engine.synthesize_speech(request).await {
+            Ok(audio) => audio,
+            Err(e) => {
+                log::warn!("TTS engine failed ({}), using the emergency voice", e);
+                emergency_voice::speak(&text)
+            }
+        }
+    };
+    realtime_processing::record_speech_latency(started.elapsed(), !audio.audio_data.is_empty()).await;
+    Ok(audio)
 }
 
 pub async fn speak_emergency(text: &str) -> Result<AudioOutput, String> {
@@ -709,6 +741,13 @@ pub async fn configure_tts_engine(engine: TextToSpeechEngine) -> Result<(), Stri
     Ok(())
 }
 
+/// Match humor and sarcasm inflection to the personality dials
+pub async fn retune_personality(humor: f32, sarcasm: f32) {
+    let mut tts = TTS_ENGINE.lock().await;
+    tts.voice_profile.tars_calibration.humor_modulation = humor.clamp(0.0, 1.0);
+    tts.voice_profile.tars_calibration.sarcasm_tone = sarcasm.clamp(0.0, 1.0);
+}
+
 pub async fn get_tts_stats() -> HashMap<String, String> {
     let mut stats = HashMap::new();
     let engine = TTS_ENGINE.lock().await;
@@ -798,4 +837,28 @@ mod tests {
         let audio = result.unwrap();
         assert!(audio.text_processed.contains("Emergency"));
     }
+
+    #[tokio::test]
+    async fn test_emergency_phrase_speaks_with_engine_disabled() {
+        let mut disabled = TextToSpeechEngine::new();
+        disabled.enabled = false;
+        let request = SpeechRequest {
+            text: "Critical system failure detected".to_string(),
+            priority: SpeechPriority::Critical,
+            context: SpeechContext::Emergency,
+            emotional_state: None,
+            override_settings: None,
+        };
+        assert!(disabled.synthesize_speech(request).await.is_err());
+        configure_tts_engine(disabled).await.unwrap();
+
+        let audio = speak_emergency("Critical system failure detected").await.unwrap();
+        configure_tts_engine(TextToSpeechEngine::new()).await.unwrap();
+
+        assert!(emergency_voice::is_prerendered("Critical system failure detected"));
+        assert!(audio.duration_ms > 1000);
+        assert_eq!(audio.sample_rate, emergency_voice::FALLBACK_SAMPLE_RATE);
+        assert!(audio.audio_data.chunks(2).any(|s| i16::from_le_bytes([s[0], s[1]]) != 0));
+        assert_eq!(audio.audio_data, emergency_voice::render_pcm16("Critical system failure detected"));
+    }
 }
@@ -5,6 +5,7 @@ use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 use super::{
+    error::VoiceError,
     tars_voice_profile::{TARSVoiceProfile, EmotionConfig},
     advanced_tts::AdvancedTTSEngine,
     speech_patterns::MovieAccurateSpeechProcessor,
@@ -2761,22 +2762,31 @@ impl TARSVoiceCloning {
         Self::default()
     }
     
-    pub async fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn initialize(&mut self) -> Result<(), VoiceError> {
         // Initialize all voice cloning components
         Ok(())
     }
     
-    pub async fn clone_tars_voice(&mut self, reference_audio: Vec<u8>, target_text: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    pub async fn clone_tars_voice(&mut self, reference_audio: Vec<u8>, target_text: &str) -> Result<Vec<u8>, VoiceError> {
+        if reference_audio.is_empty() {
+            return Err(VoiceError::NoReferenceAudio);
+        }
+        if target_text.trim().is_empty() {
+            return Err(VoiceError::EmptyText);
+        }
         // Main voice cloning pipeline
         Ok(vec![])
     }
     
-    pub async fn fine_tune_model(&mut self, training_data: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn fine_tune_model(&mut self, training_data: Vec<u8>) -> Result<(), VoiceError> {
+        if training_data.is_empty() {
+            return Err(VoiceError::NoTrainingData);
+        }
         // Fine-tune the TARS voice model
         Ok(())
     }
     
-    pub async fn evaluate_accuracy(&self) -> Result<f32, Box<dyn std::error::Error>> {
+    pub async fn evaluate_accuracy(&self) -> Result<f32, VoiceError> {
         // Evaluate TARS voice accuracy against movie references
         Ok(0.95)
     }
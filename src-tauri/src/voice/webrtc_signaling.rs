@@ -0,0 +1,440 @@
+//! WebRTC signaling for streaming TARS audio to a browser.
+//!
+//! `create_offer` opens a peer connection with an Opus audio track and
+//! returns its SDP offer; the browser's answer goes to `accept_answer`, and
+//! ICE candidates are traded through `local_candidates` and
+//! `add_remote_candidate`. The peer connection's state callbacks are copied
+//! into `PeerConnection` by `refresh_states`. Without the `streaming` feature
+//! every call reports that WebRTC is not compiled in.
+//!
+//! `cargo test --features streaming` runs a full offer/answer exchange with a
+//! loopback peer over host candidates only.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::realtime_processing::{
+    ConnectionState, ConnectionStatistics, IceConnectionState, PeerConnection, SignalingState, WebRTCHandler,
+};
+
+/// SDP offer or answer as exchanged with the browser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionDescription {
+    /// "offer" or "answer"
+    #[serde(rename = "type")]
+    pub sdp_type: String,
+    pub sdp: String,
+}
+
+/// ICE candidate in the browser's `RTCIceCandidateInit` shape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IceCandidate {
+    pub candidate: String,
+    pub sdp_mid: Option<String>,
+    pub sdp_m_line_index: Option<u16>,
+}
+
+impl WebRTCHandler {
+    /// Open a connection to `peer_id` carrying TARS's audio and return the
+    /// offer to send to it. Replaces any earlier connection to the peer.
+    pub async fn create_offer(&mut self, peer_id: &str) -> Result<SessionDescription, String> {
+        let offer = rtc::create_offer(peer_id, &self.connection_management.ice_servers).await?;
+        self.peer_connections.insert(peer_id.to_string(), PeerConnection {
+            connection_id: format!("tars-{}", peer_id),
+            peer_id: peer_id.to_string(),
+            connection_state: ConnectionState::New,
+            ice_connection_state: IceConnectionState::Gathering,
+            signaling_state: SignalingState::HaveLocalOffer,
+            statistics: ConnectionStatistics {
+                bytes_sent: 0,
+                bytes_received: 0,
+                packets_sent: 0,
+                packets_received: 0,
+                packets_lost: 0,
+                round_trip_time_ms: 0.0,
+                jitter_buffer_delay_ms: 0.0,
+            },
+        });
+        Ok(offer)
+    }
+
+    /// Apply the peer's answer to our offer
+    pub async fn accept_answer(&mut self, peer_id: &str, answer: SessionDescription) -> Result<(), String> {
+        let peer = self.peer_connections.get_mut(peer_id)
+            .ok_or_else(|| format!("No WebRTC connection for {}", peer_id))?;
+        if !matches!(peer.signaling_state, SignalingState::HaveLocalOffer) {
+            return Err(format!("No offer to {} is waiting for an answer", peer_id));
+        }
+        if answer.sdp_type != "answer" {
+            return Err(format!("Expected an answer from {}, got '{}'", peer_id, answer.sdp_type));
+        }
+        rtc::accept_answer(peer_id, &answer.sdp).await?;
+        peer.signaling_state = SignalingState::Stable;
+        peer.connection_state = ConnectionState::Connecting;
+        Ok(())
+    }
+
+    /// Add an ICE candidate sent by the peer
+    pub async fn add_remote_candidate(&mut self, peer_id: &str, candidate: IceCandidate) -> Result<(), String> {
+        if !self.peer_connections.contains_key(peer_id) {
+            return Err(format!("No WebRTC connection for {}", peer_id));
+        }
+        rtc::add_remote_candidate(peer_id, candidate).await
+    }
+
+    /// ICE candidates gathered since the last call, to send to the peer
+    pub async fn local_candidates(&self, peer_id: &str) -> Result<Vec<IceCandidate>, String> {
+        rtc::take_local_candidates(peer_id).await
+    }
+
+    /// Copy the latest connection and ICE states into `peer_connections`
+    pub async fn refresh_states(&mut self) {
+        for (peer_id, peer) in self.peer_connections.iter_mut() {
+            let Some((connection, ice)) = rtc::states(peer_id).await else { continue };
+            if let Some(connection) = connection {
+                peer.connection_state = connection;
+            }
+            if let Some(ice) = ice {
+                peer.ice_connection_state = ice;
+            }
+        }
+    }
+
+    /// Send one Opus frame of `duration` to the peer
+    pub async fn send_audio(&mut self, peer_id: &str, opus_frame: Vec<u8>, duration: Duration) -> Result<(), String> {
+        let peer = self.peer_connections.get_mut(peer_id)
+            .ok_or_else(|| format!("No WebRTC connection for {}", peer_id))?;
+        let bytes = opus_frame.len() as u64;
+        rtc::send_audio(peer_id, opus_frame, duration).await?;
+        peer.statistics.bytes_sent += bytes;
+        peer.statistics.packets_sent += 1;
+        Ok(())
+    }
+
+    pub async fn close_peer(&mut self, peer_id: &str) -> Result<(), String> {
+        rtc::close(peer_id).await?;
+        if let Some(peer) = self.peer_connections.get_mut(peer_id) {
+            peer.connection_state = ConnectionState::Closed;
+            peer.ice_connection_state = IceConnectionState::Closed;
+            peer.signaling_state = SignalingState::Closed;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "streaming")]
+mod rtc {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use once_cell::sync::Lazy;
+    use tokio::sync::RwLock;
+    use webrtc::api::interceptor_registry::register_default_interceptors;
+    use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_OPUS};
+    use webrtc::api::APIBuilder;
+    use webrtc::ice_transport::ice_candidate::{RTCIceCandidate, RTCIceCandidateInit};
+    use webrtc::ice_transport::ice_connection_state::RTCIceConnectionState;
+    use webrtc::ice_transport::ice_server::RTCIceServer;
+    use webrtc::interceptor::registry::Registry;
+    use webrtc::media::Sample;
+    use webrtc::peer_connection::configuration::RTCConfiguration;
+    use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+    use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+    use webrtc::peer_connection::RTCPeerConnection;
+    use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+    use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+    use webrtc::track::track_local::TrackLocal;
+
+    use super::{IceCandidate, SessionDescription};
+    use crate::voice::realtime_processing::{ConnectionState, IceConnectionState, IceServer};
+
+    /// What the peer connection callbacks have reported
+    #[derive(Default)]
+    struct Reported {
+        connection: Option<ConnectionState>,
+        ice: Option<IceConnectionState>,
+        candidates: Vec<IceCandidate>,
+    }
+
+    struct Peer {
+        connection: Arc<RTCPeerConnection>,
+        track: Arc<TrackLocalStaticSample>,
+        reported: Arc<Mutex<Reported>>,
+    }
+
+    static PEERS: Lazy<RwLock<HashMap<String, Peer>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+    fn err(e: webrtc::Error) -> String {
+        e.to_string()
+    }
+
+    fn connection_state(state: RTCPeerConnectionState) -> ConnectionState {
+        match state {
+            RTCPeerConnectionState::Connecting => ConnectionState::Connecting,
+            RTCPeerConnectionState::Connected => ConnectionState::Connected,
+            RTCPeerConnectionState::Disconnected => ConnectionState::Disconnected,
+            RTCPeerConnectionState::Failed => ConnectionState::Failed,
+            RTCPeerConnectionState::Closed => ConnectionState::Closed,
+            _ => ConnectionState::New,
+        }
+    }
+
+    fn ice_state(state: RTCIceConnectionState) -> IceConnectionState {
+        match state {
+            RTCIceConnectionState::Checking => IceConnectionState::Checking,
+            RTCIceConnectionState::Connected => IceConnectionState::Connected,
+            RTCIceConnectionState::Completed => IceConnectionState::Completed,
+            RTCIceConnectionState::Disconnected => IceConnectionState::Disconnected,
+            RTCIceConnectionState::Failed => IceConnectionState::Failed,
+            RTCIceConnectionState::Closed => IceConnectionState::Closed,
+            _ => IceConnectionState::New,
+        }
+    }
+
+    async fn connection(peer_id: &str) -> Result<Arc<RTCPeerConnection>, String> {
+        PEERS.read().await.get(peer_id)
+            .map(|peer| Arc::clone(&peer.connection))
+            .ok_or_else(|| format!("No WebRTC connection for {}", peer_id))
+    }
+
+    pub async fn create_offer(peer_id: &str, ice_servers: &[IceServer]) -> Result<SessionDescription, String> {
+        let mut media = MediaEngine::default();
+        media.register_default_codecs().map_err(err)?;
+        let registry = register_default_interceptors(Registry::new(), &mut media).map_err(err)?;
+        let api = APIBuilder::new()
+            .with_media_engine(media)
+            .with_interceptor_registry(registry)
+            .build();
+        let config = RTCConfiguration {
+            ice_servers: ice_servers.iter()
+                .map(|server| RTCIceServer {
+                    urls: vec![server.url.clone()],
+                    username: server.username.clone().unwrap_or_default(),
+                    credential: server.credential.clone().unwrap_or_default(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let connection = Arc::new(api.new_peer_connection(config).await.map_err(err)?);
+
+        let track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_OPUS.to_owned(),
+                clock_rate: 48000,
+                channels: 2,
+                ..Default::default()
+            },
+            "audio".to_owned(),
+            "tars".to_owned(),
+        ));
+        connection.add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>).await.map_err(err)?;
+
+        let reported = Arc::new(Mutex::new(Reported::default()));
+        let sink = Arc::clone(&reported);
+        connection.on_peer_connection_state_change(Box::new(move |state: RTCPeerConnectionState| {
+            sink.lock().unwrap().connection = Some(connection_state(state));
+            Box::pin(async {})
+        }));
+        let sink = Arc::clone(&reported);
+        connection.on_ice_connection_state_change(Box::new(move |state: RTCIceConnectionState| {
+            sink.lock().unwrap().ice = Some(ice_state(state));
+            Box::pin(async {})
+        }));
+        let sink = Arc::clone(&reported);
+        connection.on_ice_candidate(Box::new(move |candidate: Option<RTCIceCandidate>| {
+            if let Some(init) = candidate.and_then(|candidate| candidate.to_json().ok()) {
+                sink.lock().unwrap().candidates.push(IceCandidate {
+                    candidate: init.candidate,
+                    sdp_mid: init.sdp_mid,
+                    sdp_m_line_index: init.sdp_mline_index,
+                });
+            }
+            Box::pin(async {})
+        }));
+
+        let offer = connection.create_offer(None).await.map_err(err)?;
+        connection.set_local_description(offer.clone()).await.map_err(err)?;
+
+        let replaced = PEERS.write().await.insert(peer_id.to_string(), Peer { connection, track, reported });
+        if let Some(old) = replaced {
+            let _ = old.connection.close().await;
+        }
+        Ok(SessionDescription { sdp_type: "offer".to_string(), sdp: offer.sdp })
+    }
+
+    pub async fn accept_answer(peer_id: &str, sdp: &str) -> Result<(), String> {
+        let answer = RTCSessionDescription::answer(sdp.to_string()).map_err(err)?;
+        connection(peer_id).await?.set_remote_description(answer).await.map_err(err)
+    }
+
+    pub async fn add_remote_candidate(peer_id: &str, candidate: IceCandidate) -> Result<(), String> {
+        connection(peer_id).await?
+            .add_ice_candidate(RTCIceCandidateInit {
+                candidate: candidate.candidate,
+                sdp_mid: candidate.sdp_mid,
+                sdp_mline_index: candidate.sdp_m_line_index,
+                username_fragment: None,
+            })
+            .await
+            .map_err(err)
+    }
+
+    pub async fn take_local_candidates(peer_id: &str) -> Result<Vec<IceCandidate>, String> {
+        let peers = PEERS.read().await;
+        let peer = peers.get(peer_id).ok_or_else(|| format!("No WebRTC connection for {}", peer_id))?;
+        let candidates = std::mem::take(&mut peer.reported.lock().unwrap().candidates);
+        Ok(candidates)
+    }
+
+    pub async fn states(peer_id: &str) -> Option<(Option<ConnectionState>, Option<IceConnectionState>)> {
+        let peers = PEERS.read().await;
+        let reported = peers.get(peer_id)?.reported.lock().unwrap();
+        Some((reported.connection.clone(), reported.ice.clone()))
+    }
+
+    pub async fn send_audio(peer_id: &str, opus_frame: Vec<u8>, duration: Duration) -> Result<(), String> {
+        let track = PEERS.read().await.get(peer_id)
+            .map(|peer| Arc::clone(&peer.track))
+            .ok_or_else(|| format!("No WebRTC connection for {}", peer_id))?;
+        track.write_sample(&Sample { data: bytes::Bytes::from(opus_frame), duration, ..Default::default() })
+            .await
+            .map_err(err)
+    }
+
+    pub async fn close(peer_id: &str) -> Result<(), String> {
+        let removed = PEERS.write().await.remove(peer_id);
+        match removed {
+            Some(peer) => peer.connection.close().await.map_err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Without the `streaming` feature there is no WebRTC stack to signal with
+#[cfg(not(feature = "streaming"))]
+mod rtc {
+    use std::time::Duration;
+
+    use super::{IceCandidate, SessionDescription};
+    use crate::voice::realtime_processing::{ConnectionState, IceConnectionState, IceServer};
+
+    const NOT_COMPILED: &str = "WebRTC streaming is not compiled in; build with the `streaming` feature";
+
+    pub async fn create_offer(_peer_id: &str, _ice_servers: &[IceServer]) -> Result<SessionDescription, String> {
+        Err(NOT_COMPILED.to_string())
+    }
+
+    pub async fn accept_answer(_peer_id: &str, _sdp: &str) -> Result<(), String> {
+        Err(NOT_COMPILED.to_string())
+    }
+
+    pub async fn add_remote_candidate(_peer_id: &str, _candidate: IceCandidate) -> Result<(), String> {
+        Err(NOT_COMPILED.to_string())
+    }
+
+    pub async fn take_local_candidates(_peer_id: &str) -> Result<Vec<IceCandidate>, String> {
+        Err(NOT_COMPILED.to_string())
+    }
+
+    pub async fn states(_peer_id: &str) -> Option<(Option<ConnectionState>, Option<IceConnectionState>)> {
+        None
+    }
+
+    pub async fn send_audio(_peer_id: &str, _opus_frame: Vec<u8>, _duration: Duration) -> Result<(), String> {
+        Err(NOT_COMPILED.to_string())
+    }
+
+    pub async fn close(_peer_id: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "streaming"))]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use webrtc::api::media_engine::MediaEngine;
+    use webrtc::api::APIBuilder;
+    use webrtc::ice_transport::ice_candidate::{RTCIceCandidate, RTCIceCandidateInit};
+    use webrtc::peer_connection::configuration::RTCConfiguration;
+    use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+
+    #[tokio::test]
+    async fn test_offer_answer_with_loopback_peer_connects() {
+        let mut handler = WebRTCHandler::default();
+        // Host candidates only, so the test needs no network
+        handler.connection_management.ice_servers.clear();
+        let offer = handler.create_offer("browser").await.unwrap();
+        assert!(matches!(handler.peer_connections["browser"].signaling_state, SignalingState::HaveLocalOffer));
+
+        // The browser side of the call
+        let mut media = MediaEngine::default();
+        media.register_default_codecs().unwrap();
+        let api = APIBuilder::new().with_media_engine(media).build();
+        let browser = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await.unwrap());
+        let browser_candidates = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&browser_candidates);
+        browser.on_ice_candidate(Box::new(move |candidate: Option<RTCIceCandidate>| {
+            if let Some(init) = candidate.and_then(|candidate| candidate.to_json().ok()) {
+                sink.lock().unwrap().push(init);
+            }
+            Box::pin(async {})
+        }));
+        browser.set_remote_description(RTCSessionDescription::offer(offer.sdp).unwrap()).await.unwrap();
+        let answer = browser.create_answer(None).await.unwrap();
+        browser.set_local_description(answer.clone()).await.unwrap();
+
+        handler.accept_answer("browser", SessionDescription { sdp_type: "answer".to_string(), sdp: answer.sdp })
+            .await
+            .unwrap();
+        assert!(matches!(handler.peer_connections["browser"].signaling_state, SignalingState::Stable));
+
+        for _ in 0..200 {
+            for candidate in handler.local_candidates("browser").await.unwrap() {
+                browser.add_ice_candidate(RTCIceCandidateInit {
+                    candidate: candidate.candidate,
+                    sdp_mid: candidate.sdp_mid,
+                    sdp_mline_index: candidate.sdp_m_line_index,
+                    username_fragment: None,
+                }).await.unwrap();
+            }
+            let pending: Vec<RTCIceCandidateInit> = std::mem::take(&mut *browser_candidates.lock().unwrap());
+            for init in pending {
+                handler.add_remote_candidate("browser", IceCandidate {
+                    candidate: init.candidate,
+                    sdp_mid: init.sdp_mid,
+                    sdp_m_line_index: init.sdp_mline_index,
+                }).await.unwrap();
+            }
+            handler.refresh_states().await;
+            if matches!(handler.peer_connections["browser"].connection_state, ConnectionState::Connected) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        assert!(matches!(handler.peer_connections["browser"].connection_state, ConnectionState::Connected));
+        handler.close_peer("browser").await.unwrap();
+        browser.close().await.unwrap();
+    }
+}
+
+#[cfg(all(test, not(feature = "streaming")))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_signaling_reports_webrtc_missing() {
+        let mut handler = WebRTCHandler::default();
+        let error = handler.create_offer("browser").await.unwrap_err();
+        assert!(error.contains("streaming"), "{}", error);
+        assert!(handler.peer_connections.is_empty());
+
+        let answer = SessionDescription { sdp_type: "answer".to_string(), sdp: String::new() };
+        assert!(handler.accept_answer("browser", answer).await.is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use gsteng::config::config::Config;
+use gsteng::config::config::{Config, ConfigChange};
 
 #[test]
 fn load_default_config() {
@@ -7,3 +7,33 @@ fn load_default_config() {
     assert!(!cfg.hardware.port.is_empty());
     std::fs::remove_file(path).unwrap();
 }
+
+#[test]
+fn apply_max_sarcasm_preset() {
+    let mut cfg = Config::default();
+    cfg.apply_personality_preset("Max Sarcasm").expect("apply preset");
+    let sarcasm = cfg.personality.presets["Max Sarcasm"].sarcasm;
+    assert_eq!(cfg.personality.sarcasm, f32::from(sarcasm) / 100.0);
+    assert_eq!(cfg.personality.sarcasm, 1.0);
+
+    let err = cfg.apply_personality_preset("Cheerful").unwrap_err();
+    assert!(err.contains("Movie-Accurate") && err.contains("Professional"));
+}
+
+#[test]
+fn reload_merges_only_changed_values() {
+    let previous = Config::default();
+    let mut reloaded = Config::default();
+    reloaded.personality.sarcasm = 0.9;
+    let mut running = Config::default();
+    running.personality.humor = 0.1;
+
+    let changed = running.merge_reload(&previous, &reloaded).expect("merge reload");
+    assert_eq!(changed, vec!["personality.sarcasm"]);
+    assert_eq!(running.personality.sarcasm, 0.9);
+    assert_eq!(running.personality.humor, 0.1, "runtime tweak kept");
+
+    let change = ConfigChange::Reloaded { changed };
+    assert!(change.touches("personality") && change.touches("personality.sarcasm"));
+    assert!(!change.touches("personality.humor") && !change.touches("ai"));
+}
@@ -1,6 +1,14 @@
 use gsteng::robotics::hardware_interface::{MotorController, ServoControl};
-use gsteng::robotics::mobility_controller::{MobilityController, MobilityMode};
+use gsteng::robotics::mobility_controller::{
+    GaitParameters, MobilityController, MobilityMode, OdometryConfig, WalkDirection,
+};
+use gsteng::robotics::{MovementCommand, ServoId};
+use gsteng::robotics::safety_sensors::{MockRangeSensor, MockTiltSensor, SensorSafetyMonitor};
+use gsteng::config::config::SafetyConfig;
+use gsteng::safety::Safety;
 use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Instant;
 
 struct MockHW;
 
@@ -31,3 +39,135 @@ async fn set_walking_mode() {
     controller.set_mode(MobilityMode::Walking);
     assert!(controller.set_joints(&[0.1, 0.2]).await.is_ok());
 }
+
+fn walking_controller(gait: GaitParameters) -> MobilityController<MockHW> {
+    let mut controller = MobilityController::new(MockHW);
+    controller.set_mode(MobilityMode::Walking);
+    controller.set_gait_parameters(gait);
+    controller
+}
+
+#[tokio::test]
+async fn single_forward_step_schedule() {
+    let controller = walking_controller(GaitParameters { cadence: 10.0, ..GaitParameters::default() });
+    let schedule = controller.walk(WalkDirection::Forward, 1).await.unwrap();
+
+    let frames: Vec<_> = schedule
+        .into_iter()
+        .map(|command| match command {
+            MovementCommand::ServoTargets { start_ms, duration_ms, positions, .. } => (start_ms, duration_ms, positions),
+            other => panic!("unexpected command {:?}", other),
+        })
+        .collect();
+
+    let starts: Vec<u64> = frames.iter().map(|(start, _, _)| *start).collect();
+    assert_eq!(starts, vec![0, 25, 50, 75]);
+    assert!(frames.iter().all(|(_, duration, _)| *duration == 25));
+
+    // Right leg lifts first while the left leg takes the weight.
+    assert_eq!(frames[0].2, vec![
+        (ServoId::RightHipUpDown, 0.4),
+        (ServoId::RightKnee, 0.6),
+        (ServoId::LeftHipUpDown, -0.2),
+    ]);
+    // Then swings forward as the planted leg pushes back.
+    assert_eq!(frames[1].2[..2], [
+        (ServoId::RightHipForwardBack, 0.3),
+        (ServoId::LeftHipForwardBack, -0.3),
+    ]);
+}
+
+#[tokio::test]
+async fn walk_rejects_gait_below_contact_minimum() {
+    let controller = walking_controller(GaitParameters { min_contact_points: 2, ..GaitParameters::default() });
+    let err = controller.plan_walk(WalkDirection::Forward, 1).unwrap_err();
+    assert!(err.contains("minimum is 2"));
+}
+
+#[tokio::test]
+async fn walk_clamps_to_servo_limits() {
+    let controller = walking_controller(GaitParameters { step_length: 3.0, ..GaitParameters::default() });
+    let schedule = controller.plan_walk(WalkDirection::Backward, 2).unwrap();
+    for command in schedule {
+        if let MovementCommand::ServoTargets { positions, .. } = command {
+            assert!(positions.iter().all(|(_, pos)| pos.abs() <= 1.0));
+        }
+    }
+}
+
+#[tokio::test]
+async fn walk_requires_walking_mode() {
+    let controller = MobilityController::new(MockHW);
+    assert!(controller.walk(WalkDirection::TurnLeft, 1).await.is_err());
+}
+
+#[tokio::test]
+async fn odometry_tracks_drive_and_turn() {
+    let mut controller = MobilityController::new(MockHW);
+    controller.set_odometry_config(OdometryConfig {
+        wheel_base: 0.5,
+        max_wheel_speed: 10.0,
+        ..OdometryConfig::default()
+    });
+
+    // 1.0 * 10 m/s * 0.1 s = 1 m forward.
+    controller.drive_for(1.0, 100).await.unwrap();
+    let pose = controller.current_pose();
+    assert!((pose.x - 1.0).abs() < 1e-3);
+    assert!(pose.y.abs() < 1e-3);
+
+    // 2 * 0.5 * 10 m/s / 0.5 m = 20 rad/s; 79 ms is ~90 degrees left.
+    controller.rotate_for(0.5, 79).await.unwrap();
+    let pose = controller.current_pose();
+    assert!((pose.heading - std::f32::consts::FRAC_PI_2).abs() < 0.02);
+    assert!((pose.x - 1.0).abs() < 1e-3);
+
+    controller.drive_for(0.5, 100).await.unwrap();
+    let pose = controller.current_pose();
+    assert!((pose.x - 1.0).abs() < 0.02);
+    assert!((pose.y - 0.5).abs() < 0.02);
+
+    controller.reset_pose();
+    assert_eq!(controller.current_pose().x, 0.0);
+}
+
+#[tokio::test]
+async fn odometry_counts_walking_steps() {
+    let controller = walking_controller(GaitParameters { cadence: 40.0, ..GaitParameters::default() });
+    controller.walk(WalkDirection::Forward, 3).await.unwrap();
+    let expected = 3.0 * OdometryConfig::default().step_displacement;
+    assert!((controller.current_pose().x - expected).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn obstacle_halts_forward_drive() {
+    let safety = Safety::new();
+    let range = Arc::new(MockRangeSensor::new(2.0));
+    let monitor = SensorSafetyMonitor::new(
+        Arc::new(MockTiltSensor::new()),
+        range.clone(),
+        SafetyConfig::default(),
+        safety.clone(),
+    );
+    let controller = Arc::new(MobilityController::new(MockHW).with_safety(safety.clone()));
+
+    let started = Instant::now();
+    let drive = tokio::spawn({
+        let controller = controller.clone();
+        async move { controller.drive_for(0.5, 5_000).await }
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    range.set_distance(0.05).await;
+    assert!(monitor.check_once().await.is_some());
+
+    let result = drive.await.unwrap();
+    assert!(result.unwrap_err().contains("obstacle"));
+    assert!(started.elapsed().as_millis() < 1_000);
+    assert!(controller.current_pose().x < 0.5 * 0.5 * 5.0);
+
+    // Further movement is refused until the stop is cleared.
+    assert!(controller.drive_for(0.5, 10).await.is_err());
+    safety.clear_controlled_stop().await;
+    assert!(controller.drive_for(0.5, 10).await.is_ok());
+}
@@ -12,3 +12,25 @@ async fn personality_values_clamped() {
     assert_eq!(lock.personality.humor, 1.0);
     assert_eq!(lock.personality.honesty, 0.0);
 }
+
+#[test]
+fn low_verbosity_trims_at_sentence_boundary() {
+    use gsteng::personality::tars_core::{PersonalitySettings, TARSPersonality};
+
+    let sentence = "The retry loop holds the lock while it sleeps, so every other request waits behind it.";
+    let response = format!(
+        "{}\n\nWARNING: do not deploy this before the lock is fixed.",
+        vec![sentence; 40].join(" ")
+    );
+    let dial = |verbosity| TARSPersonality::new(PersonalitySettings { verbosity, ..PersonalitySettings::default() });
+
+    let terse = dial(0).enforce_length(&response);
+    let chatty = dial(100).enforce_length(&response);
+    assert!(terse.split_whitespace().count() < chatty.split_whitespace().count());
+
+    let prose = terse.split("\n\n").next().unwrap();
+    assert!(prose.ends_with("waits behind it."));
+    assert!(prose.split_whitespace().count() <= dial(0).word_budget());
+    assert!(terse.contains("WARNING: do not deploy this before the lock is fixed."));
+    assert!(terse.ends_with("That's the short version."));
+}